@@ -11,6 +11,9 @@ use crate::types::{Content, ContentBlock, Message, Role};
 pub struct Conversation {
     messages: Vec<Message>,
     system: Option<String>,
+    /// Message index and ID of each prompt added with [`Self::add_prompt`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    prompt_ids: Vec<(usize, String)>,
 }
 
 impl Conversation {
@@ -24,8 +27,8 @@ impl Conversation {
     #[must_use]
     pub fn with_system(system: impl Into<String>) -> Self {
         Self {
-            messages: Vec::new(),
             system: Some(system.into()),
+            ..Self::default()
         }
     }
 
@@ -54,6 +57,30 @@ impl Conversation {
         });
     }
 
+    /// Add a user prompt that can be found again by `id`.
+    pub fn add_prompt(&mut self, id: impl Into<String>, text: impl Into<String>) {
+        self.prompt_ids.push((self.messages.len(), id.into()));
+        self.add_user_message(text);
+    }
+
+    /// Index of the prompt added with `id`, if it's still in the conversation.
+    #[must_use]
+    pub fn prompt_position(&self, id: &str) -> Option<usize> {
+        self.prompt_ids
+            .iter()
+            .find(|(_, prompt_id)| prompt_id == id)
+            .map(|&(index, _)| index)
+    }
+
+    /// ID and index of the earliest prompt still in the conversation.
+    #[must_use]
+    pub fn first_prompt(&self) -> Option<(usize, &str)> {
+        self.prompt_ids
+            .iter()
+            .min_by_key(|&&(index, _)| index)
+            .map(|(index, id)| (*index, id.as_str()))
+    }
+
     /// Add an assistant message.
     pub fn add_assistant_message(&mut self, text: impl Into<String>) {
         self.messages.push(Message {
@@ -63,10 +90,12 @@ impl Conversation {
     }
 
     /// Add an assistant message with content blocks (for tool use).
+    ///
+    /// Adjacent text blocks are merged into one.
     pub fn add_assistant_blocks(&mut self, blocks: Vec<ContentBlock>) {
         self.messages.push(Message {
             role: Role::Assistant,
-            content: Content::Blocks(blocks),
+            content: Content::Blocks(coalesce_text_blocks(blocks)),
         });
     }
 
@@ -85,9 +114,163 @@ impl Conversation {
         });
     }
 
+    /// Find the result recorded for a tool call, with whether it was an error.
+    #[must_use]
+    pub fn tool_result(&self, tool_use_id: &str) -> Option<(&str, bool)> {
+        self.messages
+            .iter()
+            .rev()
+            .filter_map(|m| match &m.content {
+                Content::Blocks(blocks) => Some(blocks),
+                Content::Text(_) => None,
+            })
+            .flatten()
+            .find_map(|block| match block {
+                ContentBlock::ToolResult {
+                    tool_use_id: id,
+                    content,
+                    is_error,
+                } if id == tool_use_id => Some((content.as_str(), is_error.unwrap_or(false))),
+                _ => None,
+            })
+    }
+
+    /// Add error results for tool calls in the last assistant message that never got a result.
+    ///
+    /// Keeps the conversation valid for the next request after an interrupted turn.
+    /// Returns the number of tool calls repaired.
+    pub fn repair_dangling_tool_calls(&mut self, reason: &str) -> usize {
+        let Some(last_assistant) = self
+            .messages
+            .iter()
+            .rposition(|m| m.role == Role::Assistant)
+        else {
+            return 0;
+        };
+
+        let Content::Blocks(blocks) = &self.messages[last_assistant].content else {
+            return 0;
+        };
+
+        let answered: Vec<&str> = self.messages[last_assistant + 1..]
+            .iter()
+            .filter_map(|m| match &m.content {
+                Content::Blocks(blocks) => Some(blocks),
+                Content::Text(_) => None,
+            })
+            .flatten()
+            .filter_map(|b| match b {
+                ContentBlock::ToolResult { tool_use_id, .. } => Some(tool_use_id.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        let dangling: Vec<String> = blocks
+            .iter()
+            .filter_map(|b| match b {
+                ContentBlock::ToolUse { id, .. } if !answered.contains(&id.as_str()) => {
+                    Some(id.clone())
+                }
+                _ => None,
+            })
+            .collect();
+
+        let count = dangling.len();
+        for id in dangling {
+            self.add_tool_result(id, reason.to_string(), true);
+        }
+        count
+    }
+
+    /// ID and index of the latest prompt still in the conversation.
+    #[must_use]
+    pub fn last_prompt(&self) -> Option<(usize, &str)> {
+        self.prompt_ids
+            .iter()
+            .max_by_key(|&&(index, _)| index)
+            .map(|(index, id)| (*index, id.as_str()))
+    }
+
+    /// Remove the last prompt added with [`Self::add_prompt`] and everything
+    /// after it.
+    ///
+    /// Other user messages, like model-switch notes and compaction
+    /// summaries, aren't prompts, so they're removed with the turn rather
+    /// than rewound to. Returns the removed prompt text, or `None` if there
+    /// is no prompt.
+    pub fn rewind_last_turn(&mut self) -> Option<String> {
+        let (index, _) = self.last_prompt()?;
+        let prompt = self.messages[index].content.text();
+        self.truncate_to(index);
+        Some(prompt)
+    }
+
+    /// Keep only the messages before `index`.
+    pub fn truncate_to(&mut self, index: usize) {
+        self.messages.truncate(index);
+        self.prompt_ids.retain(|&(i, _)| i < index);
+    }
+
+    /// Rough token count for the system prompt and messages.
+    ///
+    /// Assumes four bytes per token, which is close enough to decide when
+    /// the context is filling up.
+    #[must_use]
+    pub fn estimated_tokens(&self) -> u32 {
+        let block_len = |block: &ContentBlock| match block {
+            ContentBlock::Text { text } => text.len(),
+            ContentBlock::ToolUse { name, input, .. } => name.len() + input.to_string().len(),
+            ContentBlock::ToolResult { content, .. } => content.len(),
+            ContentBlock::Thinking { thinking, .. } => thinking.len(),
+        };
+        let bytes: usize = self.system.as_ref().map_or(0, String::len)
+            + self
+                .messages
+                .iter()
+                .map(|m| match &m.content {
+                    Content::Text(text) => text.len(),
+                    Content::Blocks(blocks) => blocks.iter().map(block_len).sum(),
+                })
+                .sum::<usize>();
+        u32::try_from(bytes / 4).unwrap_or(u32::MAX)
+    }
+
+    /// Index of the first message kept when compacting down to `keep` messages.
+    ///
+    /// The kept tail starts at an assistant message, so a summary inserted as
+    /// a user message keeps roles alternating and no tool result is separated
+    /// from its call. Returns `None` if there is nothing to compact.
+    #[must_use]
+    pub fn compaction_split(&self, keep: usize) -> Option<usize> {
+        let start = self.messages.len().checked_sub(keep)?;
+        (1..=start)
+            .rev()
+            .find(|&i| self.messages[i].role == Role::Assistant)
+    }
+
+    /// Replace the first `count` messages with a summary.
+    pub fn compact(&mut self, count: usize, summary: &str) {
+        let count = count.min(self.messages.len());
+        // Summarized prompts are gone; the rest move up behind the summary
+        self.prompt_ids.retain(|&(i, _)| i >= count);
+        for (index, _) in &mut self.prompt_ids {
+            *index = *index + 1 - count;
+        }
+        self.messages.splice(
+            ..count,
+            [Message {
+                role: Role::User,
+                content: Content::Text(format!(
+                    "Summary of the earlier conversation:\n\n{summary}"
+                )),
+            }],
+        );
+    }
+
     /// Clear all messages.
     pub fn clear(&mut self) {
         self.messages.clear();
+        self.prompt_ids.clear();
     }
 
     /// Save conversation to a file.
@@ -130,6 +313,21 @@ impl Conversation {
     }
 }
 
+/// Merge runs of adjacent text blocks into a single text block.
+#[must_use]
+pub fn coalesce_text_blocks(blocks: Vec<ContentBlock>) -> Vec<ContentBlock> {
+    let mut merged: Vec<ContentBlock> = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        match (merged.last_mut(), block) {
+            (Some(ContentBlock::Text { text: prev }), ContentBlock::Text { text }) => {
+                prev.push_str(&text);
+            }
+            (_, block) => merged.push(block),
+        }
+    }
+    merged
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,4 +352,146 @@ mod tests {
 
         assert!(conv.messages().is_empty());
     }
+
+    #[test]
+    fn rewind_last_turn_removes_prompt_and_response() {
+        let mut conv = Conversation::new();
+        conv.add_prompt("p1", "first");
+        conv.add_assistant_message("one");
+        conv.add_prompt("p2", "second");
+        conv.add_assistant_blocks(vec![ContentBlock::ToolUse {
+            id: "t1".to_string(),
+            name: "shell".to_string(),
+            input: serde_json::json!({}),
+        }]);
+        conv.add_tool_result("t1".to_string(), "ok".to_string(), false);
+        conv.add_assistant_message("two");
+        // A model switch adds a user message that isn't a prompt
+        conv.add_user_message("[Model switched from a to b]");
+
+        assert_eq!(conv.last_prompt(), Some((2, "p2")));
+        assert_eq!(conv.rewind_last_turn().as_deref(), Some("second"));
+        assert_eq!(conv.messages().len(), 2);
+        assert_eq!(conv.messages()[1].content.text(), "one");
+
+        assert_eq!(conv.rewind_last_turn().as_deref(), Some("first"));
+        assert_eq!(conv.rewind_last_turn(), None);
+    }
+
+    #[test]
+    fn prompts_are_found_by_id_through_compaction() {
+        let mut conv = Conversation::new();
+        conv.add_prompt("p1", "first");
+        conv.add_assistant_message("one");
+        conv.add_prompt("p2", "second");
+        conv.add_assistant_message("two");
+        // Not a prompt, so it can't be edited
+        conv.add_user_message("[Model switched]");
+        conv.add_assistant_message("ok");
+        conv.add_prompt("p3", "third");
+
+        assert_eq!(conv.prompt_position("p2"), Some(2));
+        assert_eq!(conv.prompt_position("p3"), Some(6));
+
+        conv.compact(2, "summary");
+        assert_eq!(conv.prompt_position("p1"), None);
+        assert_eq!(conv.prompt_position("p2"), Some(1));
+        assert_eq!(conv.prompt_position("p3"), Some(5));
+        assert_eq!(conv.messages()[5].content.text(), "third");
+        assert_eq!(conv.first_prompt(), Some((1, "p2")));
+
+        conv.truncate_to(5);
+        assert_eq!(conv.prompt_position("p3"), None);
+        assert_eq!(conv.prompt_position("p2"), Some(1));
+    }
+
+    #[test]
+    fn assistant_blocks_merge_adjacent_text() {
+        let text = |t: &str| ContentBlock::Text {
+            text: t.to_string(),
+        };
+        let mut conv = Conversation::new();
+        conv.add_assistant_blocks(vec![
+            text("Hel"),
+            text("lo"),
+            ContentBlock::ToolUse {
+                id: "t1".to_string(),
+                name: "shell".to_string(),
+                input: serde_json::Value::Null,
+            },
+            text("a"),
+            text("b"),
+            text("c"),
+        ]);
+
+        let Content::Blocks(blocks) = &conv.messages()[0].content else {
+            panic!("expected blocks");
+        };
+        assert_eq!(blocks.len(), 3);
+        assert!(matches!(&blocks[0], ContentBlock::Text { text } if text == "Hello"));
+        assert!(matches!(&blocks[1], ContentBlock::ToolUse { .. }));
+        assert!(matches!(&blocks[2], ContentBlock::Text { text } if text == "abc"));
+    }
+
+    #[test]
+    fn repair_dangling_tool_calls_adds_missing_results() {
+        let mut conv = Conversation::new();
+        conv.add_user_message("run things");
+        conv.add_assistant_blocks(vec![
+            ContentBlock::ToolUse {
+                id: "a".to_string(),
+                name: "shell".to_string(),
+                input: serde_json::Value::Null,
+            },
+            ContentBlock::ToolUse {
+                id: "b".to_string(),
+                name: "shell".to_string(),
+                input: serde_json::Value::Null,
+            },
+        ]);
+        conv.add_tool_result("a".to_string(), "ok".to_string(), false);
+
+        assert_eq!(conv.repair_dangling_tool_calls("cancelled"), 1);
+        assert_eq!(conv.messages().len(), 4);
+        assert!(matches!(
+            &conv.messages()[3].content,
+            Content::Blocks(blocks) if matches!(
+                &blocks[0],
+                ContentBlock::ToolResult { tool_use_id, is_error: Some(true), .. } if tool_use_id == "b"
+            )
+        ));
+
+        // Nothing left to repair
+        assert_eq!(conv.repair_dangling_tool_calls("cancelled"), 0);
+    }
+
+    #[test]
+    fn compaction_keeps_an_alternating_tail() {
+        let mut conv = Conversation::new();
+        conv.add_user_message("first");
+        conv.add_assistant_message("one");
+        conv.add_user_message("second");
+        conv.add_assistant_blocks(vec![ContentBlock::ToolUse {
+            id: "t1".to_string(),
+            name: "shell".to_string(),
+            input: serde_json::json!({}),
+        }]);
+        conv.add_tool_result("t1".to_string(), "ok".to_string(), false);
+        conv.add_assistant_message("two");
+        conv.add_user_message("third");
+
+        // Keeping 3 would start at a tool result, so the call comes along
+        let split = conv.compaction_split(3).unwrap();
+        assert_eq!(split, 3);
+        conv.compact(split, "earlier work");
+
+        assert_eq!(conv.messages().len(), 5);
+        assert_eq!(conv.messages()[0].role, Role::User);
+        assert!(conv.messages()[0].content.text().contains("earlier work"));
+        assert_eq!(conv.messages()[1].role, Role::Assistant);
+        assert_eq!(conv.messages()[4].content.text(), "third");
+
+        assert_eq!(conv.compaction_split(10), None);
+        assert!(conv.estimated_tokens() > 0);
+    }
 }
@@ -38,6 +38,10 @@ pub enum AgentError {
     /// Agent entered an infinite loop.
     #[error("loop detected: {0}")]
     LoopDetected(String),
+
+    /// The turn was cancelled by the user.
+    #[error("cancelled by user")]
+    Cancelled,
 }
 
 /// Result type for agent operations.
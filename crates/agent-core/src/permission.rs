@@ -40,6 +40,91 @@ pub struct AgentPermissions {
     pub web_search: PermissionPreset,
     /// Code search permission.
     pub code_search: PermissionPreset,
+    /// Storing long-term memories permission.
+    pub remember: PermissionPreset,
+    /// Plugin tool permission.
+    pub plugin: PermissionPreset,
+    /// Shell command rules, checked in order before the presets.
+    pub rules: Vec<PermissionRule>,
+}
+
+/// Permission for shell commands matching a pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionRule {
+    /// Command prefix (`git status`), or a glob (`cargo * --release`) when it
+    /// contains `*` or `?`.
+    pub pattern: String,
+    /// Action for matching commands.
+    pub action: PermissionPreset,
+}
+
+impl PermissionRule {
+    /// Check if a single (non-compound) command matches the pattern.
+    ///
+    /// Prefixes match whole words, so `git status` matches `git status -s`
+    /// but not `git statuses`.
+    #[must_use]
+    pub fn matches(&self, command: &str) -> bool {
+        let command = command.trim();
+        let pattern = self.pattern.trim();
+        if pattern.contains(['*', '?']) {
+            return glob_match(pattern.as_bytes(), command.as_bytes());
+        }
+
+        let mut words = command.split_whitespace();
+        let needle: Vec<&str> = pattern.split_whitespace().collect();
+        !needle.is_empty() && needle.iter().all(|w| words.next() == Some(w))
+    }
+}
+
+/// Match `text` against a glob where `*` is any run and `?` any one byte.
+#[must_use]
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text it has consumed up to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more byte
+                Some((after, consumed)) => {
+                    star = Some((after, consumed + 1));
+                    p = after;
+                    t = consumed + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Check for an unquoted `<` or `>`, covering file redirections and process
+/// substitution (`<(...)`, `>(...)`).
+fn has_redirection(command: &str) -> bool {
+    let bytes = command.as_bytes();
+    let mut quote = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match (quote, c) {
+            (None | Some(b'"'), b'\\') => i += 1,
+            (None, b'\'' | b'"') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, b'<' | b'>') => return true,
+            _ => {}
+        }
+        i += 1;
+    }
+    false
 }
 
 impl Default for AgentPermissions {
@@ -52,11 +137,70 @@ impl Default for AgentPermissions {
             read: PermissionPreset::Allow,
             web_search: PermissionPreset::Ask,
             code_search: PermissionPreset::Ask,
+            remember: PermissionPreset::Ask,
+            plugin: PermissionPreset::Ask,
+            rules: Vec::new(),
         }
     }
 }
 
 impl AgentPermissions {
+    /// Action the rules give a shell command, if any rule applies.
+    ///
+    /// Compound commands (`a && b`, `a | b`, ...) are checked part by
+    /// part: any denied part denies the whole command, and it is only
+    /// allowed when every part is. Commands with substitutions or unquoted
+    /// redirections are never allowed by a rule.
+    #[must_use]
+    pub fn command_rule(&self, command: &str) -> Option<PermissionPreset> {
+        if self.rules.is_empty() {
+            return None;
+        }
+
+        let actions: Vec<Option<PermissionPreset>> = command
+            .split([';', '&', '|', '\n'])
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                self.rules
+                    .iter()
+                    .find(|rule| rule.matches(part))
+                    .map(|rule| rule.action)
+            })
+            .collect();
+
+        if actions.contains(&Some(PermissionPreset::Deny)) {
+            Some(PermissionPreset::Deny)
+        } else if actions.contains(&Some(PermissionPreset::Ask)) {
+            Some(PermissionPreset::Ask)
+        } else if actions.is_empty() || actions.contains(&None) {
+            None
+        } else if command.contains('`') || command.contains("$(") || has_redirection(command) {
+            Some(PermissionPreset::Ask)
+        } else {
+            Some(PermissionPreset::Allow)
+        }
+    }
+
+    /// Permissions when nobody can answer a prompt, such as with piped input.
+    ///
+    /// Reads are allowed and everything else is denied.
+    #[must_use]
+    pub const fn read_only() -> Self {
+        Self {
+            edit: PermissionPreset::Deny,
+            write: PermissionPreset::Deny,
+            bash_write: PermissionPreset::Deny,
+            bash_read: PermissionPreset::Allow,
+            read: PermissionPreset::Allow,
+            web_search: PermissionPreset::Deny,
+            code_search: PermissionPreset::Deny,
+            remember: PermissionPreset::Deny,
+            plugin: PermissionPreset::Deny,
+            rules: Vec::new(),
+        }
+    }
+
     /// Permissions for read-only plan mode.
     #[must_use]
     pub const fn plan_mode() -> Self {
@@ -68,12 +212,16 @@ impl AgentPermissions {
             read: PermissionPreset::Allow,
             web_search: PermissionPreset::Ask,
             code_search: PermissionPreset::Ask,
+            remember: PermissionPreset::Ask,
+            plugin: PermissionPreset::Deny,
+            rules: Vec::new(),
         }
     }
 }
 
 /// Action that requires permission.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum PermissionAction {
     /// Execute a shell command.
     Execute,
@@ -95,10 +243,30 @@ pub enum PermissionAction {
     ListDir,
     /// Fetch content from a URL.
     WebFetch,
+    /// Store a fact in long-term memory.
+    Remember,
+    /// Call a tool provided by a plugin.
+    PluginTool,
+    /// Execute a shell command matching a configured high-risk pattern.
+    ///
+    /// Always prompts; never cached by `AllowForSession`.
+    ExecuteHighRisk {
+        /// The pattern the command matched.
+        pattern: String,
+    },
+}
+
+impl PermissionAction {
+    /// Whether this action must be confirmed every time.
+    #[must_use]
+    pub const fn is_high_risk(&self) -> bool {
+        matches!(self, Self::ExecuteHighRisk { .. })
+    }
 }
 
 /// Tool-specific context for permission dialogs.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum PermissionContext {
     /// Shell command execution.
     Bash {
@@ -129,19 +297,94 @@ pub enum PermissionContext {
     ListDir { path: PathBuf },
     /// Fetch content from URL.
     WebFetch { url: String },
+    /// Store a long-term memory.
+    Remember { content: String, category: String },
+    /// Plugin tool call.
+    PluginTool {
+        plugin: String,
+        tool: String,
+        input: String,
+    },
 }
 
 /// User's response to a permission request.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PermissionResponse {
     /// Allow this single operation.
     Allow,
     /// Allow all similar operations for this session.
     AllowForSession,
+    /// Allow this operation now and in later sessions.
+    AllowAlways,
     /// Deny the operation.
     Deny,
 }
 
+/// Operation the user chose to always allow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Approval {
+    /// Tool that was approved.
+    pub tool: String,
+    /// Exact command, file path, URL or plugin input approved, None = every
+    /// call to a read-only tool.
+    #[serde(default, alias = "command", skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Approval timestamp (millis).
+    pub approved_at: i64,
+}
+
+impl Approval {
+    /// Approval for a tool call, pinned to what it runs, writes or fetches.
+    #[must_use]
+    pub fn new(tool: &str, context: &PermissionContext) -> Self {
+        Self {
+            tool: tool.to_string(),
+            target: Self::target_of(context),
+            approved_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// Check if this approval covers a tool call.
+    #[must_use]
+    pub fn matches(&self, tool: &str, context: &PermissionContext) -> bool {
+        self.tool == tool
+            && match &self.target {
+                Some(target) => Self::target_of(context).as_ref() == Some(target),
+                None => true,
+            }
+    }
+
+    /// What a call acts on, so approving one write doesn't approve them all.
+    fn target_of(context: &PermissionContext) -> Option<String> {
+        match context {
+            PermissionContext::Bash { command, .. } => Some(command.trim().to_string()),
+            PermissionContext::WriteFile { path, .. }
+            | PermissionContext::EditFile { path, .. } => Some(path.display().to_string()),
+            PermissionContext::WebFetch { url } => Some(url.clone()),
+            PermissionContext::PluginTool { input, .. } => Some(input.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Storage for approvals that outlive the process.
+pub trait ApprovalStore: Send + Sync {
+    /// Load saved approvals.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the store can't be read.
+    fn load(&self) -> anyhow::Result<Vec<Approval>>;
+
+    /// Save a new approval.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the store can't be written.
+    fn save(&self, approval: &Approval) -> anyhow::Result<()>;
+}
+
 /// Response for `ask_user` tool (contains actual answer).
 #[derive(Debug, Clone)]
 pub enum AskUserResponse {
@@ -204,6 +447,8 @@ pub struct PermissionClient {
     session_id: String,
     permission_tx: mpsc::UnboundedSender<PermissionMessage>,
     presets: Arc<RwLock<AgentPermissions>>,
+    approvals: Arc<RwLock<Vec<Approval>>>,
+    approval_store: Option<Arc<dyn ApprovalStore>>,
 }
 
 impl PermissionClient {
@@ -213,11 +458,7 @@ impl PermissionClient {
         session_id: String,
         permission_tx: mpsc::UnboundedSender<PermissionMessage>,
     ) -> Self {
-        Self {
-            session_id,
-            permission_tx,
-            presets: Arc::new(RwLock::new(AgentPermissions::default())),
-        }
+        Self::with_presets(session_id, permission_tx, AgentPermissions::default())
     }
 
     /// Create a new permission client with specific presets.
@@ -231,24 +472,92 @@ impl PermissionClient {
             session_id,
             permission_tx,
             presets: Arc::new(RwLock::new(presets)),
+            approvals: Arc::new(RwLock::new(Vec::new())),
+            approval_store: None,
         }
     }
 
+    /// Load saved approvals from `store` and save new "always allow" answers to it.
+    #[must_use]
+    pub fn with_approval_store(mut self, store: Arc<dyn ApprovalStore>) -> Self {
+        match store.load() {
+            Ok(approvals) => *self.approvals.write() = approvals,
+            Err(e) => tracing::warn!("failed to load saved approvals: {e}"),
+        }
+        self.approval_store = Some(store);
+        self
+    }
+
+    /// Check if a saved approval covers a tool call.
+    fn is_approved(&self, tool: &str, context: &PermissionContext) -> bool {
+        self.approvals
+            .read()
+            .iter()
+            .any(|approval| approval.matches(tool, context))
+    }
+
+    /// Remember an "always allow" answer, saving it when there's a store.
+    fn approve(&self, approval: Approval) {
+        if let Some(store) = &self.approval_store {
+            if let Err(e) = store.save(&approval) {
+                tracing::warn!("failed to save approval: {e}");
+            }
+        }
+        self.approvals.write().push(approval);
+    }
+
     /// Update the permission presets (e.g., when switching agents).
     pub fn set_presets(&self, presets: AgentPermissions) {
         *self.presets.write() = presets;
     }
 
+    /// Get the action the command rules give a shell command, if any.
+    #[must_use]
+    pub fn command_rule(&self, command: &str) -> Option<PermissionPreset> {
+        self.presets.read().command_rule(command)
+    }
+
+    /// Get the preset for an action, applying command rules to shell commands.
+    fn resolve_preset(
+        &self,
+        action: &PermissionAction,
+        context: &PermissionContext,
+    ) -> PermissionPreset {
+        let rule = match (action, context) {
+            (
+                PermissionAction::Execute | PermissionAction::ExecuteHighRisk { .. },
+                PermissionContext::Bash { command, .. },
+            ) => self.command_rule(command),
+            _ => None,
+        };
+
+        match (rule, action) {
+            // Like the presets, rules can't pre-approve high-risk commands
+            (Some(PermissionPreset::Allow), PermissionAction::ExecuteHighRisk { .. }) => {
+                PermissionPreset::Ask
+            }
+            (Some(rule), _) => rule,
+            (None, _) => self.get_preset(action),
+        }
+    }
+
     /// Get the preset for a given action.
     fn get_preset(&self, action: &PermissionAction) -> PermissionPreset {
         let presets = self.presets.read();
         match action {
             PermissionAction::Execute => presets.bash_write,
+            // High-risk commands can be denied outright but never pre-approved
+            PermissionAction::ExecuteHighRisk { .. } => match presets.bash_write {
+                PermissionPreset::Deny => PermissionPreset::Deny,
+                PermissionPreset::Allow | PermissionPreset::Ask => PermissionPreset::Ask,
+            },
             PermissionAction::WriteFile => presets.write,
             PermissionAction::EditFile => presets.edit,
             PermissionAction::AskUser => PermissionPreset::Allow, // Always allow ask_user
             PermissionAction::WebSearch | PermissionAction::WebFetch => presets.web_search,
             PermissionAction::CodeSearch => presets.code_search,
+            PermissionAction::Remember => presets.remember,
+            PermissionAction::PluginTool => presets.plugin,
             // Read-only operations default to allow
             PermissionAction::Glob | PermissionAction::Grep | PermissionAction::ListDir => {
                 presets.read
@@ -260,10 +569,11 @@ impl PermissionClient {
     ///
     /// Returns `true` if approved, `false` if denied.
     ///
-    /// Checks the agent's permission presets first:
+    /// Checks the agent's command rules and permission presets first:
     /// - `Allow`: Returns `true` immediately without prompting.
     /// - `Deny`: Returns `false` immediately without prompting.
-    /// - `Ask`: Shows the permission dialog to the user.
+    /// - `Ask`: Allows operations the user approved with `AllowAlways`,
+    ///   otherwise shows the permission dialog to the user.
     ///
     /// # Errors
     ///
@@ -275,12 +585,17 @@ impl PermissionClient {
         context: PermissionContext,
     ) -> Result<bool, PermissionError> {
         // Check preset first - may short-circuit without user prompt
-        match self.get_preset(&action) {
+        match self.resolve_preset(&action, &context) {
             PermissionPreset::Allow => return Ok(true),
             PermissionPreset::Deny => return Ok(false),
             PermissionPreset::Ask => {} // Continue to prompt user.
         }
+        // High-risk commands can't be approved ahead of time
+        if !action.is_high_risk() && self.is_approved(tool, &context) {
+            return Ok(true);
+        }
 
+        let approval = Approval::new(tool, &context);
         let (response_tx, response_rx) = oneshot::channel();
 
         self.permission_tx
@@ -298,6 +613,10 @@ impl PermissionClient {
             .map_err(|_| PermissionError::ChannelClosed)?
         {
             PermissionResponse::Allow | PermissionResponse::AllowForSession => Ok(true),
+            PermissionResponse::AllowAlways => {
+                self.approve(approval);
+                Ok(true)
+            }
             PermissionResponse::Deny => Ok(false),
         }
     }
@@ -381,9 +700,9 @@ impl PermissionActor {
                 context,
                 response_tx,
             } => {
-                // Check cache first
+                // Check cache first (high-risk actions always prompt)
                 let cache_key = (session_id, tool_name.clone(), action.clone());
-                if self.session_cache.contains(&cache_key) {
+                if !action.is_high_risk() && self.session_cache.contains(&cache_key) {
                     let _ = response_tx.send(PermissionResponse::AllowForSession);
                     return;
                 }
@@ -457,8 +776,8 @@ impl PermissionActor {
         action: &PermissionAction,
     ) {
         if let Some(tx) = self.pending_requests.remove(&request_id) {
-            // Cache if `AllowForSession`
-            if response == PermissionResponse::AllowForSession {
+            // Cache if `AllowForSession` (never for high-risk actions)
+            if response == PermissionResponse::AllowForSession && !action.is_high_risk() {
                 self.session_cache.insert((
                     session_id.to_string(),
                     tool_name.to_string(),
@@ -732,6 +1051,28 @@ mod tests {
         assert!(!result.unwrap()); // Should be denied without prompting.
     }
 
+    #[test]
+    fn read_only_denies_everything_but_reads() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let client =
+            PermissionClient::with_presets("test".to_string(), tx, AgentPermissions::read_only());
+
+        for action in [
+            PermissionAction::Execute,
+            PermissionAction::WriteFile,
+            PermissionAction::EditFile,
+            PermissionAction::WebFetch,
+            PermissionAction::Remember,
+            PermissionAction::PluginTool,
+        ] {
+            assert_eq!(client.get_preset(&action), PermissionPreset::Deny);
+        }
+        assert_eq!(
+            client.get_preset(&PermissionAction::Grep),
+            PermissionPreset::Allow
+        );
+    }
+
     #[tokio::test]
     async fn preset_ask_shows_dialog() {
         let (tx, mut rx) = mpsc::unbounded_channel();
@@ -786,4 +1127,278 @@ mod tests {
             PermissionPreset::Allow
         );
     }
+
+    #[test]
+    fn high_risk_ignores_allow_preset() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let presets = AgentPermissions {
+            bash_write: PermissionPreset::Allow,
+            ..Default::default()
+        };
+        let client = PermissionClient::with_presets("test-session".to_string(), tx, presets);
+        let action = PermissionAction::ExecuteHighRisk {
+            pattern: "git push".to_string(),
+        };
+        assert_eq!(client.get_preset(&action), PermissionPreset::Ask);
+
+        client.set_presets(AgentPermissions {
+            bash_write: PermissionPreset::Deny,
+            ..Default::default()
+        });
+        assert_eq!(client.get_preset(&action), PermissionPreset::Deny);
+    }
+
+    fn rules(rules: &[(&str, PermissionPreset)]) -> AgentPermissions {
+        AgentPermissions {
+            rules: rules
+                .iter()
+                .map(|(pattern, action)| PermissionRule {
+                    pattern: (*pattern).to_string(),
+                    action: *action,
+                })
+                .collect(),
+            ..AgentPermissions::default()
+        }
+    }
+
+    #[test]
+    fn rule_patterns_match_prefixes_and_globs() {
+        let rule = |pattern: &str| PermissionRule {
+            pattern: pattern.to_string(),
+            action: PermissionPreset::Allow,
+        };
+        assert!(rule("git status").matches("git status -s"));
+        assert!(rule("git status").matches("  git   status"));
+        assert!(!rule("git status").matches("git statuses"));
+        assert!(!rule("git status").matches("git"));
+        assert!(rule("cargo * --release").matches("cargo build --release"));
+        assert!(!rule("cargo * --release").matches("cargo build --release -v"));
+        assert!(rule("npm run ?est*").matches("npm run test:unit"));
+        assert!(!rule("").matches("ls"));
+    }
+
+    #[test]
+    fn command_rules_check_every_part_of_a_command() {
+        let permissions = rules(&[
+            ("rm", PermissionPreset::Ask),
+            ("git push --force", PermissionPreset::Deny),
+            ("git", PermissionPreset::Allow),
+            ("cargo build", PermissionPreset::Allow),
+        ]);
+        let rule = |command| permissions.command_rule(command);
+
+        // First matching rule wins
+        assert_eq!(rule("git push"), Some(PermissionPreset::Allow));
+        assert_eq!(rule("git push --force"), Some(PermissionPreset::Deny));
+        assert_eq!(
+            rule("cargo build && git commit -m x"),
+            Some(PermissionPreset::Allow)
+        );
+        assert_eq!(
+            rule("cargo build && rm -rf target"),
+            Some(PermissionPreset::Ask)
+        );
+        assert_eq!(
+            rule("git add . | git push --force"),
+            Some(PermissionPreset::Deny)
+        );
+        // Unruled parts fall back to the presets
+        assert_eq!(rule("cargo build; make install"), None);
+        assert_eq!(rule("make"), None);
+        assert_eq!(rule("git log $(curl evil)"), Some(PermissionPreset::Ask));
+    }
+
+    #[test]
+    fn command_rules_never_allow_redirections() {
+        let permissions = rules(&[
+            ("git status", PermissionPreset::Allow),
+            ("cargo build", PermissionPreset::Allow),
+            ("echo", PermissionPreset::Allow),
+        ]);
+        let rule = |command| permissions.command_rule(command);
+
+        assert_eq!(rule("cargo build <(rm -rf ~)"), Some(PermissionPreset::Ask));
+        assert_eq!(rule("git status >(tee x)"), Some(PermissionPreset::Ask));
+        assert_eq!(rule("git status > ~/.bashrc"), Some(PermissionPreset::Ask));
+        assert_eq!(rule("git status >> ~/.bashrc"), Some(PermissionPreset::Ask));
+        assert_eq!(rule("cargo build < input"), Some(PermissionPreset::Ask));
+        // Quoted arrows are plain text
+        assert_eq!(rule("echo 'a > b' \"<c>\""), Some(PermissionPreset::Allow));
+    }
+
+    #[tokio::test]
+    async fn rules_apply_before_presets() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let client = PermissionClient::with_presets(
+            "test-session".to_string(),
+            tx,
+            rules(&[
+                ("cargo build", PermissionPreset::Allow),
+                ("git push", PermissionPreset::Allow),
+                ("rm", PermissionPreset::Deny),
+            ]),
+        );
+        let bash = |command: &str| PermissionContext::Bash {
+            command: command.to_string(),
+            working_dir: PathBuf::from("/tmp"),
+        };
+
+        let allowed = client
+            .request("shell", PermissionAction::Execute, bash("cargo build"))
+            .await;
+        assert!(allowed.unwrap());
+        let denied = client
+            .request("shell", PermissionAction::Execute, bash("rm -rf src"))
+            .await;
+        assert!(!denied.unwrap());
+        assert!(rx.try_recv().is_err());
+
+        // Allow rules don't pre-approve high-risk commands
+        let high_risk = PermissionAction::ExecuteHighRisk {
+            pattern: "git push".to_string(),
+        };
+        assert_eq!(
+            client.resolve_preset(&high_risk, &bash("git push")),
+            PermissionPreset::Ask
+        );
+        // Other tools keep their presets
+        assert_eq!(
+            client.resolve_preset(
+                &PermissionAction::WriteFile,
+                &PermissionContext::WriteFile {
+                    path: PathBuf::from("cargo build"),
+                    content_preview: String::new(),
+                },
+            ),
+            PermissionPreset::Ask
+        );
+    }
+
+    #[derive(Default)]
+    struct MemoryApprovals(parking_lot::Mutex<Vec<Approval>>);
+
+    impl ApprovalStore for MemoryApprovals {
+        fn load(&self) -> anyhow::Result<Vec<Approval>> {
+            Ok(self.0.lock().clone())
+        }
+
+        fn save(&self, approval: &Approval) -> anyhow::Result<()> {
+            self.0.lock().push(approval.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn allow_always_is_saved_and_reloaded() {
+        let store = Arc::new(MemoryApprovals::default());
+        let bash = |command: &str| PermissionContext::Bash {
+            command: command.to_string(),
+            working_dir: PathBuf::from("/tmp"),
+        };
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let client =
+            PermissionClient::new("first".to_string(), tx).with_approval_store(store.clone());
+        let answer = tokio::spawn(async move {
+            if let Some(PermissionMessage::Request { response_tx, .. }) = rx.recv().await {
+                response_tx.send(PermissionResponse::AllowAlways).unwrap();
+            }
+            rx
+        });
+        let allowed = client
+            .request("shell", PermissionAction::Execute, bash("cargo test"))
+            .await;
+        assert!(allowed.unwrap());
+        let mut rx = answer.await.unwrap();
+        assert_eq!(store.0.lock().len(), 1);
+        assert_eq!(store.0.lock()[0].target.as_deref(), Some("cargo test"));
+
+        // A fresh client (e.g. after a restart) allows it without asking
+        let (tx, restarted_rx) = mpsc::unbounded_channel();
+        let restarted =
+            PermissionClient::new("second".to_string(), tx).with_approval_store(store.clone());
+        let allowed = restarted
+            .request("shell", PermissionAction::Execute, bash("cargo test"))
+            .await;
+        assert!(allowed.unwrap());
+        assert!(rx.try_recv().is_err());
+
+        // Other commands and high-risk ones still ask
+        assert!(!restarted.is_approved("shell", &bash("cargo publish")));
+        drop(restarted_rx);
+        let high_risk = restarted
+            .request(
+                "shell",
+                PermissionAction::ExecuteHighRisk {
+                    pattern: "cargo test".to_string(),
+                },
+                bash("cargo test"),
+            )
+            .await;
+        // It tries to prompt, which fails now that no one is listening
+        assert!(matches!(high_risk, Err(PermissionError::ChannelClosed)));
+    }
+
+    #[test]
+    fn file_approvals_cover_only_their_path() {
+        let write = |path: &str| PermissionContext::WriteFile {
+            path: PathBuf::from(path),
+            content_preview: String::new(),
+        };
+        let approval = Approval::new("write_file", &write("src/lib.rs"));
+        assert_eq!(approval.target.as_deref(), Some("src/lib.rs"));
+        assert!(approval.matches("write_file", &write("src/lib.rs")));
+        assert!(!approval.matches("write_file", &write("/etc/passwd")));
+
+        // Approvals saved before targets were recorded still load
+        let saved: Approval =
+            serde_json::from_str(r#"{"tool":"shell","command":"cargo test","approved_at":0}"#)
+                .unwrap();
+        assert_eq!(saved.target.as_deref(), Some("cargo test"));
+    }
+
+    #[test]
+    fn actor_never_caches_high_risk() {
+        let (mut actor, _tx) = PermissionActor::new();
+        let (interface_tx, mut interface_rx) = mpsc::unbounded_channel();
+        actor.handle_message(PermissionMessage::RegisterInterface { interface_tx });
+
+        let action = PermissionAction::ExecuteHighRisk {
+            pattern: "git push".to_string(),
+        };
+        let request = |actor: &mut PermissionActor| {
+            let (response_tx, response_rx) = oneshot::channel();
+            actor.handle_message(PermissionMessage::Request {
+                session_id: "s".to_string(),
+                tool_name: "shell".to_string(),
+                action: action.clone(),
+                context: PermissionContext::Bash {
+                    command: "git push".to_string(),
+                    working_dir: PathBuf::from("/tmp"),
+                },
+                response_tx,
+            });
+            response_rx
+        };
+
+        let _first = request(&mut actor);
+        let Ok(InterfaceMessage::ShowPermissionDialog { request_id, .. }) = interface_rx.try_recv()
+        else {
+            panic!("expected permission dialog");
+        };
+        actor.respond(
+            request_id,
+            PermissionResponse::AllowForSession,
+            "s",
+            "shell",
+            &action,
+        );
+
+        // Second request must prompt again
+        let _second = request(&mut actor);
+        assert!(matches!(
+            interface_rx.try_recv(),
+            Ok(InterfaceMessage::ShowPermissionDialog { .. })
+        ));
+    }
 }
@@ -4,6 +4,29 @@ use std::path::{Path, PathBuf};
 
 use chrono::Local;
 
+/// Phase of the plan-mode workflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanPhase {
+    /// Reading code and gathering context.
+    Exploring,
+    /// Writing or refining the plan file.
+    Drafting,
+    /// Waiting for the user to approve the plan.
+    AwaitingApproval,
+}
+
+impl PlanPhase {
+    /// Short human-readable label for display.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Exploring => "exploring",
+            Self::Drafting => "drafting",
+            Self::AwaitingApproval => "awaiting approval",
+        }
+    }
+}
+
 /// Manages plan file storage and validation.
 pub struct PlanManager {
     /// Project root if in a git repo.
@@ -69,6 +92,29 @@ impl PlanManager {
         false
     }
 
+    /// Determine the plan phase implied by a tool call.
+    ///
+    /// Returns `None` for tools that don't indicate a phase change (e.g. `ask_user`).
+    #[must_use]
+    pub fn phase_for_tool(
+        &self,
+        tool: &str,
+        input: &serde_json::Value,
+        plan_written: bool,
+    ) -> Option<PlanPhase> {
+        match tool {
+            "plan_exit" => Some(PlanPhase::AwaitingApproval),
+            "write_file" | "edit_file" | "multi_edit" => {
+                let path = input.get("path").and_then(|v| v.as_str())?;
+                self.is_plan_path(Path::new(path))
+                    .then_some(PlanPhase::Drafting)
+            }
+            "ask_user" | "todo_read" | "todo_write" => None,
+            _ if plan_written => Some(PlanPhase::Drafting),
+            _ => Some(PlanPhase::Exploring),
+        }
+    }
+
     /// Get the plans directory (creates if needed).
     ///
     /// # Errors
@@ -159,6 +205,31 @@ mod tests {
         assert!(!manager.is_plan_path(Path::new("/other/path.md")));
     }
 
+    #[test]
+    fn phase_for_tool_tracks_workflow() {
+        let manager = PlanManager::with_paths(None, PathBuf::from("/tmp/plans"));
+        let read = serde_json::json!({ "path": "src/main.rs" });
+        let plan = serde_json::json!({ "path": "/tmp/plans/2026-01-26-test.md" });
+
+        assert_eq!(
+            manager.phase_for_tool("read_file", &read, false),
+            Some(PlanPhase::Exploring)
+        );
+        assert_eq!(
+            manager.phase_for_tool("write_file", &plan, false),
+            Some(PlanPhase::Drafting)
+        );
+        assert_eq!(
+            manager.phase_for_tool("read_file", &read, true),
+            Some(PlanPhase::Drafting)
+        );
+        assert_eq!(
+            manager.phase_for_tool("plan_exit", &serde_json::Value::Null, true),
+            Some(PlanPhase::AwaitingApproval)
+        );
+        assert_eq!(manager.phase_for_tool("ask_user", &read, false), None);
+    }
+
     #[test]
     fn is_plan_path_validates_project_local() {
         let manager =
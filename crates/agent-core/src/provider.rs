@@ -4,6 +4,7 @@ use std::pin::Pin;
 
 use async_trait::async_trait;
 use futures::Stream;
+use serde::{Deserialize, Serialize};
 
 use super::error::Result;
 use super::types::{ContentBlock, Message, StopReason, Tool, Usage};
@@ -21,6 +22,51 @@ pub struct CompletionRequest {
     pub system: Option<String>,
     /// Available tools.
     pub tools: Option<Vec<Tool>>,
+    /// Reasoning effort, ignored by models that don't reason.
+    pub reasoning_effort: Option<ReasoningEffort>,
+}
+
+/// How hard a reasoning model thinks before answering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    /// Name used by the `reasoning_effort` API parameter.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    /// Token budget for Anthropic extended thinking.
+    #[must_use]
+    pub const fn budget_tokens(self) -> u32 {
+        match self {
+            Self::Low => 2_048,
+            Self::Medium => 8_192,
+            Self::High => 24_576,
+        }
+    }
+
+    /// The next effort when cycling, with `None` meaning reasoning is off.
+    #[must_use]
+    pub const fn cycle(effort: Option<Self>) -> Option<Self> {
+        match effort {
+            None => Some(Self::Low),
+            Some(Self::Low) => Some(Self::Medium),
+            Some(Self::Medium) => Some(Self::High),
+            Some(Self::High) => None,
+        }
+    }
 }
 
 /// A streaming event from the LLM.
@@ -28,6 +74,8 @@ pub struct CompletionRequest {
 pub enum CompletionEvent {
     /// A chunk of text content.
     TextDelta(String),
+    /// A chunk of reasoning text, streamed separately from the answer.
+    ReasoningDelta(String),
     /// Start of a tool use block.
     ToolUseStart {
         index: usize,
@@ -5,17 +5,26 @@ use futures::StreamExt;
 use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
 
 use crate::error::{AgentError, Result};
-use crate::provider::{CompletionEvent, CompletionRequest, CompletionStream, LlmProvider};
-use crate::types::{ContentBlock, Delta, MessagesRequest, StreamEvent};
+use crate::provider::{
+    CompletionEvent, CompletionRequest, CompletionStream, LlmProvider, ReasoningEffort,
+};
+use crate::types::{
+    CacheControl, ContentBlock, Delta, MessagesRequest, RequestTool, StreamEvent, SystemBlock,
+    SystemPrompt, ThinkingConfig, Usage,
+};
 
 const API_URL: &str = "https://api.anthropic.com/v1/messages";
 const API_VERSION: &str = "2023-06-01";
 
+/// Smallest extended-thinking budget the API accepts.
+const MIN_THINKING_BUDGET: u32 = 1_024;
+
 /// Anthropic (Claude) LLM provider.
 #[derive(Debug, Clone)]
 pub struct AnthropicProvider {
     http: reqwest::Client,
     api_key: String,
+    prompt_cache: bool,
 }
 
 impl AnthropicProvider {
@@ -33,8 +42,77 @@ impl AnthropicProvider {
         Ok(Self {
             http: reqwest::Client::new(),
             api_key,
+            prompt_cache: false,
         })
     }
+
+    /// Mark the system prompt and tools as cacheable.
+    ///
+    /// Repeated requests then read the shared prefix from the prompt cache
+    /// at a fraction of the input price.
+    #[must_use]
+    pub const fn with_prompt_cache(mut self, prompt_cache: bool) -> Self {
+        self.prompt_cache = prompt_cache;
+        self
+    }
+
+    /// Convert a completion request to the Messages API format.
+    fn messages_request(&self, request: CompletionRequest) -> MessagesRequest {
+        let cache_control = self.prompt_cache.then(CacheControl::ephemeral);
+
+        let system = request.system.map(|text| match cache_control {
+            Some(cache_control) => SystemPrompt::Blocks(vec![SystemBlock {
+                kind: "text",
+                text,
+                cache_control: Some(cache_control),
+            }]),
+            None => SystemPrompt::Text(text),
+        });
+
+        // A breakpoint on the last tool caches every tool definition
+        let tools = request.tools.map(|tools| {
+            let last = tools.len().saturating_sub(1);
+            tools
+                .into_iter()
+                .enumerate()
+                .map(|(i, tool)| RequestTool {
+                    tool,
+                    cache_control: cache_control.filter(|_| i == last),
+                })
+                .collect()
+        });
+
+        let thinking = request
+            .reasoning_effort
+            .and_then(|effort| thinking_config(&request.model, effort, request.max_tokens));
+
+        MessagesRequest {
+            model: request.model,
+            max_tokens: request.max_tokens,
+            messages: request.messages,
+            system,
+            tools,
+            thinking,
+            stream: true,
+        }
+    }
+}
+
+/// Extended thinking for `effort`, if `model` supports it.
+///
+/// The budget has to fit under `max_tokens` with room left for the answer,
+/// so a request too small to think in is sent without it.
+fn thinking_config(
+    model: &str,
+    effort: ReasoningEffort,
+    max_tokens: u32,
+) -> Option<ThinkingConfig> {
+    // Claude 3.7 was the first model with extended thinking
+    if model.starts_with("claude-3-") && !model.starts_with("claude-3-7") {
+        return None;
+    }
+    let budget = effort.budget_tokens().min(max_tokens / 2);
+    (budget >= MIN_THINKING_BUDGET).then(|| ThinkingConfig::enabled(budget))
 }
 
 #[async_trait]
@@ -53,14 +131,7 @@ impl LlmProvider for AnthropicProvider {
         headers.insert("anthropic-version", HeaderValue::from_static(API_VERSION));
 
         // Convert to Anthropic-specific request format
-        let anthropic_request = MessagesRequest {
-            model: request.model,
-            max_tokens: request.max_tokens,
-            messages: request.messages,
-            system: request.system,
-            tools: request.tools,
-            stream: true,
-        };
+        let anthropic_request = self.messages_request(request);
 
         let response = self
             .http
@@ -84,6 +155,7 @@ impl LlmProvider for AnthropicProvider {
         let stream = async_stream::stream! {
             let mut buffer = String::new();
             let mut current_blocks: Vec<ContentBlock> = Vec::new();
+            let mut start_usage = Usage::default();
 
             futures::pin_mut!(byte_stream);
 
@@ -101,6 +173,10 @@ impl LlmProvider for AnthropicProvider {
 
                     // Convert Anthropic events to generic completion events
                     match event {
+                        StreamEvent::MessageStart { message } => {
+                            start_usage = message.usage;
+                        }
+
                         StreamEvent::ContentBlockStart { index, content_block } => {
                             // Ensure we have space
                             while current_blocks.len() <= index {
@@ -125,6 +201,17 @@ impl LlmProvider for AnthropicProvider {
                                 Delta::InputJsonDelta { partial_json } => {
                                     yield Ok(CompletionEvent::ToolInputDelta { index, partial_json });
                                 }
+                                Delta::ThinkingDelta { thinking } => {
+                                    if let Some(ContentBlock::Thinking { thinking: t, .. }) = current_blocks.get_mut(index) {
+                                        t.push_str(&thinking);
+                                    }
+                                    yield Ok(CompletionEvent::ReasoningDelta(thinking));
+                                }
+                                Delta::SignatureDelta { signature } => {
+                                    if let Some(ContentBlock::Thinking { signature: s, .. }) = current_blocks.get_mut(index) {
+                                        s.push_str(&signature);
+                                    }
+                                }
                             }
                         }
 
@@ -137,7 +224,7 @@ impl LlmProvider for AnthropicProvider {
                         StreamEvent::MessageDelta { delta, usage } => {
                             yield Ok(CompletionEvent::Done {
                                 stop_reason: delta.stop_reason,
-                                usage: Some(usage),
+                                usage: Some(usage.merged_with(&start_usage)),
                             });
                         }
 
@@ -203,4 +290,143 @@ mod tests {
         let result = AnthropicProvider::new("test-key");
         assert!(result.is_ok());
     }
+
+    fn request() -> CompletionRequest {
+        let tool = |name: &str| crate::types::Tool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({"type": "object"}),
+        };
+        CompletionRequest {
+            model: "claude-sonnet-4-20250514".to_string(),
+            max_tokens: 16,
+            messages: Vec::new(),
+            system: Some("be brief".to_string()),
+            tools: Some(vec![tool("read_file"), tool("grep")]),
+            reasoning_effort: None,
+        }
+    }
+
+    #[test]
+    fn reasoning_effort_requests_a_thinking_budget() {
+        let provider = AnthropicProvider::new("test-key").unwrap();
+        let thinking_request = |model: &str, max_tokens| CompletionRequest {
+            model: model.to_string(),
+            max_tokens,
+            reasoning_effort: Some(ReasoningEffort::Medium),
+            ..request()
+        };
+
+        let body = thinking_request("claude-sonnet-4-20250514", 32_000);
+        let body = serde_json::to_value(provider.messages_request(body)).unwrap();
+        assert_eq!(
+            body["thinking"],
+            serde_json::json!({"type": "enabled", "budget_tokens": 8192})
+        );
+
+        // The budget shrinks to leave room for the answer
+        let body = thinking_request("claude-sonnet-4-20250514", 4_096);
+        let body = serde_json::to_value(provider.messages_request(body)).unwrap();
+        assert_eq!(body["thinking"]["budget_tokens"], 2048);
+
+        for body in [
+            thinking_request("claude-3-5-haiku-20241022", 32_000),
+            thinking_request("claude-sonnet-4-20250514", 1_024),
+            request(),
+        ] {
+            let body = serde_json::to_value(provider.messages_request(body)).unwrap();
+            assert!(body.get("thinking").is_none());
+        }
+    }
+
+    #[test]
+    fn thinking_deltas_parse() {
+        let start = concat!(
+            r#"data: {"type":"content_block_start","index":0,"#,
+            r#""content_block":{"type":"thinking","thinking":""}}"#,
+            "\n\n"
+        );
+        let delta = concat!(
+            r#"data: {"type":"content_block_delta","index":0,"#,
+            r#""delta":{"type":"thinking_delta","thinking":"Hmm"}}"#,
+            "\n\n"
+        );
+        assert!(matches!(
+            parse_sse_event(start),
+            Some((
+                Some(StreamEvent::ContentBlockStart {
+                    content_block: ContentBlock::Thinking { .. },
+                    ..
+                }),
+                _
+            ))
+        ));
+        assert!(matches!(
+            parse_sse_event(delta),
+            Some((
+                Some(StreamEvent::ContentBlockDelta {
+                    delta: Delta::ThinkingDelta { .. },
+                    ..
+                }),
+                _
+            ))
+        ));
+    }
+
+    #[test]
+    fn prompt_cache_marks_system_and_last_tool() {
+        let provider = AnthropicProvider::new("test-key")
+            .unwrap()
+            .with_prompt_cache(true);
+        let body = serde_json::to_value(provider.messages_request(request())).unwrap();
+
+        assert_eq!(
+            body["system"],
+            serde_json::json!([{
+                "type": "text",
+                "text": "be brief",
+                "cache_control": {"type": "ephemeral"},
+            }])
+        );
+        assert!(body["tools"][0].get("cache_control").is_none());
+        assert_eq!(body["tools"][1]["name"], "grep");
+        assert_eq!(body["tools"][1]["cache_control"]["type"], "ephemeral");
+    }
+
+    #[test]
+    fn uncached_request_sends_plain_system_prompt() {
+        let provider = AnthropicProvider::new("test-key").unwrap();
+        let body = serde_json::to_value(provider.messages_request(request())).unwrap();
+
+        assert_eq!(body["system"], "be brief");
+        assert!(body["tools"][1].get("cache_control").is_none());
+    }
+
+    #[test]
+    fn usage_merges_message_start_counts() {
+        let start = concat!(
+            r#"data: {"type":"message_start","message":{"id":"msg_1","usage":"#,
+            r#"{"input_tokens":12,"cache_creation_input_tokens":300,"#,
+            r#""cache_read_input_tokens":2000,"output_tokens":1}}}"#,
+            "\n\n"
+        );
+        let end = concat!(
+            r#"data: {"type":"message_delta","delta":{"stop_reason":"end_turn"},"#,
+            r#""usage":{"output_tokens":42}}"#,
+            "\n\n"
+        );
+        let Some((Some(StreamEvent::MessageStart { message }), _)) = parse_sse_event(start) else {
+            panic!("expected message_start");
+        };
+        let Some((Some(StreamEvent::MessageDelta { usage, .. }), _)) = parse_sse_event(end) else {
+            panic!("expected message_delta");
+        };
+
+        let usage = usage.merged_with(&message.usage);
+        assert_eq!(usage.input_tokens, 12);
+        assert_eq!(usage.output_tokens, 42);
+        assert_eq!(usage.cache_creation_input_tokens, 300);
+        assert_eq!(usage.cache_read_input_tokens, 2000);
+        assert_eq!(usage.prompt_tokens(), 2312);
+    }
 }
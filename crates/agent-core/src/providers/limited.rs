@@ -0,0 +1,269 @@
+//! Concurrency and rate limiting for providers.
+//!
+//! Limiters are shared per provider name, so every agent talking to the same
+//! provider draws from the same semaphore and token bucket.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::{AgentError, Result};
+use crate::provider::{CompletionRequest, CompletionStream, LlmProvider};
+
+/// Request limits for a provider. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Maximum requests streaming at once.
+    pub max_concurrent: Option<usize>,
+    /// Maximum requests started per minute.
+    pub requests_per_minute: Option<u32>,
+}
+
+impl RateLimit {
+    /// Whether no limit is configured.
+    #[must_use]
+    pub const fn is_unlimited(&self) -> bool {
+        self.max_concurrent.is_none() && self.requests_per_minute.is_none()
+    }
+}
+
+/// Token bucket refilled continuously at `requests_per_minute`.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    per_second: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(requests_per_minute: u32, now: Instant) -> Self {
+        let capacity = f64::from(requests_per_minute.max(1));
+        Self {
+            capacity,
+            tokens: capacity,
+            per_second: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    /// Take a token, or return how long to wait until one is available.
+    fn try_take(&mut self, now: Instant) -> std::result::Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = elapsed
+            .mul_add(self.per_second, self.tokens)
+            .min(self.capacity);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.per_second,
+            ))
+        }
+    }
+}
+
+/// Shared limiter state for one provider.
+#[derive(Debug)]
+pub struct ProviderLimiter {
+    limit: RateLimit,
+    semaphore: Option<Arc<Semaphore>>,
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+/// Limiters by provider name.
+static LIMITERS: LazyLock<Mutex<HashMap<String, Arc<ProviderLimiter>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+impl ProviderLimiter {
+    /// Create a standalone limiter.
+    #[must_use]
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            semaphore: limit
+                .max_concurrent
+                .map(|n| Arc::new(Semaphore::new(n.max(1)))),
+            bucket: limit
+                .requests_per_minute
+                .map(|rpm| Mutex::new(TokenBucket::new(rpm, Instant::now()))),
+        }
+    }
+
+    /// Get the limiter shared by all agents using `provider`.
+    ///
+    /// A new limiter replaces the shared one if the limits have changed.
+    #[must_use]
+    pub fn shared(provider: &str, limit: RateLimit) -> Arc<Self> {
+        let mut limiters = LIMITERS.lock();
+        match limiters.get(provider) {
+            Some(existing) if existing.limit == limit => Arc::clone(existing),
+            _ => {
+                let limiter = Arc::new(Self::new(limit));
+                limiters.insert(provider.to_string(), Arc::clone(&limiter));
+                limiter
+            }
+        }
+    }
+
+    /// Wait for a concurrency slot and a rate-limit token.
+    ///
+    /// The returned permit, if any, holds the concurrency slot until dropped.
+    async fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>> {
+        let permit = match &self.semaphore {
+            Some(semaphore) => Some(
+                Arc::clone(semaphore)
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| AgentError::Config(e.to_string()))?,
+            ),
+            None => None,
+        };
+
+        if let Some(bucket) = &self.bucket {
+            loop {
+                let wait = bucket.lock().try_take(Instant::now());
+                match wait {
+                    Ok(()) => break,
+                    Err(wait) => {
+                        tracing::debug!(?wait, "provider rate limit reached, waiting");
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+            }
+        }
+
+        Ok(permit)
+    }
+}
+
+/// Provider wrapper that enforces a [`ProviderLimiter`].
+pub struct RateLimitedProvider {
+    inner: Box<dyn LlmProvider>,
+    limiter: Arc<ProviderLimiter>,
+}
+
+impl RateLimitedProvider {
+    /// Wrap a provider with a limiter.
+    #[must_use]
+    pub fn new(inner: Box<dyn LlmProvider>, limiter: Arc<ProviderLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RateLimitedProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+        let permit = self.limiter.acquire().await?;
+        let stream = self.inner.stream(request).await?;
+
+        // Hold the concurrency slot until the stream is dropped
+        Ok(Box::pin(stream.map(move |event| {
+            let _slot = &permit;
+            event
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::CompletionEvent;
+
+    struct EmptyProvider;
+
+    #[async_trait]
+    impl LlmProvider for EmptyProvider {
+        fn name(&self) -> &'static str {
+            "empty"
+        }
+
+        async fn stream(&self, _request: CompletionRequest) -> Result<CompletionStream> {
+            Ok(Box::pin(futures::stream::iter(vec![Ok(
+                CompletionEvent::TextDelta("hi".to_string()),
+            )])))
+        }
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            model: "test".to_string(),
+            max_tokens: 1,
+            messages: Vec::new(),
+            system: None,
+            tools: None,
+            reasoning_effort: None,
+        }
+    }
+
+    #[test]
+    fn token_bucket_allows_burst_then_waits() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(60, start);
+
+        for _ in 0..60 {
+            assert!(bucket.try_take(start).is_ok());
+        }
+        let wait = bucket.try_take(start).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(1));
+
+        // Refills at one per second
+        assert!(bucket.try_take(start + Duration::from_secs(1)).is_ok());
+        assert!(
+            bucket
+                .try_take(start + Duration::from_millis(1500))
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrency_slot_is_held_until_stream_drops() {
+        let limiter = Arc::new(ProviderLimiter::new(RateLimit {
+            max_concurrent: Some(1),
+            requests_per_minute: None,
+        }));
+        let provider = RateLimitedProvider::new(Box::new(EmptyProvider), limiter);
+
+        let first = provider.stream(request()).await.unwrap();
+        let blocked =
+            tokio::time::timeout(Duration::from_millis(50), provider.stream(request())).await;
+        assert!(blocked.is_err(), "second stream should wait for a slot");
+
+        drop(first);
+        let second =
+            tokio::time::timeout(Duration::from_millis(50), provider.stream(request())).await;
+        assert!(second.is_ok());
+    }
+
+    #[test]
+    fn shared_limiter_is_reused_until_limits_change() {
+        let limit = RateLimit {
+            max_concurrent: Some(2),
+            requests_per_minute: None,
+        };
+        let a = ProviderLimiter::shared("limited-test", limit);
+        let b = ProviderLimiter::shared("limited-test", limit);
+        assert!(Arc::ptr_eq(&a, &b));
+
+        let changed = ProviderLimiter::shared(
+            "limited-test",
+            RateLimit {
+                max_concurrent: Some(3),
+                requests_per_minute: None,
+            },
+        );
+        assert!(!Arc::ptr_eq(&a, &changed));
+    }
+}
@@ -1,9 +1,11 @@
 //! LLM provider implementations.
 
 mod anthropic;
+mod limited;
 mod openai;
 mod unified;
 
 pub use anthropic::AnthropicProvider;
+pub use limited::{ProviderLimiter, RateLimit, RateLimitedProvider};
 pub use openai::OpenAiProvider;
 pub use unified::UnifiedProvider;
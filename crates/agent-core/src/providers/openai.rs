@@ -4,21 +4,36 @@
 
 use async_trait::async_trait;
 use futures::StreamExt;
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
 
 use crate::error::{AgentError, Result};
-use crate::provider::{CompletionEvent, CompletionRequest, CompletionStream, LlmProvider};
+use crate::provider::{
+    CompletionEvent, CompletionRequest, CompletionStream, LlmProvider, ReasoningEffort,
+};
 use crate::types::{Content, ContentBlock, Message, Role, StopReason, Tool};
 
 const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
 
+/// Azure `OpenAI` API version used when none is configured.
+pub const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+
 /// LLM provider for the `OpenAI` API and compatible endpoints.
 #[derive(Debug, Clone)]
 pub struct OpenAiProvider {
     http: reqwest::Client,
     api_key: Option<String>,
     base_url: String,
+    anonymous: bool,
+    /// Azure deployment, if this provider talks to Azure `OpenAI`
+    azure: Option<AzureDeployment>,
+}
+
+/// Azure `OpenAI` deployment addressing.
+#[derive(Debug, Clone)]
+struct AzureDeployment {
+    deployment: String,
+    api_version: String,
 }
 
 impl OpenAiProvider {
@@ -37,6 +52,8 @@ impl OpenAiProvider {
             http: reqwest::Client::new(),
             api_key: Some(api_key),
             base_url: DEFAULT_BASE_URL.to_string(),
+            anonymous: false,
+            azure: None,
         })
     }
 
@@ -53,8 +70,153 @@ impl OpenAiProvider {
             http: reqwest::Client::new(),
             api_key,
             base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            anonymous: false,
+            azure: None,
+        })
+    }
+
+    /// Create a provider for an Azure `OpenAI` deployment.
+    ///
+    /// `endpoint` is the resource URL (e.g., `https://my-resource.openai.azure.com`).
+    /// Requests go to the deployment's chat completions route and authenticate
+    /// with an `api-key` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if API key is empty.
+    pub fn azure(
+        api_key: impl Into<String>,
+        endpoint: impl Into<String>,
+        deployment: impl Into<String>,
+        api_version: Option<String>,
+    ) -> Result<Self> {
+        let api_key = api_key.into();
+        if api_key.is_empty() {
+            return Err(AgentError::ApiKeyMissing);
+        }
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            api_key: Some(api_key),
+            base_url: endpoint.into().trim_end_matches('/').to_string(),
+            anonymous: false,
+            azure: Some(AzureDeployment {
+                deployment: deployment.into(),
+                api_version: api_version.unwrap_or_else(|| DEFAULT_AZURE_API_VERSION.to_string()),
+            }),
         })
     }
+
+    /// Chat completions URL for this endpoint.
+    fn chat_url(&self) -> String {
+        match &self.azure {
+            Some(azure) => format!(
+                "{}/openai/deployments/{}/chat/completions?api-version={}",
+                self.base_url, azure.deployment, azure.api_version
+            ),
+            None => format!("{}/chat/completions", self.base_url),
+        }
+    }
+
+    /// Request headers, including the API key if present.
+    fn headers(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        if let Some(api_key) = &self.api_key {
+            // Azure takes the key as-is; everything else uses a bearer token
+            let (name, value) = if self.azure.is_some() {
+                (HeaderName::from_static("api-key"), api_key.clone())
+            } else {
+                (AUTHORIZATION, format!("Bearer {api_key}"))
+            };
+            headers.insert(
+                name,
+                HeaderValue::from_str(&value).map_err(|_| AgentError::ApiKeyMissing)?,
+            );
+        }
+
+        Ok(headers)
+    }
+
+    /// Send privacy-respecting request flags where the endpoint supports them.
+    ///
+    /// - `OpenAI`: `store: false`, so completions are not retained for evals or distillation
+    /// - `OpenRouter`: `provider.data_collection = "deny"`, so requests only route to
+    ///   upstream providers that do not log or train on prompts
+    ///
+    /// Other compatible endpoints receive no extra fields.
+    #[must_use]
+    pub const fn with_anonymous(mut self, anonymous: bool) -> Self {
+        self.anonymous = anonymous;
+        self
+    }
+
+    /// Privacy fields for the request body, based on the endpoint
+    fn privacy_fields(&self) -> (Option<bool>, Option<ProviderPreferences>) {
+        if !self.anonymous {
+            return (None, None);
+        }
+        if self.base_url.contains("openrouter.ai") {
+            (
+                None,
+                Some(ProviderPreferences {
+                    data_collection: "deny",
+                }),
+            )
+        } else if self.base_url == DEFAULT_BASE_URL {
+            (Some(false), None)
+        } else {
+            (None, None)
+        }
+    }
+
+    /// Convert a completion request to the chat completions format.
+    fn chat_request(&self, request: CompletionRequest) -> OpenAiRequest {
+        let tools = request.tools.as_ref().map(|t| convert_tools(t));
+        let (store, provider) = self.privacy_fields();
+        let reasoning_effort = request
+            .reasoning_effort
+            .filter(|_| is_reasoning_model(&request.model))
+            .map(ReasoningEffort::as_str);
+        // Reasoning models reject `max_tokens`
+        let (max_tokens, max_completion_tokens) = if is_o_series(&request.model) {
+            (None, Some(request.max_tokens))
+        } else {
+            (Some(request.max_tokens), None)
+        };
+
+        OpenAiRequest {
+            messages: convert_messages(&request.messages, request.system.as_deref()),
+            model: request.model,
+            max_tokens,
+            max_completion_tokens,
+            tools,
+            stream: true,
+            stream_options: Some(StreamOptions {
+                include_usage: true,
+            }),
+            store,
+            provider,
+            reasoning_effort,
+        }
+    }
+}
+
+/// Whether `model` is an o-series or GPT-5 model, which take
+/// `max_completion_tokens` instead of `max_tokens`.
+fn is_o_series(model: &str) -> bool {
+    // Gateways such as OpenRouter prefix the vendor, e.g. `openai/o3-mini`
+    let model = model.rsplit('/').next().unwrap_or(model);
+    ["o1", "o3", "o4", "gpt-5"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+}
+
+/// Whether `model` accepts `reasoning_effort`, as the o-series and GPT-5 do.
+fn is_reasoning_model(model: &str) -> bool {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    is_o_series(model) && name != "o1-preview" && !name.starts_with("o1-mini")
 }
 
 // OpenAI request types
@@ -62,13 +224,22 @@ impl OpenAiProvider {
 #[derive(Debug, Serialize)]
 struct OpenAiRequest {
     model: String,
-    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<u32>,
     messages: Vec<OpenAiMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAiTool>>,
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    store: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provider: Option<ProviderPreferences>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<&'static str>,
 }
 
 #[derive(Debug, Serialize)]
@@ -76,6 +247,12 @@ struct StreamOptions {
     include_usage: bool,
 }
 
+/// `OpenRouter` provider routing preferences
+#[derive(Debug, Serialize)]
+struct ProviderPreferences {
+    data_collection: &'static str,
+}
+
 #[derive(Debug, Serialize)]
 struct OpenAiMessage {
     role: &'static str,
@@ -138,10 +315,27 @@ struct OpenAiChoice {
 struct OpenAiDelta {
     #[serde(default)]
     content: Option<String>,
+    /// Reasoning text from models such as `deepseek-reasoner`.
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    /// Reasoning text from gateways such as `OpenRouter` and Ollama.
+    #[serde(default)]
+    reasoning: Option<String>,
     #[serde(default)]
     tool_calls: Option<Vec<OpenAiToolCallDelta>>,
 }
 
+impl OpenAiDelta {
+    /// Take the reasoning text, under whichever field the server uses.
+    fn take_reasoning(&mut self) -> Option<String> {
+        self.reasoning_content
+            .take()
+            .into_iter()
+            .chain(self.reasoning.take())
+            .find(|text| !text.is_empty())
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct OpenAiToolCallDelta {
     index: usize,
@@ -215,6 +409,8 @@ fn convert_messages(messages: &[Message], system: Option<&str>) -> Vec<OpenAiMes
                         } => {
                             tool_results.push((tool_use_id.clone(), content.clone()));
                         }
+                        // Thinking is Anthropic-only and not sent elsewhere
+                        ContentBlock::Thinking { .. } => {}
                     }
                 }
 
@@ -331,32 +527,11 @@ impl LlmProvider for OpenAiProvider {
 
     #[allow(clippy::too_many_lines)]
     async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
-        let mut headers = HeaderMap::new();
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-
-        // Add authorization header if API key is present
-        if let Some(api_key) = &self.api_key {
-            headers.insert(
-                AUTHORIZATION,
-                HeaderValue::from_str(&format!("Bearer {api_key}"))
-                    .map_err(|_| AgentError::ApiKeyMissing)?,
-            );
-        }
+        let headers = self.headers()?;
 
-        let openai_tools = request.tools.as_ref().map(|t| convert_tools(t));
+        let openai_request = self.chat_request(request);
 
-        let openai_request = OpenAiRequest {
-            model: request.model,
-            max_tokens: request.max_tokens,
-            messages: convert_messages(&request.messages, request.system.as_deref()),
-            tools: openai_tools,
-            stream: true,
-            stream_options: Some(StreamOptions {
-                include_usage: true,
-            }),
-        };
-
-        let url = format!("{}/chat/completions", self.base_url);
+        let url = self.chat_url();
         let response = self
             .http
             .post(&url)
@@ -398,7 +573,12 @@ impl LlmProvider for OpenAiProvider {
                         continue;
                     };
 
-                    for choice in chunk.choices {
+                    for mut choice in chunk.choices {
+                        // Reasoning arrives before the answer on reasoning models
+                        if let Some(reasoning) = choice.delta.take_reasoning() {
+                            yield Ok(CompletionEvent::ReasoningDelta(reasoning));
+                        }
+
                         // Handle text content
                         if let Some(text) = choice.delta.content {
                             if !text.is_empty() {
@@ -501,6 +681,65 @@ mod tests {
         assert_eq!(provider.name(), "openai");
     }
 
+    #[test]
+    fn anonymous_sets_endpoint_privacy_flags() {
+        let openai = OpenAiProvider::new("key").unwrap().with_anonymous(true);
+        let (store, provider) = openai.privacy_fields();
+        assert_eq!(store, Some(false));
+        assert!(provider.is_none());
+
+        let openrouter = OpenAiProvider::with_config(
+            Some("key".to_string()),
+            Some("https://openrouter.ai/api/v1".to_string()),
+        )
+        .unwrap()
+        .with_anonymous(true);
+        let (store, provider) = openrouter.privacy_fields();
+        assert!(store.is_none());
+        assert_eq!(provider.unwrap().data_collection, "deny");
+
+        let local = OpenAiProvider::with_config(None, Some("http://localhost:11434/v1".into()))
+            .unwrap()
+            .with_anonymous(true);
+        let (store, provider) = local.privacy_fields();
+        assert!(store.is_none() && provider.is_none());
+
+        let (store, provider) = OpenAiProvider::new("key").unwrap().privacy_fields();
+        assert!(store.is_none() && provider.is_none());
+    }
+
+    #[test]
+    fn reasoning_effort_is_sent_to_reasoning_models_only() {
+        let provider = OpenAiProvider::new("key").unwrap();
+        let request = |model: &str| CompletionRequest {
+            model: model.to_string(),
+            max_tokens: 16,
+            messages: Vec::new(),
+            system: None,
+            tools: None,
+            reasoning_effort: Some(ReasoningEffort::High),
+        };
+
+        let body = serde_json::to_value(provider.chat_request(request("o3-mini"))).unwrap();
+        assert_eq!(body["reasoning_effort"], "high");
+        // The token limit goes where reasoning models look for it
+        assert_eq!(body["max_completion_tokens"], 16);
+        assert!(body.get("max_tokens").is_none());
+        let body = serde_json::to_value(provider.chat_request(request("openai/o4-mini"))).unwrap();
+        assert_eq!(body["reasoning_effort"], "high");
+        assert_eq!(body["max_completion_tokens"], 16);
+
+        // o1-mini takes no effort but still rejects max_tokens
+        let body = serde_json::to_value(provider.chat_request(request("o1-mini"))).unwrap();
+        assert!(body.get("reasoning_effort").is_none());
+        assert_eq!(body["max_completion_tokens"], 16);
+
+        let body = serde_json::to_value(provider.chat_request(request("gpt-4o"))).unwrap();
+        assert!(body.get("reasoning_effort").is_none());
+        assert_eq!(body["max_tokens"], 16);
+        assert!(body.get("max_completion_tokens").is_none());
+    }
+
     #[test]
     fn with_config_uses_default_base_url() {
         let provider = OpenAiProvider::with_config(Some("key".to_string()), None).unwrap();
@@ -523,6 +762,37 @@ mod tests {
         assert!(provider.api_key.is_none());
     }
 
+    #[test]
+    fn azure_uses_deployment_url_and_api_key_header() {
+        let provider = OpenAiProvider::azure(
+            "secret",
+            "https://my-resource.openai.azure.com/",
+            "gpt-4o-prod",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            provider.chat_url(),
+            format!(
+                "https://my-resource.openai.azure.com/openai/deployments/gpt-4o-prod/chat/completions?api-version={DEFAULT_AZURE_API_VERSION}"
+            )
+        );
+
+        let headers = provider.headers().unwrap();
+        assert_eq!(headers.get("api-key").unwrap(), "secret");
+        assert!(headers.get(AUTHORIZATION).is_none());
+
+        let openai = OpenAiProvider::new("secret").unwrap();
+        assert_eq!(
+            openai.chat_url(),
+            format!("{DEFAULT_BASE_URL}/chat/completions")
+        );
+        assert_eq!(
+            openai.headers().unwrap().get(AUTHORIZATION).unwrap(),
+            "Bearer secret"
+        );
+    }
+
     #[test]
     fn convert_tools_produces_function_type() {
         let tools = vec![Tool {
@@ -570,4 +840,29 @@ mod tests {
         assert!(chunk.is_none());
         assert!(remainder.is_empty());
     }
+
+    #[test]
+    fn parse_sse_event_reads_reasoning_content() {
+        let buffer = "data: {\"choices\":[{\"delta\":{\"reasoning_content\":\"Let me think\",\"content\":null},\"finish_reason\":null}]}\n\n";
+        let (chunk, _) = parse_sse_event(buffer).unwrap();
+        let delta = &chunk.unwrap().choices[0].delta;
+        assert_eq!(delta.reasoning_content.as_deref(), Some("Let me think"));
+        assert!(delta.content.is_none());
+    }
+
+    #[test]
+    fn reasoning_is_kept_apart_from_answer_text() {
+        let buffer = "data: {\"choices\":[{\"delta\":{\"reasoning\":\"Weighing options\",\"content\":\"Use a map\"},\"finish_reason\":null}]}\n\n";
+        let (chunk, _) = parse_sse_event(buffer).unwrap();
+        let mut delta = chunk.unwrap().choices.remove(0).delta;
+        assert_eq!(delta.take_reasoning().as_deref(), Some("Weighing options"));
+        assert_eq!(delta.content.as_deref(), Some("Use a map"));
+        assert!(delta.take_reasoning().is_none());
+
+        let mut empty = OpenAiDelta {
+            reasoning_content: Some(String::new()),
+            ..OpenAiDelta::default()
+        };
+        assert!(empty.take_reasoning().is_none());
+    }
 }
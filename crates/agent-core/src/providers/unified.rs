@@ -188,6 +188,7 @@ fn convert_messages(messages: &[Message], system: Option<&str>) -> Vec<ChatMessa
                                 },
                             });
                         }
+                        ContentBlock::Thinking { .. } => {}
                     }
                 }
 
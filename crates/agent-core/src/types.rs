@@ -70,6 +70,13 @@ pub enum ContentBlock {
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
     },
+
+    /// Extended thinking from assistant, sent back verbatim on later turns.
+    Thinking {
+        thinking: String,
+        #[serde(default)]
+        signature: String,
+    },
 }
 
 /// Tool definition.
@@ -92,12 +99,75 @@ pub struct MessagesRequest {
     pub max_tokens: u32,
     pub messages: Vec<Message>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub system: Option<String>,
+    pub system: Option<SystemPrompt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<RequestTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tools: Option<Vec<Tool>>,
+    pub thinking: Option<ThinkingConfig>,
     pub stream: bool,
 }
 
+/// Extended thinking settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ThinkingConfig {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub budget_tokens: u32,
+}
+
+impl ThinkingConfig {
+    /// Enable thinking with a token budget.
+    #[must_use]
+    pub const fn enabled(budget_tokens: u32) -> Self {
+        Self {
+            kind: "enabled",
+            budget_tokens,
+        }
+    }
+}
+
+/// System prompt, either plain text or blocks carrying cache breakpoints.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum SystemPrompt {
+    Text(String),
+    Blocks(Vec<SystemBlock>),
+}
+
+/// Text block in a system prompt.
+#[derive(Debug, Serialize)]
+pub struct SystemBlock {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+}
+
+/// Tool definition as sent to the Messages API.
+#[derive(Debug, Serialize)]
+pub struct RequestTool {
+    #[serde(flatten)]
+    pub tool: Tool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+}
+
+/// Prompt cache breakpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CacheControl {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+impl CacheControl {
+    /// Short-lived cache entry, refreshed each time it is read.
+    #[must_use]
+    pub const fn ephemeral() -> Self {
+        Self { kind: "ephemeral" }
+    }
+}
+
 /// Why the response stopped.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -109,10 +179,49 @@ pub enum StopReason {
 }
 
 /// Token usage.
-#[derive(Debug, Clone, Deserialize)]
+///
+/// `input_tokens` excludes prompt tokens written to or read from the cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
 pub struct Usage {
+    #[serde(default)]
     pub input_tokens: u32,
+    #[serde(default)]
     pub output_tokens: u32,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u32,
+    #[serde(default)]
+    pub cache_read_input_tokens: u32,
+}
+
+impl Usage {
+    /// All prompt tokens, cached or not.
+    #[must_use]
+    pub const fn prompt_tokens(&self) -> u32 {
+        self.input_tokens
+            .saturating_add(self.cache_creation_input_tokens)
+            .saturating_add(self.cache_read_input_tokens)
+    }
+
+    /// Fill in counts missing from `self` with those from `start`.
+    ///
+    /// Anthropic reports prompt tokens when the message starts and output
+    /// tokens when it ends.
+    #[must_use]
+    pub fn merged_with(self, start: &Self) -> Self {
+        let pick = |end: u32, start: u32| if end == 0 { start } else { end };
+        Self {
+            input_tokens: pick(self.input_tokens, start.input_tokens),
+            output_tokens: pick(self.output_tokens, start.output_tokens),
+            cache_creation_input_tokens: pick(
+                self.cache_creation_input_tokens,
+                start.cache_creation_input_tokens,
+            ),
+            cache_read_input_tokens: pick(
+                self.cache_read_input_tokens,
+                start.cache_read_input_tokens,
+            ),
+        }
+    }
 }
 
 /// Streaming event from Anthropic SSE.
@@ -157,6 +266,8 @@ pub struct MessageStart {
 pub enum Delta {
     TextDelta { text: String },
     InputJsonDelta { partial_json: String },
+    ThinkingDelta { thinking: String },
+    SignatureDelta { signature: String },
 }
 
 /// Message-level delta (stop reason).
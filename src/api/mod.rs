@@ -6,13 +6,17 @@
 // Allow clippy lint triggered by utoipa's OpenApi derive macro
 #![allow(clippy::needless_for_each)]
 
+mod ws;
+
 use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
     Json, Router,
-    extract::State,
-    http::{HeaderMap, StatusCode},
+    body::Body,
+    extract::{Query, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
     middleware::{self, Next},
     response::{
         IntoResponse, Response,
@@ -25,14 +29,20 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use tokio_stream::StreamExt;
 use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use axum::extract::Path;
 
-use crate::config::Config;
-use crate::core::session::{ExportedSession, SessionManager, ShareOptions};
+use crate::config::{Config, CorsConfig};
+use crate::core::agent::ChatEvent;
+use crate::core::session::{
+    ExportedMessage, ExportedSession, Session, SessionManager, ShareExpiredError, ShareOptions,
+    SharePasswordError,
+};
 use crate::core::{Agent, TaskResult};
 
 /// Shared application state.
@@ -40,30 +50,43 @@ pub struct AppState {
     /// The agent (if configured).
     pub agent: Option<Agent>,
 
+    /// Model the agent was configured with at startup, `None` without a
+    /// provider. Unlike `agent`, it stays set while a turn runs.
+    pub model: Option<String>,
+
     /// History of executed tasks.
     pub history: Vec<TaskResult>,
 
     /// API token for authentication (if configured).
     pub token: Option<String>,
+
+    /// Failed share password attempts, kept apart so checks skip the lock.
+    share_attempts: Arc<parking_lot::Mutex<ShareAttempts>>,
 }
 
 impl AppState {
     fn new() -> Self {
         let config = Config::load().unwrap_or_default();
-        let agent = config.agent.create_provider().ok().map(|provider| {
-            Agent::with_context(provider, &config.agent.model, config.agent.max_tokens, None)
-        });
+        let agent = Agent::from_config(&config).ok();
 
         Self {
+            model: agent.as_ref().map(|agent| agent.model().to_string()),
             agent,
             history: Vec::new(),
             token: config.api.token(),
+            share_attempts: Arc::default(),
         }
     }
 }
 
 type SharedState = Arc<RwLock<AppState>>;
 
+/// How long in-flight requests get to finish after a shutdown signal.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often shutdown checks whether streaming tasks returned the agent.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// `OpenAPI` documentation.
 #[derive(OpenApi)]
 #[openapi(
@@ -73,8 +96,31 @@ type SharedState = Arc<RwLock<AppState>>;
         version = "0.1.0",
         license(name = "MIT")
     ),
-    paths(health, execute_agent, get_history),
-    components(schemas(AgentRequest, AgentResponse, TaskResult))
+    paths(
+        health,
+        ready,
+        execute_agent,
+        execute_agent_stream,
+        get_history,
+        list_sessions,
+        get_session_messages,
+        create_share,
+        get_shared_session,
+        delete_share
+    ),
+    components(schemas(
+        ReadyResponse,
+        AgentRequest,
+        AgentResponse,
+        StreamEvent,
+        TaskResult,
+        CreateShareRequest,
+        CreateShareResponse,
+        DeleteShareRequest,
+        ExportedSession,
+        Session,
+        ExportedMessage
+    ))
 )]
 struct ApiDoc;
 
@@ -123,16 +169,171 @@ async fn auth_middleware(
 ///
 /// Returns an error if the server fails to bind or start.
 pub async fn serve(host: &str, port: u16) -> anyhow::Result<()> {
+    let cors = cors_layer(&Config::load().unwrap_or_default().api.cors)?;
     let state: SharedState = Arc::new(RwLock::new(AppState::new()));
 
     // Check if auth is enabled
     let auth_enabled = state.read().await.token.is_some();
 
+    let app = router(Arc::clone(&state));
+    let app = match cors {
+        Some(cors) => app.layer(cors),
+        None => app,
+    };
+
+    let addr = format!("{host}:{port}");
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+
+    if auth_enabled {
+        tracing::info!(addr = %addr, "starting HTTP API server (auth enabled)");
+    } else {
+        tracing::warn!(addr = %addr, "starting HTTP API server (NO AUTH - localhost only recommended)");
+    }
+
+    serve_until(listener, app, state, shutdown_signal()).await
+}
+
+/// Serve `app` until `signal` resolves, then shut down gracefully.
+///
+/// New connections are refused once the signal fires. In-flight requests,
+/// and streaming tasks handing the agent back to `state`, get up to
+/// [`SHUTDOWN_TIMEOUT`] to finish before the server gives up on them.
+async fn serve_until(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    state: SharedState,
+    signal: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<()> {
+    let had_agent = state.read().await.agent.is_some();
+    let shutdown = CancellationToken::new();
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown({
+            let shutdown = shutdown.clone();
+            async move {
+                signal.await;
+                tracing::info!(
+                    timeout_secs = SHUTDOWN_TIMEOUT.as_secs(),
+                    "shutting down HTTP API server, waiting for in-flight requests"
+                );
+                shutdown.cancel();
+            }
+        })
+        .into_future();
+    let mut server = std::pin::pin!(server);
+
+    tokio::select! {
+        result = &mut server => return Ok(result?),
+        () = shutdown.cancelled() => {}
+    }
+
+    let drained = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+        server.await?;
+        // Streaming tasks put the agent back once their turn is persisted
+        while had_agent && state.read().await.agent.is_none() {
+            tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+        }
+        anyhow::Ok(())
+    })
+    .await;
+    if let Ok(result) = drained {
+        result?;
+    } else {
+        tracing::warn!("shutdown timed out with requests still running");
+    }
+
+    tracing::info!("HTTP API server stopped");
+    Ok(())
+}
+
+/// Resolve on Ctrl+C, or SIGTERM on Unix.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("failed to listen for Ctrl+C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
+
+/// Build the CORS layer for `[api.cors]`, or `None` when no origins are allowed.
+///
+/// # Errors
+///
+/// Returns an error if an origin or method is invalid, or if `"*"` is
+/// combined with `allow_credentials`, which the CORS spec forbids.
+fn cors_layer(config: &CorsConfig) -> anyhow::Result<Option<CorsLayer>> {
+    if config.allowed_origins.is_empty() {
+        return Ok(None);
+    }
+
+    let any_origin = config.allowed_origins.iter().any(|origin| origin == "*");
+    if any_origin && config.allow_credentials {
+        anyhow::bail!(
+            "[api.cors] allowed_origins = \"*\" can't be combined with allow_credentials; list the origins instead"
+        );
+    }
+
+    let origins = if any_origin {
+        AllowOrigin::any()
+    } else {
+        let origins = config
+            .allowed_origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin)
+                    .map_err(|_| anyhow::anyhow!("invalid [api.cors] origin: {origin}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+    let methods = config
+        .allowed_methods
+        .iter()
+        .map(|method| {
+            Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                .map_err(|_| anyhow::anyhow!("invalid [api.cors] method: {method}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+            .allow_credentials(config.allow_credentials),
+    ))
+}
+
+/// Build the API router.
+fn router(state: SharedState) -> Router {
     // Protected routes (require auth if token configured)
     let protected_routes = Router::new()
         .route("/api/agent", post(execute_agent))
         .route("/api/agent/stream", post(execute_agent_stream))
+        .route("/api/agent/ws", get(ws::agent_socket))
         .route("/api/history", get(get_history))
+        .route("/api/sessions", get(list_sessions))
+        .route("/api/sessions/{id}/messages", get(get_session_messages))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -141,37 +342,25 @@ pub async fn serve(host: &str, port: u16) -> anyhow::Result<()> {
     // Public routes (no auth required)
     let public_routes = Router::new()
         .route("/health", get(health))
-        .route("/api/share/:token", get(get_shared_session))
+        .route("/ready", get(ready))
+        .route("/api/share/{token}", get(get_shared_session))
         .merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", ApiDoc::openapi()));
 
     // Share routes (some require auth)
     let share_routes = Router::new()
         .route("/api/share", post(create_share))
-        .route("/api/share/:token", axum::routing::delete(delete_share))
+        .route("/api/share/{token}", axum::routing::delete(delete_share))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
         ));
 
-    let app = Router::new()
+    Router::new()
         .merge(protected_routes)
         .merge(share_routes)
         .merge(public_routes)
         .with_state(state)
-        .layer(TraceLayer::new_for_http());
-
-    let addr = format!("{host}:{port}");
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-
-    if auth_enabled {
-        tracing::info!(addr = %addr, "starting HTTP API server (auth enabled)");
-    } else {
-        tracing::warn!(addr = %addr, "starting HTTP API server (NO AUTH - localhost only recommended)");
-    }
-
-    axum::serve(listener, app).await?;
-
-    Ok(())
+        .layer(TraceLayer::new_for_http())
 }
 
 /// Health check endpoint.
@@ -184,6 +373,46 @@ async fn health() -> &'static str {
     "ok"
 }
 
+/// Readiness report.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ReadyResponse {
+    /// `ready` or `not_ready`.
+    pub status: String,
+    /// Whether a provider was created with a resolvable API key.
+    pub provider_configured: bool,
+    /// Model the agent will use, if ready.
+    pub model: Option<String>,
+}
+
+/// Readiness check endpoint.
+///
+/// Unlike `/health`, fails with 503 until the agent can actually run.
+#[utoipa::path(
+    get,
+    path = "/ready",
+    responses(
+        (status = 200, description = "Agent ready", body = ReadyResponse),
+        (status = 503, description = "No usable provider configured", body = ReadyResponse)
+    )
+)]
+async fn ready(State(state): State<SharedState>) -> (StatusCode, Json<ReadyResponse>) {
+    let model = state.read().await.model.clone();
+
+    let (status, label) = if model.is_some() {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+    (
+        status,
+        Json(ReadyResponse {
+            status: label.to_string(),
+            provider_configured: model.is_some(),
+            model,
+        }),
+    )
+}
+
 /// Request body for agent execution.
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct AgentRequest {
@@ -191,17 +420,76 @@ pub struct AgentRequest {
     pub prompt: String,
 }
 
+/// Query parameters for agent execution.
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+pub struct AgentQuery {
+    /// Set to `chunked` to stream the output as plain text.
+    pub stream: Option<String>,
+}
+
+impl AgentQuery {
+    /// Whether the response should be streamed as chunked plain text.
+    ///
+    /// Chosen by `?stream=chunked`, or by an `Accept` header that prefers
+    /// `text/plain` over JSON.
+    fn wants_chunked(&self, headers: &HeaderMap) -> bool {
+        if self.stream.as_deref() == Some("chunked") {
+            return true;
+        }
+        headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|accept| {
+                accept.contains("text/plain") && !accept.contains("application/json")
+            })
+    }
+}
+
 /// Request body for creating a share.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct CreateShareRequest {
     /// Session ID to share.
     pub session_id: String,
     /// TTL in seconds (optional).
     pub ttl_seconds: Option<u64>,
+    /// Password required to read the share (optional).
+    pub password: Option<String>,
+}
+
+/// Header carrying the password for a protected share.
+const SHARE_PASSWORD_HEADER: &str = "x-share-password";
+
+/// Wrong passwords allowed per share within [`SHARE_ATTEMPT_WINDOW`].
+const MAX_SHARE_ATTEMPTS: u32 = 5;
+
+/// Window over which wrong share passwords are counted.
+const SHARE_ATTEMPT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Wrong share passwords by share token, with when counting started.
+#[derive(Debug, Default)]
+struct ShareAttempts(std::collections::HashMap<String, (u32, std::time::Instant)>);
+
+impl ShareAttempts {
+    /// Whether `token` has used up its attempts for the current window.
+    fn is_locked(&mut self, token: &str) -> bool {
+        self.0
+            .retain(|_, (_, since)| since.elapsed() < SHARE_ATTEMPT_WINDOW);
+        self.0
+            .get(token)
+            .is_some_and(|&(failures, _)| failures >= MAX_SHARE_ATTEMPTS)
+    }
+
+    /// Count a wrong password for `token`.
+    fn record_failure(&mut self, token: &str) {
+        self.0
+            .entry(token.to_string())
+            .or_insert((0, std::time::Instant::now()))
+            .0 += 1;
+    }
 }
 
 /// Response body for share creation.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct CreateShareResponse {
     /// Share token for the URL.
     pub token: String,
@@ -212,7 +500,7 @@ pub struct CreateShareResponse {
 }
 
 /// Request body for deleting a share.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct DeleteShareRequest {
     /// Secret for authorization.
     pub secret: String,
@@ -228,12 +516,42 @@ pub struct AgentResponse {
 }
 
 /// SSE event for streaming responses.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
 #[serde(tag = "type")]
 pub enum StreamEvent {
     /// Text chunk.
     #[serde(rename = "text")]
     Text { content: String },
+    /// Reasoning chunk, separate from the answer.
+    #[serde(rename = "reasoning")]
+    Reasoning { content: String },
+    /// A tool call is starting.
+    #[serde(rename = "tool_start")]
+    ToolStart { name: String },
+    /// A tool call finished.
+    #[serde(rename = "tool_call")]
+    ToolCall {
+        name: String,
+        invocation: String,
+        output: String,
+        is_error: bool,
+    },
+    /// Token usage and cost for a response.
+    #[serde(rename = "usage")]
+    Usage {
+        input_tokens: u32,
+        output_tokens: u32,
+        cost_usd: f64,
+    },
+    /// Plan-mode phase changed (`null` when leaving plan mode).
+    #[serde(rename = "plan_phase")]
+    PlanPhase { phase: Option<String> },
+    /// The provider stream dropped and is being retried.
+    #[serde(rename = "reconnecting")]
+    Reconnecting { attempt: u32, max_attempts: u32 },
+    /// Older messages were replaced with a summary.
+    #[serde(rename = "compacted")]
+    Compacted { messages: usize },
     /// Stream completed.
     #[serde(rename = "done")]
     Done,
@@ -242,20 +560,74 @@ pub enum StreamEvent {
     Error { message: String },
 }
 
+impl From<ChatEvent> for StreamEvent {
+    fn from(event: ChatEvent) -> Self {
+        match event {
+            ChatEvent::Text(content) => Self::Text { content },
+            ChatEvent::Reasoning(content) => Self::Reasoning { content },
+            ChatEvent::ToolStart { name } => Self::ToolStart { name },
+            ChatEvent::ToolCall {
+                name,
+                invocation,
+                output,
+                is_error,
+            } => Self::ToolCall {
+                name,
+                invocation,
+                output,
+                is_error,
+            },
+            ChatEvent::Usage {
+                input_tokens,
+                output_tokens,
+                cost_usd,
+            } => Self::Usage {
+                input_tokens,
+                output_tokens,
+                cost_usd,
+            },
+            ChatEvent::PlanPhase(phase) => Self::PlanPhase {
+                phase: phase.map(|phase| phase.label().to_string()),
+            },
+            ChatEvent::Reconnecting {
+                attempt,
+                max_attempts,
+            } => Self::Reconnecting {
+                attempt,
+                max_attempts,
+            },
+            ChatEvent::Compacted { messages } => Self::Compacted { messages },
+        }
+    }
+}
+
 /// Execute an agentic task.
+///
+/// Returns JSON by default. With `?stream=chunked` or `Accept: text/plain`,
+/// the output is streamed as a chunked plain-text body instead.
 #[utoipa::path(
     post,
     path = "/api/agent",
+    params(AgentQuery),
     request_body = AgentRequest,
     responses(
-        (status = 200, description = "Task executed", body = AgentResponse),
+        (status = 200, description = "Task executed (JSON) or streamed output (chunked text)", content(
+            (AgentResponse = "application/json"),
+            (String = "text/plain")
+        )),
         (status = 503, description = "No API key configured")
     )
 )]
 async fn execute_agent(
     State(state): State<SharedState>,
+    Query(query): Query<AgentQuery>,
+    headers: HeaderMap,
     Json(req): Json<AgentRequest>,
-) -> Result<Json<AgentResponse>, (StatusCode, String)> {
+) -> Result<Response, (StatusCode, String)> {
+    if query.wants_chunked(&headers) {
+        return execute_agent_chunked(state, req).await;
+    }
+
     let mut state = state.write().await;
 
     let Some(ref mut agent) = state.agent else {
@@ -284,7 +656,8 @@ async fn execute_agent(
             Ok(Json(AgentResponse {
                 success: true,
                 output,
-            }))
+            })
+            .into_response())
         }
         Err(e) => {
             let error_output = e.to_string();
@@ -297,11 +670,64 @@ async fn execute_agent(
             Ok(Json(AgentResponse {
                 success: false,
                 output: error_output,
-            }))
+            })
+            .into_response())
         }
     }
 }
 
+/// Stream agent output as a chunked plain-text body.
+///
+/// Errors are reported as a final `Error: ...` line since the status has
+/// already been sent.
+async fn execute_agent_chunked(
+    state: SharedState,
+    req: AgentRequest,
+) -> Result<Response, (StatusCode, String)> {
+    let Some(agent) = state.write().await.agent.take() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No API key configured".to_string(),
+        ));
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+
+    tokio::spawn(async move {
+        let mut agent = agent;
+        let mut output = String::new();
+
+        let result = agent
+            .chat(&req.prompt, |text| {
+                output.push_str(text);
+                let _ = tx.send(text.to_string());
+            })
+            .await;
+
+        let task_result = match result {
+            Ok(_) => TaskResult {
+                success: true,
+                output,
+            },
+            Err(e) => {
+                let _ = tx.send(format!("\nError: {e}\n"));
+                TaskResult {
+                    success: false,
+                    output: e.to_string(),
+                }
+            }
+        };
+
+        // Return agent to state
+        let mut state_guard = state.write().await;
+        state_guard.agent = Some(agent);
+        state_guard.history.push(task_result);
+    });
+
+    let body = Body::from_stream(UnboundedReceiverStream::new(rx).map(Ok::<_, Infallible>));
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response())
+}
+
 /// Get task execution history.
 #[utoipa::path(
     get,
@@ -313,7 +739,78 @@ async fn get_history(State(state): State<SharedState>) -> Json<Vec<TaskResult>>
     Json(state.history.clone())
 }
 
+/// Query parameters for session listing.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct SessionsQuery {
+    /// Maximum sessions returned, newest first.
+    #[serde(default = "SessionsQuery::default_limit")]
+    pub limit: usize,
+}
+
+impl SessionsQuery {
+    /// Same default as `omni session list`.
+    const fn default_limit() -> usize {
+        20
+    }
+}
+
+/// List sessions for the server's project, most recently updated first.
+#[utoipa::path(
+    get,
+    path = "/api/sessions",
+    params(SessionsQuery),
+    responses((status = 200, description = "Sessions", body = Vec<Session>))
+)]
+async fn list_sessions(
+    Query(query): Query<SessionsQuery>,
+) -> Result<Json<Vec<Session>>, (StatusCode, String)> {
+    let manager = SessionManager::for_current_project()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut sessions = manager
+        .list_sessions()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    sessions.truncate(query.limit);
+
+    Ok(Json(sessions))
+}
+
+/// Get the messages of a session.
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{id}/messages",
+    params(("id" = String, Path, description = "Session ID or slug")),
+    responses(
+        (status = 200, description = "Messages with their parts", body = Vec<ExportedMessage>),
+        (status = 404, description = "Session not found")
+    )
+)]
+async fn get_session_messages(
+    Path(id): Path<String>,
+) -> Result<Json<Vec<ExportedMessage>>, (StatusCode, String)> {
+    let manager = SessionManager::for_current_project()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let session = manager
+        .find_session(&id)
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    let exported = manager
+        .export_session(&session.id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(exported.messages))
+}
+
 /// Execute an agentic task with SSE streaming.
+#[utoipa::path(
+    post,
+    path = "/api/agent/stream",
+    request_body = AgentRequest,
+    responses(
+        (status = 200, description = "Server-sent events, one JSON event per message", body = StreamEvent, content_type = "text/event-stream"),
+        (status = 503, description = "No API key configured")
+    )
+)]
 async fn execute_agent_stream(
     State(state): State<SharedState>,
     Json(req): Json<AgentRequest>,
@@ -336,11 +833,8 @@ async fn execute_agent_stream(
         let mut agent = agent;
 
         let result = agent
-            .chat(&req.prompt, |text| {
-                let event = StreamEvent::Text {
-                    content: text.to_string(),
-                };
-                let _ = tx.send(event);
+            .chat_with_events(&req.prompt, |event| {
+                let _ = tx.send(StreamEvent::from(event));
             })
             .await;
 
@@ -369,6 +863,15 @@ async fn execute_agent_stream(
 }
 
 /// Create a share token for a session.
+#[utoipa::path(
+    post,
+    path = "/api/share",
+    request_body = CreateShareRequest,
+    responses(
+        (status = 200, description = "Share created", body = CreateShareResponse),
+        (status = 400, description = "Session not found or share failed")
+    )
+)]
 async fn create_share(
     headers: HeaderMap,
     Json(req): Json<CreateShareRequest>,
@@ -378,6 +881,7 @@ async fn create_share(
 
     let options = ShareOptions {
         ttl_seconds: req.ttl_seconds,
+        password: req.password,
     };
 
     let share = manager
@@ -403,20 +907,75 @@ async fn create_share(
 }
 
 /// Get a shared session (public, no auth required).
+#[utoipa::path(
+    get,
+    path = "/api/share/{token}",
+    params(
+        ("token" = String, Path, description = "Share token"),
+        ("X-Share-Password" = Option<String>, Header, description = "Password for a protected share")
+    ),
+    responses(
+        (status = 200, description = "Shared session", body = ExportedSession),
+        (status = 401, description = "Password missing or incorrect"),
+        (status = 404, description = "Share not found"),
+        (status = 410, description = "Share expired"),
+        (status = 429, description = "Too many wrong passwords; try again later")
+    )
+)]
 async fn get_shared_session(
+    State(state): State<SharedState>,
     Path(token): Path<String>,
+    headers: HeaderMap,
 ) -> Result<Json<ExportedSession>, (StatusCode, String)> {
+    let attempts = Arc::clone(&state.read().await.share_attempts);
+    if attempts.lock().is_locked(&token) {
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            "too many wrong passwords for this share; try again later".to_string(),
+        ));
+    }
+
     let manager = SessionManager::for_current_project()
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let exported = manager
-        .get_shared_session(&token)
-        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    // Only the header is read, so passwords stay out of URL logs
+    let password = headers
+        .get(SHARE_PASSWORD_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    // Password checks are deliberately slow, so keep them off the runtime
+    let share_token = token.clone();
+    let exported = tokio::task::spawn_blocking(move || {
+        manager.get_shared_session(&share_token, password.as_deref())
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| {
+        let status = if e.downcast_ref::<ShareExpiredError>().is_some() {
+            StatusCode::GONE
+        } else if e.downcast_ref::<SharePasswordError>().is_some() {
+            attempts.lock().record_failure(&token);
+            StatusCode::UNAUTHORIZED
+        } else {
+            StatusCode::NOT_FOUND
+        };
+        (status, e.to_string())
+    })?;
 
     Ok(Json(exported))
 }
 
 /// Delete a share token.
+#[utoipa::path(
+    delete,
+    path = "/api/share/{token}",
+    params(("token" = String, Path, description = "Share token")),
+    request_body = DeleteShareRequest,
+    responses(
+        (status = 204, description = "Share revoked"),
+        (status = 400, description = "Invalid secret or unknown share")
+    )
+)]
 async fn delete_share(
     Path(token): Path<String>,
     Json(req): Json<DeleteShareRequest>,
@@ -443,8 +1002,10 @@ mod tests {
     fn create_test_state(token: Option<String>) -> SharedState {
         Arc::new(RwLock::new(AppState {
             agent: None,
+            model: None,
             history: Vec::new(),
             token,
+            share_attempts: Arc::default(),
         }))
     }
 
@@ -457,7 +1018,9 @@ mod tests {
                 auth_middleware,
             ));
 
-        let public_routes = Router::new().route("/health", get(health));
+        let public_routes = Router::new()
+            .route("/health", get(health))
+            .route("/ready", get(ready));
 
         Router::new()
             .merge(protected_routes)
@@ -483,6 +1046,52 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn ready_reports_unavailable_without_a_provider() {
+        let state = create_test_state(Some("secret".to_string()));
+        let app = create_test_router(state);
+
+        // Public even when auth is enabled
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/ready")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let ready: ReadyResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(ready.status, "not_ready");
+        assert!(!ready.provider_configured);
+        assert!(ready.model.is_none());
+    }
+
+    #[tokio::test]
+    async fn ready_stays_ready_while_a_turn_holds_the_agent() {
+        let state = create_test_state(None);
+        // A running turn has taken the agent
+        state.write().await.model = Some("claude-sonnet-4".to_string());
+        let app = create_test_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/ready")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn auth_middleware_allows_request_when_no_token_configured() {
         let state = create_test_state(None);
@@ -519,6 +1128,38 @@ mod tests {
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
+    #[tokio::test]
+    async fn shares_lock_after_repeated_wrong_passwords() {
+        let state = create_test_state(None);
+        let attempts = Arc::clone(&state.read().await.share_attempts);
+        {
+            let mut attempts = attempts.lock();
+            for _ in 1..MAX_SHARE_ATTEMPTS {
+                attempts.record_failure("abc");
+            }
+            assert!(!attempts.is_locked("abc"));
+            attempts.record_failure("abc");
+            assert!(attempts.is_locked("abc"));
+            assert!(!attempts.is_locked("other"));
+        }
+
+        // A locked share is refused before its password is checked
+        let app = Router::new()
+            .route("/api/share/{token}", get(get_shared_session))
+            .with_state(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/share/abc")
+                    .header(SHARE_PASSWORD_HEADER, "guess")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
     #[tokio::test]
     async fn auth_middleware_rejects_invalid_token() {
         let state = create_test_state(Some("secret-token".to_string()));
@@ -609,6 +1250,38 @@ mod tests {
         assert!(json.contains(r#""type":"text""#));
         assert!(json.contains(r#""content":"hello""#));
 
+        let tool_event = StreamEvent::from(ChatEvent::ToolCall {
+            name: "Bash".to_string(),
+            invocation: "ls".to_string(),
+            output: "Cargo.toml".to_string(),
+            is_error: false,
+        });
+        let json = serde_json::to_string(&tool_event).unwrap();
+        assert!(json.contains(r#""type":"tool_call""#));
+        assert!(json.contains(r#""invocation":"ls""#));
+        assert!(json.contains(r#""is_error":false"#));
+
+        let start_event = StreamEvent::from(ChatEvent::ToolStart {
+            name: "Bash".to_string(),
+        });
+        let json = serde_json::to_string(&start_event).unwrap();
+        assert!(json.contains(r#""type":"tool_start""#));
+
+        let reasoning_event = StreamEvent::from(ChatEvent::Reasoning("hmm".to_string()));
+        let json = serde_json::to_string(&reasoning_event).unwrap();
+        assert!(json.contains(r#""type":"reasoning""#));
+        assert!(json.contains(r#""content":"hmm""#));
+
+        let usage_event = StreamEvent::from(ChatEvent::Usage {
+            input_tokens: 10,
+            output_tokens: 5,
+            cost_usd: 0.5,
+        });
+        let json = serde_json::to_string(&usage_event).unwrap();
+        assert!(json.contains(r#""type":"usage""#));
+        assert!(json.contains(r#""input_tokens":10"#));
+        assert!(json.contains(r#""cost_usd":0.5"#));
+
         let done_event = StreamEvent::Done;
         let json = serde_json::to_string(&done_event).unwrap();
         assert!(json.contains(r#""type":"done""#));
@@ -621,6 +1294,175 @@ mod tests {
         assert!(json.contains(r#""message":"oops""#));
     }
 
+    #[test]
+    fn chunked_output_selected_by_query_or_accept() {
+        let mut headers = HeaderMap::new();
+        let chunked = AgentQuery {
+            stream: Some("chunked".to_string()),
+        };
+        assert!(chunked.wants_chunked(&headers));
+        assert!(!AgentQuery::default().wants_chunked(&headers));
+
+        headers.insert(header::ACCEPT, "text/plain".parse().unwrap());
+        assert!(AgentQuery::default().wants_chunked(&headers));
+
+        headers.insert(
+            header::ACCEPT,
+            "application/json, text/plain".parse().unwrap(),
+        );
+        assert!(!AgentQuery::default().wants_chunked(&headers));
+    }
+
+    #[tokio::test]
+    async fn chunked_agent_request_returns_503_when_no_agent() {
+        let state = create_test_state(None);
+        let app = create_test_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/agent?stream=chunked")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(r#"{"prompt": "test"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn full_router_builds_and_serves_ready() {
+        // Building the router panics on invalid route syntax
+        let app = router(create_test_state(None));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/ready")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn session_routes_require_auth() {
+        let app = router(create_test_state(Some("secret".to_string())));
+
+        for uri in ["/api/sessions?limit=5", "/api/sessions/abc/messages"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn cors_answers_preflight_for_allowed_origins() {
+        let config = CorsConfig {
+            allowed_origins: vec!["http://localhost:5173".to_string()],
+            allow_credentials: true,
+            ..CorsConfig::default()
+        };
+        let cors = cors_layer(&config).unwrap().unwrap();
+        let app = router(create_test_state(Some("secret".to_string()))).layer(cors);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("OPTIONS")
+                    .uri("/api/history")
+                    .header("origin", "http://localhost:5173")
+                    .header("access-control-request-method", "GET")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "http://localhost:5173"
+        );
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+    }
+
+    #[test]
+    fn cors_is_off_by_default_and_rejects_wildcard_credentials() {
+        assert!(cors_layer(&CorsConfig::default()).unwrap().is_none());
+
+        let config = CorsConfig {
+            allowed_origins: vec!["*".to_string()],
+            ..CorsConfig::default()
+        };
+        assert!(cors_layer(&config).unwrap().is_some());
+
+        let config = CorsConfig {
+            allow_credentials: true,
+            ..config
+        };
+        assert!(cors_layer(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn server_stops_after_shutdown_signal() {
+        let state = create_test_state(None);
+        let app = create_test_router(Arc::clone(&state));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve_until(listener, app, state, async {
+            let _ = stop_rx.await;
+        }));
+
+        stop_tx.send(()).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("server should stop promptly")
+            .unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn sessions_query_defaults_to_cli_limit() {
+        let query: SessionsQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query.limit, 20);
+    }
+
+    #[test]
+    fn openapi_documents_every_route() {
+        let spec = ApiDoc::openapi();
+        for path in [
+            "/health",
+            "/ready",
+            "/api/agent",
+            "/api/agent/stream",
+            "/api/history",
+            "/api/sessions",
+            "/api/sessions/{id}/messages",
+            "/api/share",
+            "/api/share/{token}",
+        ] {
+            assert!(spec.paths.paths.contains_key(path), "missing {path}");
+        }
+
+        let share = &spec.paths.paths["/api/share/{token}"];
+        assert!(share.get.is_some() && share.delete.is_some());
+
+        let schemas = &spec.components.as_ref().unwrap().schemas;
+        for name in ["ExportedSession", "Session", "Part", "CreateShareResponse"] {
+            assert!(schemas.contains_key(name), "missing schema {name}");
+        }
+    }
+
     #[test]
     fn agent_request_deserializes_correctly() {
         let json = r#"{"prompt": "do something"}"#;
@@ -0,0 +1,451 @@
+//! WebSocket endpoint for interactive agent turns.
+//!
+//! Unlike `/api/agent/stream`, the socket carries frames both ways, so
+//! clients can answer permission prompts and `ask_user` questions while a
+//! turn runs. Every frame is a JSON text message tagged by `type`.
+//!
+//! Client to server ([`ClientFrame`]):
+//!
+//! ```json
+//! {"type": "prompt", "prompt": "Fix the failing test"}
+//! {"type": "permission_response", "request_id": "<uuid>", "response": "allow"}
+//! {"type": "ask_user_response", "request_id": "<uuid>", "answer": "yes"}
+//! ```
+//!
+//! `response` is `allow`, `allow_for_session`, `allow_always` or `deny`.
+//! Omitting `answer` cancels the question.
+//!
+//! Server to client ([`ServerFrame`]): agent events (`text`, `reasoning`,
+//! `tool_start`, `tool_call`, `usage`, `plan_phase`, `reconnecting`,
+//! `compacted`), prompts (`permission_request`, `ask_user`), and one `done`
+//! or `error` frame per turn.
+//!
+//! Each socket gets its own agent, so its conversation carries across its
+//! turns without tying up the agent other endpoints use. Closing the socket
+//! denies any open prompts and cancels the running turn.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        State,
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+    },
+    response::Response,
+};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use super::{SharedState, StreamEvent};
+use crate::config::Config;
+use crate::core::agent::{
+    AskUserResponse, ChatEvent, InterfaceMessage, PermissionAction, PermissionActor,
+    PermissionClient, PermissionContext, PermissionMessage, PermissionResponse,
+};
+use crate::core::approvals::ApprovalManager;
+use crate::core::{Agent, TaskResult};
+
+/// Session ID for permissions granted over a socket.
+const SESSION_ID: &str = "api-ws";
+
+/// Frame sent by the client.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientFrame {
+    /// Start a turn. Only one turn runs at a time.
+    Prompt { prompt: String },
+    /// Answer a `permission_request`.
+    PermissionResponse {
+        request_id: Uuid,
+        response: PermissionResponse,
+    },
+    /// Answer an `ask_user` question, or cancel it without `answer`.
+    AskUserResponse {
+        request_id: Uuid,
+        answer: Option<String>,
+    },
+}
+
+/// Frame sent by the server.
+///
+/// Agent events use the `/api/agent/stream` schema, so both endpoints stay
+/// in step; only the prompts and `done` are socket-specific.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerFrame {
+    /// A tool needs permission; answer with `permission_response`.
+    PermissionRequest {
+        request_id: Uuid,
+        tool_name: String,
+        action: PermissionAction,
+        context: PermissionContext,
+    },
+    /// The agent asked a question; answer with `ask_user_response`.
+    AskUser {
+        request_id: Uuid,
+        question: String,
+        options: Option<Vec<String>>,
+    },
+    /// The turn completed.
+    Done { output: String },
+    /// An agent event or error, tagged as in the SSE stream.
+    #[serde(untagged)]
+    Event(StreamEvent),
+}
+
+impl ServerFrame {
+    /// Error frame for a failed turn or a rejected frame.
+    fn error(message: impl Into<String>) -> Self {
+        Self::Event(StreamEvent::Error {
+            message: message.into(),
+        })
+    }
+}
+
+impl From<ChatEvent> for ServerFrame {
+    fn from(event: ChatEvent) -> Self {
+        Self::Event(event.into())
+    }
+}
+
+/// Prompts awaiting an answer, with what's needed to cache `allow_for_session`.
+type PendingPrompts = HashMap<Uuid, (String, PermissionAction)>;
+
+/// Convert a dialog request from the permission actor into a frame.
+fn prompt_frame(message: InterfaceMessage, pending: &mut PendingPrompts) -> Option<ServerFrame> {
+    match message {
+        InterfaceMessage::ShowPermissionDialog {
+            request_id,
+            tool_name,
+            action,
+            context,
+        } => {
+            pending.insert(request_id, (tool_name.clone(), action.clone()));
+            Some(ServerFrame::PermissionRequest {
+                request_id,
+                tool_name,
+                action,
+                context,
+            })
+        }
+        InterfaceMessage::ShowAskUserDialog {
+            request_id,
+            question,
+            options,
+        } => Some(ServerFrame::AskUser {
+            request_id,
+            question,
+            options,
+        }),
+        InterfaceMessage::HideDialog => None,
+    }
+}
+
+/// Interactive agent session over a WebSocket.
+///
+/// See the module docs for the frame schema.
+pub(super) async fn agent_socket(
+    State(state): State<SharedState>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| run_socket(socket, state))
+}
+
+async fn run_socket(socket: WebSocket, state: SharedState) {
+    let (mut sink, mut incoming) = socket.split();
+
+    // The socket stands in for the TUI's dialogs
+    let (mut actor, permission_tx) = PermissionActor::new();
+    let (interface_tx, mut interface_rx) = mpsc::unbounded_channel();
+    actor.handle_message(PermissionMessage::RegisterInterface { interface_tx });
+
+    let (frame_tx, mut frame_rx) = mpsc::unbounded_channel::<ServerFrame>();
+    let mut pending = PendingPrompts::new();
+    let mut agent: Option<Agent> = None;
+    let mut turn: Option<JoinHandle<Agent>> = None;
+    let cancel = CancellationToken::new();
+
+    loop {
+        tokio::select! {
+            Some(message) = actor.inbox.recv() => actor.handle_message(message),
+
+            Some(message) = interface_rx.recv() => {
+                if let Some(frame) = prompt_frame(message, &mut pending) {
+                    let _ = frame_tx.send(frame);
+                }
+            }
+
+            Some(frame) = frame_rx.recv() => {
+                let text = serde_json::to_string(&frame).unwrap_or_default();
+                if sink.send(WsMessage::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+
+            message = incoming.next() => {
+                let text = match message {
+                    Some(Ok(WsMessage::Text(text))) => text,
+                    Some(Ok(WsMessage::Close(_)) | Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+
+                match serde_json::from_str::<ClientFrame>(&text) {
+                    Ok(ClientFrame::Prompt { prompt }) => {
+                        if turn.as_ref().is_some_and(|turn| !turn.is_finished()) {
+                            let _ = frame_tx.send(ServerFrame::error("a turn is already running"));
+                        } else {
+                            // A panicked turn loses its agent, so the next one starts fresh
+                            if let Some(finished) = turn.take() {
+                                agent = finished.await.ok();
+                            }
+                            let next = match agent.take() {
+                                Some(agent) => Ok(agent),
+                                None => connection_agent(&state).await,
+                            };
+                            match next {
+                                Ok(next) => {
+                                    turn = Some(start_turn(next, &state, prompt, &permission_tx, &frame_tx, &cancel));
+                                }
+                                Err(e) => {
+                                    let _ = frame_tx.send(ServerFrame::error(e.to_string()));
+                                }
+                            }
+                        }
+                    }
+                    Ok(ClientFrame::PermissionResponse { request_id, response }) => {
+                        if let Some((tool_name, action)) = pending.remove(&request_id) {
+                            actor.respond(request_id, response, SESSION_ID, &tool_name, &action);
+                        }
+                    }
+                    Ok(ClientFrame::AskUserResponse { request_id, answer }) => {
+                        let response = answer.map_or(AskUserResponse::Cancelled, AskUserResponse::Answer);
+                        actor.respond_ask_user(request_id, response);
+                    }
+                    Err(e) => {
+                        let _ = frame_tx.send(ServerFrame::error(format!("invalid frame: {e}")));
+                    }
+                }
+            }
+        }
+    }
+
+    // Nobody is left to answer, so deny open prompts and stop the turn
+    actor.handle_message(PermissionMessage::UnregisterInterface);
+    cancel.cancel();
+}
+
+/// Create the agent for a socket's turns.
+async fn connection_agent(state: &SharedState) -> anyhow::Result<Agent> {
+    if state.read().await.model.is_none() {
+        anyhow::bail!("No API key configured");
+    }
+    Agent::from_config(&Config::load()?)
+}
+
+/// Run a turn in the background, streaming its events as frames.
+///
+/// The handle hands the agent back once the turn ends.
+fn start_turn(
+    mut agent: Agent,
+    state: &SharedState,
+    prompt: String,
+    permission_tx: &mpsc::UnboundedSender<PermissionMessage>,
+    frame_tx: &mpsc::UnboundedSender<ServerFrame>,
+    cancel: &CancellationToken,
+) -> JoinHandle<Agent> {
+    let mut client = PermissionClient::new(SESSION_ID.to_string(), permission_tx.clone());
+    if let Ok(approvals) = ApprovalManager::for_current_project() {
+        client = client.with_approval_store(Arc::new(approvals));
+    }
+    agent.set_permission_client(client);
+    agent.set_cancellation_token(cancel.child_token());
+
+    let state = Arc::clone(state);
+    let frame_tx = frame_tx.clone();
+    tokio::spawn(async move {
+        let events = frame_tx.clone();
+        let result = agent
+            .chat_with_events(&prompt, |event| {
+                let _ = events.send(event.into());
+            })
+            .await;
+
+        let (frame, task_result) = match result {
+            Ok(output) => (
+                ServerFrame::Done {
+                    output: output.clone(),
+                },
+                TaskResult {
+                    success: true,
+                    output,
+                },
+            ),
+            Err(e) => (
+                ServerFrame::error(e.to_string()),
+                TaskResult {
+                    success: false,
+                    output: e.to_string(),
+                },
+            ),
+        };
+
+        state.write().await.history.push(task_result);
+        let _ = frame_tx.send(frame);
+        agent
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn client_frames_parse() {
+        let id = Uuid::new_v4();
+        let frame: ClientFrame = serde_json::from_value(serde_json::json!({
+            "type": "permission_response",
+            "request_id": id,
+            "response": "allow_for_session",
+        }))
+        .unwrap();
+        assert!(matches!(
+            frame,
+            ClientFrame::PermissionResponse {
+                request_id,
+                response: PermissionResponse::AllowForSession,
+            } if request_id == id
+        ));
+
+        let frame: ClientFrame = serde_json::from_value(serde_json::json!({
+            "type": "ask_user_response",
+            "request_id": id,
+        }))
+        .unwrap();
+        assert!(matches!(
+            frame,
+            ClientFrame::AskUserResponse { answer: None, .. }
+        ));
+    }
+
+    #[test]
+    fn permission_prompts_become_frames() {
+        let mut pending = PendingPrompts::new();
+        let request_id = Uuid::new_v4();
+        let frame = prompt_frame(
+            InterfaceMessage::ShowPermissionDialog {
+                request_id,
+                tool_name: "shell".to_string(),
+                action: PermissionAction::Execute,
+                context: PermissionContext::Bash {
+                    command: "cargo test".to_string(),
+                    working_dir: PathBuf::from("/repo"),
+                },
+            },
+            &mut pending,
+        )
+        .unwrap();
+
+        let json = serde_json::to_value(&frame).unwrap();
+        assert_eq!(json["type"], "permission_request");
+        assert_eq!(json["request_id"], request_id.to_string());
+        assert_eq!(json["action"]["kind"], "execute");
+        assert_eq!(json["context"]["kind"], "bash");
+        assert_eq!(json["context"]["command"], "cargo test");
+        assert_eq!(
+            pending.get(&request_id),
+            Some(&("shell".to_string(), PermissionAction::Execute))
+        );
+
+        assert!(prompt_frame(InterfaceMessage::HideDialog, &mut pending).is_none());
+    }
+
+    #[test]
+    fn chat_events_become_frames() {
+        let json =
+            serde_json::to_value(ServerFrame::from(ChatEvent::Text("hi".to_string()))).unwrap();
+        assert_eq!(json, serde_json::json!({"type": "text", "content": "hi"}));
+
+        let json = serde_json::to_value(ServerFrame::from(ChatEvent::PlanPhase(None))).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "plan_phase", "phase": null})
+        );
+
+        let json = serde_json::to_value(ServerFrame::error("boom")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "error", "message": "boom"})
+        );
+        let json = serde_json::to_value(ServerFrame::Done {
+            output: "ok".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json, serde_json::json!({"type": "done", "output": "ok"}));
+    }
+
+    async fn next_frame<S>(socket: &mut S) -> serde_json::Value
+    where
+        S: futures::Stream<
+                Item = Result<
+                    tokio_tungstenite::tungstenite::Message,
+                    tokio_tungstenite::tungstenite::Error,
+                >,
+            > + Unpin,
+    {
+        loop {
+            if let Some(Ok(tokio_tungstenite::tungstenite::Message::Text(text))) =
+                socket.next().await
+            {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn socket_reports_errors_as_frames() {
+        use tokio_tungstenite::tungstenite::Message;
+
+        let state: SharedState = Arc::new(tokio::sync::RwLock::new(super::super::AppState {
+            agent: None,
+            model: None,
+            history: Vec::new(),
+            token: None,
+            share_attempts: Arc::default(),
+        }));
+        let app = axum::Router::new()
+            .route("/api/agent/ws", axum::routing::get(agent_socket))
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/api/agent/ws"))
+            .await
+            .unwrap();
+        socket
+            .send(Message::Text(r#"{"type":"bogus"}"#.into()))
+            .await
+            .unwrap();
+        let frame = next_frame(&mut socket).await;
+        assert_eq!(frame["type"], "error");
+        assert!(
+            frame["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("invalid frame")
+        );
+
+        socket
+            .send(Message::Text(r#"{"type":"prompt","prompt":"hi"}"#.into()))
+            .await
+            .unwrap();
+        let frame = next_frame(&mut socket).await;
+        assert_eq!(frame["message"], "No API key configured");
+    }
+}
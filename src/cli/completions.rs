@@ -0,0 +1,390 @@
+//! Shell completion scripts generated from the clap command tree.
+//!
+//! Completion is static: subcommands, flags, and fixed flag values are
+//! offered, but values that come from config or local data (provider and
+//! model names, session IDs) are not.
+//!
+//! This stands in for `clap_complete` until it can be added as a
+//! dependency. [`Shell`] and [`generate`] mirror `clap_complete::Shell` and
+//! `clap_complete::generate`, so switching over only touches the imports.
+
+use std::fmt::Write as _;
+use std::io;
+
+use clap::{Command, ValueEnum};
+
+/// Shells a completion script can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(name = "powershell")]
+    PowerShell,
+}
+
+/// A command in the tree, identified by its subcommand path.
+struct Node {
+    /// Subcommand names from the root, space separated (empty for the root).
+    path: String,
+    subcommands: Vec<Entry>,
+    flags: Vec<Flag>,
+}
+
+/// A subcommand offered at a node.
+struct Entry {
+    name: String,
+    help: String,
+}
+
+/// A flag offered at a node.
+struct Flag {
+    long: Option<String>,
+    short: Option<char>,
+    help: String,
+    /// Fixed values the flag accepts, if any.
+    values: Vec<String>,
+}
+
+impl Flag {
+    /// Spellings of the flag, long form first.
+    fn spellings(&self) -> Vec<String> {
+        self.long
+            .iter()
+            .map(|long| format!("--{long}"))
+            .chain(self.short.map(|short| format!("-{short}")))
+            .collect()
+    }
+}
+
+/// Write the completion script for `shell` to `out`, completing `bin_name`.
+///
+/// # Errors
+///
+/// Returns an error if writing to `out` fails.
+pub fn generate(
+    shell: Shell,
+    cmd: &mut Command,
+    bin_name: impl Into<String>,
+    out: &mut dyn io::Write,
+) -> io::Result<()> {
+    cmd.build();
+    let bin = bin_name.into();
+    let mut nodes = Vec::new();
+    collect(cmd, String::new(), &mut nodes);
+
+    let script = match shell {
+        Shell::Bash => bash(&bin, &nodes),
+        Shell::Zsh => zsh(&bin, &nodes),
+        Shell::Fish => fish(&bin, &nodes),
+        Shell::PowerShell => powershell(&bin, &nodes),
+    };
+    out.write_all(script.as_bytes())
+}
+
+/// Flatten the visible command tree into nodes, parents first.
+fn collect(cmd: &Command, path: String, nodes: &mut Vec<Node>) {
+    let subcommands: Vec<&Command> = cmd.get_subcommands().filter(|c| !c.is_hide_set()).collect();
+    let flags = cmd
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && !arg.is_positional())
+        .map(|arg| Flag {
+            long: arg.get_long().map(str::to_string),
+            short: arg.get_short(),
+            help: arg.get_help().map(ToString::to_string).unwrap_or_default(),
+            values: arg
+                .get_possible_values()
+                .iter()
+                .filter(|value| !value.is_hide_set())
+                .map(|value| value.get_name().to_string())
+                .collect(),
+        })
+        .collect();
+
+    nodes.push(Node {
+        path: path.clone(),
+        subcommands: subcommands
+            .iter()
+            .map(|sub| Entry {
+                name: sub.get_name().to_string(),
+                help: sub.get_about().map(ToString::to_string).unwrap_or_default(),
+            })
+            .collect(),
+        flags,
+    });
+
+    // `help <command>` mirrors the tree; offer it without its subtree
+    for sub in subcommands
+        .into_iter()
+        .filter(|sub| sub.get_name() != "help")
+    {
+        let child = if path.is_empty() {
+            sub.get_name().to_string()
+        } else {
+            format!("{path} {}", sub.get_name())
+        };
+        collect(sub, child, nodes);
+    }
+}
+
+/// Words offered at a node: subcommands, then flag spellings.
+fn words(node: &Node) -> Vec<String> {
+    node.subcommands
+        .iter()
+        .map(|sub| sub.name.clone())
+        .chain(node.flags.iter().flat_map(Flag::spellings))
+        .collect()
+}
+
+/// `parent:child` keys for every subcommand step in the tree.
+fn transitions(nodes: &[Node]) -> Vec<String> {
+    nodes
+        .iter()
+        .flat_map(|node| {
+            node.subcommands
+                .iter()
+                .map(move |sub| format!("{}:{}", node.path, sub.name))
+        })
+        .collect()
+}
+
+/// `path:flag` keys and the fixed values each flag accepts.
+fn flag_values(nodes: &[Node]) -> Vec<(String, &[String])> {
+    nodes
+        .iter()
+        .flat_map(|node| {
+            node.flags
+                .iter()
+                .filter(|flag| !flag.values.is_empty())
+                .flat_map(move |flag| {
+                    flag.spellings().into_iter().map(move |spelling| {
+                        (format!("{}:{spelling}", node.path), &flag.values[..])
+                    })
+                })
+        })
+        .collect()
+}
+
+fn bash(bin: &str, nodes: &[Node]) -> String {
+    let func = format!("_{}", bin.replace('-', "_"));
+    let mut script = format!(
+        "{func}() {{\n    local cur prev word i cmd_path opts\n    \
+         cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    \
+         cmd_path=\"\"\n    for ((i = 1; i < COMP_CWORD; i++)); do\n        \
+         word=\"${{COMP_WORDS[i]}}\"\n        case \"$cmd_path:$word\" in\n"
+    );
+    let _ = writeln!(
+        script,
+        "            {}) cmd_path=\"${{cmd_path:+$cmd_path }}$word\" ;;",
+        quoted_alternatives(&transitions(nodes))
+    );
+    script.push_str("        esac\n    done\n\n    case \"$cmd_path:$prev\" in\n");
+    for (key, values) in flag_values(nodes) {
+        let _ = writeln!(
+            script,
+            "        \"{key}\") COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;",
+            values.join(" ")
+        );
+    }
+    script.push_str("    esac\n\n    case \"$cmd_path\" in\n");
+    for node in nodes {
+        let _ = writeln!(
+            script,
+            "        \"{}\") opts=\"{}\" ;;",
+            node.path,
+            words(node).join(" ")
+        );
+    }
+    let _ = write!(
+        script,
+        "    esac\n    COMPREPLY=($(compgen -W \"$opts\" -- \"$cur\"))\n}}\n\n\
+         complete -F {func} -o default {bin}\n"
+    );
+    script
+}
+
+fn zsh(bin: &str, nodes: &[Node]) -> String {
+    let func = format!("_{}", bin.replace('-', "_"));
+    let mut script = format!(
+        "#compdef {bin}\n\n{func}() {{\n    local cmd_path=\"\" word prev i\n    local -a opts\n    \
+         for ((i = 2; i < CURRENT; i++)); do\n        word=\"${{words[i]}}\"\n        \
+         case \"$cmd_path:$word\" in\n"
+    );
+    let _ = writeln!(
+        script,
+        "            {}) cmd_path=\"${{cmd_path:+$cmd_path }}$word\" ;;",
+        quoted_alternatives(&transitions(nodes))
+    );
+    script.push_str("        esac\n    done\n\n    prev=\"${words[CURRENT-1]}\"\n    case \"$cmd_path:$prev\" in\n");
+    for (key, values) in flag_values(nodes) {
+        let _ = writeln!(
+            script,
+            "        \"{key}\") compadd -- {}; return ;;",
+            values.join(" ")
+        );
+    }
+    script.push_str("    esac\n\n    case \"$cmd_path\" in\n");
+    for node in nodes {
+        let _ = writeln!(
+            script,
+            "        \"{}\") opts=({}) ;;",
+            node.path,
+            words(node).join(" ")
+        );
+    }
+    let _ = write!(
+        script,
+        "    esac\n    compadd -- $opts\n}}\n\n\
+         if [ \"$funcstack[1]\" = \"{func}\" ]; then\n    {func} \"$@\"\nelse\n    \
+         compdef {func} {bin}\nfi\n"
+    );
+    script
+}
+
+fn fish(bin: &str, nodes: &[Node]) -> String {
+    let func = format!("__{}_at", bin.replace('-', "_"));
+    let mut script = format!(
+        "# Succeeds when the command line is at subcommand path $want\n\
+         function {func} -a want\n    set -l cmd_path \"\"\n    \
+         for word in (commandline -opc)[2..-1]\n        switch \"$cmd_path:$word\"\n"
+    );
+    let _ = writeln!(
+        script,
+        "            case {}\n                set cmd_path (string trim -- \"$cmd_path $word\")",
+        transitions(nodes)
+            .iter()
+            .map(|key| fish_quote(key))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let _ = writeln!(
+        script,
+        "        end\n    end\n    test \"$cmd_path\" = \"$want\"\nend\n"
+    );
+
+    for node in nodes {
+        let condition = fish_quote(&format!("{func} {}", fish_quote(&node.path)));
+        for sub in &node.subcommands {
+            let _ = writeln!(
+                script,
+                "complete -c {bin} -n {condition} -f -a {} -d {}",
+                sub.name,
+                fish_quote(&sub.help)
+            );
+        }
+        for flag in &node.flags {
+            let mut line = format!("complete -c {bin} -n {condition}");
+            if let Some(long) = &flag.long {
+                let _ = write!(line, " -l {long}");
+            }
+            if let Some(short) = flag.short {
+                let _ = write!(line, " -s {short}");
+            }
+            if !flag.values.is_empty() {
+                let _ = write!(line, " -r -a {}", fish_quote(&flag.values.join(" ")));
+            }
+            let _ = writeln!(script, "{line} -d {}", fish_quote(&flag.help));
+        }
+    }
+    script
+}
+
+fn powershell(bin: &str, nodes: &[Node]) -> String {
+    let mut script = format!(
+        "Register-ArgumentCompleter -Native -CommandName '{bin}' -ScriptBlock {{\n    \
+         param($wordToComplete, $commandAst, $cursorPosition)\n\n    $transitions = @(\n"
+    );
+    for key in transitions(nodes) {
+        let _ = writeln!(script, "        '{key}'");
+    }
+    script.push_str("    )\n    $values = @{\n");
+    for (key, values) in flag_values(nodes) {
+        let _ = writeln!(script, "        '{key}' = @({})", ps_list(values));
+    }
+    script.push_str("    }\n    $candidates = @{\n");
+    for node in nodes {
+        let _ = writeln!(
+            script,
+            "        '{}' = @({})",
+            node.path,
+            ps_list(&words(node))
+        );
+    }
+    script.push_str(
+        "    }\n\n    \
+         $elements = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object { $_.ToString() })\n    \
+         if ($wordToComplete -ne '') { $elements = @($elements | Select-Object -SkipLast 1) }\n    \
+         $path = ''\n    $prev = ''\n    foreach ($word in $elements) {\n        \
+         if ($transitions -contains \"${path}:$word\") { $path = \"$path $word\".Trim() }\n        \
+         $prev = $word\n    }\n\n    \
+         $options = if ($values.ContainsKey(\"${path}:$prev\")) { $values[\"${path}:$prev\"] } else { $candidates[$path] }\n    \
+         $options | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {\n        \
+         [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)\n    }\n}\n",
+    );
+    script
+}
+
+/// `"a"|"b"|...` for a bash or zsh `case` pattern.
+fn quoted_alternatives(keys: &[String]) -> String {
+    keys.iter()
+        .map(|key| format!("\"{key}\""))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Single-quote `text` for fish.
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// `'a', 'b'` for a PowerShell array.
+fn ps_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| format!("'{}'", item.replace('\'', "''")))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+    use crate::cli::Cli;
+
+    fn script(shell: Shell) -> String {
+        let mut out = Vec::new();
+        generate(shell, &mut Cli::command(), "omni", &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn bash_completes_nested_subcommands_and_flag_values() {
+        let script = script(Shell::Bash);
+        assert!(script.contains("complete -F _omni -o default omni"));
+        assert!(script.contains("\"session:list\""));
+        assert!(
+            script
+                .contains("\"agent:--show-tools\") COMPREPLY=($(compgen -W \"none summary full\"")
+        );
+        assert!(script.contains("\"\") opts=\"agent "));
+    }
+
+    #[test]
+    fn every_shell_lists_subcommands() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let script = script(shell);
+            for name in ["agent", "session", "completions"] {
+                assert!(script.contains(name), "{shell:?} script is missing {name}");
+            }
+        }
+    }
+
+    #[test]
+    fn fish_quotes_descriptions() {
+        assert_eq!(fish_quote("don't"), "'don\\'t'");
+        let script = script(Shell::Fish);
+        assert!(script.contains("complete -c omni -n '__omni_at \\'\\'' -f -a agent"));
+    }
+}
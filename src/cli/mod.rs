@@ -1,9 +1,16 @@
 //! CLI command parsing and execution.
 
-use clap::{Parser, Subcommand};
+pub mod completions;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::core::session::{ExportFilter, ExportRole, ToolFilter};
+
+pub use completions::Shell;
 
 /// Omni CLI - Agentic CLI for the Omni ecosystem.
 #[derive(Parser)]
+#[allow(clippy::struct_excessive_bools)]
 #[command(name = "omni")]
 #[command(about = "Agentic CLI for the Omni ecosystem")]
 #[command(version)]
@@ -23,10 +30,22 @@ pub struct Cli {
     #[arg(short = 'n', long)]
     pub dry_run: bool,
 
+    /// Use the clipboard contents as an agent prompt.
+    #[arg(long, conflicts_with = "prompt")]
+    pub paste: bool,
+
     /// Increase logging verbosity.
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     pub verbose: u8,
 
+    /// Disable colored output (also honors `NO_COLOR`).
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Share one conversation history across all projects.
+    #[arg(long, global = true)]
+    pub global_history: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -46,6 +65,30 @@ pub enum Commands {
         /// Resume a specific session by ID.
         #[arg(short, long, conflicts_with = "continue")]
         session: Option<String>,
+
+        /// Start in plan mode (read-only exploration).
+        #[arg(long)]
+        plan: bool,
+
+        /// Append extra instructions to the system prompt (repeatable).
+        #[arg(long = "append-system", value_name = "TEXT")]
+        append_system: Vec<String>,
+
+        /// How tool activity is printed (none, summary, or full).
+        #[arg(long, default_value = "summary", value_parser = ["none", "summary", "full"])]
+        show_tools: String,
+
+        /// Output format (text streams the response, json prints one object at the end).
+        #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+        output: String,
+
+        /// Print the request that would be sent as JSON, without calling the provider.
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Work in a new git worktree (optionally named) instead of the current checkout.
+        #[arg(long, value_name = "NAME", num_args = 0..=1, require_equals = true)]
+        worktree: Option<Option<String>>,
     },
 
     /// Start the TUI interface.
@@ -57,6 +100,14 @@ pub enum Commands {
         /// Resume a specific session by ID.
         #[arg(short, long, conflicts_with = "continue")]
         session: Option<String>,
+
+        /// Start in plan mode (read-only exploration).
+        #[arg(long)]
+        plan: bool,
+
+        /// Work in a new git worktree (optionally named) instead of the current checkout.
+        #[arg(long, value_name = "NAME", num_args = 0..=1, require_equals = true)]
+        worktree: Option<Option<String>>,
     },
 
     /// Start the HTTP API server.
@@ -81,6 +132,134 @@ pub enum Commands {
         #[command(subcommand)]
         command: SessionCommands,
     },
+
+    /// Scaffold `.omni/config.toml` and an AGENTS.md for this project.
+    Init {
+        /// Overwrite existing files.
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Check the environment and report problems.
+    Doctor,
+
+    /// List configured models by provider.
+    Models {
+        /// Only show this provider.
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Send a minimal request to each provider to check its credentials.
+        #[arg(long)]
+        probe: bool,
+
+        /// Output format (table or json).
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+    },
+
+    /// Browse and restore file snapshots taken before agent edits.
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+
+    /// Inspect and edit facts the agent remembers for this project.
+    Memory {
+        #[command(subcommand)]
+        command: MemoryCommands,
+    },
+
+    /// Manage isolated git worktrees for parallel work.
+    Worktree {
+        #[command(subcommand)]
+        command: WorktreeCommands,
+    },
+
+    /// Manage saved credentials and approvals.
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommands,
+    },
+
+    /// Inspect and clean up local data.
+    Maintenance {
+        #[command(subcommand)]
+        command: MaintenanceCommands,
+    },
+
+    /// Export one or all sessions to files.
+    Export {
+        /// Session ID or slug to export.
+        #[arg(required_unless_present = "all")]
+        session_id: Option<String>,
+
+        /// Export every session in the project.
+        #[arg(long, conflicts_with = "session_id", requires = "dir")]
+        all: bool,
+
+        /// Output format (json, markdown or html).
+        #[arg(short, long, default_value = "json")]
+        format: String,
+
+        /// Output file path (stdout if neither this nor --dir is set).
+        #[arg(short, long, conflicts_with = "dir")]
+        output: Option<String>,
+
+        /// Directory to write one file per session, named by slug.
+        #[arg(long)]
+        dir: Option<String>,
+
+        #[command(flatten)]
+        filter: ExportFilterArgs,
+    },
+
+    /// Print a shell completion script.
+    ///
+    /// Subcommands, flags, and fixed flag values are completed. Provider and
+    /// model names come from config and session IDs from local data, so they
+    /// are not completed; list them with `omni models` and `omni session list`.
+    Completions {
+        /// Shell to generate the script for.
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+/// Filters shared by the export commands.
+#[derive(Args, Debug, Default)]
+pub struct ExportFilterArgs {
+    /// Only export messages from this role.
+    #[arg(long, value_parser = ["user", "assistant"])]
+    pub only: Option<String>,
+
+    /// Leave out tool calls.
+    #[arg(long, conflicts_with = "only_tools")]
+    pub no_tools: bool,
+
+    /// Only export tool calls.
+    #[arg(long)]
+    pub only_tools: bool,
+}
+
+impl ExportFilterArgs {
+    /// Build the export filter these flags describe.
+    #[must_use]
+    pub fn filter(&self) -> ExportFilter {
+        let role = match self.only.as_deref() {
+            Some("user") => Some(ExportRole::User),
+            Some("assistant") => Some(ExportRole::Assistant),
+            _ => None,
+        };
+        let tools = if self.no_tools {
+            ToolFilter::Exclude
+        } else if self.only_tools {
+            ToolFilter::Only
+        } else {
+            ToolFilter::Include
+        };
+        ExportFilter { role, tools }
+    }
 }
 
 #[derive(Subcommand)]
@@ -93,6 +272,197 @@ pub enum ConfigCommands {
 
     /// Generate a new API token for remote access.
     GenerateToken,
+
+    /// Check that providers, models, API keys and URLs in the configuration resolve.
+    Validate,
+
+    /// Manage personas.
+    Persona {
+        #[command(subcommand)]
+        command: PersonaCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PersonaCommands {
+    /// List global and project personas.
+    List {
+        /// Output format (table or json).
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuthCommands {
+    /// Save a provider's API key in the OS keychain.
+    Login {
+        /// Provider name from [agent.providers].
+        provider: String,
+    },
+
+    /// Delete saved API keys from the OS keychain and credentials file.
+    Logout {
+        /// Provider to log out of (prompts to pick one if omitted).
+        provider: Option<String>,
+
+        /// Delete the saved keys of every provider.
+        #[arg(long, conflicts_with = "provider")]
+        all: bool,
+
+        /// Skip the confirmation prompt.
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show where each provider's API key comes from.
+    Status {
+        /// Send a minimal request to check each key is accepted.
+        #[arg(long)]
+        probe: bool,
+
+        /// Output format (table or json).
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+    },
+
+    /// Audit or revoke "always allow" permission answers for this project.
+    Approvals {
+        #[command(subcommand)]
+        command: ApprovalCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ApprovalCommands {
+    /// List saved approvals.
+    List {
+        /// Output format (table or json).
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+    },
+
+    /// Remove every saved approval.
+    Clear,
+}
+
+#[derive(Subcommand)]
+pub enum MaintenanceCommands {
+    /// Show file snapshots for this project.
+    Snapshots {
+        /// Prune snapshots outside the retention policy.
+        #[arg(long)]
+        prune: bool,
+    },
+}
+
+/// Memory categories accepted on the command line.
+const MEMORY_CATEGORIES: [&str; 4] = ["preference", "project_fact", "correction", "general"];
+
+#[derive(Subcommand)]
+pub enum MemoryCommands {
+    /// List memories for this project.
+    List {
+        /// Only memories in this category.
+        #[arg(short, long, value_parser = MEMORY_CATEGORIES)]
+        category: Option<String>,
+
+        /// Output format (table or json).
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+    },
+
+    /// Store a new memory.
+    Add {
+        /// The fact to remember.
+        content: String,
+
+        /// Memory category.
+        #[arg(short, long, default_value = "general", value_parser = MEMORY_CATEGORIES)]
+        category: String,
+
+        /// Always include this memory in the system prompt.
+        #[arg(long)]
+        pin: bool,
+
+        /// Tag for filtering (repeatable).
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+
+    /// Search memories by content or tag.
+    Search {
+        /// Search query.
+        query: String,
+
+        /// Output format (table or json).
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+    },
+
+    /// Delete a memory.
+    Rm {
+        /// Memory ID.
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WorktreeCommands {
+    /// List worktrees for this project.
+    List,
+
+    /// Create a worktree on a new `omni/<name>` branch.
+    Create {
+        /// Worktree name (random if omitted).
+        name: Option<String>,
+    },
+
+    /// Remove a worktree and delete its branch.
+    Remove {
+        /// Worktree name.
+        name: String,
+
+        /// Skip the confirmation prompt.
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Reset a worktree to the default branch, discarding all changes.
+    Reset {
+        /// Worktree name.
+        name: String,
+
+        /// Skip the confirmation prompt.
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    /// List snapshots for this project, newest first.
+    List {
+        /// Limit number of snapshots shown.
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Show changes to the working tree since a snapshot.
+    Diff {
+        /// Snapshot hash or unique prefix (defaults to the newest).
+        hash: Option<String>,
+    },
+
+    /// Restore the working tree to a snapshot.
+    Restore {
+        /// Snapshot hash or unique prefix.
+        hash: String,
+
+        /// Skip the confirmation prompt, as needed without a terminal.
+        #[arg(short, long, visible_alias = "yes", short_alias = 'y')]
+        force: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -100,7 +470,33 @@ pub enum SessionCommands {
     /// List all sessions.
     List {
         /// Output format (table or json).
-        #[arg(short, long, default_value = "table")]
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+
+        /// Limit number of sessions shown.
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+
+        /// Only sessions updated within this window (e.g., 1h, 7d, 2w).
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only sessions updated on or after this date (YYYY-MM-DD or RFC 3339).
+        #[arg(long)]
+        after: Option<String>,
+
+        /// Only sessions updated before this date (YYYY-MM-DD or RFC 3339).
+        #[arg(long)]
+        before: Option<String>,
+    },
+
+    /// Find sessions whose messages mention a phrase.
+    Search {
+        /// Text to look for (case-insensitive).
+        query: String,
+
+        /// Output format (table or json).
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
         format: String,
 
         /// Limit number of sessions shown.
@@ -113,13 +509,28 @@ pub enum SessionCommands {
         /// Session ID to export.
         session_id: String,
 
-        /// Output format (json or markdown).
+        /// Output format (json, markdown or html).
         #[arg(short, long, default_value = "json")]
         format: String,
 
         /// Output file path (stdout if not specified).
         #[arg(short, long)]
         output: Option<String>,
+
+        #[command(flatten)]
+        filter: ExportFilterArgs,
+    },
+
+    /// Regenerate a session title from its first message.
+    Retitle {
+        /// Session ID or slug.
+        session_id: String,
+    },
+
+    /// Import a session from a JSON export.
+    Import {
+        /// Path to the exported JSON file.
+        file: String,
     },
 
     /// Share a session via URL.
@@ -130,6 +541,27 @@ pub enum SessionCommands {
         /// Expiration time (e.g., 1h, 7d, 30d).
         #[arg(short, long)]
         expires: Option<String>,
+
+        /// Require a password to read the share (prompted for).
+        #[arg(short, long)]
+        password: bool,
+    },
+
+    /// List active shares for the current project.
+    Shares {
+        /// Output format (table or json).
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+    },
+
+    /// Copy a session into a new one to explore another direction.
+    Fork {
+        /// Session ID or slug to fork.
+        session_id: String,
+
+        /// Copy messages up to and including this one (defaults to all).
+        #[arg(long, value_name = "MESSAGE_ID")]
+        at: Option<String>,
     },
 
     /// Revoke a shared session.
@@ -141,6 +573,17 @@ pub enum SessionCommands {
         #[arg(short, long)]
         secret: String,
     },
+
+    /// Revert the file changes made during a session's last turn.
+    Undo {
+        /// Session ID or slug (defaults to the most recent session).
+        session_id: Option<String>,
+
+        /// Skip the confirmation prompt, as needed without a terminal, and
+        /// revert even files edited since the turn.
+        #[arg(short, long, visible_alias = "yes", short_alias = 'y')]
+        force: bool,
+    },
 }
 
 #[cfg(test)]
@@ -155,6 +598,15 @@ mod tests {
         assert!(cli.command.is_none());
     }
 
+    #[test]
+    fn cli_parses_paste_flag() {
+        let cli = Cli::parse_from(["omni", "--paste"]);
+        assert!(cli.paste);
+        assert!(cli.prompt.is_none());
+
+        assert!(Cli::try_parse_from(["omni", "--paste", "list files"]).is_err());
+    }
+
     #[test]
     fn cli_parses_verbose_flag() {
         let cli = Cli::parse_from(["omni", "-v"]);
@@ -178,6 +630,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parses_agent_dry_run() {
+        let cli = Cli::parse_from(["omni", "agent", "do something", "--dry-run"]);
+        match cli.command {
+            Some(Commands::Agent { dry_run, .. }) => assert!(dry_run),
+            _ => panic!("expected Agent command"),
+        }
+    }
+
     #[test]
     fn cli_parses_agent_alias() {
         let cli = Cli::parse_from(["omni", "a", "do something"]);
@@ -202,9 +663,13 @@ mod tests {
             Some(Commands::Tui {
                 r#continue,
                 session,
+                plan,
+                worktree,
             }) => {
+                assert!(worktree.is_none());
                 assert!(r#continue);
                 assert!(session.is_none());
+                assert!(!plan);
             }
             _ => panic!("expected Tui command"),
         }
@@ -217,6 +682,7 @@ mod tests {
             Some(Commands::Tui {
                 r#continue,
                 session,
+                ..
             }) => {
                 assert!(!r#continue);
                 assert_eq!(session, Some("ses_123".to_string()));
@@ -233,6 +699,7 @@ mod tests {
                 prompt,
                 r#continue,
                 session,
+                ..
             }) => {
                 assert_eq!(prompt, "do more");
                 assert!(r#continue);
@@ -242,6 +709,88 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parses_plan_flag() {
+        let cli = Cli::parse_from(["omni", "tui", "--plan"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Tui { plan: true, .. })
+        ));
+
+        let cli = Cli::parse_from(["omni", "agent", "--plan", "design a cache"]);
+        match cli.command {
+            Some(Commands::Agent { prompt, plan, .. }) => {
+                assert_eq!(prompt, "design a cache");
+                assert!(plan);
+            }
+            _ => panic!("expected Agent command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_repeated_append_system() {
+        let cli = Cli::parse_from([
+            "omni",
+            "agent",
+            "--append-system",
+            "be terse",
+            "--append-system",
+            "explain your reasoning",
+            "task",
+        ]);
+        match cli.command {
+            Some(Commands::Agent { append_system, .. }) => {
+                assert_eq!(append_system, vec!["be terse", "explain your reasoning"]);
+            }
+            _ => panic!("expected Agent command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_show_tools() {
+        let cli = Cli::parse_from(["omni", "agent", "task"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Agent { show_tools, .. }) if show_tools == "summary"
+        ));
+
+        let cli = Cli::parse_from(["omni", "agent", "--show-tools", "full", "task"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Agent { show_tools, .. }) if show_tools == "full"
+        ));
+
+        assert!(Cli::try_parse_from(["omni", "agent", "--show-tools", "loud", "task"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_agent_output_format() {
+        let cli = Cli::parse_from(["omni", "agent", "task"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Agent { output, .. }) if output == "text"
+        ));
+
+        let cli = Cli::parse_from(["omni", "agent", "--output", "json", "task"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Agent { output, .. }) if output == "json"
+        ));
+
+        assert!(Cli::try_parse_from(["omni", "agent", "--output", "yaml", "task"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_session_fork() {
+        let cli = Cli::parse_from(["omni", "session", "fork", "quick-fox-123", "--at", "msg_1"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Session {
+                command: SessionCommands::Fork { session_id, at }
+            }) if session_id == "quick-fox-123" && at.as_deref() == Some("msg_1")
+        ));
+    }
+
     #[test]
     fn cli_parses_serve_with_defaults() {
         let cli = Cli::parse_from(["omni", "serve"]);
@@ -299,6 +848,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parses_config_validate() {
+        let cli = Cli::parse_from(["omni", "config", "validate"]);
+        match cli.command {
+            Some(Commands::Config { command }) => {
+                assert!(matches!(command, ConfigCommands::Validate));
+            }
+            _ => panic!("expected Config command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_config_persona_list() {
+        let cli = Cli::parse_from(["omni", "config", "persona", "list", "--format", "json"]);
+        match cli.command {
+            Some(Commands::Config {
+                command:
+                    ConfigCommands::Persona {
+                        command: PersonaCommands::List { format },
+                    },
+            }) => assert_eq!(format, "json"),
+            _ => panic!("expected config persona list"),
+        }
+    }
+
+    #[test]
+    fn table_formats_reject_unknown_values() {
+        for args in [
+            ["omni", "memory", "list", "--format", "yaml"].as_slice(),
+            &["omni", "approvals", "list", "--format", "yaml"],
+            &["omni", "config", "persona", "list", "--format", "yaml"],
+            &["omni", "auth", "status", "--format", "yaml"],
+        ] {
+            assert!(Cli::try_parse_from(args).is_err(), "{args:?}");
+        }
+        assert!(Cli::try_parse_from(["omni", "memory", "list", "--format", "json"]).is_ok());
+    }
+
+    #[test]
+    fn cli_parses_init_force() {
+        let cli = Cli::parse_from(["omni", "init", "--force"]);
+        assert!(matches!(cli.command, Some(Commands::Init { force: true })));
+    }
+
+    #[test]
+    fn cli_parses_auth_login() {
+        let cli = Cli::parse_from(["omni", "auth", "login", "openai"]);
+        match cli.command {
+            Some(Commands::Auth {
+                command: AuthCommands::Login { provider },
+            }) => assert_eq!(provider, "openai"),
+            _ => panic!("expected auth login"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_auth_logout() {
+        let cli = Cli::parse_from(["omni", "auth", "logout", "--all", "-y"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Auth {
+                command: AuthCommands::Logout {
+                    provider: None,
+                    all: true,
+                    yes: true,
+                },
+            })
+        ));
+        assert!(Cli::try_parse_from(["omni", "auth", "logout", "openai", "--all"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_auth_status() {
+        let cli = Cli::parse_from(["omni", "auth", "status", "--probe", "-f", "json"]);
+        match cli.command {
+            Some(Commands::Auth {
+                command: AuthCommands::Status { probe, format },
+            }) => {
+                assert!(probe);
+                assert_eq!(format, "json");
+            }
+            _ => panic!("expected auth status"),
+        }
+    }
+
     #[test]
     fn cli_verbose_is_global() {
         let cli = Cli::parse_from(["omni", "-v", "tui"]);
@@ -327,6 +961,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parses_session_list_time_filters() {
+        let cli = Cli::parse_from([
+            "omni",
+            "session",
+            "list",
+            "--since",
+            "7d",
+            "--after",
+            "2025-01-01",
+            "--before",
+            "2025-02-01",
+        ]);
+        match cli.command {
+            Some(Commands::Session {
+                command:
+                    SessionCommands::List {
+                        since,
+                        after,
+                        before,
+                        ..
+                    },
+            }) => {
+                assert_eq!(since.as_deref(), Some("7d"));
+                assert_eq!(after.as_deref(), Some("2025-01-01"));
+                assert_eq!(before.as_deref(), Some("2025-02-01"));
+            }
+            _ => panic!("expected Session list command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_session_search() {
+        let cli = Cli::parse_from(["omni", "session", "search", "websocket auth", "-f", "json"]);
+        match cli.command {
+            Some(Commands::Session {
+                command: SessionCommands::Search { query, format, .. },
+            }) => {
+                assert_eq!(query, "websocket auth");
+                assert_eq!(format, "json");
+            }
+            _ => panic!("expected Session search command"),
+        }
+    }
+
     #[test]
     fn cli_parses_session_export() {
         let cli = Cli::parse_from(["omni", "session", "export", "abc123", "-f", "markdown"]);
@@ -336,14 +1015,251 @@ mod tests {
                     session_id,
                     format,
                     output,
+                    filter,
                 } => {
                     assert_eq!(session_id, "abc123");
                     assert_eq!(format, "markdown");
                     assert!(output.is_none());
+                    assert!(filter.filter().is_noop());
                 }
                 _ => panic!("expected Export command"),
             },
             _ => panic!("expected Session command"),
         }
     }
+
+    #[test]
+    fn cli_parses_session_retitle() {
+        let cli = Cli::parse_from(["omni", "session", "retitle", "abc123"]);
+        match cli.command {
+            Some(Commands::Session {
+                command: SessionCommands::Retitle { session_id },
+            }) => assert_eq!(session_id, "abc123"),
+            _ => panic!("expected Session Retitle command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_session_undo() {
+        let cli = Cli::parse_from(["omni", "session", "undo", "--force"]);
+        match cli.command {
+            Some(Commands::Session {
+                command: SessionCommands::Undo { session_id, force },
+            }) => {
+                assert!(session_id.is_none());
+                assert!(force);
+            }
+            _ => panic!("expected Session Undo command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_session_import() {
+        let cli = Cli::parse_from(["omni", "session", "import", "backup.json"]);
+        match cli.command {
+            Some(Commands::Session {
+                command: SessionCommands::Import { file },
+            }) => assert_eq!(file, "backup.json"),
+            _ => panic!("expected Session Import command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_doctor_command() {
+        let cli = Cli::parse_from(["omni", "doctor"]);
+        assert!(matches!(cli.command, Some(Commands::Doctor)));
+    }
+
+    #[test]
+    fn cli_parses_export_all() {
+        let cli = Cli::parse_from([
+            "omni", "export", "--all", "-f", "markdown", "--dir", "./backup",
+        ]);
+        match cli.command {
+            Some(Commands::Export {
+                session_id,
+                all,
+                format,
+                dir,
+                ..
+            }) => {
+                assert!(session_id.is_none());
+                assert!(all);
+                assert_eq!(format, "markdown");
+                assert_eq!(dir.as_deref(), Some("./backup"));
+            }
+            _ => panic!("expected Export command"),
+        }
+
+        // --all needs a directory, and a session or --all is required
+        assert!(Cli::try_parse_from(["omni", "export", "--all"]).is_err());
+        assert!(Cli::try_parse_from(["omni", "export"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_export_filters() {
+        let cli = Cli::parse_from([
+            "omni",
+            "session",
+            "export",
+            "abc123",
+            "--only",
+            "assistant",
+            "--no-tools",
+        ]);
+        match cli.command {
+            Some(Commands::Session {
+                command: SessionCommands::Export { filter, .. },
+            }) => assert_eq!(
+                filter.filter(),
+                ExportFilter {
+                    role: Some(ExportRole::Assistant),
+                    tools: ToolFilter::Exclude,
+                }
+            ),
+            _ => panic!("expected Session Export command"),
+        }
+
+        let cli = Cli::parse_from(["omni", "export", "abc123", "--only-tools"]);
+        match cli.command {
+            Some(Commands::Export { filter, .. }) => {
+                assert_eq!(filter.filter().tools, ToolFilter::Only);
+            }
+            _ => panic!("expected Export command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["omni", "export", "abc123", "--no-tools", "--only-tools"])
+                .is_err()
+        );
+        assert!(Cli::try_parse_from(["omni", "export", "abc123", "--only", "tool"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_models_probe() {
+        let cli = Cli::parse_from(["omni", "models", "--provider", "openai", "--probe"]);
+        match cli.command {
+            Some(Commands::Models {
+                provider,
+                probe,
+                format,
+            }) => {
+                assert_eq!(provider.as_deref(), Some("openai"));
+                assert!(probe);
+                assert_eq!(format, "table");
+            }
+            _ => panic!("expected Models command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_maintenance_snapshots() {
+        let cli = Cli::parse_from(["omni", "maintenance", "snapshots", "--prune"]);
+        match cli.command {
+            Some(Commands::Maintenance {
+                command: MaintenanceCommands::Snapshots { prune },
+            }) => assert!(prune),
+            _ => panic!("expected Maintenance Snapshots command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_snapshot_commands() {
+        let cli = Cli::parse_from(["omni", "snapshot", "diff"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Snapshot {
+                command: SnapshotCommands::Diff { hash: None }
+            })
+        ));
+
+        let cli = Cli::parse_from(["omni", "snapshot", "restore", "abc123", "--yes"]);
+        match cli.command {
+            Some(Commands::Snapshot {
+                command: SnapshotCommands::Restore { hash, force },
+            }) => {
+                assert_eq!(hash, "abc123");
+                assert!(force);
+            }
+            _ => panic!("expected Snapshot Restore command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_memory_commands() {
+        let cli = Cli::parse_from([
+            "omni",
+            "memory",
+            "add",
+            "prefers tabs",
+            "--category",
+            "preference",
+            "--pin",
+            "--tag",
+            "style",
+        ]);
+        match cli.command {
+            Some(Commands::Memory {
+                command:
+                    MemoryCommands::Add {
+                        content,
+                        category,
+                        pin,
+                        tags,
+                    },
+            }) => {
+                assert_eq!(content, "prefers tabs");
+                assert_eq!(category, "preference");
+                assert!(pin);
+                assert_eq!(tags, ["style"]);
+            }
+            _ => panic!("expected Memory Add command"),
+        }
+
+        assert!(Cli::try_parse_from(["omni", "memory", "list", "--category", "nope"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_worktree_flag() {
+        let cli = Cli::parse_from(["omni", "agent", "--worktree", "fix the bug"]);
+        match cli.command {
+            Some(Commands::Agent {
+                prompt, worktree, ..
+            }) => {
+                assert_eq!(prompt, "fix the bug");
+                assert_eq!(worktree, Some(None));
+            }
+            _ => panic!("expected Agent command"),
+        }
+
+        let cli = Cli::parse_from(["omni", "tui", "--worktree=spike"]);
+        match cli.command {
+            Some(Commands::Tui { worktree, .. }) => {
+                assert_eq!(worktree, Some(Some("spike".to_string())));
+            }
+            _ => panic!("expected Tui command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_worktree_commands() {
+        let cli = Cli::parse_from(["omni", "worktree", "create"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Worktree {
+                command: WorktreeCommands::Create { name: None }
+            })
+        ));
+
+        let cli = Cli::parse_from(["omni", "worktree", "reset", "calm-otter", "-y"]);
+        match cli.command {
+            Some(Commands::Worktree {
+                command: WorktreeCommands::Reset { name, yes },
+            }) => {
+                assert_eq!(name, "calm-otter");
+                assert!(yes);
+            }
+            _ => panic!("expected Worktree Reset command"),
+        }
+    }
 }
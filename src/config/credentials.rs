@@ -0,0 +1,381 @@
+//! API key resolution across env vars, config, the OS keychain and the
+//! credentials file.
+
+use std::fmt;
+
+use super::{AgentConfig, ProviderConfig};
+use crate::core::keychain::{self, KeychainError, file::CredentialFile};
+
+/// Where a provider's API key was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySource {
+    /// The environment variable named by `api_key_env`.
+    Env(String),
+    /// `api_key` in the config file.
+    Config,
+    /// The OS keychain.
+    Keychain,
+    /// The encrypted credentials file.
+    File,
+}
+
+impl fmt::Display for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env(name) => write!(f, "env {name}"),
+            Self::Config => f.write_str("config api_key"),
+            Self::Keychain => f.write_str("keychain"),
+            Self::File => f.write_str("credentials file"),
+        }
+    }
+}
+
+/// A resolved API key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    /// The key itself.
+    pub key: String,
+    /// Where it was found.
+    pub source: KeySource,
+}
+
+// Keep the key itself out of debug output
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ApiKey {
+    /// Keys shorter than this are masked completely.
+    const MIN_UNMASKED_LEN: usize = 12;
+
+    /// The key with everything but its last four characters hidden, for
+    /// display.
+    ///
+    /// Key prefixes like `sk-ant-` are shared by every key of a provider, so
+    /// only the tail tells keys apart.
+    #[must_use]
+    pub fn masked(&self) -> String {
+        let chars: Vec<char> = self.key.chars().collect();
+        if chars.len() < Self::MIN_UNMASKED_LEN {
+            return "****".to_string();
+        }
+        let tail: String = chars[chars.len() - 4..].iter().collect();
+        format!("****{tail}")
+    }
+}
+
+/// No API key could be found for a provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingApiKey {
+    /// Provider name.
+    pub provider: String,
+    /// Environment variable the key could be set in.
+    pub env: Option<String>,
+    /// Why the OS keychain couldn't be checked, if it couldn't.
+    pub keychain_unavailable: Option<String>,
+}
+
+impl fmt::Display for MissingApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "API key not set for provider '{}'", self.provider)?;
+        if let Some(reason) = &self.keychain_unavailable {
+            write!(
+                f,
+                "; the OS keyring is unavailable ({reason}), so keys from `omni auth login` can't be read"
+            )?;
+            match &self.env {
+                Some(env) => write!(f, ". Set {env} instead")?,
+                None => f.write_str(". Set api_key_env for this provider instead")?,
+            }
+            f.write_str(", or enable [api] allow_file_credentials and log in again")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MissingApiKey {}
+
+impl AgentConfig {
+    /// Find the API key for provider `name`.
+    ///
+    /// Checks the provider's env var, then `api_key`, then the OS keychain,
+    /// then the credentials file when `[api] allow_file_credentials` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MissingApiKey`] if no source has a key, noting when the
+    /// keychain couldn't be reached.
+    pub fn find_api_key(&self, name: &str) -> Result<ApiKey, MissingApiKey> {
+        let file = self
+            .allow_file_credentials
+            .then(CredentialFile::in_data_dir)
+            .and_then(|store| {
+                store
+                    .inspect_err(|e| tracing::warn!("credentials file unavailable: {e}"))
+                    .ok()
+            });
+        self.find_api_key_with(name, || keychain::get_api_key(name), file.as_ref())
+    }
+
+    /// Save `key` for provider `name` where [`Self::find_api_key`] will find it.
+    ///
+    /// Uses the OS keychain, or the credentials file when the keyring is
+    /// unavailable and `[api] allow_file_credentials` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if neither store can take the key.
+    pub fn store_api_key(&self, name: &str, key: &str) -> anyhow::Result<KeySource> {
+        match keychain::set_api_key(name, key) {
+            Ok(()) => Ok(KeySource::Keychain),
+            Err(KeychainError::Unavailable(reason)) if self.allow_file_credentials => {
+                tracing::debug!("keyring unavailable ({reason}), using credentials file");
+                CredentialFile::in_data_dir()?.set(name, key)?;
+                Ok(KeySource::File)
+            }
+            Err(KeychainError::Unavailable(reason)) => anyhow::bail!(
+                "the OS keyring is unavailable ({reason}); set the provider's API key env var \
+                 instead, or enable [api] allow_file_credentials to store keys in an encrypted file"
+            ),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Stores that hold a saved key for provider `name`.
+    ///
+    /// Only the keychain and credentials file count; env vars and `api_key`
+    /// aren't saved by omni.
+    #[must_use]
+    pub fn saved_key_sources(&self, name: &str) -> Vec<KeySource> {
+        let mut sources = Vec::new();
+        if matches!(keychain::get_api_key(name), Ok(Some(_))) {
+            sources.push(KeySource::Keychain);
+        }
+        if CredentialFile::in_data_dir()
+            .and_then(|store| store.get(name))
+            .is_ok_and(|key| key.is_some())
+        {
+            sources.push(KeySource::File);
+        }
+        sources
+    }
+
+    /// Providers with a saved key, configured ones first, each listed once.
+    #[must_use]
+    pub fn providers_with_saved_keys(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.providers.keys().cloned().collect();
+        names.sort();
+        // Keys can outlive the config entry of the provider they belong to
+        let file = CredentialFile::in_data_dir().and_then(|store| store.providers());
+        for name in file
+            .into_iter()
+            .flatten()
+            .chain(keychain::list_providers().into_iter().flatten())
+        {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+            .into_iter()
+            .filter(|name| !self.saved_key_sources(name).is_empty())
+            .collect()
+    }
+
+    /// Delete the saved key for provider `name` from the keychain and the
+    /// credentials file, returning the stores it was removed from.
+    ///
+    /// An unavailable keyring is skipped, since it can't hold a key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a store holds the key but can't delete it.
+    pub fn remove_api_key(&self, name: &str) -> anyhow::Result<Vec<KeySource>> {
+        let mut removed = Vec::new();
+        match keychain::delete_api_key(name) {
+            Ok(true) => removed.push(KeySource::Keychain),
+            Ok(false) => {}
+            Err(KeychainError::Unavailable(reason)) => {
+                tracing::debug!("skipping keychain ({reason})");
+            }
+            Err(e) => return Err(e.into()),
+        }
+        if CredentialFile::in_data_dir()?.remove(name)? {
+            removed.push(KeySource::File);
+        }
+        Ok(removed)
+    }
+
+    /// Find the API key for `name`, asking `keychain` and `file` after config.
+    pub(crate) fn find_api_key_with(
+        &self,
+        name: &str,
+        keychain: impl FnOnce() -> Result<Option<String>, KeychainError>,
+        file: Option<&CredentialFile>,
+    ) -> Result<ApiKey, MissingApiKey> {
+        let config = self.providers.get(name);
+        let found = match config.and_then(configured_key) {
+            Some(found) => found,
+            None => {
+                stored_key(name, keychain, file).map_err(|keychain_unavailable| MissingApiKey {
+                    provider: name.to_string(),
+                    env: config.and_then(|c| c.api_key_env.clone()),
+                    keychain_unavailable,
+                })?
+            }
+        };
+
+        tracing::debug!(provider = %name, source = %found.source, "resolved API key");
+        Ok(found)
+    }
+}
+
+/// The key for `name` from `keychain`, or from `file` if the keychain has none.
+///
+/// On a miss, returns why the keychain couldn't be read, unless the file
+/// fallback is in use and makes that expected.
+fn stored_key(
+    name: &str,
+    keychain: impl FnOnce() -> Result<Option<String>, KeychainError>,
+    file: Option<&CredentialFile>,
+) -> Result<ApiKey, Option<String>> {
+    let keychain_unavailable = match keychain() {
+        Ok(Some(key)) => {
+            return Ok(ApiKey {
+                key,
+                source: KeySource::Keychain,
+            });
+        }
+        Ok(None) => None,
+        Err(KeychainError::Unavailable(reason)) => Some(reason),
+        Err(e) => {
+            tracing::warn!(provider = %name, "keychain lookup failed: {e}");
+            None
+        }
+    };
+
+    let Some(store) = file else {
+        return Err(keychain_unavailable);
+    };
+    match store.get(name) {
+        Ok(Some(key)) => Ok(ApiKey {
+            key,
+            source: KeySource::File,
+        }),
+        Ok(None) => Err(None),
+        Err(e) => {
+            tracing::warn!(provider = %name, "{e}");
+            Err(None)
+        }
+    }
+}
+
+/// The key from the provider's env var or `api_key`, if either is set.
+fn configured_key(config: &ProviderConfig) -> Option<ApiKey> {
+    if let Some(env_name) = &config.api_key_env {
+        if let Ok(key) = std::env::var(env_name) {
+            return Some(ApiKey {
+                key,
+                source: KeySource::Env(env_name.clone()),
+            });
+        }
+    }
+    config.api_key.clone().map(|key| ApiKey {
+        key,
+        source: KeySource::Config,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderApiType;
+
+    fn config_with(provider: ProviderConfig) -> AgentConfig {
+        let mut config = AgentConfig::default();
+        config.providers.insert("custom".to_string(), provider);
+        config
+    }
+
+    #[test]
+    fn config_key_wins_over_keychain() {
+        let config = config_with(ProviderConfig {
+            api_type: ProviderApiType::OpenAi,
+            api_key: Some("sk-direct".to_string()),
+            ..ProviderConfig::default()
+        });
+        let found = config
+            .find_api_key_with("custom", || panic!("keychain not needed"), None)
+            .unwrap();
+        assert_eq!(found.source, KeySource::Config);
+        assert_eq!(found.key, "sk-direct");
+    }
+
+    #[test]
+    fn falls_back_to_keychain_then_file() {
+        let config = config_with(ProviderConfig {
+            api_type: ProviderApiType::OpenAi,
+            api_key_env: Some("OMNI_TEST_CREDENTIALS_UNSET".to_string()),
+            ..ProviderConfig::default()
+        });
+        let found = config
+            .find_api_key_with("custom", || Ok(Some("sk-keychain".to_string())), None)
+            .unwrap();
+        assert_eq!(found.source, KeySource::Keychain);
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = CredentialFile::new(dir.path().to_path_buf());
+        store.set("custom", "sk-file").unwrap();
+        let found = config
+            .find_api_key_with(
+                "custom",
+                || Err(KeychainError::Unavailable("no D-Bus".to_string())),
+                Some(&store),
+            )
+            .unwrap();
+        assert_eq!(found.source, KeySource::File);
+        assert_eq!(found.key, "sk-file");
+    }
+
+    #[test]
+    fn masks_all_but_the_end_of_a_key() {
+        let key = |key: &str| ApiKey {
+            key: key.to_string(),
+            source: KeySource::Config,
+        };
+        assert_eq!(key("sk-ant-api03-abcdefwxyz").masked(), "****wxyz");
+        assert_eq!(key("short-key").masked(), "****");
+    }
+
+    #[test]
+    fn unavailable_keyring_is_explained() {
+        let config = config_with(ProviderConfig {
+            api_type: ProviderApiType::OpenAi,
+            api_key_env: Some("OMNI_TEST_CREDENTIALS_UNSET".to_string()),
+            ..ProviderConfig::default()
+        });
+
+        let missing = config
+            .find_api_key_with("custom", || Ok(None), None)
+            .unwrap_err();
+        assert_eq!(missing.to_string(), "API key not set for provider 'custom'");
+
+        let missing = config
+            .find_api_key_with(
+                "custom",
+                || Err(KeychainError::Unavailable("no D-Bus".to_string())),
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(
+            missing.to_string(),
+            "API key not set for provider 'custom'; the OS keyring is unavailable (no D-Bus), \
+             so keys from `omni auth login` can't be read. Set OMNI_TEST_CREDENTIALS_UNSET \
+             instead, or enable [api] allow_file_credentials and log in again"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! `${VAR}` and `$VAR` expansion in config values.
+
+/// Expand environment variable references in `value`.
+///
+/// `${VAR}` and `$VAR` are replaced with the variable's value and `$$` with a
+/// literal `$`. A `$` not followed by a name is kept as is.
+///
+/// # Errors
+///
+/// Returns an error naming the variable if one is unset, or if a `${` is
+/// never closed.
+pub fn expand_env(value: &str) -> anyhow::Result<String> {
+    expand_with(value, |name| std::env::var(name).ok())
+}
+
+/// Expand references in `value`, resolving names with `lookup`.
+fn expand_with(value: &str, lookup: impl Fn(&str) -> Option<String>) -> anyhow::Result<String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+
+        let (name, remaining) = if let Some(escaped) = after.strip_prefix('$') {
+            expanded.push('$');
+            rest = escaped;
+            continue;
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| anyhow::anyhow!("unclosed '${{' in \"{value}\""))?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+        let resolved = lookup(name)
+            .ok_or_else(|| anyhow::anyhow!("environment variable {name} is not set"))?;
+        expanded.push_str(&resolved);
+        rest = remaining;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "GATEWAY" => Some("https://gw.example.com".to_string()),
+            "TEAM" => Some("core".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expands_braced_and_bare_references() {
+        assert_eq!(
+            expand_with("${GATEWAY}/v1", lookup).unwrap(),
+            "https://gw.example.com/v1"
+        );
+        assert_eq!(expand_with("$TEAM-key", lookup).unwrap(), "core-key");
+        assert_eq!(
+            expand_with("no references", lookup).unwrap(),
+            "no references"
+        );
+    }
+
+    #[test]
+    fn keeps_escaped_and_bare_dollars() {
+        assert_eq!(expand_with("pa$$word", lookup).unwrap(), "pa$word");
+        assert_eq!(
+            expand_with("costs $5 or $", lookup).unwrap(),
+            "costs $5 or $"
+        );
+    }
+
+    #[test]
+    fn reports_unset_and_unclosed_references() {
+        let err = expand_with("${MISSING}/v1", lookup).unwrap_err();
+        assert_eq!(err.to_string(), "environment variable MISSING is not set");
+        assert!(expand_with("${GATEWAY", lookup).is_err());
+    }
+}
@@ -1,18 +1,36 @@
 //! Configuration management for the Omni CLI.
 
+mod credentials;
+mod env;
 mod persona;
+mod validate;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use serde::{Deserialize, Serialize};
 
 use crate::core::agent::{
-    AgentMode, AnthropicProvider, LlmProvider, OpenAiProvider, UnifiedProvider,
+    AgentMode, AnthropicProvider, DEFAULT_COMPACTION_RATIO, DEFAULT_MAX_OUTPUT_BYTES,
+    DEFAULT_MAX_PARALLEL_TOOLS, DEFAULT_TOOL_TIMEOUT_SECS, LlmProvider, ModelPricing,
+    OpenAiProvider, ProviderLimiter, RateLimit, RateLimitedProvider, ReasoningEffort,
+    UnifiedProvider, default_pricing,
 };
 
-pub use agent_core::permission::{AgentPermissions, PermissionPreset};
-pub use persona::{Persona, list_personas, load_persona, personas_dir};
+use crate::core::mcp::McpServerConfig;
+use crate::core::project::Project;
+use crate::core::snapshot::RetentionPolicy;
+use crate::core::stdin::DEFAULT_MAX_STDIN_BYTES;
+
+pub use agent_core::permission::{AgentPermissions, PermissionPreset, PermissionRule};
+pub use credentials::{ApiKey, KeySource, MissingApiKey};
+pub use env::expand_env;
+pub use persona::{
+    Persona, PersonaEntry, PersonaSource, list_personas, load_persona, personas_dir,
+    project_personas_dir,
+};
+pub use validate::{Finding, Severity, validate};
 
 /// Model information with provider association.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +58,8 @@ pub enum ProviderApiType {
     Groq,
     /// Mistral API
     Mistral,
+    /// Azure `OpenAI` deployment (Chat Completions with Azure routing and auth)
+    Azure,
 }
 
 /// Individual agent definition.
@@ -76,6 +96,37 @@ pub struct ProviderConfig {
     /// Direct API key (discouraged, prefer `api_key_env`)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
+
+    /// Maximum concurrent requests to this provider (unlimited if unset).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<usize>,
+
+    /// Maximum requests per minute to this provider (unlimited if unset).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+
+    /// Azure resource name, used as `https://<resource>.openai.azure.com`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+
+    /// Azure deployment name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+
+    /// Azure API version (defaults to a recent GA version).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+}
+
+impl ProviderConfig {
+    /// Request limits for this provider.
+    #[must_use]
+    pub const fn rate_limit(&self) -> RateLimit {
+        RateLimit {
+            max_concurrent: self.max_concurrent,
+            requests_per_minute: self.requests_per_minute,
+        }
+    }
 }
 
 /// Application configuration.
@@ -90,6 +141,32 @@ pub struct Config {
 
     /// Agent configuration.
     pub agent: AgentConfig,
+
+    /// Storage configuration.
+    pub storage: StorageConfig,
+
+    /// Plugin configuration.
+    pub plugins: PluginsConfig,
+}
+
+/// Global conversation history file, shared by every project before per-project history.
+const GLOBAL_HISTORY_FILE: &str = "conversation.json";
+
+/// Set by `--global-history`.
+static GLOBAL_HISTORY: AtomicBool = AtomicBool::new(false);
+
+/// History file for `project_id`, moving the global history there if it has none yet.
+fn project_history_path(data_dir: &Path, project_id: &str) -> anyhow::Result<PathBuf> {
+    let path = data_dir.join("history").join(format!("{project_id}.json"));
+    let global = data_dir.join(GLOBAL_HISTORY_FILE);
+    if !path.exists() && global.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&global, &path)?;
+        tracing::info!(to = %path.display(), "moved global conversation history into project");
+    }
+    Ok(path)
 }
 
 impl Config {
@@ -119,9 +196,49 @@ impl Config {
             }
         }
 
+        config.expand_env_vars()?;
+        config.agent.allow_file_credentials = config.api.allow_file_credentials;
         Ok(config)
     }
 
+    /// Expand `${VAR}` and `$VAR` references in provider settings.
+    ///
+    /// Applies to `base_url`, `api_key_env`, `resource` and `deployment`, so
+    /// one config works across machines. `api_key` is left alone: it holds a
+    /// secret, and expanding it would print the variable's value in
+    /// `config show`.
+    ///
+    /// A variable that's unset only matters for the active provider. Other
+    /// providers keep the reference as written, with a warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the field if a variable referenced by the
+    /// active provider is unset.
+    fn expand_env_vars(&mut self) -> anyhow::Result<()> {
+        for (name, provider) in &mut self.agent.providers {
+            let fields = [
+                ("base_url", &mut provider.base_url),
+                ("api_key_env", &mut provider.api_key_env),
+                ("resource", &mut provider.resource),
+                ("deployment", &mut provider.deployment),
+            ];
+            for (field, value) in fields {
+                let Some(value) = value else {
+                    continue;
+                };
+                match expand_env(value) {
+                    Ok(expanded) => *value = expanded,
+                    Err(e) if *name == self.agent.provider => {
+                        anyhow::bail!("[agent.providers.{name}] {field}: {e}");
+                    }
+                    Err(e) => tracing::warn!("[agent.providers.{name}] {field}: {e}"),
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Get the project-local configuration file path.
     ///
     /// Looks for `.omni/config.toml` in the current directory.
@@ -133,12 +250,72 @@ impl Config {
     /// Merge another config into this one (project overrides global).
     fn merge(&mut self, other: Self) {
         // Agent model override
-        if other.agent.model != AgentConfig::default().model {
+        let model_changed = other.agent.model != AgentConfig::default().model;
+        if model_changed {
             self.agent.model = other.agent.model;
         }
         if other.agent.max_tokens != AgentConfig::default().max_tokens {
             self.agent.max_tokens = other.agent.max_tokens;
         }
+        if other.agent.persona != AgentConfig::default().persona {
+            self.agent.persona = other.agent.persona;
+        }
+        if other.agent.default_agent != AgentConfig::default().default_agent {
+            self.agent.default_agent = other.agent.default_agent;
+        }
+
+        // Project providers and models augment the global ones. Entries
+        // matching the built-in defaults are only there because the project
+        // didn't set them, so they don't replace global customizations.
+        // A project can't redefine a global or built-in provider, and its
+        // own providers can't name a key: pointing a base URL elsewhere would
+        // send the user's key to that host. Keys for project providers come
+        // from `omni auth login <name>`.
+        let default_providers = AgentConfig::default_providers();
+        for (name, mut provider) in other.agent.providers {
+            if default_providers.get(&name) == Some(&provider) {
+                continue;
+            }
+            match self.agent.providers.get(&name) {
+                Some(existing) if *existing != provider => {
+                    tracing::warn!(
+                        "ignoring project config for provider '{name}', which is already defined globally"
+                    );
+                }
+                Some(_) => {}
+                None if default_providers.contains_key(&name) => {
+                    tracing::warn!("ignoring project config for built-in provider '{name}'");
+                }
+                None => {
+                    if provider.api_key.take().is_some() || provider.api_key_env.take().is_some() {
+                        tracing::warn!(
+                            "ignoring api_key and api_key_env for project provider '{name}'"
+                        );
+                    }
+                    self.agent.providers.insert(name, provider);
+                }
+            }
+        }
+        let default_models = AgentConfig::default_models();
+        for model in other.agent.models {
+            if default_models
+                .iter()
+                .any(|m| m.id == model.id && m.provider == model.provider)
+            {
+                continue;
+            }
+            match self.agent.models.iter_mut().find(|m| m.id == model.id) {
+                Some(existing) => *existing = model,
+                None => self.agent.models.push(model),
+            }
+        }
+
+        // A project model runs on its own provider, not the global one
+        if model_changed {
+            if let Some(provider) = self.agent.provider_for_model(&self.agent.model) {
+                self.agent.provider = provider.to_string();
+            }
+        }
 
         // API config overrides
         if other.api.port != ApiConfig::default().port {
@@ -147,6 +324,11 @@ impl Config {
         if other.api.host != ApiConfig::default().host {
             self.api.host = other.api.host;
         }
+
+        // Storage overrides
+        if other.storage.session_dir.is_some() {
+            self.storage.session_dir = other.storage.session_dir;
+        }
     }
 
     /// Get the configuration file path.
@@ -182,13 +364,38 @@ impl Config {
         Ok(base.data_dir().join("omni").join("cli"))
     }
 
-    /// Get the conversation history file path.
+    /// Share one conversation history across all projects (`--global-history`).
+    pub fn use_global_history() {
+        GLOBAL_HISTORY.store(true, Ordering::Relaxed);
+    }
+
+    /// Get the conversation history file path for the current project.
+    ///
+    /// Each project keeps its own history under `history/<project id>.json`.
+    /// The first time a project's history is requested, an existing global
+    /// `conversation.json` is moved there. With `--global-history`, or when
+    /// no project can be detected, the global file is used.
     ///
     /// # Errors
     ///
-    /// Returns an error if the data directory cannot be determined.
+    /// Returns an error if the data directory cannot be determined or the
+    /// global history cannot be moved.
     pub fn history_path() -> anyhow::Result<PathBuf> {
-        Ok(Self::data_dir()?.join("conversation.json"))
+        let data_dir = Self::data_dir()?;
+        if GLOBAL_HISTORY.load(Ordering::Relaxed) {
+            return Ok(data_dir.join(GLOBAL_HISTORY_FILE));
+        }
+
+        let project = std::env::current_dir()
+            .map_err(anyhow::Error::from)
+            .and_then(|cwd| Project::detect(&cwd));
+        match project {
+            Ok(project) => project_history_path(&data_dir, &project.id),
+            Err(e) => {
+                tracing::debug!("no project for history, using global file: {e}");
+                Ok(data_dir.join(GLOBAL_HISTORY_FILE))
+            }
+        }
     }
 
     /// Get the state file path for persisting runtime state
@@ -241,6 +448,76 @@ impl Config {
     }
 }
 
+/// Plugin configuration.
+///
+/// Only read from the global config file: a project config can't start
+/// processes on the user's machine.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    /// MCP servers run as plugins, keyed by plugin name.
+    pub mcp: HashMap<String, McpPluginConfig>,
+}
+
+/// MCP server started over stdio as a plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpPluginConfig {
+    /// Executable to run.
+    pub command: String,
+
+    /// Arguments passed to the command.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Environment variables set for the server.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Whether the server is started.
+    #[serde(default = "McpPluginConfig::default_enabled")]
+    pub enabled: bool,
+
+    /// Longest a tool call may take, in seconds (default 120).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl McpPluginConfig {
+    const fn default_enabled() -> bool {
+        true
+    }
+
+    /// Server configuration for the MCP client.
+    #[must_use]
+    pub fn server_config(&self) -> McpServerConfig {
+        let mut command = vec![self.command.clone()];
+        command.extend(self.args.iter().cloned());
+        let mut config = McpServerConfig::local(command).with_env(self.env.clone());
+        config.enabled = self.enabled;
+        config
+    }
+}
+
+/// Storage configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Directory for sessions instead of the global data directory.
+    ///
+    /// Relative paths resolve against the project root, so `.omni/sessions`
+    /// keeps sessions in the repository. Share tokens stay in the global
+    /// data directory so their secrets are never committed.
+    pub session_dir: Option<PathBuf>,
+}
+
+impl StorageConfig {
+    /// Resolve `session_dir` for a project rooted at `worktree`.
+    #[must_use]
+    pub fn session_root(&self, worktree: &Path) -> Option<PathBuf> {
+        self.session_dir.as_ref().map(|dir| worktree.join(dir))
+    }
+}
+
 /// API server configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -255,6 +532,15 @@ pub struct ApiConfig {
     /// Can also be set via `OMNI_API_TOKEN` environment variable.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token: Option<String>,
+
+    /// Cross-origin access for browser clients (`[api.cors]`).
+    pub cors: CorsConfig,
+
+    /// Store API keys in an encrypted file when the OS keyring is unavailable.
+    ///
+    /// Meant for headless machines without a Secret Service; the file is
+    /// weaker protection than a keyring.
+    pub allow_file_credentials: bool,
 }
 
 impl Default for ApiConfig {
@@ -263,10 +549,62 @@ impl Default for ApiConfig {
             host: "127.0.0.1".to_string(),
             port: 7890,
             token: None,
+            cors: CorsConfig::default(),
+            allow_file_credentials: false,
+        }
+    }
+}
+
+/// CORS settings for the API server (`[api.cors]`).
+///
+/// No origins (the default) sends no CORS headers, so browsers block
+/// cross-origin requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API, or `"*"` for any origin.
+    #[serde(deserialize_with = "CorsConfig::deserialize_origins")]
+    pub allowed_origins: Vec<String>,
+
+    /// HTTP methods allowed for cross-origin requests.
+    pub allowed_methods: Vec<String>,
+
+    /// Let browsers send credentials such as the `Authorization` header.
+    ///
+    /// Can't be combined with a `"*"` origin.
+    pub allow_credentials: bool,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: ["GET", "POST", "DELETE"].map(String::from).to_vec(),
+            allow_credentials: false,
         }
     }
 }
 
+impl CorsConfig {
+    /// Accept either a single origin such as `"*"` or a list.
+    fn deserialize_origins<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Origins {
+            One(String),
+            Many(Vec<String>),
+        }
+
+        Ok(match Origins::deserialize(deserializer)? {
+            Origins::One(origin) => vec![origin],
+            Origins::Many(origins) => origins,
+        })
+    }
+}
+
 impl ApiConfig {
     /// Get the API token, preferring env var over config file.
     #[must_use]
@@ -289,12 +627,37 @@ impl ApiConfig {
 /// TUI configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct TuiConfig {
     /// Enable mouse support.
     pub mouse: bool,
 
     /// Show ecosystem tips on welcome screen.
     pub tips: bool,
+
+    /// Key combination that submits the prompt.
+    pub submit_key: SubmitKey,
+
+    /// Keep the model chosen for `/regenerate <model>` instead of reverting.
+    pub regenerate_keeps_model: bool,
+
+    /// Fold consecutive tool calls into a summary line (toggle with `/collapse`).
+    pub collapse_tools: bool,
+
+    /// Minimum number of consecutive tool calls to fold.
+    pub collapse_tools_threshold: usize,
+
+    /// Highlight code blocks and file contents by language.
+    pub syntax_highlight: bool,
+
+    /// Vim-style normal and insert modes in the prompt (Esc enters normal mode).
+    pub vim_mode: bool,
+
+    /// Most files the `@` mention picker lists.
+    pub file_picker_max_files: usize,
+
+    /// Color theme.
+    pub theme: ThemeConfig,
 }
 
 impl Default for TuiConfig {
@@ -302,6 +665,150 @@ impl Default for TuiConfig {
         Self {
             mouse: true,
             tips: true,
+            submit_key: SubmitKey::default(),
+            regenerate_keeps_model: false,
+            collapse_tools: false,
+            collapse_tools_threshold: 3,
+            syntax_highlight: true,
+            vim_mode: false,
+            file_picker_max_files: 5000,
+            theme: ThemeConfig::default(),
+        }
+    }
+}
+
+/// TUI color theme (`[tui.theme]`).
+///
+/// Roles take a color name (`red`, `light-blue`), `#rrggbb` or an ANSI index
+/// (`0`-`255`). Unset roles use the preset's color.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// Base palette the roles below override.
+    pub preset: ThemePreset,
+
+    /// Dialog borders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border: Option<String>,
+
+    /// Highlights such as the prompt bar, command palette and selected items.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accent: Option<String>,
+
+    /// Bar beside user messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+
+    /// Assistant response text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assistant: Option<String>,
+
+    /// Tool call names.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+
+    /// Added lines in diffs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff_add: Option<String>,
+
+    /// Removed lines in diffs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff_remove: Option<String>,
+
+    /// Secondary text such as hints, status and tool output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dim: Option<String>,
+
+    /// Background of user messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub panel: Option<String>,
+
+    /// Background of the prompt input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+
+    /// Background of dialogs and dropdowns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialog: Option<String>,
+
+    /// Background of the selected item in lists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection: Option<String>,
+
+    /// Text in dialogs and dropdowns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Built-in TUI color palette.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemePreset {
+    /// Light text on dark backgrounds.
+    #[default]
+    Dark,
+    /// Dark text on light backgrounds.
+    Light,
+}
+
+/// Key combination that submits the TUI prompt.
+///
+/// Whichever Enter combination does not submit inserts a newline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmitKey {
+    /// Enter submits; Shift+Enter or Alt+Enter inserts a newline.
+    #[default]
+    Enter,
+    /// Ctrl+Enter submits; Enter inserts a newline.
+    CtrlEnter,
+    /// Alt+Enter submits; Enter inserts a newline.
+    AltEnter,
+}
+
+impl SubmitKey {
+    /// Short key hint for status lines.
+    #[must_use]
+    pub const fn hint(self) -> &'static str {
+        match self {
+            Self::Enter => "enter send · shift+enter newline",
+            Self::CtrlEnter => "ctrl+enter send · enter newline",
+            Self::AltEnter => "alt+enter send · enter newline",
+        }
+    }
+}
+
+/// A section of the assembled system prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptSection {
+    /// Model identity line.
+    Identity,
+    /// Persona instructions.
+    Persona,
+    /// Pinned and recent project memories.
+    Memory,
+    /// Gathered project context (git, instruction files, platform).
+    Context,
+}
+
+/// System prompt assembly configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptConfig {
+    /// Sections to include, in order. Unlisted sections are omitted.
+    pub sections: Vec<PromptSection>,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        Self {
+            sections: vec![
+                PromptSection::Identity,
+                PromptSection::Persona,
+                PromptSection::Memory,
+                PromptSection::Context,
+            ],
         }
     }
 }
@@ -309,6 +816,7 @@ impl Default for TuiConfig {
 /// Agent configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct AgentConfig {
     /// Active provider name (key in providers table).
     pub provider: String,
@@ -325,6 +833,92 @@ pub struct AgentConfig {
     /// Default agent to use on startup.
     pub default_agent: String,
 
+    /// Append agent shell commands to `.omni/shell_history.log`.
+    pub log_shell_commands: bool,
+
+    /// Shell command patterns that always prompt, even if approved for the session.
+    pub high_risk_commands: Vec<String>,
+
+    /// Maximum bytes of shell output buffered before the command is killed.
+    pub max_tool_output_bytes: usize,
+
+    /// Maximum bytes of piped stdin appended to a bare prompt.
+    pub max_stdin_bytes: usize,
+
+    /// Seconds a shell command may run before it is killed (0 for no limit).
+    ///
+    /// Local read-only tools are limited to the shorter of this and 30 seconds.
+    pub tool_timeout_secs: u64,
+
+    /// Maximum read-only tool calls executed concurrently.
+    ///
+    /// Writes, edits and shell commands always run one at a time.
+    pub max_parallel_tools: usize,
+
+    /// Model used for compaction summaries (defaults to `model`).
+    ///
+    /// Must be served by the active provider; falls back to `model` if the
+    /// request fails.
+    pub compaction_model: Option<String>,
+
+    /// Model used for session titles (defaults to `model`).
+    ///
+    /// Must be served by the active provider; falls back to `model` if the
+    /// request fails.
+    pub title_model: Option<String>,
+
+    /// Fraction of the context window that triggers automatic compaction.
+    ///
+    /// Set to 0 to turn automatic compaction off.
+    pub compaction_threshold: f64,
+
+    /// Context window in tokens, overriding the built-in size for the model.
+    pub context_window: Option<u32>,
+
+    /// Reasoning effort for models that think before answering ("low", "medium" or "high").
+    ///
+    /// Sent as `reasoning_effort` to `OpenAI` reasoning models and as an
+    /// extended-thinking budget to Anthropic. Unset leaves reasoning off.
+    pub reasoning_effort: Option<ReasoningEffort>,
+
+    /// Snapshot the project before tools that change files.
+    ///
+    /// Snapshots back `omni snapshot restore` and `omni session undo`.
+    pub auto_snapshot: bool,
+
+    /// Days to keep file snapshots before cleanup prunes them.
+    pub snapshot_retention_days: u32,
+
+    /// Most recent snapshots kept regardless of age.
+    pub snapshot_keep_last_n: usize,
+
+    /// System prompt section order.
+    pub prompt: PromptConfig,
+
+    /// Include uncommitted changes (`git diff HEAD`) in the project context.
+    ///
+    /// The diff is taken when the session starts and labeled as such; it
+    /// isn't refreshed as the session edits files.
+    pub context_diff: bool,
+
+    /// Most diff lines included with `context_diff`.
+    ///
+    /// Larger diffs are reduced to a diffstat plus the first hunk of each file.
+    pub context_diff_max_lines: usize,
+
+    /// Send privacy-respecting defaults to providers.
+    ///
+    /// Omni never sends user-id headers or Anthropic `metadata.user_id`. When
+    /// enabled, `OpenAI` requests set `store: false` and `OpenRouter` requests set
+    /// `provider.data_collection = "deny"`. Other providers have no equivalent flag.
+    pub anonymous: bool,
+
+    /// Mark the system prompt and tool definitions as cacheable.
+    ///
+    /// Applies to Anthropic providers, which bill cache reads at a tenth of
+    /// the input price. Other providers cache automatically or not at all.
+    pub prompt_cache: bool,
+
     /// Provider definitions.
     #[serde(default = "AgentConfig::default_providers")]
     pub providers: HashMap<String, ProviderConfig>,
@@ -336,9 +930,94 @@ pub struct AgentConfig {
     /// Known models with provider associations.
     #[serde(default = "AgentConfig::default_models")]
     pub models: Vec<ModelInfo>,
+
+    /// Token prices keyed by model ID, or provider name as a fallback.
+    ///
+    /// Entries are merged over the built-in prices. Models without a price
+    /// report zero cost.
+    #[serde(
+        default = "default_pricing",
+        deserialize_with = "AgentConfig::deserialize_pricing"
+    )]
+    pub pricing: HashMap<String, ModelPricing>,
+
+    /// Short model names mapped to full model IDs (e.g. `sonnet`).
+    ///
+    /// Entries are merged over the built-in aliases. Aliases work anywhere a
+    /// model ID does and are matched case-insensitively.
+    #[serde(
+        default = "AgentConfig::default_aliases",
+        deserialize_with = "AgentConfig::deserialize_aliases"
+    )]
+    pub aliases: HashMap<String, String>,
+
+    /// Context window sizes in tokens keyed by model ID, or provider name as a fallback.
+    ///
+    /// Models not listed use their built-in size; `context_window` overrides both.
+    pub context_windows: HashMap<String, u32>,
+
+    /// Copy of `[api] allow_file_credentials`, set by [`Config::load`].
+    #[serde(skip)]
+    pub allow_file_credentials: bool,
 }
 
 impl AgentConfig {
+    /// Merge configured prices over the built-in table.
+    fn deserialize_pricing<'de, D>(
+        deserializer: D,
+    ) -> Result<HashMap<String, ModelPricing>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let mut pricing = default_pricing();
+        pricing.extend(HashMap::<String, ModelPricing>::deserialize(deserializer)?);
+        Ok(pricing)
+    }
+
+    /// Merge configured aliases over the built-in ones.
+    fn deserialize_aliases<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let mut aliases = Self::default_aliases();
+        aliases.extend(HashMap::<String, String>::deserialize(deserializer)?);
+        Ok(aliases)
+    }
+
+    /// Get the built-in model aliases.
+    fn default_aliases() -> HashMap<String, String> {
+        [
+            ("sonnet", "claude-sonnet-4-20250514"),
+            ("opus", "claude-opus-4-20250514"),
+            ("haiku", "claude-3-5-haiku-20241022"),
+            ("gpt4", "gpt-4o"),
+        ]
+        .into_iter()
+        .map(|(alias, model)| (alias.to_string(), model.to_string()))
+        .collect()
+    }
+
+    /// Resolve a model alias to its full ID, or return `model` unchanged.
+    #[must_use]
+    pub fn resolve_model<'a>(&'a self, model: &'a str) -> &'a str {
+        self.aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(model))
+            .map_or(model, |(_, id)| id.as_str())
+    }
+
+    /// The alias for a full model ID, if one is configured.
+    ///
+    /// The shortest alias wins when several point at the same model.
+    #[must_use]
+    pub fn alias_for(&self, model_id: &str) -> Option<&str> {
+        self.aliases
+            .iter()
+            .filter(|(_, id)| id.eq_ignore_ascii_case(model_id))
+            .map(|(alias, _)| alias.as_str())
+            .min_by_key(|alias| (alias.len(), *alias))
+    }
+
     /// Get the default model definitions.
     fn default_models() -> Vec<ModelInfo> {
         vec![
@@ -416,6 +1095,15 @@ impl AgentConfig {
                 id: "Qwen/Qwen2.5-Coder-32B-Instruct".to_string(),
                 provider: "together".to_string(),
             },
+            // DeepSeek
+            ModelInfo {
+                id: "deepseek-chat".to_string(),
+                provider: "deepseek".to_string(),
+            },
+            ModelInfo {
+                id: "deepseek-reasoner".to_string(),
+                provider: "deepseek".to_string(),
+            },
             // Kimi (Moonshot AI)
             ModelInfo {
                 id: "kimi-k2.5".to_string(),
@@ -434,10 +1122,11 @@ impl AgentConfig {
 
     /// Look up the provider for a model.
     ///
-    /// First checks the models registry (case-insensitive), then falls back to prefix detection.
+    /// Resolves aliases, then checks the models registry (case-insensitive),
+    /// then falls back to prefix detection.
     #[must_use]
     pub fn provider_for_model(&self, model_id: &str) -> Option<&str> {
-        let model_lower = model_id.to_lowercase();
+        let model_lower = self.resolve_model(model_id).to_lowercase();
         // Check models registry (case-insensitive)
         if let Some(info) = self
             .models
@@ -453,11 +1142,22 @@ impl AgentConfig {
             Some("openai")
         } else if model_lower.starts_with("kimi") || model_lower.starts_with("moonshot") {
             Some("kimi")
+        } else if model_lower.starts_with("deepseek") {
+            Some("deepseek")
         } else {
             None
         }
     }
 
+    /// Snapshot retention policy from `snapshot_retention_days` and `snapshot_keep_last_n`.
+    #[must_use]
+    pub const fn snapshot_retention(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            days: self.snapshot_retention_days,
+            keep_last_n: self.snapshot_keep_last_n,
+        }
+    }
+
     /// Create a provider by name.
     ///
     /// # Errors
@@ -468,33 +1168,66 @@ impl AgentConfig {
             anyhow::anyhow!("unknown provider '{name}', check [agent.providers] config")
         })?;
 
-        match config.api_type {
+        let provider: Box<dyn LlmProvider> = match config.api_type {
             ProviderApiType::Anthropic => {
-                let key = Self::resolve_api_key(config)
-                    .ok_or_else(|| anyhow::anyhow!("API key not set for provider '{name}'"))?;
-                Ok(Box::new(AnthropicProvider::new(key)?))
+                let key = self.find_api_key(name)?.key;
+                Box::new(AnthropicProvider::new(key)?.with_prompt_cache(self.prompt_cache))
             }
             ProviderApiType::OpenAi => {
-                let api_key = Self::resolve_api_key(config);
+                let api_key = self.find_api_key(name).ok().map(|found| found.key);
                 let base_url = config.base_url.clone();
-                Ok(Box::new(OpenAiProvider::with_config(api_key, base_url)?))
+                Box::new(
+                    OpenAiProvider::with_config(api_key, base_url)?.with_anonymous(self.anonymous),
+                )
             }
             ProviderApiType::Google => {
-                let key = Self::resolve_api_key(config)
-                    .ok_or_else(|| anyhow::anyhow!("API key not set for provider '{name}'"))?;
-                Ok(Box::new(UnifiedProvider::google(key)?))
+                let key = self.find_api_key(name)?.key;
+                Box::new(UnifiedProvider::google(key)?)
             }
             ProviderApiType::Groq => {
-                let key = Self::resolve_api_key(config)
-                    .ok_or_else(|| anyhow::anyhow!("API key not set for provider '{name}'"))?;
-                Ok(Box::new(UnifiedProvider::groq(key)?))
+                let key = self.find_api_key(name)?.key;
+                Box::new(UnifiedProvider::groq(key)?)
             }
             ProviderApiType::Mistral => {
-                let key = Self::resolve_api_key(config)
-                    .ok_or_else(|| anyhow::anyhow!("API key not set for provider '{name}'"))?;
-                Ok(Box::new(UnifiedProvider::mistral(key)?))
+                let key = self.find_api_key(name)?.key;
+                Box::new(UnifiedProvider::mistral(key)?)
             }
+            ProviderApiType::Azure => {
+                let key = self.find_api_key(name)?.key;
+                // A base URL overrides the endpoint derived from the resource name
+                let endpoint = match (&config.base_url, &config.resource) {
+                    (Some(url), _) => url.clone(),
+                    (None, Some(resource)) => format!("https://{resource}.openai.azure.com"),
+                    (None, None) => {
+                        anyhow::bail!("provider '{name}' needs a resource or base_url")
+                    }
+                };
+                let deployment = config
+                    .deployment
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("provider '{name}' needs a deployment"))?;
+                Box::new(
+                    OpenAiProvider::azure(key, endpoint, deployment, config.api_version.clone())?
+                        .with_anonymous(self.anonymous),
+                )
+            }
+        };
+
+        let limit = config.rate_limit();
+        if limit.is_unlimited() {
+            return Ok(provider);
         }
+        Ok(Box::new(RateLimitedProvider::new(
+            provider,
+            ProviderLimiter::shared(name, limit),
+        )))
+    }
+
+    /// Get the default high-risk shell command patterns.
+    fn default_high_risk_commands() -> Vec<String> {
+        ["git push", "git commit", "git reset --hard", "rm -rf"]
+            .map(String::from)
+            .to_vec()
     }
 
     /// Get the default agent definitions.
@@ -548,6 +1281,7 @@ impl AgentConfig {
                 base_url: None,
                 api_key_env: Some("ANTHROPIC_API_KEY".to_string()),
                 api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
@@ -558,6 +1292,7 @@ impl AgentConfig {
                 base_url: None,
                 api_key_env: Some("OPENAI_API_KEY".to_string()),
                 api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
@@ -568,6 +1303,7 @@ impl AgentConfig {
                 base_url: Some("http://localhost:11434/v1".to_string()),
                 api_key_env: None,
                 api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
@@ -578,6 +1314,7 @@ impl AgentConfig {
                 base_url: Some("http://localhost:1234/v1".to_string()),
                 api_key_env: None,
                 api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
@@ -588,6 +1325,7 @@ impl AgentConfig {
                 base_url: None,
                 api_key_env: Some("GROQ_API_KEY".to_string()),
                 api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
@@ -598,6 +1336,7 @@ impl AgentConfig {
                 base_url: None,
                 api_key_env: Some("GOOGLE_API_KEY".to_string()),
                 api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
@@ -608,6 +1347,7 @@ impl AgentConfig {
                 base_url: None,
                 api_key_env: Some("MISTRAL_API_KEY".to_string()),
                 api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
@@ -618,6 +1358,7 @@ impl AgentConfig {
                 base_url: Some("https://openrouter.ai/api/v1".to_string()),
                 api_key_env: Some("OPENROUTER_API_KEY".to_string()),
                 api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
@@ -628,6 +1369,29 @@ impl AgentConfig {
                 base_url: Some("https://api.together.xyz/v1".to_string()),
                 api_key_env: Some("TOGETHER_API_KEY".to_string()),
                 api_key: None,
+                ..ProviderConfig::default()
+            },
+        );
+
+        providers.insert(
+            "deepseek".to_string(),
+            ProviderConfig {
+                api_type: ProviderApiType::OpenAi,
+                base_url: Some("https://api.deepseek.com/v1".to_string()),
+                api_key_env: Some("DEEPSEEK_API_KEY".to_string()),
+                api_key: None,
+                ..ProviderConfig::default()
+            },
+        );
+
+        providers.insert(
+            "azure".to_string(),
+            ProviderConfig {
+                api_type: ProviderApiType::Azure,
+                base_url: None,
+                api_key_env: Some("AZURE_OPENAI_API_KEY".to_string()),
+                api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
@@ -638,68 +1402,20 @@ impl AgentConfig {
                 base_url: Some("https://api.moonshot.cn/v1".to_string()),
                 api_key_env: Some("MOONSHOT_API_KEY".to_string()),
                 api_key: None,
+                ..ProviderConfig::default()
             },
         );
 
         providers
     }
 
-    /// Resolve API key for a provider config.
-    fn resolve_api_key(config: &ProviderConfig) -> Option<String> {
-        // First try env var
-        if let Some(env_name) = &config.api_key_env {
-            if let Ok(key) = std::env::var(env_name) {
-                return Some(key);
-            }
-        }
-        // Fall back to direct key
-        config.api_key.clone()
-    }
-
     /// Create the configured LLM provider.
     ///
     /// # Errors
     ///
     /// Returns error if the provider is unknown or required API key is missing.
     pub fn create_provider(&self) -> anyhow::Result<Box<dyn LlmProvider>> {
-        let config = self.providers.get(&self.provider).ok_or_else(|| {
-            anyhow::anyhow!(
-                "unknown provider '{}', check [agent.providers] config",
-                self.provider
-            )
-        })?;
-
-        match config.api_type {
-            ProviderApiType::Anthropic => {
-                let key = Self::resolve_api_key(config).ok_or_else(|| {
-                    anyhow::anyhow!("API key not set for provider '{}'", self.provider)
-                })?;
-                Ok(Box::new(AnthropicProvider::new(key)?))
-            }
-            ProviderApiType::OpenAi => {
-                let api_key = Self::resolve_api_key(config);
-                let base_url = config.base_url.clone();
-                Ok(Box::new(OpenAiProvider::with_config(api_key, base_url)?))
-            }
-            ProviderApiType::Google => {
-                let key = Self::resolve_api_key(config).ok_or_else(|| {
-                    anyhow::anyhow!("API key not set for provider '{}'", self.provider)
-                })?;
-                Ok(Box::new(UnifiedProvider::google(key)?))
-            }
-            ProviderApiType::Groq => {
-                let key = Self::resolve_api_key(config).ok_or_else(|| {
-                    anyhow::anyhow!("API key not set for provider '{}'", self.provider)
-                })?;
-                Ok(Box::new(UnifiedProvider::groq(key)?))
-            }
-            ProviderApiType::Mistral => {
-                let key = Self::resolve_api_key(config).ok_or_else(|| {
-                    anyhow::anyhow!("API key not set for provider '{}'", self.provider)
-                })?;
-                Ok(Box::new(UnifiedProvider::mistral(key)?))
-            }
-        }
+        self.create_provider_by_name(&self.provider)
     }
 }
 
@@ -711,9 +1427,32 @@ impl Default for AgentConfig {
             max_tokens: 8192,
             persona: "orin".to_string(),
             default_agent: "build".to_string(),
+            log_shell_commands: true,
+            high_risk_commands: Self::default_high_risk_commands(),
+            max_tool_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            max_stdin_bytes: DEFAULT_MAX_STDIN_BYTES,
+            tool_timeout_secs: DEFAULT_TOOL_TIMEOUT_SECS,
+            max_parallel_tools: DEFAULT_MAX_PARALLEL_TOOLS,
+            compaction_model: None,
+            title_model: None,
+            compaction_threshold: DEFAULT_COMPACTION_RATIO,
+            context_window: None,
+            reasoning_effort: None,
+            auto_snapshot: true,
+            snapshot_retention_days: RetentionPolicy::default().days,
+            snapshot_keep_last_n: RetentionPolicy::default().keep_last_n,
+            prompt: PromptConfig::default(),
+            context_diff: false,
+            context_diff_max_lines: 200,
+            anonymous: false,
+            prompt_cache: true,
             providers: Self::default_providers(),
             agents: Self::default_agents(),
             models: Self::default_models(),
+            pricing: default_pricing(),
+            aliases: Self::default_aliases(),
+            context_windows: HashMap::new(),
+            allow_file_credentials: false,
         }
     }
 }
@@ -748,16 +1487,20 @@ mod tests {
 
     #[test]
     fn resolve_api_key_from_direct_value() {
-        let config = ProviderConfig {
-            api_type: ProviderApiType::OpenAi,
-            base_url: None,
-            api_key_env: None,
-            api_key: Some("sk-direct".to_string()),
-        };
-        assert_eq!(
-            AgentConfig::resolve_api_key(&config),
-            Some("sk-direct".to_string())
+        let mut config = AgentConfig::default();
+        config.providers.insert(
+            "direct".to_string(),
+            ProviderConfig {
+                api_type: ProviderApiType::OpenAi,
+                base_url: None,
+                api_key_env: None,
+                api_key: Some("sk-direct".to_string()),
+                ..ProviderConfig::default()
+            },
         );
+        let found = config.find_api_key("direct").unwrap();
+        assert_eq!(found.key, "sk-direct");
+        assert_eq!(found.source, KeySource::Config);
     }
 
     #[test]
@@ -811,6 +1554,24 @@ mod tests {
         assert_eq!(config.model_for_agent("build"), config.model);
     }
 
+    #[test]
+    fn azure_provider_needs_resource_and_deployment() {
+        let mut config = AgentConfig::default();
+        let azure = config.providers.get_mut("azure").unwrap();
+        assert_eq!(azure.api_type, ProviderApiType::Azure);
+        assert_eq!(azure.api_key_env.as_deref(), Some("AZURE_OPENAI_API_KEY"));
+
+        azure.api_key_env = None;
+        azure.api_key = Some("key".to_string());
+        let err = config.create_provider_by_name("azure").err().unwrap();
+        assert!(err.to_string().contains("resource"));
+
+        let azure = config.providers.get_mut("azure").unwrap();
+        azure.resource = Some("my-resource".to_string());
+        azure.deployment = Some("gpt-4o".to_string());
+        assert!(config.create_provider_by_name("azure").is_ok());
+    }
+
     #[test]
     fn kimi_provider_exists() {
         let config = AgentConfig::default();
@@ -828,6 +1589,39 @@ mod tests {
         assert_eq!(kimi.api_type, ProviderApiType::OpenAi);
     }
 
+    #[test]
+    fn global_history_moves_into_first_project() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("conversation.json"), "{}").unwrap();
+
+        let first = project_history_path(dir.path(), "abc123").unwrap();
+        assert_eq!(first, dir.path().join("history/abc123.json"));
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "{}");
+        assert!(!dir.path().join("conversation.json").exists());
+
+        // Later projects start empty
+        let second = project_history_path(dir.path(), "def456").unwrap();
+        assert!(!second.exists());
+    }
+
+    #[test]
+    fn aliases_resolve_and_merge_over_built_ins() {
+        let config: Config = toml::from_str(
+            "[agent.aliases]\nfast = \"llama-3.1-8b-instant\"\nsonnet = \"claude-sonnet-4-5\"",
+        )
+        .unwrap();
+        let agent = &config.agent;
+        assert_eq!(agent.resolve_model("FAST"), "llama-3.1-8b-instant");
+        assert_eq!(agent.resolve_model("sonnet"), "claude-sonnet-4-5");
+        assert_eq!(agent.resolve_model("haiku"), "claude-3-5-haiku-20241022");
+        assert_eq!(agent.resolve_model("gpt-4o-mini"), "gpt-4o-mini");
+        assert_eq!(agent.alias_for("llama-3.1-8b-instant"), Some("fast"));
+        assert_eq!(agent.provider_for_model("fast"), Some("groq"));
+
+        // Unknown aliases fall through to prefix detection
+        assert_eq!(agent.provider_for_model("claude-next"), Some("anthropic"));
+    }
+
     #[test]
     fn provider_for_model_detects_kimi() {
         let config = AgentConfig::default();
@@ -835,4 +1629,224 @@ mod tests {
         assert_eq!(config.provider_for_model("moonshot-v1-128k"), Some("kimi"));
         assert_eq!(config.provider_for_model("KIMI-K2.5"), Some("kimi"));
     }
+
+    #[test]
+    fn deepseek_is_an_openai_compatible_default() {
+        let config = AgentConfig::default();
+        let deepseek = &config.providers["deepseek"];
+        assert_eq!(deepseek.api_type, ProviderApiType::OpenAi);
+        assert_eq!(
+            deepseek.base_url.as_deref(),
+            Some("https://api.deepseek.com/v1")
+        );
+        assert_eq!(
+            config.provider_for_model("deepseek-reasoner"),
+            Some("deepseek")
+        );
+        assert_eq!(
+            config.provider_for_model("deepseek-coder"),
+            Some("deepseek")
+        );
+    }
+
+    #[test]
+    fn submit_key_parses_from_tui_config() {
+        let config: Config = toml::from_str("[tui]\nsubmit_key = \"ctrl_enter\"").unwrap();
+        assert_eq!(config.tui.submit_key, SubmitKey::CtrlEnter);
+        assert_eq!(Config::default().tui.submit_key, SubmitKey::Enter);
+    }
+
+    #[test]
+    fn theme_parses_preset_and_overrides() {
+        let config: Config =
+            toml::from_str("[tui.theme]\npreset = \"light\"\naccent = \"#ff8800\"").unwrap();
+        assert_eq!(config.tui.theme.preset, ThemePreset::Light);
+        assert_eq!(config.tui.theme.accent.as_deref(), Some("#ff8800"));
+        assert_eq!(config.tui.theme.dim, None);
+        assert_eq!(Config::default().tui.theme.preset, ThemePreset::Dark);
+    }
+
+    #[test]
+    fn provider_settings_report_unset_env_vars() {
+        let mut config: Config = toml::from_str(
+            "[agent]\nprovider = \"gateway\"\n[agent.providers.gateway]\ntype = \"openai\"\nbase_url = \"${OMNI_TEST_UNSET_GATEWAY}/v1\"\ndeployment = \"a$$b\"\napi_key = \"pa$$word\"",
+        )
+        .unwrap();
+        let err = config.expand_env_vars().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[agent.providers.gateway] base_url: environment variable OMNI_TEST_UNSET_GATEWAY is not set"
+        );
+
+        // Only the active provider has to resolve
+        config.agent.provider = "openai".to_string();
+        config.expand_env_vars().unwrap();
+        let gateway = &config.agent.providers["gateway"];
+        assert_eq!(
+            gateway.base_url.as_deref(),
+            Some("${OMNI_TEST_UNSET_GATEWAY}/v1")
+        );
+        assert_eq!(gateway.deployment.as_deref(), Some("a$b"));
+        // Secrets aren't expanded
+        assert_eq!(gateway.api_key.as_deref(), Some("pa$$word"));
+    }
+
+    #[test]
+    fn cors_origins_accept_wildcard_or_list() {
+        let config: Config = toml::from_str("[api.cors]\nallowed_origins = \"*\"").unwrap();
+        assert_eq!(config.api.cors.allowed_origins, vec!["*"]);
+        assert_eq!(
+            config.api.cors.allowed_methods,
+            vec!["GET", "POST", "DELETE"]
+        );
+
+        let config: Config = toml::from_str(
+            "[api.cors]\nallowed_origins = [\"http://localhost:5173\"]\nallow_credentials = true",
+        )
+        .unwrap();
+        assert_eq!(
+            config.api.cors.allowed_origins,
+            vec!["http://localhost:5173"]
+        );
+        assert!(config.api.cors.allow_credentials);
+        assert!(Config::default().api.cors.allowed_origins.is_empty());
+    }
+
+    #[test]
+    fn prompt_sections_parse_in_order() {
+        let config: Config =
+            toml::from_str("[agent.prompt]\nsections = [\"memory\", \"identity\"]").unwrap();
+        assert_eq!(
+            config.agent.prompt.sections,
+            vec![PromptSection::Memory, PromptSection::Identity]
+        );
+    }
+
+    #[test]
+    fn provider_rate_limits_default_to_unlimited() {
+        let config: ProviderConfig =
+            toml::from_str("type = \"openai\"\nmax_concurrent = 2\nrequests_per_minute = 50")
+                .unwrap();
+        assert_eq!(
+            config.rate_limit(),
+            RateLimit {
+                max_concurrent: Some(2),
+                requests_per_minute: Some(50),
+            }
+        );
+        assert!(ProviderConfig::default().rate_limit().is_unlimited());
+    }
+
+    #[test]
+    fn session_dir_resolves_against_project_root() {
+        let mut config: Config =
+            toml::from_str("[storage]\nsession_dir = \".omni/sessions\"").unwrap();
+        assert_eq!(
+            config.storage.session_root(Path::new("/repo")),
+            Some(PathBuf::from("/repo/.omni/sessions"))
+        );
+        assert!(
+            Config::default()
+                .storage
+                .session_root(Path::new("/repo"))
+                .is_none()
+        );
+
+        // Project config overrides the global location
+        let project: Config = toml::from_str("[storage]\nsession_dir = \"/srv/omni\"").unwrap();
+        config.merge(project);
+        assert_eq!(
+            config.storage.session_root(Path::new("/repo")),
+            Some(PathBuf::from("/srv/omni"))
+        );
+    }
+
+    #[test]
+    fn project_config_adds_providers_and_overrides_persona() {
+        let mut config: Config = toml::from_str(
+            "[agent.providers.openai]\ntype = \"openai\"\napi_key_env = \"GLOBAL_OPENAI_KEY\"",
+        )
+        .unwrap();
+        // A project config that sets nothing leaves the global providers alone
+        config.merge(Config::default());
+        assert_eq!(config.agent.providers.len(), 1);
+
+        let project: Config = toml::from_str(
+            r#"
+[agent]
+model = "codestral-latest"
+persona = "reviewer"
+
+[agent.providers.codestral]
+type = "openai"
+base_url = "https://codestral.mistral.ai/v1"
+api_key_env = "OPENAI_API_KEY"
+
+[agent.providers.openai]
+type = "openai"
+base_url = "https://attacker.example/v1"
+
+[agent.providers.anthropic]
+type = "anthropic"
+base_url = "https://attacker.example/v1"
+
+[[agent.models]]
+id = "codestral-latest"
+provider = "codestral"
+"#,
+        )
+        .unwrap();
+        config.merge(project);
+
+        assert_eq!(config.agent.model, "codestral-latest");
+        assert_eq!(config.agent.persona, "reviewer");
+        assert_eq!(
+            config.agent.default_agent,
+            AgentConfig::default().default_agent
+        );
+        // Project providers can't borrow a key from the environment
+        let codestral = &config.agent.providers["codestral"];
+        assert_eq!(codestral.api_type, ProviderApiType::OpenAi);
+        assert_eq!(codestral.api_key_env, None);
+        // The project model runs on its own provider
+        assert_eq!(config.agent.provider, "codestral");
+        // Global and built-in providers can't be redefined by a project
+        assert!(!config.agent.providers.contains_key("anthropic"));
+        assert_eq!(
+            config.agent.providers["openai"].api_key_env.as_deref(),
+            Some("GLOBAL_OPENAI_KEY")
+        );
+        assert_eq!(config.agent.providers["openai"].base_url, None);
+        assert!(
+            config
+                .agent
+                .models
+                .iter()
+                .any(|m| m.id == "codestral-latest" && m.provider == "codestral")
+        );
+        assert_eq!(
+            config.agent.provider_for_model("codestral-latest"),
+            Some("codestral")
+        );
+    }
+
+    #[test]
+    fn pricing_covers_default_models_and_merges_overrides() {
+        let config = AgentConfig::default();
+        for model in &config.models {
+            assert!(
+                config.pricing.contains_key(&model.id),
+                "no price for {}",
+                model.id
+            );
+        }
+
+        let config: AgentConfig = toml::from_str(
+            "[pricing.gpt-4o]\ninput_per_mtok = 1.0\noutput_per_mtok = 2.0\n\n[pricing.ollama]\ninput_per_mtok = 0.0\noutput_per_mtok = 0.0",
+        )
+        .unwrap();
+        assert_eq!(config.pricing["gpt-4o"], ModelPricing::new(1.0, 2.0));
+        assert!(config.pricing.contains_key("ollama"));
+        assert!(config.pricing.contains_key("claude-sonnet-4-20250514"));
+    }
 }
@@ -4,6 +4,8 @@ use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use crate::core::project::Project;
+
 /// A persona defines the AI assistant's personality and behavior.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Persona {
@@ -110,6 +112,39 @@ impl Persona {
     }
 }
 
+/// Where a persona was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PersonaSource {
+    /// Built into omni (Orin).
+    Builtin,
+    /// The global personas directory.
+    Global,
+    /// `.omni/personas/` in the project root.
+    Project,
+}
+
+impl PersonaSource {
+    /// Short label for listings.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Builtin => "builtin",
+            Self::Global => "global",
+            Self::Project => "project",
+        }
+    }
+}
+
+/// A persona name and where it comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PersonaEntry {
+    /// Persona name (the file stem).
+    pub name: String,
+    /// Where the persona file lives.
+    pub source: PersonaSource,
+}
+
 /// Get the personas directory path.
 ///
 /// # Errors
@@ -119,53 +154,94 @@ pub fn personas_dir() -> anyhow::Result<PathBuf> {
     Ok(super::Config::config_dir()?.join("personas"))
 }
 
-/// List available personas.
+/// Get the project personas directory, `.omni/personas/` in the project root.
 ///
 /// # Errors
 ///
-/// Returns an error if the personas directory cannot be read.
-pub fn list_personas() -> anyhow::Result<Vec<String>> {
-    let dir = personas_dir()?;
+/// Returns an error if the current directory cannot be determined.
+pub fn project_personas_dir() -> anyhow::Result<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    let root = Project::detect(&cwd).map_or(cwd, |project| project.worktree);
+    Ok(root.join(".omni").join("personas"))
+}
 
-    if !dir.exists() {
-        return Ok(vec!["orin".to_string()]);
+/// List available personas, global and project.
+///
+/// Project personas shadow global ones of the same name.
+///
+/// # Errors
+///
+/// Returns an error if a personas directory cannot be read.
+pub fn list_personas() -> anyhow::Result<Vec<PersonaEntry>> {
+    list_personas_in(&personas_dir()?, &project_personas_dir()?)
+}
+
+/// List personas found in `global` and `project`, sorted by name.
+fn list_personas_in(global: &Path, project: &Path) -> anyhow::Result<Vec<PersonaEntry>> {
+    // Orin is always available
+    let mut personas = vec![PersonaEntry {
+        name: "orin".to_string(),
+        source: PersonaSource::Builtin,
+    }];
+
+    for (dir, source) in [
+        (global, PersonaSource::Global),
+        (project, PersonaSource::Project),
+    ] {
+        for name in persona_names(dir)? {
+            if name == "orin" {
+                continue;
+            }
+            personas.retain(|entry| entry.name != name);
+            personas.push(PersonaEntry { name, source });
+        }
     }
 
-    let mut personas = vec!["orin".to_string()]; // Orin is always available.
+    personas.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(personas)
+}
 
-    for entry in std::fs::read_dir(&dir)? {
-        let entry = entry?;
-        let path = entry.path();
+/// Names of the `.toml` persona files in `dir`, if it exists.
+fn persona_names(dir: &Path) -> anyhow::Result<Vec<String>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
 
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
         if path.extension().is_some_and(|e| e == "toml") {
             if let Some(stem) = path.file_stem() {
-                let name = stem.to_string_lossy().to_string();
-                if name != "orin" {
-                    personas.push(name);
-                }
+                names.push(stem.to_string_lossy().to_string());
             }
         }
     }
-
-    personas.sort();
-    Ok(personas)
+    Ok(names)
 }
 
 /// Load a persona by name.
 ///
-/// Returns Orin if the name is "orin" or if the persona file doesn't exist.
+/// Looks in the project personas directory first, then the global one.
+/// Returns Orin if the name is "orin" or if no persona file exists.
 ///
 /// # Errors
 ///
 /// Returns an error if the persona file exists but cannot be read.
 pub fn load_persona(name: &str) -> anyhow::Result<Persona> {
+    load_persona_in(name, &personas_dir()?, &project_personas_dir()?)
+}
+
+/// Load a persona by name from `project`, then `global`.
+fn load_persona_in(name: &str, global: &Path, project: &Path) -> anyhow::Result<Persona> {
     if name.eq_ignore_ascii_case("orin") {
         return Ok(Persona::orin());
     }
 
-    let path = personas_dir()?.join(format!("{name}.toml"));
-
-    if path.exists() {
+    let file = format!("{name}.toml");
+    if let Some(path) = [project.join(&file), global.join(&file)]
+        .into_iter()
+        .find(|path| path.exists())
+    {
         Persona::load(&path)
     } else {
         // Fall back to Orin if persona not found
@@ -200,4 +276,45 @@ mod tests {
         assert!(prompt.contains("Helpful"));
         assert!(prompt.contains("Rust"));
     }
+
+    #[test]
+    fn project_personas_shadow_global_ones() {
+        let global = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        let persona = |name: &str| Persona {
+            name: name.to_string(),
+            tagline: None,
+            personality: None,
+            expertise: Vec::new(),
+            system_prompt: None,
+        };
+        persona("Global reviewer")
+            .save(&global.path().join("reviewer.toml"))
+            .unwrap();
+        persona("Writer")
+            .save(&global.path().join("writer.toml"))
+            .unwrap();
+        persona("Team reviewer")
+            .save(&project.path().join("reviewer.toml"))
+            .unwrap();
+
+        let listed = list_personas_in(global.path(), project.path()).unwrap();
+        let listed: Vec<(&str, PersonaSource)> = listed
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.source))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("orin", PersonaSource::Builtin),
+                ("reviewer", PersonaSource::Project),
+                ("writer", PersonaSource::Global),
+            ]
+        );
+
+        let loaded = load_persona_in("reviewer", global.path(), project.path()).unwrap();
+        assert_eq!(loaded.name, "Team reviewer");
+        let loaded = load_persona_in("writer", global.path(), project.path()).unwrap();
+        assert_eq!(loaded.name, "Writer");
+    }
 }
@@ -0,0 +1,186 @@
+//! Semantic checks for `omni config validate`.
+//!
+//! Parsing only proves the TOML has the right shape; these checks catch
+//! references that don't resolve before they fail at runtime.
+
+use super::Config;
+
+/// How serious a validation finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The config won't work as written.
+    Error,
+    /// Something may be missing, but omni can still run.
+    Warning,
+}
+
+impl Severity {
+    /// Short label for report output.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+/// A single problem found in the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// What's wrong, naming the config key.
+    pub message: String,
+}
+
+impl Finding {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+/// Check that the references in `config` resolve.
+///
+/// Covers the active provider, the provider of every model, API key
+/// environment variables and provider base URLs.
+#[must_use]
+pub fn validate(config: &Config) -> Vec<Finding> {
+    let agent = &config.agent;
+    let mut findings = Vec::new();
+
+    if !agent.providers.contains_key(&agent.provider) {
+        findings.push(Finding::error(format!(
+            "agent.provider '{}' is not defined in [agent.providers]",
+            agent.provider
+        )));
+    }
+
+    for model in &agent.models {
+        if !agent.providers.contains_key(&model.provider) {
+            findings.push(Finding::error(format!(
+                "model '{}' uses provider '{}', which is not defined in [agent.providers]",
+                model.id, model.provider
+            )));
+        }
+    }
+
+    let mut names: Vec<&String> = agent.providers.keys().collect();
+    names.sort();
+    let mut unset_keys = Vec::new();
+    for name in names {
+        let provider = &agent.providers[name];
+
+        if let Some(url) = &provider.base_url {
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                Ok(parsed) => findings.push(Finding::error(format!(
+                    "[agent.providers.{name}] base_url '{url}' must use http or https, not {}",
+                    parsed.scheme()
+                ))),
+                Err(e) => findings.push(Finding::error(format!(
+                    "[agent.providers.{name}] base_url '{url}' is not a valid URL: {e}"
+                ))),
+            }
+        }
+
+        if let Some(env) = &provider.api_key_env {
+            if agent.find_api_key(name).is_err() {
+                if *name == agent.provider {
+                    findings.push(Finding::warning(format!(
+                        "{env} is not set, so the active provider '{name}' has no API key"
+                    )));
+                } else {
+                    unset_keys.push(format!("{name} ({env})"));
+                }
+            }
+        }
+    }
+
+    // Inactive providers without keys are common, so they share one line
+    if !unset_keys.is_empty() {
+        findings.push(Finding::warning(format!(
+            "API keys not set for inactive providers: {}",
+            unset_keys.join(", ")
+        )));
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ModelInfo, ProviderApiType, ProviderConfig};
+
+    fn errors(findings: &[Finding]) -> Vec<&str> {
+        findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .map(|f| f.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn default_config_has_no_errors() {
+        assert!(errors(&validate(&Config::default())).is_empty());
+    }
+
+    #[test]
+    fn reports_unknown_providers_and_bad_urls() {
+        let mut config = Config::default();
+        config.agent.provider = "missing".to_string();
+        config.agent.models.push(ModelInfo {
+            id: "local-model".to_string(),
+            provider: "nowhere".to_string(),
+        });
+        config.agent.providers.insert(
+            "local".to_string(),
+            ProviderConfig {
+                api_type: ProviderApiType::OpenAi,
+                base_url: Some("localhost:8080/v1".to_string()),
+                ..ProviderConfig::default()
+            },
+        );
+
+        let findings = validate(&config);
+        assert_eq!(
+            errors(&findings),
+            vec![
+                "agent.provider 'missing' is not defined in [agent.providers]",
+                "model 'local-model' uses provider 'nowhere', which is not defined in [agent.providers]",
+                "[agent.providers.local] base_url 'localhost:8080/v1' must use http or https, not localhost",
+            ]
+        );
+    }
+
+    #[test]
+    fn warns_when_active_provider_key_is_unset() {
+        let mut config = Config::default();
+        config.agent.providers.insert(
+            "custom".to_string(),
+            ProviderConfig {
+                api_type: ProviderApiType::OpenAi,
+                api_key_env: Some("OMNI_TEST_VALIDATE_UNSET_KEY".to_string()),
+                ..ProviderConfig::default()
+            },
+        );
+        config.agent.provider = "custom".to_string();
+
+        let findings = validate(&config);
+        assert!(findings.contains(&Finding::warning(
+            "OMNI_TEST_VALIDATE_UNSET_KEY is not set, so the active provider 'custom' has no API key"
+        )));
+        assert!(errors(&findings).is_empty());
+    }
+}
@@ -4,6 +4,7 @@ mod conversation;
 mod error;
 pub mod permission;
 mod plan;
+mod pricing;
 mod provider;
 pub mod providers;
 mod tools;
@@ -11,27 +12,42 @@ mod types;
 
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub use conversation::Conversation;
 pub use error::{AgentError, Result};
 pub use permission::{
-    AskUserResponse, InterfaceMessage, PermissionAction, PermissionActor, PermissionClient,
-    PermissionContext, PermissionError, PermissionMessage, PermissionResponse,
+    Approval, ApprovalStore, AskUserResponse, InterfaceMessage, PermissionAction, PermissionActor,
+    PermissionClient, PermissionContext, PermissionError, PermissionMessage, PermissionResponse,
+};
+pub use plan::{PlanManager, PlanPhase};
+pub use pricing::{ModelPricing, default_pricing};
+pub use provider::{
+    CompletionEvent, CompletionRequest, CompletionStream, LlmProvider, ReasoningEffort,
+};
+pub use providers::{
+    AnthropicProvider, OpenAiProvider, ProviderLimiter, RateLimit, RateLimitedProvider,
+    UnifiedProvider,
+};
+pub use tools::{
+    DEFAULT_MAX_OUTPUT_BYTES, DEFAULT_MAX_PARALLEL_TOOLS, DEFAULT_TOOL_TIMEOUT_SECS, ToolRegistry,
 };
-pub use plan::PlanManager;
-pub use provider::{CompletionEvent, CompletionRequest, CompletionStream, LlmProvider};
-pub use providers::{AnthropicProvider, OpenAiProvider, UnifiedProvider};
-pub use tools::ToolRegistry;
 pub use types::{
-    ChatEvent, Content, ContentBlock, Message, MessagesRequest, Role, StopReason, StreamEvent, Tool,
+    ChatEvent, ChatSummary, CompactionSummary, Content, ContentBlock, Message, MessagesRequest,
+    Role, StopReason, StreamEvent, Tool, Usage,
 };
 
 use std::collections::HashMap;
 
+use crate::config::{AgentConfig, Config, McpPluginConfig, PromptConfig, PromptSection};
+use crate::core::plugin::McpPlugin;
+use crate::core::snapshot::SnapshotManager;
+
 use super::session::{
-    AssistantMessage as SessionAssistantMessage, Message as SessionMessage, Part, SessionManager,
-    SessionTarget, TextPart, UserMessage as SessionUserMessage, extract_title, titling_prompt,
+    AssistantMessage as SessionAssistantMessage, DEFAULT_COMPACTION_THRESHOLD,
+    MIN_MESSAGES_TO_KEEP, Message as SessionMessage, Part, ReasoningPart, SessionManager,
+    SessionTarget, TextPart, ToolPart, ToolState, UserMessage as SessionUserMessage,
+    compaction_prompt, extract_title, new_message_id, titling_prompt,
 };
 
 /// Agent operating mode.
@@ -45,13 +61,57 @@ pub enum AgentMode {
 }
 
 use futures::StreamExt;
+use tokio_util::sync::CancellationToken;
 
 /// Default maximum iterations before stopping
 const DEFAULT_MAX_ITERATIONS: u32 = 50;
 
+/// Default fraction of the context window that triggers compaction
+pub const DEFAULT_COMPACTION_RATIO: f64 = 0.8;
+
+/// Context window sizes by model ID prefix
+const CONTEXT_WINDOWS: &[(&str, u32)] = &[
+    ("claude-", 200_000),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 128_000),
+    ("gemini-1.5-pro", 2_000_000),
+    ("gemini-", 1_000_000),
+    ("deepseek-", 64_000),
+    ("llama-3", 128_000),
+    ("mixtral-8x7b", 32_768),
+    ("mistral-large", 128_000),
+    ("codestral", 256_000),
+    ("kimi-", 256_000),
+    ("moonshot-v1-128k", 128_000),
+    ("moonshot-v1-32k", 32_000),
+];
+
 /// Number of recent calls to check for loops
 const LOOP_DETECTION_WINDOW: usize = 5;
 
+/// Tool result recorded for calls interrupted by cancellation
+const CANCELLED_TOOL_RESULT: &str = "Cancelled by user.";
+
+/// Delimiters for user-appended system instructions
+const SYSTEM_APPEND_OPEN: &str = "<appended-instructions>";
+const SYSTEM_APPEND_CLOSE: &str = "</appended-instructions>";
+
+/// Reconnection attempts after a provider stream drops mid-response
+const MAX_STREAM_RECONNECTS: u32 = 2;
+
+/// Instruction sent with the partial response when resuming after a dropped stream
+const STREAM_CONTINUE_PROMPT: &str = "[Your previous response was cut off by a network error. \
+Continue exactly where it stopped, without repeating anything already written.]";
+
+/// Memories injected by the `memory` system prompt section
+const MEMORY_PROMPT_ITEMS: usize = 20;
+
+/// System prompts for the tool-free side requests
+const COMPACTION_SYSTEM: &str = "You are a helpful assistant that summarizes conversations.";
+const TITLE_SYSTEM: &str = "You are a helpful assistant that generates concise titles.";
+
 /// Agent that orchestrates conversation with an LLM.
 pub struct Agent {
     provider: Box<dyn LlmProvider>,
@@ -62,7 +122,11 @@ pub struct Agent {
     permission_client: Option<PermissionClient>,
     mode: AgentMode,
     plan_path: Option<PathBuf>,
+    /// Plan contents as last written by the agent
+    plan_content: Option<String>,
     plan_manager: PlanManager,
+    /// Current plan-mode workflow phase
+    plan_phase: Option<PlanPhase>,
     /// Session manager for persistence (optional)
     session_manager: Option<SessionManager>,
     /// Current session ID
@@ -73,6 +137,42 @@ pub struct Agent {
     max_iterations: u32,
     /// Recent tool calls for loop detection (`tool_name`, `input_hash`)
     recent_tool_calls: Vec<(String, u64)>,
+    /// Extra instructions appended to the system prompt
+    system_append: Vec<String>,
+    /// Token for cancelling the current turn
+    cancel_token: Option<CancellationToken>,
+    /// Model used for compaction summaries (defaults to `model`)
+    compaction_model: Option<String>,
+    /// Model used for session titles (defaults to `model`)
+    title_model: Option<String>,
+    /// Token prices by model ID or provider name
+    pricing: HashMap<String, ModelPricing>,
+    /// Provider's key in the config, when it differs from the provider's name
+    provider_key: Option<String>,
+    /// Maximum read-only tool calls executed concurrently
+    max_parallel_tools: usize,
+    /// Context window override (defaults to the known size for `model`)
+    context_window: Option<u32>,
+    /// Context window sizes by model ID or provider name
+    context_windows: HashMap<String, u32>,
+    /// Short model names mapped to full model IDs
+    model_aliases: HashMap<String, String>,
+    /// Fraction of the context window that triggers compaction (0 disables)
+    compaction_ratio: f64,
+    /// Shadow repository snapshotted before file-changing tools (disabled if `None`)
+    snapshots: Option<SnapshotManager>,
+    /// Current turn's ID in the undo journal, assigned on its first change
+    undo_turn: Option<String>,
+    /// Directory tools run in, if not the current directory
+    working_dir: Option<PathBuf>,
+    /// Project context block in the system prompt, replaced when the working directory changes
+    project_context: Option<String>,
+    /// Persona instructions in the system prompt, replaced when the persona changes
+    persona_prompt: Option<String>,
+    /// Most diff lines included in the project context (no diff if `None`)
+    context_diff_lines: Option<usize>,
+    /// Reasoning effort sent with each turn (off if `None`)
+    reasoning_effort: Option<ReasoningEffort>,
 }
 
 impl Agent {
@@ -87,12 +187,32 @@ impl Agent {
             permission_client: None,
             mode: AgentMode::default(),
             plan_path: None,
+            plan_content: None,
             plan_manager: PlanManager::new(),
+            plan_phase: None,
             session_manager: None,
             current_session_id: None,
             tool_filter: None,
             max_iterations: DEFAULT_MAX_ITERATIONS,
             recent_tool_calls: Vec::new(),
+            system_append: Vec::new(),
+            cancel_token: None,
+            compaction_model: None,
+            title_model: None,
+            pricing: HashMap::new(),
+            provider_key: None,
+            max_parallel_tools: DEFAULT_MAX_PARALLEL_TOOLS,
+            context_window: None,
+            context_windows: HashMap::new(),
+            model_aliases: HashMap::new(),
+            compaction_ratio: DEFAULT_COMPACTION_RATIO,
+            snapshots: None,
+            undo_turn: None,
+            working_dir: None,
+            project_context: None,
+            persona_prompt: None,
+            context_diff_lines: None,
+            reasoning_effort: None,
         }
     }
 
@@ -112,12 +232,32 @@ impl Agent {
             permission_client: None,
             mode: AgentMode::default(),
             plan_path: None,
+            plan_content: None,
             plan_manager: PlanManager::new(),
+            plan_phase: None,
             session_manager: None,
             current_session_id: None,
             tool_filter: None,
             max_iterations: DEFAULT_MAX_ITERATIONS,
             recent_tool_calls: Vec::new(),
+            system_append: Vec::new(),
+            cancel_token: None,
+            compaction_model: None,
+            title_model: None,
+            pricing: HashMap::new(),
+            provider_key: None,
+            max_parallel_tools: DEFAULT_MAX_PARALLEL_TOOLS,
+            context_window: None,
+            context_windows: HashMap::new(),
+            model_aliases: HashMap::new(),
+            compaction_ratio: DEFAULT_COMPACTION_RATIO,
+            snapshots: None,
+            undo_turn: None,
+            working_dir: None,
+            project_context: None,
+            persona_prompt: None,
+            context_diff_lines: None,
+            reasoning_effort: None,
         }
     }
 
@@ -126,7 +266,8 @@ impl Agent {
     /// Gathers context from the current directory including:
     /// - Working directory and platform info
     /// - Git status (branch, modified files, recent commits)
-    /// - Project type detection (Rust, Node, Python, Go)
+    /// - Project stacks in the root and its immediate subdirectories (e.g.
+    ///   Rust at the root plus Node in `web/`), with their build commands
     /// - Instruction files (CLAUDE.md from project and ~/.claude/)
     /// - Current model name
     pub fn with_context(
@@ -134,22 +275,90 @@ impl Agent {
         model: impl Into<String>,
         max_tokens: u32,
         persona_prompt: Option<&str>,
+    ) -> Self {
+        Self::with_prompt_sections(
+            provider,
+            model,
+            max_tokens,
+            persona_prompt,
+            &PromptConfig::default().sections,
+        )
+    }
+
+    /// Create an agent whose system prompt contains only `sections`, in order.
+    ///
+    /// Project context and memories are only gathered when their section is listed.
+    pub fn with_prompt_sections(
+        provider: Box<dyn LlmProvider>,
+        model: impl Into<String>,
+        max_tokens: u32,
+        persona_prompt: Option<&str>,
+        sections: &[PromptSection],
     ) -> Self {
         use crate::core::context::ProjectContext;
+        use crate::core::memory::MemoryManager;
 
         let model_str: String = model.into();
-        let context = ProjectContext::gather();
-        let context_str = context.to_prompt_context();
+        let mut project_context = None;
+        let mut persona = None;
+
+        let system_prompt = assemble_system_prompt(sections, |section| match section {
+            PromptSection::Identity => Some(format!(
+                "You are {model_str}, accessed through the Omni CLI."
+            )),
+            PromptSection::Persona => {
+                persona = persona_prompt.map(str::to_string);
+                persona.clone()
+            }
+            PromptSection::Memory => MemoryManager::for_current_project()
+                .and_then(|m| m.get_context(MEMORY_PROMPT_ITEMS))
+                .map(|items| MemoryManager::format_for_prompt(&items))
+                .ok(),
+            PromptSection::Context => {
+                let context = ProjectContext::gather().to_prompt_context();
+                project_context = Some(context.clone());
+                Some(context)
+            }
+        });
 
-        // Build system prompt with model identity at the very start
-        let model_identity = format!("You are {model_str}, accessed through the Omni CLI.");
+        let mut agent = Self::with_system(provider, model_str, max_tokens, system_prompt);
+        agent.project_context = project_context;
+        agent.persona_prompt = persona;
+        agent
+    }
 
-        let system_prompt = match persona_prompt {
-            Some(persona) => format!("{model_identity}\n\n{persona}\n\n{context_str}"),
-            None => format!("{model_identity}\n\n{context_str}"),
-        };
+    /// Create an agent for the configured provider with `config` applied.
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let provider = config.agent.create_provider()?;
+        let mut agent = Self::with_prompt_sections(
+            provider,
+            &config.agent.model,
+            config.agent.max_tokens,
+            None,
+            &config.agent.prompt.sections,
+        );
+        agent.apply_config(&config.agent);
+        agent.set_mcp_plugins(&config.plugins.mcp);
+        Ok(agent)
+    }
 
-        Self::with_system(provider, model_str, max_tokens, system_prompt)
+    /// Apply the `[agent]` settings for tools, compaction, pricing and context.
+    pub fn apply_config(&mut self, config: &AgentConfig) {
+        self.set_shell_logging(config.log_shell_commands);
+        self.set_auto_snapshot(config.auto_snapshot);
+        self.set_high_risk_commands(config.high_risk_commands.clone());
+        self.set_max_tool_output(config.max_tool_output_bytes);
+        self.set_tool_timeout(config.tool_timeout_secs);
+        self.set_max_parallel_tools(config.max_parallel_tools);
+        self.set_reasoning_effort(config.reasoning_effort);
+        self.set_compaction_model(config.compaction_model.clone());
+        self.set_title_model(config.title_model.clone());
+        self.set_auto_compaction(config.context_window, config.compaction_threshold);
+        self.set_pricing(config.pricing.clone());
+        self.set_provider_key(&config.provider);
+        self.set_context_windows(config.context_windows.clone());
+        self.set_model_aliases(config.aliases.clone());
+        self.set_context_diff(config.context_diff.then_some(config.context_diff_max_lines));
     }
 
     /// Set the permission client for tool execution.
@@ -157,6 +366,232 @@ impl Agent {
         self.permission_client = Some(client);
     }
 
+    /// Enable or disable the per-project shell command audit log.
+    pub fn set_shell_logging(&mut self, enabled: bool) {
+        let history = enabled
+            .then(|| std::env::current_dir().ok())
+            .flatten()
+            .map(|cwd| crate::core::shell::ShellHistory::for_project(&cwd));
+        self.tools.set_shell_history(history);
+    }
+
+    /// Enable or disable snapshots of the project before file-changing tools.
+    ///
+    /// Snapshots back `omni snapshot restore` and `omni session undo`.
+    pub fn set_auto_snapshot(&mut self, enabled: bool) {
+        let snapshots = if enabled {
+            SnapshotManager::for_current_project()
+                .inspect_err(|e| tracing::warn!("snapshots disabled: {e}"))
+                .ok()
+        } else {
+            None
+        };
+        self.set_snapshot_manager(snapshots);
+    }
+
+    /// Run tools in `dir` instead of the current directory.
+    ///
+    /// Also points the project context, snapshots, and the current session's
+    /// directory at `dir`, so an agent can work in a separate git worktree.
+    pub fn set_working_dir(&mut self, dir: PathBuf) {
+        use crate::core::project::Project;
+
+        self.refresh_project_context(&dir);
+
+        if self.snapshots.is_some() {
+            self.snapshots = Project::detect(&dir)
+                .and_then(|project| SnapshotManager::for_project(&project))
+                .inspect_err(|e| tracing::warn!("snapshots disabled: {e}"))
+                .ok();
+        }
+
+        self.tools.set_working_dir(Some(dir.clone()));
+        self.working_dir = Some(dir);
+        self.record_session_directory();
+    }
+
+    /// Include up to `max_lines` of uncommitted changes in the project context.
+    ///
+    /// `None` leaves the diff out. Only applies when the system prompt has a
+    /// context section.
+    pub fn set_context_diff(&mut self, max_lines: Option<usize>) {
+        if self.context_diff_lines == max_lines {
+            return;
+        }
+        self.context_diff_lines = max_lines;
+        let dir = self.working_dir();
+        self.refresh_project_context(&dir);
+    }
+
+    /// Regather the project context block in the system prompt from `dir`.
+    fn refresh_project_context(&mut self, dir: &Path) {
+        use crate::core::context::ProjectContext;
+
+        if let (Some(old), Some(system)) = (&self.project_context, self.conversation.system()) {
+            let mut context = ProjectContext::gather_from(dir);
+            if let Some(max_lines) = self.context_diff_lines {
+                context = context.with_diff(max_lines);
+            }
+            let context = context.to_prompt_context();
+            let system = system.replacen(old.as_str(), &context, 1);
+            self.conversation.set_system(system);
+            self.project_context = Some(context);
+        }
+    }
+
+    /// Directory tools run in.
+    #[must_use]
+    pub fn working_dir(&self) -> PathBuf {
+        self.tools.working_dir()
+    }
+
+    /// Save a custom working directory on the current session.
+    fn record_session_directory(&self) {
+        let (Some(dir), Some(manager), Some(session_id)) = (
+            &self.working_dir,
+            &self.session_manager,
+            &self.current_session_id,
+        ) else {
+            return;
+        };
+
+        let result = manager.get_session(session_id).and_then(|mut session| {
+            if session.directory == *dir {
+                return Ok(());
+            }
+            session.directory.clone_from(dir);
+            manager.update_session(&session)
+        });
+        if let Err(e) = result {
+            tracing::warn!("failed to record session directory: {e}");
+        }
+    }
+
+    /// Set the shadow repository used for automatic snapshots.
+    pub fn set_snapshot_manager(&mut self, snapshots: Option<SnapshotManager>) {
+        self.snapshots = snapshots;
+    }
+
+    /// Register MCP servers as plugins, keyed by plugin name.
+    ///
+    /// Servers start the first time their tools are listed.
+    pub fn set_mcp_plugins(&mut self, plugins: &HashMap<String, McpPluginConfig>) {
+        for (name, config) in plugins {
+            let mut plugin = McpPlugin::new(name, config.server_config());
+            if let Some(secs) = config.timeout_secs {
+                plugin = plugin.with_call_timeout(std::time::Duration::from_secs(secs));
+            }
+            self.tools
+                .register_plugin(name.clone(), std::sync::Arc::new(plugin));
+        }
+    }
+
+    /// Set the shell command patterns that always require confirmation.
+    pub fn set_high_risk_commands(&mut self, patterns: Vec<String>) {
+        self.tools.set_high_risk_commands(patterns);
+    }
+
+    /// Set the maximum shell output buffered before a command is killed.
+    pub const fn set_max_tool_output(&mut self, bytes: usize) {
+        self.tools.set_max_output_bytes(bytes);
+    }
+
+    /// Set how long a shell command may run before it is killed.
+    ///
+    /// A timeout of 0 lets tools run indefinitely.
+    pub const fn set_tool_timeout(&mut self, secs: u64) {
+        let timeout = if secs == 0 {
+            None
+        } else {
+            Some(std::time::Duration::from_secs(secs))
+        };
+        self.tools.set_tool_timeout(timeout);
+    }
+
+    /// Set the model used for compaction summaries.
+    ///
+    /// Pass `None` to summarize with the session model.
+    pub fn set_compaction_model(&mut self, model: Option<String>) {
+        self.compaction_model = model;
+    }
+
+    /// Set the model used for session titles.
+    ///
+    /// Pass `None` to title with the session model.
+    pub fn set_title_model(&mut self, model: Option<String>) {
+        self.title_model = model;
+    }
+
+    /// Set how many read-only tool calls may run at once.
+    ///
+    /// A limit of 0 or 1 runs every tool call sequentially.
+    pub const fn set_max_parallel_tools(&mut self, limit: usize) {
+        self.max_parallel_tools = limit;
+    }
+
+    /// Set when history is compacted automatically.
+    ///
+    /// Compaction runs once the conversation passes `ratio` of the context
+    /// window; `window` overrides the built-in size for the model. A ratio
+    /// of 0 turns automatic compaction off.
+    pub const fn set_auto_compaction(&mut self, window: Option<u32>, ratio: f64) {
+        self.context_window = window;
+        self.compaction_ratio = ratio;
+    }
+
+    /// Set token prices, keyed by model ID or provider name.
+    pub fn set_pricing(&mut self, pricing: HashMap<String, ModelPricing>) {
+        self.pricing = pricing;
+    }
+
+    /// Set model aliases, resolving the current model if it is one.
+    pub fn set_model_aliases(&mut self, aliases: HashMap<String, String>) {
+        self.model_aliases = aliases;
+        self.model = self.resolve_model(&self.model);
+    }
+
+    /// Full model ID for `model`, which may be an alias.
+    fn resolve_model(&self, model: &str) -> String {
+        self.model_aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(model))
+            .map_or_else(|| model.to_string(), |(_, id)| id.clone())
+    }
+
+    /// Set context window sizes, keyed by model ID or provider name.
+    pub fn set_context_windows(&mut self, windows: HashMap<String, u32>) {
+        self.context_windows = windows;
+    }
+
+    /// Context window of the active model in tokens, if known.
+    ///
+    /// The `context_window` override wins, then the configured sizes for the
+    /// model or provider, then the built-in sizes.
+    #[must_use]
+    pub fn context_window(&self) -> Option<u32> {
+        self.context_window
+            .or_else(|| self.context_windows.get(&self.model).copied())
+            .or_else(|| self.context_windows.get(self.provider_key()).copied())
+            .or_else(|| {
+                CONTEXT_WINDOWS
+                    .iter()
+                    .find(|(prefix, _)| self.model.starts_with(prefix))
+                    .map(|&(_, size)| size)
+            })
+    }
+
+    /// Estimated cost of a response with the active model.
+    ///
+    /// Models without a configured price cost nothing.
+    fn usage_cost(&self, usage: &Usage) -> f64 {
+        if let Some(price) = pricing::lookup(&self.pricing, &self.model, self.provider_key()) {
+            price.usage_cost(usage)
+        } else {
+            tracing::debug!(model = %self.model, "no pricing configured for model");
+            0.0
+        }
+    }
+
     /// Set tool filter to restrict available tools.
     ///
     /// Only tools in this list will be available to the agent.
@@ -177,6 +612,45 @@ impl Agent {
         }
     }
 
+    /// Set the cancellation token for subsequent turns.
+    ///
+    /// Cancelling the token stops streaming and tool execution, keeps any
+    /// partial response, and makes the turn return [`AgentError::Cancelled`].
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel_token = Some(token);
+    }
+
+    /// Check whether the current turn has been cancelled.
+    fn is_cancelled(&self) -> bool {
+        self.cancel_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Wrap up a cancelled turn, keeping partial text and repairing tool calls.
+    fn finish_cancelled(&mut self, content_blocks: Vec<ContentBlock>) -> AgentError {
+        // Incomplete tool calls are dropped; only streamed text is kept
+        let text: String = content_blocks
+            .into_iter()
+            .filter_map(|b| match b {
+                ContentBlock::Text { text } => Some(text),
+                _ => None,
+            })
+            .collect();
+
+        if !text.is_empty() {
+            self.conversation.add_assistant_message(&text);
+            self.persist_assistant_message(&text);
+        }
+
+        let repaired = self
+            .conversation
+            .repair_dangling_tool_calls(CANCELLED_TOOL_RESULT);
+        tracing::info!(repaired, "turn cancelled");
+
+        AgentError::Cancelled
+    }
+
     /// Set maximum iterations for the agent loop
     pub const fn set_max_iterations(&mut self, max: u32) {
         self.max_iterations = max;
@@ -237,12 +711,12 @@ impl Agent {
         let manager =
             SessionManager::for_current_project().map_err(|e| AgentError::Config(e.to_string()))?;
 
-        let (session_id, should_load) = match target {
+        let (session, should_load) = match target {
             SessionTarget::New => {
                 let session = manager
                     .create_session()
                     .map_err(|e| AgentError::Config(e.to_string()))?;
-                (session.id, false)
+                (session, false)
             }
             SessionTarget::MostRecent => {
                 let session = manager
@@ -253,19 +727,24 @@ impl Agent {
                     .list_messages(&session.id)
                     .map(|msgs| !msgs.is_empty())
                     .unwrap_or(false);
-                (session.id, has_messages)
+                (session, has_messages)
             }
             SessionTarget::Specific(id_or_slug) => {
                 // Find by ID or slug
                 let session = manager
                     .find_session(&id_or_slug)
                     .map_err(|e| AgentError::Config(e.to_string()))?;
-                (session.id, true)
+                (session, true)
             }
         };
+        let session_id = session.id;
+        self.system_append = session.system_append;
+        self.apply_system_append();
 
         self.session_manager = Some(manager);
         self.current_session_id = Some(session_id.clone());
+        self.tools.set_session_id(self.current_session_id.clone());
+        self.record_session_directory();
 
         // Load existing messages into conversation if resuming
         if should_load {
@@ -292,7 +771,7 @@ impl Agent {
                 .map_err(|e| AgentError::Config(e.to_string()))?;
 
             match msg {
-                SessionMessage::User(_) => {
+                SessionMessage::User(SessionUserMessage { id, .. }) => {
                     // Collect text parts into user message
                     let text: String = parts
                         .iter()
@@ -304,23 +783,11 @@ impl Agent {
                         .join("\n");
 
                     if !text.is_empty() {
-                        self.conversation.add_user_message(&text);
+                        self.conversation.add_prompt(id, &text);
                     }
                 }
                 SessionMessage::Assistant(_) => {
-                    // Collect text parts into assistant message
-                    let text: String = parts
-                        .iter()
-                        .filter_map(|p| match p {
-                            Part::Text(t) => Some(t.text.as_str()),
-                            _ => None,
-                        })
-                        .collect::<Vec<_>>()
-                        .join("");
-
-                    if !text.is_empty() {
-                        self.conversation.add_assistant_message(&text);
-                    }
+                    Self::load_assistant_parts(&mut self.conversation, parts);
                 }
             }
         }
@@ -347,13 +814,16 @@ impl Agent {
         };
 
         // Verify session exists
-        manager
+        let session = manager
             .get_session(session_id)
             .map_err(|e| AgentError::Config(e.to_string()))?;
 
         // Clear conversation and switch
         self.conversation.clear();
+        self.system_append = session.system_append;
+        self.apply_system_append();
         self.current_session_id = Some(session_id.to_string());
+        self.tools.set_session_id(self.current_session_id.clone());
 
         tracing::info!(session_id, "switched session");
         Ok(())
@@ -374,12 +844,73 @@ impl Agent {
             .map_err(|e| AgentError::Config(e.to_string()))?;
 
         self.conversation.clear();
+        self.system_append.clear();
+        self.apply_system_append();
         self.current_session_id = Some(session.id.clone());
+        self.tools.set_session_id(self.current_session_id.clone());
+        self.record_session_directory();
 
         tracing::info!(session_id = %session.id, "created new session");
         Ok(session.id)
     }
 
+    /// Append extra instructions to the system prompt.
+    ///
+    /// Multiple appends are kept in order and saved on the current session
+    /// so they survive resumption.
+    pub fn append_system(&mut self, text: impl Into<String>) {
+        self.system_append.push(text.into());
+        self.apply_system_append();
+
+        if let (Some(manager), Some(session_id)) = (&self.session_manager, &self.current_session_id)
+        {
+            let result = manager.get_session(session_id).and_then(|mut session| {
+                session.system_append.clone_from(&self.system_append);
+                manager.update_session(&session)
+            });
+            if let Err(e) = result {
+                tracing::warn!(error = %e, "failed to persist system append");
+            }
+        }
+    }
+
+    /// Get the extra instructions appended to the system prompt.
+    #[must_use]
+    pub fn system_append(&self) -> &[String] {
+        &self.system_append
+    }
+
+    /// Rewrite the appended-instructions block in the system prompt.
+    fn apply_system_append(&mut self) {
+        let mut system = self.conversation.system().unwrap_or_default().to_string();
+
+        // Remove any previous block
+        if let (Some(start), Some(end)) = (
+            system.find(SYSTEM_APPEND_OPEN),
+            system.find(SYSTEM_APPEND_CLOSE),
+        ) {
+            let before = system[..start].trim_end();
+            let after = &system[end + SYSTEM_APPEND_CLOSE.len()..];
+            system = format!("{before}{after}");
+        }
+
+        if !self.system_append.is_empty() {
+            let block = format!(
+                "{SYSTEM_APPEND_OPEN}\n{}\n{SYSTEM_APPEND_CLOSE}",
+                self.system_append.join("\n\n")
+            );
+            system = if system.is_empty() {
+                block
+            } else {
+                format!("{}\n\n{block}", system.trim_end())
+            };
+        }
+
+        if !system.is_empty() || self.conversation.system().is_some() {
+            self.conversation.set_system(system);
+        }
+    }
+
     /// Get the session manager reference.
     #[must_use]
     pub const fn session_manager(&self) -> Option<&SessionManager> {
@@ -410,7 +941,55 @@ impl Agent {
             return None;
         }
 
-        // Get first user message
+        self.first_user_message()
+    }
+
+    /// Add a persisted assistant message, with its tool calls and their results.
+    fn load_assistant_parts(conversation: &mut Conversation, parts: Vec<Part>) {
+        let mut blocks = Vec::new();
+        let mut results = Vec::new();
+        for part in parts {
+            match part {
+                Part::Text(t) => blocks.push(ContentBlock::Text { text: t.text }),
+                Part::Tool(t) => {
+                    let output = t.output().unwrap_or_default().to_string();
+                    let (input, result) = match t.state {
+                        ToolState::Completed { input, .. } => (input, (output, false)),
+                        ToolState::Error { input, error, .. } => (input, (error, true)),
+                        ToolState::Pending { input, .. } | ToolState::Running { input, .. } => {
+                            (input, (CANCELLED_TOOL_RESULT.to_string(), true))
+                        }
+                    };
+                    blocks.push(ContentBlock::ToolUse {
+                        id: t.call_id.clone(),
+                        name: t.tool,
+                        input,
+                    });
+                    results.push((t.call_id, result));
+                }
+                // Reasoning isn't replayed to the model
+                Part::Reasoning(_) => {}
+            }
+        }
+
+        if results.is_empty() {
+            let text = Content::Blocks(blocks).text();
+            if !text.is_empty() {
+                conversation.add_assistant_message(text);
+            }
+            return;
+        }
+        conversation.add_assistant_blocks(blocks);
+        for (id, (content, is_error)) in results {
+            conversation.add_tool_result(id, content, is_error);
+        }
+    }
+
+    /// Get the text of the current session's first user message
+    fn first_user_message(&self) -> Option<String> {
+        let manager = self.session_manager.as_ref()?;
+        let session_id = self.current_session_id.as_ref()?;
+
         let messages = manager.list_messages(session_id).ok()?;
         for msg in messages {
             if let SessionMessage::User(_) = msg {
@@ -427,8 +1006,25 @@ impl Agent {
         None
     }
 
+    /// Regenerate the current session title from its first user message
+    ///
+    /// Unlike automatic titling, this overrides titles that were already set.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the session has no user message or title generation fails.
+    pub async fn regenerate_title(&self) -> Result<String> {
+        let first_message = self.first_user_message().ok_or_else(|| {
+            AgentError::Config("session has no user message to title".to_string())
+        })?;
+        self.generate_title(&first_message).await
+    }
+
     /// Generate and set a title for the current session based on the first message
     ///
+    /// Uses the configured title model, falling back to the session model if
+    /// that request fails. The title is left alone if the reply is empty.
+    ///
     /// # Errors
     ///
     /// Returns error if title generation or session update fails.
@@ -440,34 +1036,26 @@ impl Agent {
             return Err(AgentError::Config("no session".to_string()));
         };
 
-        // Generate title prompt
         let prompt = titling_prompt(first_message);
-
-        // Make a simple completion request (no tools, no streaming needed)
-        let request = CompletionRequest {
-            model: "claude-3-5-haiku-latest".to_string(), // Use haiku for cheap/fast titling
-            max_tokens: 50,
-            messages: vec![Message {
-                role: Role::User,
-                content: Content::Text(prompt),
-            }],
-            system: Some("You are a helpful assistant that generates concise titles.".to_string()),
-            tools: None,
+        let model = self.title_model.as_deref().unwrap_or(&self.model);
+        let response = match self.complete_text(model, TITLE_SYSTEM, &prompt).await {
+            Ok(response) => response,
+            Err(e) if model != self.model && !matches!(e, AgentError::Cancelled) => {
+                tracing::warn!(model, error = %e, "title model unavailable, using session model");
+                self.complete_text(&self.model, TITLE_SYSTEM, &prompt)
+                    .await?
+            }
+            Err(e) => return Err(e),
         };
 
-        let stream = self.provider.stream(request).await?;
-        futures::pin_mut!(stream);
-
-        let mut title_text = String::new();
-        while let Some(event) = stream.next().await {
-            if let Ok(CompletionEvent::TextDelta(text)) = event {
-                title_text.push_str(&text);
-            }
+        // A reply of only quotes leaves nothing worth replacing a title with
+        let title = extract_title(&response);
+        if title.is_empty() {
+            return Err(AgentError::Parse(
+                "model returned an empty title".to_string(),
+            ));
         }
 
-        // Extract and clean the title
-        let title = extract_title(&title_text);
-
         // Update session title
         manager
             .set_session_title(session_id, &title)
@@ -476,8 +1064,179 @@ impl Agent {
         Ok(title)
     }
 
-    /// Persist a user message to the current session
-    fn persist_user_message(&self, text: &str) {
+    /// Summarize conversation history for compaction
+    ///
+    /// Uses the configured compaction model, falling back to the session
+    /// model if that request fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if neither model produces a summary.
+    pub async fn generate_compaction_summary(&self, context: &str) -> Result<String> {
+        let prompt = compaction_prompt(context);
+        let model = self.compaction_model.as_deref().unwrap_or(&self.model);
+
+        match self.complete_text(model, COMPACTION_SYSTEM, &prompt).await {
+            Ok(summary) => Ok(summary),
+            Err(e) if model != self.model && !matches!(e, AgentError::Cancelled) => {
+                tracing::warn!(model, error = %e, "compaction model unavailable, using session model");
+                self.complete_text(&self.model, COMPACTION_SYSTEM, &prompt)
+                    .await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Token count above which history is compacted, if enabled.
+    ///
+    /// Models with no known context window use
+    /// [`DEFAULT_COMPACTION_THRESHOLD`].
+    fn compaction_limit(&self) -> Option<u32> {
+        if self.compaction_ratio <= 0.0 {
+            return None;
+        }
+
+        Some(
+            self.context_window()
+                .map_or(DEFAULT_COMPACTION_THRESHOLD, |window| {
+                    #[allow(
+                        clippy::cast_possible_truncation,
+                        clippy::cast_sign_loss,
+                        clippy::cast_precision_loss
+                    )]
+                    let limit = (f64::from(window) * self.compaction_ratio.min(1.0)) as u32;
+                    limit
+                }),
+        )
+    }
+
+    /// Summarize older messages if the conversation is near the context limit.
+    ///
+    /// Keeps the last [`MIN_MESSAGES_TO_KEEP`] messages. A failed summary
+    /// leaves the history untouched, so the request is still attempted.
+    async fn compact_if_needed<F>(&mut self, on_event: &mut F)
+    where
+        F: FnMut(ChatEvent),
+    {
+        let Some(limit) = self.compaction_limit() else {
+            return;
+        };
+        if self.conversation.estimated_tokens() <= limit {
+            return;
+        }
+        let Some(split) = self.conversation.compaction_split(MIN_MESSAGES_TO_KEEP) else {
+            return;
+        };
+
+        match self.compact_history(split).await {
+            Ok(_) => on_event(ChatEvent::Compacted { messages: split }),
+            Err(AgentError::Cancelled) => {}
+            Err(e) => tracing::warn!(error = %e, "automatic compaction failed"),
+        }
+    }
+
+    /// Summarize older messages now, whatever the automatic threshold.
+    ///
+    /// Keeps the last [`MIN_MESSAGES_TO_KEEP`] messages, like automatic
+    /// compaction. Returns `None` if the conversation is too short to compact.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the summary can't be generated; the history is
+    /// left untouched.
+    pub async fn compact(&mut self) -> Result<Option<CompactionSummary>> {
+        let Some(split) = self.conversation.compaction_split(MIN_MESSAGES_TO_KEEP) else {
+            return Ok(None);
+        };
+
+        let tokens_before = self.conversation.estimated_tokens();
+        let summary = self.compact_history(split).await?;
+
+        // The summary is first; kept replies run up to the first kept prompt
+        let messages = self.conversation.messages();
+        let (kept_prompt, lead_end) = self
+            .conversation
+            .first_prompt()
+            .map_or((None, messages.len()), |(index, id)| {
+                (Some(id.to_string()), index)
+            });
+        Ok(Some(CompactionSummary {
+            messages: split,
+            tokens_before,
+            tokens_after: self.conversation.estimated_tokens(),
+            summary,
+            kept_prompt,
+            kept_replies: reply_count(&messages[1.min(lead_end)..lead_end]),
+        }))
+    }
+
+    /// Replace the first `split` messages with a summary and record it on
+    /// the session, returning the summary.
+    async fn compact_history(&mut self, split: usize) -> Result<String> {
+        let context = transcript(&self.conversation.messages()[..split]);
+        let summary = self.generate_compaction_summary(&context).await?;
+
+        self.conversation.compact(split, &summary);
+        tracing::info!(messages = split, "compacted conversation history");
+
+        if let (Some(manager), Some(session_id)) = (&self.session_manager, &self.current_session_id)
+        {
+            if let Err(e) = manager.mark_session_compacted(session_id) {
+                tracing::warn!("failed to mark session compacted: {e}");
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Run a single tool-free completion and collect the response text
+    ///
+    /// Stops with [`AgentError::Cancelled`] when the current turn is cancelled.
+    async fn complete_text(&self, model: &str, system: &str, prompt: &str) -> Result<String> {
+        let request = CompletionRequest {
+            model: model.to_string(),
+            max_tokens: self.max_tokens,
+            messages: vec![Message {
+                role: Role::User,
+                content: Content::Text(prompt.to_string()),
+            }],
+            system: Some(system.to_string()),
+            tools: None,
+            reasoning_effort: None,
+        };
+
+        let cancelled = async {
+            match &self.cancel_token {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        let collect = async {
+            let stream = self.provider.stream(request).await?;
+            futures::pin_mut!(stream);
+
+            let mut text = String::new();
+            while let Some(event) = stream.next().await {
+                if let CompletionEvent::TextDelta(delta) = event? {
+                    text.push_str(&delta);
+                }
+            }
+            Ok::<_, AgentError>(text)
+        };
+        let text = tokio::select! {
+            biased;
+            () = cancelled => return Err(AgentError::Cancelled),
+            text = collect => text?,
+        };
+
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(AgentError::StreamEnded);
+        }
+        Ok(text.to_string())
+    }
+
+    /// Persist a user message to the current session as `id`
+    fn persist_user_message(&self, id: &str, text: &str) {
         let Some(ref manager) = self.session_manager else {
             return;
         };
@@ -491,12 +1250,14 @@ impl Agent {
         };
 
         // Create session message
-        let msg = SessionMessage::User(SessionUserMessage::new(
+        let mut user = SessionUserMessage::new(
             session_id,
             mode_str,
             "anthropic", // TODO: get from provider
             &self.model,
-        ));
+        );
+        user.id = id.to_string();
+        let msg = SessionMessage::User(user);
 
         // Save message
         if let Err(e) = manager.save_message(session_id, &msg) {
@@ -518,6 +1279,18 @@ impl Agent {
 
     /// Persist an assistant message to the current session
     fn persist_assistant_message(&self, text: &str) {
+        self.persist_assistant_turn(
+            &[ContentBlock::Text {
+                text: text.to_string(),
+            }],
+            "",
+        );
+    }
+
+    /// Persist an assistant turn's reasoning, text and tool calls to the current session
+    ///
+    /// Tool calls are saved with the results already in the conversation.
+    fn persist_assistant_turn(&self, blocks: &[ContentBlock], reasoning: &str) {
         let Some(ref manager) = self.session_manager else {
             return;
         };
@@ -539,16 +1312,43 @@ impl Agent {
             &self.model,
         ));
 
-        // Save message
+        let mut parts = Vec::new();
+        if !reasoning.is_empty() {
+            let mut part = ReasoningPart::new(msg.id(), session_id, reasoning);
+            part.complete();
+            parts.push(Part::Reasoning(part));
+        }
+        for block in blocks {
+            match block {
+                ContentBlock::Text { text } if !text.is_empty() => {
+                    parts.push(Part::Text(TextPart::new(msg.id(), session_id, text)));
+                }
+                ContentBlock::ToolUse { id, name, input } => {
+                    let mut part = ToolPart::new(msg.id(), session_id, id, name, input.clone());
+                    match self.conversation.tool_result(id) {
+                        Some((error, true)) => part.error(error),
+                        Some((output, false)) => part.complete(output),
+                        None => {}
+                    }
+                    parts.push(Part::Tool(part));
+                }
+                _ => {}
+            }
+        }
+        if parts.is_empty() {
+            return;
+        }
+
+        // Save message
         if let Err(e) = manager.save_message(session_id, &msg) {
             tracing::warn!("failed to persist assistant message: {e}");
             return;
         }
 
-        // Create text part
-        let part = Part::Text(TextPart::new(msg.id(), session_id, text));
-        if let Err(e) = manager.save_part(msg.id(), &part) {
-            tracing::warn!("failed to persist assistant message part: {e}");
+        for part in &parts {
+            if let Err(e) = manager.save_part(msg.id(), part) {
+                tracing::warn!("failed to persist assistant message part: {e}");
+            }
         }
 
         // Touch session
@@ -585,13 +1385,35 @@ impl Agent {
     /// # Errors
     ///
     /// Returns error if API call or tool execution fails.
-    pub async fn chat_with_events<F>(&mut self, message: &str, mut on_event: F) -> Result<String>
+    pub async fn chat_with_events<F>(&mut self, message: &str, on_event: F) -> Result<String>
+    where
+        F: FnMut(ChatEvent),
+    {
+        self.chat_prompt_with_events(&new_message_id(), message, on_event)
+            .await
+    }
+
+    /// Like [`Self::chat_with_events`], recording the prompt as `prompt_id`
+    ///
+    /// The ID is shared with the session message, so the prompt can be found
+    /// again by [`Self::branch_before_prompt`] after compaction or a resume.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if API call or tool execution fails.
+    pub async fn chat_prompt_with_events<F>(
+        &mut self,
+        prompt_id: &str,
+        message: &str,
+        mut on_event: F,
+    ) -> Result<String>
     where
         F: FnMut(ChatEvent),
     {
-        self.conversation.add_user_message(message);
-        self.persist_user_message(message);
+        self.conversation.add_prompt(prompt_id, message);
+        self.persist_user_message(prompt_id, message);
         self.clear_tool_history();
+        self.undo_turn = None;
 
         let mut iterations = 0u32;
 
@@ -610,7 +1432,29 @@ impl Agent {
                 )));
             }
 
-            let (content_blocks, stop_reason) = self.stream_response_events(&mut on_event).await?;
+            if self.is_cancelled() {
+                return Err(self.finish_cancelled(Vec::new()));
+            }
+
+            self.compact_if_needed(&mut on_event).await;
+            if self.is_cancelled() {
+                return Err(self.finish_cancelled(Vec::new()));
+            }
+
+            // Reasoning is only streamed, so collect it for the session
+            let mut reasoning = String::new();
+            let (content_blocks, stop_reason) = self
+                .stream_response_events(&mut |event| {
+                    if let ChatEvent::Reasoning(text) = &event {
+                        reasoning.push_str(text);
+                    }
+                    on_event(event);
+                })
+                .await?;
+
+            if self.is_cancelled() {
+                return Err(self.finish_cancelled(content_blocks));
+            }
 
             if !content_blocks.is_empty() {
                 self.conversation
@@ -637,6 +1481,11 @@ impl Agent {
 
                 self.handle_tool_use_events(&content_blocks, &mut on_event)
                     .await?;
+                self.persist_assistant_turn(&content_blocks, &reasoning);
+
+                if self.is_cancelled() {
+                    return Err(self.finish_cancelled(Vec::new()));
+                }
             } else {
                 let text = content_blocks
                     .iter()
@@ -648,15 +1497,32 @@ impl Agent {
                     .join("");
 
                 // Persist final assistant response
-                if !text.is_empty() {
-                    self.persist_assistant_message(&text);
-                }
+                self.persist_assistant_turn(&content_blocks, &reasoning);
 
                 return Ok(text);
             }
         }
     }
 
+    /// Build the request the next turn would send for `message`, without sending it.
+    #[must_use]
+    pub fn preview_request(&self, message: &str) -> CompletionRequest {
+        let mut messages = self.conversation.messages().to_vec();
+        messages.push(Message {
+            role: Role::User,
+            content: Content::Text(message.to_string()),
+        });
+
+        CompletionRequest {
+            model: self.model.clone(),
+            max_tokens: self.max_tokens,
+            messages,
+            system: self.conversation.system().map(String::from),
+            tools: Some(self.filtered_tools()),
+            reasoning_effort: self.reasoning_effort,
+        }
+    }
+
     #[allow(dead_code)]
     async fn stream_response<F>(
         &self,
@@ -671,6 +1537,7 @@ impl Agent {
             messages: self.conversation.messages().to_vec(),
             system: self.conversation.system().map(String::from),
             tools: Some(self.filtered_tools()),
+            reasoning_effort: self.reasoning_effort,
         };
 
         let stream = self.provider.stream(request).await?;
@@ -693,6 +1560,8 @@ impl Agent {
                         content_blocks.push(ContentBlock::Text { text });
                     }
                 }
+                // Reasoning is not part of the plain-text response
+                CompletionEvent::ReasoningDelta(_) => {}
                 CompletionEvent::ToolUseStart { index, id, name } => {
                     while content_blocks.len() <= index {
                         content_blocks.push(ContentBlock::Text {
@@ -738,6 +1607,10 @@ impl Agent {
                             let _ = (id, name); // Suppress unused warning.
                         }
                     }
+                    // Thinking is sent back with the tool results that follow it
+                    if matches!(block, ContentBlock::Thinking { .. }) {
+                        content_blocks.push(block);
+                    }
                 }
                 CompletionEvent::Done {
                     stop_reason: sr, ..
@@ -814,6 +1687,10 @@ impl Agent {
                     }
                 };
 
+                if !is_error && self.mode == AgentMode::Plan && self.writes_plan_file(name, input) {
+                    self.record_plan_content();
+                }
+
                 self.conversation
                     .add_tool_result(id.clone(), content, is_error);
             }
@@ -829,22 +1706,95 @@ impl Agent {
     where
         F: FnMut(ChatEvent),
     {
-        let request = CompletionRequest {
-            model: self.model.clone(),
-            max_tokens: self.max_tokens,
-            messages: self.conversation.messages().to_vec(),
-            system: self.conversation.system().map(String::from),
-            tools: Some(self.filtered_tools()),
-        };
+        let mut content_blocks: Vec<ContentBlock> = Vec::new();
+        let mut attempt = 0;
+
+        loop {
+            let request = CompletionRequest {
+                model: self.model.clone(),
+                max_tokens: self.max_tokens,
+                messages: continuation_messages(self.conversation.messages(), &content_blocks),
+                system: self.conversation.system().map(String::from),
+                tools: Some(self.filtered_tools()),
+                reasoning_effort: self.reasoning_effort,
+            };
+
+            match self
+                .stream_attempt(request, &mut content_blocks, on_event)
+                .await
+            {
+                Err(e)
+                    if attempt < MAX_STREAM_RECONNECTS
+                        && is_stream_disconnect(&e)
+                        && !self.is_cancelled() =>
+                {
+                    attempt += 1;
+                    tracing::warn!(error = %e, attempt, "provider stream dropped, reconnecting");
+                    on_event(ChatEvent::Reconnecting {
+                        attempt,
+                        max_attempts: MAX_STREAM_RECONNECTS,
+                    });
 
+                    // Keep streamed text; tool calls are re-issued by the model
+                    content_blocks
+                        .retain(|b| matches!(b, ContentBlock::Text { text } if !text.is_empty()));
+
+                    let backoff =
+                        tokio::time::sleep(std::time::Duration::from_secs(u64::from(attempt)));
+                    match &self.cancel_token {
+                        Some(token) => tokio::select! {
+                            () = token.cancelled() => {}
+                            () = backoff => {}
+                        },
+                        None => backoff.await,
+                    }
+                }
+                Err(e) => return Err(e),
+                Ok(stop_reason) => {
+                    if attempt > 0 {
+                        // Stitching can leave empty padding blocks behind
+                        content_blocks.retain(
+                            |b| !matches!(b, ContentBlock::Text { text } if text.is_empty()),
+                        );
+                    }
+                    return Ok((content_blocks, stop_reason));
+                }
+            }
+        }
+    }
+
+    /// Stream one provider response, appending to `content_blocks`.
+    ///
+    /// Block indices from the provider are offset past any blocks already
+    /// present, so a resumed response is stitched onto the partial one.
+    async fn stream_attempt<F>(
+        &self,
+        request: CompletionRequest,
+        content_blocks: &mut Vec<ContentBlock>,
+        on_event: &mut F,
+    ) -> Result<Option<StopReason>>
+    where
+        F: FnMut(ChatEvent),
+    {
         let stream = self.provider.stream(request).await?;
         futures::pin_mut!(stream);
 
-        let mut content_blocks: Vec<ContentBlock> = Vec::new();
+        let offset = content_blocks.len();
         let mut current_tool_inputs: HashMap<usize, String> = HashMap::new();
         let mut stop_reason = None;
 
-        while let Some(event) = stream.next().await {
+        loop {
+            let next = match &self.cancel_token {
+                Some(token) => tokio::select! {
+                    biased;
+                    () = token.cancelled() => break,
+                    next = stream.next() => next,
+                },
+                None => stream.next().await,
+            };
+            let Some(event) = next else {
+                break;
+            };
             let event = event?;
 
             match event {
@@ -856,7 +1806,11 @@ impl Agent {
                         content_blocks.push(ContentBlock::Text { text });
                     }
                 }
+                CompletionEvent::ReasoningDelta(text) => {
+                    on_event(ChatEvent::Reasoning(text));
+                }
                 CompletionEvent::ToolUseStart { index, id, name } => {
+                    let index = index + offset;
                     while content_blocks.len() <= index {
                         content_blocks.push(ContentBlock::Text {
                             text: String::new(),
@@ -873,11 +1827,12 @@ impl Agent {
                     partial_json,
                 } => {
                     current_tool_inputs
-                        .entry(index)
+                        .entry(index + offset)
                         .or_default()
                         .push_str(&partial_json);
                 }
                 CompletionEvent::ContentBlockDone { index, block } => {
+                    let index = index + offset;
                     if let Some(ContentBlock::ToolUse { input, .. }) = content_blocks.get_mut(index)
                     {
                         if let Some(json_str) = current_tool_inputs.remove(&index) {
@@ -898,6 +1853,9 @@ impl Agent {
                             let _ = (id, name);
                         }
                     }
+                    if matches!(block, ContentBlock::Thinking { .. }) {
+                        content_blocks.push(block);
+                    }
                 }
                 CompletionEvent::Done {
                     stop_reason: sr,
@@ -906,15 +1864,10 @@ impl Agent {
                     stop_reason = sr;
                     // Emit usage event if we have usage data
                     if let Some(u) = usage {
-                        // Cost calculation (Anthropic Claude Sonnet pricing per million tokens)
-                        // Input: $3/M, Output: $15/M (approximate)
-                        let cost = f64::from(u.input_tokens)
-                            .mul_add(3.0, f64::from(u.output_tokens) * 15.0)
-                            / 1_000_000.0;
                         on_event(ChatEvent::Usage {
-                            input_tokens: u.input_tokens,
+                            input_tokens: u.prompt_tokens(),
                             output_tokens: u.output_tokens,
-                            cost_usd: cost,
+                            cost_usd: self.usage_cost(&u),
                         });
                     }
                 }
@@ -927,7 +1880,7 @@ impl Agent {
             }
         }
 
-        Ok((content_blocks, stop_reason))
+        Ok(stop_reason)
     }
 
     async fn handle_tool_use_events<F>(
@@ -938,61 +1891,309 @@ impl Agent {
     where
         F: FnMut(ChatEvent),
     {
-        for block in content_blocks {
-            if let ContentBlock::ToolUse { id, name, input } = block {
-                // Format invocation for display
-                let invocation = format_tool_invocation(name, input);
+        let calls: Vec<(&String, &String, &serde_json::Value)> = content_blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolUse { id, name, input } => Some((id, name, input)),
+                _ => None,
+            })
+            .collect();
+
+        let mut rest = calls.as_slice();
+        while !rest.is_empty() {
+            // Batch consecutive read-only calls; anything else runs alone
+            let batch_len = rest
+                .iter()
+                .take(self.max_parallel_tools)
+                .take_while(|(_, name, _)| ToolRegistry::is_parallel_safe(name))
+                .count()
+                .max(1);
+            let (batch, tail) = rest.split_at(batch_len);
+            rest = tail;
+
+            if self.is_cancelled() {
+                for (id, _, _) in batch {
+                    self.conversation.add_tool_result(
+                        (*id).clone(),
+                        CANCELLED_TOOL_RESULT.to_string(),
+                        true,
+                    );
+                }
+                continue;
+            }
 
+            for (_, name, input) in batch {
                 // Emit tool start event for activity status
-                on_event(ChatEvent::ToolStart { name: name.clone() });
+                on_event(ChatEvent::ToolStart {
+                    name: (*name).clone(),
+                });
 
-                let result = self
-                    .tools
-                    .execute(
-                        name,
-                        input.clone(),
-                        self.permission_client.as_ref(),
-                        self.mode,
-                        &self.plan_manager,
-                    )
-                    .await;
+                if self.mode == AgentMode::Plan {
+                    let phase = self
+                        .plan_manager
+                        .phase_for_tool(name, input, self.plan_written());
+                    self.set_plan_phase(phase, on_event);
+                }
+            }
 
-                let (content, is_error) = match result {
-                    Ok(output) => {
-                        if output == "[MODE_SWITCH:PLAN]" {
-                            self.switch_mode(AgentMode::Plan, None);
-                            ("Switched to plan mode".to_string(), false)
-                        } else if output == "[MODE_SWITCH:BUILD]" {
-                            self.switch_mode(AgentMode::Build, None);
-                            ("Switched to build mode".to_string(), false)
-                        } else {
-                            (output, false)
-                        }
+            // Writes run alone, so a batch needs at most one snapshot
+            let snapshot = if batch
+                .iter()
+                .any(|(_, name, input)| ToolRegistry::modifies_files(name, input))
+            {
+                self.snapshot_before_tool().await
+            } else {
+                None
+            };
+
+            let executions = batch.iter().map(|(_, name, input)| {
+                self.tools.execute(
+                    name,
+                    (*input).clone(),
+                    self.permission_client.as_ref(),
+                    self.mode,
+                    &self.plan_manager,
+                )
+            });
+            let execution = futures::future::join_all(executions);
+            let results = match &self.cancel_token {
+                Some(token) => tokio::select! {
+                    biased;
+                    () = token.cancelled() => {
+                        batch.iter().map(|_| Err(AgentError::Cancelled)).collect()
                     }
-                    Err(e) => (e.to_string(), true),
-                };
+                    results = execution => results,
+                },
+                None => execution.await,
+            };
 
-                // Emit tool event
-                on_event(ChatEvent::ToolCall {
-                    name: name.clone(),
-                    invocation,
-                    output: content.clone(),
-                    is_error,
-                });
+            if let Some(hash) = snapshot {
+                self.record_tool_changes(hash).await;
+            }
 
-                self.conversation
-                    .add_tool_result(id.clone(), content, is_error);
+            // Results are recorded in call order, however they finished
+            for ((id, name, input), result) in batch.iter().zip(results) {
+                self.finish_tool_call(id, name, input, result, on_event);
             }
         }
 
         Ok(())
     }
 
+    /// Snapshot the project before a tool changes it, returning the tree hash.
+    ///
+    /// Best-effort: a failed snapshot is logged and the tool runs anyway.
+    async fn snapshot_before_tool(&self) -> Option<String> {
+        let snapshots = self.snapshots.clone()?;
+        // Snapshots shell out to git, which can take a while on big trees
+        let snapshot = tokio::task::spawn_blocking(move || snapshots.track())
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|snapshot| snapshot);
+        match snapshot {
+            Ok(snapshot) => Some(snapshot.hash),
+            Err(e) => {
+                tracing::warn!("failed to snapshot before tool: {e}");
+                None
+            }
+        }
+    }
+
+    /// Record the files changed since `hash` in the session's undo journal.
+    async fn record_tool_changes(&mut self, hash: String) {
+        let Some(snapshots) = self.snapshots.clone() else {
+            return;
+        };
+        if self.session_manager.is_none() || self.current_session_id.is_none() {
+            return;
+        }
+
+        // Snapshot the result too, so undo can tell if the files changed since
+        let changes = tokio::task::spawn_blocking(move || {
+            let patch = snapshots.patch(&hash)?;
+            let after = if patch.files.is_empty() {
+                None
+            } else {
+                Some(snapshots.track()?.hash)
+            };
+            anyhow::Ok((patch, after))
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|changes| changes);
+        let (patch, after) = match changes {
+            Ok((patch, _)) if patch.files.is_empty() => return,
+            Ok(changes) => changes,
+            Err(e) => {
+                tracing::warn!("failed to diff snapshot: {e}");
+                return;
+            }
+        };
+        let (Some(manager), Some(session_id)) = (&self.session_manager, &self.current_session_id)
+        else {
+            return;
+        };
+        let turn = self
+            .undo_turn
+            .get_or_insert_with(|| ulid::Ulid::new().to_string());
+        if let Err(e) = manager.record_turn_change(session_id, turn, patch, after) {
+            tracing::warn!("failed to record tool changes: {e}");
+        }
+    }
+
+    /// Apply a tool result to the agent state and conversation.
+    fn finish_tool_call<F>(
+        &mut self,
+        id: &str,
+        name: &str,
+        input: &serde_json::Value,
+        result: Result<String>,
+        on_event: &mut F,
+    ) where
+        F: FnMut(ChatEvent),
+    {
+        let (content, is_error) = match result {
+            Ok(output) => {
+                if output == "[MODE_SWITCH:PLAN]" {
+                    self.switch_mode(AgentMode::Plan, None);
+                    on_event(ChatEvent::PlanPhase(self.plan_phase));
+                    ("Switched to plan mode".to_string(), false)
+                } else if output == "[MODE_SWITCH:BUILD]" {
+                    self.switch_mode(AgentMode::Build, None);
+                    on_event(ChatEvent::PlanPhase(None));
+                    ("Switched to build mode".to_string(), false)
+                } else {
+                    (output, false)
+                }
+            }
+            Err(e) => {
+                // Declined plan_exit returns to drafting
+                if name == "plan_exit" && self.mode == AgentMode::Plan {
+                    let phase = if self.plan_written() {
+                        PlanPhase::Drafting
+                    } else {
+                        PlanPhase::Exploring
+                    };
+                    self.set_plan_phase(Some(phase), on_event);
+                }
+                (e.to_string(), true)
+            }
+        };
+
+        // Remember the plan as written, so manual edits can be detected later
+        if !is_error && self.mode == AgentMode::Plan && self.writes_plan_file(name, input) {
+            self.record_plan_content();
+        }
+
+        // Emit tool event
+        on_event(ChatEvent::ToolCall {
+            name: name.to_string(),
+            invocation: format_tool_invocation(name, input),
+            output: content.clone(),
+            is_error,
+        });
+
+        self.conversation
+            .add_tool_result(id.to_string(), content, is_error);
+    }
+
+    /// Update the plan phase, emitting an event if it changed.
+    fn set_plan_phase<F>(&mut self, phase: Option<PlanPhase>, on_event: &mut F)
+    where
+        F: FnMut(ChatEvent),
+    {
+        if let Some(phase) = phase {
+            if self.plan_phase != Some(phase) {
+                self.plan_phase = Some(phase);
+                on_event(ChatEvent::PlanPhase(Some(phase)));
+            }
+        }
+    }
+
+    /// Whether a tool call writes to the plan file.
+    fn writes_plan_file(&self, tool: &str, input: &serde_json::Value) -> bool {
+        matches!(tool, "write_file" | "edit_file" | "multi_edit")
+            && input
+                .get("path")
+                .and_then(|v| v.as_str())
+                .is_some_and(|path| self.plan_manager.is_plan_path(std::path::Path::new(path)))
+    }
+
+    /// Remember the plan file contents as written by the agent.
+    fn record_plan_content(&mut self) {
+        self.plan_content = self
+            .plan_path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok());
+    }
+
+    /// Check whether the current plan file has been written.
+    fn plan_written(&self) -> bool {
+        self.plan_path.as_ref().is_some_and(|p| p.exists())
+    }
+
     /// Clear conversation history.
     pub fn clear(&mut self) {
         self.conversation.clear();
     }
 
+    /// Branch the conversation just before the last prompt so it can be
+    /// sent again.
+    ///
+    /// Like [`Self::branch_before_prompt`], the previous answer stays in the
+    /// original session. Returns the ID and text of the prompt, or `None` if
+    /// there is none.
+    pub fn branch_before_last_prompt(&mut self) -> Option<(String, String)> {
+        let prompt_id = self.conversation.last_prompt()?.1.to_string();
+        let prompt = self.branch_before_prompt(&prompt_id)?;
+        Some((prompt_id, prompt))
+    }
+
+    /// Branch the conversation just before an earlier prompt so it can be
+    /// edited and sent again.
+    ///
+    /// The current session is forked up to the prompt sent as `prompt_id`
+    /// and the fork becomes the active session, leaving the original branch
+    /// intact. Returns the removed prompt, or `None` if the prompt is no
+    /// longer in the conversation (compacted away, say).
+    pub fn branch_before_prompt(&mut self, prompt_id: &str) -> Option<String> {
+        let index = self.conversation.prompt_position(prompt_id)?;
+        let prompt = self.conversation.messages()[index].content.text();
+        self.conversation.truncate_to(index);
+        if let Err(e) = self.fork_session_before_prompt(prompt_id) {
+            tracing::warn!("failed to fork session for edited prompt: {e}");
+        }
+        Some(prompt)
+    }
+
+    /// Switch to a fork of the current session that ends just before a prompt
+    fn fork_session_before_prompt(&mut self, prompt_id: &str) -> anyhow::Result<()> {
+        let (Some(manager), Some(session_id)) = (&self.session_manager, &self.current_session_id)
+        else {
+            return Ok(());
+        };
+
+        let messages = manager.list_messages(session_id)?;
+        let start = messages
+            .iter()
+            .position(|msg| msg.id() == prompt_id)
+            .ok_or_else(|| anyhow::anyhow!("prompt not found in session"))?;
+        let fork = if let Some(previous) = start.checked_sub(1) {
+            manager.fork_session(session_id, messages[previous].id())?
+        } else {
+            let mut fork = manager.create_session()?;
+            fork.system_append.clone_from(&self.system_append);
+            fork.forked_from = Some(session_id.clone());
+            manager.update_session(&fork)?;
+            fork
+        };
+
+        tracing::info!(from = %session_id, to = %fork.id, "forked session for edited prompt");
+        self.current_session_id = Some(fork.id);
+        self.tools.set_session_id(self.current_session_id.clone());
+        Ok(())
+    }
+
     /// Save conversation history to the default path.
     ///
     /// # Errors
@@ -1010,6 +2211,8 @@ impl Agent {
     /// Load conversation history from the default path.
     ///
     /// Preserves the current system prompt while loading message history.
+    /// A history file that doesn't parse is moved aside to `<file>.bak` and
+    /// the conversation starts empty.
     ///
     /// # Errors
     ///
@@ -1017,12 +2220,28 @@ impl Agent {
     pub fn load_history(&mut self) -> Result<()> {
         let path =
             crate::config::Config::history_path().map_err(|e| AgentError::Config(e.to_string()))?;
+        self.load_history_from(&path)
+    }
 
+    /// Load conversation history from `path`, backing up a malformed file.
+    fn load_history_from(&mut self, path: &Path) -> Result<()> {
         // Preserve current system prompt (fresh context)
         let current_system = self.conversation.system().map(String::from);
 
-        self.conversation =
-            Conversation::load(&path).map_err(|e| AgentError::Config(e.to_string()))?;
+        self.conversation = match Conversation::load(path) {
+            Ok(conversation) => conversation,
+            Err(e) if e.is::<serde_json::Error>() => {
+                let mut backup = path.as_os_str().to_owned();
+                backup.push(".bak");
+                std::fs::rename(path, &backup).map_err(|e| AgentError::Config(e.to_string()))?;
+                tracing::warn!(
+                    backup = %Path::new(&backup).display(),
+                    "conversation history is malformed ({e}), starting fresh"
+                );
+                Conversation::new()
+            }
+            Err(e) => return Err(AgentError::Config(e.to_string())),
+        };
 
         // Restore fresh system prompt
         if let Some(system) = current_system {
@@ -1056,12 +2275,26 @@ impl Agent {
         &self.model
     }
 
-    /// Set the model name.
+    /// Get the reasoning effort, if reasoning is on.
+    #[must_use]
+    pub const fn reasoning_effort(&self) -> Option<ReasoningEffort> {
+        self.reasoning_effort
+    }
+
+    /// Set the reasoning effort, or turn reasoning off with `None`.
+    ///
+    /// Providers whose model doesn't reason ignore it.
+    pub const fn set_reasoning_effort(&mut self, effort: Option<ReasoningEffort>) {
+        self.reasoning_effort = effort;
+    }
+
+    /// Set the model name, resolving aliases to the full model ID.
     ///
     /// Also updates the system prompt and adds a context message to help
     /// the new model understand it has taken over the conversation.
     pub fn set_model(&mut self, model: impl Into<String>) {
-        let old_model = std::mem::replace(&mut self.model, model.into());
+        let model = self.resolve_model(&model.into());
+        let old_model = std::mem::replace(&mut self.model, model);
         self.update_model_in_system_prompt();
 
         // Add context message if there's conversation history and model changed
@@ -1097,10 +2330,35 @@ impl Agent {
         }
     }
 
+    /// Replace the persona instructions in the system prompt with `prompt`.
+    ///
+    /// The model identity and project context are kept. Without a persona
+    /// yet, the instructions go right after the identity line.
+    pub fn set_persona(&mut self, prompt: impl Into<String>) {
+        let prompt = prompt.into();
+        let system = self.conversation.system().unwrap_or_default();
+        let updated = match self
+            .persona_prompt
+            .as_deref()
+            .filter(|old| !old.is_empty() && system.contains(old))
+        {
+            Some(old) => system.replacen(old, &prompt, 1),
+            None if system.is_empty() => prompt.clone(),
+            None if system.starts_with("You are ") => match system.split_once("\n\n") {
+                Some((identity, rest)) => format!("{identity}\n\n{prompt}\n\n{rest}"),
+                None => format!("{system}\n\n{prompt}"),
+            },
+            None => format!("{prompt}\n\n{system}"),
+        };
+        self.conversation.set_system(updated);
+        self.persona_prompt = Some(prompt);
+    }
+
     /// Set the LLM provider.
     pub fn set_provider(&mut self, provider: Box<dyn LlmProvider>) {
         tracing::info!(provider = %provider.name(), "switched provider");
         self.provider = provider;
+        self.provider_key = None;
     }
 
     /// Get the current provider name.
@@ -1109,6 +2367,22 @@ impl Agent {
         self.provider.name()
     }
 
+    /// Record the config key the current provider was created from.
+    ///
+    /// Custom providers share a backend's name, so pricing entries keyed by
+    /// the config name need this to match.
+    pub fn set_provider_key(&mut self, key: &str) {
+        self.provider_key = (key != self.provider_name()).then(|| key.to_string());
+    }
+
+    /// Get the current provider's config key, or its name if it has none.
+    #[must_use]
+    pub fn provider_key(&self) -> &str {
+        self.provider_key
+            .as_deref()
+            .unwrap_or_else(|| self.provider_name())
+    }
+
     /// Get the current plan file path, if any.
     #[must_use]
     pub const fn plan_path(&self) -> Option<&PathBuf> {
@@ -1128,9 +2402,12 @@ impl Agent {
             AgentMode::Plan => {
                 let slug = slug.unwrap_or("plan");
                 self.plan_path = Some(self.plan_manager.new_plan_path(slug));
+                self.plan_content = None;
+                self.plan_phase = Some(PlanPhase::Exploring);
                 self.inject_plan_mode_context();
             }
             AgentMode::Build => {
+                self.plan_phase = None;
                 self.inject_build_mode_context();
             }
         }
@@ -1176,18 +2453,15 @@ Plan file location: {plan_path}
         }
     }
 
-    /// Inject build mode context with plan reference.
+    /// Inject build mode context with the current plan.
+    ///
+    /// The plan file is re-read so manual edits made during plan mode are
+    /// followed; if it is missing, the plan as last written by the agent is used.
     fn inject_build_mode_context(&mut self) {
         if let Some(plan_path) = &self.plan_path {
-            let build_context = format!(
-                r"
-## Active Plan
-
-You have an approved implementation plan at: {}
-Follow this plan. Refer back to it as you work.
-",
-                plan_path.display()
-            );
+            let on_disk = std::fs::read_to_string(plan_path).ok();
+            let build_context =
+                build_mode_context(plan_path, on_disk.as_deref(), self.plan_content.as_deref());
 
             // Append to existing system prompt
             if let Some(existing) = self.conversation.system() {
@@ -1199,6 +2473,12 @@ Follow this plan. Refer back to it as you work.
         }
     }
 
+    /// Get the current plan-mode workflow phase, if in plan mode.
+    #[must_use]
+    pub const fn plan_phase(&self) -> Option<PlanPhase> {
+        self.plan_phase
+    }
+
     /// Get a reference to the plan manager.
     #[must_use]
     pub const fn plan_manager(&self) -> &PlanManager {
@@ -1206,6 +2486,81 @@ Follow this plan. Refer back to it as you work.
     }
 }
 
+/// Build mode instructions for a plan, preferring the on-disk version
+fn build_mode_context(
+    path: &std::path::Path,
+    on_disk: Option<&str>,
+    written: Option<&str>,
+) -> String {
+    let mut context = format!(
+        r"
+## Active Plan
+
+You have an approved implementation plan at: {}
+Follow this plan. Refer back to it as you work.
+",
+        path.display()
+    );
+
+    let plan = match (on_disk, written) {
+        (Some(disk), Some(written)) if disk != written => {
+            context.push_str(
+                "The user edited the plan after you wrote it. Follow their version below.\n",
+            );
+            Some(disk)
+        }
+        (Some(disk), _) => Some(disk),
+        (None, Some(written)) => {
+            context.push_str("The plan file is missing. This is the last version you wrote.\n");
+            Some(written)
+        }
+        (None, None) => None,
+    };
+
+    if let Some(plan) = plan.filter(|p| !p.trim().is_empty()) {
+        use std::fmt::Write;
+        let _ = write!(context, "\n<plan>\n{}\n</plan>\n", plan.trim_end());
+    }
+    context
+}
+
+/// Join the rendered system prompt sections in order, skipping empty ones
+fn assemble_system_prompt(
+    sections: &[PromptSection],
+    mut render: impl FnMut(PromptSection) -> Option<String>,
+) -> String {
+    sections
+        .iter()
+        .filter_map(|&section| render(section))
+        .filter(|text| !text.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Whether a streaming error looks like a dropped connection worth retrying
+const fn is_stream_disconnect(error: &AgentError) -> bool {
+    matches!(
+        error,
+        AgentError::Http(_) | AgentError::Transport(_) | AgentError::StreamEnded
+    )
+}
+
+/// Build request messages, resuming from a partial response if there is one
+fn continuation_messages(messages: &[Message], partial: &[ContentBlock]) -> Vec<Message> {
+    let mut messages = messages.to_vec();
+    if !partial.is_empty() {
+        messages.push(Message {
+            role: Role::Assistant,
+            content: Content::Blocks(partial.to_vec()),
+        });
+        messages.push(Message {
+            role: Role::User,
+            content: Content::Text(STREAM_CONTINUE_PROMPT.to_string()),
+        });
+    }
+    messages
+}
+
 /// Format tool input for display in the UI
 fn format_tool_invocation(name: &str, input: &serde_json::Value) -> String {
     const MAX_LEN: usize = 60;
@@ -1249,3 +2604,927 @@ fn format_tool_invocation(name: &str, input: &serde_json::Value) -> String {
         raw
     }
 }
+
+/// Text replies and tool calls in assistant `messages`, as the TUI shows them
+fn reply_count(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .filter(|message| message.role == Role::Assistant)
+        .map(|message| match &message.content {
+            Content::Text(text) => usize::from(!text.trim().is_empty()),
+            Content::Blocks(blocks) => blocks
+                .iter()
+                .filter(|block| match block {
+                    ContentBlock::Text { text } => !text.trim().is_empty(),
+                    ContentBlock::ToolUse { .. } => true,
+                    _ => false,
+                })
+                .count(),
+        })
+        .sum()
+}
+
+/// Render messages as plain text for a compaction summary
+///
+/// Tool output is cut short, since the summary only needs what happened.
+fn transcript(messages: &[Message]) -> String {
+    use std::fmt::Write;
+    const MAX_RESULT_CHARS: usize = 500;
+
+    let mut text = String::new();
+    for message in messages {
+        let role = match message.role {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+        };
+        let _ = writeln!(text, "\n## {role}");
+
+        match &message.content {
+            Content::Text(t) => {
+                text.push_str(t);
+                text.push('\n');
+            }
+            Content::Blocks(blocks) => {
+                for block in blocks {
+                    match block {
+                        ContentBlock::Text { text: t } => {
+                            text.push_str(t);
+                            text.push('\n');
+                        }
+                        ContentBlock::ToolUse { name, input, .. } => {
+                            let invocation = format_tool_invocation(name, input);
+                            let _ = writeln!(text, "[Tool: {name} {invocation}]");
+                        }
+                        ContentBlock::ToolResult { content, .. } => {
+                            let truncated: String =
+                                content.chars().take(MAX_RESULT_CHARS).collect();
+                            let _ = writeln!(text, "[Result: {truncated}]");
+                        }
+                        ContentBlock::Thinking { .. } => {}
+                    }
+                }
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+
+    use super::*;
+
+    /// Provider that replays scripted streams and records each request
+    struct ScriptedProvider {
+        streams: Mutex<VecDeque<Vec<Result<CompletionEvent>>>>,
+        requests: Arc<Mutex<Vec<CompletionRequest>>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+            self.requests.lock().push(request);
+            let events = self.streams.lock().pop_front().unwrap_or_default();
+            Ok(Box::pin(futures::stream::iter(events)))
+        }
+    }
+
+    /// Session manager for a throwaway project, storing under `dir/storage`
+    fn temp_manager(dir: &Path, worktree: &Path) -> SessionManager {
+        let project = crate::core::project::Project {
+            id: "test-project".to_string(),
+            worktree: worktree.to_path_buf(),
+            vcs: None,
+            time: crate::core::project::ProjectTime {
+                created: 0,
+                initialized: 0,
+            },
+        };
+        SessionManager::new(
+            crate::core::storage::Storage::with_root(dir.join("storage")),
+            project,
+        )
+    }
+
+    #[tokio::test]
+    async fn dropped_stream_reconnects_and_stitches_response() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([
+                vec![
+                    Ok(CompletionEvent::TextDelta("Hel".to_string())),
+                    Err(AgentError::Transport("connection reset".to_string())),
+                ],
+                vec![
+                    Ok(CompletionEvent::TextDelta("lo".to_string())),
+                    Ok(CompletionEvent::Done {
+                        stop_reason: Some(StopReason::EndTurn),
+                        usage: None,
+                    }),
+                ],
+            ])),
+            requests: Arc::clone(&requests),
+        };
+        let mut agent = Agent::new(Box::new(provider), "test-model", 1024);
+
+        let mut reconnects = 0;
+        let response = agent
+            .chat_with_events("hi", |event| {
+                if matches!(event, ChatEvent::Reconnecting { .. }) {
+                    reconnects += 1;
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response, "Hello");
+        assert_eq!(reconnects, 1);
+
+        let requests = requests.lock();
+        let resumed = &requests[1].messages;
+        assert_eq!(resumed[resumed.len() - 2].content.text(), "Hel");
+        assert_eq!(
+            resumed.last().unwrap().content.text(),
+            STREAM_CONTINUE_PROMPT
+        );
+
+        // The stitched response is stored as a single assistant message
+        let last = agent.conversation.messages().last().unwrap();
+        assert_eq!(last.role, Role::Assistant);
+        assert_eq!(last.content.text(), "Hello");
+    }
+
+    #[tokio::test]
+    async fn reasoning_is_surfaced_but_kept_out_of_the_answer() {
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([vec![
+                Ok(CompletionEvent::ReasoningDelta("hmm".to_string())),
+                Ok(CompletionEvent::TextDelta("42".to_string())),
+            ]])),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut agent = Agent::new(Box::new(provider), "deepseek-reasoner", 1024);
+
+        let mut reasoning = String::new();
+        let response = agent
+            .chat_with_events("hi", |event| {
+                if let ChatEvent::Reasoning(text) = event {
+                    reasoning.push_str(&text);
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(reasoning, "hmm");
+        assert_eq!(response, "42");
+    }
+
+    #[tokio::test]
+    async fn reasoning_effort_is_sent_and_thinking_is_kept() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([vec![
+                Ok(CompletionEvent::ReasoningDelta("hmm".to_string())),
+                Ok(CompletionEvent::ContentBlockDone {
+                    index: 0,
+                    block: ContentBlock::Thinking {
+                        thinking: "hmm".to_string(),
+                        signature: "sig".to_string(),
+                    },
+                }),
+                Ok(CompletionEvent::TextDelta("42".to_string())),
+            ]])),
+            requests: requests.clone(),
+        };
+        let mut agent = Agent::new(Box::new(provider), "claude-sonnet-4-20250514", 32_000);
+        agent.set_reasoning_effort(Some(ReasoningEffort::High));
+
+        let response = agent.chat_with_events("hi", |_| {}).await.unwrap();
+        assert_eq!(response, "42");
+        assert_eq!(
+            requests.lock()[0].reasoning_effort,
+            Some(ReasoningEffort::High)
+        );
+
+        let Content::Blocks(blocks) = &agent.conversation.messages().last().unwrap().content else {
+            panic!("expected blocks");
+        };
+        assert!(
+            matches!(&blocks[0], ContentBlock::Thinking { signature, .. } if signature == "sig")
+        );
+        assert!(matches!(&blocks[1], ContentBlock::Text { text } if text == "42"));
+    }
+
+    /// Streams one text chunk, then hangs until dropped.
+    struct StallingProvider;
+
+    #[async_trait]
+    impl LlmProvider for StallingProvider {
+        fn name(&self) -> &'static str {
+            "stalling"
+        }
+
+        async fn stream(&self, _request: CompletionRequest) -> Result<CompletionStream> {
+            let first =
+                futures::stream::iter(vec![Ok(CompletionEvent::TextDelta("partial".to_string()))]);
+            Ok(Box::pin(first.chain(futures::stream::pending())))
+        }
+    }
+
+    #[tokio::test]
+    async fn read_only_tools_run_as_a_batch_in_call_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let read = |name: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, name).unwrap();
+            ContentBlock::ToolUse {
+                id: name.to_string(),
+                name: "read_file".to_string(),
+                input: serde_json::json!({ "path": path }),
+            }
+        };
+        let blocks = vec![
+            read("a"),
+            read("b"),
+            ContentBlock::ToolUse {
+                id: "todo".to_string(),
+                name: "todo_read".to_string(),
+                input: serde_json::json!({}),
+            },
+            read("c"),
+        ];
+
+        let mut agent = Agent::new(Box::new(StallingProvider), "test-model", 1024);
+        let mut events = Vec::new();
+        agent
+            .handle_tool_use_events(&blocks, &mut |event| match event {
+                ChatEvent::ToolStart { name } => events.push(format!("start {name}")),
+                ChatEvent::ToolCall { output, .. } => events.push(format!("done {output}")),
+                _ => {}
+            })
+            .await
+            .unwrap();
+
+        // Both reads start before either result is recorded
+        assert_eq!(
+            events[..4],
+            ["start read_file", "start read_file", "done a", "done b"]
+        );
+        assert_eq!(events[4], "start todo_read");
+        assert_eq!(events[6..], ["start read_file", "done c"]);
+
+        let results: Vec<&str> = agent
+            .conversation
+            .messages()
+            .iter()
+            .filter_map(|message| match &message.content {
+                Content::Blocks(blocks) => match blocks.first() {
+                    Some(ContentBlock::ToolResult { tool_use_id, .. }) => {
+                        Some(tool_use_id.as_str())
+                    }
+                    _ => None,
+                },
+                Content::Text(_) => None,
+            })
+            .collect();
+        assert_eq!(results, ["a", "b", "todo", "c"]);
+    }
+
+    #[tokio::test]
+    async fn file_writes_are_journaled_for_undo() {
+        let dir = tempfile::tempdir().unwrap();
+        let worktree = dir.path().join("worktree");
+        std::fs::create_dir(&worktree).unwrap();
+        let file = worktree.join("a.txt");
+        std::fs::write(&file, "old").unwrap();
+
+        let manager = temp_manager(dir.path(), &worktree);
+        let session = manager.create_session().unwrap();
+
+        let mut agent = Agent::new(Box::new(StallingProvider), "test-model", 1024);
+        agent.set_snapshot_manager(Some(SnapshotManager::new(
+            dir.path().join("snapshots"),
+            worktree.clone(),
+        )));
+        agent.session_manager = Some(manager);
+        agent.current_session_id = Some(session.id.clone());
+
+        let blocks = vec![ContentBlock::ToolUse {
+            id: "write".to_string(),
+            name: "write_file".to_string(),
+            input: serde_json::json!({ "path": file, "content": "new" }),
+        }];
+        agent
+            .handle_tool_use_events(&blocks, &mut |_| {})
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new");
+
+        let manager = agent.session_manager.as_ref().unwrap();
+        let turn = manager.last_turn_changes(&session.id).unwrap().unwrap();
+        assert_eq!(turn.patches.len(), 1);
+        assert!(turn.patches[0].files.iter().any(|f| f.ends_with("a.txt")));
+        // The post-turn snapshot matches until the file is edited again
+        let snapshots = agent.snapshots.as_ref().unwrap();
+        let after = turn.after.as_deref().unwrap();
+        assert!(snapshots.patch(after).unwrap().files.is_empty());
+        std::fs::write(&file, "edited").unwrap();
+        assert_eq!(snapshots.patch(after).unwrap().files.len(), 1);
+        assert!(
+            snapshots
+                .diff_paths(&turn.patches[0].hash, std::slice::from_ref(&file))
+                .unwrap()
+                .contains("+edited")
+        );
+
+        agent
+            .snapshots
+            .as_ref()
+            .unwrap()
+            .revert(&turn.patches)
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "old");
+    }
+
+    #[tokio::test]
+    async fn tool_calls_and_reasoning_are_persisted_and_resumed() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "hello from disk").unwrap();
+        let manager = || temp_manager(dir.path(), dir.path());
+        let session = manager().create_session().unwrap();
+
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([
+                vec![
+                    Ok(CompletionEvent::ReasoningDelta(
+                        "check the file".to_string(),
+                    )),
+                    Ok(CompletionEvent::TextDelta("Reading it.".to_string())),
+                    Ok(CompletionEvent::ToolUseStart {
+                        index: 1,
+                        id: "t1".to_string(),
+                        name: "read_file".to_string(),
+                    }),
+                    Ok(CompletionEvent::ContentBlockDone {
+                        index: 1,
+                        block: ContentBlock::ToolUse {
+                            id: "t1".to_string(),
+                            name: "read_file".to_string(),
+                            input: serde_json::json!({ "path": file }),
+                        },
+                    }),
+                    Ok(CompletionEvent::Done {
+                        stop_reason: Some(StopReason::ToolUse),
+                        usage: None,
+                    }),
+                ],
+                vec![Ok(CompletionEvent::TextDelta("It says hello.".to_string()))],
+            ])),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut agent = Agent::new(Box::new(provider), "test-model", 1024);
+        agent.session_manager = Some(manager());
+        agent.current_session_id = Some(session.id.clone());
+        agent.chat_with_events("read notes", |_| {}).await.unwrap();
+
+        let manager = manager();
+        let messages = manager.list_messages(&session.id).unwrap();
+        let parts = manager.list_parts(messages[1].id()).unwrap();
+        assert!(matches!(&parts[0], Part::Reasoning(r) if r.text == "check the file"));
+        assert!(matches!(&parts[1], Part::Text(t) if t.text == "Reading it."));
+        let Part::Tool(tool) = &parts[2] else {
+            panic!("expected a tool part");
+        };
+        assert_eq!(tool.tool, "read_file");
+        assert!(tool.output().unwrap().contains("hello from disk"));
+
+        // A resumed session replays the call and its result to the model
+        let mut resumed = Agent::new(Box::new(StallingProvider), "test-model", 1024);
+        resumed.session_manager = Some(manager);
+        resumed.load_session_into_conversation(&session.id).unwrap();
+        let roles: Vec<Role> = resumed
+            .conversation
+            .messages()
+            .iter()
+            .map(|m| m.role)
+            .collect();
+        assert_eq!(
+            roles,
+            [Role::User, Role::Assistant, Role::User, Role::Assistant]
+        );
+        let (output, is_error) = resumed.conversation.tool_result("t1").unwrap();
+        assert!(output.contains("hello from disk") && !is_error);
+    }
+
+    #[tokio::test]
+    async fn titles_use_the_session_model_and_never_go_blank() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = temp_manager(dir.path(), dir.path());
+        let session = manager.create_session().unwrap();
+        manager
+            .set_session_title(&session.id, "Good title")
+            .unwrap();
+
+        let reply = |text: &str| {
+            vec![
+                Ok(CompletionEvent::TextDelta(text.to_string())),
+                Ok(CompletionEvent::Done {
+                    stop_reason: Some(StopReason::EndTurn),
+                    usage: None,
+                }),
+            ]
+        };
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([
+                vec![Err(AgentError::Api {
+                    status: 404,
+                    message: "model not found".to_string(),
+                })],
+                reply("\"\""),
+                reply("\"Fix the parser\""),
+            ])),
+            requests: Arc::clone(&requests),
+        };
+        let mut agent = Agent::new(Box::new(provider), "gpt-test", 1024);
+        agent.session_manager = Some(manager);
+        agent.current_session_id = Some(session.id.clone());
+        let title = |agent: &Agent| {
+            let manager = agent.session_manager.as_ref().unwrap();
+            manager.get_session(&session.id).unwrap().title
+        };
+
+        // Stream errors and empty replies fail without touching the title
+        assert!(matches!(
+            agent.generate_title("fix it").await,
+            Err(AgentError::Api { status: 404, .. })
+        ));
+        assert!(agent.generate_title("fix it").await.is_err());
+        assert_eq!(title(&agent), "Good title");
+
+        assert_eq!(
+            agent.generate_title("fix it").await.unwrap(),
+            "Fix the parser"
+        );
+        assert_eq!(title(&agent), "Fix the parser");
+        assert!(requests.lock().iter().all(|r| r.model == "gpt-test"));
+    }
+
+    #[tokio::test]
+    async fn cancelling_stops_the_stream_and_persists_partial_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = temp_manager(dir.path(), dir.path());
+        let session = manager.create_session().unwrap();
+
+        let mut agent = Agent::new(Box::new(StallingProvider), "test-model", 1024);
+        agent.session_manager = Some(manager);
+        agent.current_session_id = Some(session.id.clone());
+        let cancel = CancellationToken::new();
+        agent.set_cancellation_token(cancel.clone());
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            agent.chat_with_events("hi", |event| {
+                if matches!(event, ChatEvent::Text(_)) {
+                    cancel.cancel();
+                }
+            }),
+        )
+        .await
+        .expect("cancelled turn should return promptly");
+        assert!(matches!(result, Err(AgentError::Cancelled)));
+
+        let manager = agent.session_manager.as_ref().unwrap();
+        let messages = manager.list_messages(&session.id).unwrap();
+        let assistant = messages
+            .iter()
+            .find(|m| matches!(m, SessionMessage::Assistant(_)))
+            .expect("partial response persisted");
+        let parts = manager.list_parts(assistant.id()).unwrap();
+        assert!(matches!(&parts[0], Part::Text(t) if t.text == "partial"));
+    }
+
+    #[test]
+    fn context_window_prefers_override_then_config_then_built_in() {
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::new()),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut agent = Agent::new(Box::new(provider), "claude-test", 1024);
+        assert_eq!(agent.context_window(), Some(200_000));
+
+        agent.set_model("local-model");
+        assert_eq!(agent.context_window(), None);
+        agent.set_context_windows(HashMap::from([("scripted".to_string(), 8_192)]));
+        assert_eq!(agent.context_window(), Some(8_192));
+        agent.set_context_windows(HashMap::from([
+            ("scripted".to_string(), 8_192),
+            ("local-model".to_string(), 32_768),
+        ]));
+        assert_eq!(agent.context_window(), Some(32_768));
+
+        // Custom providers are looked up by their config key
+        agent.set_model("other-model");
+        agent.set_provider_key("local-llama");
+        assert_eq!(agent.context_window(), None);
+        agent.set_context_windows(HashMap::from([("local-llama".to_string(), 65_536)]));
+        assert_eq!(agent.context_window(), Some(65_536));
+
+        agent.set_auto_compaction(Some(1_000), 0.5);
+        assert_eq!(agent.context_window(), Some(1_000));
+    }
+
+    #[test]
+    fn malformed_history_is_backed_up_and_replaced() {
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::new()),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut agent = Agent::with_system(Box::new(provider), "claude-test", 1024, "system");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("conversation.json");
+        std::fs::write(&path, r#"{"messages": [{"role": "user", "con"#).unwrap();
+
+        agent.load_history_from(&path).unwrap();
+        assert!(agent.conversation_is_empty());
+        assert_eq!(agent.conversation.system(), Some("system"));
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("conversation.json.bak")).unwrap(),
+            r#"{"messages": [{"role": "user", "con"#
+        );
+    }
+
+    #[test]
+    fn set_persona_replaces_only_the_persona_section() {
+        let provider = || ScriptedProvider {
+            streams: Mutex::new(VecDeque::new()),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let sections = [PromptSection::Identity, PromptSection::Persona];
+        let mut agent = Agent::with_prompt_sections(
+            Box::new(provider()),
+            "claude-test",
+            1024,
+            Some("You are Orin."),
+            &sections,
+        );
+        agent.conversation.set_system(format!(
+            "{}\n\n<project>context</project>",
+            agent.conversation.system().unwrap()
+        ));
+
+        agent.set_persona("You are Nova.");
+        assert_eq!(
+            agent.conversation.system(),
+            Some(
+                "You are claude-test, accessed through the Omni CLI.\n\n\
+                 You are Nova.\n\n<project>context</project>"
+            )
+        );
+
+        // No persona section yet: inserted after the identity line
+        let mut agent =
+            Agent::with_prompt_sections(Box::new(provider()), "claude-test", 1024, None, &sections);
+        agent.set_persona("You are Nova.");
+        agent.set_persona("You are Ada.");
+        assert_eq!(
+            agent.conversation.system(),
+            Some("You are claude-test, accessed through the Omni CLI.\n\nYou are Ada.")
+        );
+    }
+
+    #[test]
+    fn set_model_resolves_aliases() {
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::new()),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut agent = Agent::new(Box::new(provider), "sonnet", 1024);
+        agent.set_model_aliases(HashMap::from([(
+            "sonnet".to_string(),
+            "claude-sonnet-4-20250514".to_string(),
+        )]));
+        assert_eq!(agent.model(), "claude-sonnet-4-20250514");
+
+        agent.set_model("Sonnet");
+        assert_eq!(agent.model(), "claude-sonnet-4-20250514");
+        agent.set_model("unknown-model");
+        assert_eq!(agent.model(), "unknown-model");
+    }
+
+    #[tokio::test]
+    async fn long_history_is_compacted_before_the_request() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let reply = |text: &str| {
+            vec![
+                Ok(CompletionEvent::TextDelta(text.to_string())),
+                Ok(CompletionEvent::Done {
+                    stop_reason: Some(StopReason::EndTurn),
+                    usage: None,
+                }),
+            ]
+        };
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([reply("the summary"), reply("answer")])),
+            requests: Arc::clone(&requests),
+        };
+        let mut agent = Agent::new(Box::new(provider), "test-model", 1024);
+        agent.set_auto_compaction(Some(100), 0.5);
+        for i in 0..4 {
+            agent
+                .conversation
+                .add_user_message(format!("question {i} {}", "x".repeat(40)));
+            agent
+                .conversation
+                .add_assistant_message(format!("answer {i}"));
+        }
+
+        let mut compacted = None;
+        let text = agent
+            .chat_with_events("next", |event| {
+                if let ChatEvent::Compacted { messages } = event {
+                    compacted = Some(messages);
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(text, "answer");
+        assert_eq!(compacted, Some(5));
+        let requests = requests.lock();
+        assert!(
+            requests[0].messages[0]
+                .content
+                .text()
+                .contains("question 0")
+        );
+        let sent = &requests[1].messages;
+        assert_eq!(sent.len(), 5);
+        assert!(sent[0].content.text().contains("the summary"));
+        assert_eq!(sent[4].content.text(), "next");
+    }
+
+    #[tokio::test]
+    async fn compact_summarizes_regardless_of_threshold() {
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([vec![
+                Ok(CompletionEvent::TextDelta("the summary".to_string())),
+                Ok(CompletionEvent::Done {
+                    stop_reason: Some(StopReason::EndTurn),
+                    usage: None,
+                }),
+            ]])),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut agent = Agent::new(Box::new(provider), "test-model", 1024);
+        agent.set_auto_compaction(None, 0.0);
+        agent.conversation.add_user_message("short");
+        assert_eq!(agent.compact().await.unwrap(), None);
+
+        for i in 0..4 {
+            agent
+                .conversation
+                .add_assistant_message(format!("answer {i}"));
+            agent
+                .conversation
+                .add_user_message(format!("question {i} {}", "x".repeat(40)));
+        }
+        let compacted = agent.compact().await.unwrap().unwrap();
+        assert_eq!(compacted.messages, 5);
+        assert_eq!(compacted.summary, "the summary");
+        // Untracked prompts leave only the replies to count from the end
+        assert_eq!(compacted.kept_prompt, None);
+        assert_eq!(compacted.kept_replies, 2);
+        assert!(compacted.tokens_after < compacted.tokens_before);
+        assert_eq!(agent.conversation.messages().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn cancelling_stops_a_compaction_summary() {
+        let mut agent = Agent::new(Box::new(StallingProvider), "test-model", 1024);
+        for i in 0..4 {
+            agent.conversation.add_user_message(format!("question {i}"));
+            agent
+                .conversation
+                .add_assistant_message(format!("answer {i}"));
+        }
+        let cancel = CancellationToken::new();
+        agent.set_cancellation_token(cancel.clone());
+        cancel.cancel();
+
+        let result = tokio::time::timeout(std::time::Duration::from_secs(5), agent.compact())
+            .await
+            .expect("cancelled compaction should return promptly");
+        assert!(matches!(result, Err(AgentError::Cancelled)));
+        assert_eq!(agent.conversation.messages().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn branch_before_prompt_finds_the_prompt_by_id() {
+        let done = || {
+            vec![Ok(CompletionEvent::Done {
+                stop_reason: Some(StopReason::EndTurn),
+                usage: None,
+            })]
+        };
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([done(), done()])),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut agent = Agent::new(Box::new(provider), "claude-test", 1024);
+        agent
+            .chat_prompt_with_events("p1", "first", |_| {})
+            .await
+            .unwrap();
+        // Switching models adds a user message that isn't a prompt
+        agent.set_model("claude-other");
+        agent
+            .chat_prompt_with_events("p2", "second", |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(agent.branch_before_prompt("p2").as_deref(), Some("second"));
+        assert_eq!(agent.branch_before_prompt("p1").as_deref(), Some("first"));
+        assert!(agent.conversation.messages().is_empty());
+        assert!(agent.branch_before_prompt("p2").is_none());
+    }
+
+    #[tokio::test]
+    async fn branch_before_last_prompt_skips_model_switch_notes() {
+        let done = || {
+            vec![Ok(CompletionEvent::Done {
+                stop_reason: Some(StopReason::EndTurn),
+                usage: None,
+            })]
+        };
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([done(), done()])),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut agent = Agent::new(Box::new(provider), "claude-test", 1024);
+        for (id, prompt) in [("p1", "first"), ("p2", "second")] {
+            agent
+                .chat_prompt_with_events(id, prompt, |_| {})
+                .await
+                .unwrap();
+        }
+        // What `/regenerate <model>` does before resending
+        agent.set_model("claude-other");
+
+        assert_eq!(
+            agent.branch_before_last_prompt(),
+            Some(("p2".to_string(), "second".to_string()))
+        );
+        let messages = agent.conversation.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content.text(), "first");
+    }
+
+    #[tokio::test]
+    async fn usage_cost_uses_configured_model_price() {
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([vec![Ok(CompletionEvent::Done {
+                stop_reason: Some(StopReason::EndTurn),
+                usage: Some(Usage {
+                    input_tokens: 1_000_000,
+                    output_tokens: 1_000_000,
+                    ..Usage::default()
+                }),
+            })]])),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut agent = Agent::new(Box::new(provider), "gpt-4o", 1024);
+        agent.set_pricing(default_pricing());
+
+        let mut cost = None;
+        agent
+            .chat_with_events("hi", |event| {
+                if let ChatEvent::Usage { cost_usd, .. } = event {
+                    cost = Some(cost_usd);
+                }
+            })
+            .await
+            .unwrap();
+        assert!((cost.unwrap() - 12.5).abs() < 1e-9);
+
+        // Unpriced models report nothing rather than guessing
+        agent.set_model("unpriced-model");
+        let usage = Usage {
+            input_tokens: 1_000,
+            output_tokens: 1_000,
+            ..Usage::default()
+        };
+        assert!(agent.usage_cost(&usage).abs() < f64::EPSILON);
+
+        // Custom providers are priced by their config key, not their backend
+        agent.set_pricing(HashMap::from([(
+            "local-llama".to_string(),
+            ModelPricing::new(1.0, 1.0),
+        )]));
+        assert!(agent.usage_cost(&usage).abs() < f64::EPSILON);
+        agent.set_provider_key("local-llama");
+        assert!((agent.usage_cost(&usage) - 0.002).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn compaction_falls_back_to_session_model() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::from([
+                vec![Err(AgentError::Api {
+                    status: 404,
+                    message: "model not found".to_string(),
+                })],
+                vec![Ok(CompletionEvent::TextDelta("Summary".to_string()))],
+            ])),
+            requests: Arc::clone(&requests),
+        };
+        let mut agent = Agent::new(Box::new(provider), "session-model", 1024);
+        agent.set_compaction_model(Some("cheap-model".to_string()));
+
+        let summary = agent.generate_compaction_summary("history").await.unwrap();
+        assert_eq!(summary, "Summary");
+
+        let requests = requests.lock();
+        assert_eq!(requests[0].model, "cheap-model");
+        assert_eq!(requests[1].model, "session-model");
+    }
+
+    #[test]
+    fn preview_request_includes_prompt_without_sending() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let provider = ScriptedProvider {
+            streams: Mutex::new(VecDeque::new()),
+            requests: Arc::clone(&requests),
+        };
+        let agent = Agent::with_system(Box::new(provider), "test-model", 1024, "be brief");
+
+        let request = agent.preview_request("hello");
+        assert_eq!(request.model, "test-model");
+        assert_eq!(request.system.as_deref(), Some("be brief"));
+        assert_eq!(request.messages.len(), 1);
+        assert_eq!(request.messages[0].content.text(), "hello");
+        assert!(request.tools.is_some_and(|tools| !tools.is_empty()));
+
+        assert!(requests.lock().is_empty());
+        assert!(agent.conversation.messages().is_empty());
+    }
+
+    #[test]
+    fn system_prompt_follows_configured_section_order() {
+        let render = |section| match section {
+            PromptSection::Identity => Some("identity".to_string()),
+            PromptSection::Persona => None,
+            PromptSection::Memory => Some(String::new()),
+            PromptSection::Context => Some("context".to_string()),
+        };
+
+        let prompt = assemble_system_prompt(
+            &[
+                PromptSection::Context,
+                PromptSection::Persona,
+                PromptSection::Memory,
+                PromptSection::Identity,
+            ],
+            render,
+        );
+        assert_eq!(prompt, "context\n\nidentity");
+
+        // Unlisted sections are omitted
+        assert_eq!(
+            assemble_system_prompt(&[PromptSection::Identity], render),
+            "identity"
+        );
+    }
+
+    #[test]
+    fn build_mode_context_prefers_edited_plan_file() {
+        let path = std::path::Path::new("/tmp/plan.md");
+
+        let context = build_mode_context(path, Some("1. user step"), Some("1. agent step"));
+        assert!(context.contains("The user edited the plan"));
+        assert!(context.contains("<plan>\n1. user step\n</plan>"));
+        assert!(!context.contains("agent step"));
+
+        let context = build_mode_context(path, None, Some("1. agent step"));
+        assert!(context.contains("The plan file is missing"));
+        assert!(context.contains("1. agent step"));
+
+        let context = build_mode_context(path, Some("same"), Some("same"));
+        assert!(!context.contains("edited") && context.contains("<plan>\nsame\n</plan>"));
+
+        let context = build_mode_context(path, None, None);
+        assert!(context.contains("/tmp/plan.md") && !context.contains("<plan>"));
+    }
+}
@@ -0,0 +1,145 @@
+//! Token pricing for usage cost estimates.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::Usage;
+
+/// Price of writing a prompt to the cache, relative to input.
+const CACHE_WRITE_MULTIPLIER: f64 = 1.25;
+
+/// Price of reading a cached prompt, relative to input.
+const CACHE_READ_MULTIPLIER: f64 = 0.1;
+
+/// Price in US dollars per million tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Price per million input tokens.
+    pub input_per_mtok: f64,
+    /// Price per million output tokens.
+    pub output_per_mtok: f64,
+}
+
+impl ModelPricing {
+    /// Create a price from per-million-token rates.
+    #[must_use]
+    pub const fn new(input_per_mtok: f64, output_per_mtok: f64) -> Self {
+        Self {
+            input_per_mtok,
+            output_per_mtok,
+        }
+    }
+
+    /// Cost in US dollars for a request.
+    #[must_use]
+    pub fn cost(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        f64::from(input_tokens).mul_add(
+            self.input_per_mtok,
+            f64::from(output_tokens) * self.output_per_mtok,
+        ) / 1_000_000.0
+    }
+
+    /// Cost in US dollars for a response's usage, including cached prompt tokens.
+    #[must_use]
+    pub fn usage_cost(&self, usage: &Usage) -> f64 {
+        let cached = f64::from(usage.cache_creation_input_tokens).mul_add(
+            CACHE_WRITE_MULTIPLIER,
+            f64::from(usage.cache_read_input_tokens) * CACHE_READ_MULTIPLIER,
+        );
+        cached.mul_add(
+            self.input_per_mtok / 1_000_000.0,
+            self.cost(usage.input_tokens, usage.output_tokens),
+        )
+    }
+}
+
+/// Look up the price for `model`, falling back to a `provider` entry.
+#[must_use]
+pub fn lookup<'a>(
+    pricing: &'a HashMap<String, ModelPricing>,
+    model: &str,
+    provider: &str,
+) -> Option<&'a ModelPricing> {
+    pricing.get(model).or_else(|| pricing.get(provider))
+}
+
+/// List prices for the built-in models.
+#[must_use]
+pub fn default_pricing() -> HashMap<String, ModelPricing> {
+    [
+        // Anthropic
+        ("claude-sonnet-4-20250514", 3.0, 15.0),
+        ("claude-opus-4-20250514", 15.0, 75.0),
+        ("claude-3-5-haiku-20241022", 0.8, 4.0),
+        // OpenAI
+        ("gpt-4o", 2.5, 10.0),
+        ("gpt-4-turbo", 10.0, 30.0),
+        ("gpt-3.5-turbo", 0.5, 1.5),
+        ("o1", 15.0, 60.0),
+        ("o1-mini", 1.1, 4.4),
+        // Groq
+        ("llama-3.3-70b-versatile", 0.59, 0.79),
+        ("llama-3.1-8b-instant", 0.05, 0.08),
+        ("mixtral-8x7b-32768", 0.24, 0.24),
+        // Google
+        ("gemini-2.0-flash", 0.1, 0.4),
+        ("gemini-1.5-pro", 1.25, 5.0),
+        // Mistral
+        ("mistral-large-latest", 2.0, 6.0),
+        ("codestral-latest", 0.3, 0.9),
+        // Together
+        ("meta-llama/Llama-3.3-70B-Instruct-Turbo", 0.88, 0.88),
+        ("Qwen/Qwen2.5-Coder-32B-Instruct", 0.8, 0.8),
+        // DeepSeek
+        ("deepseek-chat", 0.27, 1.1),
+        ("deepseek-reasoner", 0.55, 2.19),
+        // Kimi
+        ("kimi-k2.5", 0.6, 3.0),
+        ("moonshot-v1-128k", 2.0, 5.0),
+        ("moonshot-v1-32k", 1.0, 3.0),
+    ]
+    .into_iter()
+    .map(|(model, input, output)| (model.to_string(), ModelPricing::new(input, output)))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_scales_per_million_tokens() {
+        let price = ModelPricing::new(3.0, 15.0);
+        let cost = price.cost(1_000_000, 100_000);
+        assert!((cost - 4.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cached_prompt_tokens_are_discounted() {
+        let price = ModelPricing::new(3.0, 15.0);
+        let usage = Usage {
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_creation_input_tokens: 1_000_000,
+            cache_read_input_tokens: 1_000_000,
+        };
+        assert!((price.usage_cost(&usage) - 4.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lookup_prefers_model_then_provider() {
+        let mut pricing = default_pricing();
+        pricing.insert("ollama".to_string(), ModelPricing::default());
+
+        assert_eq!(
+            lookup(&pricing, "gpt-4o", "openai"),
+            Some(&ModelPricing::new(2.5, 10.0))
+        );
+        assert_eq!(
+            lookup(&pricing, "llama3", "ollama"),
+            Some(&ModelPricing::default())
+        );
+        assert!(lookup(&pricing, "unknown", "openai").is_none());
+    }
+}
@@ -2,4 +2,6 @@
 //!
 //! Re-exports from agent-core.
 
-pub use agent_core::provider::{CompletionEvent, CompletionRequest, CompletionStream, LlmProvider};
+pub use agent_core::provider::{
+    CompletionEvent, CompletionRequest, CompletionStream, LlmProvider, ReasoningEffort,
+};
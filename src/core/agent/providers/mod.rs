@@ -2,4 +2,7 @@
 //!
 //! Re-exports from agent-core.
 
-pub use agent_core::providers::{AnthropicProvider, OpenAiProvider, UnifiedProvider};
+pub use agent_core::providers::{
+    AnthropicProvider, OpenAiProvider, ProviderLimiter, RateLimit, RateLimitedProvider,
+    UnifiedProvider,
+};
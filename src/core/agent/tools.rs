@@ -1,14 +1,15 @@
 //! Tool definitions and execution.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::time::Duration;
 
 use similar::{ChangeTag, TextDiff};
 use tokio::process::Command;
 
 use super::AgentMode;
 use super::error::{AgentError, Result};
-use super::permission::{PermissionAction, PermissionClient, PermissionContext};
+use super::permission::{PermissionAction, PermissionClient, PermissionContext, PermissionPreset};
 use super::plan::PlanManager;
 use super::types::Tool;
 use crate::core::lsp::{LspManager, LspOperation, LspResult};
@@ -17,6 +18,7 @@ use crate::core::memory::{MemoryCategory, MemoryItem, MemoryManager};
 use crate::core::plugin::{PluginLoader, PluginRegistry};
 use crate::core::search::{self, CodeSearchParams, WebSearchParams};
 use crate::core::secret::mask_secrets;
+use crate::core::shell::{ShellHistory, ShellHistoryEntry};
 use crate::core::skill::SkillRegistry;
 
 /// Check if a shell command is read-only (safe to execute without permission).
@@ -77,6 +79,172 @@ pub fn is_read_only(command: &str) -> bool {
     false
 }
 
+/// Find the first high-risk pattern a shell command matches.
+///
+/// Patterns match as whole-word sequences anywhere in the command, so
+/// `git push` matches `cd app && git push origin main` but not `git pushd`.
+#[must_use]
+pub fn match_high_risk<'a>(command: &str, patterns: &'a [String]) -> Option<&'a str> {
+    let normalized = command.replace([';', '&', '|', '(', ')', '`'], " ");
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+
+    patterns.iter().map(String::as_str).find(|pattern| {
+        let needle: Vec<&str> = pattern.split_whitespace().collect();
+        !needle.is_empty() && words.windows(needle.len()).any(|w| w == needle.as_slice())
+    })
+}
+
+/// Default cap on buffered shell output (4 MiB).
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Default cap on tool calls executed concurrently.
+pub const DEFAULT_MAX_PARALLEL_TOOLS: usize = 5;
+
+/// Default time a shell command may run before it is killed.
+pub const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 120;
+
+/// Longest a local read-only tool may run, whatever the shell timeout.
+const READ_ONLY_TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Local read-only tools, which never wait on the user.
+const LOCAL_READ_ONLY_TOOLS: &[&str] = &["read_file", "glob", "grep", "list_dir"];
+
+/// Tools that write files directly.
+const FILE_WRITING_TOOLS: &[&str] = &["write_file", "edit_file", "multi_edit", "apply_patch"];
+
+/// Network tools with no side effects.
+const WEB_READ_ONLY_TOOLS: &[&str] = &["web_fetch", "web_search"];
+
+/// Memories returned by `recall` without a query or category.
+const DEFAULT_RECALL_LIMIT: usize = 20;
+
+/// Kills a process group when dropped, unless disarmed.
+struct GroupGuard(Option<u32>);
+
+impl Drop for GroupGuard {
+    fn drop(&mut self) {
+        if let Some(pid) = self.0 {
+            crate::core::process::kill_group(pid);
+        }
+    }
+}
+
+/// Output collected from a shell command run under an output cap.
+struct CappedOutput {
+    /// Exit code (`None` if killed by a signal or the cap)
+    exit_code: Option<i32>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    /// Whether the command was killed for exceeding the cap
+    exceeded: bool,
+    /// Whether the command was killed for running too long
+    timed_out: bool,
+}
+
+/// Run a shell command in `cwd`, killing it and everything it started once
+/// stdout and stderr exceed `max_bytes` or it runs longer than `timeout`.
+async fn run_capped(
+    command: &str,
+    cwd: &Path,
+    max_bytes: usize,
+    timeout: Option<Duration>,
+) -> std::io::Result<CappedOutput> {
+    use tokio::io::AsyncReadExt;
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    crate::core::process::isolate(cmd.as_std_mut());
+    let mut child = cmd.spawn()?;
+    // Kills whatever the command started if it's cut off, including when
+    // the turn is cancelled and this future dropped
+    let mut group = GroupGuard(child.id());
+
+    let mut stdout_pipe = child.stdout.take();
+    let mut stderr_pipe = child.stderr.take();
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut stdout_buf = vec![0u8; 8192];
+    let mut stderr_buf = vec![0u8; 8192];
+    let deadline = timeout.map(|timeout| tokio::time::Instant::now() + timeout);
+
+    while stdout_pipe.is_some() || stderr_pipe.is_some() {
+        tokio::select! {
+            () = async { tokio::time::sleep_until(deadline.unwrap()).await }, if deadline.is_some() => {
+                child.kill().await?;
+                return Ok(CappedOutput {
+                    exit_code: None,
+                    stdout,
+                    stderr,
+                    exceeded: false,
+                    timed_out: true,
+                });
+            }
+            n = async { stdout_pipe.as_mut().unwrap().read(&mut stdout_buf).await }, if stdout_pipe.is_some() => {
+                match n? {
+                    0 => stdout_pipe = None,
+                    n => stdout.extend_from_slice(&stdout_buf[..n]),
+                }
+            }
+            n = async { stderr_pipe.as_mut().unwrap().read(&mut stderr_buf).await }, if stderr_pipe.is_some() => {
+                match n? {
+                    0 => stderr_pipe = None,
+                    n => stderr.extend_from_slice(&stderr_buf[..n]),
+                }
+            }
+        }
+
+        if stdout.len() + stderr.len() > max_bytes {
+            child.kill().await?;
+            return Ok(CappedOutput {
+                exit_code: None,
+                stdout,
+                stderr,
+                exceeded: true,
+                timed_out: false,
+            });
+        }
+    }
+
+    // Output is closed, but the command may still be running
+    let status = match deadline {
+        Some(deadline) => {
+            if let Ok(status) = tokio::time::timeout_at(deadline, child.wait()).await {
+                status?
+            } else {
+                child.kill().await?;
+                return Ok(CappedOutput {
+                    exit_code: None,
+                    stdout,
+                    stderr,
+                    exceeded: false,
+                    timed_out: true,
+                });
+            }
+        }
+        None => child.wait().await?,
+    };
+    // Finished on its own, so leave any background jobs it started
+    group.0 = None;
+    Ok(CappedOutput {
+        exit_code: status.code(),
+        stdout,
+        stderr,
+        exceeded: false,
+        timed_out: false,
+    })
+}
+
+/// Error for a tool killed after running for `timeout`.
+fn timed_out(timeout: Duration) -> AgentError {
+    AgentError::ToolExecution(format!("timed out after {}s", timeout.as_secs()))
+}
+
 /// A single todo item for agent task tracking.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TodoItem {
@@ -100,6 +268,18 @@ pub struct ToolRegistry {
     mcp_client: std::sync::Arc<parking_lot::RwLock<McpClient>>,
     /// Plugin registry for loaded plugins
     plugin_registry: std::sync::Arc<parking_lot::RwLock<PluginRegistry>>,
+    /// Audit log for executed shell commands (disabled if `None`)
+    shell_history: Option<ShellHistory>,
+    /// Current session ID, recorded in the shell audit log
+    session_id: Option<String>,
+    /// Shell command patterns that always require confirmation
+    high_risk_commands: Vec<String>,
+    /// Maximum shell output buffered before the command is killed
+    max_output_bytes: usize,
+    /// How long a shell command may run (`None` for no limit)
+    tool_timeout: Option<Duration>,
+    /// Directory tools run in (`None` for the current directory)
+    working_dir: Option<PathBuf>,
 }
 
 impl Default for ToolRegistry {
@@ -109,26 +289,24 @@ impl Default for ToolRegistry {
             .map(|p| SkillRegistry::discover(&p))
             .unwrap_or_default();
 
-        // Auto-discover plugins from data directory
-        let plugin_registry = PluginRegistry::new();
-        if let Ok(loader) = PluginLoader::new() {
-            match loader.list_available() {
-                Ok(plugins) => {
-                    for info in &plugins {
-                        tracing::info!(plugin = %info.name, version = %info.version, "discovered plugin");
-                    }
-                }
-                Err(e) => {
-                    tracing::debug!(error = %e, "failed to list plugins");
-                }
-            }
-        }
+        // Unit tests never start the plugins installed on the machine
+        let plugin_registry = if cfg!(test) {
+            PluginRegistry::new()
+        } else {
+            Self::installed_plugins()
+        };
 
         Self {
             todos: std::sync::Arc::new(parking_lot::RwLock::new(Vec::new())),
             skill_registry,
             mcp_client: std::sync::Arc::new(parking_lot::RwLock::new(McpClient::new())),
             plugin_registry: std::sync::Arc::new(parking_lot::RwLock::new(plugin_registry)),
+            shell_history: None,
+            session_id: None,
+            high_risk_commands: Vec::new(),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            tool_timeout: Some(Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS)),
+            working_dir: None,
         }
     }
 }
@@ -144,6 +322,39 @@ impl std::fmt::Debug for ToolRegistry {
 }
 
 impl ToolRegistry {
+    /// Whether a tool is read-only and can run alongside other such calls.
+    #[must_use]
+    pub fn is_parallel_safe(name: &str) -> bool {
+        LOCAL_READ_ONLY_TOOLS.contains(&name) || WEB_READ_ONLY_TOOLS.contains(&name)
+    }
+
+    /// Whether a tool call may change files in the project.
+    ///
+    /// Covers the file-writing tools and shell commands that aren't read-only.
+    #[must_use]
+    pub fn modifies_files(name: &str, input: &serde_json::Value) -> bool {
+        FILE_WRITING_TOOLS.contains(&name)
+            || (name == "shell" && input["command"].as_str().is_some_and(|c| !is_read_only(c)))
+    }
+
+    /// Plugins loaded from the data directory.
+    fn installed_plugins() -> PluginRegistry {
+        let mut plugin_registry = PluginRegistry::new();
+        if let Ok(loader) = PluginLoader::new() {
+            match loader.load_into(&mut plugin_registry) {
+                Ok(plugins) => {
+                    for name in &plugins {
+                        tracing::info!(plugin = %name, "loaded plugin");
+                    }
+                }
+                Err(e) => {
+                    tracing::debug!(error = %e, "failed to load plugins");
+                }
+            }
+        }
+        plugin_registry
+    }
+
     /// Create a new tool registry with default tools.
     #[must_use]
     pub fn new() -> Self {
@@ -158,6 +369,63 @@ impl ToolRegistry {
             skill_registry,
             mcp_client: std::sync::Arc::new(parking_lot::RwLock::new(McpClient::new())),
             plugin_registry: std::sync::Arc::new(parking_lot::RwLock::new(PluginRegistry::new())),
+            shell_history: None,
+            session_id: None,
+            high_risk_commands: Vec::new(),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            tool_timeout: Some(Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS)),
+            working_dir: None,
+        }
+    }
+
+    /// Set the shell command audit log (`None` disables logging).
+    pub fn set_shell_history(&mut self, history: Option<ShellHistory>) {
+        self.shell_history = history;
+    }
+
+    /// Set the session ID recorded with audited shell commands.
+    pub fn set_session_id(&mut self, session_id: Option<String>) {
+        self.session_id = session_id;
+    }
+
+    /// Set the shell command patterns that always require confirmation.
+    pub fn set_high_risk_commands(&mut self, patterns: Vec<String>) {
+        self.high_risk_commands = patterns;
+    }
+
+    /// Set the maximum shell output buffered before a command is killed.
+    pub const fn set_max_output_bytes(&mut self, bytes: usize) {
+        self.max_output_bytes = bytes;
+    }
+
+    /// Set how long a shell command may run before it is killed.
+    ///
+    /// Local read-only tools use the shorter of this and 30 seconds. `None`
+    /// removes both limits.
+    pub const fn set_tool_timeout(&mut self, timeout: Option<Duration>) {
+        self.tool_timeout = timeout;
+    }
+
+    /// Set the directory tools run in (`None` for the current directory).
+    ///
+    /// Relative paths in tool input resolve against it.
+    pub fn set_working_dir(&mut self, dir: Option<PathBuf>) {
+        self.working_dir = dir;
+    }
+
+    /// Directory tools run in.
+    #[must_use]
+    pub fn working_dir(&self) -> PathBuf {
+        self.working_dir
+            .clone()
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
+    }
+
+    /// Resolve a tool input path against the working directory.
+    fn resolve_path(&self, path: &str) -> PathBuf {
+        match &self.working_dir {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
         }
     }
 
@@ -178,7 +446,8 @@ impl ToolRegistry {
             .all_tools()
             .into_iter()
             .map(|(qualified_name, plugin_tool)| Tool {
-                name: format!("plugin_{qualified_name}"),
+                // Providers only accept [a-zA-Z0-9_-] in tool names
+                name: format!("plugin_{}", qualified_name.replacen("::", "__", 1)),
                 description: plugin_tool.description,
                 input_schema: plugin_tool.input_schema,
             })
@@ -663,9 +932,9 @@ impl ToolRegistry {
                 }),
             },
             Tool {
-                name: "memory_add".to_string(),
+                name: "remember".to_string(),
                 description:
-                    "Store a fact in long-term memory for future sessions. Use for user preferences, project patterns, or corrections."
+                    "Store a fact in long-term memory for future sessions. Use for user preferences, project patterns, or corrections. The user approves what gets stored."
                         .to_string(),
                 input_schema: serde_json::json!({
                     "type": "object",
@@ -693,9 +962,9 @@ impl ToolRegistry {
                 }),
             },
             Tool {
-                name: "memory_search".to_string(),
+                name: "recall".to_string(),
                 description:
-                    "Search long-term memory for relevant facts about this project or user."
+                    "Search long-term memory for relevant facts about this project or user. Without a query or category, returns pinned and recent memories."
                         .to_string(),
                 input_schema: serde_json::json!({
                     "type": "object",
@@ -708,6 +977,10 @@ impl ToolRegistry {
                             "type": "string",
                             "enum": ["preference", "project_fact", "correction", "general"],
                             "description": "Filter by category"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum memories to return without a query (default: 20)"
                         }
                     },
                     "required": []
@@ -818,9 +1091,19 @@ impl ToolRegistry {
         mode: AgentMode,
         plan_manager: &PlanManager,
     ) -> Result<String> {
-        let result = self
-            .execute_inner(name, input, permissions, mode, plan_manager)
-            .await?;
+        let execution = self.execute_inner(name, input, permissions, mode, plan_manager);
+
+        // Shell commands time out inside `execute_shell`, after any prompt
+        let timeout = self
+            .tool_timeout
+            .filter(|_| LOCAL_READ_ONLY_TOOLS.contains(&name))
+            .map(|timeout| timeout.min(READ_ONLY_TOOL_TIMEOUT));
+        let result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, execution)
+                .await
+                .map_err(|_| timed_out(timeout))??,
+            None => execution.await?,
+        };
 
         // Mask any secrets in the output
         Ok(mask_secrets(&result).into_owned())
@@ -863,13 +1146,16 @@ impl ToolRegistry {
                     .await
             }
             "sandbox_exec" => self.execute_sandbox(input, permissions, mode).await,
-            "memory_add" => self.execute_memory_add(input),
-            "memory_search" => self.execute_memory_search(input),
+            "remember" => self.execute_remember(input, permissions).await,
+            "recall" => self.execute_recall(input),
             "memory_delete" => self.execute_memory_delete(input),
             "skill" => self.execute_skill(input),
             "lsp" => self.execute_lsp(input).await,
             _ if name.starts_with("mcp_") => self.execute_mcp_tool(name, input),
-            _ if name.starts_with("plugin_") => self.execute_plugin_tool(name, input).await,
+            _ if name.starts_with("plugin_") => {
+                self.execute_plugin_tool(name, input, permissions, mode)
+                    .await
+            }
             _ => Err(AgentError::ToolExecution(format!("unknown tool: {name}"))),
         }
     }
@@ -888,17 +1174,58 @@ impl ToolRegistry {
     }
 
     /// Execute a plugin tool
-    async fn execute_plugin_tool(&self, name: &str, input: serde_json::Value) -> Result<String> {
+    ///
+    /// Plugins can do anything, so calls are refused in plan mode and
+    /// otherwise go through the `plugin` permission preset.
+    async fn execute_plugin_tool(
+        &self,
+        name: &str,
+        input: serde_json::Value,
+        permissions: Option<&PermissionClient>,
+        mode: AgentMode,
+    ) -> Result<String> {
         // Strip the "plugin_" prefix to get the qualified name
         let qualified_name = name
             .strip_prefix("plugin_")
-            .ok_or_else(|| AgentError::ToolExecution("invalid plugin tool name".to_string()))?;
+            .ok_or_else(|| AgentError::ToolExecution("invalid plugin tool name".to_string()))?
+            .replacen("__", "::", 1);
+
+        if mode == AgentMode::Plan {
+            return Err(AgentError::ToolExecution(
+                "In plan mode, plugin tools are not allowed. Use plan_exit to switch to build mode."
+                    .to_string(),
+            ));
+        }
+
+        if let Some(perms) = permissions {
+            let (plugin, tool) = qualified_name
+                .split_once("::")
+                .unwrap_or((qualified_name.as_str(), ""));
+            let approved = perms
+                .request(
+                    name,
+                    PermissionAction::PluginTool,
+                    PermissionContext::PluginTool {
+                        plugin: plugin.to_string(),
+                        tool: tool.to_string(),
+                        input: serde_json::to_string_pretty(&input).unwrap_or_default(),
+                    },
+                )
+                .await
+                .map_err(|e| AgentError::ToolExecution(e.to_string()))?;
+
+            if !approved {
+                return Err(AgentError::ToolExecution(
+                    "Permission denied by user. Do not retry this action.".to_string(),
+                ));
+            }
+        }
 
         // Look up the plugin and clone the Arc before dropping the lock
         let (plugin, tool_name) = {
             let registry = self.plugin_registry.read();
             registry
-                .lookup_tool(qualified_name)
+                .lookup_tool(&qualified_name)
                 .map_err(|e| AgentError::ToolExecution(format!("plugin tool error: {e}")))?
         };
 
@@ -936,17 +1263,28 @@ impl ToolRegistry {
             ));
         }
 
+        // High-risk commands always prompt, even if they look read-only
+        let high_risk = match_high_risk(command, &self.high_risk_commands);
+        // So do read-only commands a rule asks about or denies
+        let ruled = permissions
+            .and_then(|perms| perms.command_rule(command))
+            .is_some_and(|rule| rule != PermissionPreset::Allow);
+
         // Check if permission needed
-        if !read_only {
+        if !read_only || high_risk.is_some() || ruled {
             if let Some(perms) = permissions {
+                let action = high_risk.map_or(PermissionAction::Execute, |pattern| {
+                    PermissionAction::ExecuteHighRisk {
+                        pattern: pattern.to_string(),
+                    }
+                });
                 let approved = perms
                     .request(
                         "shell",
-                        PermissionAction::Execute,
+                        action,
                         PermissionContext::Bash {
                             command: command.to_string(),
-                            working_dir: std::env::current_dir()
-                                .unwrap_or_else(|_| PathBuf::from("/")),
+                            working_dir: self.working_dir(),
                         },
                     )
                     .await
@@ -960,19 +1298,31 @@ impl ToolRegistry {
             }
         }
 
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(command)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .await
-            .map_err(|e| AgentError::ToolExecution(e.to_string()))?;
+        let output = run_capped(
+            command,
+            &self.working_dir(),
+            self.max_output_bytes,
+            self.tool_timeout,
+        )
+        .await;
+        self.log_shell_command(command, output.as_ref().ok().and_then(|o| o.exit_code));
+        let output = output.map_err(|e| AgentError::ToolExecution(e.to_string()))?;
+
+        if output.timed_out {
+            return Err(timed_out(self.tool_timeout.unwrap_or_default()));
+        }
+
+        if output.exceeded {
+            return Err(AgentError::ToolExecution(format!(
+                "output exceeded limit ({} bytes), command terminated",
+                self.max_output_bytes
+            )));
+        }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr);
 
-        if output.status.success() {
+        if output.exit_code == Some(0) {
             Ok(stdout.to_string())
         } else {
             Ok(format!(
@@ -981,6 +1331,16 @@ impl ToolRegistry {
         }
     }
 
+    /// Record an executed shell command in the audit log, if enabled.
+    fn log_shell_command(&self, command: &str, exit_code: Option<i32>) {
+        if let Some(history) = &self.shell_history {
+            let entry = ShellHistoryEntry::new(command, exit_code, self.session_id.as_deref());
+            if let Err(e) = history.append(&entry) {
+                tracing::warn!(error = %e, "failed to write shell history");
+            }
+        }
+    }
+
     async fn execute_read_file(&self, input: serde_json::Value) -> Result<String> {
         let path = input["path"]
             .as_str()
@@ -988,7 +1348,7 @@ impl ToolRegistry {
 
         tracing::info!(path = %path, "reading file");
 
-        tokio::fs::read_to_string(path)
+        tokio::fs::read_to_string(self.resolve_path(path))
             .await
             .map_err(|e| AgentError::ToolExecution(e.to_string()))
     }
@@ -1007,7 +1367,7 @@ impl ToolRegistry {
             .as_str()
             .ok_or_else(|| AgentError::ToolExecution("missing content".to_string()))?;
 
-        let path_buf = PathBuf::from(path);
+        let path_buf = self.resolve_path(path);
 
         // In Plan mode, only allow writing to plan files
         if mode == AgentMode::Plan && !plan_manager.is_plan_path(&path_buf) {
@@ -1059,7 +1419,7 @@ impl ToolRegistry {
             }
         }
 
-        tokio::fs::write(path, content)
+        tokio::fs::write(&path_buf, content)
             .await
             .map_err(|e| AgentError::ToolExecution(e.to_string()))?;
 
@@ -1314,7 +1674,8 @@ impl ToolRegistry {
         tracing::info!(path = %path, "editing file");
 
         // Read current content
-        let content = tokio::fs::read_to_string(path)
+        let path_buf = self.resolve_path(path);
+        let content = tokio::fs::read_to_string(&path_buf)
             .await
             .map_err(|e| AgentError::ToolExecution(format!("failed to read file: {e}")))?;
 
@@ -1352,7 +1713,7 @@ impl ToolRegistry {
                     "edit_file",
                     PermissionAction::EditFile,
                     PermissionContext::EditFile {
-                        path: PathBuf::from(path),
+                        path: path_buf.clone(),
                         diff: diff.clone(),
                     },
                 )
@@ -1367,7 +1728,7 @@ impl ToolRegistry {
         }
 
         // Write new content
-        tokio::fs::write(path, &new_content)
+        tokio::fs::write(&path_buf, &new_content)
             .await
             .map_err(|e| AgentError::ToolExecution(e.to_string()))?;
 
@@ -1385,10 +1746,9 @@ impl ToolRegistry {
         let pattern = input["pattern"]
             .as_str()
             .ok_or_else(|| AgentError::ToolExecution("missing pattern".to_string()))?;
-        let search_path = input["path"].as_str().map_or_else(
-            || std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
-            PathBuf::from,
-        );
+        let search_path = input["path"]
+            .as_str()
+            .map_or_else(|| self.working_dir(), |path| self.resolve_path(path));
 
         tracing::info!(pattern = %pattern, path = %search_path.display(), "glob search");
 
@@ -1437,10 +1797,9 @@ impl ToolRegistry {
         let pattern = input["pattern"]
             .as_str()
             .ok_or_else(|| AgentError::ToolExecution("missing pattern".to_string()))?;
-        let search_path = input["path"].as_str().map_or_else(
-            || std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
-            PathBuf::from,
-        );
+        let search_path = input["path"]
+            .as_str()
+            .map_or_else(|| self.working_dir(), |path| self.resolve_path(path));
         let include = input["include"].as_str();
 
         tracing::info!(pattern = %pattern, path = %search_path.display(), "grep search");
@@ -1465,6 +1824,7 @@ impl ToolRegistry {
         let output = cmd
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
+            .kill_on_drop(true)
             .output()
             .await;
 
@@ -1481,6 +1841,7 @@ impl ToolRegistry {
                 let grep_out = grep_cmd
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
+                    .kill_on_drop(true)
                     .output()
                     .await
                     .map_err(|e| AgentError::ToolExecution(e.to_string()))?;
@@ -1496,6 +1857,7 @@ impl ToolRegistry {
             let grep_out = grep_cmd
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
+                .kill_on_drop(true)
                 .output()
                 .await
                 .map_err(|e| AgentError::ToolExecution(e.to_string()))?;
@@ -1520,10 +1882,9 @@ impl ToolRegistry {
     }
 
     async fn execute_list_dir(&self, input: serde_json::Value) -> Result<String> {
-        let path = input["path"].as_str().map_or_else(
-            || std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
-            PathBuf::from,
-        );
+        let path = input["path"]
+            .as_str()
+            .map_or_else(|| self.working_dir(), |path| self.resolve_path(path));
 
         tracing::info!(path = %path.display(), "listing directory");
 
@@ -1752,10 +2113,9 @@ impl ToolRegistry {
         let patch = input["patch"]
             .as_str()
             .ok_or_else(|| AgentError::ToolExecution("missing patch".to_string()))?;
-        let base_path = input["path"].as_str().map_or_else(
-            || std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
-            PathBuf::from,
-        );
+        let base_path = input["path"]
+            .as_str()
+            .map_or_else(|| self.working_dir(), |path| self.resolve_path(path));
 
         // In Plan mode, disallow patches
         if mode == AgentMode::Plan {
@@ -1889,9 +2249,11 @@ impl ToolRegistry {
             }
 
             // Read and validate
-            let content = tokio::fs::read_to_string(path).await.map_err(|e| {
-                AgentError::ToolExecution(format!("edit {i}: failed to read {path}: {e}"))
-            })?;
+            let content = tokio::fs::read_to_string(self.resolve_path(path))
+                .await
+                .map_err(|e| {
+                    AgentError::ToolExecution(format!("edit {i}: failed to read {path}: {e}"))
+                })?;
 
             if !content.contains(old_string) {
                 return Err(AgentError::ToolExecution(format!(
@@ -1937,7 +2299,7 @@ impl ToolRegistry {
 
         // Apply all edits
         for (path, new_content) in pending_edits {
-            tokio::fs::write(&path, &new_content)
+            tokio::fs::write(self.resolve_path(&path), &new_content)
                 .await
                 .map_err(|e| AgentError::ToolExecution(format!("failed to write {path}: {e}")))?;
             results.push(format!("Edited: {path}"));
@@ -1985,7 +2347,7 @@ impl ToolRegistry {
                     PermissionAction::Execute,
                     PermissionContext::Bash {
                         command: cmd_desc,
-                        working_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/")),
+                        working_dir: self.working_dir(),
                     },
                 )
                 .await
@@ -1999,6 +2361,7 @@ impl ToolRegistry {
         }
 
         let mut cmd = Command::new("gh");
+        cmd.current_dir(self.working_dir());
         cmd.args(["pr", "create", "--title", title]);
 
         if !body.is_empty() {
@@ -2065,8 +2428,7 @@ impl ToolRegistry {
                             PermissionAction::Execute,
                             PermissionContext::Bash {
                                 command: format!("gh issue create --title {title:?}"),
-                                working_dir: std::env::current_dir()
-                                    .unwrap_or_else(|_| PathBuf::from("/")),
+                                working_dir: self.working_dir(),
                             },
                         )
                         .await
@@ -2080,6 +2442,7 @@ impl ToolRegistry {
                 }
 
                 let mut cmd = Command::new("gh");
+                cmd.current_dir(self.working_dir());
                 cmd.args(["issue", "create", "--title", title]);
 
                 if !body.is_empty() {
@@ -2113,6 +2476,7 @@ impl ToolRegistry {
                     .ok_or_else(|| AgentError::ToolExecution("missing number".to_string()))?;
 
                 let output = Command::new("gh")
+                    .current_dir(self.working_dir())
                     .args(["issue", "view", &number.to_string()])
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
@@ -2133,6 +2497,7 @@ impl ToolRegistry {
             }
             "list" => {
                 let output = Command::new("gh")
+                    .current_dir(self.working_dir())
                     .args(["issue", "list"])
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
@@ -2170,8 +2535,7 @@ impl ToolRegistry {
                             PermissionAction::Execute,
                             PermissionContext::Bash {
                                 command: format!("gh issue close {number}"),
-                                working_dir: std::env::current_dir()
-                                    .unwrap_or_else(|_| PathBuf::from("/")),
+                                working_dir: self.working_dir(),
                             },
                         )
                         .await
@@ -2185,6 +2549,7 @@ impl ToolRegistry {
                 }
 
                 let output = Command::new("gh")
+                    .current_dir(self.working_dir())
                     .args(["issue", "close", &number.to_string()])
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
@@ -2226,6 +2591,7 @@ impl ToolRegistry {
                     .ok_or_else(|| AgentError::ToolExecution("missing number".to_string()))?;
 
                 let output = Command::new("gh")
+                    .current_dir(self.working_dir())
                     .args(["pr", "view", &number.to_string()])
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
@@ -2250,6 +2616,7 @@ impl ToolRegistry {
                     .ok_or_else(|| AgentError::ToolExecution("missing number".to_string()))?;
 
                 let output = Command::new("gh")
+                    .current_dir(self.working_dir())
                     .args(["pr", "diff", &number.to_string()])
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
@@ -2274,6 +2641,7 @@ impl ToolRegistry {
                     .ok_or_else(|| AgentError::ToolExecution("missing number".to_string()))?;
 
                 let output = Command::new("gh")
+                    .current_dir(self.working_dir())
                     .args(["pr", "checks", &number.to_string()])
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
@@ -2314,8 +2682,7 @@ impl ToolRegistry {
                             PermissionAction::Execute,
                             PermissionContext::Bash {
                                 command: format!("gh pr comment {number}"),
-                                working_dir: std::env::current_dir()
-                                    .unwrap_or_else(|_| PathBuf::from("/")),
+                                working_dir: self.working_dir(),
                             },
                         )
                         .await
@@ -2329,6 +2696,7 @@ impl ToolRegistry {
                 }
 
                 let output = Command::new("gh")
+                    .current_dir(self.working_dir())
                     .args(["pr", "comment", &number.to_string(), "--body", body])
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
@@ -2351,6 +2719,7 @@ impl ToolRegistry {
                 let state = input["state"].as_str().unwrap_or("open");
 
                 let output = Command::new("gh")
+                    .current_dir(self.working_dir())
                     .args(["pr", "list", "--state", state])
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
@@ -2393,7 +2762,7 @@ impl ToolRegistry {
 
         // Validate and canonicalize workdir if provided
         let workdir = if let Some(dir) = input["workdir"].as_str() {
-            let path = PathBuf::from(dir);
+            let path = self.resolve_path(dir);
             let canonical = path
                 .canonicalize()
                 .map_err(|e| AgentError::ToolExecution(format!("invalid workdir: {e}")))?;
@@ -2433,7 +2802,7 @@ impl ToolRegistry {
                     PermissionAction::Execute,
                     PermissionContext::Bash {
                         command: desc,
-                        working_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/")),
+                        working_dir: self.working_dir(),
                     },
                 )
                 .await
@@ -2620,7 +2989,11 @@ impl ToolRegistry {
     }
 
     #[allow(clippy::unused_self)]
-    fn execute_memory_add(&self, input: serde_json::Value) -> Result<String> {
+    async fn execute_remember(
+        &self,
+        input: serde_json::Value,
+        permissions: Option<&PermissionClient>,
+    ) -> Result<String> {
         let content = input["content"]
             .as_str()
             .ok_or_else(|| AgentError::ToolExecution("missing content".to_string()))?;
@@ -2632,6 +3005,26 @@ impl ToolRegistry {
             _ => MemoryCategory::General,
         };
 
+        if let Some(perms) = permissions {
+            let approved = perms
+                .request(
+                    "remember",
+                    PermissionAction::Remember,
+                    PermissionContext::Remember {
+                        content: content.to_string(),
+                        category: category.to_string(),
+                    },
+                )
+                .await
+                .map_err(|e| AgentError::ToolExecution(e.to_string()))?;
+
+            if !approved {
+                return Err(AgentError::ToolExecution(
+                    "Permission denied by user. Do not retry this action.".to_string(),
+                ));
+            }
+        }
+
         let mut item = MemoryItem::new(content.to_string(), category);
 
         // Add tags
@@ -2659,25 +3052,38 @@ impl ToolRegistry {
     }
 
     #[allow(clippy::unused_self)]
-    fn execute_memory_search(&self, input: serde_json::Value) -> Result<String> {
+    fn execute_recall(&self, input: serde_json::Value) -> Result<String> {
         let manager = MemoryManager::for_current_project()
             .map_err(|e| AgentError::ToolExecution(format!("failed to init memory: {e}")))?;
 
+        let category = match input["category"].as_str() {
+            Some("preference") => Some(MemoryCategory::Preference),
+            Some("project_fact") => Some(MemoryCategory::ProjectFact),
+            Some("correction") => Some(MemoryCategory::Correction),
+            Some("general") => Some(MemoryCategory::General),
+            _ => None,
+        };
+
         let items = if let Some(query) = input["query"].as_str() {
-            manager
+            let mut items = manager
                 .search(query)
-                .map_err(|e| AgentError::ToolExecution(format!("search failed: {e}")))?
-        } else {
-            let category = match input["category"].as_str() {
-                Some("preference") => Some(MemoryCategory::Preference),
-                Some("project_fact") => Some(MemoryCategory::ProjectFact),
-                Some("correction") => Some(MemoryCategory::Correction),
-                Some("general") => Some(MemoryCategory::General),
-                _ => None,
-            };
+                .map_err(|e| AgentError::ToolExecution(format!("search failed: {e}")))?;
+            if let Some(category) = category {
+                items.retain(|item| item.category == category);
+            }
+            items
+        } else if category.is_some() {
             manager
                 .list(category)
                 .map_err(|e| AgentError::ToolExecution(format!("list failed: {e}")))?
+        } else {
+            let limit = input["limit"]
+                .as_u64()
+                .and_then(|n| usize::try_from(n).ok())
+                .unwrap_or(DEFAULT_RECALL_LIMIT);
+            manager
+                .get_context(limit)
+                .map_err(|e| AgentError::ToolExecution(format!("recall failed: {e}")))?
         };
 
         if items.is_empty() {
@@ -2790,7 +3196,7 @@ impl ToolRegistry {
             .as_str()
             .ok_or_else(|| AgentError::ToolExecution("missing file_path".to_string()))?;
 
-        let path = PathBuf::from(file_path);
+        let path = self.resolve_path(file_path);
         if !path.exists() {
             return Err(AgentError::ToolExecution(format!(
                 "file not found: {file_path}"
@@ -3017,6 +3423,163 @@ mod tests {
         assert!(output.contains("hello"));
     }
 
+    #[tokio::test]
+    async fn tools_run_in_the_working_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = ToolRegistry::new();
+        registry.set_working_dir(Some(dir.path().to_path_buf()));
+        let plan_manager = PlanManager::new();
+
+        registry
+            .execute(
+                "write_file",
+                serde_json::json!({"path": "notes.txt", "content": "hi"}),
+                None,
+                AgentMode::Build,
+                &plan_manager,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("notes.txt")).unwrap(),
+            "hi"
+        );
+
+        let output = registry
+            .execute(
+                "shell",
+                serde_json::json!({"command": "cat notes.txt"}),
+                None,
+                AgentMode::Build,
+                &plan_manager,
+            )
+            .await
+            .unwrap();
+        assert_eq!(output, "hi");
+    }
+
+    #[tokio::test]
+    async fn remember_respects_permission_preset() {
+        use super::super::permission::AgentPermissions;
+
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let client = PermissionClient::with_presets(
+            "test-session".to_string(),
+            tx,
+            AgentPermissions {
+                remember: PermissionPreset::Deny,
+                ..AgentPermissions::default()
+            },
+        );
+        let registry = ToolRegistry::new();
+        let result = registry
+            .execute(
+                "remember",
+                serde_json::json!({"content": "prefers tabs", "category": "preference"}),
+                Some(&client),
+                AgentMode::Build,
+                &PlanManager::new(),
+            )
+            .await;
+
+        assert!(matches!(result, Err(AgentError::ToolExecution(msg)) if msg.contains("denied")));
+    }
+
+    #[tokio::test]
+    async fn shell_rules_can_deny_read_only_commands() {
+        use super::super::permission::{AgentPermissions, PermissionRule};
+
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let client = PermissionClient::with_presets(
+            "test-session".to_string(),
+            tx,
+            AgentPermissions {
+                rules: vec![PermissionRule {
+                    pattern: "cat *.env".to_string(),
+                    action: PermissionPreset::Deny,
+                }],
+                ..AgentPermissions::default()
+            },
+        );
+        let registry = ToolRegistry::new();
+        let result = registry
+            .execute(
+                "shell",
+                serde_json::json!({"command": "cat prod.env"}),
+                Some(&client),
+                AgentMode::Build,
+                &PlanManager::new(),
+            )
+            .await;
+
+        assert!(matches!(result, Err(AgentError::ToolExecution(msg)) if msg.contains("denied")));
+    }
+
+    #[tokio::test]
+    async fn shell_tool_kills_command_over_output_limit() {
+        let mut registry = ToolRegistry::new();
+        registry.set_max_output_bytes(64 * 1024);
+        let plan_manager = PlanManager::new();
+        let result = registry
+            .execute(
+                "shell",
+                serde_json::json!({"command": "yes"}),
+                None,
+                AgentMode::Build,
+                &plan_manager,
+            )
+            .await;
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("output exceeded limit"));
+        assert!(err.contains("command terminated"));
+    }
+
+    #[tokio::test]
+    async fn shell_tool_kills_command_after_timeout() {
+        let mut registry = ToolRegistry::new();
+        registry.set_tool_timeout(Some(Duration::from_secs(1)));
+        let plan_manager = PlanManager::new();
+        let started = std::time::Instant::now();
+        let result = registry
+            .execute(
+                "shell",
+                serde_json::json!({"command": "sleep 30"}),
+                None,
+                AgentMode::Build,
+                &plan_manager,
+            )
+            .await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "tool execution failed: timed out after 1s"
+        );
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn run_capped_kills_background_children_on_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = run_capped(
+            "sleep 30 & echo $!; wait",
+            dir.path(),
+            1024,
+            Some(Duration::from_millis(300)),
+        )
+        .await
+        .unwrap();
+        assert!(output.timed_out);
+
+        let pid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Gone, or a zombie waiting to be reaped
+        let alive = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+            .is_ok_and(|stat| !stat.contains(") Z "));
+        assert!(!alive, "background sleep {pid} survived");
+    }
+
     #[tokio::test]
     async fn unknown_tool_returns_error() {
         let registry = ToolRegistry::new();
@@ -3034,6 +3597,47 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn modifies_files_covers_writes_and_destructive_shell() {
+        let shell = |command: &str| serde_json::json!({ "command": command });
+        assert!(ToolRegistry::modifies_files(
+            "edit_file",
+            &serde_json::json!({})
+        ));
+        assert!(ToolRegistry::modifies_files(
+            "shell",
+            &shell("rm -rf build")
+        ));
+        assert!(!ToolRegistry::modifies_files("shell", &shell("git status")));
+        assert!(!ToolRegistry::modifies_files(
+            "read_file",
+            &serde_json::json!({})
+        ));
+    }
+
+    #[test]
+    fn match_high_risk_finds_word_sequences() {
+        let patterns = vec![
+            "git push".to_string(),
+            "git reset --hard".to_string(),
+            "rm -rf".to_string(),
+        ];
+        assert_eq!(
+            match_high_risk("cd app && git push origin main", &patterns),
+            Some("git push")
+        );
+        assert_eq!(
+            match_high_risk("git reset --hard HEAD~1", &patterns),
+            Some("git reset --hard")
+        );
+        assert_eq!(
+            match_high_risk("echo hi; rm -rf build", &patterns),
+            Some("rm -rf")
+        );
+        assert_eq!(match_high_risk("git pushd", &patterns), None);
+        assert_eq!(match_high_risk("git reset --soft HEAD~1", &patterns), None);
+    }
+
     #[test]
     fn is_read_only_detects_safe_commands() {
         assert!(is_read_only("ls"));
@@ -3100,4 +3704,61 @@ mod tests {
         let tools = registry.definitions(AgentMode::Build);
         assert!(!tools.is_empty());
     }
+
+    #[tokio::test]
+    async fn plugin_tools_get_provider_safe_names() {
+        use crate::core::plugin::{PluginHooks, ToolDefinition, ToolResult};
+
+        struct Ping;
+
+        #[async_trait::async_trait]
+        impl PluginHooks for Ping {
+            fn tools(&self) -> Vec<ToolDefinition> {
+                vec![ToolDefinition {
+                    name: "ping".to_string(),
+                    description: "Ping".to_string(),
+                    input_schema: serde_json::json!({"type": "object"}),
+                }]
+            }
+
+            async fn execute_tool(
+                &self,
+                name: &str,
+                _args: serde_json::Value,
+            ) -> anyhow::Result<ToolResult> {
+                Ok(ToolResult {
+                    output: format!("pong from {name}"),
+                    is_error: false,
+                })
+            }
+        }
+
+        let registry = ToolRegistry::new();
+        registry.register_plugin("demo", std::sync::Arc::new(Ping));
+        let tools = registry.definitions(AgentMode::Build);
+        assert!(tools.iter().any(|t| t.name == "plugin_demo__ping"));
+
+        let output = registry
+            .execute(
+                "plugin_demo__ping",
+                serde_json::json!({}),
+                None,
+                AgentMode::Build,
+                &PlanManager::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output, "pong from ping");
+
+        let planned = registry
+            .execute(
+                "plugin_demo__ping",
+                serde_json::json!({}),
+                None,
+                AgentMode::Plan,
+                &PlanManager::new(),
+            )
+            .await;
+        assert!(planned.is_err());
+    }
 }
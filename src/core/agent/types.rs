@@ -2,8 +2,9 @@
 //!
 //! Re-exports from agent-core, plus CLI-specific types.
 
+pub use agent_core::plan::PlanPhase;
 pub use agent_core::types::{
-    Content, ContentBlock, Message, MessagesRequest, Role, StopReason, StreamEvent, Tool,
+    Content, ContentBlock, Message, MessagesRequest, Role, StopReason, StreamEvent, Tool, Usage,
 };
 
 /// Events emitted during chat for UI rendering
@@ -11,6 +12,8 @@ pub use agent_core::types::{
 pub enum ChatEvent {
     /// Text chunk from the assistant
     Text(String),
+    /// Reasoning chunk from the assistant, separate from the answer
+    Reasoning(String),
     /// Tool invocation starting (for activity status)
     ToolStart { name: String },
     /// Tool invocation with result
@@ -26,4 +29,148 @@ pub enum ChatEvent {
         output_tokens: u32,
         cost_usd: f64,
     },
+    /// Plan-mode workflow phase changed (`None` when leaving plan mode)
+    PlanPhase(Option<PlanPhase>),
+    /// Provider stream dropped; retrying and stitching the response
+    Reconnecting { attempt: u32, max_attempts: u32 },
+    /// Older messages were replaced with a summary to free context
+    Compacted { messages: usize },
+}
+
+/// Result of compacting the conversation on request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionSummary {
+    /// Number of messages replaced by the summary
+    pub messages: usize,
+    /// Estimated conversation tokens before compacting
+    pub tokens_before: u32,
+    /// Estimated conversation tokens after compacting
+    pub tokens_after: u32,
+    /// The summary that replaced them
+    pub summary: String,
+    /// ID of the earliest prompt kept after the summary, if any
+    pub kept_prompt: Option<String>,
+    /// Text replies and tool calls kept ahead of `kept_prompt`, or of the
+    /// end of the conversation without one
+    pub kept_replies: usize,
+}
+
+/// Structured result of a chat turn, accumulated from its events
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct ChatSummary {
+    /// Session the turn was recorded in
+    pub session_id: Option<String>,
+    /// Full assistant text across all responses in the turn
+    pub response: String,
+    /// Tools invoked during the turn, in order
+    pub tool_calls: Vec<ToolCallSummary>,
+    /// Token usage summed over every response
+    pub usage: UsageSummary,
+    /// Estimated cost in US dollars
+    pub cost_usd: f64,
+    /// Whether the turn was cancelled before finishing
+    pub cancelled: bool,
+    /// Why the run failed, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A tool invocation recorded in a [`ChatSummary`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ToolCallSummary {
+    pub name: String,
+    pub invocation: String,
+    pub output: String,
+    pub is_error: bool,
+}
+
+/// Token totals recorded in a [`ChatSummary`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct UsageSummary {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+impl ChatSummary {
+    /// Fold a chat event into the summary
+    pub fn record(&mut self, event: ChatEvent) {
+        match event {
+            ChatEvent::Text(text) => self.response.push_str(&text),
+            ChatEvent::ToolCall {
+                name,
+                invocation,
+                output,
+                is_error,
+            } => self.tool_calls.push(ToolCallSummary {
+                name,
+                invocation,
+                output,
+                is_error,
+            }),
+            ChatEvent::Usage {
+                input_tokens,
+                output_tokens,
+                cost_usd,
+            } => {
+                self.usage.input_tokens = self.usage.input_tokens.saturating_add(input_tokens);
+                self.usage.output_tokens = self.usage.output_tokens.saturating_add(output_tokens);
+                self.cost_usd += cost_usd;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_accumulates_text_tools_and_usage() {
+        let mut summary = ChatSummary::default();
+        for event in [
+            ChatEvent::Text("Checking".to_string()),
+            ChatEvent::ToolStart {
+                name: "shell".to_string(),
+            },
+            ChatEvent::ToolCall {
+                name: "shell".to_string(),
+                invocation: "ls".to_string(),
+                output: "README.md".to_string(),
+                is_error: false,
+            },
+            ChatEvent::Usage {
+                input_tokens: 100,
+                output_tokens: 10,
+                cost_usd: 0.25,
+            },
+            ChatEvent::Text("... done".to_string()),
+            ChatEvent::Usage {
+                input_tokens: 150,
+                output_tokens: 20,
+                cost_usd: 0.5,
+            },
+        ] {
+            summary.record(event);
+        }
+
+        assert_eq!(summary.response, "Checking... done");
+        assert_eq!(summary.tool_calls.len(), 1);
+        assert_eq!(summary.tool_calls[0].invocation, "ls");
+        assert_eq!(
+            summary.usage,
+            UsageSummary {
+                input_tokens: 250,
+                output_tokens: 30
+            }
+        );
+        assert!((summary.cost_usd - 0.75).abs() < 1e-9);
+
+        // The error key only appears for failed runs
+        let json = serde_json::to_value(&summary).unwrap();
+        assert!(json.get("error").is_none());
+        summary.error = Some("rate limited".to_string());
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["error"], "rate limited");
+    }
 }
@@ -0,0 +1,133 @@
+//! Saved "always allow" permission answers.
+//!
+//! Approvals are stored per project, so trusting a command in one
+//! repository doesn't trust it everywhere.
+
+use super::agent::permission::{Approval, ApprovalStore};
+use super::project::Project;
+use super::storage::{Storage, StorageError};
+
+/// Approvals the user gave for a project
+pub struct ApprovalManager {
+    storage: Storage,
+    project: Project,
+}
+
+impl ApprovalManager {
+    /// Create a new approval manager
+    #[must_use]
+    pub const fn new(storage: Storage, project: Project) -> Self {
+        Self { storage, project }
+    }
+
+    /// Create an approval manager for the current project
+    ///
+    /// # Errors
+    ///
+    /// Returns error if project detection or storage initialization fails.
+    pub fn for_current_project() -> anyhow::Result<Self> {
+        let storage = Storage::new()?;
+        let project = Project::detect(&std::env::current_dir()?)?;
+        Ok(Self::new(storage, project))
+    }
+
+    /// List saved approvals, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage fails.
+    pub fn list(&self) -> anyhow::Result<Vec<Approval>> {
+        match self.storage.read(&["approval", &self.project.id]) {
+            Ok(approvals) => Ok(approvals),
+            Err(StorageError::NotFound(_)) => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save an approval, ignoring repeats
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage fails.
+    pub fn add(&self, approval: &Approval) -> anyhow::Result<()> {
+        let mut approvals = self.list()?;
+        if approvals
+            .iter()
+            .any(|a| a.tool == approval.tool && a.target == approval.target)
+        {
+            return Ok(());
+        }
+        approvals.push(approval.clone());
+        self.storage
+            .write(&["approval", &self.project.id], &approvals)?;
+        Ok(())
+    }
+
+    /// Remove every approval for the project
+    ///
+    /// Returns the number removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage fails.
+    pub fn clear(&self) -> anyhow::Result<usize> {
+        let count = self.list()?.len();
+        if count > 0 {
+            self.storage.remove(&["approval", &self.project.id])?;
+        }
+        Ok(count)
+    }
+}
+
+impl ApprovalStore for ApprovalManager {
+    fn load(&self) -> anyhow::Result<Vec<Approval>> {
+        self.list()
+    }
+
+    fn save(&self, approval: &Approval) -> anyhow::Result<()> {
+        self.add(approval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::agent::permission::PermissionContext;
+    use crate::core::project::ProjectTime;
+
+    fn temp_manager(id: &str, dir: &tempfile::TempDir) -> ApprovalManager {
+        let project = Project {
+            id: id.to_string(),
+            worktree: dir.path().to_path_buf(),
+            vcs: None,
+            time: ProjectTime {
+                created: 0,
+                initialized: 0,
+            },
+        };
+        ApprovalManager::new(Storage::with_root(dir.path().to_path_buf()), project)
+    }
+
+    #[test]
+    fn approvals_are_saved_per_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = temp_manager("one", &dir);
+        let other = temp_manager("two", &dir);
+        let approval = Approval::new(
+            "shell",
+            &PermissionContext::Bash {
+                command: "cargo test".to_string(),
+                working_dir: dir.path().to_path_buf(),
+            },
+        );
+
+        manager.save(&approval).unwrap();
+        manager.save(&approval).unwrap();
+        assert_eq!(manager.load().unwrap(), vec![approval]);
+        assert!(other.list().unwrap().is_empty());
+
+        assert_eq!(manager.clear().unwrap(), 1);
+        assert!(manager.list().unwrap().is_empty());
+        assert_eq!(manager.clear().unwrap(), 0);
+    }
+}
@@ -0,0 +1,79 @@
+//! System clipboard access.
+
+/// Read text from the system clipboard, trimmed and normalized.
+///
+/// # Errors
+///
+/// Returns an error if the clipboard cannot be accessed or holds no text.
+#[cfg(feature = "clipboard")]
+pub fn read_text() -> anyhow::Result<String> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| anyhow::anyhow!("could not access clipboard: {e}"))?;
+    let text = clipboard
+        .get_text()
+        .map_err(|e| anyhow::anyhow!("could not read text from clipboard: {e}"))?;
+
+    let text = normalize(&text);
+    if text.is_empty() {
+        anyhow::bail!("clipboard is empty");
+    }
+    Ok(text)
+}
+
+/// Read text from the system clipboard.
+///
+/// # Errors
+///
+/// Always fails: clipboard support was not compiled in.
+#[cfg(not(feature = "clipboard"))]
+pub fn read_text() -> anyhow::Result<String> {
+    anyhow::bail!("clipboard support not enabled (rebuild with the `clipboard` feature)")
+}
+
+/// Copy text to the system clipboard.
+///
+/// # Errors
+///
+/// Returns an error if the clipboard cannot be accessed, e.g. on a headless
+/// system.
+#[cfg(feature = "clipboard")]
+pub fn write_text(text: &str) -> anyhow::Result<()> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| anyhow::anyhow!("could not access clipboard: {e}"))?;
+    clipboard
+        .set_text(text)
+        .map_err(|e| anyhow::anyhow!("could not write text to clipboard: {e}"))
+}
+
+/// Copy text to the system clipboard.
+///
+/// # Errors
+///
+/// Always fails: clipboard support was not compiled in.
+#[cfg(not(feature = "clipboard"))]
+pub fn write_text(_text: &str) -> anyhow::Result<()> {
+    anyhow::bail!("clipboard support not enabled (rebuild with the `clipboard` feature)")
+}
+
+/// Normalize clipboard text: unify line endings and trim surrounding whitespace.
+#[must_use]
+pub fn normalize(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_trims_and_unifies_line_endings() {
+        assert_eq!(
+            normalize("  \r\nerror: oops\r\n  at line 3\r\n\n"),
+            "error: oops\n  at line 3"
+        );
+        assert_eq!(normalize(" \n\t "), "");
+    }
+}
@@ -0,0 +1,19 @@
+//! Color output policy.
+//!
+//! Colors are disabled by `--no-color` or a non-empty `NO_COLOR` environment
+//! variable (<https://no-color.org>).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Disable colored output for the rest of the process (`--no-color`).
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether colored output is enabled.
+#[must_use]
+pub fn enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed) && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
@@ -23,12 +23,14 @@ pub struct ProjectContext {
     pub git_branch: Option<String>,
     /// Git status summary (modified, untracked, staged counts).
     pub git_status: Option<GitStatus>,
-    /// Detected project type.
-    pub project_type: Option<ProjectType>,
+    /// Detected project stacks, in the project root and its subdirectories.
+    pub project_stacks: Vec<ProjectStack>,
     /// Instruction content from CLAUDE.md files.
     pub instructions: Vec<InstructionSource>,
     /// File tree of the project (depth-limited).
     pub file_tree: Option<String>,
+    /// Uncommitted changes, truncated (see [`ProjectContext::with_diff`]).
+    pub git_diff: Option<String>,
 }
 
 /// Git repository status.
@@ -45,15 +47,171 @@ pub struct GitStatus {
 }
 
 /// Detected project type.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProjectType {
     Rust,
     Node,
+    Deno,
     Python,
     Go,
+    Maven,
+    Gradle,
+    Ruby,
+    Php,
+    DotNet,
+    Zig,
+    Elixir,
     Unknown,
 }
 
+/// A project type found in one directory of the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectStack {
+    /// Project type identified by the directory's manifest.
+    pub project_type: ProjectType,
+    /// Directory holding the manifest, relative to the project root (empty
+    /// for the root itself).
+    pub path: PathBuf,
+}
+
+impl ProjectStack {
+    /// Project type label, followed by the directory when it isn't the root.
+    #[must_use]
+    pub fn label(&self) -> String {
+        if self.path.as_os_str().is_empty() {
+            self.project_type.label().to_string()
+        } else {
+            format!("{} in {}/", self.project_type.label(), self.path.display())
+        }
+    }
+
+    /// Build, test and lint commands, run from the project root.
+    ///
+    /// Commands for a subdirectory stack `cd` into it first.
+    #[must_use]
+    pub fn commands(&self) -> Vec<(&'static str, String)> {
+        [
+            ("Build", self.project_type.build_command()),
+            ("Test", self.project_type.test_command()),
+            ("Lint", self.project_type.lint_command()),
+        ]
+        .into_iter()
+        .filter_map(|(kind, command)| {
+            let command = command?;
+            Some(if self.path.as_os_str().is_empty() {
+                (kind, command.to_string())
+            } else {
+                (kind, format!("cd {} && {command}", self.path.display()))
+            })
+        })
+        .collect()
+    }
+}
+
+/// Project types with the manifest files that identify them.
+///
+/// Entries starting with `*.` match by extension.
+const PROJECT_MANIFESTS: &[(ProjectType, &[&str])] = &[
+    (ProjectType::Rust, &["Cargo.toml"]),
+    (ProjectType::Node, &["package.json"]),
+    (ProjectType::Deno, &["deno.json", "deno.jsonc"]),
+    (
+        ProjectType::Python,
+        &["pyproject.toml", "setup.py", "requirements.txt"],
+    ),
+    (ProjectType::Go, &["go.mod"]),
+    (ProjectType::Maven, &["pom.xml"]),
+    (
+        ProjectType::Gradle,
+        &[
+            "build.gradle",
+            "build.gradle.kts",
+            "settings.gradle",
+            "settings.gradle.kts",
+        ],
+    ),
+    (ProjectType::Ruby, &["Gemfile"]),
+    (ProjectType::Php, &["composer.json"]),
+    (ProjectType::DotNet, &["*.sln", "*.csproj", "*.fsproj"]),
+    (ProjectType::Zig, &["build.zig"]),
+    (ProjectType::Elixir, &["mix.exs"]),
+];
+
+impl ProjectType {
+    /// Human-readable name, with the usual tooling.
+    #[must_use]
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::Rust => "Rust (Cargo)",
+            Self::Node => "Node.js (npm/bun)",
+            Self::Deno => "Deno",
+            Self::Python => "Python",
+            Self::Go => "Go",
+            Self::Maven => "Java (Maven)",
+            Self::Gradle => "Java/Kotlin (Gradle)",
+            Self::Ruby => "Ruby (Bundler)",
+            Self::Php => "PHP (Composer)",
+            Self::DotNet => ".NET",
+            Self::Zig => "Zig",
+            Self::Elixir => "Elixir (Mix)",
+            Self::Unknown => "Unknown",
+        }
+    }
+
+    /// Usual command to build the project.
+    #[must_use]
+    pub const fn build_command(&self) -> Option<&'static str> {
+        match self {
+            Self::Rust => Some("cargo build"),
+            Self::Node => Some("npm run build"),
+            Self::Go => Some("go build ./..."),
+            Self::Maven => Some("mvn package"),
+            Self::Gradle => Some("./gradlew build"),
+            Self::DotNet => Some("dotnet build"),
+            Self::Zig => Some("zig build"),
+            Self::Elixir => Some("mix compile"),
+            Self::Deno | Self::Python | Self::Ruby | Self::Php | Self::Unknown => None,
+        }
+    }
+
+    /// Usual command to run the project's tests.
+    #[must_use]
+    pub const fn test_command(&self) -> Option<&'static str> {
+        match self {
+            Self::Rust => Some("cargo test"),
+            Self::Node => Some("npm test"),
+            Self::Deno => Some("deno test"),
+            Self::Python => Some("pytest"),
+            Self::Go => Some("go test ./..."),
+            Self::Maven => Some("mvn test"),
+            Self::Gradle => Some("./gradlew test"),
+            Self::Ruby => Some("bundle exec rake test"),
+            Self::Php => Some("vendor/bin/phpunit"),
+            Self::DotNet => Some("dotnet test"),
+            Self::Zig => Some("zig build test"),
+            Self::Elixir => Some("mix test"),
+            Self::Unknown => None,
+        }
+    }
+
+    /// Usual command to lint the project.
+    #[must_use]
+    pub const fn lint_command(&self) -> Option<&'static str> {
+        match self {
+            Self::Rust => Some("cargo clippy"),
+            Self::Node => Some("npm run lint"),
+            Self::Deno => Some("deno lint"),
+            Self::Go => Some("go vet ./..."),
+            Self::Ruby => Some("bundle exec rubocop"),
+            Self::DotNet => Some("dotnet format --verify-no-changes"),
+            Self::Elixir => Some("mix format --check-formatted"),
+            Self::Python | Self::Maven | Self::Gradle | Self::Php | Self::Zig | Self::Unknown => {
+                None
+            }
+        }
+    }
+}
+
 /// Source of instruction content.
 #[derive(Debug, Clone)]
 pub struct InstructionSource {
@@ -87,7 +245,7 @@ impl ProjectContext {
         } else {
             None
         };
-        let project_type = detect_project_type(dir);
+        let project_stacks = detect_project_stacks(dir);
         let instructions = gather_instructions(dir);
         let file_tree = generate_file_tree(dir, 3); // depth 3
 
@@ -98,10 +256,23 @@ impl ProjectContext {
             is_git_repo,
             git_branch,
             git_status,
-            project_type,
+            project_stacks,
             instructions,
             file_tree,
+            git_diff: None,
+        }
+    }
+
+    /// Add the uncommitted changes, staged and unstaged, capped at `max_lines`.
+    ///
+    /// A diff over the cap is reduced to its diffstat plus the first hunk of
+    /// each file. Does nothing outside a git repository or with no changes.
+    #[must_use]
+    pub fn with_diff(mut self, max_lines: usize) -> Self {
+        if self.is_git_repo {
+            self.git_diff = get_git_diff(&self.working_dir, max_lines);
         }
+        self
     }
 
     /// Build a context string for injection into system prompts.
@@ -143,16 +314,25 @@ impl ProjectContext {
             parts.push(git_info);
         }
 
+        // Diff section
+        if let Some(diff) = &self.git_diff {
+            // Gathered once with the system prompt, so it goes stale as tools edit files
+            parts.push(format!(
+                "<git-diff>\nUncommitted changes when the session started; files may have changed since.\n{diff}\n</git-diff>"
+            ));
+        }
+
         // Project type section
-        if let Some(project_type) = &self.project_type {
-            let type_str = match project_type {
-                ProjectType::Rust => "Rust (Cargo)",
-                ProjectType::Node => "Node.js (npm/bun)",
-                ProjectType::Python => "Python",
-                ProjectType::Go => "Go",
-                ProjectType::Unknown => "Unknown",
-            };
-            parts.push(format!("<project-type>{type_str}</project-type>"));
+        if !self.project_stacks.is_empty() {
+            let mut project = String::from("<project-type>");
+            for stack in &self.project_stacks {
+                let _ = write!(project, "\n{}", stack.label());
+                for (kind, command) in stack.commands() {
+                    let _ = write!(project, "\n  {kind}: {command}");
+                }
+            }
+            project.push_str("\n</project-type>");
+            parts.push(project);
         }
 
         // File tree section
@@ -290,32 +470,146 @@ fn get_git_status(dir: &Path) -> GitStatus {
     status
 }
 
-/// Detect the project type based on manifest files.
-fn detect_project_type(dir: &Path) -> Option<ProjectType> {
-    // Walk up the directory tree to find project root
-    let mut current = dir.to_path_buf();
-    loop {
-        if current.join("Cargo.toml").exists() {
-            return Some(ProjectType::Rust);
-        }
-        if current.join("package.json").exists() {
-            return Some(ProjectType::Node);
-        }
-        if current.join("pyproject.toml").exists()
-            || current.join("setup.py").exists()
-            || current.join("requirements.txt").exists()
-        {
-            return Some(ProjectType::Python);
-        }
-        if current.join("go.mod").exists() {
-            return Some(ProjectType::Go);
-        }
+/// Lines kept from each file when a diff is over its cap.
+const DIFF_FILE_LINES: usize = 20;
+
+/// Get the diff of the working tree against `HEAD`, truncated to `max_lines`.
+fn get_git_diff(dir: &Path, max_lines: usize) -> Option<String> {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+    };
+
+    // Without commits there's no HEAD, so staged changes are all there is
+    let (diff, stat) = match git(&["diff", "HEAD"]) {
+        Some(diff) => (diff, git(&["diff", "HEAD", "--stat"])),
+        None => (
+            git(&["diff", "--staged"])?,
+            git(&["diff", "--staged", "--stat"]),
+        ),
+    };
+    if diff.trim().is_empty() {
+        return None;
+    }
+    Some(truncate_diff(
+        &diff,
+        stat.as_deref().unwrap_or(""),
+        max_lines,
+    ))
+}
 
-        if !current.pop() {
-            break;
+/// Cap `diff` at `max_lines`.
+///
+/// Over the cap, the result is `stat` followed by each file's header and
+/// first hunk, at most `DIFF_FILE_LINES` lines per file.
+fn truncate_diff(diff: &str, stat: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = diff.lines().collect();
+    if lines.len() <= max_lines {
+        return lines.join("\n");
+    }
+
+    let mut kept: Vec<String> = stat.lines().map(str::to_string).collect();
+    let mut file: Vec<&str> = Vec::new();
+    for line in lines {
+        if line.starts_with("diff --git ") && !file.is_empty() {
+            push_first_hunk(&file, &mut kept);
+            file.clear();
         }
+        file.push(line);
     }
-    None
+    push_first_hunk(&file, &mut kept);
+
+    if kept.len() > max_lines {
+        kept.truncate(max_lines);
+        kept.push("... (diff truncated)".to_string());
+    }
+    kept.join("\n")
+}
+
+/// Push one file's diff header and first hunk, at most `DIFF_FILE_LINES` lines.
+fn push_first_hunk(file: &[&str], kept: &mut Vec<String>) {
+    let first_hunk_end = file
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with("@@"))
+        .nth(1)
+        .map_or(file.len(), |(i, _)| i);
+    let shown = first_hunk_end.min(DIFF_FILE_LINES);
+    kept.extend(file[..shown].iter().map(|line| (*line).to_string()));
+    if file.len() > shown {
+        kept.push(format!("... ({} more lines)", file.len() - shown));
+    }
+}
+
+/// Detect the project stacks in the project root and its immediate
+/// subdirectories.
+///
+/// The root is the nearest of `dir` and its ancestors with any manifest file,
+/// or `dir` itself when none has one. Every stack with a manifest is
+/// reported, so a repository with a `Cargo.toml` at the top and a
+/// `package.json` in `web/` yields Rust and Node in `web/`. Hidden
+/// subdirectories are skipped.
+#[must_use]
+pub fn detect_project_stacks(dir: &Path) -> Vec<ProjectStack> {
+    let root = dir
+        .ancestors()
+        .find(|ancestor| !project_types_in(ancestor).is_empty())
+        .unwrap_or(dir);
+
+    let mut subdirs: Vec<PathBuf> = std::fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| !name.starts_with('.'))
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    subdirs.sort();
+
+    std::iter::once(PathBuf::new())
+        .chain(subdirs)
+        .flat_map(|path| {
+            project_types_in(&root.join(&path))
+                .into_iter()
+                .map(move |project_type| ProjectStack {
+                    project_type,
+                    path: path.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Project types with a manifest directly in `dir`.
+fn project_types_in(dir: &Path) -> Vec<ProjectType> {
+    let file_names: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    PROJECT_MANIFESTS
+        .iter()
+        .filter(|(_, manifests)| {
+            manifests.iter().any(|manifest| {
+                manifest.strip_prefix('*').map_or_else(
+                    || file_names.iter().any(|name| name == manifest),
+                    |extension| file_names.iter().any(|name| name.ends_with(extension)),
+                )
+            })
+        })
+        .map(|(project_type, _)| *project_type)
+        .collect()
 }
 
 /// Gather instruction files (CLAUDE.md) from the directory tree.
@@ -471,6 +765,115 @@ mod tests {
         assert!(["linux", "macos", "windows", "unknown"].contains(&platform.as_str()));
     }
 
+    #[test]
+    fn detects_every_stack_in_the_project_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("App.csproj"), "").unwrap();
+        std::fs::write(dir.path().join("mix.exs"), "").unwrap();
+        let nested = dir.path().join("src").join("web");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let root = |project_type| ProjectStack {
+            project_type,
+            path: PathBuf::new(),
+        };
+        let expected = vec![
+            root(ProjectType::Rust),
+            root(ProjectType::Node),
+            root(ProjectType::DotNet),
+            root(ProjectType::Elixir),
+        ];
+        assert_eq!(detect_project_stacks(dir.path()), expected);
+        assert_eq!(detect_project_stacks(&nested), expected);
+
+        let context = ProjectContext {
+            project_stacks: vec![
+                root(ProjectType::Rust),
+                ProjectStack {
+                    project_type: ProjectType::Php,
+                    path: PathBuf::from("api"),
+                },
+            ],
+            ..ProjectContext::default()
+        };
+        let prompt = context.to_prompt_context();
+        assert!(prompt.contains(
+            "<project-type>\nRust (Cargo)\n  Build: cargo build\n  Test: cargo test\n  Lint: cargo clippy\nPHP (Composer) in api/\n  Test: cd api && vendor/bin/phpunit\n</project-type>"
+        ));
+    }
+
+    #[test]
+    fn detects_stacks_in_subdirectories() {
+        let dir = tempfile::tempdir().unwrap();
+        for (subdir, manifest) in [
+            ("web", "package.json"),
+            ("api", "go.mod"),
+            (".cache", "Cargo.toml"),
+        ] {
+            std::fs::create_dir(dir.path().join(subdir)).unwrap();
+            std::fs::write(dir.path().join(subdir).join(manifest), "").unwrap();
+        }
+
+        let stacks = detect_project_stacks(dir.path());
+        let labels: Vec<String> = stacks.iter().map(ProjectStack::label).collect();
+        assert_eq!(labels, vec!["Go in api/", "Node.js (npm/bun) in web/"]);
+    }
+
+    #[test]
+    fn diff_section_says_when_it_was_taken() {
+        let context = ProjectContext {
+            git_diff: Some("+new".to_string()),
+            ..ProjectContext::default()
+        };
+        assert!(context.to_prompt_context().contains(
+            "<git-diff>\nUncommitted changes when the session started; files may have changed since.\n+new\n</git-diff>"
+        ));
+    }
+
+    #[test]
+    fn large_diffs_keep_stat_and_first_hunks() {
+        let file = |name: &str| {
+            let mut lines = vec![
+                format!("diff --git a/{name} b/{name}"),
+                format!("--- a/{name}"),
+                format!("+++ b/{name}"),
+                "@@ -1,3 +1,3 @@".to_string(),
+                "-old".to_string(),
+                "+new".to_string(),
+                "@@ -40,3 +40,3 @@".to_string(),
+            ];
+            lines.extend((0..30).map(|i| format!("+line {i}")));
+            lines.join("\n")
+        };
+        let diff = format!("{}\n{}", file("a.rs"), file("b.rs"));
+        let stat = " a.rs | 32 +\n b.rs | 32 +\n 2 files changed";
+
+        assert_eq!(truncate_diff(&diff, stat, 1_000), diff);
+
+        let truncated = truncate_diff(&diff, stat, 40);
+        let lines: Vec<&str> = truncated.lines().collect();
+        assert_eq!(&lines[..3], stat.lines().collect::<Vec<_>>().as_slice());
+        assert_eq!(
+            &lines[3..10],
+            &[
+                "diff --git a/a.rs b/a.rs",
+                "--- a/a.rs",
+                "+++ b/a.rs",
+                "@@ -1,3 +1,3 @@",
+                "-old",
+                "+new",
+                "... (31 more lines)",
+            ]
+        );
+        assert_eq!(lines[10], "diff --git a/b.rs b/b.rs");
+
+        let capped = truncate_diff(&diff, stat, 5);
+        assert_eq!(capped.lines().count(), 6);
+        assert!(capped.ends_with("... (diff truncated)"));
+    }
+
     #[test]
     fn gather_context_works() {
         let ctx = ProjectContext::gather();
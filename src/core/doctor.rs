@@ -0,0 +1,271 @@
+//! Environment diagnostics for `omni doctor`.
+//!
+//! Aggregates provider, git, terminal, directory, and storage checks into a
+//! single report suitable for bug reports.
+
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::{AgentConfig, Config};
+use crate::core::storage::Storage;
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// Everything is fine.
+    Ok,
+    /// Something is missing but omni can still run.
+    Warn,
+    /// A critical problem that prevents normal use.
+    Fail,
+}
+
+impl CheckStatus {
+    /// Short label for report output.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warn => "warn",
+            Self::Fail => "FAIL",
+        }
+    }
+}
+
+/// A single diagnostic result.
+#[derive(Debug, Clone)]
+pub struct Check {
+    /// Report section the check belongs to.
+    pub section: &'static str,
+    /// What was checked.
+    pub name: String,
+    /// Outcome.
+    pub status: CheckStatus,
+    /// Human-readable detail.
+    pub detail: String,
+}
+
+impl Check {
+    fn new(
+        section: &'static str,
+        name: impl Into<String>,
+        status: CheckStatus,
+        detail: impl Into<String>,
+    ) -> Self {
+        Self {
+            section,
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Run all diagnostic checks.
+#[must_use]
+pub fn run_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    match Config::load() {
+        Ok(config) => {
+            checks.push(Check::new("config", "load", CheckStatus::Ok, "parsed"));
+            checks.extend(provider_checks(&config.agent));
+        }
+        Err(e) => checks.push(Check::new(
+            "config",
+            "load",
+            CheckStatus::Fail,
+            e.to_string(),
+        )),
+    }
+
+    checks.extend(directory_checks());
+    checks.push(git_check());
+    checks.extend(terminal_checks());
+    checks.push(storage_check());
+
+    checks
+}
+
+/// Check which providers have resolvable API keys.
+#[must_use]
+pub fn provider_checks(agent: &AgentConfig) -> Vec<Check> {
+    let mut names: Vec<&String> = agent.providers.keys().collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let config = &agent.providers[name];
+            let active = *name == agent.provider;
+            let needs_key = config.api_key_env.is_some() || config.api_key.is_some();
+
+            let (status, detail) = match agent.find_api_key(name) {
+                Ok(found) => (CheckStatus::Ok, format!("API key found ({})", found.source)),
+                Err(_) if !needs_key => (CheckStatus::Ok, "no API key required".to_string()),
+                Err(missing) => {
+                    let env = config.api_key_env.as_deref().unwrap_or("api_key");
+                    let status = if active {
+                        CheckStatus::Fail
+                    } else {
+                        CheckStatus::Warn
+                    };
+                    let detail = match missing.keychain_unavailable {
+                        Some(reason) => format!("{env} not set; OS keyring unavailable ({reason})"),
+                        None => format!("{env} not set"),
+                    };
+                    (status, detail)
+                }
+            };
+
+            let label = if active {
+                format!("{name} (active)")
+            } else {
+                name.clone()
+            };
+            Check::new("providers", label, status, detail)
+        })
+        .collect()
+}
+
+fn directory_checks() -> Vec<Check> {
+    [
+        ("config dir", Config::config_dir()),
+        ("data dir", Config::data_dir()),
+    ]
+    .into_iter()
+    .map(|(name, dir)| match dir {
+        Ok(dir) => match check_writable(&dir) {
+            Ok(()) => Check::new(
+                "directories",
+                name,
+                CheckStatus::Ok,
+                dir.display().to_string(),
+            ),
+            Err(e) => Check::new(
+                "directories",
+                name,
+                CheckStatus::Fail,
+                format!("{} is not writable: {e}", dir.display()),
+            ),
+        },
+        Err(e) => Check::new("directories", name, CheckStatus::Fail, e.to_string()),
+    })
+    .collect()
+}
+
+/// Verify a directory exists (creating it if needed) and accepts writes.
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let probe = dir.join(".omni-doctor-probe");
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+fn git_check() -> Check {
+    match Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => Check::new(
+            "tools",
+            "git",
+            CheckStatus::Ok,
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        _ => Check::new(
+            "tools",
+            "git",
+            CheckStatus::Warn,
+            "not found (snapshots and worktrees unavailable)",
+        ),
+    }
+}
+
+fn terminal_checks() -> Vec<Check> {
+    if !std::io::stdout().is_terminal() {
+        return vec![Check::new(
+            "terminal",
+            "tty",
+            CheckStatus::Warn,
+            "stdout is not a terminal (TUI unavailable)",
+        )];
+    }
+
+    let raw_mode = match crossterm::terminal::enable_raw_mode() {
+        Ok(()) => {
+            let _ = crossterm::terminal::disable_raw_mode();
+            Check::new("terminal", "raw mode", CheckStatus::Ok, "supported")
+        }
+        Err(e) => Check::new("terminal", "raw mode", CheckStatus::Fail, e.to_string()),
+    };
+
+    let enhancement = match crossterm::terminal::supports_keyboard_enhancement() {
+        Ok(true) => Check::new(
+            "terminal",
+            "keyboard enhancement",
+            CheckStatus::Ok,
+            "supported",
+        ),
+        _ => Check::new(
+            "terminal",
+            "keyboard enhancement",
+            CheckStatus::Warn,
+            "unsupported (Shift+Enter may not insert newlines)",
+        ),
+    };
+
+    vec![raw_mode, enhancement]
+}
+
+fn storage_check() -> Check {
+    let storage = match Storage::new() {
+        Ok(storage) => storage,
+        Err(e) => return Check::new("storage", "entries", CheckStatus::Fail, e.to_string()),
+    };
+
+    match storage.scan_corrupt() {
+        Ok(corrupt) if corrupt.is_empty() => {
+            Check::new("storage", "entries", CheckStatus::Ok, "no corrupt entries")
+        }
+        Ok(corrupt) => {
+            let paths: Vec<String> = corrupt.iter().map(|p| p.display().to_string()).collect();
+            Check::new(
+                "storage",
+                "entries",
+                CheckStatus::Warn,
+                format!("{} corrupt: {}", corrupt.len(), paths.join(", ")),
+            )
+        }
+        Err(e) => Check::new("storage", "entries", CheckStatus::Warn, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderConfig;
+
+    #[test]
+    fn missing_key_fails_only_for_active_provider() {
+        let mut agent = AgentConfig::default();
+        agent.providers.clear();
+        agent.provider = "active".to_string();
+        for name in ["active", "other"] {
+            agent.providers.insert(
+                name.to_string(),
+                ProviderConfig {
+                    api_key_env: Some("OMNI_DOCTOR_TEST_UNSET_KEY".to_string()),
+                    ..ProviderConfig::default()
+                },
+            );
+        }
+        agent
+            .providers
+            .insert("local".to_string(), ProviderConfig::default());
+
+        let checks = provider_checks(&agent);
+        let status = |name: &str| checks.iter().find(|c| c.name == name).unwrap().status;
+        assert_eq!(status("active (active)"), CheckStatus::Fail);
+        assert_eq!(status("other"), CheckStatus::Warn);
+        assert_eq!(status("local"), CheckStatus::Ok);
+    }
+}
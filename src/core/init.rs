@@ -0,0 +1,183 @@
+//! Project scaffolding for `omni init`.
+//!
+//! Writes a commented `.omni/config.toml` and, when the project has no
+//! instruction file yet, a starter `AGENTS.md` with the detected build and
+//! test commands.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use super::context::{ProjectStack, detect_project_stacks};
+
+/// Instruction files the agent reads, in the order they're looked for.
+const INSTRUCTION_FILES: &[&str] = &["AGENTS.md", "CLAUDE.md"];
+
+/// What happened to one scaffolded file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitAction {
+    /// The file didn't exist and was written.
+    Created,
+    /// The file existed and was replaced because of `--force`.
+    Overwritten,
+    /// An instruction file already exists, so it was left alone.
+    Kept,
+}
+
+impl InitAction {
+    /// Short label for command output.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Overwritten => "overwrote",
+            Self::Kept => "kept",
+        }
+    }
+}
+
+/// Scaffold project-local omni files in `root`.
+///
+/// Existing files are only replaced when `force` is set. An existing
+/// `AGENTS.md` or `CLAUDE.md` is kept as is without it, since a project only
+/// needs one instruction file.
+///
+/// # Errors
+///
+/// Returns an error if `.omni/config.toml` already exists and `force` is not
+/// set, or if a file cannot be written.
+pub fn init_project(root: &Path, force: bool) -> anyhow::Result<Vec<(PathBuf, InitAction)>> {
+    let config_path = root.join(".omni").join("config.toml");
+    if config_path.exists() && !force {
+        anyhow::bail!(
+            "{} already exists (use --force to overwrite)",
+            config_path.display()
+        );
+    }
+
+    let project_stacks = detect_project_stacks(root);
+    let existing_instructions = INSTRUCTION_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.exists());
+
+    let mut results = Vec::new();
+    results.push(write_file(&config_path, &config_template())?);
+    match existing_instructions {
+        Some(path) if !force => results.push((path, InitAction::Kept)),
+        Some(path) => results.push(write_file(&path, &instructions_template(&project_stacks))?),
+        None => results.push(write_file(
+            &root.join(INSTRUCTION_FILES[0]),
+            &instructions_template(&project_stacks),
+        )?),
+    }
+    Ok(results)
+}
+
+/// Write `contents` to `path`, creating parent directories.
+fn write_file(path: &Path, contents: &str) -> anyhow::Result<(PathBuf, InitAction)> {
+    let action = if path.exists() {
+        InitAction::Overwritten
+    } else {
+        InitAction::Created
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents)?;
+    Ok((path.to_path_buf(), action))
+}
+
+/// Starter `.omni/config.toml`, with every setting commented out.
+fn config_template() -> String {
+    "\
+# Project settings for omni, merged over the global config.
+# Uncomment a setting to override it for this project.
+
+[agent]
+# model = \"claude-sonnet-4-20250514\"
+# max_tokens = 8192
+
+[storage]
+# Keep sessions with the project instead of in the global data directory
+# session_dir = \".omni/sessions\"
+
+# Project personas live in .omni/personas/<name>.toml
+"
+    .to_string()
+}
+
+/// Starter instruction file, with commands for each of `project_stacks`.
+fn instructions_template(project_stacks: &[ProjectStack]) -> String {
+    let mut contents = String::from(
+        "# Agent instructions\n\n\
+         Guidance for AI agents working in this repository.\n\n\
+         ## Commands\n\n",
+    );
+
+    let commands: Vec<String> = project_stacks
+        .iter()
+        .flat_map(ProjectStack::commands)
+        .map(|(kind, command)| format!("- {kind}: `{command}`"))
+        .collect();
+    if commands.is_empty() {
+        contents.push_str("- Build: <command>\n- Test: <command>\n");
+    }
+    for command in commands {
+        let _ = writeln!(contents, "{command}");
+    }
+
+    contents.push_str(
+        "\n## Conventions\n\n\
+         - Describe code style, naming and layout rules here.\n",
+    );
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaffolds_config_and_instructions_for_project_type() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        std::fs::create_dir(dir.path().join("web")).unwrap();
+        std::fs::write(dir.path().join("web/package.json"), "{}").unwrap();
+
+        let results = init_project(dir.path(), false).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                (dir.path().join(".omni/config.toml"), InitAction::Created),
+                (dir.path().join("AGENTS.md"), InitAction::Created),
+            ]
+        );
+
+        let config = std::fs::read_to_string(dir.path().join(".omni/config.toml")).unwrap();
+        assert!(toml::from_str::<crate::Config>(&config).is_ok());
+        let agents = std::fs::read_to_string(dir.path().join("AGENTS.md")).unwrap();
+        assert!(agents.contains("- Build: `cargo build`"));
+        assert!(agents.contains("- Test: `cargo test`"));
+        assert!(agents.contains("- Test: `cd web && npm test`"));
+    }
+
+    #[test]
+    fn refuses_to_overwrite_without_force() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("CLAUDE.md"), "keep me").unwrap();
+        init_project(dir.path(), false).unwrap();
+
+        assert!(init_project(dir.path(), false).is_err());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("CLAUDE.md")).unwrap(),
+            "keep me"
+        );
+        assert!(!dir.path().join("AGENTS.md").exists());
+
+        let results = init_project(dir.path(), true).unwrap();
+        assert_eq!(
+            results[1],
+            (dir.path().join("CLAUDE.md"), InitAction::Overwritten)
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Encrypted file fallback for API keys (`[api] allow_file_credentials`).
+//!
+//! Keys are sealed with ChaCha20-Poly1305 under a random key kept in a
+//! separate file, both readable only by the owner. This keeps keys out of
+//! plain text and tamper-evident, but anyone who can read both files can
+//! decrypt them, so it is weaker than an OS keyring.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// Sealed keys, keyed by provider.
+const CREDENTIALS_FILE: &str = "credentials.json";
+
+/// Encryption key for the sealed keys.
+const KEY_FILE: &str = "credentials.key";
+
+/// API keys sealed in a directory.
+#[derive(Debug, Clone)]
+pub struct CredentialFile {
+    dir: PathBuf,
+}
+
+impl CredentialFile {
+    /// Store in `dir`.
+    #[must_use]
+    pub const fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Store in the data directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data directory cannot be determined.
+    pub fn in_data_dir() -> anyhow::Result<Self> {
+        Ok(Self::new(crate::config::Config::data_dir()?))
+    }
+
+    /// The stored key for `provider`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files can't be read or the entry doesn't decrypt.
+    pub fn get(&self, provider: &str) -> anyhow::Result<Option<String>> {
+        let entries = self.read_entries()?;
+        let Some(sealed) = entries.get(provider) else {
+            return Ok(None);
+        };
+
+        let mut sealed = hex::decode(sealed)?;
+        if sealed.len() < NONCE_LEN {
+            anyhow::bail!("credentials entry for '{provider}' is truncated");
+        }
+        let mut in_out = sealed.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&sealed)
+            .map_err(|_| anyhow::anyhow!("credentials entry for '{provider}' is corrupt"))?;
+        let plain = self
+            .key(false)?
+            .open_in_place(nonce, Aad::from(provider.as_bytes()), &mut in_out)
+            .map_err(|_| anyhow::anyhow!("credentials entry for '{provider}' failed to decrypt"))?;
+        Ok(Some(String::from_utf8(plain.to_vec())?))
+    }
+
+    /// Store `key` for `provider`, replacing any existing one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files can't be written.
+    pub fn set(&self, provider: &str, key: &str) -> anyhow::Result<()> {
+        let sealing_key = self.key(true)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow::anyhow!("could not generate a nonce"))?;
+
+        let mut in_out = key.as_bytes().to_vec();
+        sealing_key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(provider.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| anyhow::anyhow!("could not encrypt the API key"))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(in_out);
+        let mut entries = self.read_entries()?;
+        entries.insert(provider.to_string(), hex::encode(sealed));
+        self.write_entries(&entries)
+    }
+
+    /// Remove the stored key for `provider`, returning whether there was one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the credentials file can't be read or written.
+    pub fn remove(&self, provider: &str) -> anyhow::Result<bool> {
+        let mut entries = self.read_entries()?;
+        let removed = entries.remove(provider).is_some();
+        if removed {
+            self.write_entries(&entries)?;
+        }
+        Ok(removed)
+    }
+
+    /// Providers with a stored key, sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the credentials file can't be read.
+    pub fn providers(&self) -> anyhow::Result<Vec<String>> {
+        Ok(self.read_entries()?.into_keys().collect())
+    }
+
+    fn read_entries(&self) -> anyhow::Result<BTreeMap<String, String>> {
+        let path = self.dir.join(CREDENTIALS_FILE);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    fn write_entries(&self, entries: &BTreeMap<String, String>) -> anyhow::Result<()> {
+        write_private(
+            &self.dir.join(CREDENTIALS_FILE),
+            serde_json::to_string_pretty(entries)?.as_bytes(),
+        )
+    }
+
+    /// Load the encryption key, generating it first if `create` is set.
+    fn key(&self, create: bool) -> anyhow::Result<LessSafeKey> {
+        let path = self.dir.join(KEY_FILE);
+        let bytes = if path.exists() {
+            std::fs::read(&path)?
+        } else if create {
+            let mut bytes = vec![0u8; CHACHA20_POLY1305.key_len()];
+            SystemRandom::new()
+                .fill(&mut bytes)
+                .map_err(|_| anyhow::anyhow!("could not generate an encryption key"))?;
+            write_private(&path, &bytes)?;
+            bytes
+        } else {
+            anyhow::bail!("credentials key {} is missing", path.display());
+        };
+
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &bytes)
+            .map_err(|_| anyhow::anyhow!("credentials key {} is invalid", path.display()))?;
+        Ok(LessSafeKey::new(key))
+    }
+}
+
+/// Write `contents` to `path`, readable only by the owner on Unix.
+fn write_private(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::io::Write as _;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(contents)?;
+    }
+    #[cfg(not(unix))]
+    std::fs::write(path, contents)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_and_removes_sealed_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CredentialFile::new(dir.path().to_path_buf());
+        assert_eq!(store.get("openai").unwrap(), None);
+
+        store.set("openai", "sk-test-1").unwrap();
+        store.set("groq", "gsk-test-2").unwrap();
+        assert_eq!(store.get("openai").unwrap().as_deref(), Some("sk-test-1"));
+        assert_eq!(store.providers().unwrap(), vec!["groq", "openai"]);
+
+        let raw = std::fs::read_to_string(dir.path().join(CREDENTIALS_FILE)).unwrap();
+        assert!(!raw.contains("sk-test-1"));
+
+        assert!(store.remove("openai").unwrap());
+        assert!(!store.remove("openai").unwrap());
+        assert_eq!(store.get("openai").unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_entries_moved_to_another_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CredentialFile::new(dir.path().to_path_buf());
+        store.set("openai", "sk-test").unwrap();
+
+        let mut entries = store.read_entries().unwrap();
+        let sealed = entries.remove("openai").unwrap();
+        entries.insert("anthropic".to_string(), sealed);
+        store.write_entries(&entries).unwrap();
+
+        assert!(store.get("anthropic").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = CredentialFile::new(dir.path().to_path_buf());
+        store.set("openai", "sk-test").unwrap();
+        for name in [CREDENTIALS_FILE, KEY_FILE] {
+            let mode = std::fs::metadata(dir.path().join(name))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600, "{name}");
+        }
+    }
+}
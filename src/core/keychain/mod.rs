@@ -0,0 +1,336 @@
+//! API keys stored in the OS keychain.
+//!
+//! Uses the `security` tool on macOS and `secret-tool` (libsecret) on Linux,
+//! so no keyring library is linked in. Headless machines often have no
+//! Secret Service running; that is reported as [`KeychainError::Unavailable`]
+//! rather than as a missing key, so callers can point users at the env var or
+//! the [`file`] fallback.
+
+pub mod file;
+
+use std::io::Write as _;
+use std::process::{Command, Output, Stdio};
+
+/// Service name keys are stored under.
+const SERVICE: &str = "omni-cli";
+
+/// Exit code `security` uses for a missing item.
+const MACOS_NOT_FOUND: i32 = 44;
+
+/// Why the keychain couldn't be used.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum KeychainError {
+    /// There's no usable keyring, e.g. no Secret Service over D-Bus.
+    #[error("OS keyring is unavailable: {0}")]
+    Unavailable(String),
+
+    /// The keyring is there but the operation failed.
+    #[error("keychain command failed: {0}")]
+    Failed(String),
+}
+
+/// Look up the stored API key for `provider`.
+///
+/// # Errors
+///
+/// Returns an error if the keychain can't be reached; a missing key is `Ok(None)`.
+pub fn get_api_key(provider: &str) -> Result<Option<String>, KeychainError> {
+    if cfg!(target_os = "macos") {
+        let output = run(
+            "security",
+            &["find-generic-password", "-s", SERVICE, "-a", provider, "-w"],
+            None,
+        )?;
+        lookup_result(&output, Some(MACOS_NOT_FOUND))
+    } else if cfg!(target_os = "linux") {
+        let output = run(
+            "secret-tool",
+            &["lookup", "service", SERVICE, "provider", provider],
+            None,
+        )?;
+        lookup_result(&output, None)
+    } else {
+        Err(unsupported())
+    }
+}
+
+/// Store `key` as the API key for `provider`, replacing any existing one.
+///
+/// On macOS the key is passed to `security` as an argument, as the tool has
+/// no other non-interactive input.
+///
+/// # Errors
+///
+/// Returns an error if the keychain can't be reached or rejects the key.
+pub fn set_api_key(provider: &str, key: &str) -> Result<(), KeychainError> {
+    let output = if cfg!(target_os = "macos") {
+        run(
+            "security",
+            &[
+                "add-generic-password",
+                "-U",
+                "-s",
+                SERVICE,
+                "-a",
+                provider,
+                "-w",
+                key,
+            ],
+            None,
+        )?
+    } else if cfg!(target_os = "linux") {
+        let label = format!("omni API key for {provider}");
+        run(
+            "secret-tool",
+            &[
+                "store", "--label", &label, "service", SERVICE, "provider", provider,
+            ],
+            Some(key),
+        )?
+    } else {
+        return Err(unsupported());
+    };
+    check_status(&output)
+}
+
+/// Remove the stored API key for `provider`.
+///
+/// Returns whether a key was stored.
+///
+/// # Errors
+///
+/// Returns an error if the keychain can't be reached.
+pub fn delete_api_key(provider: &str) -> Result<bool, KeychainError> {
+    if cfg!(target_os = "macos") {
+        let output = run(
+            "security",
+            &["delete-generic-password", "-s", SERVICE, "-a", provider],
+            None,
+        )?;
+        if output.status.code() == Some(MACOS_NOT_FOUND) {
+            return Ok(false);
+        }
+        check_status(&output).map(|()| true)
+    } else if cfg!(target_os = "linux") {
+        // `secret-tool clear` succeeds either way, so look first
+        if get_api_key(provider)?.is_none() {
+            return Ok(false);
+        }
+        let output = run(
+            "secret-tool",
+            &["clear", "service", SERVICE, "provider", provider],
+            None,
+        )?;
+        check_status(&output).map(|()| true)
+    } else {
+        Err(unsupported())
+    }
+}
+
+/// List the providers with a key stored under this tool's service.
+///
+/// # Errors
+///
+/// Returns an error if the keychain can't be reached.
+pub fn list_providers() -> Result<Vec<String>, KeychainError> {
+    let (output, attribute) = if cfg!(target_os = "macos") {
+        // Without -d the dump holds item attributes but no secrets
+        (
+            run("security", &["dump-keychain"], None)?,
+            "\"acct\"<blob>=",
+        )
+    } else if cfg!(target_os = "linux") {
+        (
+            run(
+                "secret-tool",
+                &["search", "--all", "service", SERVICE],
+                None,
+            )?,
+            "attribute.provider = ",
+        )
+    } else {
+        return Err(unsupported());
+    };
+    // Both tools fail when nothing matches, so only error output counts
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() && !stderr.trim().is_empty() {
+        return Err(KeychainError::Unavailable(stderr.trim().to_string()));
+    }
+    Ok(parse_providers(
+        &String::from_utf8_lossy(&output.stdout),
+        attribute,
+    ))
+}
+
+/// Pick provider names out of a keychain listing.
+///
+/// Items are split on `[` (`secret-tool`) or `keychain:` (`security`)
+/// headers; an item counts when it's under [`SERVICE`], and its provider
+/// is the value after `attribute`.
+fn parse_providers(listing: &str, attribute: &str) -> Vec<String> {
+    let mut providers = Vec::new();
+    let mut item: Vec<&str> = Vec::new();
+    let mut flush = |item: &mut Vec<&str>| {
+        let ours = item.iter().any(|line| {
+            *line == format!("attribute.service = {SERVICE}")
+                || *line == format!("\"svce\"<blob>=\"{SERVICE}\"")
+        });
+        let provider = item
+            .iter()
+            .find_map(|line| line.strip_prefix(attribute))
+            .map(|value| value.trim_matches('"').to_string());
+        if let (true, Some(provider)) = (ours, provider) {
+            if !provider.is_empty() && !providers.contains(&provider) {
+                providers.push(provider);
+            }
+        }
+        item.clear();
+    };
+    for line in listing.lines().map(str::trim) {
+        if line.starts_with('[') || line.starts_with("keychain:") {
+            flush(&mut item);
+        }
+        item.push(line);
+    }
+    flush(&mut item);
+    providers.sort();
+    providers
+}
+
+fn unsupported() -> KeychainError {
+    KeychainError::Unavailable("no keychain support on this platform".to_string())
+}
+
+/// Run a keychain tool, writing `input` to its stdin if given.
+fn run(program: &str, args: &[&str], input: Option<&str>) -> Result<Output, KeychainError> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                KeychainError::Unavailable(format!("{program} is not installed"))
+            }
+            _ => KeychainError::Unavailable(format!("could not run {program}: {e}")),
+        })?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| KeychainError::Failed(e.to_string()))?;
+    }
+    child
+        .wait_with_output()
+        .map_err(|e| KeychainError::Failed(e.to_string()))
+}
+
+/// Interpret the output of a lookup.
+///
+/// A failure with no error output is a missing key (`secret-tool`), as is
+/// `not_found_code` (`security`). Any other failure means the keyring itself
+/// couldn't be used.
+fn lookup_result(
+    output: &Output,
+    not_found_code: Option<i32>,
+) -> Result<Option<String>, KeychainError> {
+    if output.status.success() {
+        let key = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        return Ok((!key.is_empty()).then_some(key));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if (not_found_code.is_some() && output.status.code() == not_found_code)
+        || (not_found_code.is_none() && stderr.is_empty())
+    {
+        Ok(None)
+    } else {
+        Err(KeychainError::Unavailable(stderr))
+    }
+}
+
+fn check_status(output: &Output) -> Result<(), KeychainError> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(KeychainError::Failed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    use super::*;
+
+    fn output(code: i32, stdout: &str, stderr: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(code << 8),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn lookup_tells_missing_keys_from_unavailable_keyrings() {
+        assert_eq!(
+            lookup_result(&output(0, "sk-test\n", ""), None),
+            Ok(Some("sk-test".to_string()))
+        );
+        assert_eq!(lookup_result(&output(1, "", ""), None), Ok(None));
+        assert_eq!(
+            lookup_result(&output(44, "", "item not found"), Some(44)),
+            Ok(None)
+        );
+        assert_eq!(
+            lookup_result(
+                &output(1, "", "Cannot autolaunch D-Bus without X11 $DISPLAY"),
+                None
+            ),
+            Err(KeychainError::Unavailable(
+                "Cannot autolaunch D-Bus without X11 $DISPLAY".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn lists_providers_from_keychain_listings() {
+        let secret_tool = "\
+[/org/freedesktop/secrets/collection/login/12]
+label = omni API key for openai
+secret = sk-one
+attribute.provider = openai
+attribute.service = omni-cli
+[/org/freedesktop/secrets/collection/login/13]
+label = omni API key for old-proxy
+secret = sk-two
+attribute.service = omni-cli
+attribute.provider = old-proxy
+";
+        assert_eq!(
+            parse_providers(secret_tool, "attribute.provider = "),
+            ["old-proxy", "openai"]
+        );
+
+        let security = r#"keychain: "/Users/me/Library/Keychains/login.keychain-db"
+class: "genp"
+attributes:
+    "acct"<blob>="anthropic"
+    "svce"<blob>="omni-cli"
+keychain: "/Users/me/Library/Keychains/login.keychain-db"
+class: "genp"
+attributes:
+    "acct"<blob>="me@example.com"
+    "svce"<blob>="some-other-app"
+"#;
+        assert_eq!(parse_providers(security, "\"acct\"<blob>="), ["anthropic"]);
+    }
+}
@@ -11,4 +11,4 @@ mod server;
 pub use client::McpClient;
 pub use config::{McpConfig, McpServerConfig, ServerType};
 pub use protocol::{McpRequest, McpResponse, McpTool};
-pub use server::McpServer;
+pub use server::{McpServer, ProcessHandle, ServerStatus};
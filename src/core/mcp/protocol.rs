@@ -131,6 +131,7 @@ pub struct McpTool {
 
 /// MCP tool call result
 #[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct McpToolResult {
     pub content: Vec<McpContent>,
     #[serde(default)]
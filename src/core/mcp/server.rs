@@ -21,11 +21,39 @@ pub enum ServerStatus {
     Failed(String),
 }
 
+/// Shared handle to the server process, usable while a request holds the
+/// server
+#[derive(Clone, Default)]
+pub struct ProcessHandle(Arc<Mutex<Option<Child>>>);
+
+impl ProcessHandle {
+    /// Kill the process and anything it started, unblocking any request
+    /// waiting on it
+    pub fn kill(&self) {
+        if let Ok(mut process) = self.0.lock() {
+            if let Some(child) = process.as_mut() {
+                crate::core::process::kill_group(child.id());
+                let _ = child.kill();
+            }
+        }
+    }
+
+    fn set(&self, child: Child) {
+        if let Ok(mut process) = self.0.lock() {
+            *process = Some(child);
+        }
+    }
+
+    fn take(&self) -> Option<Child> {
+        self.0.lock().ok().and_then(|mut process| process.take())
+    }
+}
+
 /// MCP server connection
 pub struct McpServer {
     name: String,
     config: McpServerConfig,
-    process: Option<Child>,
+    process: ProcessHandle,
     request_id: AtomicU64,
     status: ServerStatus,
     capabilities: Option<InitializeResult>,
@@ -40,7 +68,7 @@ impl McpServer {
         Self {
             name: name.into(),
             config,
-            process: None,
+            process: ProcessHandle::default(),
             request_id: AtomicU64::new(1),
             status: ServerStatus::Disabled,
             capabilities: None,
@@ -74,6 +102,12 @@ impl McpServer {
         &self.tools
     }
 
+    /// Handle to the server process, for killing a request that hangs
+    #[must_use]
+    pub fn process_handle(&self) -> ProcessHandle {
+        self.process.clone()
+    }
+
     /// Connect to the server
     ///
     /// # Errors
@@ -111,6 +145,7 @@ impl McpServer {
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
+        crate::core::process::isolate(&mut command);
 
         // Add environment variables
         for (key, value) in &self.config.environment {
@@ -130,7 +165,7 @@ impl McpServer {
 
         self.stdin = Some(Arc::new(Mutex::new(stdin)));
         self.stdout_reader = Some(Arc::new(Mutex::new(BufReader::new(stdout))));
-        self.process = Some(child);
+        self.process.set(child);
 
         // Initialize the connection
         if let Err(e) = self.initialize() {
@@ -196,6 +231,24 @@ impl McpServer {
         name: &str,
         arguments: serde_json::Value,
     ) -> anyhow::Result<String> {
+        let (output, is_error) = self.call_tool_with_status(name, arguments)?;
+        if is_error {
+            anyhow::bail!("Tool error: {output}");
+        }
+        Ok(output)
+    }
+
+    /// Call a tool on this server, returning its output and whether the
+    /// tool reported an error
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the server isn't connected or the request fails
+    pub fn call_tool_with_status(
+        &mut self,
+        name: &str,
+        arguments: serde_json::Value,
+    ) -> anyhow::Result<(String, bool)> {
         if !self.is_connected() {
             anyhow::bail!("Server not connected");
         }
@@ -214,11 +267,7 @@ impl McpServer {
             .collect::<Vec<_>>()
             .join("\n");
 
-        if result.is_error {
-            anyhow::bail!("Tool error: {output}");
-        }
-
-        Ok(output)
+        Ok((output, result.is_error))
     }
 
     fn send_request(&self, request: &McpRequest) -> anyhow::Result<serde_json::Value> {
@@ -275,6 +324,7 @@ impl McpServer {
     /// Disconnect from the server
     pub fn disconnect(&mut self) {
         if let Some(mut process) = self.process.take() {
+            crate::core::process::kill_group(process.id());
             let _ = process.kill();
             let _ = process.wait();
         }
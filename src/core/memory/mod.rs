@@ -37,6 +37,20 @@ impl std::fmt::Display for MemoryCategory {
     }
 }
 
+impl std::str::FromStr for MemoryCategory {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "preference" => Ok(Self::Preference),
+            "project_fact" => Ok(Self::ProjectFact),
+            "correction" => Ok(Self::Correction),
+            "general" => Ok(Self::General),
+            other => anyhow::bail!("unknown memory category: {other}"),
+        }
+    }
+}
+
 /// A single memory item
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryItem {
@@ -307,6 +321,22 @@ impl MemoryManager {
 mod tests {
     use super::*;
 
+    #[test]
+    fn category_round_trips_through_display() {
+        for category in [
+            MemoryCategory::Preference,
+            MemoryCategory::ProjectFact,
+            MemoryCategory::Correction,
+            MemoryCategory::General,
+        ] {
+            assert_eq!(
+                category.to_string().parse::<MemoryCategory>().unwrap(),
+                category
+            );
+        }
+        assert!("nope".parse::<MemoryCategory>().is_err());
+    }
+
     #[test]
     fn memory_item_creation() {
         let item = MemoryItem::new(
@@ -1,12 +1,20 @@
 //! Core business logic shared across CLI, TUI, and API.
 
 pub mod agent;
+pub mod approvals;
+pub mod clipboard;
+pub mod color;
 pub mod context;
+pub mod doctor;
 mod error;
+pub mod init;
+pub mod keychain;
 pub mod lsp;
 pub mod mcp;
 pub mod memory;
+pub mod models;
 pub mod plugin;
+pub mod process;
 pub mod project;
 pub mod search;
 pub mod secret;
@@ -14,6 +22,7 @@ pub mod session;
 pub mod shell;
 pub mod skill;
 pub mod snapshot;
+pub mod stdin;
 pub mod storage;
 pub mod watcher;
 pub mod worktree;
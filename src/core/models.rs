@@ -0,0 +1,315 @@
+//! Configured models and provider reachability for `omni models`.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde::Serialize;
+
+use crate::config::{AgentConfig, ApiKey, MissingApiKey};
+use crate::core::agent::{AgentError, CompletionRequest, Content, Message, Role};
+
+/// How long a probe waits for the first response event.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Outcome of probing a provider with a minimal request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "detail", rename_all = "snake_case")]
+pub enum ProbeStatus {
+    /// The provider answered.
+    Ok,
+    /// The provider needs an API key and none is set.
+    MissingKey,
+    /// The provider rejected the credentials.
+    AuthFailed,
+    /// The provider could not be reached.
+    Unreachable(String),
+    /// The provider answered with some other error.
+    Error(String),
+}
+
+impl ProbeStatus {
+    /// Short label for table output.
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self {
+            Self::Ok => "ok".to_string(),
+            Self::MissingKey => "missing key".to_string(),
+            Self::AuthFailed => "auth failed".to_string(),
+            Self::Unreachable(detail) => format!("unreachable ({detail})"),
+            Self::Error(detail) => format!("error ({detail})"),
+        }
+    }
+
+    /// Classify a provider error.
+    #[must_use]
+    pub fn from_error(error: &AgentError) -> Self {
+        match error {
+            AgentError::ApiKeyMissing => Self::MissingKey,
+            AgentError::Api {
+                status: 401 | 403, ..
+            } => Self::AuthFailed,
+            AgentError::Api { message, .. } if looks_like_auth_error(message) => Self::AuthFailed,
+            AgentError::Http(e) => Self::Unreachable(e.to_string()),
+            AgentError::Transport(detail) => Self::Unreachable(detail.clone()),
+            other => Self::Error(other.to_string()),
+        }
+    }
+}
+
+/// Whether an error message without a status code describes bad credentials.
+fn looks_like_auth_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "401",
+        "403",
+        "unauthorized",
+        "invalid api key",
+        "invalid_api_key",
+    ]
+    .iter()
+    .any(|needle| message.contains(needle))
+}
+
+/// Models configured for one provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderModels {
+    /// Provider name.
+    pub provider: String,
+    /// Model IDs, in config order.
+    pub models: Vec<String>,
+    /// Probe result, if probed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe: Option<ProbeStatus>,
+}
+
+/// Group configured models by provider, sorted by provider name.
+///
+/// With `only`, lists just that provider, even if it has no models.
+///
+/// # Errors
+///
+/// Returns an error if `only` names a provider that isn't configured.
+pub fn models_by_provider(
+    config: &AgentConfig,
+    only: Option<&str>,
+) -> anyhow::Result<Vec<ProviderModels>> {
+    let mut grouped: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    if let Some(name) = only {
+        if !config.providers.contains_key(name) {
+            anyhow::bail!("unknown provider '{name}', check [agent.providers] config");
+        }
+        grouped.insert(name, Vec::new());
+    }
+
+    for model in &config.models {
+        if only.is_none_or(|name| name == model.provider) {
+            grouped
+                .entry(model.provider.as_str())
+                .or_default()
+                .push(model.id.clone());
+        }
+    }
+
+    Ok(grouped
+        .into_iter()
+        .map(|(provider, models)| ProviderModels {
+            provider: provider.to_string(),
+            models,
+            probe: None,
+        })
+        .collect())
+}
+
+/// Where one provider's API key comes from, for `omni auth status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderAuth {
+    /// Provider name.
+    pub provider: String,
+    /// Whether this is the default provider.
+    pub active: bool,
+    /// Whether the provider is configured with an API key source.
+    pub requires_key: bool,
+    /// Where the key was found, if it was.
+    pub source: Option<String>,
+    /// The key with all but its ends masked.
+    pub key: Option<String>,
+    /// Why the OS keychain couldn't be checked, if it couldn't.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keychain_unavailable: Option<String>,
+    /// Probe result, if probed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe: Option<ProbeStatus>,
+}
+
+/// Resolve the API key of every configured provider, sorted by name.
+#[must_use]
+pub fn auth_status(config: &AgentConfig) -> Vec<ProviderAuth> {
+    auth_status_with(config, |name| config.find_api_key(name))
+}
+
+/// [`auth_status`], looking keys up with `find_key`.
+fn auth_status_with(
+    config: &AgentConfig,
+    find_key: impl Fn(&str) -> Result<ApiKey, MissingApiKey>,
+) -> Vec<ProviderAuth> {
+    let mut names: Vec<&String> = config.providers.keys().collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let provider_config = &config.providers[name];
+            let (found, keychain_unavailable) = match find_key(name) {
+                Ok(found) => (Some(found), None),
+                Err(missing) => (None, missing.keychain_unavailable),
+            };
+            ProviderAuth {
+                provider: name.clone(),
+                active: *name == config.provider,
+                requires_key: provider_config.api_key_env.is_some()
+                    || provider_config.api_key.is_some(),
+                source: found.as_ref().map(|found| found.source.to_string()),
+                key: found.as_ref().map(ApiKey::masked),
+                keychain_unavailable,
+                probe: None,
+            }
+        })
+        .collect()
+}
+
+/// Model to probe `provider` with: its first configured model, or the
+/// default model if it's the default provider.
+#[must_use]
+pub fn probe_model<'a>(config: &'a AgentConfig, provider: &str) -> Option<&'a str> {
+    config
+        .models
+        .iter()
+        .find(|model| model.provider == provider)
+        .map(|model| model.id.as_str())
+        .or_else(|| (config.provider == provider).then_some(config.model.as_str()))
+}
+
+/// Send a one-token request to `provider` to check its credentials.
+///
+/// Only reachability and credentials are checked, so any `model` the
+/// provider serves will do.
+pub async fn probe_provider(config: &AgentConfig, provider: &str, model: &str) -> ProbeStatus {
+    let Some(provider_config) = config.providers.get(provider) else {
+        return ProbeStatus::Error(format!("unknown provider '{provider}'"));
+    };
+    let needs_key = provider_config.api_key_env.is_some() || provider_config.api_key.is_some();
+    if needs_key && config.find_api_key(provider).is_err() {
+        return ProbeStatus::MissingKey;
+    }
+
+    let client = match config.create_provider_by_name(provider) {
+        Ok(client) => client,
+        Err(e) => return ProbeStatus::Error(e.to_string()),
+    };
+    let request = CompletionRequest {
+        model: model.to_string(),
+        max_tokens: 1,
+        messages: vec![Message {
+            role: Role::User,
+            content: Content::Text("ping".to_string()),
+        }],
+        system: None,
+        tools: None,
+        reasoning_effort: None,
+    };
+
+    let probe = async {
+        let mut stream = client.stream(request).await?;
+        match stream.next().await {
+            Some(Err(e)) => Err(e),
+            _ => Ok(()),
+        }
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, probe).await {
+        Ok(Ok(())) => ProbeStatus::Ok,
+        Ok(Err(e)) => ProbeStatus::from_error(&e),
+        Err(_) => ProbeStatus::Unreachable("timed out".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_models_by_provider() {
+        let config = AgentConfig::default();
+        let all = models_by_provider(&config, None).unwrap();
+        let anthropic = all.iter().find(|p| p.provider == "anthropic").unwrap();
+        assert!(
+            anthropic
+                .models
+                .contains(&"claude-sonnet-4-20250514".to_string())
+        );
+        assert!(all.windows(2).all(|w| w[0].provider < w[1].provider));
+
+        let ollama = models_by_provider(&config, Some("ollama")).unwrap();
+        assert_eq!(ollama.len(), 1);
+        assert_eq!(ollama[0].provider, "ollama");
+
+        assert!(models_by_provider(&config, Some("nope")).is_err());
+    }
+
+    #[test]
+    fn reports_key_source_per_provider() {
+        let mut config = AgentConfig::default();
+        let provider = config.providers.get_mut("groq").unwrap();
+        provider.api_key_env = None;
+        provider.api_key = Some("gsk-test-0123456789".to_string());
+
+        // The keychain is never asked, so the real user's keys stay out of it
+        let status = auth_status_with(&config, |name| {
+            config.find_api_key_with(name, || Ok(None), None)
+        });
+        assert!(status.windows(2).all(|w| w[0].provider < w[1].provider));
+        let groq = status.iter().find(|p| p.provider == "groq").unwrap();
+        assert!(groq.requires_key);
+        assert_eq!(groq.source.as_deref(), Some("config api_key"));
+        assert_eq!(groq.key.as_deref(), Some("****6789"));
+        assert_eq!(
+            status.iter().filter(|p| p.active).count(),
+            usize::from(config.providers.contains_key(&config.provider))
+        );
+    }
+
+    #[test]
+    fn classifies_provider_errors() {
+        let api = |status, message: &str| AgentError::Api {
+            status,
+            message: message.to_string(),
+        };
+        assert_eq!(
+            ProbeStatus::from_error(&api(401, "bad key")),
+            ProbeStatus::AuthFailed
+        );
+        assert_eq!(
+            ProbeStatus::from_error(&api(0, "Invalid API key provided")),
+            ProbeStatus::AuthFailed
+        );
+        assert_eq!(
+            ProbeStatus::from_error(&AgentError::Transport("refused".to_string())),
+            ProbeStatus::Unreachable("refused".to_string())
+        );
+        assert!(matches!(
+            ProbeStatus::from_error(&api(500, "overloaded")),
+            ProbeStatus::Error(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn probe_reports_missing_key_without_a_request() {
+        let mut config = AgentConfig::default();
+        let provider = config.providers.get_mut("groq").unwrap();
+        provider.api_key_env = Some("OMNI_TEST_UNSET_PROBE_KEY".to_string());
+        provider.api_key = None;
+
+        let status = probe_provider(&config, "groq", "llama-3.1-8b-instant").await;
+        assert_eq!(status, ProbeStatus::MissingKey);
+    }
+}
@@ -0,0 +1,221 @@
+//! Out-of-process plugins backed by MCP servers
+//!
+//! The server is started over stdio the first time its tools are needed,
+//! and its `tools/list` result becomes the plugin's tools. Starting and
+//! calls run on blocking threads under a time limit; a server that misses
+//! it is killed.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+
+use super::{PluginHooks, ToolDefinition, ToolResult};
+use crate::core::mcp::{McpServer, McpServerConfig, ProcessHandle, ServerStatus};
+
+/// Longest a server may take to start and list its tools, unless its
+/// config sets a timeout
+const DEFAULT_START_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest a tool call may take by default
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Plugin that forwards tool calls to an MCP server
+pub struct McpPlugin {
+    server: Arc<Mutex<McpServer>>,
+    /// Server process, killed without the server lock when a call hangs
+    process: ProcessHandle,
+    /// Tools the server listed, so listing never waits on a call
+    tools: Arc<Mutex<Vec<ToolDefinition>>>,
+    /// Whether a connection has been tried (failures aren't retried)
+    started: Mutex<bool>,
+    start_timeout: Duration,
+    call_timeout: Duration,
+}
+
+impl McpPlugin {
+    /// Create a plugin for an MCP server, without starting it
+    #[must_use]
+    pub fn new(name: &str, config: McpServerConfig) -> Self {
+        let start_timeout = config
+            .timeout
+            .map_or(DEFAULT_START_TIMEOUT, Duration::from_millis);
+        let server = McpServer::new(name, config);
+        Self {
+            process: server.process_handle(),
+            server: Arc::new(Mutex::new(server)),
+            tools: Arc::new(Mutex::new(Vec::new())),
+            started: Mutex::new(false),
+            start_timeout,
+            call_timeout: DEFAULT_CALL_TIMEOUT,
+        }
+    }
+
+    /// Set the longest a tool call may take
+    #[must_use]
+    pub const fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = timeout;
+        self
+    }
+
+    /// Start the server unless a start was already tried
+    ///
+    /// Waits at most the start timeout. A server still starting after that
+    /// is killed, leaving the plugin without tools.
+    fn ensure_started(&self) {
+        {
+            let mut started = self.started.lock();
+            if *started {
+                return;
+            }
+            *started = true;
+        }
+
+        let server = Arc::clone(&self.server);
+        let tools = Arc::clone(&self.tools);
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut server = server.lock();
+            if let Err(e) = server.connect() {
+                tracing::warn!(server = %server.name(), error = %e, "failed to start MCP plugin");
+            }
+            if let ServerStatus::Failed(reason) = server.status() {
+                tracing::warn!(server = %server.name(), %reason, "MCP plugin unavailable");
+            }
+            *tools.lock() = server.tools().iter().map(tool_definition).collect();
+            let _ = done_tx.send(());
+        });
+
+        if done_rx.recv_timeout(self.start_timeout).is_err() {
+            tracing::warn!(
+                timeout_secs = self.start_timeout.as_secs(),
+                "MCP plugin did not start in time, stopping it"
+            );
+            self.process.kill();
+        }
+    }
+}
+
+/// Plugin tool definition for an MCP tool
+fn tool_definition(tool: &crate::core::mcp::McpTool) -> ToolDefinition {
+    ToolDefinition {
+        name: tool.name.clone(),
+        description: tool
+            .description
+            .clone()
+            .unwrap_or_else(|| format!("MCP tool: {}", tool.name)),
+        input_schema: tool.input_schema.clone(),
+    }
+}
+
+#[async_trait]
+impl PluginHooks for McpPlugin {
+    async fn on_unload(&self) -> anyhow::Result<()> {
+        // Unblock any hung call so the lock below frees up
+        self.process.kill();
+        let server = Arc::clone(&self.server);
+        tokio::task::spawn_blocking(move || server.lock().disconnect()).await?;
+        Ok(())
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        self.ensure_started();
+        self.tools.lock().clone()
+    }
+
+    async fn execute_tool(
+        &self,
+        name: &str,
+        args: serde_json::Value,
+    ) -> anyhow::Result<ToolResult> {
+        self.ensure_started();
+
+        // Stdio calls block, so keep them off the async workers
+        let server = Arc::clone(&self.server);
+        let tool = name.to_string();
+        let call =
+            tokio::task::spawn_blocking(move || server.lock().call_tool_with_status(&tool, args));
+        let Ok(result) = tokio::time::timeout(self.call_timeout, call).await else {
+            // The server is stuck mid-call; stop it so the call returns
+            self.process.kill();
+            self.tools.lock().clear();
+            anyhow::bail!(
+                "MCP tool '{name}' timed out after {}s, server stopped",
+                self.call_timeout.as_secs()
+            );
+        };
+        let (output, is_error) = result??;
+        Ok(ToolResult { output, is_error })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shell script that answers `initialize`, `tools/list` and one `tools/call`
+    const FAKE_SERVER: &str = r#"
+read -r _
+echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2024-11-05","capabilities":{},"serverInfo":{"name":"fake","version":"1"}}}'
+read -r _
+read -r _
+echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"echo","description":"Echo text","inputSchema":{"type":"object"}}]}}'
+read -r _
+echo '{"jsonrpc":"2.0","id":3,"result":{"content":[{"type":"text","text":"bad input"}],"isError":true}}'
+"#;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn mcp_plugin_lists_and_calls_server_tools() {
+        let config = McpServerConfig::local(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            FAKE_SERVER.to_string(),
+        ]);
+        let plugin = McpPlugin::new("fake", config);
+
+        let tools = plugin.tools();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "echo");
+        assert_eq!(tools[0].description, "Echo text");
+
+        let result = plugin
+            .execute_tool("echo", serde_json::json!({"text": "hi"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "bad input");
+        assert!(result.is_error);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn hung_call_times_out_and_stops_the_server() {
+        let script = r#"
+read -r _
+echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2024-11-05","capabilities":{},"serverInfo":{"name":"slow","version":"1"}}}'
+read -r _
+read -r _
+echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"wait","inputSchema":{"type":"object"}}]}}'
+read -r _
+sleep 30
+"#;
+        let config =
+            McpServerConfig::local(vec!["sh".to_string(), "-c".to_string(), script.to_string()]);
+        let plugin = McpPlugin::new("slow", config).with_call_timeout(Duration::from_millis(200));
+        assert_eq!(plugin.tools().len(), 1);
+
+        let err = plugin
+            .execute_tool("wait", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        assert!(plugin.tools().is_empty());
+    }
+
+    #[test]
+    fn failed_server_has_no_tools() {
+        let config = McpServerConfig::local(vec!["/nonexistent/mcp-server".to_string()]);
+        let plugin = McpPlugin::new("missing", config);
+        assert!(plugin.tools().is_empty());
+        assert!(plugin.tools().is_empty());
+    }
+}
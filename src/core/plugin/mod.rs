@@ -2,13 +2,19 @@
 //!
 //! Provides hooks for custom tools, providers, and event handling
 
+mod mcp;
+
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
+pub use mcp::McpPlugin;
+
+use crate::config::McpPluginConfig;
+
 /// Plugin metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginInfo {
@@ -20,6 +26,20 @@ pub struct PluginInfo {
     pub description: Option<String>,
     /// Plugin author
     pub author: Option<String>,
+    /// MCP server that provides the plugin's tools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp: Option<McpPluginConfig>,
+}
+
+/// Plugin found in the plugin directory
+#[derive(Debug, Clone)]
+pub struct DiscoveredPlugin {
+    /// Manifest from `plugin.json`
+    pub info: PluginInfo,
+    /// Settings from `config.json` or `config.toml` (empty if neither exists)
+    pub config: PluginConfig,
+    /// Directory the plugin was found in
+    pub dir: PathBuf,
 }
 
 /// Tool definition for plugins
@@ -102,6 +122,26 @@ pub struct PluginConfig {
     pub settings: HashMap<String, serde_json::Value>,
 }
 
+impl PluginConfig {
+    /// Settings as environment variables for an out-of-process plugin
+    ///
+    /// Keys become `OMNI_PLUGIN_<KEY>` in upper case with `-` as `_`.
+    /// Strings are passed as-is, other values as JSON.
+    #[must_use]
+    pub fn env_vars(&self) -> Vec<(String, String)> {
+        self.settings
+            .iter()
+            .map(|(key, value)| {
+                let name = format!("OMNI_PLUGIN_{}", key.to_uppercase().replace('-', "_"));
+                let value = value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), str::to_string);
+                (name, value)
+            })
+            .collect()
+    }
+}
+
 /// Plugin registry for managing loaded plugins
 pub struct PluginRegistry {
     plugins: HashMap<String, Arc<dyn PluginHooks>>,
@@ -142,6 +182,12 @@ impl PluginRegistry {
         self.configs.insert(name.into(), config);
     }
 
+    /// Get configuration for a plugin
+    #[must_use]
+    pub fn config(&self, name: &str) -> Option<&PluginConfig> {
+        self.configs.get(name)
+    }
+
     /// Get all registered plugin names
     #[must_use]
     pub fn names(&self) -> Vec<&str> {
@@ -315,6 +361,12 @@ impl PluginLoader {
         Ok(Self { plugin_dir })
     }
 
+    /// Create a plugin loader for a specific directory
+    #[must_use]
+    pub const fn with_dir(plugin_dir: PathBuf) -> Self {
+        Self { plugin_dir }
+    }
+
     /// Get the plugin directory path
     #[must_use]
     pub const fn plugin_dir(&self) -> &PathBuf {
@@ -327,6 +379,17 @@ impl PluginLoader {
     ///
     /// Returns error if directory reading fails
     pub fn list_available(&self) -> anyhow::Result<Vec<PluginInfo>> {
+        Ok(self.discover()?.into_iter().map(|p| p.info).collect())
+    }
+
+    /// Find plugins with a `plugin.json` manifest, along with their configs
+    ///
+    /// Plugins with an unreadable manifest or config are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if directory reading fails
+    pub fn discover(&self) -> anyhow::Result<Vec<DiscoveredPlugin>> {
         let mut plugins = Vec::new();
 
         if !self.plugin_dir.exists() {
@@ -334,21 +397,88 @@ impl PluginLoader {
         }
 
         for entry in std::fs::read_dir(&self.plugin_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            // Look for plugin.json or plugin.toml
-            let manifest_path = path.join("plugin.json");
-            if manifest_path.exists() {
-                let content = std::fs::read_to_string(&manifest_path)?;
-                if let Ok(info) = serde_json::from_str::<PluginInfo>(&content) {
-                    plugins.push(info);
-                }
+            let dir = entry?.path();
+
+            let manifest_path = dir.join("plugin.json");
+            if !manifest_path.exists() {
+                continue;
             }
+            let info = match std::fs::read_to_string(&manifest_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str::<PluginInfo>(&content)?))
+            {
+                Ok(info) => info,
+                Err(e) => {
+                    tracing::warn!(path = %manifest_path.display(), error = %e, "invalid plugin manifest");
+                    continue;
+                }
+            };
+            let config = match Self::read_config(&dir) {
+                Ok(config) => config,
+                Err(e) => {
+                    tracing::warn!(plugin = %info.name, error = %e, "invalid plugin config");
+                    continue;
+                }
+            };
+
+            plugins.push(DiscoveredPlugin { info, config, dir });
         }
 
+        plugins.sort_by(|a, b| a.info.name.cmp(&b.info.name));
         Ok(plugins)
     }
+
+    /// Read `config.json` or `config.toml` from a plugin directory
+    ///
+    /// Having both is an error, since neither can be picked over the other.
+    fn read_config(dir: &Path) -> anyhow::Result<PluginConfig> {
+        let json_path = dir.join("config.json");
+        let toml_path = dir.join("config.toml");
+        match (json_path.exists(), toml_path.exists()) {
+            (true, true) => anyhow::bail!("both config.json and config.toml exist; keep one"),
+            (true, false) => Ok(serde_json::from_str(&std::fs::read_to_string(json_path)?)?),
+            (false, true) => Ok(toml::from_str(&std::fs::read_to_string(toml_path)?)?),
+            (false, false) => Ok(PluginConfig::default()),
+        }
+    }
+
+    /// Register discovered plugins in `registry` and apply their configs
+    ///
+    /// Only plugins that declare how they run (currently an `mcp` server)
+    /// can be registered; others are skipped. Relative command paths such as
+    /// `./server` resolve against the plugin directory, and each config
+    /// setting reaches the server as an `OMNI_PLUGIN_<KEY>` environment
+    /// variable. Returns the names of the registered plugins.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if directory reading fails
+    pub fn load_into(&self, registry: &mut PluginRegistry) -> anyhow::Result<Vec<String>> {
+        let mut loaded = Vec::new();
+
+        for plugin in self.discover()? {
+            let name = plugin.info.name;
+            let Some(mut mcp) = plugin.info.mcp else {
+                tracing::debug!(plugin = %name, "plugin has no runnable entry, skipping");
+                continue;
+            };
+            if mcp.command.contains('/') && Path::new(&mcp.command).is_relative() {
+                mcp.command = plugin.dir.join(&mcp.command).display().to_string();
+            }
+            for (key, var) in plugin.config.env_vars() {
+                mcp.env.entry(key).or_insert(var);
+            }
+
+            registry.register(
+                name.clone(),
+                Arc::new(McpPlugin::new(&name, mcp.server_config())),
+            );
+            registry.set_config(name.clone(), plugin.config);
+            loaded.push(name);
+        }
+
+        Ok(loaded)
+    }
 }
 
 #[cfg(test)]
@@ -399,6 +529,59 @@ mod tests {
         assert_eq!(tools[0].0, "test::test_tool");
     }
 
+    #[test]
+    fn loader_registers_mcp_plugins_with_their_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "search/plugin.json",
+            r#"{"name": "search", "version": "1.0.0", "description": null, "author": null,
+                "mcp": {"command": "./bin/server", "args": ["--stdio"]}}"#,
+        );
+        write("search/config.toml", "index = \"docs\"\nlimit = 5\n");
+        write(
+            "notes/plugin.json",
+            r#"{"name": "notes", "version": "0.1.0", "description": null, "author": null}"#,
+        );
+        write("notes/config.json", r#"{"folder": "~/notes"}"#);
+        write("broken/plugin.json", "{");
+        write(
+            "twice/plugin.json",
+            r#"{"name": "twice", "version": "1.0.0", "description": null, "author": null}"#,
+        );
+        write("twice/config.json", "{}");
+        write("twice/config.toml", "");
+
+        let loader = PluginLoader::with_dir(dir.path().to_path_buf());
+        let discovered = loader.discover().unwrap();
+        let names: Vec<&str> = discovered.iter().map(|p| p.info.name.as_str()).collect();
+        assert_eq!(names, ["notes", "search"]);
+        assert_eq!(discovered[0].config.settings["folder"], "~/notes");
+
+        let mut registry = PluginRegistry::new();
+        let registered = loader.load_into(&mut registry).unwrap();
+        // Without a runnable entry, notes can't be registered
+        assert_eq!(registered, ["search"]);
+        assert!(registry.get("search").is_some());
+        let config = registry.config("search").unwrap();
+        assert_eq!(config.settings["index"], "docs");
+        assert_eq!(config.settings["limit"], 5);
+
+        let mut vars = config.env_vars();
+        vars.sort();
+        assert_eq!(
+            vars,
+            [
+                ("OMNI_PLUGIN_INDEX".to_string(), "docs".to_string()),
+                ("OMNI_PLUGIN_LIMIT".to_string(), "5".to_string()),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn registry_executes_tool() {
         let mut registry = PluginRegistry::new();
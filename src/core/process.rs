@@ -0,0 +1,32 @@
+//! Child process helpers.
+//!
+//! Commands started in their own process group can be killed along with
+//! everything they spawned, so a wrapper like `sh -c` or `npx` doesn't
+//! leave its children running.
+
+/// Start `command` in a new process group led by the child.
+pub fn isolate(command: &mut std::process::Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        command.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// Kill the process group led by `pid`, started with [`isolate`].
+///
+/// Does nothing on platforms without process groups.
+pub fn kill_group(pid: u32) {
+    #[cfg(unix)]
+    if let Ok(pid) = i32::try_from(pid) {
+        use nix::sys::signal::{Signal, killpg};
+        use nix::unistd::Pid;
+
+        let _ = killpg(Pid::from_raw(pid), Signal::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
@@ -1,7 +1,8 @@
 //! Project detection and management.
 //!
 //! Projects are identified by their git root commit hash.
-//! Non-git directories use the "global" project ID.
+//! Non-git directories use the "global" project ID, rooted at the nearest
+//! ancestor containing a `.omni` marker directory if there is one.
 
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -64,10 +65,10 @@ impl Project {
                 })
             }
             None => {
-                // No git repo, use global project
+                // No git repo, use global project rooted at the nearest `.omni` marker
                 Ok(Self {
                     id: GLOBAL_PROJECT_ID.to_string(),
-                    worktree: cwd.to_path_buf(),
+                    worktree: find_marker_root(cwd).unwrap_or_else(|| cwd.to_path_buf()),
                     vcs: None,
                     time: ProjectTime {
                         created: chrono::Utc::now().timestamp_millis(),
@@ -113,6 +114,14 @@ fn find_git_root(start: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Find the nearest ancestor (including `start`) containing a `.omni` directory.
+fn find_marker_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".omni").is_dir())
+        .map(Path::to_path_buf)
+}
+
 /// Get the root commit hash of a git repository.
 ///
 /// This returns the first commit, which uniquely identifies the repository.
@@ -158,6 +167,44 @@ mod tests {
         assert!(!project.id.is_empty());
     }
 
+    #[test]
+    fn detect_nested_directory_resolves_to_repo_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=omni", "-c", "user.email=omni@example.com"])
+                .args(args)
+                .current_dir(&root)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+
+        let nested = root.join("src").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let from_root = Project::detect(&root).unwrap();
+        let from_nested = Project::detect(&nested).unwrap();
+        assert_eq!(from_nested.id, from_root.id);
+        assert_eq!(from_nested.worktree, root);
+    }
+
+    #[test]
+    fn detect_non_git_directory_uses_marker_root() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp.path().join(".omni")).unwrap();
+        let nested = temp.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let project = Project::detect(&nested).unwrap();
+        assert_eq!(project.id, GLOBAL_PROJECT_ID);
+        assert_eq!(project.worktree, temp.path());
+    }
+
     #[test]
     fn detect_non_git_directory() {
         // Use temp directory which won't be a git repo
@@ -3,7 +3,7 @@
 //! When a session's context exceeds the threshold, older messages are
 //! summarized and replaced with a compact summary to free up context space
 
-use super::{Message, Part, Session, SessionManager};
+use super::{Message, Session, SessionManager};
 
 /// Default token threshold before triggering compaction
 pub const DEFAULT_COMPACTION_THRESHOLD: u32 = 100_000;
@@ -75,45 +75,6 @@ impl SessionManager {
         Ok(messages.into_iter().take(compact_count).collect())
     }
 
-    /// Build context for summarization from messages and their parts
-    ///
-    /// # Errors
-    ///
-    /// Returns error if part listing fails
-    pub fn build_compaction_context(&self, messages: &[Message]) -> anyhow::Result<String> {
-        use std::fmt::Write;
-        let mut context = String::new();
-
-        for msg in messages {
-            let role = match msg {
-                Message::User(_) => "User",
-                Message::Assistant(_) => "Assistant",
-            };
-
-            let _ = writeln!(context, "\n## {role}");
-
-            // Get parts for this message
-            let parts = self.list_parts(msg.id())?;
-            for part in parts {
-                match part {
-                    Part::Text(t) => {
-                        context.push_str(&t.text);
-                        context.push('\n');
-                    }
-                    Part::Tool(t) => {
-                        let _ = writeln!(context, "[Tool: {} - {}]", t.tool, t.state.as_str());
-                    }
-                    Part::Reasoning(r) => {
-                        let truncated: String = r.text.chars().take(100).collect();
-                        let _ = writeln!(context, "[Reasoning: {truncated}...]");
-                    }
-                }
-            }
-        }
-
-        Ok(context)
-    }
-
     /// Delete compacted messages and their parts
     ///
     /// # Errors
@@ -1,10 +1,12 @@
-//! Session export for sharing and backup
+//! Session export and import for sharing and backup
 //!
-//! Export sessions to JSON or Markdown format
+//! Export sessions to JSON, Markdown or HTML format, and import JSON exports
 
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use serde::Serialize;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
 
 use super::{Message, Part, Session, SessionManager};
 
@@ -15,10 +17,122 @@ pub enum ExportFormat {
     Json,
     /// Markdown format (human readable)
     Markdown,
+    /// Standalone HTML page (for sharing outside the terminal)
+    Html,
+}
+
+impl ExportFormat {
+    /// File extension for this format
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+
+    /// Render an exported session in this format
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn render(self, exported: &ExportedSession) -> anyhow::Result<String> {
+        match self {
+            Self::Json => Ok(serde_json::to_string_pretty(exported)?),
+            Self::Markdown => Ok(format_as_markdown(exported)),
+            Self::Html => Ok(super::html::format_as_html(exported)),
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            other => {
+                anyhow::bail!("unknown export format: {other} (expected json, markdown or html)")
+            }
+        }
+    }
+}
+
+/// Message role to keep in an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportRole {
+    /// User messages
+    User,
+    /// Assistant messages
+    Assistant,
+}
+
+impl ExportRole {
+    const fn matches(self, message: &Message) -> bool {
+        matches!(
+            (self, message),
+            (Self::User, Message::User(_)) | (Self::Assistant, Message::Assistant(_))
+        )
+    }
+}
+
+/// How tool parts are treated in an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolFilter {
+    /// Keep tool parts alongside everything else
+    #[default]
+    Include,
+    /// Drop tool parts
+    Exclude,
+    /// Keep only tool parts
+    Only,
+}
+
+/// Filter that shapes an export before it is formatted
+///
+/// Messages left with no parts after filtering are dropped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportFilter {
+    /// Keep only messages with this role
+    pub role: Option<ExportRole>,
+    /// Tool part handling
+    pub tools: ToolFilter,
+}
+
+impl ExportFilter {
+    /// Whether this filter keeps everything
+    #[must_use]
+    pub fn is_noop(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply the filter to an exported session
+    #[must_use]
+    pub fn apply(&self, mut exported: ExportedSession) -> ExportedSession {
+        if self.is_noop() {
+            return exported;
+        }
+
+        exported.messages.retain_mut(|m| {
+            if self.role.is_some_and(|role| !role.matches(&m.message)) {
+                return false;
+            }
+            match self.tools {
+                ToolFilter::Include => {}
+                ToolFilter::Exclude => m.parts.retain(|p| !matches!(p, Part::Tool(_))),
+                ToolFilter::Only => m.parts.retain(|p| matches!(p, Part::Tool(_))),
+            }
+            !m.parts.is_empty()
+        });
+        exported
+    }
 }
 
 /// Exported session data
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ExportedSession {
     /// Session metadata
     pub session: Session,
@@ -27,7 +141,7 @@ pub struct ExportedSession {
 }
 
 /// Exported message with parts
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ExportedMessage {
     /// Message data
     #[serde(flatten)]
@@ -36,6 +150,17 @@ pub struct ExportedMessage {
     pub parts: Vec<Part>,
 }
 
+/// Result of importing a session
+#[derive(Debug, Clone)]
+pub struct ImportedSession {
+    /// The newly created session
+    pub session: Session,
+    /// Number of messages imported
+    pub messages: usize,
+    /// Number of parts imported
+    pub parts: usize,
+}
+
 impl SessionManager {
     /// Export a session to a structured format
     ///
@@ -64,8 +189,7 @@ impl SessionManager {
     ///
     /// Returns error if export or serialization fails
     pub fn export_to_json(&self, session_id: &str) -> anyhow::Result<String> {
-        let exported = self.export_session(session_id)?;
-        Ok(serde_json::to_string_pretty(&exported)?)
+        self.export_formatted(session_id, ExportFormat::Json, &ExportFilter::default())
     }
 
     /// Export session to Markdown string
@@ -74,8 +198,31 @@ impl SessionManager {
     ///
     /// Returns error if export fails
     pub fn export_to_markdown(&self, session_id: &str) -> anyhow::Result<String> {
-        let exported = self.export_session(session_id)?;
-        Ok(format_as_markdown(&exported))
+        self.export_formatted(session_id, ExportFormat::Markdown, &ExportFilter::default())
+    }
+
+    /// Export session to a standalone HTML page
+    ///
+    /// # Errors
+    ///
+    /// Returns error if export fails
+    pub fn export_to_html(&self, session_id: &str) -> anyhow::Result<String> {
+        self.export_formatted(session_id, ExportFormat::Html, &ExportFilter::default())
+    }
+
+    /// Export session in `format` after applying `filter`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if export or serialization fails
+    pub fn export_formatted(
+        &self,
+        session_id: &str,
+        format: ExportFormat,
+        filter: &ExportFilter,
+    ) -> anyhow::Result<String> {
+        let exported = filter.apply(self.export_session(session_id)?);
+        format.render(&exported)
     }
 
     /// Export session to file
@@ -88,11 +235,9 @@ impl SessionManager {
         session_id: &str,
         path: &Path,
         format: ExportFormat,
+        filter: &ExportFilter,
     ) -> anyhow::Result<()> {
-        let content = match format {
-            ExportFormat::Json => self.export_to_json(session_id)?,
-            ExportFormat::Markdown => self.export_to_markdown(session_id)?,
-        };
+        let content = self.export_formatted(session_id, format, filter)?;
 
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
@@ -102,6 +247,128 @@ impl SessionManager {
         std::fs::write(path, content)?;
         Ok(())
     }
+
+    /// Import a JSON session export into this project
+    ///
+    /// Sessions, messages, and parts get fresh IDs; content, order, and
+    /// timestamps are preserved. The slug is kept unless it is already taken.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the JSON is not a session export or storage fails
+    pub fn import_from_json(&self, json: &str) -> anyhow::Result<ImportedSession> {
+        let exported: ExportedSession =
+            serde_json::from_str(json).context("not a valid session export")?;
+        self.import_session(exported)
+    }
+
+    /// Recreate an exported session in this project
+    ///
+    /// # Errors
+    ///
+    /// Returns error if ID generation or storage fails
+    pub fn import_session(&self, exported: ExportedSession) -> anyhow::Result<ImportedSession> {
+        // Monotonic IDs keep messages and parts in their exported order
+        let mut ids = ulid::Generator::new();
+        let mut next_id = |prefix: &str| -> anyhow::Result<String> {
+            Ok(format!("{prefix}_{}", ids.generate()?))
+        };
+
+        let slug_taken = self
+            .list_sessions()?
+            .iter()
+            .any(|s| s.slug == exported.session.slug);
+
+        let mut session = Session::new(&self.project);
+        session.id = next_id("ses")?;
+        if !slug_taken {
+            session.slug = exported.session.slug;
+        }
+        session.title = exported.session.title;
+        session.time = exported.session.time;
+        session.summary = exported.session.summary;
+        session.system_append = exported.session.system_append;
+        self.update_session(&session)?;
+
+        let mut message_ids: HashMap<String, String> = HashMap::new();
+        let mut parts_imported = 0;
+        for ExportedMessage { mut message, parts } in exported.messages {
+            let message_id = next_id("msg")?;
+            message_ids.insert(message.id().to_string(), message_id.clone());
+            match &mut message {
+                Message::User(m) => {
+                    m.id.clone_from(&message_id);
+                    m.session_id.clone_from(&session.id);
+                }
+                Message::Assistant(m) => {
+                    m.id.clone_from(&message_id);
+                    m.session_id.clone_from(&session.id);
+                    if let Some(parent) = message_ids.get(&m.parent_id) {
+                        m.parent_id.clone_from(parent);
+                    }
+                }
+            }
+            self.save_message(&session.id, &message)?;
+
+            for mut part in parts {
+                let part_id = next_id("prt")?;
+                let (id, part_message_id, part_session_id) = match &mut part {
+                    Part::Text(p) => (&mut p.id, &mut p.message_id, &mut p.session_id),
+                    Part::Tool(p) => (&mut p.id, &mut p.message_id, &mut p.session_id),
+                    Part::Reasoning(p) => (&mut p.id, &mut p.message_id, &mut p.session_id),
+                };
+                *id = part_id;
+                part_message_id.clone_from(&message_id);
+                part_session_id.clone_from(&session.id);
+                self.save_part(&message_id, &part)?;
+                parts_imported += 1;
+            }
+        }
+
+        Ok(ImportedSession {
+            session,
+            messages: message_ids.len(),
+            parts: parts_imported,
+        })
+    }
+
+    /// Export every session to its own file in `dir`, named by slug
+    ///
+    /// Colliding slugs get a numeric suffix. Returns the written paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if sessions cannot be listed or any export fails
+    pub fn export_all_to_dir(
+        &self,
+        dir: &Path,
+        format: ExportFormat,
+        filter: &ExportFilter,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(dir)?;
+
+        let mut used = HashSet::new();
+        let mut written = Vec::new();
+        for session in self.list_sessions()? {
+            let base = if session.slug.is_empty() {
+                session.id.clone()
+            } else {
+                session.slug.clone()
+            };
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while !used.insert(name.clone()) {
+                name = format!("{base}-{suffix}");
+                suffix += 1;
+            }
+
+            let path = dir.join(format!("{name}.{}", format.extension()));
+            self.export_to_file(&session.id, &path, format, filter)?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
 }
 
 /// Format exported session as Markdown
@@ -131,7 +398,11 @@ fn format_as_markdown(exported: &ExportedSession) -> String {
             Message::Assistant(_) => "Assistant",
         };
 
-        let _ = writeln!(md, "## {role}\n");
+        let _ = writeln!(
+            md,
+            "## {role} · {}\n",
+            format_timestamp(exported_msg.message.time().created)
+        );
 
         // Parts
         for part in &exported_msg.parts {
@@ -177,7 +448,7 @@ fn format_as_markdown(exported: &ExportedSession) -> String {
 }
 
 /// Format timestamp as ISO 8601
-fn format_timestamp(ts: i64) -> String {
+pub(super) fn format_timestamp(ts: i64) -> String {
     chrono::DateTime::from_timestamp_millis(ts).map_or_else(
         || "Unknown".to_string(),
         |dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
@@ -188,7 +459,7 @@ fn format_timestamp(ts: i64) -> String {
 mod tests {
     use super::*;
     use crate::core::project::{Project, ProjectTime};
-    use crate::core::session::{TextPart, UserMessage};
+    use crate::core::session::{AssistantMessage, TextPart, ToolPart, UserMessage};
     use crate::core::storage::Storage;
 
     fn temp_manager() -> (SessionManager, tempfile::TempDir) {
@@ -247,4 +518,204 @@ mod tests {
         let md = manager.export_to_markdown(&session.id).unwrap();
         assert!(md.contains(&session.title));
     }
+
+    #[test]
+    fn export_to_html_is_standalone_and_escaped() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+
+        let user = Message::User(UserMessage::new(
+            &session.id,
+            "build",
+            "anthropic",
+            "claude",
+        ));
+        manager.save_message(&session.id, &user).unwrap();
+        let question = "<script>alert(1)</script>\n\nSee [this](javascript:alert(2))";
+        let part = Part::Text(TextPart::new(user.id(), &session.id, question));
+        manager.save_part(user.id(), &part).unwrap();
+
+        let assistant = Message::Assistant(AssistantMessage::new(
+            &session.id,
+            user.id(),
+            "build",
+            "anthropic",
+            "claude",
+        ));
+        manager.save_message(&session.id, &assistant).unwrap();
+        let mut tool = ToolPart::new(
+            assistant.id(),
+            &session.id,
+            "call_1",
+            "read_file",
+            serde_json::json!({ "path": "a.html" }),
+        );
+        tool.complete("<b>bold</b>");
+        manager
+            .save_part(assistant.id(), &Part::Tool(tool))
+            .unwrap();
+        let answer = "Use this:\n\n```rust\nlet x = 1 < 2;\n```\n";
+        let part = Part::Text(TextPart::new(assistant.id(), &session.id, answer));
+        manager.save_part(assistant.id(), &part).unwrap();
+
+        let html = manager.export_to_html(&session.id).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<style>"));
+        assert!(!html.contains("<script") && !html.contains("<link"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(html.contains("<a href=\"#\">this</a>"));
+        assert!(html.contains("&lt;b&gt;bold&lt;/b&gt;"));
+        assert!(html.contains("<details class=\"tool\">"));
+        assert!(html.contains("<code class=\"language-rust\">"));
+        assert!(!html.contains("1 < 2"));
+    }
+
+    #[test]
+    fn export_all_writes_one_file_per_session() {
+        let (manager, dir) = temp_manager();
+        let first = manager.create_session().unwrap();
+        let mut second = manager.create_session().unwrap();
+        // Force a slug collision
+        second.slug.clone_from(&first.slug);
+        manager.update_session(&second).unwrap();
+
+        let out = dir.path().join("backup");
+        let written = manager
+            .export_all_to_dir(&out, ExportFormat::Markdown, &ExportFilter::default())
+            .unwrap();
+
+        assert_eq!(written.len(), 2);
+        let mut names: Vec<String> = written
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![format!("{}-2.md", first.slug), format!("{}.md", first.slug)]
+        );
+        assert!(written.iter().all(|p| p.exists()));
+    }
+
+    #[test]
+    fn import_round_trips_an_export_with_new_ids() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+        let mut user = UserMessage::new(&session.id, "build", "anthropic", "claude");
+        user.time.created = 1_700_000_000_000;
+        let msg = Message::User(user);
+        manager.save_message(&session.id, &msg).unwrap();
+        let part = Part::Text(TextPart::new(msg.id(), &session.id, "Hello world"));
+        manager.save_part(msg.id(), &part).unwrap();
+
+        let json = manager.export_to_json(&session.id).unwrap();
+        let imported = manager.import_from_json(&json).unwrap();
+
+        assert_eq!(imported.messages, 1);
+        assert_eq!(imported.parts, 1);
+        assert_ne!(imported.session.id, session.id);
+        // Slug was taken by the original session
+        assert_ne!(imported.session.slug, session.slug);
+        assert_eq!(imported.session.title, session.title);
+
+        let messages = manager.list_messages(&imported.session.id).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_ne!(messages[0].id(), msg.id());
+        assert_eq!(messages[0].time().created, 1_700_000_000_000);
+        let parts = manager.list_parts(messages[0].id()).unwrap();
+        assert!(matches!(&parts[0], Part::Text(t) if t.text == "Hello world"));
+        assert_eq!(manager.list_sessions().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn import_rejects_invalid_files() {
+        let (manager, _dir) = temp_manager();
+        let err = manager
+            .import_from_json("{\"not\": \"an export\"}")
+            .unwrap_err();
+        assert!(err.to_string().contains("not a valid session export"));
+    }
+
+    #[test]
+    fn exports_include_message_timestamps() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+
+        let mut user = UserMessage::new(&session.id, "build", "anthropic", "claude");
+        user.time.created = 1_700_000_000_000;
+        let msg = Message::User(user);
+        manager.save_message(&session.id, &msg).unwrap();
+
+        let md = manager.export_to_markdown(&session.id).unwrap();
+        assert!(md.contains("## User · 2023-11-14 22:13:20 UTC"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&manager.export_to_json(&session.id).unwrap()).unwrap();
+        assert_eq!(
+            json["messages"][0]["time"]["created"],
+            1_700_000_000_000_i64
+        );
+    }
+
+    #[test]
+    fn filters_shape_the_export_before_formatting() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+
+        let user = Message::User(UserMessage::new(
+            &session.id,
+            "build",
+            "anthropic",
+            "claude",
+        ));
+        manager.save_message(&session.id, &user).unwrap();
+        let part = Part::Text(TextPart::new(user.id(), &session.id, "question"));
+        manager.save_part(user.id(), &part).unwrap();
+
+        let assistant = Message::Assistant(AssistantMessage::new(
+            &session.id,
+            user.id(),
+            "build",
+            "anthropic",
+            "claude",
+        ));
+        manager.save_message(&session.id, &assistant).unwrap();
+        let tool = Part::Tool(ToolPart::new(
+            assistant.id(),
+            &session.id,
+            "call_1",
+            "read_file",
+            serde_json::json!({}),
+        ));
+        manager.save_part(assistant.id(), &tool).unwrap();
+        let answer = Part::Text(TextPart::new(assistant.id(), &session.id, "answer"));
+        manager.save_part(assistant.id(), &answer).unwrap();
+
+        let only_assistant = ExportFilter {
+            role: Some(ExportRole::Assistant),
+            tools: ToolFilter::Exclude,
+        };
+        let md = manager
+            .export_formatted(&session.id, ExportFormat::Markdown, &only_assistant)
+            .unwrap();
+        assert!(md.contains("answer"));
+        assert!(!md.contains("question"));
+        assert!(!md.contains("read_file"));
+
+        let only_tools = ExportFilter {
+            tools: ToolFilter::Only,
+            ..ExportFilter::default()
+        };
+        let json: serde_json::Value = serde_json::from_str(
+            &manager
+                .export_formatted(&session.id, ExportFormat::Json, &only_tools)
+                .unwrap(),
+        )
+        .unwrap();
+        // The user message has no tool parts left, so it is dropped
+        let messages = json["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["parts"].as_array().unwrap().len(), 1);
+        assert_eq!(messages[0]["parts"][0]["tool"], "read_file");
+    }
 }
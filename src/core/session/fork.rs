@@ -0,0 +1,149 @@
+//! Copying part of a session into a new one
+
+use super::{Message, Part, Session, SessionManager, new_message_id, new_part_id};
+
+impl SessionManager {
+    /// Create a session holding a copy of messages up to and including `at_message_id`
+    ///
+    /// The fork is titled "Fork of <original>" and records its parent in
+    /// `forked_from`. Copies get new IDs, so the original session is left
+    /// untouched and either can be deleted without affecting the other.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the session or message is not found, or storage fails
+    pub fn fork_session(&self, session_id: &str, at_message_id: &str) -> anyhow::Result<Session> {
+        let original = self.get_session(session_id)?;
+        let messages = self.list_messages(session_id)?;
+        let end = messages
+            .iter()
+            .position(|msg| msg.id() == at_message_id)
+            .ok_or_else(|| anyhow::anyhow!("message not found in session: {at_message_id}"))?;
+
+        let mut fork = self.create_session()?;
+        fork.title = format!("Fork of {}", original.title);
+        fork.forked_from = Some(original.id.clone());
+        fork.directory.clone_from(&original.directory);
+        fork.system_append.clone_from(&original.system_append);
+        self.update_session(&fork)?;
+
+        // Old user message ID -> copied ID, so assistant replies keep their parent
+        let mut copied_ids: Vec<(String, String)> = Vec::new();
+        for mut msg in messages.into_iter().take(end + 1) {
+            let old_id = msg.id().to_string();
+            let new_id = new_message_id();
+            match &mut msg {
+                Message::User(m) => {
+                    m.id.clone_from(&new_id);
+                    m.session_id.clone_from(&fork.id);
+                }
+                Message::Assistant(m) => {
+                    m.id.clone_from(&new_id);
+                    m.session_id.clone_from(&fork.id);
+                    if let Some((_, parent)) =
+                        copied_ids.iter().find(|(old, _)| *old == m.parent_id)
+                    {
+                        m.parent_id.clone_from(parent);
+                    }
+                }
+            }
+            self.save_message(&fork.id, &msg)?;
+
+            for mut part in self.list_parts(&old_id)? {
+                let (id, message_id, part_session_id) = match &mut part {
+                    Part::Text(p) => (&mut p.id, &mut p.message_id, &mut p.session_id),
+                    Part::Tool(p) => (&mut p.id, &mut p.message_id, &mut p.session_id),
+                    Part::Reasoning(p) => (&mut p.id, &mut p.message_id, &mut p.session_id),
+                };
+                *id = new_part_id();
+                message_id.clone_from(&new_id);
+                part_session_id.clone_from(&fork.id);
+                self.save_part(&new_id, &part)?;
+            }
+            copied_ids.push((old_id, new_id));
+        }
+
+        Ok(fork)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::core::project::{Project, ProjectTime};
+    use crate::core::session::{AssistantMessage, TextPart, UserMessage};
+    use crate::core::storage::Storage;
+
+    fn temp_manager() -> (SessionManager, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let project = Project {
+            id: "test-project".to_string(),
+            worktree: PathBuf::from(dir.path()),
+            vcs: None,
+            time: ProjectTime {
+                created: 0,
+                initialized: 0,
+            },
+        };
+        let storage = Storage::with_root(dir.path().to_path_buf());
+        (SessionManager::new(storage, project), dir)
+    }
+
+    /// Save a user prompt and an assistant reply, returning their IDs
+    fn save_turn(manager: &SessionManager, session_id: &str, prompt: &str) -> (String, String) {
+        let user = Message::User(UserMessage::new(session_id, "build", "anthropic", "claude"));
+        manager.save_message(session_id, &user).unwrap();
+        let part = Part::Text(TextPart::new(user.id(), session_id, prompt));
+        manager.save_part(user.id(), &part).unwrap();
+
+        let reply = Message::Assistant(AssistantMessage::new(
+            session_id,
+            user.id(),
+            "build",
+            "anthropic",
+            "claude",
+        ));
+        manager.save_message(session_id, &reply).unwrap();
+        (user.id().to_string(), reply.id().to_string())
+    }
+
+    #[test]
+    fn fork_copies_messages_up_to_the_given_one() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+        let (_, first_reply) = save_turn(&manager, &session.id, "first");
+        save_turn(&manager, &session.id, "second");
+
+        let fork = manager.fork_session(&session.id, &first_reply).unwrap();
+        assert_ne!(fork.id, session.id);
+        assert_eq!(fork.title, format!("Fork of {}", session.title));
+        assert_eq!(fork.forked_from.as_deref(), Some(session.id.as_str()));
+
+        let copied = manager.list_messages(&fork.id).unwrap();
+        assert_eq!(copied.len(), 2);
+        assert!(copied.iter().all(|msg| msg.session_id() == fork.id));
+        let Message::Assistant(reply) = &copied[1] else {
+            panic!("expected assistant reply");
+        };
+        assert_eq!(reply.parent_id, copied[0].id());
+
+        let parts = manager.list_parts(copied[0].id()).unwrap();
+        let Part::Text(text) = &parts[0] else {
+            panic!("expected text part");
+        };
+        assert_eq!(text.text, "first");
+        assert_eq!(text.session_id, fork.id);
+
+        // The original is untouched
+        assert_eq!(manager.list_messages(&session.id).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn fork_rejects_unknown_message() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+        assert!(manager.fork_session(&session.id, "missing").is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Standalone HTML rendering for session exports
+//!
+//! Pages carry their own CSS and load nothing from the network. Message
+//! text is rendered as markdown, with any raw HTML shown as text.
+
+use std::fmt::Write;
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+
+use super::export::{ExportedSession, format_timestamp};
+use super::{Message, Part, ToolPart, ToolState};
+
+const STYLE: &str = "
+:root { color-scheme: light; }
+body { margin: 0; background: #f6f7f9; color: #1f2328;
+  font: 15px/1.55 -apple-system, BlinkMacSystemFont, 'Segoe UI', Helvetica, Arial, sans-serif; }
+main { max-width: 860px; margin: 0 auto; padding: 32px 20px 64px; }
+header { border-bottom: 1px solid #d8dee4; margin-bottom: 24px; padding-bottom: 12px; }
+header h1 { font-size: 22px; margin: 0 0 8px; }
+header dl { display: grid; grid-template-columns: max-content 1fr; gap: 2px 12px;
+  margin: 0; color: #59636e; font-size: 13px; }
+header dd { margin: 0; font-family: ui-monospace, SFMono-Regular, Menlo, monospace; }
+.message { display: flex; flex-direction: column; margin: 18px 0; }
+.message.user { align-items: flex-end; }
+.meta { color: #59636e; font-size: 12px; margin: 0 6px 4px; }
+.bubble { max-width: 85%; padding: 10px 14px; border-radius: 14px; overflow-wrap: anywhere;
+  background: #ffffff; border: 1px solid #d8dee4; }
+.user .bubble { background: #ddf4ff; border-color: #b6e3ff; }
+.bubble > :first-child { margin-top: 0; }
+.bubble > :last-child { margin-bottom: 0; }
+pre { background: #2b303b; color: #c0c5ce; padding: 10px 12px; border-radius: 8px;
+  overflow-x: auto; font-size: 13px; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, monospace; }
+:not(pre) > code { background: #eff1f3; padding: 1px 5px; border-radius: 5px; font-size: 13px; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #d8dee4; padding: 4px 8px; }
+details { max-width: 85%; width: 100%; margin: 6px 0; border: 1px solid #d8dee4;
+  border-radius: 10px; background: #ffffff; }
+details > summary { cursor: pointer; padding: 6px 12px; color: #59636e; font-size: 13px; }
+details > :not(summary) { margin: 0 12px 10px; }
+details.error { border-color: #ffcecb; }
+details.error .status { color: #d1242f; }
+.label { font-size: 12px; color: #59636e; margin-bottom: 4px; }
+.plain { white-space: pre-wrap; }
+";
+
+/// Format exported session as a self-contained HTML page
+pub(super) fn format_as_html(exported: &ExportedSession) -> String {
+    let mut html = String::new();
+    let session = &exported.session;
+    let title = escape(&session.title);
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<main>\n"
+    );
+    let _ = writeln!(
+        html,
+        "<header>\n<h1>{title}</h1>\n<dl>\n\
+         <dt>Session</dt><dd>{}</dd>\n<dt>Created</dt><dd>{}</dd>\n<dt>Directory</dt><dd>{}</dd>\n\
+         </dl>\n</header>",
+        escape(&session.id),
+        format_timestamp(session.time.created),
+        escape(&session.directory.display().to_string()),
+    );
+
+    for exported_msg in &exported.messages {
+        let (class, role) = match &exported_msg.message {
+            Message::User(_) => ("user", "User"),
+            Message::Assistant(_) => ("assistant", "Assistant"),
+        };
+        let _ = writeln!(
+            html,
+            "<section class=\"message {class}\">\n<div class=\"meta\">{role} · {}</div>",
+            format_timestamp(exported_msg.message.time().created)
+        );
+
+        for part in &exported_msg.parts {
+            match part {
+                Part::Text(t) => {
+                    html.push_str("<div class=\"bubble\">\n");
+                    push_markdown(&mut html, &t.text);
+                    html.push_str("</div>\n");
+                }
+                Part::Tool(t) => push_tool(&mut html, t),
+                Part::Reasoning(r) => {
+                    let _ = writeln!(
+                        html,
+                        "<details class=\"reasoning\">\n<summary>Reasoning</summary>\n\
+                         <div class=\"plain\">{}</div>\n</details>",
+                        escape(&r.text)
+                    );
+                }
+            }
+        }
+
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</main>\n</body>\n</html>\n");
+    html
+}
+
+/// Render a tool call as a collapsed block with its input and output
+fn push_tool(html: &mut String, tool: &ToolPart) {
+    let (input, output, is_error) = match &tool.state {
+        ToolState::Pending { input, .. } | ToolState::Running { input, .. } => (input, None, false),
+        ToolState::Completed { input, .. } => (input, tool.output(), false),
+        ToolState::Error { input, error, .. } => (input, Some(error.as_str()), true),
+    };
+    let class = if is_error { "tool error" } else { "tool" };
+
+    let _ = writeln!(
+        html,
+        "<details class=\"{class}\">\n<summary><code>{}</code> <span class=\"status\">{}</span></summary>",
+        escape(&tool.tool),
+        tool.state.as_str()
+    );
+    let input = serde_json::to_string_pretty(input).unwrap_or_default();
+    let _ = writeln!(
+        html,
+        "<div class=\"label\">Input</div>\n<pre><code>{}</code></pre>",
+        escape(&input)
+    );
+    if let Some(output) = output.filter(|output| !output.is_empty()) {
+        let _ = writeln!(
+            html,
+            "<div class=\"label\">Output</div>\n<pre><code>{}</code></pre>",
+            escape(output)
+        );
+    }
+    html.push_str("</details>\n");
+}
+
+/// Render markdown, highlighting fenced code and escaping raw HTML
+fn push_markdown(html: &mut String, text: &str) {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    // Language and source of the code block being collected
+    let mut code: Option<(String, String)> = None;
+
+    let events = Parser::new_ext(text, options).filter_map(|event| match event {
+        Event::Start(Tag::CodeBlock(kind)) => {
+            let language = match kind {
+                CodeBlockKind::Fenced(info) => info
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                CodeBlockKind::Indented => String::new(),
+            };
+            code = Some((language, String::new()));
+            None
+        }
+        Event::End(TagEnd::CodeBlock) => {
+            let (language, source) = code.take()?;
+            Some(Event::Html(code_block(&source, &language).into()))
+        }
+        Event::Text(t) if code.is_some() => {
+            if let Some((_, source)) = code.as_mut() {
+                source.push_str(&t);
+            }
+            None
+        }
+        // Raw HTML in a message is shown, never interpreted
+        Event::Html(raw) | Event::InlineHtml(raw) => Some(Event::Text(raw)),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Some(Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        })),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Some(Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        })),
+        other => Some(other),
+    });
+
+    pulldown_cmark::html::push_html(html, events);
+}
+
+/// Replace URLs that would run script when followed
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    // Browsers ignore whitespace and control characters inside the scheme
+    let scheme: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .take(16)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if ["javascript:", "vbscript:", "data:"]
+        .iter()
+        .any(|prefix| scheme.starts_with(prefix))
+    {
+        CowStr::Borrowed("#")
+    } else {
+        url
+    }
+}
+
+/// Render a code block, highlighted when the language is known
+fn code_block(source: &str, language: &str) -> String {
+    let body = highlight(source, language).unwrap_or_else(|| escape(source));
+    if language.is_empty() {
+        format!("<pre><code>{body}</code></pre>\n")
+    } else {
+        format!(
+            "<pre><code class=\"language-{}\">{body}</code></pre>\n",
+            escape(language)
+        )
+    }
+}
+
+/// Highlight `source` as `language`, with colors inlined on each span
+#[cfg(feature = "syntax-highlight")]
+fn highlight(source: &str, language: &str) -> Option<String> {
+    use std::sync::LazyLock;
+
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::{Theme, ThemeSet};
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::LinesWithEndings;
+
+    static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+    static THEME: LazyLock<Theme> = LazyLock::new(|| {
+        ThemeSet::load_defaults()
+            .themes
+            .remove("base16-ocean.dark")
+            .unwrap_or_default()
+    });
+
+    if language.is_empty() {
+        return None;
+    }
+    let syntax = SYNTAXES
+        .find_syntax_by_token(language)
+        .filter(|syntax| syntax.name != "Plain Text")?;
+    let mut highlighter = HighlightLines::new(syntax, &THEME);
+
+    let mut html = String::new();
+    for line in LinesWithEndings::from(source) {
+        for (style, text) in highlighter.highlight_line(line, &SYNTAXES).ok()? {
+            let fg = style.foreground;
+            let _ = write!(
+                html,
+                "<span style=\"color:#{:02x}{:02x}{:02x}\">{}</span>",
+                fg.r,
+                fg.g,
+                fg.b,
+                escape(text)
+            );
+        }
+    }
+    Some(html)
+}
+
+/// Highlight `source` as `language`.
+///
+/// Always `None`: highlighting support was not compiled in.
+#[cfg(not(feature = "syntax-highlight"))]
+const fn highlight(_source: &str, _language: &str) -> Option<String> {
+    None
+}
+
+/// Escape text for an HTML element or attribute
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use super::new_message_id;
 
 /// Message in a conversation.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(tag = "role", rename_all = "lowercase")]
 pub enum Message {
     /// User message.
@@ -33,6 +33,15 @@ impl Message {
         }
     }
 
+    /// Get the message timestamps.
+    #[must_use]
+    pub const fn time(&self) -> &MessageTime {
+        match self {
+            Self::User(m) => &m.time,
+            Self::Assistant(m) => &m.time,
+        }
+    }
+
     /// Check if this is a user message.
     #[must_use]
     pub const fn is_user(&self) -> bool {
@@ -56,7 +65,7 @@ impl Message {
 }
 
 /// User message.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct UserMessage {
     /// Unique message identifier.
     pub id: String,
@@ -100,7 +109,7 @@ impl UserMessage {
 }
 
 /// Assistant message.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct AssistantMessage {
     /// Unique message identifier.
     pub id: String,
@@ -187,7 +196,7 @@ impl AssistantMessage {
 }
 
 /// Model reference.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ModelRef {
     /// Provider ID.
     pub provider_id: String,
@@ -196,7 +205,7 @@ pub struct ModelRef {
 }
 
 /// Message timestamps.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct MessageTime {
     /// When the message was created.
     pub created: i64,
@@ -205,7 +214,7 @@ pub struct MessageTime {
 }
 
 /// Token usage statistics.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct TokenUsage {
     /// Input tokens.
     pub input: u32,
@@ -237,14 +246,14 @@ impl TokenUsage {
 }
 
 /// Message summary (auto-generated).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct MessageSummary {
     /// Auto-generated title.
     pub title: Option<String>,
 }
 
 /// Message error.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct MessageError {
     /// Error type.
     pub error_type: String,
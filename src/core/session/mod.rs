@@ -5,26 +5,34 @@
 
 mod compaction;
 mod export;
+mod fork;
+mod html;
 mod message;
 mod part;
 mod share;
 mod titling;
+mod undo;
 
 use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
 
 use serde::{Deserialize, Serialize};
-use ulid::Ulid;
+use ulid::{Generator, Ulid};
 
 pub use compaction::{
     CompactionResult, DEFAULT_COMPACTION_THRESHOLD, MIN_MESSAGES_TO_KEEP, compaction_prompt,
 };
-pub use export::{ExportFormat, ExportedMessage, ExportedSession};
+pub use export::{
+    ExportFilter, ExportFormat, ExportRole, ExportedMessage, ExportedSession, ImportedSession,
+    ToolFilter,
+};
 pub use message::{
     AssistantMessage, Message, MessageSummary, MessageTime, TokenUsage, UserMessage,
 };
 pub use part::{Part, PartTime, ReasoningPart, TextPart, ToolPart, ToolState};
-pub use share::{ShareOptions, ShareToken};
+pub use share::{ShareExpiredError, ShareOptions, SharePasswordError, ShareSummary, ShareToken};
 pub use titling::{MAX_TITLE_LENGTH, extract_title, titling_prompt};
+pub use undo::TurnChanges;
 
 use super::project::Project;
 
@@ -53,22 +61,35 @@ impl SessionTarget {
 }
 use super::storage::Storage;
 
+/// Generate a ULID that sorts after every ID generated before it.
+///
+/// IDs created within the same millisecond would otherwise order randomly,
+/// shuffling messages and parts when they're listed.
+fn monotonic_ulid() -> Ulid {
+    static GENERATOR: Mutex<Generator> = Mutex::new(Generator::new());
+    GENERATOR
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .generate()
+        .unwrap_or_else(|_| Ulid::new())
+}
+
 /// Generate a new session ID.
 #[must_use]
 pub fn new_session_id() -> String {
-    format!("ses_{}", Ulid::new())
+    format!("ses_{}", monotonic_ulid())
 }
 
 /// Generate a new message ID.
 #[must_use]
 pub fn new_message_id() -> String {
-    format!("msg_{}", Ulid::new())
+    format!("msg_{}", monotonic_ulid())
 }
 
 /// Generate a new part ID.
 #[must_use]
 pub fn new_part_id() -> String {
-    format!("prt_{}", Ulid::new())
+    format!("prt_{}", monotonic_ulid())
 }
 
 /// Generate a human-readable slug.
@@ -91,7 +112,7 @@ pub fn new_slug() -> String {
 }
 
 /// Session metadata.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct Session {
     /// Unique session identifier.
     pub id: String,
@@ -106,6 +127,7 @@ pub struct Session {
     pub title: String,
 
     /// Working directory for this session.
+    #[schema(value_type = String)]
     pub directory: PathBuf,
 
     /// Timestamps.
@@ -113,10 +135,18 @@ pub struct Session {
 
     /// File change summary.
     pub summary: Option<SessionSummary>,
+
+    /// Extra instructions appended to the system prompt, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub system_append: Vec<String>,
+
+    /// Session this one was forked from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<String>,
 }
 
 /// Session timestamps.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct SessionTime {
     /// When the session was created.
     pub created: i64,
@@ -128,8 +158,35 @@ pub struct SessionTime {
     pub compacted: Option<i64>,
 }
 
+/// How recently a session was updated, for highlighting in lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recency {
+    /// Updated within the last 24 hours.
+    Today,
+    /// Updated within the last 7 days.
+    ThisWeek,
+    /// Updated more than 7 days ago.
+    Older,
+}
+
+impl SessionTime {
+    /// Classify the last update relative to `now` (epoch millis).
+    #[must_use]
+    pub const fn recency(&self, now: i64) -> Recency {
+        const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+        let age = now - self.updated;
+        if age < DAY_MS {
+            Recency::Today
+        } else if age < 7 * DAY_MS {
+            Recency::ThisWeek
+        } else {
+            Recency::Older
+        }
+    }
+}
+
 /// Session file change summary.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct SessionSummary {
     /// Lines added.
     pub additions: u32,
@@ -160,6 +217,8 @@ impl Session {
                 compacted: None,
             },
             summary: None,
+            system_append: Vec::new(),
+            forked_from: None,
         }
     }
 
@@ -173,6 +232,7 @@ impl Session {
 /// Session manager for CRUD operations.
 pub struct SessionManager {
     storage: Storage,
+    share_storage: Option<Storage>,
     project: Project,
 }
 
@@ -180,18 +240,40 @@ impl SessionManager {
     /// Create a new session manager.
     #[must_use]
     pub const fn new(storage: Storage, project: Project) -> Self {
-        Self { storage, project }
+        Self {
+            storage,
+            share_storage: None,
+            project,
+        }
+    }
+
+    /// Keep share tokens in separate storage from sessions.
+    #[must_use]
+    pub fn with_share_storage(mut self, storage: Storage) -> Self {
+        self.share_storage = Some(storage);
+        self
     }
 
     /// Create a session manager for the current project
     ///
+    /// Sessions live in `[storage] session_dir` when configured, otherwise
+    /// in the global data directory.
+    ///
     /// # Errors
     ///
-    /// Returns error if project detection or storage initialization fails
+    /// Returns error if project detection, config loading or storage
+    /// initialization fails. A broken config isn't ignored, since falling
+    /// back to the default directory would hide sessions kept elsewhere.
     pub fn for_current_project() -> anyhow::Result<Self> {
-        let storage = Storage::new()?;
         let project = Project::detect(&std::env::current_dir()?)?;
-        Ok(Self::new(storage, project))
+        let config = crate::config::Config::load()?;
+
+        match config.storage.session_root(&project.worktree) {
+            Some(root) => Ok(
+                Self::new(Storage::with_root(root), project).with_share_storage(Storage::new()?)
+            ),
+            None => Ok(Self::new(Storage::new()?, project)),
+        }
     }
 
     /// Get the storage reference.
@@ -200,6 +282,14 @@ impl SessionManager {
         &self.storage
     }
 
+    /// Storage holding share tokens.
+    const fn share_storage(&self) -> &Storage {
+        match &self.share_storage {
+            Some(storage) => storage,
+            None => &self.storage,
+        }
+    }
+
     /// Get the project reference.
     #[must_use]
     pub const fn project(&self) -> &Project {
@@ -289,6 +379,7 @@ impl SessionManager {
         for msg in messages {
             self.delete_message(session_id, msg.id())?;
         }
+        self.clear_turn_changes(session_id)?;
 
         // Delete session
         self.storage
@@ -323,6 +414,46 @@ impl SessionManager {
         Ok(sessions)
     }
 
+    /// Find sessions whose messages mention `query`, ignoring case.
+    ///
+    /// Searches text, reasoning and tool output. Returns sessions sorted by
+    /// update time (newest first).
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage read fails.
+    pub fn search_sessions(&self, query: &str) -> anyhow::Result<Vec<Session>> {
+        let query = query.to_lowercase();
+        let mut matches = Vec::new();
+
+        for session in self.list_sessions()? {
+            if self.session_mentions(&session.id, &query)? {
+                matches.push(session);
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Whether any part in a session contains the lowercase `query`.
+    ///
+    /// Stops reading parts at the first match.
+    fn session_mentions(&self, session_id: &str, query: &str) -> anyhow::Result<bool> {
+        for message in self.list_messages(session_id)? {
+            for part in self.list_parts(message.id())? {
+                let text = match &part {
+                    Part::Text(p) => Some(p.text.as_str()),
+                    Part::Reasoning(p) => Some(p.text.as_str()),
+                    Part::Tool(p) => p.output(),
+                };
+                if text.is_some_and(|text| text.to_lowercase().contains(query)) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
     /// Get the most recent session, or create one if none exist.
     ///
     /// # Errors
@@ -490,6 +621,20 @@ mod tests {
         (SessionManager::new(storage, project), dir)
     }
 
+    #[test]
+    fn recency_buckets_by_update_age() {
+        let hour = 60 * 60 * 1000;
+        let now = 1_000 * 24 * hour;
+        let time = |updated| SessionTime {
+            created: 0,
+            updated,
+            compacted: None,
+        };
+        assert_eq!(time(now - hour).recency(now), Recency::Today);
+        assert_eq!(time(now - 3 * 24 * hour).recency(now), Recency::ThisWeek);
+        assert_eq!(time(now - 8 * 24 * hour).recency(now), Recency::Older);
+    }
+
     #[test]
     fn create_and_list_sessions() {
         let (manager, _dir) = temp_manager();
@@ -542,4 +687,44 @@ mod tests {
 
         assert_eq!(session1.id, session2.id);
     }
+
+    #[test]
+    fn system_append_persists_on_session() {
+        let (manager, _dir) = temp_manager();
+
+        let mut session = manager.create_session().unwrap();
+        assert!(session.system_append.is_empty());
+
+        session.system_append = vec!["be terse".to_string(), "use tables".to_string()];
+        manager.update_session(&session).unwrap();
+
+        let loaded = manager.get_session(&session.id).unwrap();
+        assert_eq!(loaded.system_append, vec!["be terse", "use tables"]);
+    }
+
+    #[test]
+    fn search_matches_message_text_case_insensitively() {
+        let (manager, _dir) = temp_manager();
+
+        let add_text = |text: &str| {
+            let session = manager.create_session().unwrap();
+            let msg = Message::User(UserMessage::new(
+                &session.id,
+                "build",
+                "anthropic",
+                "claude",
+            ));
+            manager.save_message(&session.id, &msg).unwrap();
+            let part = Part::Text(TextPart::new(msg.id(), &session.id, text));
+            manager.save_part(msg.id(), &part).unwrap();
+            session
+        };
+        let auth = add_text("How should WebSocket Auth work?");
+        add_text("Fix the parser");
+
+        let found = manager.search_sessions("websocket auth").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, auth.id);
+        assert!(manager.search_sessions("nothing here").unwrap().is_empty());
+    }
 }
@@ -6,7 +6,7 @@ use serde_json::Value;
 use super::new_part_id;
 
 /// Part of a message.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum Part {
     /// Text content.
@@ -50,7 +50,7 @@ impl Part {
 }
 
 /// Text part.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct TextPart {
     /// Unique part identifier.
     pub id: String,
@@ -100,7 +100,7 @@ impl TextPart {
 }
 
 /// Tool part.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ToolPart {
     /// Unique part identifier.
     pub id: String,
@@ -244,7 +244,7 @@ impl ToolPart {
 }
 
 /// Tool execution state.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(tag = "status", rename_all = "lowercase")]
 pub enum ToolState {
     /// Tool is waiting to be executed.
@@ -297,7 +297,7 @@ impl ToolState {
 }
 
 /// Reasoning part (extended thinking).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ReasoningPart {
     /// Unique part identifier.
     pub id: String,
@@ -340,7 +340,7 @@ impl ReasoningPart {
 }
 
 /// Part timestamps.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct PartTime {
     /// When the part started.
     pub start: i64,
@@ -2,10 +2,29 @@
 //!
 //! Generate short tokens to share sessions via URLs
 
+use std::num::NonZeroU32;
+
+use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
+use ring::pbkdf2;
 use serde::{Deserialize, Serialize};
 
 use super::{ExportedSession, SessionManager};
 
+/// PHC identifier of the share password hash
+const PASSWORD_SCHEME: &str = "pbkdf2-sha256";
+
+/// PBKDF2 variant for share passwords
+static PASSWORD_ALGORITHM: pbkdf2::Algorithm = pbkdf2::PBKDF2_HMAC_SHA256;
+
+/// PBKDF2 rounds for share passwords (OWASP's recommendation for SHA-256)
+const PASSWORD_ITERATIONS: NonZeroU32 = NonZeroU32::new(600_000).unwrap();
+
+/// Bytes of random salt per password
+const SALT_LEN: usize = 16;
+
+/// Bytes of derived hash per password
+const HASH_LEN: usize = 32;
+
 /// Share token info
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShareToken {
@@ -23,6 +42,26 @@ pub struct ShareToken {
     pub expires_at: Option<i64>,
     /// Number of times accessed
     pub access_count: u32,
+    /// Salted hash of the password needed to read the share, None = public
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_hash: Option<String>,
+}
+
+/// Share details safe to display, without the secret or password hash
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareSummary {
+    /// Short token for URLs
+    pub token: String,
+    /// Session ID this token refers to
+    pub session_id: String,
+    /// Creation timestamp (millis)
+    pub created_at: i64,
+    /// Expiration timestamp (millis), None = never
+    pub expires_at: Option<i64>,
+    /// Number of times accessed
+    pub access_count: u32,
+    /// Whether reading the share needs a password
+    pub protected: bool,
 }
 
 /// Share creation options
@@ -30,8 +69,26 @@ pub struct ShareToken {
 pub struct ShareOptions {
     /// Time-to-live in seconds (None = never expires)
     pub ttl_seconds: Option<u64>,
+    /// Password required to read the share (None = anyone with the token)
+    pub password: Option<String>,
+}
+
+/// Why a password-protected share refused access
+#[derive(Debug, thiserror::Error)]
+pub enum SharePasswordError {
+    /// No password was given
+    #[error("share is password protected")]
+    Required,
+    /// The password didn't match
+    #[error("incorrect share password")]
+    Incorrect,
 }
 
+/// A share was read after its expiry
+#[derive(Debug, thiserror::Error)]
+#[error("share token has expired")]
+pub struct ShareExpiredError;
+
 impl ShareToken {
     /// Check if the token has expired
     #[must_use]
@@ -43,6 +100,41 @@ impl ShareToken {
             false
         }
     }
+
+    /// Redacted view of the share for listings
+    #[must_use]
+    pub fn summary(&self) -> ShareSummary {
+        ShareSummary {
+            token: self.token.clone(),
+            session_id: self.session_id.clone(),
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            access_count: self.access_count,
+            protected: self.is_protected(),
+        }
+    }
+
+    /// Check if reading the share needs a password
+    #[must_use]
+    pub const fn is_protected(&self) -> bool {
+        self.password_hash.is_some()
+    }
+
+    /// Check `password` against the share's password, if it has one
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the share is protected and `password` is missing or wrong
+    pub fn check_password(&self, password: Option<&str>) -> Result<(), SharePasswordError> {
+        let Some(hash) = &self.password_hash else {
+            return Ok(());
+        };
+        match password {
+            None => Err(SharePasswordError::Required),
+            Some(password) if verify_password(password, hash) => Ok(()),
+            Some(_) => Err(SharePasswordError::Incorrect),
+        }
+    }
 }
 
 /// Generate a short share token (8 chars, URL-safe)
@@ -63,6 +155,61 @@ fn generate_secret() -> String {
     uuid::Uuid::new_v4().to_string()
 }
 
+/// Hash a password as a PHC string, `$pbkdf2-sha256$i=<iterations>$<salt>$<hash>`
+fn hash_password(password: &str) -> String {
+    let salt = rand::random::<[u8; SALT_LEN]>();
+    let mut hash = [0_u8; HASH_LEN];
+    pbkdf2::derive(
+        PASSWORD_ALGORITHM,
+        PASSWORD_ITERATIONS,
+        &salt,
+        password.as_bytes(),
+        &mut hash,
+    );
+    format!(
+        "${PASSWORD_SCHEME}$i={PASSWORD_ITERATIONS}${}${}",
+        STANDARD_NO_PAD.encode(salt),
+        STANDARD_NO_PAD.encode(hash)
+    )
+}
+
+/// Check a password against a hash from [`hash_password`]
+fn verify_password(password: &str, stored: &str) -> bool {
+    let mut fields = stored.split('$');
+    let (Some(""), Some(PASSWORD_SCHEME), Some(params), Some(salt), Some(expected), None) = (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    ) else {
+        return false;
+    };
+    let Some(iterations) = params
+        .strip_prefix("i=")
+        .and_then(|i| i.parse::<NonZeroU32>().ok())
+    else {
+        return false;
+    };
+    let (Ok(salt), Ok(expected)) = (
+        STANDARD_NO_PAD.decode(salt),
+        STANDARD_NO_PAD.decode(expected),
+    ) else {
+        return false;
+    };
+
+    // Constant-time comparison is done by ring
+    pbkdf2::verify(
+        PASSWORD_ALGORITHM,
+        iterations,
+        &salt,
+        password.as_bytes(),
+        &expected,
+    )
+    .is_ok()
+}
+
 impl SessionManager {
     /// Create a share token for a session
     ///
@@ -88,13 +235,15 @@ impl SessionManager {
             created_at: now,
             expires_at,
             access_count: 0,
+            password_hash: options.password.as_deref().map(hash_password),
         };
 
         // Store the token
-        self.storage.write(&["share", &token.token], &token)?;
+        self.share_storage()
+            .write(&["share", &token.token], &token)?;
 
         // Also store reverse mapping (session -> token) for lookup
-        self.storage
+        self.share_storage()
             .write(&["session_share", session_id], &token.token)?;
 
         Ok(token)
@@ -106,12 +255,12 @@ impl SessionManager {
     ///
     /// Returns error if token doesn't exist
     pub fn get_share(&self, token: &str) -> anyhow::Result<ShareToken> {
-        Ok(self.storage.read(&["share", token])?)
+        Ok(self.share_storage().read(&["share", token])?)
     }
 
     /// Get a share token for a session (if one exists)
     pub fn get_share_for_session(&self, session_id: &str) -> anyhow::Result<Option<ShareToken>> {
-        let token: Result<String, _> = self.storage.read(&["session_share", session_id]);
+        let token: Result<String, _> = self.share_storage().read(&["session_share", session_id]);
         match token {
             Ok(t) => {
                 let share = self.get_share(&t)?;
@@ -129,19 +278,27 @@ impl SessionManager {
 
     /// Get shared session data (for public access)
     ///
+    /// Protected shares need their `password`; other shares ignore it.
+    ///
     /// # Errors
     ///
-    /// Returns error if token is invalid or expired
-    pub fn get_shared_session(&self, token: &str) -> anyhow::Result<ExportedSession> {
+    /// Returns error if token is invalid, a [`ShareExpiredError`] if it has
+    /// expired, or a [`SharePasswordError`] if the password is missing or wrong
+    pub fn get_shared_session(
+        &self,
+        token: &str,
+        password: Option<&str>,
+    ) -> anyhow::Result<ExportedSession> {
         let mut share = self.get_share(token)?;
 
         if share.is_expired() {
-            anyhow::bail!("share token has expired");
+            return Err(ShareExpiredError.into());
         }
+        share.check_password(password)?;
 
         // Increment access count
         share.access_count += 1;
-        self.storage.write(&["share", token], &share)?;
+        self.share_storage().write(&["share", token], &share)?;
 
         // Export the session
         self.export_session(&share.session_id)
@@ -160,24 +317,56 @@ impl SessionManager {
         }
 
         // Remove token
-        self.storage.remove(&["share", token])?;
+        self.share_storage().remove(&["share", token])?;
 
         // Remove reverse mapping
-        self.storage.remove(&["session_share", &share.session_id])?;
+        self.share_storage()
+            .remove(&["session_share", &share.session_id])?;
 
         Ok(())
     }
 
-    /// List all active shares
+    /// List active shares for the current project, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage read fails
     pub fn list_shares(&self) -> anyhow::Result<Vec<ShareToken>> {
-        let tokens: Vec<ShareToken> = self
-            .storage
+        let mut tokens: Vec<ShareToken> = self
+            .share_storage()
             .list_prefix::<ShareToken>("share")?
             .into_iter()
-            .filter(|t| !t.is_expired())
+            .filter(|t| t.project_id == self.project().id && !t.is_expired())
             .collect();
+        tokens.sort_by_key(|t| std::cmp::Reverse(t.created_at));
         Ok(tokens)
     }
+
+    /// Remove expired shares from storage
+    ///
+    /// Returns the number of shares removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage read or removal fails
+    pub fn cleanup_expired_shares(&self) -> anyhow::Result<usize> {
+        let storage = self.share_storage();
+        let expired: Vec<ShareToken> = storage
+            .list_prefix::<ShareToken>("share")?
+            .into_iter()
+            .filter(ShareToken::is_expired)
+            .collect();
+
+        for share in &expired {
+            storage.remove(&["share", &share.token])?;
+            // The session may have been shared again since
+            let current: Option<String> = storage.read(&["session_share", &share.session_id]).ok();
+            if current.as_deref() == Some(share.token.as_str()) {
+                storage.remove(&["session_share", &share.session_id])?;
+            }
+        }
+        Ok(expired.len())
+    }
 }
 
 #[cfg(test)]
@@ -239,11 +428,145 @@ mod tests {
 
         assert_eq!(share.access_count, 0);
 
-        let _ = manager.get_shared_session(&share.token).unwrap();
+        let _ = manager.get_shared_session(&share.token, None).unwrap();
         let updated = manager.get_share(&share.token).unwrap();
         assert_eq!(updated.access_count, 1);
     }
 
+    #[test]
+    fn protected_share_requires_the_password() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+
+        let options = ShareOptions {
+            password: Some("hunter2".to_string()),
+            ..ShareOptions::default()
+        };
+        let share = manager.create_share(&session.id, options).unwrap();
+        let hash = share.password_hash.as_deref().unwrap();
+        assert!(!hash.contains("hunter2"));
+
+        let denied = |password| {
+            manager
+                .get_shared_session(&share.token, password)
+                .unwrap_err()
+                .downcast::<SharePasswordError>()
+                .unwrap()
+        };
+        assert!(matches!(denied(None), SharePasswordError::Required));
+        assert!(matches!(
+            denied(Some("wrong")),
+            SharePasswordError::Incorrect
+        ));
+
+        let exported = manager
+            .get_shared_session(&share.token, Some("hunter2"))
+            .unwrap();
+        assert_eq!(exported.session.id, session.id);
+        // Refused reads aren't counted
+        assert_eq!(manager.get_share(&share.token).unwrap().access_count, 1);
+    }
+
+    #[test]
+    fn password_hashes_are_salted_phc_strings() {
+        let hash = hash_password("pw");
+        assert!(hash.starts_with("$pbkdf2-sha256$i=600000$"));
+        assert_ne!(hash, hash_password("pw"));
+        assert!(verify_password("pw", &hash));
+        assert!(!verify_password("pW", &hash));
+        assert!(!verify_password("pw", "plaintext"));
+        // The RFC 7914 test vector
+        assert!(verify_password(
+            "passwd",
+            "$pbkdf2-sha256$i=1$c2FsdA$VawEblbjCJ/sFpHCJUS2BflBhSFt3gRl5oudV8INrLw"
+        ));
+    }
+
+    /// Move a share's expiry into the past
+    fn expire(manager: &SessionManager, share: &ShareToken) {
+        let mut share = share.clone();
+        share.expires_at = Some(chrono::Utc::now().timestamp_millis() - 1000);
+        manager
+            .share_storage()
+            .write(&["share", &share.token], &share)
+            .unwrap();
+    }
+
+    #[test]
+    fn expired_share_is_refused_and_cleaned_up() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+        let stale = manager
+            .create_share(&session.id, ShareOptions::default())
+            .unwrap();
+        let live = manager
+            .create_share(&session.id, ShareOptions::default())
+            .unwrap();
+        expire(&manager, &stale);
+
+        let err = manager.get_shared_session(&stale.token, None).unwrap_err();
+        assert!(err.downcast_ref::<ShareExpiredError>().is_some());
+        assert_eq!(manager.list_shares().unwrap().len(), 1);
+
+        assert_eq!(manager.cleanup_expired_shares().unwrap(), 1);
+        assert!(manager.get_share(&stale.token).is_err());
+        // The session's newer share is untouched
+        let current = manager.get_share_for_session(&session.id).unwrap().unwrap();
+        assert_eq!(current.token, live.token);
+    }
+
+    #[test]
+    fn list_sessions_keeps_expired_shares() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+        let share = manager
+            .create_share(&session.id, ShareOptions::default())
+            .unwrap();
+        expire(&manager, &share);
+
+        manager.list_sessions().unwrap();
+        // Readers still get told the share expired
+        let err = manager.get_shared_session(&share.token, None).unwrap_err();
+        assert!(err.downcast_ref::<ShareExpiredError>().is_some());
+    }
+
+    #[test]
+    fn summary_leaves_out_the_secret_and_password_hash() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+        let options = ShareOptions {
+            password: Some("hunter2".to_string()),
+            ..ShareOptions::default()
+        };
+        let share = manager.create_share(&session.id, options).unwrap();
+
+        let json = serde_json::to_string(&share.summary()).unwrap();
+        assert!(!json.contains(&share.secret));
+        assert!(!json.contains("pbkdf2"));
+        assert!(json.contains("\"protected\":true"));
+    }
+
+    #[test]
+    fn list_shares_only_covers_the_current_project() {
+        let (manager, _dir) = temp_manager();
+        let session = manager.create_session().unwrap();
+        let share = manager
+            .create_share(&session.id, ShareOptions::default())
+            .unwrap();
+
+        let mut other = share.clone();
+        other.token = "otherprj".to_string();
+        other.project_id = "other-project".to_string();
+        manager
+            .share_storage()
+            .write(&["share", &other.token], &other)
+            .unwrap();
+
+        let shares = manager.list_shares().unwrap();
+        assert_eq!(shares.len(), 1);
+        assert_eq!(shares[0].token, share.token);
+    }
+
     #[test]
     fn revoke_share_removes_token() {
         let (manager, _dir) = temp_manager();
@@ -270,4 +593,27 @@ mod tests {
         let result = manager.revoke_share(&share.token, "wrong-secret");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn shares_stay_out_of_project_local_session_storage() {
+        let (manager, dir) = temp_manager();
+        let sessions_root = dir.path().join(".omni").join("sessions");
+        let shares_root = dir.path().join("global");
+        let manager = SessionManager::new(
+            Storage::with_root(sessions_root.clone()),
+            manager.project().clone(),
+        )
+        .with_share_storage(Storage::with_root(shares_root.clone()));
+
+        let session = manager.create_session().unwrap();
+        let share = manager
+            .create_share(&session.id, ShareOptions::default())
+            .unwrap();
+
+        assert!(sessions_root.join("session").exists());
+        assert!(!sessions_root.join("share").exists());
+        assert!(shares_root.join("share").exists());
+        let exported = manager.get_shared_session(&share.token, None).unwrap();
+        assert_eq!(exported.session.id, session.id);
+    }
 }
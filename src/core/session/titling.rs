@@ -45,15 +45,18 @@ Title:"
 }
 
 /// Extract a title from LLM response (cleans up any quotes or extra whitespace)
+///
+/// Returns an empty string if nothing but quotes and whitespace is left.
 #[must_use]
 pub fn extract_title(response: &str) -> String {
     response
         .trim()
-        .trim_matches('"')
-        .trim_matches('\'')
         .lines()
         .next()
-        .unwrap_or(response.trim())
+        .unwrap_or_default()
+        .trim()
+        .trim_matches(['"', '\''])
+        .trim()
         .to_string()
 }
 
@@ -76,4 +79,10 @@ mod tests {
     fn extract_title_trims_whitespace() {
         assert_eq!(extract_title("  My Title  \n"), "My Title");
     }
+
+    #[test]
+    fn extract_title_of_only_quotes_is_empty() {
+        assert_eq!(extract_title("\"\""), "");
+        assert_eq!(extract_title(""), "");
+    }
 }
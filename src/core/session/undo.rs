@@ -0,0 +1,179 @@
+//! Per-turn file changes for `omni session undo` and the TUI's `/undo`
+//!
+//! The agent snapshots the worktree before each tool that writes files and
+//! records what the tool changed, grouped by turn, so the most recent turn can
+//! be reverted without touching files it didn't change.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::SessionManager;
+use crate::core::snapshot::Patch;
+
+/// Files changed by the tools of one agent turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnChanges {
+    /// Turn identifier (ULID, so turns sort chronologically)
+    pub id: String,
+    /// Changes in the order the tools ran, each against the snapshot taken
+    /// just before that tool
+    pub patches: Vec<Patch>,
+    /// Snapshot taken after the turn's latest change, to spot later edits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+impl TurnChanges {
+    /// Every file the turn changed, sorted and deduplicated
+    #[must_use]
+    pub fn files(&self) -> Vec<&PathBuf> {
+        let mut files: Vec<_> = self.patches.iter().flat_map(|p| &p.files).collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+}
+
+impl SessionManager {
+    /// Record the files a tool changed during a turn, and the snapshot taken
+    /// right after it
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage read or write fails
+    pub fn record_turn_change(
+        &self,
+        session_id: &str,
+        turn_id: &str,
+        patch: Patch,
+        after: Option<String>,
+    ) -> anyhow::Result<()> {
+        let key = ["undo", session_id, turn_id];
+        let mut turn = if self.storage.exists(&key) {
+            self.storage.read::<TurnChanges>(&key)?
+        } else {
+            TurnChanges {
+                id: turn_id.to_string(),
+                patches: Vec::new(),
+                after: None,
+            }
+        };
+        turn.patches.push(patch);
+        turn.after = after;
+        self.storage.write(&key, &turn)?;
+        Ok(())
+    }
+
+    /// Changes made by the most recent turn that changed files
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage read fails
+    pub fn last_turn_changes(&self, session_id: &str) -> anyhow::Result<Option<TurnChanges>> {
+        let keys = self.storage.list(&["undo", session_id])?;
+        let Some(turn_id) = keys.last().and_then(|key| key.last()) else {
+            return Ok(None);
+        };
+        Ok(Some(self.storage.read(&["undo", session_id, turn_id])?))
+    }
+
+    /// Snapshot taken before the session's first recorded change, the base
+    /// for a diff of everything the session changed
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage read fails
+    pub fn first_snapshot(&self, session_id: &str) -> anyhow::Result<Option<String>> {
+        let keys = self.storage.list(&["undo", session_id])?;
+        let Some(turn_id) = keys.first().and_then(|key| key.last()) else {
+            return Ok(None);
+        };
+        let turn: TurnChanges = self.storage.read(&["undo", session_id, turn_id])?;
+        Ok(turn.patches.into_iter().next().map(|patch| patch.hash))
+    }
+
+    /// Forget a turn's changes once they have been reverted
+    ///
+    /// # Errors
+    ///
+    /// Returns error if deletion fails
+    pub fn remove_turn_changes(&self, session_id: &str, turn_id: &str) -> anyhow::Result<()> {
+        self.storage.remove(&["undo", session_id, turn_id])?;
+        Ok(())
+    }
+
+    /// Forget every recorded turn for a session
+    pub(super) fn clear_turn_changes(&self, session_id: &str) -> anyhow::Result<()> {
+        for key in self.storage.list(&["undo", session_id])? {
+            if let Some(turn_id) = key.last() {
+                self.remove_turn_changes(session_id, turn_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::project::{Project, ProjectTime};
+    use crate::core::storage::Storage;
+
+    fn patch(hash: &str, file: &str) -> Patch {
+        Patch {
+            hash: hash.to_string(),
+            files: vec![PathBuf::from(file)],
+        }
+    }
+
+    #[test]
+    fn last_turn_changes_groups_patches_by_turn() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = Project {
+            id: "test-project".to_string(),
+            worktree: dir.path().to_path_buf(),
+            vcs: None,
+            time: ProjectTime {
+                created: 0,
+                initialized: 0,
+            },
+        };
+        let manager = SessionManager::new(Storage::with_root(dir.path().join("data")), project);
+        let session = manager.create_session().unwrap();
+        assert!(manager.last_turn_changes(&session.id).unwrap().is_none());
+
+        manager
+            .record_turn_change(&session.id, "01A", patch("aaa", "a.txt"), None)
+            .unwrap();
+        manager
+            .record_turn_change(&session.id, "01B", patch("bbb", "b.txt"), None)
+            .unwrap();
+        manager
+            .record_turn_change(
+                &session.id,
+                "01B",
+                patch("ccc", "c.txt"),
+                Some("ddd".to_string()),
+            )
+            .unwrap();
+
+        let last = manager.last_turn_changes(&session.id).unwrap().unwrap();
+        assert_eq!(last.id, "01B");
+        assert_eq!(
+            manager.first_snapshot(&session.id).unwrap().as_deref(),
+            Some("aaa")
+        );
+        let hashes: Vec<&str> = last.patches.iter().map(|p| p.hash.as_str()).collect();
+        assert_eq!(hashes, ["bbb", "ccc"]);
+        assert_eq!(last.after.as_deref(), Some("ddd"));
+
+        manager.remove_turn_changes(&session.id, "01B").unwrap();
+        let last = manager.last_turn_changes(&session.id).unwrap().unwrap();
+        assert_eq!(last.id, "01A");
+
+        manager.delete_session(&session.id).unwrap();
+        assert!(manager.last_turn_changes(&session.id).unwrap().is_none());
+        assert!(manager.first_snapshot(&session.id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! Audit log of shell commands executed by the agent.
+//!
+//! Each command is appended as a JSON line to `.omni/shell_history.log` in the
+//! project root. When the log exceeds its size cap it is rotated to
+//! `shell_history.log.1`, replacing any previous rotation.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Log file name within the project `.omni` directory.
+const LOG_FILE: &str = "shell_history.log";
+
+/// Default maximum log size before rotation (1 MiB).
+pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+
+/// A single logged shell command.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShellHistoryEntry {
+    /// When the command finished (RFC 3339).
+    pub timestamp: String,
+    /// Session the command ran in, if any.
+    pub session_id: Option<String>,
+    /// Exit code (`None` if terminated by a signal or failed to spawn).
+    pub exit_code: Option<i32>,
+    /// The command as passed to the shell.
+    pub command: String,
+}
+
+impl ShellHistoryEntry {
+    /// Create an entry timestamped now.
+    #[must_use]
+    pub fn new(command: &str, exit_code: Option<i32>, session_id: Option<&str>) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            session_id: session_id.map(String::from),
+            exit_code,
+            command: command.to_string(),
+        }
+    }
+}
+
+/// Append-only, size-capped shell command log.
+#[derive(Debug, Clone)]
+pub struct ShellHistory {
+    path: PathBuf,
+    max_bytes: u64,
+}
+
+impl ShellHistory {
+    /// Create a log at an explicit path.
+    #[must_use]
+    pub const fn new(path: PathBuf, max_bytes: u64) -> Self {
+        Self { path, max_bytes }
+    }
+
+    /// Create a log for the project containing `dir`.
+    #[must_use]
+    pub fn for_project(dir: &Path) -> Self {
+        let root = crate::core::project::Project::detect(dir)
+            .map_or_else(|_| dir.to_path_buf(), |p| p.worktree);
+        Self::new(root.join(".omni").join(LOG_FILE), DEFAULT_MAX_BYTES)
+    }
+
+    /// Path of the active log file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry, rotating the log first if it exceeds the size cap.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log cannot be written.
+    pub fn append(&self, entry: &ShellHistoryEntry) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        if std::fs::metadata(&self.path).is_ok_and(|m| m.len() >= self.max_bytes) {
+            std::fs::rename(&self.path, self.path.with_extension("log.1"))?;
+        }
+
+        let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{line}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_writes_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = ShellHistory::new(dir.path().join(LOG_FILE), DEFAULT_MAX_BYTES);
+
+        history
+            .append(&ShellHistoryEntry::new("ls -la", Some(0), Some("ses_1")))
+            .unwrap();
+        history
+            .append(&ShellHistoryEntry::new("false", Some(1), None))
+            .unwrap();
+
+        let contents = std::fs::read_to_string(history.path()).unwrap();
+        let entries: Vec<ShellHistoryEntry> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].command, "ls -la");
+        assert_eq!(entries[0].session_id.as_deref(), Some("ses_1"));
+        assert_eq!(entries[1].exit_code, Some(1));
+    }
+
+    #[test]
+    fn append_rotates_when_over_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = ShellHistory::new(dir.path().join(LOG_FILE), 10);
+
+        history
+            .append(&ShellHistoryEntry::new("echo first", Some(0), None))
+            .unwrap();
+        history
+            .append(&ShellHistoryEntry::new("echo second", Some(0), None))
+            .unwrap();
+
+        let rotated = std::fs::read_to_string(dir.path().join("shell_history.log.1")).unwrap();
+        let current = std::fs::read_to_string(history.path()).unwrap();
+        assert!(rotated.contains("echo first"));
+        assert!(current.contains("echo second"));
+        assert!(!current.contains("echo first"));
+    }
+}
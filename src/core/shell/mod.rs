@@ -1,5 +1,7 @@
 //! Natural language to shell command translation.
 
+mod history;
+mod preview;
 mod whitelist;
 
 use std::io::{BufRead, BufReader, Write};
@@ -11,6 +13,8 @@ use crate::core::agent::{
     AgentError, CompletionEvent, CompletionRequest, Content, LlmProvider, Message, Result, Role,
 };
 
+pub use history::{ShellHistory, ShellHistoryEntry};
+pub use preview::{CommandPreview, preview_command};
 pub use whitelist::is_whitelisted;
 
 /// Marker returned by the LLM when the prompt is not a shell task.
@@ -78,6 +82,7 @@ pub async fn generate_command(
         }],
         system: Some(SHELL_SYSTEM_PROMPT.to_string()),
         tools: None,
+        reasoning_effort: None,
     };
 
     let stream = provider.stream(request).await?;
@@ -0,0 +1,253 @@
+//! Dry-run previews of shell commands awaiting approval.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::core::agent::permission::glob_match;
+
+/// Preview lines kept for display.
+const MAX_PREVIEW_LINES: usize = 8;
+
+/// Git subcommands whose effect depends on the working tree state.
+const GIT_STATUS_SUBCOMMANDS: &[&str] = &[
+    "add", "checkout", "clean", "commit", "mv", "reset", "restore", "rm", "stash",
+];
+
+/// What a command is predicted to touch, worked out without running it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandPreview {
+    /// How the prediction was made, e.g. `git status --short`.
+    pub source: String,
+    /// Predicted lines, at most `MAX_PREVIEW_LINES`.
+    pub lines: Vec<String>,
+    /// Lines left out after the cap.
+    pub omitted: usize,
+}
+
+impl CommandPreview {
+    fn new(source: impl Into<String>, mut lines: Vec<String>) -> Self {
+        let omitted = lines.len().saturating_sub(MAX_PREVIEW_LINES);
+        lines.truncate(MAX_PREVIEW_LINES);
+        Self {
+            source: source.into(),
+            lines,
+            omitted,
+        }
+    }
+}
+
+/// Predict what `command` would touch, for commands with a known safe preview.
+///
+/// `git` commands that act on the working tree show `git status --short`, and
+/// `rm` lists the paths its arguments match, with a leading `~` taken as the
+/// home directory. Commands with pipes, redirects, chaining, substitutions or
+/// `~user` paths return `None`, since their effect can't be read off the
+/// arguments.
+#[must_use]
+pub fn preview_command(command: &str, working_dir: &Path) -> Option<CommandPreview> {
+    if command.contains(['|', ';', '&', '>', '<', '$', '`', '(', ')', '\n']) {
+        return None;
+    }
+
+    let words: Vec<&str> = command.split_whitespace().collect();
+    match words.as_slice() {
+        ["git", subcommand, ..] if GIT_STATUS_SUBCOMMANDS.contains(subcommand) => {
+            git_status(working_dir)
+        }
+        // Quoted paths can't be split on whitespace
+        ["rm", args @ ..] if !command.contains(['"', '\'']) => rm_targets(args, working_dir),
+        _ => None,
+    }
+}
+
+/// Short status of the repository at `working_dir`, if it is one.
+fn git_status(working_dir: &Path) -> Option<CommandPreview> {
+    let output = Command::new("git")
+        .args(["status", "--short"])
+        .current_dir(working_dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let lines = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    Some(CommandPreview::new("git status --short", lines))
+}
+
+/// Paths that `rm` with `args` would remove, with globs expanded.
+fn rm_targets(args: &[&str], working_dir: &Path) -> Option<CommandPreview> {
+    let mut recursive = false;
+    let mut paths = Vec::new();
+    let mut options_done = false;
+    for arg in args {
+        if !options_done && *arg == "--" {
+            options_done = true;
+        } else if !options_done && arg.starts_with('-') {
+            recursive |=
+                *arg == "--recursive" || (!arg.starts_with("--") && arg.contains(['r', 'R']));
+        } else {
+            paths.push(*arg);
+        }
+    }
+    if paths.is_empty()
+        || paths
+            .iter()
+            .any(|path| resolve(path, working_dir).is_none())
+    {
+        return None;
+    }
+
+    let mut lines = Vec::new();
+    for path in paths {
+        if path.contains(['*', '?']) {
+            let matches = expand_glob(path, working_dir);
+            if matches.is_empty() {
+                lines.push(format!("{path} (no matches)"));
+            }
+            lines.extend(
+                matches
+                    .iter()
+                    .map(|found| describe_path(found, working_dir, recursive)),
+            );
+        } else {
+            lines.push(describe_path(path, working_dir, recursive));
+        }
+    }
+    Some(CommandPreview::new("matching paths", lines))
+}
+
+/// Entries matching a glob in its last path segment, sorted.
+///
+/// Like the shell, hidden files only match patterns that start with a dot.
+fn expand_glob(pattern: &str, working_dir: &Path) -> Vec<String> {
+    let (dir, name_pattern) = pattern
+        .rsplit_once('/')
+        .map_or(("", pattern), |(dir, name)| (dir, name));
+    if dir.contains(['*', '?']) {
+        return Vec::new();
+    }
+
+    let search_dir = match dir {
+        "" => Some(working_dir.to_path_buf()),
+        _ => resolve(dir, working_dir),
+    };
+    let Some(Ok(entries)) = search_dir.map(std::fs::read_dir) else {
+        return Vec::new();
+    };
+
+    let mut matches: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| !name.starts_with('.') || name_pattern.starts_with('.'))
+        .filter(|name| glob_match(name_pattern.as_bytes(), name.as_bytes()))
+        .map(|name| match dir {
+            "" => name,
+            _ => format!("{dir}/{name}"),
+        })
+        .collect();
+    matches.sort();
+    matches
+}
+
+/// Where `path` points, as the shell would see it from `working_dir`.
+///
+/// A leading `~` or `~/` is the home directory. Other users' homes (`~user`)
+/// aren't looked up, so those give `None`.
+fn resolve(path: &str, working_dir: &Path) -> Option<PathBuf> {
+    match path.strip_prefix('~') {
+        None => Some(working_dir.join(path)),
+        Some("") => dirs::home_dir(),
+        Some(rest) => {
+            let rest = rest.strip_prefix('/')?;
+            dirs::home_dir().map(|home| home.join(rest))
+        }
+    }
+}
+
+/// A path with what removing it would do.
+fn describe_path(path: &str, working_dir: &Path, recursive: bool) -> String {
+    let Some(full) = resolve(path, working_dir) else {
+        return format!("{path} (not found)");
+    };
+    if full.is_dir() {
+        if recursive {
+            format!("{path}/ (and everything in it)")
+        } else {
+            format!("{path}/ (directory, needs -r)")
+        }
+    } else if full.exists() {
+        path.to_string()
+    } else {
+        format!("{path} (not found)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rm_expands_globs_and_flags_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.log"), "").unwrap();
+        std::fs::write(dir.path().join("b.log"), "").unwrap();
+        std::fs::write(dir.path().join(".hidden.log"), "").unwrap();
+        std::fs::create_dir(dir.path().join("build")).unwrap();
+
+        let preview = preview_command("rm -rf *.log build missing.txt", dir.path()).unwrap();
+        assert_eq!(preview.source, "matching paths");
+        assert_eq!(
+            preview.lines,
+            vec![
+                "a.log",
+                "b.log",
+                "build/ (and everything in it)",
+                "missing.txt (not found)",
+            ]
+        );
+
+        let preview = preview_command("rm build", dir.path()).unwrap();
+        assert_eq!(preview.lines, vec!["build/ (directory, needs -r)"]);
+    }
+
+    #[test]
+    fn rm_resolves_home_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        assert_eq!(resolve("~", dir.path()), Some(home.clone()));
+        assert_eq!(resolve("~/notes", dir.path()), Some(home.join("notes")));
+        assert_eq!(resolve("notes", dir.path()), Some(dir.path().join("notes")));
+
+        let preview = preview_command("rm -r ~", dir.path()).unwrap();
+        assert_eq!(preview.lines, vec!["~/ (and everything in it)"]);
+        assert!(preview_command("rm -r ~root/notes", dir.path()).is_none());
+    }
+
+    #[test]
+    fn caps_long_previews() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..12 {
+            std::fs::write(dir.path().join(format!("{i:02}.tmp")), "").unwrap();
+        }
+        let preview = preview_command("rm *.tmp", dir.path()).unwrap();
+        assert_eq!(preview.lines.len(), MAX_PREVIEW_LINES);
+        assert_eq!(preview.omitted, 4);
+    }
+
+    #[test]
+    fn skips_commands_it_cannot_predict() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(preview_command("rm $(cat list)", dir.path()).is_none());
+        assert!(preview_command("find . | xargs rm", dir.path()).is_none());
+        assert!(preview_command("rm \"my file\"", dir.path()).is_none());
+        assert!(preview_command("cargo build", dir.path()).is_none());
+        // Not a git repository
+        assert!(preview_command("git add .", dir.path()).is_none());
+    }
+}
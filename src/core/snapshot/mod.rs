@@ -4,11 +4,15 @@
 //! rollback/undo functionality
 
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Output};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
 
 use crate::core::project::Project;
 
 /// Snapshot manager for tracking file changes
+#[derive(Clone)]
 pub struct SnapshotManager {
     /// Path to the shadow git directory
     git_dir: PathBuf,
@@ -23,8 +27,17 @@ pub struct Snapshot {
     pub hash: String,
 }
 
+/// A recorded snapshot and when it was taken
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    /// Tree hash identifying the snapshot
+    pub hash: String,
+    /// Creation time in milliseconds since the epoch
+    pub created: i64,
+}
+
 /// Files changed since a snapshot
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Patch {
     /// Snapshot hash
     pub hash: String,
@@ -32,6 +45,53 @@ pub struct Patch {
     pub files: Vec<PathBuf>,
 }
 
+/// Outcome of a confirmed restore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreOutcome {
+    /// Working tree was restored to the snapshot
+    Restored,
+    /// Working tree already matches the snapshot
+    Unchanged,
+    /// Restore was declined at the confirmation step
+    Declined,
+}
+
+/// How long snapshots are kept before cleanup prunes them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Snapshots older than this many days are pruned
+    pub days: u32,
+    /// Most recent snapshots kept regardless of age
+    pub keep_last_n: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            days: 7,
+            keep_last_n: 0,
+        }
+    }
+}
+
+/// Outcome of a snapshot cleanup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// Snapshots removed
+    pub removed: usize,
+    /// Snapshots kept
+    pub kept: usize,
+}
+
+/// Ref namespace recording when each snapshot was taken
+const SNAPSHOT_REFS: &str = "refs/snapshots/";
+
+/// Marker file touched after each cleanup
+const CLEANUP_MARKER: &str = "omni-last-cleanup";
+
+/// Minimum time between opportunistic cleanups
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
 /// File diff information
 #[derive(Debug, Clone)]
 pub struct FileDiff {
@@ -48,6 +108,12 @@ pub struct FileDiff {
 }
 
 impl SnapshotManager {
+    /// Create a snapshot manager with an explicit shadow repository
+    #[must_use]
+    pub const fn new(git_dir: PathBuf, worktree: PathBuf) -> Self {
+        Self { git_dir, worktree }
+    }
+
     /// Create a snapshot manager for a project
     ///
     /// # Errors
@@ -64,6 +130,16 @@ impl SnapshotManager {
         })
     }
 
+    /// Create a snapshot manager for the project in the current directory
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the project or data directory cannot be determined
+    pub fn for_current_project() -> anyhow::Result<Self> {
+        let project = Project::detect(&std::env::current_dir()?)?;
+        Self::for_project(&project)
+    }
+
     /// Initialize the snapshot repository
     ///
     /// # Errors
@@ -139,6 +215,22 @@ impl SnapshotManager {
         }
 
         let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        // Keep the tree reachable until retention prunes it
+        let created = chrono::Utc::now().timestamp_millis();
+        let short = hash.get(..12).unwrap_or(&hash);
+        let output = self.git(&[
+            "update-ref",
+            &format!("{SNAPSHOT_REFS}{created}-{short}"),
+            &hash,
+        ])?;
+        if !output.status.success() {
+            tracing::warn!(
+                "failed to record snapshot ref: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
         tracing::info!(hash = %hash, "created snapshot");
 
         Ok(Snapshot { hash })
@@ -254,6 +346,30 @@ impl SnapshotManager {
         Ok(())
     }
 
+    /// Restore a snapshot only after `confirm` approves the diff being reverted
+    ///
+    /// The diff covers every change since the snapshot, including edits that
+    /// were not made by the agent, so callers should show it before confirming.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if git operations fail
+    pub fn restore_confirmed(
+        &self,
+        hash: &str,
+        confirm: impl FnOnce(&str) -> bool,
+    ) -> anyhow::Result<RestoreOutcome> {
+        let diff = self.diff(hash)?;
+        if diff.is_empty() {
+            return Ok(RestoreOutcome::Unchanged);
+        }
+        if !confirm(&diff) {
+            return Ok(RestoreOutcome::Declined);
+        }
+        self.restore(hash)?;
+        Ok(RestoreOutcome::Restored)
+    }
+
     /// Revert specific files from patches
     ///
     /// # Errors
@@ -324,6 +440,27 @@ impl SnapshotManager {
     ///
     /// Returns error if git operations fail
     pub fn diff(&self, hash: &str) -> anyhow::Result<String> {
+        self.diff_paths(hash, &[])
+    }
+
+    /// Get diff text since a snapshot for `paths` only (all files if empty)
+    ///
+    /// # Errors
+    ///
+    /// Returns error if git operations fail
+    pub fn diff_paths(&self, hash: &str, paths: &[PathBuf]) -> anyhow::Result<String> {
+        let pathspecs: Vec<String> = if paths.is_empty() {
+            vec![".".to_string()]
+        } else {
+            paths
+                .iter()
+                .map(|path| {
+                    let relative = path.strip_prefix(&self.worktree).unwrap_or(path);
+                    relative.to_string_lossy().into_owned()
+                })
+                .collect()
+        };
+
         // Add current state
         let _ = Command::new("git")
             .args([
@@ -349,8 +486,8 @@ impl SnapshotManager {
                 "--no-ext-diff",
                 hash,
                 "--",
-                ".",
             ])
+            .args(pathspecs)
             .current_dir(&self.worktree)
             .output()?;
 
@@ -362,30 +499,146 @@ impl SnapshotManager {
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
 
-    /// Cleanup old snapshots
+    /// Recorded snapshots as `(created_millis, ref_name)`, newest first
     ///
     /// # Errors
     ///
     /// Returns error if git operations fail
-    pub fn cleanup(&self) -> anyhow::Result<()> {
+    pub fn list(&self) -> anyhow::Result<Vec<(i64, String)>> {
         if !self.git_dir.exists() {
-            return Ok(());
+            return Ok(Vec::new());
         }
 
-        let output = Command::new("git")
-            .args([
-                "--git-dir",
-                &self.git_dir.to_string_lossy(),
-                "--work-tree",
-                &self.worktree.to_string_lossy(),
-                "gc",
-                "--prune=7.days",
-            ])
-            .current_dir(&self.worktree)
-            .output()?;
+        let output = self.git(&["for-each-ref", "--format=%(refname)", SNAPSHOT_REFS])?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to list snapshots: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        let mut snapshots: Vec<(i64, String)> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|name| {
+                let created = name
+                    .strip_prefix(SNAPSHOT_REFS)?
+                    .split('-')
+                    .next()?
+                    .parse()
+                    .ok()?;
+                Some((created, name.to_string()))
+            })
+            .collect();
+        snapshots.sort_by(|a, b| b.cmp(a));
+        Ok(snapshots)
+    }
+
+    /// Recorded snapshots with their tree hashes, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns error if git operations fail
+    pub fn history(&self) -> anyhow::Result<Vec<SnapshotEntry>> {
+        if !self.git_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let output = self.git(&[
+            "for-each-ref",
+            "--format=%(refname) %(objectname)",
+            SNAPSHOT_REFS,
+        ])?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to list snapshots: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        let mut entries: Vec<SnapshotEntry> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (name, hash) = line.split_once(' ')?;
+                let created = name
+                    .strip_prefix(SNAPSHOT_REFS)?
+                    .split('-')
+                    .next()?
+                    .parse()
+                    .ok()?;
+                Some(SnapshotEntry {
+                    hash: hash.to_string(),
+                    created,
+                })
+            })
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.created));
+        Ok(entries)
+    }
+
+    /// Expand a hash prefix to the full hash of a recorded snapshot
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no snapshot, or more than one, matches the prefix
+    pub fn resolve(&self, prefix: &str) -> anyhow::Result<String> {
+        let mut matches: Vec<String> = self
+            .history()?
+            .into_iter()
+            .filter(|entry| entry.hash.starts_with(prefix))
+            .map(|entry| entry.hash)
+            .collect();
+        matches.sort();
+        matches.dedup();
+
+        match matches.as_slice() {
+            [] => anyhow::bail!("no snapshot matches '{prefix}'"),
+            [hash] => Ok(hash.clone()),
+            _ => anyhow::bail!(
+                "'{prefix}' matches {} snapshots, use more of the hash",
+                matches.len()
+            ),
+        }
+    }
+
+    /// Prune snapshots outside the retention policy
+    ///
+    /// The newest `keep_last_n` snapshots are always kept; older ones are
+    /// removed once they pass the retention window.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if git operations fail
+    pub fn cleanup(&self, policy: &RetentionPolicy) -> anyhow::Result<CleanupReport> {
+        if !self.git_dir.exists() {
+            return Ok(CleanupReport::default());
+        }
+
+        let cutoff =
+            chrono::Utc::now().timestamp_millis() - i64::from(policy.days) * 24 * 60 * 60 * 1000;
+        let mut report = CleanupReport::default();
+        for (i, (created, name)) in self.list()?.into_iter().enumerate() {
+            if i < policy.keep_last_n || created >= cutoff {
+                report.kept += 1;
+                continue;
+            }
+            let output = self.git(&["update-ref", "-d", &name])?;
+            if output.status.success() {
+                report.removed += 1;
+            } else {
+                tracing::warn!(
+                    "failed to remove snapshot {name}: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                );
+            }
+        }
 
+        let output = self.git(&["gc", &format!("--prune={}.days", policy.days)])?;
         if output.status.success() {
-            tracing::info!("snapshot cleanup completed");
+            tracing::info!(
+                removed = report.removed,
+                kept = report.kept,
+                "snapshot cleanup completed"
+            );
         } else {
             tracing::warn!(
                 "snapshot cleanup failed: {}",
@@ -393,7 +646,45 @@ impl SnapshotManager {
             );
         }
 
-        Ok(())
+        std::fs::write(self.git_dir.join(CLEANUP_MARKER), "")?;
+        Ok(report)
+    }
+
+    /// Run [`Self::cleanup`] if it has not run in the last day
+    ///
+    /// Returns `None` when cleanup was not due.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if git operations fail
+    pub fn cleanup_if_due(
+        &self,
+        policy: &RetentionPolicy,
+    ) -> anyhow::Result<Option<CleanupReport>> {
+        let last = std::fs::metadata(self.git_dir.join(CLEANUP_MARKER))
+            .and_then(|m| m.modified())
+            .ok();
+        let due = last.is_none_or(|last| {
+            SystemTime::now()
+                .duration_since(last)
+                .is_ok_and(|elapsed| elapsed >= CLEANUP_INTERVAL)
+        });
+        if !due {
+            return Ok(None);
+        }
+        self.cleanup(policy).map(Some)
+    }
+
+    /// Run git against the shadow repository
+    fn git(&self, args: &[&str]) -> std::io::Result<Output> {
+        Command::new("git")
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .arg("--work-tree")
+            .arg(&self.worktree)
+            .args(args)
+            .current_dir(&self.worktree)
+            .output()
     }
 }
 
@@ -417,4 +708,109 @@ mod tests {
         assert!(manager.git_dir.to_string_lossy().contains("snapshot"));
         assert!(manager.git_dir.to_string_lossy().contains("test-project"));
     }
+
+    #[test]
+    fn restore_confirmed_shows_diff_and_respects_decline() {
+        let dir = tempfile::tempdir().unwrap();
+        let worktree = dir.path().join("work");
+        std::fs::create_dir_all(&worktree).unwrap();
+        let manager = SnapshotManager {
+            git_dir: dir.path().join("snapshot"),
+            worktree: worktree.clone(),
+        };
+
+        let file = worktree.join("notes.txt");
+        std::fs::write(&file, "original\n").unwrap();
+        let snapshot = manager.track().unwrap();
+        std::fs::write(&file, "user edit\n").unwrap();
+
+        let outcome = manager
+            .restore_confirmed(&snapshot.hash, |diff| {
+                assert!(diff.contains("+user edit"));
+                false
+            })
+            .unwrap();
+        assert_eq!(outcome, RestoreOutcome::Declined);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "user edit\n");
+
+        let outcome = manager.restore_confirmed(&snapshot.hash, |_| true).unwrap();
+        assert_eq!(outcome, RestoreOutcome::Restored);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "original\n");
+
+        let outcome = manager.restore_confirmed(&snapshot.hash, |_| true).unwrap();
+        assert_eq!(outcome, RestoreOutcome::Unchanged);
+    }
+
+    #[test]
+    fn history_lists_hashes_and_resolves_prefixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let worktree = dir.path().join("work");
+        std::fs::create_dir_all(&worktree).unwrap();
+        let manager = SnapshotManager {
+            git_dir: dir.path().join("snapshot"),
+            worktree: worktree.clone(),
+        };
+        assert!(manager.history().unwrap().is_empty());
+
+        std::fs::write(worktree.join("notes.txt"), "one").unwrap();
+        let first = manager.track().unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        std::fs::write(worktree.join("notes.txt"), "two").unwrap();
+        let second = manager.track().unwrap();
+
+        let history = manager.history().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].hash, second.hash);
+        assert_eq!(history[1].hash, first.hash);
+        assert!(history[0].created >= history[1].created);
+
+        assert_eq!(manager.resolve(&first.hash[..8]).unwrap(), first.hash);
+        assert!(manager.resolve("zzzz").is_err());
+        assert!(manager.resolve("").is_err());
+    }
+
+    #[test]
+    fn cleanup_keeps_last_n_and_prunes_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let worktree = dir.path().join("work");
+        std::fs::create_dir_all(&worktree).unwrap();
+        let manager = SnapshotManager {
+            git_dir: dir.path().join("snapshot"),
+            worktree: worktree.clone(),
+        };
+
+        for content in ["one", "two", "three"] {
+            std::fs::write(worktree.join("notes.txt"), content).unwrap();
+            manager.track().unwrap();
+        }
+        assert_eq!(manager.list().unwrap().len(), 3);
+
+        // Everything is inside a 7-day window
+        let report = manager.cleanup(&RetentionPolicy::default()).unwrap();
+        assert_eq!(
+            report,
+            CleanupReport {
+                removed: 0,
+                kept: 3
+            }
+        );
+
+        let policy = RetentionPolicy {
+            days: 0,
+            keep_last_n: 1,
+        };
+        let newest = manager.list().unwrap()[0].clone();
+        let report = manager.cleanup(&policy).unwrap();
+        assert_eq!(
+            report,
+            CleanupReport {
+                removed: 2,
+                kept: 1
+            }
+        );
+        assert_eq!(manager.list().unwrap(), vec![newest]);
+
+        // Cleanup just ran, so it is not due again
+        assert!(manager.cleanup_if_due(&policy).unwrap().is_none());
+    }
 }
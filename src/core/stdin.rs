@@ -0,0 +1,128 @@
+//! Piped standard input as prompt context.
+
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Read};
+
+/// Default maximum bytes of piped input included in a prompt.
+pub const DEFAULT_MAX_STDIN_BYTES: usize = 256 * 1024;
+
+/// Input read from a pipe, capped at a byte limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipedInput {
+    /// The text that was kept.
+    pub text: String,
+    /// Whether more than the limit was piped.
+    pub truncated: bool,
+}
+
+impl PipedInput {
+    /// Keep at most `max_bytes` of `bytes`, cut at a character boundary.
+    #[must_use]
+    pub fn new(bytes: &[u8], max_bytes: usize) -> Self {
+        let text = String::from_utf8_lossy(bytes);
+        if text.len() <= max_bytes {
+            return Self {
+                text: text.into_owned(),
+                truncated: false,
+            };
+        }
+
+        let mut end = max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        Self {
+            text: text[..end].to_string(),
+            truncated: true,
+        }
+    }
+
+    /// Append the input to `prompt` as a fenced block.
+    #[must_use]
+    pub fn append_to(&self, prompt: &str) -> String {
+        let fence = fence_for(&self.text);
+        let mut combined = format!("{prompt}\n\n{fence}\n{}", self.text);
+        if !self.text.ends_with('\n') {
+            combined.push('\n');
+        }
+        combined.push_str(&fence);
+        if self.truncated {
+            let _ = write!(
+                combined,
+                "\n\n[input truncated: showing the first {} bytes]",
+                self.text.len()
+            );
+        }
+        combined
+    }
+}
+
+/// Read piped standard input, or `None` when stdin is a terminal or empty.
+///
+/// Reads one byte past `max_bytes` to detect truncation and stops there, so
+/// the rest of a large or endless pipe is never read.
+///
+/// # Errors
+///
+/// Returns an error if stdin cannot be read.
+pub fn read_piped(max_bytes: usize) -> std::io::Result<Option<PipedInput>> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok(None);
+    }
+
+    let mut bytes = Vec::new();
+    let limit = u64::try_from(max_bytes)
+        .unwrap_or(u64::MAX)
+        .saturating_add(1);
+    stdin.lock().take(limit).read_to_end(&mut bytes)?;
+    if bytes.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+
+    Ok(Some(PipedInput::new(&bytes, max_bytes)))
+}
+
+/// A backtick fence longer than any run of backticks in `text`.
+fn fence_for(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    "`".repeat(longest.max(2) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appends_input_as_fenced_block() {
+        let input = PipedInput::new(b"error: boom\n", 1024);
+        assert_eq!(
+            input.append_to("explain this"),
+            "explain this\n\n```\nerror: boom\n```"
+        );
+    }
+
+    #[test]
+    fn fence_outgrows_backticks_in_input() {
+        let input = PipedInput::new(b"```rust\nfn main() {}\n```", 1024);
+        let combined = input.append_to("review");
+        assert!(combined.starts_with("review\n\n````\n```rust"));
+        assert!(combined.ends_with("```\n````"));
+    }
+
+    #[test]
+    fn truncates_at_char_boundary_with_notice() {
+        let input = PipedInput::new("aé".as_bytes(), 2);
+        assert_eq!(input.text, "a");
+        assert!(input.truncated);
+        assert!(
+            input
+                .append_to("x")
+                .ends_with("[input truncated: showing the first 1 bytes]")
+        );
+    }
+}
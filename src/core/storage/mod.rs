@@ -214,6 +214,22 @@ impl Storage {
 
         Ok(items)
     }
+
+    /// Find stored entries that cannot be parsed as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the storage directory cannot be read.
+    pub fn scan_corrupt(&self) -> Result<Vec<PathBuf>> {
+        let mut corrupt = Vec::new();
+        for key in self.list(&[])? {
+            let key_refs: Vec<&str> = key.iter().map(String::as_str).collect();
+            if self.read::<serde_json::Value>(&key_refs).is_err() {
+                corrupt.push(self.path(&key_refs));
+            }
+        }
+        Ok(corrupt)
+    }
 }
 
 #[cfg(test)]
@@ -317,4 +333,18 @@ mod tests {
         assert!(keys.iter().any(|k| k.last() == Some(&"ses1".to_string())));
         assert!(keys.iter().any(|k| k.last() == Some(&"ses2".to_string())));
     }
+
+    #[test]
+    fn scan_corrupt_finds_unparseable_entries() {
+        let (storage, dir) = temp_storage();
+        let data = TestData {
+            id: "ok".to_string(),
+            value: 1,
+        };
+        storage.write(&["session", "proj1", "ok"], &data).unwrap();
+        std::fs::write(dir.path().join("session/proj1/bad.json"), "{ not json").unwrap();
+
+        let corrupt = storage.scan_corrupt().unwrap();
+        assert_eq!(corrupt, vec![dir.path().join("session/proj1/bad.json")]);
+    }
 }
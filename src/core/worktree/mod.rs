@@ -134,6 +134,18 @@ impl WorktreeManager {
         Ok(worktrees)
     }
 
+    /// Find a worktree by name
+    ///
+    /// # Errors
+    ///
+    /// Returns error if git operations fail or no worktree has that name
+    pub fn find(&self, name: &str) -> anyhow::Result<WorktreeInfo> {
+        self.list()?
+            .into_iter()
+            .find(|w| w.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Worktree '{name}' not found"))
+    }
+
     /// Create a new worktree
     ///
     /// # Errors
@@ -2,12 +2,19 @@ use std::io::Write as _;
 use std::process::ExitCode;
 
 use clap::Parser;
+use tokio_util::sync::CancellationToken;
 use tracing_subscriber::EnvFilter;
 
 use omni_cli::{
     Config,
-    cli::{Cli, Commands, ConfigCommands, SessionCommands},
-    core::session::SessionTarget,
+    cli::{
+        ApprovalCommands, AuthCommands, Cli, Commands, ConfigCommands, MaintenanceCommands,
+        MemoryCommands, PersonaCommands, SessionCommands, SnapshotCommands, WorktreeCommands,
+    },
+    core::{
+        agent::{AgentError, AgentMode, ChatEvent, ChatSummary},
+        session::SessionTarget,
+    },
 };
 
 #[tokio::main]
@@ -26,6 +33,13 @@ async fn main() -> ExitCode {
         .with_env_filter(EnvFilter::new(filter))
         .init();
 
+    if cli.no_color {
+        omni_cli::core::color::disable();
+    }
+    if cli.global_history {
+        Config::use_global_history();
+    }
+
     match run(cli).await {
         Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
@@ -42,6 +56,25 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
             anyhow::bail!("Cannot use both a prompt and a subcommand");
         }
         let config = Config::load()?;
+
+        // Piped input is context for a question, not a shell task. It may
+        // carry instructions and nobody can answer a prompt, so only
+        // read-only tools run
+        if let Some(input) = omni_cli::core::stdin::read_piped(config.agent.max_stdin_bytes)? {
+            return run_agent(
+                &input.append_to(&prompt),
+                SessionTarget::New,
+                false,
+                Vec::new(),
+                "summary",
+                false,
+                cli.dry_run,
+                None,
+                true,
+            )
+            .await;
+        }
+
         let provider = config.agent.create_provider()?;
         return omni_cli::core::shell::run(
             provider,
@@ -54,16 +87,50 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
         .map_err(|e| anyhow::anyhow!("{e}"));
     }
 
+    // Clipboard contents as an agent prompt
+    if cli.paste {
+        if cli.command.is_some() {
+            anyhow::bail!("Cannot use --paste with a subcommand");
+        }
+        let prompt = omni_cli::core::clipboard::read_text()?;
+        return run_agent(
+            &prompt,
+            SessionTarget::New,
+            false,
+            Vec::new(),
+            "summary",
+            false,
+            cli.dry_run,
+            None,
+            false,
+        )
+        .await;
+    }
+
     // No subcommand = launch TUI
     let Some(command) = cli.command else {
+        spawn_snapshot_cleanup();
         return omni_cli::tui::run().await;
     };
 
+    if matches!(
+        command,
+        Commands::Agent { .. } | Commands::Tui { .. } | Commands::Serve { .. }
+    ) {
+        spawn_snapshot_cleanup();
+    }
+
     match command {
         Commands::Agent {
             prompt,
             r#continue,
             session,
+            plan,
+            append_system,
+            show_tools,
+            output,
+            dry_run,
+            worktree,
         } => {
             // Fail fast if explicit session ID doesn't exist
             if let Some(ref id) = session {
@@ -74,34 +141,33 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
             }
 
             let target = SessionTarget::from_flags(r#continue, session);
-            let config = Config::load()?;
-            let provider = config.agent.create_provider()?;
-            let mut agent = omni_cli::core::Agent::with_context(
-                provider,
-                &config.agent.model,
-                config.agent.max_tokens,
-                None,
-            );
-
-            // Enable sessions with target
-            if let Err(e) = agent.enable_sessions_with_target(target) {
-                tracing::warn!("failed to enable sessions: {e}");
+            let worktree = match worktree {
+                Some(name) if !dry_run => Some(create_session_worktree(name.as_deref())?),
+                _ => None,
+            };
+            let result = run_agent(
+                &prompt,
+                target,
+                plan,
+                append_system,
+                &show_tools,
+                output == "json",
+                dry_run,
+                worktree.as_ref().map(|w| w.directory.clone()),
+                false,
+            )
+            .await;
+            if let Some(worktree) = &worktree {
+                offer_worktree_removal(worktree);
             }
-
-            let _response = agent
-                .chat(&prompt, |text| {
-                    print!("{text}");
-                    std::io::stdout().flush().ok();
-                })
-                .await
-                .map_err(|e| anyhow::anyhow!("{e}"))?;
-
-            println!();
+            result?;
         }
 
         Commands::Tui {
             r#continue,
             session,
+            plan,
+            worktree,
         } => {
             // Fail fast if explicit session ID doesn't exist
             if let Some(ref id) = session {
@@ -112,7 +178,20 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
             }
 
             let target = SessionTarget::from_flags(r#continue, session);
-            omni_cli::tui::run_with_target(target).await?;
+            let mode = plan.then_some(AgentMode::Plan);
+            let worktree = worktree
+                .map(|name| create_session_worktree(name.as_deref()))
+                .transpose()?;
+            let result = omni_cli::tui::run_with_target(
+                target,
+                mode,
+                worktree.as_ref().map(|w| w.directory.clone()),
+            )
+            .await;
+            if let Some(worktree) = &worktree {
+                offer_worktree_removal(worktree);
+            }
+            result?;
         }
 
         Commands::Serve { host, port } => {
@@ -138,56 +217,1084 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                 println!("Or set environment variable:");
                 println!("  export OMNI_API_TOKEN=\"{token}\"");
             }
+            ConfigCommands::Validate => {
+                run_config_validate()?;
+            }
+            ConfigCommands::Persona {
+                command: PersonaCommands::List { format },
+            } => {
+                let personas = omni_cli::config::list_personas()?;
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&personas)?);
+                } else {
+                    println!("{:<24} Source", "Name");
+                    println!("{}", "-".repeat(34));
+                    for persona in personas {
+                        println!("{:<24} {}", persona.name, persona.source.label());
+                    }
+                }
+            }
         },
 
         Commands::Session { command } => {
-            handle_session_command(command)?;
+            handle_session_command(command).await?;
+        }
+
+        Commands::Init { force } => {
+            let root = std::env::current_dir()?;
+            for (path, action) in omni_cli::core::init::init_project(&root, force)? {
+                let shown = path.strip_prefix(&root).unwrap_or(&path);
+                println!("{:<9} {}", action.label(), shown.display());
+            }
+        }
+
+        Commands::Doctor => {
+            run_doctor()?;
+        }
+
+        Commands::Models {
+            provider,
+            probe,
+            format,
+        } => {
+            run_models(provider.as_deref(), probe, &format).await?;
+        }
+
+        Commands::Snapshot { command } => {
+            run_snapshot_command(command)?;
+        }
+
+        Commands::Memory { command } => {
+            run_memory_command(command)?;
+        }
+
+        Commands::Worktree { command } => {
+            run_worktree_command(command)?;
+        }
+
+        Commands::Auth { command } => {
+            run_auth_command(command).await?;
+        }
+
+        Commands::Maintenance {
+            command: MaintenanceCommands::Snapshots { prune },
+        } => {
+            run_snapshot_maintenance(prune)?;
+        }
+
+        Commands::Export {
+            session_id,
+            all: _,
+            format,
+            output,
+            dir,
+            filter,
+        } => {
+            run_export(session_id, &format, output, dir, filter.filter())?;
+        }
+
+        Commands::Completions { shell } => {
+            use clap::CommandFactory as _;
+
+            omni_cli::cli::completions::generate(
+                shell,
+                &mut Cli::command(),
+                "omni",
+                &mut std::io::stdout(),
+            )?;
         }
     }
 
     Ok(())
 }
 
-fn handle_session_command(command: SessionCommands) -> anyhow::Result<()> {
-    use omni_cli::core::session::SessionManager;
+/// Prune the current project's snapshots in the background if a day has passed.
+fn spawn_snapshot_cleanup() {
+    use omni_cli::core::snapshot::SnapshotManager;
 
-    let manager = SessionManager::for_current_project()?;
+    std::thread::spawn(|| {
+        let result = Config::load().and_then(|config| {
+            SnapshotManager::for_current_project()?
+                .cleanup_if_due(&config.agent.snapshot_retention())
+        });
+        if let Err(e) = result {
+            tracing::debug!("snapshot cleanup skipped: {e}");
+        }
+    });
+}
+
+/// Show snapshot counts, pruning them first if requested.
+fn run_snapshot_maintenance(prune: bool) -> anyhow::Result<()> {
+    use omni_cli::core::snapshot::SnapshotManager;
+
+    let policy = Config::load()?.agent.snapshot_retention();
+    let manager = SnapshotManager::for_current_project()?;
+
+    if prune {
+        let report = manager.cleanup(&policy)?;
+        println!(
+            "Pruned {} snapshot(s), kept {}",
+            report.removed, report.kept
+        );
+        return Ok(());
+    }
+
+    let snapshots = manager.list()?;
+    println!("{} snapshot(s)", snapshots.len());
+    if let Some((newest, _)) = snapshots.first() {
+        let when = chrono::DateTime::from_timestamp_millis(*newest)
+            .map_or_else(|| "unknown".to_string(), |dt| dt.to_rfc3339());
+        println!("Newest: {when}");
+    }
+    println!(
+        "Retention: {} day(s), keep last {}",
+        policy.days, policy.keep_last_n
+    );
+    Ok(())
+}
+
+/// Ask a yes/no question on stdin, defaulting to no.
+fn confirm(prompt: &str) -> bool {
+    print!("{prompt} [y/N] ");
+    std::io::stdout().flush().ok();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn run_memory_command(command: MemoryCommands) -> anyhow::Result<()> {
+    use omni_cli::core::memory::{MemoryItem, MemoryManager};
+
+    let manager = MemoryManager::for_current_project()?;
 
     match command {
-        SessionCommands::List { format, limit } => {
-            let sessions = manager.list_sessions()?;
-            let sessions: Vec<_> = sessions.into_iter().take(limit).collect();
+        MemoryCommands::List { category, format } => {
+            let category = category.map(|c| c.parse()).transpose()?;
+            print_memories(&manager.list(category)?, &format)?;
+        }
 
-            if format == "json" {
-                println!("{}", serde_json::to_string_pretty(&sessions)?);
+        MemoryCommands::Add {
+            content,
+            category,
+            pin,
+            tags,
+        } => {
+            let mut item = MemoryItem::new(content, category.parse()?);
+            item.tags = tags;
+            item.pinned = pin;
+            let id = manager.add(item)?;
+            println!("Saved memory {id}");
+        }
+
+        MemoryCommands::Search { query, format } => {
+            print_memories(&manager.search(&query)?, &format)?;
+        }
+
+        MemoryCommands::Rm { id } => {
+            if manager.delete(&id)? {
+                println!("Deleted memory {id}");
+            } else {
+                anyhow::bail!("memory not found: {id}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_auth_command(command: AuthCommands) -> anyhow::Result<()> {
+    use omni_cli::core::approvals::ApprovalManager;
+
+    match command {
+        AuthCommands::Login { provider } => {
+            let config = Config::load()?;
+            if !config.agent.providers.contains_key(&provider) {
+                anyhow::bail!("unknown provider '{provider}', check [agent.providers] config");
+            }
+            let key = read_secret(&format!("API key for {provider}: "))?;
+            if key.is_empty() {
+                anyhow::bail!("no API key entered");
+            }
+            let source = config.agent.store_api_key(&provider, &key)?;
+            println!("Saved API key for {provider} in the {source}");
+        }
+
+        AuthCommands::Logout { provider, all, yes } => {
+            run_auth_logout(provider, all, yes)?;
+        }
+
+        AuthCommands::Status { probe, format } => {
+            run_auth_status(probe, &format).await?;
+        }
+
+        AuthCommands::Approvals { command } => {
+            let manager = ApprovalManager::for_current_project()?;
+            match command {
+                ApprovalCommands::List { format } => {
+                    let approvals = manager.list()?;
+                    if format == "json" {
+                        println!("{}", serde_json::to_string_pretty(&approvals)?);
+                    } else if approvals.is_empty() {
+                        println!("No saved approvals for this project");
+                    } else {
+                        println!("{:<14} {:<17} Target", "Tool", "Approved");
+                        println!("{}", "-".repeat(60));
+                        for approval in approvals {
+                            let approved =
+                                chrono::DateTime::from_timestamp_millis(approval.approved_at)
+                                    .map_or_else(
+                                        || "Unknown".to_string(),
+                                        |dt| dt.format("%Y-%m-%d %H:%M").to_string(),
+                                    );
+                            let target = approval.target.as_deref().unwrap_or("(any)");
+                            println!("{:<14} {approved:<17} {target}", approval.tool);
+                        }
+                    }
+                }
+
+                ApprovalCommands::Clear => {
+                    let count = manager.clear()?;
+                    println!("Removed {count} saved approval(s)");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// List where each provider's API key comes from, optionally probing them.
+async fn run_auth_status(probe: bool, format: &str) -> anyhow::Result<()> {
+    use omni_cli::core::models::{self, ProbeStatus};
+
+    let config = Config::load()?;
+    let mut providers = models::auth_status(&config.agent);
+
+    if probe {
+        let probes = providers.iter().map(|entry| async {
+            match models::probe_model(&config.agent, &entry.provider) {
+                Some(model) => models::probe_provider(&config.agent, &entry.provider, model).await,
+                None => ProbeStatus::Error("no model configured".to_string()),
+            }
+        });
+        let results = futures::future::join_all(probes).await;
+        for (entry, status) in providers.iter_mut().zip(results) {
+            entry.probe = Some(status);
+        }
+    }
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&providers)?);
+        return Ok(());
+    }
+
+    println!("{:<14} {:<30} {:<16} Probe", "Provider", "Source", "Key");
+    println!("{}", "-".repeat(72));
+    for entry in &providers {
+        // Mark the default provider
+        let name = if entry.active {
+            format!("{} *", entry.provider)
+        } else {
+            entry.provider.clone()
+        };
+        let source = match (&entry.source, &entry.keychain_unavailable) {
+            (Some(source), _) => source.clone(),
+            (None, _) if !entry.requires_key => "not required".to_string(),
+            (None, Some(_)) => "missing (keyring unavailable)".to_string(),
+            (None, None) => "missing".to_string(),
+        };
+        let key = entry.key.as_deref().unwrap_or("-");
+        let probe = entry
+            .probe
+            .as_ref()
+            .map_or_else(|| "-".to_string(), ProbeStatus::label);
+        println!("{name:<14} {source:<30} {key:<16} {probe}");
+    }
+    Ok(())
+}
+
+/// Delete saved API keys for one provider, a picked one, or all of them.
+fn run_auth_logout(provider: Option<String>, all: bool, yes: bool) -> anyhow::Result<()> {
+    let config = Config::load()?;
+    let saved = config.agent.providers_with_saved_keys();
+
+    let targets = match provider {
+        Some(provider) => vec![provider],
+        None if saved.is_empty() => {
+            println!("No saved API keys");
+            return Ok(());
+        }
+        None if all => saved,
+        None => {
+            for (i, name) in saved.iter().enumerate() {
+                println!("  {}. {name}", i + 1);
+            }
+            print!("Log out of which provider? [1-{}] ", saved.len());
+            std::io::stdout().flush()?;
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            let picked = answer
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| saved.get(i))
+                .ok_or_else(|| anyhow::anyhow!("no provider selected"))?;
+            vec![picked.clone()]
+        }
+    };
+
+    if !yes
+        && !confirm(&format!(
+            "Delete saved API keys for {}?",
+            targets.join(", ")
+        ))
+    {
+        println!("Cancelled");
+        return Ok(());
+    }
+    for name in targets {
+        let removed = config.agent.remove_api_key(&name)?;
+        if removed.is_empty() {
+            println!("No saved API key for {name}");
+        } else {
+            let from: Vec<String> = removed.iter().map(ToString::to_string).collect();
+            println!("Removed API key for {name} from the {}", from.join(" and "));
+        }
+    }
+    Ok(())
+}
+
+/// Read a secret line, without echoing it when stdin is a terminal.
+fn read_secret(prompt: &str) -> anyhow::Result<String> {
+    use std::io::{IsTerminal, Write};
+
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+
+    let mut stderr = std::io::stderr();
+    write!(stderr, "{prompt}")?;
+    stderr.flush()?;
+
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        return Ok(line.trim().to_string());
+    }
+
+    crossterm::terminal::enable_raw_mode()?;
+    let mut secret = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(anyhow::anyhow!("cancelled"));
+                }
+                KeyCode::Char(c) => secret.push(c),
+                KeyCode::Backspace => {
+                    secret.pop();
+                }
+                _ => {}
+            },
+            // Pasted keys arrive as one event when bracketed paste is on
+            Ok(Event::Paste(text)) => secret.push_str(&text),
+            Ok(_) => {}
+            Err(e) => break Err(e.into()),
+        }
+    };
+    crossterm::terminal::disable_raw_mode()?;
+    writeln!(stderr)?;
+    result.map(|()| secret.trim().to_string())
+}
+
+fn print_memories(
+    items: &[omni_cli::core::memory::MemoryItem],
+    format: &str,
+) -> anyhow::Result<()> {
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(items)?);
+        return Ok(());
+    }
+    if items.is_empty() {
+        println!("No memories for this project");
+        return Ok(());
+    }
+
+    println!("{:<31} {:<13} {:<7} Content", "ID", "Category", "Pinned");
+    println!("{}", "-".repeat(100));
+    for item in items {
+        let first_line = item.content.lines().next().unwrap_or_default();
+        let mut preview: String = first_line.chars().take(45).collect();
+        if preview.len() < item.content.len() {
+            preview.push_str("...");
+        }
+        println!(
+            "{:<31} {:<13} {:<7} {preview}",
+            item.id,
+            item.category.to_string(),
+            if item.pinned { "yes" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+/// Create a worktree for a session started with `--worktree`.
+fn create_session_worktree(
+    name: Option<&str>,
+) -> anyhow::Result<omni_cli::core::worktree::WorktreeInfo> {
+    use omni_cli::core::{project::Project, worktree::WorktreeManager};
+
+    let project = Project::detect(&std::env::current_dir()?)?;
+    let worktree = WorktreeManager::for_project(&project)?.create(name)?;
+    eprintln!(
+        "Working in worktree {} ({}) at {}",
+        worktree.name,
+        worktree.branch,
+        worktree.directory.display()
+    );
+    Ok(worktree)
+}
+
+/// Offer to remove a session's worktree once the session closes.
+fn offer_worktree_removal(worktree: &omni_cli::core::worktree::WorktreeInfo) {
+    use std::io::IsTerminal as _;
+
+    use omni_cli::core::{project::Project, worktree::WorktreeManager};
+
+    let keep = !std::io::stdin().is_terminal()
+        || !confirm(&format!(
+            "Remove worktree {} and delete branch {}?",
+            worktree.name, worktree.branch
+        ));
+    if keep {
+        eprintln!(
+            "Worktree kept at {} (remove with `omni worktree remove {}`)",
+            worktree.directory.display(),
+            worktree.name
+        );
+        return;
+    }
+
+    let result = std::env::current_dir()
+        .map_err(anyhow::Error::from)
+        .and_then(|cwd| Project::detect(&cwd))
+        .and_then(|project| WorktreeManager::for_project(&project))
+        .and_then(|manager| manager.remove(&worktree.directory));
+    match result {
+        Ok(()) => eprintln!("Removed worktree {}", worktree.name),
+        Err(e) => eprintln!("Failed to remove worktree {}: {e}", worktree.name),
+    }
+}
+
+fn run_worktree_command(command: WorktreeCommands) -> anyhow::Result<()> {
+    use omni_cli::core::{project::Project, worktree::WorktreeManager};
+
+    let project = Project::detect(&std::env::current_dir()?)?;
+    let manager = WorktreeManager::for_project(&project)?;
+
+    match command {
+        WorktreeCommands::List => {
+            let worktrees = manager.list()?;
+            if worktrees.is_empty() {
+                println!("No worktrees for this project");
+                return Ok(());
+            }
+            println!("{:<24} {:<30} Directory", "Name", "Branch");
+            println!("{}", "-".repeat(80));
+            for worktree in worktrees {
+                println!(
+                    "{:<24} {:<30} {}",
+                    worktree.name,
+                    worktree.branch,
+                    worktree.directory.display()
+                );
+            }
+        }
+
+        WorktreeCommands::Create { name } => {
+            let worktree = manager.create(name.as_deref())?;
+            println!("Created worktree {}", worktree.name);
+            println!("  Branch:    {}", worktree.branch);
+            println!("  Directory: {}", worktree.directory.display());
+        }
+
+        WorktreeCommands::Remove { name, yes } => {
+            let worktree = manager.find(&name)?;
+            if !yes
+                && !confirm(&format!(
+                    "Remove {} and delete branch {}?",
+                    worktree.directory.display(),
+                    worktree.branch
+                ))
+            {
+                println!("Remove cancelled");
+                return Ok(());
+            }
+            manager.remove(&worktree.directory)?;
+            println!("Removed worktree {}", worktree.name);
+        }
+
+        WorktreeCommands::Reset { name, yes } => {
+            let worktree = manager.find(&name)?;
+            println!(
+                "Warning: this resets {} to the default branch and runs `git clean -fdx`,",
+                worktree.name
+            );
+            println!("deleting all uncommitted changes and untracked or ignored files.");
+            if !yes && !confirm("Reset worktree?") {
+                println!("Reset cancelled");
+                return Ok(());
+            }
+            manager.reset(&worktree.directory)?;
+            println!("Reset worktree {}", worktree.name);
+        }
+    }
+
+    Ok(())
+}
+
+/// List, diff or restore the current project's snapshots.
+fn run_snapshot_command(command: SnapshotCommands) -> anyhow::Result<()> {
+    use omni_cli::core::snapshot::{RestoreOutcome, SnapshotManager};
+
+    let manager = SnapshotManager::for_current_project()?;
+    let short = |hash: &str| hash.get(..12).unwrap_or(hash).to_string();
+
+    match command {
+        SnapshotCommands::List { limit } => {
+            let history = manager.history()?;
+            if history.is_empty() {
+                println!("No snapshots for this project");
+                return Ok(());
+            }
+            println!("{:<14} Created", "Hash");
+            println!("{}", "-".repeat(34));
+            for entry in history.iter().take(limit) {
+                let when = chrono::DateTime::from_timestamp_millis(entry.created).map_or_else(
+                    || "Unknown".to_string(),
+                    |dt| {
+                        dt.with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M:%S")
+                            .to_string()
+                    },
+                );
+                println!("{:<14} {when}", short(&entry.hash));
+            }
+        }
+
+        SnapshotCommands::Diff { hash } => {
+            let hash = match hash {
+                Some(prefix) => manager.resolve(&prefix)?,
+                None => manager
+                    .history()?
+                    .into_iter()
+                    .next()
+                    .map(|entry| entry.hash)
+                    .ok_or_else(|| anyhow::anyhow!("no snapshots for this project"))?,
+            };
+            let diff = manager.diff(&hash)?;
+            if diff.is_empty() {
+                println!("No changes since snapshot {}", short(&hash));
             } else {
-                // Table format - show slug for easy CLI use
-                println!("{:<20} {:<30} Created", "Slug", "Title");
-                println!("{}", "-".repeat(70));
-                for session in sessions {
-                    let created = chrono::DateTime::from_timestamp_millis(session.time.created)
-                        .map_or_else(
-                            || "Unknown".to_string(),
-                            |dt| dt.format("%Y-%m-%d %H:%M").to_string(),
-                        );
-                    let title: String = session.title.chars().take(28).collect();
-                    println!("{:<20} {:<30} {}", session.slug, title, created);
+                println!("{diff}");
+            }
+        }
+
+        SnapshotCommands::Restore { hash, force } => {
+            use std::io::IsTerminal as _;
+
+            let hash = manager.resolve(&hash)?;
+            let interactive = std::io::stdin().is_terminal();
+            let outcome = manager.restore_confirmed(&hash, |diff| {
+                if force {
+                    return true;
+                }
+                println!("{diff}\n");
+                interactive
+                    && confirm(&format!(
+                        "Restore snapshot {}? The changes above will be overwritten.",
+                        short(&hash)
+                    ))
+            })?;
+            match outcome {
+                RestoreOutcome::Restored => println!("Restored snapshot {}", short(&hash)),
+                RestoreOutcome::Unchanged => {
+                    println!("Working tree already matches snapshot {}", short(&hash));
                 }
+                RestoreOutcome::Declined if !interactive => {
+                    anyhow::bail!(
+                        "restore needs confirmation; rerun with --force to restore without a terminal"
+                    );
+                }
+                RestoreOutcome::Declined => println!("Restore cancelled"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the environment diagnostic report.
+fn run_doctor() -> anyhow::Result<()> {
+    use omni_cli::core::doctor::{self, CheckStatus};
+
+    let checks = doctor::run_checks();
+    let mut section = "";
+    for check in &checks {
+        if check.section != section {
+            section = check.section;
+            println!("\n{section}");
+        }
+        println!(
+            "  [{:>4}] {}: {}",
+            check.status.label(),
+            check.name,
+            check.detail
+        );
+    }
+
+    let failures = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Fail)
+        .count();
+    println!();
+    if failures > 0 {
+        anyhow::bail!("{failures} critical problem(s) found");
+    }
+    println!("No critical problems found.");
+    Ok(())
+}
+
+/// Report semantic problems in the configuration.
+fn run_config_validate() -> anyhow::Result<()> {
+    use omni_cli::config::{Severity, validate};
+
+    let config = Config::load()?;
+    let findings = validate(&config);
+    for finding in &findings {
+        println!("{}: {}", finding.severity.label(), finding.message);
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    let warnings = findings.len() - errors;
+    if errors > 0 {
+        anyhow::bail!("{errors} error(s), {warnings} warning(s) in configuration");
+    }
+    if warnings > 0 {
+        println!("\nConfiguration is valid with {warnings} warning(s).");
+    } else {
+        println!("Configuration is valid.");
+    }
+    Ok(())
+}
+
+/// List configured models, optionally probing each provider.
+async fn run_models(provider: Option<&str>, probe: bool, format: &str) -> anyhow::Result<()> {
+    use omni_cli::core::models;
+
+    let config = Config::load()?;
+    let mut providers = models::models_by_provider(&config.agent, provider)?;
+
+    if probe {
+        let probes = providers.iter().map(|entry| {
+            let model = entry.models.first().unwrap_or(&config.agent.model);
+            models::probe_provider(&config.agent, &entry.provider, model)
+        });
+        let results = futures::future::join_all(probes).await;
+        for (entry, status) in providers.iter_mut().zip(results) {
+            entry.probe = Some(status);
+        }
+    }
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&providers)?);
+        return Ok(());
+    }
+
+    for entry in &providers {
+        match &entry.probe {
+            Some(status) => println!("{} [{}]", entry.provider, status.label()),
+            None => println!("{}", entry.provider),
+        }
+        for model in &entry.models {
+            let active = if entry.provider == config.agent.provider && *model == config.agent.model
+            {
+                " (default)"
+            } else {
+                ""
+            };
+            println!("  {model}{active}");
+        }
+    }
+    Ok(())
+}
+
+/// Run a single agent turn, streaming output to stdout.
+///
+/// With `json`, nothing is streamed and a [`ChatSummary`] is printed once the
+/// turn ends, with an `error` field if the run failed. With `read_only`,
+/// tools that would change anything are denied.
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
+async fn run_agent(
+    prompt: &str,
+    target: SessionTarget,
+    plan: bool,
+    append_system: Vec<String>,
+    show_tools: &str,
+    json: bool,
+    dry_run: bool,
+    working_dir: Option<std::path::PathBuf>,
+    read_only: bool,
+) -> anyhow::Result<()> {
+    let mut summary = ChatSummary::default();
+    let result = run_agent_turn(
+        prompt,
+        target,
+        plan,
+        append_system,
+        show_tools,
+        json.then_some(&mut summary),
+        dry_run,
+        working_dir,
+        read_only,
+    )
+    .await;
+
+    if json {
+        if let Err(e) = &result {
+            summary.error = Some(format!("{e:#}"));
+        }
+        // A successful dry run has already printed its request
+        if !(dry_run && result.is_ok()) {
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        }
+    }
+    result
+}
+
+/// Run the turn for [`run_agent`], recording into `summary` instead of
+/// streaming when one is given.
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
+async fn run_agent_turn(
+    prompt: &str,
+    target: SessionTarget,
+    plan: bool,
+    append_system: Vec<String>,
+    show_tools: &str,
+    mut summary: Option<&mut ChatSummary>,
+    dry_run: bool,
+    working_dir: Option<std::path::PathBuf>,
+    read_only: bool,
+) -> anyhow::Result<()> {
+    let config = Config::load()?;
+    let mut agent = omni_cli::core::Agent::from_config(&config)?;
+
+    // Enable sessions with target (a dry run never creates a new session)
+    if !(dry_run && matches!(target, SessionTarget::New)) {
+        if let Err(e) = agent.enable_sessions_with_target(target) {
+            tracing::warn!("failed to enable sessions: {e}");
+        }
+    }
+
+    if let Some(dir) = working_dir {
+        agent.set_working_dir(dir);
+    }
+
+    for text in append_system {
+        agent.append_system(text);
+    }
+
+    if plan {
+        agent.switch_mode(AgentMode::Plan, None);
+    }
+
+    if read_only {
+        use omni_cli::core::agent::{PermissionClient, permission::AgentPermissions};
+
+        // No actor listens, so anything that would prompt is refused
+        let (permission_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        agent.set_permission_client(PermissionClient::with_presets(
+            "piped".to_string(),
+            permission_tx,
+            AgentPermissions::read_only(),
+        ));
+    }
+
+    if dry_run {
+        return print_request_preview(&agent, prompt);
+    }
+
+    // First Ctrl+C cancels the turn gracefully, second forces exit
+    let cancel = CancellationToken::new();
+    agent.set_cancellation_token(cancel.clone());
+    let interrupt = tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\nCancelling... (press Ctrl+C again to force exit)");
+            cancel.cancel();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        }
+    });
+
+    let result = agent
+        .chat_with_events(prompt, |event| {
+            if let Some(summary) = summary.as_deref_mut() {
+                print_status_event(&event);
+                summary.record(event);
+            } else {
+                print_chat_event(event, show_tools);
+                std::io::stdout().flush().ok();
+            }
+        })
+        .await;
+    interrupt.abort();
+
+    let cancelled = matches!(result, Err(AgentError::Cancelled));
+    if cancelled {
+        eprintln!("[cancelled]");
+    }
+    if let Some(summary) = summary {
+        summary.session_id = agent.session_id().map(str::to_string);
+        summary.cancelled = cancelled;
+    } else {
+        println!();
+    }
+    match result {
+        Ok(_) | Err(AgentError::Cancelled) => Ok(()),
+        Err(e) => Err(anyhow::anyhow!("{e}")),
+    }
+}
+
+/// Print a chat event to stdout for CLI agent runs.
+///
+/// `show_tools` controls tool narration: `none`, `summary` (one line per tool),
+/// or `full` (invocation plus output).
+fn print_chat_event(event: ChatEvent, show_tools: &str) {
+    match event {
+        ChatEvent::Text(text) => print!("{text}"),
+        ChatEvent::ToolCall {
+            name,
+            invocation,
+            output,
+            is_error,
+        } if show_tools != "none" => {
+            let status = if is_error { " (error)" } else { "" };
+            println!("\n[{name}] {invocation}{status}");
+            if show_tools == "full" && !output.is_empty() {
+                println!("{}", output.trim_end());
+            }
+        }
+        event => print_status_event(&event),
+    }
+}
+
+/// Print connection and compaction notices to stderr.
+fn print_status_event(event: &ChatEvent) {
+    match event {
+        ChatEvent::Reconnecting {
+            attempt,
+            max_attempts,
+        } => eprintln!("\n[connection dropped, reconnecting {attempt}/{max_attempts}...]"),
+        ChatEvent::Compacted { messages } => {
+            eprintln!("\n[compacted history: {messages} messages summarized]");
+        }
+        _ => {}
+    }
+}
+
+/// Export one session, or all of them when no session is given.
+fn run_export(
+    session_id: Option<String>,
+    format: &str,
+    output: Option<String>,
+    dir: Option<String>,
+    filter: omni_cli::core::session::ExportFilter,
+) -> anyhow::Result<()> {
+    use omni_cli::core::session::{ExportFormat, SessionManager};
+
+    let manager = SessionManager::for_current_project()?;
+    let format: ExportFormat = format.parse()?;
+
+    let Some(session_id) = session_id else {
+        let dir = dir.ok_or_else(|| anyhow::anyhow!("--all requires --dir"))?;
+        let written = manager.export_all_to_dir(std::path::Path::new(&dir), format, &filter)?;
+        println!("Exported {} session(s) to {dir}", written.len());
+        return Ok(());
+    };
+
+    let session = manager.find_session(&session_id)?;
+    if let Some(dir) = dir {
+        let path =
+            std::path::Path::new(&dir).join(format!("{}.{}", session.slug, format.extension()));
+        manager.export_to_file(&session.id, &path, format, &filter)?;
+        println!("Exported session to {}", path.display());
+    } else if let Some(path) = output {
+        manager.export_to_file(&session.id, std::path::Path::new(&path), format, &filter)?;
+        println!("Exported session to {path}");
+    } else {
+        println!(
+            "{}",
+            manager.export_formatted(&session.id, format, &filter)?
+        );
+    }
+    Ok(())
+}
+
+/// Print the request an agent turn would send, warning about secret-looking content.
+fn print_request_preview(agent: &omni_cli::core::Agent, prompt: &str) -> anyhow::Result<()> {
+    let request = agent.preview_request(prompt);
+    let tools: Vec<&str> = request
+        .tools
+        .iter()
+        .flatten()
+        .map(|tool| tool.name.as_str())
+        .collect();
+    let preview = serde_json::json!({
+        "provider": agent.provider_name(),
+        "model": request.model,
+        "max_tokens": request.max_tokens,
+        "system": request.system,
+        "message_count": request.messages.len(),
+        "tools": tools,
+    });
+
+    let messages = serde_json::to_string(&request.messages)?;
+    let has_secret = omni_cli::core::secret::contains_secrets(&messages)
+        || request
+            .system
+            .as_deref()
+            .is_some_and(omni_cli::core::secret::contains_secrets);
+    if has_secret {
+        eprintln!("warning: the request contains content that looks like a secret");
+    }
+
+    println!("{}", serde_json::to_string_pretty(&preview)?);
+    Ok(())
+}
+
+/// Regenerate a session's title, replacing any existing one.
+async fn run_retitle(id_or_slug: &str) -> anyhow::Result<()> {
+    use omni_cli::core::session::SessionManager;
+
+    let session = SessionManager::for_current_project()?.find_session(id_or_slug)?;
+
+    let mut agent = omni_cli::core::Agent::from_config(&Config::load()?)?;
+    agent
+        .enable_sessions_with_target(SessionTarget::Specific(session.id))
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    let title = agent
+        .regenerate_title()
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    println!("Retitled {}: {title}", session.slug);
+    Ok(())
+}
+
+/// Print sessions as a table or JSON.
+fn print_sessions(
+    sessions: &[omni_cli::core::session::Session],
+    format: &str,
+    now: i64,
+) -> anyhow::Result<()> {
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(sessions)?);
+    } else {
+        // Table format - show slug for easy CLI use, brighter for recent sessions
+        use omni_cli::core::session::Recency;
+        use std::io::IsTerminal;
+
+        let use_color = omni_cli::core::color::enabled() && std::io::stdout().is_terminal();
+        let format_time = |ts: i64| {
+            chrono::DateTime::from_timestamp_millis(ts).map_or_else(
+                || "Unknown".to_string(),
+                |dt| dt.format("%Y-%m-%d %H:%M").to_string(),
+            )
+        };
+
+        println!("{:<20} {:<30} {:<17} Created", "Slug", "Title", "Updated");
+        println!("{}", "-".repeat(88));
+        for session in sessions {
+            let title: String = session.title.chars().take(28).collect();
+            let row = format!(
+                "{:<20} {:<30} {:<17} {}",
+                session.slug,
+                title,
+                format_time(session.time.updated),
+                format_time(session.time.created)
+            );
+            if use_color {
+                let code = match session.time.recency(now) {
+                    Recency::Today => "1;97",
+                    Recency::ThisWeek => "0",
+                    Recency::Older => "2",
+                };
+                println!("\x1b[{code}m{row}\x1b[0m");
+            } else {
+                println!("{row}");
             }
         }
+    }
+    Ok(())
+}
+
+async fn handle_session_command(command: SessionCommands) -> anyhow::Result<()> {
+    use omni_cli::core::session::{ExportFormat, SessionManager};
+
+    let manager = SessionManager::for_current_project()?;
+
+    match command {
+        SessionCommands::List {
+            format,
+            limit,
+            since,
+            after,
+            before,
+        } => {
+            // Lower bound is the later of --since and --after
+            let now = chrono::Utc::now().timestamp_millis();
+            let since = since.map(|s| parse_duration(&s)).transpose()?.map(|secs| {
+                now.saturating_sub(i64::try_from(secs.saturating_mul(1000)).unwrap_or(i64::MAX))
+            });
+            let after = after.map(|s| parse_date(&s)).transpose()?;
+            let start = since.max(after);
+            let end = before.map(|s| parse_date(&s)).transpose()?;
+
+            let sessions = manager.list_sessions()?;
+            let sessions: Vec<_> = sessions
+                .into_iter()
+                .filter(|s| start.is_none_or(|start| s.time.updated >= start))
+                .filter(|s| end.is_none_or(|end| s.time.updated < end))
+                .take(limit)
+                .collect();
+
+            print_sessions(&sessions, &format, now)?;
+        }
+
+        SessionCommands::Search {
+            query,
+            format,
+            limit,
+        } => {
+            let mut sessions = manager.search_sessions(&query)?;
+            sessions.truncate(limit);
+            print_sessions(&sessions, &format, chrono::Utc::now().timestamp_millis())?;
+        }
 
         SessionCommands::Export {
             session_id,
             format,
             output,
+            filter,
         } => {
             // Resolve slug or ID to actual session ID
             let session = manager.find_session(&session_id)?;
-            let content = if format == "markdown" {
-                manager.export_to_markdown(&session.id)?
-            } else {
-                manager.export_to_json(&session.id)?
-            };
+            let format: ExportFormat = format.parse()?;
+            let content = manager.export_formatted(&session.id, format, &filter.filter())?;
 
             if let Some(path) = output {
                 std::fs::write(&path, &content)?;
@@ -197,16 +1304,39 @@ fn handle_session_command(command: SessionCommands) -> anyhow::Result<()> {
             }
         }
 
+        SessionCommands::Import { file } => {
+            let json = std::fs::read_to_string(&file)
+                .map_err(|e| anyhow::anyhow!("failed to read {file}: {e}"))?;
+            let imported = manager.import_from_json(&json)?;
+            println!(
+                "Imported \"{}\" as {} ({} messages, {} parts)",
+                imported.session.title, imported.session.slug, imported.messages, imported.parts
+            );
+        }
+
         SessionCommands::Share {
             session_id,
             expires,
+            password,
         } => {
             use omni_cli::core::session::ShareOptions;
 
             // Resolve slug or ID to actual session ID
             let session = manager.find_session(&session_id)?;
             let ttl_seconds = expires.map(|e| parse_duration(&e)).transpose()?;
-            let options = ShareOptions { ttl_seconds };
+            let password = if password {
+                let password = read_secret("Share password: ")?;
+                if password.is_empty() {
+                    anyhow::bail!("share password cannot be empty");
+                }
+                Some(password)
+            } else {
+                None
+            };
+            let options = ShareOptions {
+                ttl_seconds,
+                password,
+            };
 
             let share = manager.create_share(&session.id, options)?;
 
@@ -217,6 +1347,9 @@ fn handle_session_command(command: SessionCommands) -> anyhow::Result<()> {
             println!();
             println!("Access via API:");
             println!("  GET http://localhost:7890/api/share/{}", share.token);
+            if share.is_protected() {
+                println!("  (password required: send it in the X-Share-Password header)");
+            }
             println!();
             if let Some(expires_at) = share.expires_at {
                 let expires = chrono::DateTime::from_timestamp_millis(expires_at).map_or_else(
@@ -235,15 +1368,168 @@ fn handle_session_command(command: SessionCommands) -> anyhow::Result<()> {
             );
         }
 
+        SessionCommands::Shares { format } => {
+            // Stale shares would otherwise pile up forever
+            let purged = manager.cleanup_expired_shares()?;
+            if purged > 0 {
+                tracing::debug!("purged {purged} expired shares");
+            }
+
+            let shares = manager.list_shares()?;
+            if format == "json" {
+                let summaries: Vec<_> = shares
+                    .iter()
+                    .map(omni_cli::core::session::ShareToken::summary)
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&summaries)?);
+            } else if shares.is_empty() {
+                println!("No active shares");
+            } else {
+                println!(
+                    "{:<10} {:<20} {:<17} Accesses",
+                    "Token", "Session", "Expires"
+                );
+                println!("{}", "-".repeat(58));
+                for share in shares {
+                    // Show the slug when the session still exists
+                    let session = manager
+                        .get_session(&share.session_id)
+                        .map_or(share.session_id, |s| s.slug);
+                    let expires = share.expires_at.map_or_else(
+                        || "Never".to_string(),
+                        |ts| {
+                            chrono::DateTime::from_timestamp_millis(ts).map_or_else(
+                                || "Unknown".to_string(),
+                                |dt| dt.format("%Y-%m-%d %H:%M").to_string(),
+                            )
+                        },
+                    );
+                    println!(
+                        "{:<10} {:<20} {:<17} {}",
+                        share.token, session, expires, share.access_count
+                    );
+                }
+            }
+        }
+
+        SessionCommands::Fork { session_id, at } => {
+            let session = manager.find_session(&session_id)?;
+            let at = match at {
+                Some(id) => id,
+                None => manager
+                    .list_messages(&session.id)?
+                    .last()
+                    .map(|msg| msg.id().to_string())
+                    .ok_or_else(|| anyhow::anyhow!("session has no messages to fork"))?,
+            };
+            let fork = manager.fork_session(&session.id, &at)?;
+            println!(
+                "Forked {} into {} ({})",
+                session.slug, fork.slug, fork.title
+            );
+            println!("Resume it with `omni tui --session {}`", fork.slug);
+        }
+
         SessionCommands::Unshare { token, secret } => {
             manager.revoke_share(&token, &secret)?;
             println!("Share revoked");
         }
+
+        SessionCommands::Undo { session_id, force } => {
+            use std::io::IsTerminal as _;
+
+            use omni_cli::core::snapshot::SnapshotManager;
+
+            let session = match session_id {
+                Some(id) => manager.find_session(&id)?,
+                None => manager
+                    .list_sessions()?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("no sessions found"))?,
+            };
+            let Some(turn) = manager.last_turn_changes(&session.id)? else {
+                println!("Nothing to undo in \"{}\"", session.title);
+                return Ok(());
+            };
+
+            let files: Vec<_> = turn.files().into_iter().cloned().collect();
+            let project = omni_cli::core::project::Project::detect(&session.directory)?;
+            let snapshots = SnapshotManager::for_project(&project)?;
+
+            println!("Last turn of \"{}\" changed:", session.title);
+            for file in &files {
+                println!("  {}", file.display());
+            }
+            if let Some(before) = turn.patches.first() {
+                let diff = snapshots.diff_paths(&before.hash, &files)?;
+                if !diff.is_empty() {
+                    println!();
+                    println!("{diff}");
+                    println!();
+                }
+            }
+
+            // Reverting would also throw away edits made after the turn
+            let edited: Vec<_> = match &turn.after {
+                Some(after) => snapshots
+                    .patch(after)?
+                    .files
+                    .into_iter()
+                    .filter(|file| files.contains(file))
+                    .collect(),
+                None => Vec::new(),
+            };
+            if !edited.is_empty() {
+                let edited: Vec<_> = edited.iter().map(|f| f.display().to_string()).collect();
+                if !force {
+                    anyhow::bail!(
+                        "changed since the turn: {}\nRerun with --force to revert anyway and lose those edits",
+                        edited.join(", ")
+                    );
+                }
+                println!(
+                    "Discarding edits made since the turn: {}",
+                    edited.join(", ")
+                );
+            }
+            if !force && !std::io::stdin().is_terminal() {
+                anyhow::bail!(
+                    "undo needs confirmation; rerun with --force to revert without a terminal"
+                );
+            }
+            if !force && !confirm("Revert these files?") {
+                println!("Undo cancelled");
+                return Ok(());
+            }
+
+            snapshots.revert(&turn.patches)?;
+            manager.remove_turn_changes(&session.id, &turn.id)?;
+            println!("Reverted {} file(s)", files.len());
+        }
+
+        SessionCommands::Retitle { session_id } => {
+            run_retitle(&session_id).await?;
+        }
     }
 
     Ok(())
 }
 
+/// Parse a date (YYYY-MM-DD, as UTC midnight) or RFC 3339 timestamp to epoch millis.
+fn parse_date(s: &str) -> anyhow::Result<i64> {
+    let s = s.trim();
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.timestamp_millis());
+    }
+    let date = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("invalid date '{s}' (use YYYY-MM-DD or RFC 3339)"))?;
+    Ok(date
+        .and_time(chrono::NaiveTime::MIN)
+        .and_utc()
+        .timestamp_millis())
+}
+
 /// Parse a duration string (e.g., "1h", "7d") to seconds
 fn parse_duration(s: &str) -> anyhow::Result<u64> {
     let s = s.trim();
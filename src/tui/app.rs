@@ -1,12 +1,25 @@
 //! TUI application state.
 
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use rand::prelude::IndexedRandom;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use super::components::SessionListDialog;
-use super::message::{DisplayMessage, format_tool_invocation};
+use super::components::{
+    DropdownMode, MESSAGE_PADDING_X, OutputDialog, PersonaListDialog, SessionListDialog,
+    dropdown_mode, file_mention, message_height,
+};
+use super::edit;
+use super::file_picker::{FilePicker, list_project_files};
+use super::message::{DisplayMessage, Transcript, format_tool_invocation};
+use super::search::SearchState;
 use super::state::ViewState;
+use super::theme::Theme;
+use super::vim::Vim;
 
 /// ASCII art logo lines (main text).
 pub const LOGO_LINES: &[&str] = &[
@@ -43,13 +56,18 @@ pub const ECOSYSTEM_TIPS: &[&str] = &[
     "Friendly reminder: commit early, commit often, blame later",
 ];
 
-use crate::config::{AgentConfig, AgentPermissions, Config};
+/// How long a status notice stays visible
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+
+use crate::config::{AgentConfig, AgentPermissions, Config, SubmitKey};
 use crate::core::Agent;
 use crate::core::agent::{
-    AgentMode, AskUserResponse, InterfaceMessage, PermissionAction, PermissionContext,
-    PermissionResponse,
+    AgentMode, ApprovalStore, AskUserResponse, CompactionSummary, InterfaceMessage,
+    PermissionAction, PermissionClient, PermissionContext, PermissionMessage, PermissionResponse,
+    PlanPhase, ReasoningEffort,
 };
-use crate::core::session::{SessionManager, SessionTarget};
+use crate::core::session::{SessionManager, SessionTarget, TurnChanges};
+use crate::core::shell::CommandPreview;
 
 /// Active text selection state.
 #[derive(Debug, Clone)]
@@ -72,6 +90,8 @@ impl Selection {
 pub enum ChatMessage {
     /// Text chunk to append
     Text(String),
+    /// Reasoning chunk (shown as activity status)
+    Reasoning(String),
     /// Tool starting (for activity status)
     ToolStart { name: String },
     /// Tool invocation with name, args, output, and error status
@@ -87,6 +107,14 @@ pub enum ChatMessage {
         output_tokens: u32,
         cost_usd: f64,
     },
+    /// Plan-mode workflow phase changed
+    PlanPhase(Option<PlanPhase>),
+    /// Provider stream dropped and is being retried
+    Reconnecting { attempt: u32, max_attempts: u32 },
+    /// Older history was summarized to free context
+    Compacted { messages: usize },
+    /// `/compact` finished (`None` if there was too little history)
+    ManualCompacted(Option<CompactionSummary>),
     /// Chat completed, returning the agent
     Done(Agent),
     /// Error occurred, returning the agent
@@ -103,8 +131,10 @@ pub struct ActivePermissionDialog {
     pub action: PermissionAction,
     /// Context for display.
     pub context: PermissionContext,
-    /// Currently selected button (0=Allow, 1=Session, 2=Deny).
+    /// Currently selected button (0=Allow, 1=Session, 2=Always, 3=Deny).
     pub selected: usize,
+    /// Predicted effect of a shell command, when one can be worked out safely.
+    pub preview: Option<CommandPreview>,
 }
 
 /// Active `ask_user` dialog state.
@@ -123,11 +153,24 @@ pub struct ActiveAskUserDialog {
     pub cursor: usize,
 }
 
+/// An earlier prompt being edited before it is resent as a fork.
+#[derive(Debug, Clone)]
+pub struct PromptEdit {
+    /// ID the prompt was sent to the agent as.
+    pub prompt_id: String,
+    /// Input that was being typed before editing began.
+    pub draft: String,
+}
+
 /// Currently active dialog, if any.
 pub enum ActiveDialog {
     Permission(ActivePermissionDialog),
     AskUser(ActiveAskUserDialog),
     SessionList(SessionListDialog),
+    PersonaList(PersonaListDialog),
+    Output(OutputDialog),
+    /// Files the last turn changed, reverted once confirmed
+    ConfirmUndo(OutputDialog, TurnChanges),
 }
 
 /// Application state for the TUI.
@@ -163,6 +206,33 @@ pub struct App {
     /// Selected placeholder for the input prompt.
     pub placeholder: &'static str,
 
+    /// Key combination that submits the prompt.
+    pub submit_key: SubmitKey,
+
+    /// Keep the model chosen for `/regenerate <model>` instead of reverting.
+    pub regenerate_keeps_model: bool,
+
+    /// Model to restore once a `/regenerate <model>` turn finishes.
+    pub pending_model_revert: Option<String>,
+
+    /// Earlier prompt loaded into the input for editing.
+    pub prompt_edit: Option<PromptEdit>,
+
+    /// In-conversation search, while open.
+    pub search: Option<SearchState>,
+
+    /// Colors from `[tui.theme]`.
+    pub theme: Theme,
+
+    /// Modal editing state, when `[tui] vim_mode` is on.
+    pub vim: Option<Vim>,
+
+    /// Fold runs of consecutive tool calls into summary lines.
+    pub collapse_tools: bool,
+
+    /// Minimum run length folded when `collapse_tools` is on.
+    pub collapse_tools_threshold: usize,
+
     /// The agent (if configured).
     pub agent: Option<Agent>,
 
@@ -172,6 +242,9 @@ pub struct App {
     /// Active dialog, if any.
     pub active_dialog: Option<ActiveDialog>,
 
+    /// Prompts waiting for the active one to be answered.
+    queued_dialogs: VecDeque<ActiveDialog>,
+
     /// Receiver for interface messages from permission system.
     pub interface_rx: Option<mpsc::UnboundedReceiver<InterfaceMessage>>,
 
@@ -190,11 +263,14 @@ pub struct App {
     /// Sender for `ask_user` responses.
     pub ask_user_response_tx: Option<mpsc::UnboundedSender<(uuid::Uuid, AskUserResponse)>>,
 
+    /// Where "always allow" answers are saved across restarts.
+    pub approval_store: Option<Arc<dyn ApprovalStore>>,
+
     /// Current view state (Welcome or Session).
     pub view_state: ViewState,
 
     /// Conversation messages for display.
-    pub messages: Vec<DisplayMessage>,
+    pub messages: Transcript,
 
     /// Currently streaming assistant text (accumulated before adding to messages).
     pub streaming_text: String,
@@ -211,9 +287,18 @@ pub struct App {
     /// Session cost in USD (cumulative).
     pub session_cost: f64,
 
+    /// Tokens in the context as of the latest response (prompt plus output).
+    pub context_tokens: u32,
+
+    /// Context window of the active model, kept while the agent is busy.
+    context_window: Option<u32>,
+
     /// Current agent mode (Build or Plan).
     pub agent_mode: AgentMode,
 
+    /// Current plan-mode workflow phase (only set in plan mode).
+    pub plan_phase: Option<PlanPhase>,
+
     /// Active text selection, if any.
     pub selection: Option<Selection>,
 
@@ -229,17 +314,35 @@ pub struct App {
     /// Calculated max scroll for message list.
     pub max_message_scroll: u16,
 
+    /// Rows of the message list on screen (updated on render).
+    pub message_area_height: u16,
+
     /// Whether command dropdown is visible.
     pub show_command_dropdown: bool,
 
     /// Currently selected command index in dropdown.
     pub command_selection: usize,
 
+    /// Project files for the `@` mention picker, listed when a mention starts.
+    file_picker: Option<FilePicker>,
+
+    /// Most files listed for the `@` mention picker.
+    file_picker_max_files: usize,
+
     /// Agent configuration for permission presets.
     pub agent_config: AgentConfig,
 
     /// Current activity status (e.g., "Using Bash..." or "Thinking...")
     pub activity_status: Option<String>,
+
+    /// Short-lived status shown while idle, with when it was set.
+    notice: Option<(String, Instant)>,
+
+    /// Cancels the in-flight response.
+    pub chat_cancel: Option<CancellationToken>,
+
+    /// Whether Esc was pressed once during the current response.
+    pub cancel_armed: bool,
 }
 
 impl Default for App {
@@ -252,18 +355,18 @@ impl App {
     /// Create a new application state.
     #[must_use]
     pub fn new() -> Self {
-        Self::with_session_target(SessionTarget::default())
+        Self::with_session_target(SessionTarget::default(), None)
     }
 
     /// Create a new application state with a specific session target.
+    ///
+    /// `initial_mode` overrides the persisted agent mode when set.
     #[must_use]
-    pub fn with_session_target(target: SessionTarget) -> Self {
+    pub fn with_session_target(target: SessionTarget, initial_mode: Option<AgentMode>) -> Self {
         let config = Config::load().unwrap_or_default();
         let model = config.agent.model.clone();
 
-        let mut agent = config.agent.create_provider().ok().map(|provider| {
-            Agent::with_context(provider, &config.agent.model, config.agent.max_tokens, None)
-        });
+        let mut agent = Agent::from_config(&config).ok();
 
         // Track if we're resuming a session
         let mut session_resumed = false;
@@ -290,14 +393,16 @@ impl App {
             }
         }
 
-        // Load persisted mode and apply to agent
-        let persisted_mode = crate::config::Config::load_mode();
+        // Load persisted mode (or explicit initial mode) and apply to agent
+        let persisted_mode = initial_mode.unwrap_or_else(crate::config::Config::load_mode);
         if let Some(ref mut a) = agent {
             if persisted_mode != AgentMode::default() {
                 a.switch_mode(persisted_mode, None);
             }
         }
 
+        let plan_phase = agent.as_ref().and_then(Agent::plan_phase);
+
         // Pick a random tagline, tip, and placeholder
         let tagline = TAGLINES
             .choose(&mut rand::rng())
@@ -328,6 +433,8 @@ impl App {
             String::new()
         };
 
+        super::components::set_syntax_highlight(config.tui.syntax_highlight);
+
         // If resuming with messages, start in session view
         let (view_state, show_welcome) = if session_resumed && !display_messages.is_empty() {
             (ViewState::Session, false)
@@ -346,29 +453,136 @@ impl App {
             tagline,
             tip,
             placeholder,
+            submit_key: config.tui.submit_key,
+            regenerate_keeps_model: config.tui.regenerate_keeps_model,
+            pending_model_revert: None,
+            prompt_edit: None,
+            search: None,
+            theme: Theme::from_config(&config.tui.theme),
+            vim: config.tui.vim_mode.then(Vim::default),
+            collapse_tools: config.tui.collapse_tools,
+            collapse_tools_threshold: config.tui.collapse_tools_threshold,
             agent,
             chat_rx: None,
             active_dialog: None,
+            queued_dialogs: VecDeque::new(),
             interface_rx: None,
             permission_response_tx: None,
             ask_user_response_tx: None,
+            approval_store: None,
             view_state,
-            messages: display_messages,
+            messages: display_messages.into(),
             streaming_text: String::new(),
             message_scroll: 0,
             model,
             session_tokens: (0, 0),
             session_cost: 0.0,
+            context_tokens: 0,
+            context_window: None,
             agent_mode: persisted_mode,
+            plan_phase,
             selection: None,
             selected_text: String::new(),
             term_width: 80,
             term_height: 24,
             max_message_scroll: 0,
+            message_area_height: 0,
             show_command_dropdown: false,
             command_selection: 0,
+            file_picker: None,
+            file_picker_max_files: config.tui.file_picker_max_files,
             agent_config: config.agent,
             activity_status: None,
+            notice: None,
+            chat_cancel: None,
+            cancel_armed: false,
+        }
+    }
+
+    /// Handle Esc during a response: the first press arms, the second cancels.
+    pub fn press_cancel(&mut self) {
+        if self.cancel_armed {
+            self.cancel_chat();
+        } else if self.chat_cancel.is_some() {
+            self.cancel_armed = true;
+            self.activity_status = Some("Press Esc again to cancel".to_string());
+        }
+    }
+
+    /// Show a status message for a few seconds.
+    pub fn set_notice(&mut self, message: impl Into<String>) {
+        self.notice = Some((message.into(), Instant::now()));
+    }
+
+    /// The status message, if it hasn't expired.
+    #[must_use]
+    pub fn notice(&self) -> Option<&str> {
+        self.notice
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < NOTICE_DURATION)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// Copy the selected transcript text to the system clipboard.
+    pub fn copy_selection(&mut self) {
+        if self.selected_text.is_empty() {
+            self.set_notice("Nothing selected");
+            return;
+        }
+
+        match crate::core::clipboard::write_text(&self.selected_text) {
+            Ok(()) => {
+                let lines = self.selected_text.lines().count();
+                let noun = if lines == 1 { "line" } else { "lines" };
+                self.set_notice(format!("Copied {lines} {noun}"));
+            }
+            Err(e) => {
+                tracing::warn!("failed to copy selection: {e}");
+                self.set_notice("Clipboard unavailable");
+            }
+        }
+    }
+
+    /// Step the reasoning effort through low, medium, high and off.
+    pub fn cycle_reasoning_effort(&mut self) {
+        let Some(agent) = self.agent.as_mut() else {
+            self.set_notice("Reasoning effort can change once the response finishes");
+            return;
+        };
+        let effort = ReasoningEffort::cycle(agent.reasoning_effort());
+        agent.set_reasoning_effort(effort);
+        self.set_notice(format!(
+            "Reasoning: {}",
+            effort.map_or("off", ReasoningEffort::as_str)
+        ));
+    }
+
+    /// Cancel the in-flight response.
+    ///
+    /// The agent stops the provider stream and comes back through the chat
+    /// channel with any partial text already persisted.
+    pub fn cancel_chat(&mut self) {
+        if let Some(token) = self.chat_cancel.take() {
+            token.cancel();
+            self.activity_status = Some("Cancelling...".to_string());
+        }
+        self.cancel_armed = false;
+    }
+
+    /// Build a permission client with the current presets and saved approvals.
+    #[must_use]
+    pub fn permission_client(
+        &self,
+        permission_tx: mpsc::UnboundedSender<PermissionMessage>,
+    ) -> PermissionClient {
+        let client = PermissionClient::with_presets(
+            "tui-session".to_string(),
+            permission_tx,
+            self.current_permissions(),
+        );
+        match &self.approval_store {
+            Some(store) => client.with_approval_store(Arc::clone(store)),
+            None => client,
         }
     }
 
@@ -390,6 +604,7 @@ impl App {
     pub fn sync_agent_mode(&mut self) {
         if let Some(agent) = &self.agent {
             self.agent_mode = agent.mode();
+            self.plan_phase = agent.plan_phase();
             // Persist mode to disk
             if let Err(e) = crate::config::Config::save_mode(self.agent_mode) {
                 tracing::warn!("failed to save mode: {e}");
@@ -415,108 +630,66 @@ impl App {
         }
 
         // Find start of word (skip trailing spaces, then skip word chars)
-        let before = &self.input[..self.cursor];
-        let trimmed = before.trim_end();
-
-        let word_start = trimmed
-            .rfind(|c: char| c.is_whitespace())
-            .map_or(0, |i| i + 1);
+        let word_start = edit::prev_word_start(&self.input, self.cursor);
 
         self.input.drain(word_start..self.cursor);
         self.cursor = word_start;
     }
 
-    /// Move cursor left by one character.
+    /// Move cursor left by one grapheme.
     pub fn move_left(&mut self) {
-        if self.cursor > 0 {
-            // Find previous char boundary
-            self.cursor = self.input[..self.cursor]
-                .char_indices()
-                .next_back()
-                .map_or(0, |(i, _)| i);
-        }
+        self.cursor = edit::prev_grapheme(&self.input, self.cursor);
     }
 
-    /// Move cursor right by one character.
+    /// Move cursor right by one grapheme.
     pub fn move_right(&mut self) {
-        if self.cursor < self.input.len() {
-            // Find next char boundary
-            self.cursor = self.input[self.cursor..]
-                .char_indices()
-                .nth(1)
-                .map_or(self.input.len(), |(i, _)| self.cursor + i);
-        }
+        self.cursor = edit::next_grapheme(&self.input, self.cursor);
     }
 
     /// Move cursor left by one word.
     pub fn move_word_left(&mut self) {
-        if self.cursor == 0 {
-            return;
-        }
-
-        let before = &self.input[..self.cursor];
-        let mut chars: Vec<(usize, char)> = before.char_indices().collect();
-        chars.reverse();
-
-        // Skip whitespace
-        while let Some(&(_, c)) = chars.first() {
-            if !c.is_whitespace() {
-                break;
-            }
-            chars.remove(0);
-        }
-
-        // Skip word characters
-        while let Some(&(i, c)) = chars.first() {
-            if c.is_whitespace() {
-                self.cursor = i + c.len_utf8();
-                return;
-            }
-            chars.remove(0);
-        }
-
-        self.cursor = 0;
+        self.cursor = edit::prev_word_start(&self.input, self.cursor);
     }
 
     /// Move cursor right by one word.
     pub fn move_word_right(&mut self) {
-        if self.cursor >= self.input.len() {
-            return;
-        }
+        self.cursor = edit::next_word_start(&self.input, self.cursor);
+    }
 
-        let after = &self.input[self.cursor..];
-        let mut chars = after.char_indices().peekable();
+    /// Move cursor to the start of the current line.
+    pub fn move_line_start(&mut self) {
+        self.cursor = edit::line_start(&self.input, self.cursor);
+    }
 
-        // Skip current word characters
-        while let Some(&(_, c)) = chars.peek() {
-            if c.is_whitespace() {
-                break;
-            }
-            chars.next();
-        }
+    /// Move cursor to the end of the current line.
+    pub fn move_line_end(&mut self) {
+        self.cursor = edit::line_end(&self.input, self.cursor);
+    }
 
-        // Skip whitespace
-        while let Some(&(_, c)) = chars.peek() {
-            if !c.is_whitespace() {
-                break;
-            }
-            chars.next();
+    /// Delete the grapheme under the cursor, leaving line breaks alone.
+    pub fn delete_char_under_cursor(&mut self) {
+        let next = edit::next_grapheme(&self.input, self.cursor);
+        if !self.input[self.cursor..next].contains('\n') {
+            self.input.drain(self.cursor..next);
         }
+    }
 
-        self.cursor = chars
-            .peek()
-            .map_or(self.input.len(), |&(i, _)| self.cursor + i);
+    /// Delete the current line, leaving the cursor at the start of the next one.
+    pub fn delete_line(&mut self) {
+        let range = edit::line_with_newline(&self.input, self.cursor);
+        self.input.drain(range.clone());
+        self.cursor = edit::line_start(&self.input, range.start.min(self.input.len()));
     }
 
     /// Get the current cursor position as (`line_index`, `column`)
     ///
-    /// Line index is 0-based, column is the character count from line start
+    /// Line index is 0-based, column is the grapheme count from line start
     #[must_use]
     pub fn cursor_line_col(&self) -> (usize, usize) {
         let before_cursor = &self.input[..self.cursor];
         let line_index = before_cursor.matches('\n').count();
         let line_start = before_cursor.rfind('\n').map_or(0, |i| i + 1);
-        let column = before_cursor[line_start..].chars().count();
+        let column = edit::grapheme_count(&before_cursor[line_start..]);
         (line_index, column)
     }
 
@@ -548,15 +721,9 @@ impl App {
         // Previous line content (without newline)
         let prev_line_end = current_line_start - 1;
         let prev_line = &self.input[prev_line_start..prev_line_end];
-        let prev_line_len = prev_line.chars().count();
 
         // Move to same column or end of previous line
-        let target_col = col.min(prev_line_len);
-        self.cursor = prev_line_start
-            + prev_line
-                .char_indices()
-                .nth(target_col)
-                .map_or(prev_line.len(), |(i, _)| i);
+        self.cursor = prev_line_start + edit::grapheme_offset(prev_line, col);
     }
 
     /// Move cursor down one line, preserving column position.
@@ -585,15 +752,9 @@ impl App {
             .map_or(self.input.len(), |i| next_line_start + i);
 
         let next_line = &self.input[next_line_start..next_line_end];
-        let next_line_len = next_line.chars().count();
 
         // Move to same column or end of next line
-        let target_col = col.min(next_line_len);
-        self.cursor = next_line_start
-            + next_line
-                .char_indices()
-                .nth(target_col)
-                .map_or(next_line.len(), |(i, _)| i);
+        self.cursor = next_line_start + edit::grapheme_offset(next_line, col);
     }
 
     /// Insert character at cursor.
@@ -602,18 +763,52 @@ impl App {
         self.cursor += c.len_utf8();
     }
 
-    /// Delete character before cursor.
+    /// Delete grapheme before cursor.
     pub fn delete_char(&mut self) {
-        if self.cursor > 0 {
-            let prev = self.input[..self.cursor]
-                .char_indices()
-                .next_back()
-                .map_or(0, |(i, _)| i);
-            self.input.drain(prev..self.cursor);
-            self.cursor = prev;
+        let prev = edit::prev_grapheme(&self.input, self.cursor);
+        self.input.drain(prev..self.cursor);
+        self.cursor = prev;
+    }
+
+    /// Keep the `@` mention picker in step with the mention being typed.
+    ///
+    /// Files are listed when a mention starts and dropped once it ends, so the
+    /// next mention sees new files.
+    pub fn refresh_file_picker(&mut self) {
+        let query =
+            if self.show_command_dropdown && dropdown_mode(&self.input) == DropdownMode::Files {
+                file_mention(&self.input)
+            } else {
+                None
+            };
+        let Some(query) = query else {
+            self.file_picker = None;
+            return;
+        };
+        let max_files = self.file_picker_max_files;
+        let picker = self.file_picker.get_or_insert_with(|| {
+            let root = std::env::current_dir().unwrap_or_default();
+            FilePicker::new(list_project_files(&root, max_files))
+        });
+        picker.update(query, Instant::now());
+    }
+
+    /// Match the mention as typed, skipping the rest of the debounce.
+    pub fn flush_file_picker(&mut self) {
+        if let Some(picker) = self.file_picker.as_mut() {
+            picker.flush();
         }
     }
 
+    /// Paths the `@` mention picker offers, best match first.
+    #[must_use]
+    pub fn file_matches(&self) -> Vec<&str> {
+        self.file_picker
+            .as_ref()
+            .map(FilePicker::matches)
+            .unwrap_or_default()
+    }
+
     /// Clear input and reset cursor.
     pub fn clear_input(&mut self) {
         self.input.clear();
@@ -628,15 +823,34 @@ impl App {
         action: PermissionAction,
         context: PermissionContext,
     ) {
-        self.active_dialog = Some(ActiveDialog::Permission(ActivePermissionDialog {
+        self.present_prompt(ActiveDialog::Permission(ActivePermissionDialog {
             request_id,
             tool_name,
             action,
             context,
             selected: 0,
+            preview: None,
         }));
     }
 
+    /// Attach a command preview to the permission dialog for `request_id`.
+    ///
+    /// Previews are worked out in the background, so the dialog may have
+    /// been answered or queued by the time one arrives.
+    pub fn set_command_preview(&mut self, request_id: Uuid, preview: CommandPreview) {
+        if let Some(dialog) = self
+            .active_dialog
+            .iter_mut()
+            .chain(self.queued_dialogs.iter_mut())
+            .find_map(|dialog| match dialog {
+                ActiveDialog::Permission(d) if d.request_id == request_id => Some(d),
+                _ => None,
+            })
+        {
+            dialog.preview = Some(preview);
+        }
+    }
+
     /// Show an `ask_user` dialog.
     pub fn show_ask_user_dialog(
         &mut self,
@@ -644,7 +858,7 @@ impl App {
         question: String,
         options: Option<Vec<String>>,
     ) {
-        self.active_dialog = Some(ActiveDialog::AskUser(ActiveAskUserDialog {
+        self.present_prompt(ActiveDialog::AskUser(ActiveAskUserDialog {
             request_id,
             question,
             options,
@@ -654,9 +868,30 @@ impl App {
         }));
     }
 
+    /// Show a prompt, queueing it behind an unanswered one.
+    ///
+    /// Tools running in parallel can ask at the same time, and replacing the
+    /// active prompt would leave its request waiting forever.
+    fn present_prompt(&mut self, dialog: ActiveDialog) {
+        match self.active_dialog {
+            Some(ActiveDialog::Permission(_) | ActiveDialog::AskUser(_)) => {
+                self.queued_dialogs.push_back(dialog);
+            }
+            _ => self.active_dialog = Some(dialog),
+        }
+    }
+
+    /// Show the next queued prompt once the active dialog has closed.
+    pub fn show_queued_dialog(&mut self) {
+        if self.active_dialog.is_none() {
+            self.active_dialog = self.queued_dialogs.pop_front();
+        }
+    }
+
     /// Hide any active dialog.
     pub fn hide_dialog(&mut self) {
         self.active_dialog = None;
+        self.show_queued_dialog();
     }
 
     /// Show the session list dialog.
@@ -683,6 +918,20 @@ impl App {
         }
     }
 
+    /// Show the persona list dialog, with the current persona selected.
+    pub fn show_persona_list(&mut self) {
+        match crate::config::list_personas() {
+            Ok(personas) => {
+                let dialog = PersonaListDialog::new(personas, &self.agent_config.persona);
+                self.active_dialog = Some(ActiveDialog::PersonaList(dialog));
+            }
+            Err(e) => {
+                tracing::warn!("failed to list personas: {e}");
+                self.set_notice(format!("Failed to list personas: {e}"));
+            }
+        }
+    }
+
     /// Check if a dialog is active.
     #[must_use]
     pub const fn has_dialog(&self) -> bool {
@@ -727,6 +976,73 @@ impl App {
         self.messages.push(DisplayMessage::user(text));
     }
 
+    /// Add a prompt sent to the agent as `id` to the conversation.
+    pub fn add_prompt(&mut self, id: impl Into<String>, text: impl Into<String>) {
+        self.messages.push(DisplayMessage::prompt(id, text));
+    }
+
+    /// Remove the prompt sent as `prompt_id` and everything shown after it.
+    pub fn remove_turns_from(&mut self, prompt_id: &str) {
+        if let Some(index) = self.prompt_index(prompt_id) {
+            self.messages.truncate(index);
+        }
+    }
+
+    /// Index of the prompt sent as `prompt_id`.
+    fn prompt_index(&self, prompt_id: &str) -> Option<usize> {
+        self.messages
+            .iter()
+            .position(|m| matches!(m, DisplayMessage::User { id: Some(id), .. } if id == prompt_id))
+    }
+
+    /// Load the prompt before the one being edited (or the last one) into the input.
+    pub fn edit_previous_prompt(&mut self) {
+        let end = self
+            .prompt_edit
+            .as_ref()
+            .and_then(|edit| self.prompt_index(&edit.prompt_id))
+            .unwrap_or(self.messages.len());
+        let Some((prompt_id, text)) = self.messages[..end].iter().rev().find_map(|m| match m {
+            DisplayMessage::User {
+                text, id: Some(id), ..
+            } => Some((id.clone(), text.clone())),
+            _ => None,
+        }) else {
+            self.set_notice("No earlier prompt to edit");
+            return;
+        };
+
+        let draft = std::mem::replace(&mut self.input, text);
+        self.cursor = self.input.len();
+        let draft = self.prompt_edit.take().map_or(draft, |edit| edit.draft);
+        self.prompt_edit = Some(PromptEdit { prompt_id, draft });
+    }
+
+    /// Stop editing an earlier prompt and restore the draft.
+    pub fn cancel_prompt_edit(&mut self) {
+        if let Some(edit) = self.prompt_edit.take() {
+            self.input = edit.draft;
+            self.cursor = self.input.len();
+        }
+    }
+
+    /// Status line shown while an earlier prompt is being edited.
+    #[must_use]
+    pub fn prompt_edit_status(&self) -> Option<String> {
+        let edit = self.prompt_edit.as_ref()?;
+        let later = self.messages[self.prompt_index(&edit.prompt_id)? + 1..]
+            .iter()
+            .filter(|m| matches!(m, DisplayMessage::User { id: Some(_), .. }))
+            .count();
+        let position = match later {
+            0 => "last prompt".to_string(),
+            n => format!("prompt {} back", n + 1),
+        };
+        Some(format!(
+            "Editing {position} · Enter resends in a forked session · Esc cancels"
+        ))
+    }
+
     /// Add an assistant message to the conversation.
     pub fn add_assistant_message(&mut self, text: impl Into<String>) {
         self.messages.push(DisplayMessage::assistant(text));
@@ -750,6 +1066,27 @@ impl App {
         self.messages.clear();
         self.streaming_text.clear();
         self.message_scroll = 0;
+        self.prompt_edit = None;
+        self.search = None;
+        self.context_tokens = 0;
+    }
+
+    /// Context tokens used and the model's window, once both are known.
+    ///
+    /// The window is read from the agent whenever it's idle, so model
+    /// switches show up and the indicator stays put during a response.
+    pub fn context_usage(&mut self) -> Option<(u32, u32)> {
+        if let Some(agent) = &self.agent {
+            self.context_window = agent.context_window();
+        }
+        let window = self.context_window?;
+        (self.context_tokens > 0).then_some((self.context_tokens, window))
+    }
+
+    /// Prompt mode indicator, when vim mode is on.
+    #[must_use]
+    pub fn vim_label(&self) -> Option<&'static str> {
+        self.vim.map(|vim| vim.mode.label())
     }
 
     /// Scroll the message list up.
@@ -784,6 +1121,7 @@ impl App {
         };
         let prompt_height = (input_lines + 3).clamp(4, 13);
         let visible_height = height.saturating_sub(prompt_height);
+        self.message_area_height = visible_height;
         self.max_message_scroll = content_height.saturating_sub(visible_height);
 
         // Auto-scroll to bottom when enabled
@@ -792,6 +1130,30 @@ impl App {
         }
     }
 
+    /// Expand the lowest folded tool run on screen, noting when there's none.
+    pub fn expand_run_in_view(&mut self) {
+        let width = self.term_width.saturating_sub(MESSAGE_PADDING_X * 2);
+        let top = self.message_scroll;
+        let bottom = top.saturating_add(self.message_area_height);
+
+        let mut row: u16 = 0;
+        let mut in_view = None;
+        for message in self.messages.view() {
+            let height = message_height(message, width);
+            if let DisplayMessage::ToolGroup { first, .. } = message {
+                if row < bottom && row.saturating_add(height) > top {
+                    in_view = Some(*first);
+                }
+            }
+            row = row.saturating_add(height).saturating_add(1);
+        }
+
+        match in_view {
+            Some(first) => self.messages.expand_run(first),
+            None => self.set_notice("No folded tool calls in view"),
+        }
+    }
+
     /// Load messages from a session into display format.
     ///
     /// # Errors
@@ -809,6 +1171,9 @@ impl App {
         for msg in messages {
             let parts = manager.list_parts(msg.id())?;
 
+            let created = chrono::DateTime::from_timestamp_millis(msg.time().created)
+                .map(std::time::SystemTime::from);
+
             match msg {
                 SessionMessage::User(_) => {
                     // Collect text parts into user message
@@ -824,7 +1189,8 @@ impl App {
                     if !text.is_empty() {
                         display_messages.push(DisplayMessage::User {
                             text,
-                            timestamp: None,
+                            timestamp: created,
+                            id: Some(msg.id().to_string()),
                         });
                     }
                 }
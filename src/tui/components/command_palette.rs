@@ -3,17 +3,13 @@
 use ratatui::{
     Frame,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph},
 };
 
-use crate::config::ModelInfo;
-
-/// Brand colors
-const BRAND_TEAL: Color = Color::Rgb(77, 201, 176);
-const DIMMED: Color = Color::Rgb(100, 100, 110);
-const DROPDOWN_BG: Color = Color::Rgb(35, 38, 45);
+use crate::config::{AgentConfig, ModelInfo};
+use crate::tui::theme::Theme;
 
 /// Max width for centered UI elements (input box, command palette)
 pub const CENTERED_MAX_WIDTH: u16 = 72;
@@ -33,14 +29,50 @@ pub const COMMANDS: &[Command] = &[
         name: "/model",
         description: "Switch AI model",
     },
+    Command {
+        name: "/regenerate",
+        description: "Regenerate the last response (Ctrl+R), optionally with another model",
+    },
     Command {
         name: "/clear",
         description: "Clear conversation history",
     },
+    Command {
+        name: "/diff",
+        description: "Show files changed in this session",
+    },
+    Command {
+        name: "/undo",
+        description: "Revert the files changed by the last turn",
+    },
+    Command {
+        name: "/compact",
+        description: "Summarize older messages to free context",
+    },
+    Command {
+        name: "/collapse",
+        description: "Toggle folding of consecutive tool calls (Ctrl+O expands one)",
+    },
+    Command {
+        name: "/search",
+        description: "Search the conversation (Ctrl+F), n/N to jump between matches",
+    },
+    Command {
+        name: "/retitle",
+        description: "Regenerate the session title",
+    },
     Command {
         name: "/sessions",
         description: "Browse and switch sessions",
     },
+    Command {
+        name: "/persona",
+        description: "Switch persona",
+    },
+    Command {
+        name: "/system",
+        description: "Append instructions to the system prompt",
+    },
     Command {
         name: "/plan",
         description: "Switch to plan mode",
@@ -76,15 +108,32 @@ pub enum DropdownMode {
     Commands,
     /// Show model suggestions (e.g., "/model gpt" -> gpt-4o)
     Models,
+    /// Show project files for an `@` mention (e.g., "@main" -> src/main.rs)
+    Files,
     /// No dropdown
     None,
 }
 
+/// Commands that take a model argument.
+const MODEL_COMMANDS: &[&str] = &["/model", "/regenerate"];
+
+/// Get the model-taking command the input starts with, if any.
+#[must_use]
+pub fn model_command(input: &str) -> Option<&'static str> {
+    MODEL_COMMANDS.iter().copied().find(|cmd| {
+        input
+            .strip_prefix(cmd)
+            .is_some_and(|rest| rest.starts_with(' '))
+    })
+}
+
 /// Determine the dropdown mode based on input.
 #[must_use]
 pub fn dropdown_mode(input: &str) -> DropdownMode {
-    if input.starts_with("/model ") {
+    if model_command(input).is_some() {
         DropdownMode::Models
+    } else if file_mention(input).is_some() {
+        DropdownMode::Files
     } else if input.starts_with('/') && !input.contains(' ') {
         DropdownMode::Commands
     } else {
@@ -98,25 +147,63 @@ pub fn should_show_dropdown(input: &str) -> bool {
     dropdown_mode(input) != DropdownMode::None
 }
 
-/// Filter models by query (case-insensitive).
-#[must_use]
-pub fn filter_models<'a>(input: &str, models: &'a [ModelInfo]) -> Vec<&'a ModelInfo> {
-    let query = input
-        .strip_prefix("/model ")
+/// Model query typed after the model command, trimmed.
+fn model_query(input: &str) -> &str {
+    model_command(input)
+        .and_then(|cmd| input.strip_prefix(cmd))
         .unwrap_or("")
         .trim()
-        .to_lowercase();
+}
+
+/// Filter models by query against their ID or alias (case-insensitive).
+#[must_use]
+pub fn filter_models<'a>(input: &str, config: &'a AgentConfig) -> Vec<&'a ModelInfo> {
+    let query = model_query(input).to_lowercase();
 
     if query.is_empty() {
-        models.iter().collect()
+        config.models.iter().collect()
     } else {
-        models
+        config
+            .models
             .iter()
-            .filter(|m| m.id.to_lowercase().contains(&query))
+            .filter(|m| {
+                m.id.to_lowercase().contains(&query)
+                    || config
+                        .alias_for(&m.id)
+                        .is_some_and(|alias| alias.to_lowercase().contains(&query))
+            })
             .collect()
     }
 }
 
+/// Name to insert when `model` is picked from the dropdown.
+///
+/// Keeps the alias when that's what was typed, so the friendly name is shown.
+#[must_use]
+pub fn model_completion<'a>(input: &str, model: &'a ModelInfo, config: &'a AgentConfig) -> &'a str {
+    config
+        .alias_for(&model.id)
+        .filter(|alias| alias.eq_ignore_ascii_case(model_query(input)))
+        .unwrap_or(&model.id)
+}
+
+/// Get the `@` mention being typed at the end of the input, without the `@`.
+#[must_use]
+pub fn file_mention(input: &str) -> Option<&str> {
+    input
+        .rsplit(char::is_whitespace)
+        .next()
+        .and_then(|word| word.strip_prefix('@'))
+}
+
+/// Replace the `@` mention at the end of the input with `path`.
+#[must_use]
+pub fn complete_file_mention(input: &str, path: &str) -> String {
+    let query_len = file_mention(input).map_or(0, str::len);
+    let prefix = &input[..input.len() - query_len];
+    format!("{prefix}{path} ")
+}
+
 /// Render the command dropdown above the prompt.
 ///
 /// Returns the height used by the dropdown.
@@ -124,6 +211,7 @@ pub fn filter_models<'a>(input: &str, models: &'a [ModelInfo]) -> Vec<&'a ModelI
 pub fn render_command_dropdown(
     frame: &mut Frame,
     prompt_area: Rect,
+    theme: &Theme,
     input: &str,
     selected: usize,
 ) -> u16 {
@@ -133,7 +221,7 @@ pub fn render_command_dropdown(
     let lines: Vec<Line> = if filtered.is_empty() {
         vec![Line::from(Span::styled(
             format!("  No commands matching '{input}'"),
-            Style::default().fg(DIMMED),
+            Style::default().fg(theme.dim),
         ))]
     } else {
         filtered
@@ -144,15 +232,17 @@ pub fn render_command_dropdown(
                 let prefix = if is_selected { "▸ " } else { "  " };
 
                 let name_style = if is_selected {
-                    Style::default().fg(BRAND_TEAL).add_modifier(Modifier::BOLD)
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(BRAND_TEAL)
+                    Style::default().fg(theme.accent)
                 };
 
                 let desc_style = if is_selected {
-                    Style::default().fg(Color::White)
+                    Style::default().fg(theme.text)
                 } else {
-                    Style::default().fg(DIMMED)
+                    Style::default().fg(theme.dim)
                 };
 
                 Line::from(vec![
@@ -180,8 +270,8 @@ pub fn render_command_dropdown(
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(DIMMED))
-        .style(Style::default().bg(DROPDOWN_BG));
+        .border_style(Style::default().fg(theme.dim))
+        .style(Style::default().bg(theme.dialog));
 
     let para = Paragraph::new(lines).block(block);
     frame.render_widget(para, dropdown_area);
@@ -196,17 +286,18 @@ pub fn render_command_dropdown(
 pub fn render_model_dropdown(
     frame: &mut Frame,
     prompt_area: Rect,
+    theme: &Theme,
     input: &str,
     selected: usize,
-    models: &[ModelInfo],
+    config: &AgentConfig,
 ) -> u16 {
-    let filtered = filter_models(input, models);
+    let filtered = filter_models(input, config);
 
     let lines: Vec<Line> = if filtered.is_empty() {
         let query = input.strip_prefix("/model ").unwrap_or("").trim();
         vec![Line::from(Span::styled(
             format!("  No models matching '{query}'"),
-            Style::default().fg(DIMMED),
+            Style::default().fg(theme.dim),
         ))]
     } else {
         filtered
@@ -217,22 +308,95 @@ pub fn render_model_dropdown(
                 let prefix = if is_selected { "▸ " } else { "  " };
 
                 let name_style = if is_selected {
-                    Style::default().fg(BRAND_TEAL).add_modifier(Modifier::BOLD)
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(BRAND_TEAL)
+                    Style::default().fg(theme.accent)
                 };
 
                 let provider_style = if is_selected {
-                    Style::default().fg(Color::White)
+                    Style::default().fg(theme.text)
                 } else {
-                    Style::default().fg(DIMMED)
+                    Style::default().fg(theme.dim)
                 };
 
-                Line::from(vec![
+                let mut spans = vec![
                     Span::styled(prefix, name_style),
                     Span::styled(&model.id, name_style),
-                    Span::raw("  "),
-                    Span::styled(format!("({})", model.provider), provider_style),
+                ];
+                if let Some(alias) = config.alias_for(&model.id) {
+                    spans.push(Span::styled(format!("  {alias}"), provider_style));
+                }
+                spans.push(Span::raw("  "));
+                spans.push(Span::styled(
+                    format!("({})", model.provider),
+                    provider_style,
+                ));
+                Line::from(spans)
+            })
+            .collect()
+    };
+
+    let content_lines = lines.len().max(1);
+    let dropdown_height = (content_lines + 2) as u16;
+    let dropdown_width = prompt_area.width;
+
+    let dropdown_y = prompt_area.y.saturating_sub(dropdown_height);
+    let dropdown_x = prompt_area.x;
+
+    let dropdown_area = Rect::new(dropdown_x, dropdown_y, dropdown_width, dropdown_height);
+
+    frame.render_widget(Clear, dropdown_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.dim))
+        .style(Style::default().bg(theme.dialog));
+
+    let para = Paragraph::new(lines).block(block);
+    frame.render_widget(para, dropdown_area);
+
+    dropdown_height
+}
+
+/// Render the `@` mention file dropdown above the prompt.
+///
+/// Returns the height used by the dropdown.
+#[allow(clippy::cast_possible_truncation)]
+pub fn render_file_dropdown(
+    frame: &mut Frame,
+    prompt_area: Rect,
+    theme: &Theme,
+    input: &str,
+    selected: usize,
+    matches: &[&str],
+) -> u16 {
+    let lines: Vec<Line> = if matches.is_empty() {
+        let query = file_mention(input).unwrap_or("");
+        vec![Line::from(Span::styled(
+            format!("  No files matching '{query}'"),
+            Style::default().fg(theme.dim),
+        ))]
+    } else {
+        matches
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let is_selected = i == selected;
+                let prefix = if is_selected { "▸ " } else { "  " };
+
+                let style = if is_selected {
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.accent)
+                };
+
+                Line::from(vec![
+                    Span::styled(prefix, style),
+                    Span::styled(*path, style),
                 ])
             })
             .collect()
@@ -251,11 +415,50 @@ pub fn render_model_dropdown(
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(DIMMED))
-        .style(Style::default().bg(DROPDOWN_BG));
+        .border_style(Style::default().fg(theme.dim))
+        .style(Style::default().bg(theme.dialog));
 
     let para = Paragraph::new(lines).block(block);
     frame.render_widget(para, dropdown_area);
 
     dropdown_height
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mention_at_end_of_input_opens_file_dropdown() {
+        assert_eq!(dropdown_mode("look at @src/ma"), DropdownMode::Files);
+        assert_eq!(dropdown_mode("@"), DropdownMode::Files);
+        assert_eq!(dropdown_mode("mail me@ home"), DropdownMode::None);
+        assert_eq!(dropdown_mode("@src/main.rs then"), DropdownMode::None);
+    }
+
+    #[test]
+    fn completes_mention_with_selected_file() {
+        assert_eq!(
+            complete_file_mention("see @main", "src/main.rs"),
+            "see @src/main.rs "
+        );
+        assert_eq!(complete_file_mention("@tui", "src/tui/"), "@src/tui/ ");
+    }
+
+    #[test]
+    fn models_match_and_complete_by_alias() {
+        let config = AgentConfig::default();
+        let ids: Vec<&str> = filter_models("/model sonn", &config)
+            .iter()
+            .map(|m| m.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["claude-sonnet-4-20250514"]);
+
+        let model = filter_models("/model Sonnet", &config)[0];
+        assert_eq!(model_completion("/model Sonnet", model, &config), "sonnet");
+        assert_eq!(
+            model_completion("/model claude-son", model, &config),
+            "claude-sonnet-4-20250514"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Syntax highlighting for code in the transcript.
+//!
+//! The language comes from a file extension or a code fence tag. Unknown
+//! languages return `None` so callers keep their plain styling.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ratatui::text::Span;
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Highlighted spans, one list per source line.
+pub type HighlightedLines = Arc<Vec<Vec<Span<'static>>>>;
+
+/// Turn highlighting on or off (`[tui] syntax_highlight`).
+pub fn set_enabled(enabled: bool) {
+    DISABLED.store(!enabled, Ordering::Relaxed);
+}
+
+#[cfg_attr(not(feature = "syntax-highlight"), allow(dead_code))]
+fn enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed) && crate::core::color::enabled()
+}
+
+/// Highlight `code` as `language`, an extension or fence tag like `rs` or `rust`.
+///
+/// Results are cached by content, so redrawing while scrolling is cheap.
+#[cfg(feature = "syntax-highlight")]
+#[must_use]
+pub fn highlight(code: &str, language: &str) -> Option<HighlightedLines> {
+    use std::collections::HashMap;
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    use std::sync::LazyLock;
+
+    use parking_lot::Mutex;
+    use ratatui::style::{Color, Style};
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::{Theme, ThemeSet};
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::LinesWithEndings;
+
+    /// Highlighted blocks kept before the cache is reset
+    const CACHE_CAPACITY: usize = 128;
+
+    static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+    static THEME: LazyLock<Theme> = LazyLock::new(|| {
+        ThemeSet::load_defaults()
+            .themes
+            .remove("base16-ocean.dark")
+            .unwrap_or_default()
+    });
+    static CACHE: LazyLock<Mutex<HashMap<u64, HighlightedLines>>> =
+        LazyLock::new(|| Mutex::new(HashMap::new()));
+
+    if !enabled() || language.is_empty() {
+        return None;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    (language, code).hash(&mut hasher);
+    let key = hasher.finish();
+    if let Some(hit) = CACHE.lock().get(&key) {
+        return Some(Arc::clone(hit));
+    }
+
+    let syntax = SYNTAXES
+        .find_syntax_by_token(language)
+        .filter(|syntax| syntax.name != "Plain Text")?;
+    let mut highlighter = HighlightLines::new(syntax, &THEME);
+
+    let lines = LinesWithEndings::from(code)
+        .map(|line| {
+            let ranges = highlighter.highlight_line(line, &SYNTAXES).ok()?;
+            Some(
+                ranges
+                    .into_iter()
+                    .map(|(style, text)| {
+                        let fg = style.foreground;
+                        Span::styled(
+                            text.trim_end_matches(['\n', '\r']).to_string(),
+                            Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b)),
+                        )
+                    })
+                    .collect(),
+            )
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let lines = Arc::new(lines);
+
+    let mut cache = CACHE.lock();
+    if cache.len() >= CACHE_CAPACITY {
+        cache.clear();
+    }
+    cache.insert(key, Arc::clone(&lines));
+    Some(lines)
+}
+
+/// Highlight `code` as `language`.
+///
+/// Always `None`: highlighting support was not compiled in.
+#[cfg(not(feature = "syntax-highlight"))]
+#[must_use]
+pub fn highlight(_code: &str, _language: &str) -> Option<HighlightedLines> {
+    None
+}
+
+/// Highlight the file a tool read, using the extension of `path`.
+#[must_use]
+pub fn highlight_file(path: &str, code: &str) -> Option<HighlightedLines> {
+    let extension = std::path::Path::new(path).extension()?.to_str()?;
+    highlight(code, extension)
+}
+
+/// Highlight the contents of fenced code blocks in markdown `lines`.
+///
+/// Returns one entry per line: spans for lines inside a fence with a known
+/// language, `None` for everything else (including the fences themselves).
+#[must_use]
+pub fn highlight_fences(lines: &[&str]) -> Vec<Option<Vec<Span<'static>>>> {
+    let mut highlighted = vec![None; lines.len()];
+    let mut i = 0;
+
+    while i < lines.len() {
+        let Some(language) = lines[i].trim_start().strip_prefix("```") else {
+            i += 1;
+            continue;
+        };
+        let start = i + 1;
+        let end = lines[start..]
+            .iter()
+            .position(|line| line.trim_start().starts_with("```"))
+            .map_or(lines.len(), |offset| start + offset);
+
+        let code = lines[start..end].join("\n");
+        if let Some(spans) = highlight(&code, language.trim()) {
+            for (slot, line) in highlighted[start..end].iter_mut().zip(spans.iter()) {
+                *slot = Some(line.clone());
+            }
+        }
+        i = end + 1;
+    }
+
+    highlighted
+}
+
+#[cfg(all(test, feature = "syntax-highlight"))]
+mod tests {
+    use super::*;
+
+    fn text(spans: &[Span<'_>]) -> String {
+        spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn highlights_known_languages_and_skips_unknown() {
+        let lines = highlight_file("src/main.rs", "fn main() {}\nlet x = 1;").unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(text(&lines[0]), "fn main() {}");
+        assert!(lines[0].len() > 1, "expected several styled spans");
+
+        assert!(highlight_file("notes.unknownext", "hello").is_none());
+        assert!(highlight_file("Makefile.txt", "hello").is_none());
+    }
+
+    #[test]
+    fn fences_highlight_only_their_contents() {
+        let lines = [
+            "Here:",
+            "```rust",
+            "let x = 1;",
+            "```",
+            "```",
+            "plain",
+            "```",
+        ];
+        let highlighted = highlight_fences(&lines);
+
+        assert!(highlighted[0].is_none());
+        assert!(highlighted[1].is_none());
+        assert_eq!(text(highlighted[2].as_ref().unwrap()), "let x = 1;");
+        assert!(highlighted[3].is_none());
+        // Untagged fences stay plain
+        assert!(highlighted[5].is_none());
+    }
+}
@@ -1,45 +1,51 @@
 //! Message rendering components.
 
+use std::time::SystemTime;
+
 use ratatui::{
     Frame,
-    layout::Rect,
+    layout::{Alignment, Rect},
     style::{Color, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
 };
+use unicode_width::UnicodeWidthStr;
 
+use super::highlight::{highlight_fences, highlight_file};
 use super::markdown::parse_markdown_line;
 use crate::tui::message::{DisplayMessage, icons, tool_icon};
+use crate::tui::search::{MessageHighlight, match_ranges};
+use crate::tui::theme::Theme;
 
-/// Brand colors
-const BRAND_TEAL: Color = Color::Rgb(77, 201, 176);
-/// Lighter panel background for "previous" user messages
-const PANEL_BG: Color = Color::Rgb(28, 30, 35);
-const DIMMED: Color = Color::Rgb(100, 100, 110);
 const ERROR_COLOR: Color = Color::Red;
-const SUCCESS_COLOR: Color = Color::Rgb(77, 201, 176);
 const SELECTION_BG: Color = Color::Rgb(60, 80, 100);
 const SELECTION_FG: Color = Color::White;
 
-/// Diff colors (muted for readability)
-const DIFF_ADD: Color = Color::Rgb(80, 160, 80);
-const DIFF_DEL: Color = Color::Rgb(180, 80, 80);
+/// Diff hunk headers; added and removed lines use the theme
 const DIFF_HUNK: Color = Color::Rgb(80, 140, 180);
 
 /// Continuation character for tool output
 const CONT_CHAR: &str = "⎿";
 
+/// Tool output lines shown before the rest is cut off
+pub const TOOL_OUTPUT_PREVIEW_LINES: usize = 12;
+
+/// Search match colors; the selected match uses the theme accent
+const MATCH_BG: Color = SELECTION_BG;
+const MATCH_FG: Color = SELECTION_FG;
+const CURRENT_MATCH_FG: Color = Color::Black;
+
 /// Get the appropriate color for a line, applying diff colors if it looks like a diff
-fn line_color(line: &str) -> Color {
+fn line_color(line: &str, theme: &Theme) -> Color {
     // Check for diff patterns - apply to any output that looks like a diff
     if (line.starts_with('+') || line.starts_with('>')) && !line.starts_with("+++") {
-        DIFF_ADD
+        theme.diff_add
     } else if (line.starts_with('-') || line.starts_with('<')) && !line.starts_with("---") {
-        DIFF_DEL
+        theme.diff_remove
     } else if line.starts_with("@@") || line.starts_with("diff ") {
         DIFF_HUNK
     } else {
-        DIMMED
+        theme.dim
     }
 }
 
@@ -51,30 +57,39 @@ fn line_color(line: &str) -> Color {
 pub fn render_message_with_scroll(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     message: &DisplayMessage,
     scroll_offset: u16,
     selection: Option<(u16, u16)>,
     selected_text: &mut String,
+    search: Option<MessageHighlight<'_>>,
 ) {
     match message {
-        DisplayMessage::User { text, .. } => {
+        DisplayMessage::User {
+            text, timestamp, ..
+        } => {
             render_user_message_with_scroll(
                 frame,
                 area,
+                theme,
                 text,
+                *timestamp,
                 scroll_offset,
                 selection,
                 selected_text,
+                search,
             );
         }
         DisplayMessage::Assistant { text } => {
             render_assistant_message_with_scroll(
                 frame,
                 area,
+                theme,
                 text,
                 scroll_offset,
                 selection,
                 selected_text,
+                search,
             );
         }
         DisplayMessage::Tool {
@@ -86,6 +101,7 @@ pub fn render_message_with_scroll(
             render_tool_message_with_scroll(
                 frame,
                 area,
+                theme,
                 name,
                 invocation,
                 output,
@@ -93,27 +109,44 @@ pub fn render_message_with_scroll(
                 scroll_offset,
                 selection,
                 selected_text,
+                search,
             );
         }
+        DisplayMessage::ToolGroup { summary, .. } => {
+            if scroll_offset == 0 {
+                let line = Line::from(vec![
+                    Span::styled(
+                        format!("{} ", icons::GROUP),
+                        Style::default().fg(theme.accent),
+                    ),
+                    Span::styled(summary.as_str(), Style::default().fg(theme.tool)),
+                    Span::styled(" · Ctrl+O to expand", Style::default().fg(theme.dim)),
+                ]);
+                frame.render_widget(Paragraph::new(line).wrap(Wrap { trim: false }), area);
+            }
+        }
     }
 }
 
 /// Render a user message with scroll offset for partial visibility
-#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_truncation, clippy::too_many_arguments)]
 fn render_user_message_with_scroll(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     text: &str,
+    timestamp: Option<SystemTime>,
     scroll_offset: u16,
     selection: Option<(u16, u16)>,
     selected_text: &mut String,
+    search: Option<MessageHighlight<'_>>,
 ) {
     // Calculate actual height needed, accounting for line wrapping
     // Subtract 1 for the left border
     let width = area.width.saturating_sub(1).max(1) as usize;
     let content_height: u16 = text
         .lines()
-        .map(|line| wrapped_line_height(line.chars().count(), width))
+        .map(|line| wrapped_line_height(line.width(), width))
         .sum::<u16>()
         .max(1);
     // Add 2 for top and bottom padding, subtract scroll offset for visible portion
@@ -140,6 +173,10 @@ fn render_user_message_with_scroll(
                     Style::default().bg(SELECTION_BG).fg(SELECTION_FG),
                 ),
             ]));
+        } else if let Some(spans) = search_spans(line_text, i, search, Style::default(), theme) {
+            let mut line = vec![Span::raw(" ")]; // Left padding
+            line.extend(spans);
+            lines.push(Line::from(line));
         } else {
             lines.push(Line::from(vec![
                 Span::raw(" "), // Left padding
@@ -147,15 +184,27 @@ fn render_user_message_with_scroll(
             ]));
         }
     }
-    lines.push(Line::from("")); // Bottom padding
+    // Bottom padding, with a dimmed timestamp on the right
+    let bottom = timestamp.map_or_else(
+        || Line::from(""),
+        |ts| {
+            let time = chrono::DateTime::<chrono::Local>::from(ts).format("%H:%M ");
+            Line::from(Span::styled(
+                time.to_string(),
+                Style::default().fg(theme.dim),
+            ))
+            .alignment(Alignment::Right)
+        },
+    );
+    lines.push(bottom);
 
     // Skip lines according to scroll offset
     let visible_lines: Vec<Line> = lines.into_iter().skip(scroll_offset as usize).collect();
 
     let block = Block::default()
         .borders(Borders::LEFT)
-        .border_style(Style::default().fg(BRAND_TEAL))
-        .style(Style::default().bg(PANEL_BG));
+        .border_style(Style::default().fg(theme.user))
+        .style(Style::default().bg(theme.panel));
 
     let para = Paragraph::new(visible_lines)
         .block(block)
@@ -167,18 +216,23 @@ fn render_user_message_with_scroll(
 }
 
 /// Render an assistant message with scroll offset for partial visibility
-#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_truncation, clippy::too_many_arguments)]
 fn render_assistant_message_with_scroll(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     text: &str,
     scroll_offset: u16,
     selection: Option<(u16, u16)>,
     selected_text: &mut String,
+    search: Option<MessageHighlight<'_>>,
 ) {
     // Build lines with selection highlighting and markdown parsing
-    let all_lines: Vec<Line> = text
-        .lines()
+    let source_lines: Vec<&str> = text.lines().collect();
+    let mut code_lines = highlight_fences(&source_lines);
+    let all_lines: Vec<Line> = source_lines
+        .iter()
+        .copied()
         .enumerate()
         .map(|(i, line_text)| {
             #[allow(clippy::cast_possible_truncation)]
@@ -195,6 +249,11 @@ fn render_assistant_message_with_scroll(
                     line_text,
                     Style::default().bg(SELECTION_BG).fg(SELECTION_FG),
                 ))
+            } else if let Some(spans) = search_spans(line_text, i, search, Style::default(), theme)
+            {
+                Line::from(spans)
+            } else if let Some(spans) = code_lines[i].take() {
+                Line::from(spans)
             } else {
                 // Parse markdown formatting for non-selected lines
                 Line::from(parse_markdown_line(line_text))
@@ -205,7 +264,9 @@ fn render_assistant_message_with_scroll(
     // Skip lines according to scroll offset
     let visible_lines: Vec<Line> = all_lines.into_iter().skip(scroll_offset as usize).collect();
 
-    let para = Paragraph::new(visible_lines).wrap(Wrap { trim: false });
+    let para = Paragraph::new(visible_lines)
+        .style(Style::default().fg(theme.assistant))
+        .wrap(Wrap { trim: false });
     frame.render_widget(para, area);
 }
 
@@ -214,6 +275,7 @@ fn render_assistant_message_with_scroll(
 fn render_tool_message_with_scroll(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     name: &str,
     invocation: &str,
     output: &str,
@@ -221,13 +283,14 @@ fn render_tool_message_with_scroll(
     scroll_offset: u16,
     selection: Option<(u16, u16)>,
     selected_text: &mut String,
+    search: Option<MessageHighlight<'_>>,
 ) {
     let icon = if is_error {
         icons::ERROR
     } else {
         tool_icon(name)
     };
-    let icon_color = if is_error { ERROR_COLOR } else { SUCCESS_COLOR };
+    let icon_color = if is_error { ERROR_COLOR } else { theme.accent };
 
     let mut lines: Vec<Line> = Vec::new();
 
@@ -254,24 +317,31 @@ fn render_tool_message_with_scroll(
     } else {
         lines.push(Line::from(vec![
             Span::styled(format!("{icon} "), Style::default().fg(icon_color)),
-            Span::styled(name, Style::default().fg(Color::White)),
+            Span::styled(name, Style::default().fg(theme.tool)),
             Span::styled(
                 if invocation.is_empty() {
                     String::new()
                 } else {
                     format!("({invocation})")
                 },
-                Style::default().fg(DIMMED),
+                Style::default().fg(theme.dim),
             ),
         ]));
     }
 
     // Output lines with continuation character
     let output_lines: Vec<&str> = output.lines().collect();
-    let max_output_lines = 12;
+    let max_output_lines = TOOL_OUTPUT_PREVIEW_LINES;
     let show_lines = output_lines.len().min(max_output_lines);
     let truncated = output_lines.len() > max_output_lines;
 
+    // Highlight file contents by extension, keeping diff colors for the rest
+    let highlighted = if is_error || !matches!(name, "read_file" | "Read") {
+        None
+    } else {
+        highlight_file(invocation, &output_lines[..show_lines].join("\n"))
+    };
+
     for (i, line_text) in output_lines.iter().take(show_lines).enumerate() {
         #[allow(clippy::cast_possible_truncation)]
         let line_y = area.y + 1 + i as u16;
@@ -294,15 +364,33 @@ fn render_tool_message_with_scroll(
                 format!("{prefix}{line_text}"),
                 Style::default().bg(SELECTION_BG).fg(SELECTION_FG),
             )));
+        } else if let Some(spans) = search_spans(
+            line_text,
+            i,
+            search,
+            Style::default().fg(if is_error {
+                ERROR_COLOR
+            } else {
+                line_color(line_text, theme)
+            }),
+            theme,
+        ) {
+            let mut line = vec![Span::styled(prefix, Style::default().fg(theme.dim))];
+            line.extend(spans);
+            lines.push(Line::from(line));
+        } else if let Some(spans) = highlighted.as_ref().and_then(|lines| lines.get(i)) {
+            let mut line = vec![Span::styled(prefix, Style::default().fg(theme.dim))];
+            line.extend(spans.iter().cloned());
+            lines.push(Line::from(line));
         } else {
             // Determine text color - apply diff colors if line looks like a diff
             let text_color = if is_error {
                 ERROR_COLOR
             } else {
-                line_color(line_text)
+                line_color(line_text, theme)
             };
             lines.push(Line::from(vec![
-                Span::styled(prefix, Style::default().fg(DIMMED)),
+                Span::styled(prefix, Style::default().fg(theme.dim)),
                 Span::styled((*line_text).to_string(), Style::default().fg(text_color)),
             ]));
         }
@@ -313,7 +401,7 @@ fn render_tool_message_with_scroll(
         let remaining = output_lines.len() - max_output_lines;
         lines.push(Line::from(Span::styled(
             format!("     ... ({remaining} more lines)"),
-            Style::default().fg(DIMMED),
+            Style::default().fg(theme.dim),
         )));
     }
 
@@ -324,14 +412,51 @@ fn render_tool_message_with_scroll(
     frame.render_widget(para, area);
 }
 
-/// Calculate how many rows a line of text takes when wrapped to a given width
+/// Split a line into spans with search matches highlighted, or `None` if it has no match
+fn search_spans<'a>(
+    text: &'a str,
+    line: usize,
+    search: Option<MessageHighlight<'_>>,
+    style: Style,
+    theme: &Theme,
+) -> Option<Vec<Span<'a>>> {
+    let search = search?;
+    let ranges = match_ranges(text, search.query);
+    if ranges.is_empty() {
+        return None;
+    }
+
+    let mut spans = Vec::with_capacity(ranges.len() * 2 + 1);
+    let mut end = 0;
+    for (start, stop) in ranges {
+        if start > end {
+            spans.push(Span::styled(&text[end..start], style));
+        }
+        let (bg, fg) = if search.current == Some((line, start)) {
+            (theme.accent, CURRENT_MATCH_FG)
+        } else {
+            (MATCH_BG, MATCH_FG)
+        };
+        spans.push(Span::styled(
+            &text[start..stop],
+            Style::default().bg(bg).fg(fg),
+        ));
+        end = stop;
+    }
+    if end < text.len() {
+        spans.push(Span::styled(&text[end..], style));
+    }
+    Some(spans)
+}
+
+/// Calculate how many rows a line of `columns` display width takes when wrapped
 #[inline]
 #[allow(clippy::cast_possible_truncation)]
-pub const fn wrapped_line_height(chars: usize, width: usize) -> u16 {
-    if chars == 0 {
+pub const fn wrapped_line_height(columns: usize, width: usize) -> u16 {
+    if columns == 0 {
         1
     } else {
-        chars.div_ceil(width) as u16
+        columns.div_ceil(width) as u16
     }
 }
 
@@ -344,18 +469,18 @@ pub fn message_height(message: &DisplayMessage, width: u16) -> u16 {
             // User messages have top and bottom padding (+2)
             let content_height: u16 = text
                 .lines()
-                .map(|line| wrapped_line_height(line.chars().count(), width))
+                .map(|line| wrapped_line_height(line.width(), width))
                 .sum::<u16>()
                 .max(1);
             content_height + 2
         }
         DisplayMessage::Assistant { text } => text
             .lines()
-            .map(|line| wrapped_line_height(line.chars().count(), width))
+            .map(|line| wrapped_line_height(line.width(), width))
             .sum::<u16>()
             .max(1),
         DisplayMessage::Tool { output, .. } => {
-            let max_output_lines = 12;
+            let max_output_lines = TOOL_OUTPUT_PREVIEW_LINES;
             let output_line_count = output.lines().count();
             let truncated = output_line_count > max_output_lines;
             let prefix_len = 5; // "  ⎿  " or "     "
@@ -365,11 +490,16 @@ pub fn message_height(message: &DisplayMessage, width: u16) -> u16 {
             let output_height: u16 = output
                 .lines()
                 .take(max_output_lines)
-                .map(|line| wrapped_line_height(line.chars().count(), effective_width))
+                .map(|line| wrapped_line_height(line.width(), effective_width))
                 .sum();
 
             // 1 for header + wrapped output lines + optional truncation line
             1 + output_height + u16::from(truncated)
         }
+        DisplayMessage::ToolGroup { summary, .. } => {
+            // Icon, summary, and expand hint on one line
+            let columns = summary.width() + 21;
+            wrapped_line_height(columns, width)
+        }
     }
 }
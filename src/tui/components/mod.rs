@@ -1,17 +1,25 @@
 //! TUI components for rendering different views.
 
 mod command_palette;
+mod highlight;
 mod markdown;
 mod messages;
+mod output_dialog;
+mod persona_list;
 mod prompt;
 mod session;
 mod session_list;
 mod welcome;
 
 pub use command_palette::{
-    DropdownMode, dropdown_mode, filter_commands, filter_models, render_command_dropdown,
+    DropdownMode, complete_file_mention, dropdown_mode, file_mention, filter_commands,
+    filter_models, model_command, model_completion, render_command_dropdown, render_file_dropdown,
     render_model_dropdown, should_show_dropdown,
 };
+pub use highlight::set_enabled as set_syntax_highlight;
+pub use messages::{TOOL_OUTPUT_PREVIEW_LINES, message_height, wrapped_line_height};
+pub use output_dialog::{OutputDialog, render_output_dialog};
+pub use persona_list::{PersonaListDialog, render_persona_list};
 pub use prompt::PLACEHOLDERS;
 pub use session::{MESSAGE_PADDING_X, calculate_content_height, render_session};
 pub use session_list::{SessionListDialog, render_session_list};
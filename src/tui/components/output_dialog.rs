@@ -0,0 +1,143 @@
+//! Scrollable dialog for long command output such as `/diff` and `/undo`.
+
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::tui::theme::Theme;
+
+/// Lines scrolled by Page Up/Down.
+const PAGE_LINES: u16 = 10;
+
+/// Output dialog state.
+pub struct OutputDialog {
+    /// Dialog title.
+    title: String,
+    /// Text to show, one entry per line.
+    lines: Vec<String>,
+    /// Lines scrolled past the top.
+    scroll: u16,
+    /// Whether the dialog asks to confirm an action.
+    confirm: bool,
+}
+
+impl OutputDialog {
+    /// Create a dialog showing `text`.
+    #[must_use]
+    pub fn new(title: impl Into<String>, text: &str) -> Self {
+        Self {
+            title: title.into(),
+            lines: text.lines().map(str::to_string).collect(),
+            scroll: 0,
+            confirm: false,
+        }
+    }
+
+    /// Ask to confirm the action described by the text.
+    #[must_use]
+    pub const fn confirming(mut self) -> Self {
+        self.confirm = true;
+        self
+    }
+
+    /// Scroll up by `lines`.
+    pub const fn scroll_up(&mut self, lines: u16) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    /// Scroll down by `lines`, stopping at the last line.
+    pub fn scroll_down(&mut self, lines: u16) {
+        let last = u16::try_from(self.lines.len().saturating_sub(1)).unwrap_or(u16::MAX);
+        self.scroll = self.scroll.saturating_add(lines).min(last);
+    }
+
+    /// Scroll down one page.
+    pub fn page_down(&mut self) {
+        self.scroll_down(PAGE_LINES);
+    }
+
+    /// Scroll up one page.
+    pub const fn page_up(&mut self) {
+        self.scroll_up(PAGE_LINES);
+    }
+}
+
+/// Style for a line of output, coloring diff additions and removals.
+fn line_style(line: &str, theme: &Theme) -> Style {
+    if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff ") {
+        Style::default().fg(theme.accent)
+    } else if line.starts_with('+') {
+        Style::default().fg(theme.diff_add)
+    } else if line.starts_with('-') {
+        Style::default().fg(theme.diff_remove)
+    } else if line.starts_with("@@") {
+        Style::default().fg(theme.dim)
+    } else {
+        Style::default().fg(theme.text)
+    }
+}
+
+/// Render the output dialog.
+pub fn render_output_dialog(frame: &mut Frame, dialog: &OutputDialog, theme: &Theme) {
+    let area = frame.area();
+
+    // Center the dialog
+    let dialog_width = (area.width * 9 / 10).min(120);
+    let dialog_height = area.height * 4 / 5;
+    let dialog_x = (area.width - dialog_width) / 2;
+    let dialog_y = (area.height - dialog_height) / 2;
+
+    let dialog_area = Rect::new(dialog_x, dialog_y, dialog_width, dialog_height);
+
+    // Clear background
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(format!(" {} ", dialog.title))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.dialog));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::vertical([
+        Constraint::Min(3),    // Output
+        Constraint::Length(2), // Help
+    ])
+    .split(inner);
+
+    let lines: Vec<Line> = dialog
+        .lines
+        .iter()
+        .map(|line| Line::from(Span::styled(line.clone(), line_style(line, theme))))
+        .collect();
+    let output = Paragraph::new(lines).scroll((dialog.scroll, 0));
+    frame.render_widget(output, chunks[0]);
+
+    let mut help = vec![
+        Span::styled("↑↓", Style::default().fg(theme.accent)),
+        Span::styled(" scroll  ", Style::default().fg(theme.dim)),
+    ];
+    if dialog.confirm {
+        help.extend([
+            Span::styled("y", Style::default().fg(theme.accent)),
+            Span::styled(" confirm  ", Style::default().fg(theme.dim)),
+            Span::styled("n/Esc", Style::default().fg(theme.accent)),
+            Span::styled(" cancel", Style::default().fg(theme.dim)),
+        ]);
+    } else {
+        help.extend([
+            Span::styled("Esc", Style::default().fg(theme.accent)),
+            Span::styled(" close", Style::default().fg(theme.dim)),
+        ]);
+    }
+    let help = Paragraph::new(Line::from(help)).alignment(Alignment::Center);
+
+    frame.render_widget(help, chunks[1]);
+}
@@ -0,0 +1,175 @@
+//! Persona list dialog for switching personas with `/persona`.
+
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+
+use crate::config::PersonaEntry;
+use crate::tui::theme::Theme;
+
+/// Persona list dialog state.
+pub struct PersonaListDialog {
+    /// Personas to display.
+    personas: Vec<PersonaEntry>,
+    /// List widget state.
+    list_state: ListState,
+}
+
+impl PersonaListDialog {
+    /// Create a persona list dialog, selecting `current` if it's listed.
+    #[must_use]
+    pub fn new(personas: Vec<PersonaEntry>, current: &str) -> Self {
+        let selected = personas
+            .iter()
+            .position(|p| p.name.eq_ignore_ascii_case(current))
+            .unwrap_or(0);
+        let mut list_state = ListState::default();
+        if !personas.is_empty() {
+            list_state.select(Some(selected));
+        }
+        Self {
+            personas,
+            list_state,
+        }
+    }
+
+    /// Move selection up.
+    pub const fn select_previous(&mut self) {
+        if let Some(selected) = self.list_state.selected() {
+            self.list_state.select(Some(selected.saturating_sub(1)));
+        }
+    }
+
+    /// Move selection down.
+    pub fn select_next(&mut self) {
+        if let Some(selected) = self.list_state.selected() {
+            let last = self.personas.len().saturating_sub(1);
+            self.list_state.select(Some((selected + 1).min(last)));
+        }
+    }
+
+    /// Get the currently selected persona.
+    #[must_use]
+    pub fn selected_persona(&self) -> Option<&PersonaEntry> {
+        self.list_state
+            .selected()
+            .and_then(|i| self.personas.get(i))
+    }
+}
+
+/// Render the persona list dialog.
+pub fn render_persona_list(frame: &mut Frame, dialog: &mut PersonaListDialog, theme: &Theme) {
+    let area = frame.area();
+
+    // Center the dialog, sized to the list
+    let rows = u16::try_from(dialog.personas.len()).unwrap_or(u16::MAX);
+    let dialog_width = (area.width * 3 / 4).min(50);
+    let dialog_height = rows.saturating_add(6).min(area.height * 3 / 4);
+    let dialog_x = (area.width - dialog_width) / 2;
+    let dialog_y = (area.height - dialog_height) / 2;
+
+    let dialog_area = Rect::new(dialog_x, dialog_y, dialog_width, dialog_height);
+
+    // Clear background
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Personas ")
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.dialog));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::vertical([
+        Constraint::Min(3),    // List
+        Constraint::Length(2), // Help
+    ])
+    .split(inner);
+
+    let selected_idx = dialog.list_state.selected();
+    let items: Vec<ListItem> = dialog
+        .personas
+        .iter()
+        .enumerate()
+        .map(|(idx, persona)| {
+            let is_selected = selected_idx == Some(idx);
+            let style = if is_selected {
+                Style::default()
+                    .bg(theme.selection)
+                    .fg(theme.text)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            let source_style = if is_selected {
+                Style::default().bg(theme.selection).fg(theme.dim)
+            } else {
+                Style::default().fg(theme.dim)
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::styled(format!(" {}", if is_selected { "▸" } else { " " }), style),
+                Span::styled(persona.name.clone(), style),
+                Span::styled("  ", style),
+                Span::styled(persona.source.label(), source_style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.dim)),
+    );
+    frame.render_stateful_widget(list, chunks[0], &mut dialog.list_state);
+
+    let help = Paragraph::new(Line::from(vec![
+        Span::styled("↑↓", Style::default().fg(theme.accent)),
+        Span::styled(" navigate  ", Style::default().fg(theme.dim)),
+        Span::styled("Enter", Style::default().fg(theme.accent)),
+        Span::styled(" select  ", Style::default().fg(theme.dim)),
+        Span::styled("Esc", Style::default().fg(theme.accent)),
+        Span::styled(" close", Style::default().fg(theme.dim)),
+    ]))
+    .alignment(Alignment::Center);
+
+    frame.render_widget(help, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PersonaSource;
+
+    fn entry(name: &str) -> PersonaEntry {
+        PersonaEntry {
+            name: name.to_string(),
+            source: PersonaSource::Global,
+        }
+    }
+
+    #[test]
+    fn selects_current_persona_and_clamps_navigation() {
+        let mut dialog = PersonaListDialog::new(vec![entry("ada"), entry("orin")], "Orin");
+        assert_eq!(dialog.selected_persona().unwrap().name, "orin");
+
+        dialog.select_next();
+        assert_eq!(dialog.selected_persona().unwrap().name, "orin");
+        dialog.select_previous();
+        dialog.select_previous();
+        assert_eq!(dialog.selected_persona().unwrap().name, "ada");
+
+        assert!(
+            PersonaListDialog::new(Vec::new(), "orin")
+                .selected_persona()
+                .is_none()
+        );
+    }
+}
@@ -7,15 +7,14 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Paragraph, Wrap},
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use super::command_palette::CENTERED_MAX_WIDTH;
 use crate::core::agent::AgentMode;
+use crate::tui::theme::Theme;
 
-/// Brand colors
-const BRAND_TEAL: Color = Color::Rgb(77, 201, 176);
+/// Plan mode color; build mode uses the theme accent
 const PLAN_PURPLE: Color = Color::Rgb(160, 100, 200);
-const DIMMED: Color = Color::Rgb(100, 100, 110);
-const INPUT_BG: Color = Color::Rgb(22, 24, 28);
 
 /// Rotating placeholder prompts.
 pub const PLACEHOLDERS: &[&str] = &[
@@ -42,40 +41,50 @@ pub enum PromptMode {
 pub fn render_prompt(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     input: &str,
     cursor: usize,
     mode: PromptMode,
     status_left: Option<&str>,
-    status_right: Option<&str>,
+    status_right: Option<Line<'_>>,
     placeholder: Option<&str>,
     agent_mode: AgentMode,
+    vim_label: Option<&str>,
 ) -> ((u16, u16), Rect) {
     match mode {
         PromptMode::Centered => {
             let ph = placeholder.unwrap_or("ask anything...");
-            render_centered_prompt(frame, area, input, cursor, ph, agent_mode)
+            render_centered_prompt(frame, area, theme, input, cursor, ph, agent_mode, vim_label)
         }
         PromptMode::FullWidth => render_full_width_prompt(
             frame,
             area,
+            theme,
             input,
             cursor,
             status_left,
             status_right,
             agent_mode,
+            vim_label,
         ),
     }
 }
 
 /// Render centered prompt for welcome screen.
-#[allow(clippy::cast_possible_truncation, clippy::too_many_lines)]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::too_many_arguments,
+    clippy::too_many_lines
+)]
 fn render_centered_prompt(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     input: &str,
     cursor: usize,
     placeholder: &str,
     agent_mode: AgentMode,
+    vim_label: Option<&str>,
 ) -> ((u16, u16), Rect) {
     // Early return for tiny areas
     if area.width < 5 || area.height < 3 {
@@ -101,36 +110,7 @@ fn render_centered_prompt(
     };
 
     // Calculate cursor's visual line position for scrolling
-    let before_cursor = &input[..cursor];
-    let mut visual_line = 0;
-    let mut cursor_col = 0;
-
-    for (i, line) in input.split('\n').enumerate() {
-        let line_byte_start = input
-            .split('\n')
-            .take(i)
-            .map(|l| l.len() + 1)
-            .sum::<usize>();
-        let line_byte_end = line_byte_start + line.len();
-
-        if cursor <= line_byte_start {
-            break;
-        }
-        if cursor <= line_byte_end {
-            let chars_before = before_cursor[line_byte_start..].chars().count();
-            let wrapped_row = chars_before / text_width;
-            cursor_col = chars_before % text_width;
-            visual_line += wrapped_row;
-            break;
-        }
-        let chars = line.chars().count();
-        let line_wrapped = if chars == 0 {
-            1
-        } else {
-            chars.div_ceil(text_width)
-        };
-        visual_line += line_wrapped;
-    }
+    let (visual_line, cursor_col) = wrapped_cursor_position(input, cursor, text_width);
 
     // Max visible lines (excluding top/bottom padding)
     let max_visible_lines: usize = 10;
@@ -155,11 +135,11 @@ fn render_centered_prompt(
     let mut content: Vec<Line> = vec![Line::from("")];
     // Color input text based on agent mode
     let input_color = match agent_mode {
-        AgentMode::Build => BRAND_TEAL,
+        AgentMode::Build => theme.accent,
         AgentMode::Plan => PLAN_PURPLE,
     };
     let text_style = if input.is_empty() {
-        Style::default().fg(DIMMED)
+        Style::default().fg(theme.dim)
     } else {
         Style::default().fg(input_color)
     };
@@ -167,7 +147,7 @@ fn render_centered_prompt(
     // Only render visible lines based on scroll offset
     for line in wrapped.iter().skip(scroll_offset).take(max_visible_lines) {
         // Pad right side to fill width
-        let right_pad_len = text_width.saturating_sub(line.chars().count());
+        let right_pad_len = text_width.saturating_sub(line.width());
         let right_pad = " ".repeat(right_pad_len + 1); // +1 for right padding
         content.push(Line::from(vec![
             Span::raw(padding),
@@ -179,7 +159,7 @@ fn render_centered_prompt(
 
     // Style based on agent mode
     let border_color = match agent_mode {
-        AgentMode::Build => BRAND_TEAL,
+        AgentMode::Build => theme.accent,
         AgentMode::Plan => PLAN_PURPLE,
     };
 
@@ -187,7 +167,7 @@ fn render_centered_prompt(
         .borders(Borders::LEFT)
         .border_type(BorderType::Plain)
         .border_style(Style::default().fg(border_color))
-        .style(Style::default().bg(INPUT_BG));
+        .style(Style::default().bg(theme.input));
 
     let para = Paragraph::new(content)
         .block(block)
@@ -208,6 +188,13 @@ fn render_centered_prompt(
         let mode_para = Paragraph::new(mode_line);
         let mode_area = Rect::new(mode_x, mode_y, mode_width, 1);
         frame.render_widget(mode_para, mode_area);
+
+        // Vim mode indicator at top left
+        if let Some(label) = vim_label {
+            let label_width = (label.width() as u16).min(prompt_area.width);
+            let label_para = Paragraph::new(Span::styled(label, Style::default().fg(theme.dim)));
+            frame.render_widget(label_para, Rect::new(prompt_area.x, mode_y, label_width, 1));
+        }
     }
 
     // Cursor position relative to visible area
@@ -222,35 +209,73 @@ fn render_centered_prompt(
     ((cursor_x, cursor_y), prompt_area)
 }
 
-/// Wrap a single line of text to fit within the given width.
+/// Wrap a single line of text to fit within the given display width.
+///
+/// Wide characters (CJK, emoji) occupy two columns and are never split
+/// across rows.
 fn wrap_line(text: &str, width: usize) -> Vec<String> {
     if text.is_empty() {
         return vec![String::new()];
     }
 
-    let chars: Vec<char> = text.chars().collect();
     let mut lines = Vec::new();
-    let mut start = 0;
-
-    while start < chars.len() {
-        let end = (start + width).min(chars.len());
-        lines.push(chars[start..end].iter().collect());
-        start = end;
+    let mut current = String::new();
+    let mut current_width = 0;
+
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if current_width + char_width > width && !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+            current_width = 0;
+        }
+        current.push(c);
+        current_width += char_width;
     }
+    lines.push(current);
 
     lines
 }
 
+/// Visual (row, column) of the cursor in input wrapped by [`wrap_line`].
+fn wrapped_cursor_position(input: &str, cursor: usize, width: usize) -> (usize, usize) {
+    let before_cursor = &input[..cursor];
+    let line_start = before_cursor.rfind('\n').map_or(0, |i| i + 1);
+
+    // Rows taken by the lines above the cursor's line
+    let rows_above: usize = input[..line_start]
+        .split_terminator('\n')
+        .map(|line| wrap_line(line, width).len())
+        .sum();
+
+    let wrapped = wrap_line(&before_cursor[line_start..], width);
+    let row = wrapped.len() - 1;
+    let col = wrapped[row].width();
+
+    // The next character starts a new row if it doesn't fit on this one
+    let next_width = input[cursor..]
+        .chars()
+        .next()
+        .filter(|&c| c != '\n')
+        .map_or(1, |c| c.width().unwrap_or(0));
+    if col > 0 && col + next_width > width {
+        (rows_above + row + 1, 0)
+    } else {
+        (rows_above + row, col)
+    }
+}
+
 /// Render full-width prompt for session screen.
-#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_truncation, clippy::too_many_arguments)]
 fn render_full_width_prompt(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     input: &str,
     cursor: usize,
     status_left: Option<&str>,
-    status_right: Option<&str>,
+    status_right: Option<Line<'_>>,
     agent_mode: AgentMode,
+    vim_label: Option<&str>,
 ) -> ((u16, u16), Rect) {
     // Early return for tiny areas
     if area.width < 3 || area.height < 2 {
@@ -267,7 +292,7 @@ fn render_full_width_prompt(
     let before_cursor = &input[..cursor];
     let cursor_line = before_cursor.matches('\n').count();
     let line_start = before_cursor.rfind('\n').map_or(0, |i| i + 1);
-    let cursor_col = before_cursor[line_start..].chars().count();
+    let cursor_col = before_cursor[line_start..].width();
 
     let lines: Vec<&str> = input.split('\n').collect();
 
@@ -285,14 +310,14 @@ fn render_full_width_prompt(
     // Build multiline content with vertical padding
     // Color input text based on agent mode
     let input_color = match agent_mode {
-        AgentMode::Build => BRAND_TEAL,
+        AgentMode::Build => theme.accent,
         AgentMode::Plan => PLAN_PURPLE,
     };
     let mut content: Vec<Line> = vec![Line::from("")];
     if input.is_empty() {
         content.push(Line::from(vec![
             Span::raw(" "),
-            Span::styled("Type here...", Style::default().fg(DIMMED)),
+            Span::styled("Type here...", Style::default().fg(theme.dim)),
         ]));
     } else {
         // Only render visible lines based on scroll offset
@@ -307,7 +332,7 @@ fn render_full_width_prompt(
 
     // Style based on agent mode
     let border_color = match agent_mode {
-        AgentMode::Build => BRAND_TEAL,
+        AgentMode::Build => theme.accent,
         AgentMode::Plan => PLAN_PURPLE,
     };
 
@@ -315,29 +340,36 @@ fn render_full_width_prompt(
         .borders(Borders::LEFT)
         .border_type(BorderType::Plain)
         .border_style(Style::default().fg(border_color))
-        .style(Style::default().bg(INPUT_BG));
+        .style(Style::default().bg(theme.input));
 
     let para = Paragraph::new(content).block(block);
     frame.render_widget(para, chunks[0]);
 
     // Render status line
-    if status_left.is_some() || status_right.is_some() {
+    if status_left.is_some() || status_right.is_some() || vim_label.is_some() {
         let left = status_left.unwrap_or("");
-        let right = status_right.unwrap_or("");
+        let right = status_right.unwrap_or_default();
 
-        // Create spans for left and right status
-        let left_span = Span::styled(format!("  {left}"), Style::default().fg(DIMMED));
-        let right_span = Span::styled(right, Style::default().fg(DIMMED));
+        // Create spans for left and right status, led by the vim mode
+        let label = vim_label.map_or_else(String::new, |label| format!("  {label}"));
+        let label_span = Span::styled(label.as_str(), Style::default().fg(theme.accent));
+        let left_span = Span::styled(format!("  {left}"), Style::default().fg(theme.dim));
 
         // Calculate padding
-        let left_width = left.chars().count() + 2;
-        let right_width = right.chars().count();
+        let left_width = label.width() + left.width() + 2;
+        let right_width = right.width();
         let padding_width = (chunks[1].width as usize)
             .saturating_sub(left_width)
             .saturating_sub(right_width);
         let padding = " ".repeat(padding_width);
 
-        let status_line = Line::from(vec![left_span, Span::raw(padding), right_span]);
+        // Right-hand spans without their own color stay dim
+        let mut spans = vec![label_span, left_span, Span::raw(padding)];
+        spans.extend(right.spans.into_iter().map(|span| {
+            let style = Style::default().fg(theme.dim).patch(span.style);
+            span.style(style)
+        }));
+        let status_line = Line::from(spans);
 
         let status_para = Paragraph::new(status_line);
         frame.render_widget(status_para, chunks[1]);
@@ -391,10 +423,54 @@ mod tests {
 
     #[test]
     fn wrap_line_unicode() {
-        let result = wrap_line("日本語テスト", 3);
+        let result = wrap_line("日本語テスト", 6);
         assert_eq!(result, vec!["日本語", "テスト"]);
     }
 
+    #[test]
+    fn wrap_line_uses_display_width_for_wide_chars() {
+        // Each CJK character is two columns wide
+        assert_eq!(wrap_line("日本語テスト", 5), vec!["日本", "語テ", "スト"]);
+        assert_eq!(wrap_line("ab日本", 3), vec!["ab", "日", "本"]);
+        // Emoji are wide too
+        assert_eq!(wrap_line("hi🎉🎉", 4), vec!["hi🎉", "🎉"]);
+    }
+
+    #[test]
+    fn wrap_line_keeps_wide_char_wider_than_width() {
+        assert_eq!(wrap_line("日本", 1), vec!["日", "本"]);
+    }
+
+    #[test]
+    fn cursor_column_counts_display_width() {
+        let input = "日本語";
+        assert_eq!(wrapped_cursor_position(input, 0, 10), (0, 0));
+        assert_eq!(wrapped_cursor_position(input, "日".len(), 10), (0, 2));
+        assert_eq!(wrapped_cursor_position(input, input.len(), 10), (0, 6));
+
+        let emoji = "a🎉b";
+        assert_eq!(wrapped_cursor_position(emoji, "a🎉".len(), 10), (0, 3));
+    }
+
+    #[test]
+    fn cursor_moves_to_next_row_at_wrap_point() {
+        // "日本" fills a 4-column row, so the cursor after it starts row 1
+        let input = "日本語";
+        assert_eq!(wrapped_cursor_position(input, "日本".len(), 4), (1, 0));
+        assert_eq!(wrapped_cursor_position(input, input.len(), 4), (1, 2));
+
+        // A wide character that doesn't fit moves to the next row
+        let input = "abc日";
+        assert_eq!(wrapped_cursor_position(input, 3, 4), (1, 0));
+        assert_eq!(wrapped_cursor_position(input, input.len(), 4), (1, 2));
+    }
+
+    #[test]
+    fn cursor_row_accounts_for_wrapped_lines_above() {
+        let input = "日本語テスト\n中";
+        assert_eq!(wrapped_cursor_position(input, input.len(), 4), (3, 2));
+    }
+
     #[test]
     fn prompt_mode_equality() {
         assert_eq!(PromptMode::Centered, PromptMode::Centered);
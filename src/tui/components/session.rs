@@ -5,21 +5,27 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
-    widgets::{Paragraph, Wrap},
+    widgets::{Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
 };
+use unicode_width::UnicodeWidthStr;
 
 use super::markdown::parse_markdown_line;
 use super::messages::{render_message_with_scroll, wrapped_line_height};
 use super::prompt::{PromptMode, render_prompt};
-use crate::core::agent::AgentMode;
+use crate::core::agent::{AgentMode, PlanPhase};
 use crate::tui::app::Selection;
 use crate::tui::message::DisplayMessage;
+use crate::tui::search::SearchState;
+use crate::tui::theme::Theme;
 
 /// Horizontal padding for message area.
 pub const MESSAGE_PADDING_X: u16 = 2;
 
-/// Brand colors.
-const DIMMED: Color = Color::Rgb(100, 100, 110);
+/// Share of the context window past which the usage indicator turns yellow.
+const CONTEXT_WARN_RATIO: f64 = 0.75;
+
+/// Share of the context window past which the usage indicator turns red.
+const CONTEXT_CRITICAL_RATIO: f64 = 0.9;
 
 /// Render the session screen with message list and prompt.
 ///
@@ -28,17 +34,23 @@ const DIMMED: Color = Color::Rgb(100, 100, 110);
 pub fn render_session(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     messages: &[DisplayMessage],
     streaming_text: &str,
     input: &str,
     cursor: usize,
     scroll_offset: u16,
+    content_height: u16,
     activity_status: Option<&str>,
     model: &str,
     agent_mode: AgentMode,
+    plan_phase: Option<PlanPhase>,
     selection: Option<&Selection>,
     selected_text: &mut String,
+    search: Option<&SearchState>,
     session_cost: f64,
+    context_usage: Option<(u32, u32)>,
+    vim_label: Option<&str>,
 ) -> ((u16, u16), Rect) {
     // Calculate dynamic prompt height based on input lines
     // Height = top padding (1) + input lines + bottom padding (1) + status bar (1)
@@ -64,12 +76,15 @@ pub fn render_session(
     render_message_list(
         frame,
         chunks[0],
+        theme,
         messages,
         streaming_text,
         scroll_offset,
         selection,
         selected_text,
+        search,
     );
+    render_scrollbar(frame, chunks[0], theme, content_height, scroll_offset);
 
     // Apply same horizontal padding to prompt area for alignment
     let prompt_area = Rect::new(
@@ -81,41 +96,113 @@ pub fn render_session(
 
     // Render prompt with status
     let status_left = activity_status;
-    // Show mode, model, cost, and build version in status
+    // Show mode, model, context use, cost, and build version in status
     let version = crate::build_info::short_version();
     let cost_str = if session_cost > 0.0 {
         format!(" · ${session_cost:.4}")
     } else {
         String::new()
     };
-    let status_right_text = match agent_mode {
-        AgentMode::Build => format!("{model}{cost_str} | {version}"),
-        AgentMode::Plan => format!("plan mode · {model}{cost_str} | {version}"),
+    let mode_str = match (agent_mode, plan_phase) {
+        (AgentMode::Build, _) => String::new(),
+        (AgentMode::Plan, Some(phase)) => format!("plan mode · {} · ", phase.label()),
+        (AgentMode::Plan, None) => "plan mode · ".to_string(),
     };
+    let mut status_right = vec![Span::raw(format!("{mode_str}{model}"))];
+    if let Some((used, window)) = context_usage {
+        status_right.push(Span::raw(" · "));
+        status_right.push(context_span(used, window));
+    }
+    status_right.push(Span::raw(format!("{cost_str} | {version}")));
 
     render_prompt(
         frame,
         prompt_area,
+        theme,
         input,
         cursor,
         PromptMode::FullWidth,
         status_left,
-        Some(&status_right_text),
+        Some(Line::from(status_right)),
         None,
         agent_mode,
+        vim_label,
     )
 }
 
+/// Token count in short form, like "850", "12k" or "1.5M".
+fn format_tokens(tokens: u32) -> String {
+    if tokens < 1_000 {
+        tokens.to_string()
+    } else if tokens < 1_000_000 {
+        format!("{}k", (tokens + 500) / 1_000)
+    } else {
+        let millions = format!("{:.1}", f64::from(tokens) / 1_000_000.0);
+        format!("{}M", millions.strip_suffix(".0").unwrap_or(&millions))
+    }
+}
+
+/// Context use such as "12k / 200k", yellow then red as the window fills.
+fn context_span(used: u32, window: u32) -> Span<'static> {
+    let text = format!("{} / {}", format_tokens(used), format_tokens(window));
+    let ratio = f64::from(used) / f64::from(window.max(1));
+    if ratio > CONTEXT_CRITICAL_RATIO {
+        Span::styled(text, Style::default().fg(Color::Red))
+    } else if ratio > CONTEXT_WARN_RATIO {
+        Span::styled(text, Style::default().fg(Color::Yellow))
+    } else {
+        Span::raw(text)
+    }
+}
+
+/// Scrollbar position for the message list, or `None` when everything fits.
+fn scrollbar_state(
+    content_height: u16,
+    viewport_height: u16,
+    scroll_offset: u16,
+) -> Option<ScrollbarState> {
+    let max_scroll = content_height
+        .checked_sub(viewport_height)
+        .filter(|&max| max > 0)?;
+    Some(
+        ScrollbarState::new(usize::from(max_scroll) + 1)
+            .viewport_content_length(usize::from(viewport_height))
+            .position(usize::from(scroll_offset.min(max_scroll))),
+    )
+}
+
+/// Render a thin scrollbar on the right edge of the message area when it overflows.
+fn render_scrollbar(
+    frame: &mut Frame,
+    area: Rect,
+    theme: &Theme,
+    content_height: u16,
+    scroll_offset: u16,
+) {
+    let Some(mut state) = scrollbar_state(content_height, area.height, scroll_offset) else {
+        return;
+    };
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .track_symbol(None)
+        .thumb_symbol("▐")
+        .thumb_style(Style::default().fg(theme.dim));
+    frame.render_stateful_widget(scrollbar, area, &mut state);
+}
+
 /// Render the scrollable message list.
 #[allow(clippy::cast_possible_truncation, clippy::too_many_arguments)]
 fn render_message_list(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     messages: &[DisplayMessage],
     streaming_text: &str,
     scroll_offset: u16,
     selection: Option<&Selection>,
     selected_text: &mut String,
+    search: Option<&SearchState>,
 ) {
     // Apply padding to message area
     let padded_area = Rect::new(
@@ -129,7 +216,7 @@ fn render_message_list(
         // Render empty state
         let empty_msg = Paragraph::new(Line::from(Span::styled(
             "No messages yet. Start typing to begin.",
-            Style::default().fg(DIMMED),
+            Style::default().fg(theme.dim),
         )));
         frame.render_widget(empty_msg, padded_area);
         return;
@@ -140,7 +227,7 @@ fn render_message_list(
     // screen_y tracks where we're rendering on screen
     let mut content_y: u16 = 0;
 
-    for message in messages {
+    for (index, message) in messages.iter().enumerate() {
         let msg_height = estimate_message_height(message, padded_area.width);
         let msg_end = content_y + msg_height;
 
@@ -173,10 +260,12 @@ fn render_message_list(
         render_message_with_scroll(
             frame,
             msg_area,
+            theme,
             message,
             clip_top,
             sel_bounds,
             selected_text,
+            search.and_then(|search| search.highlight_for(index)),
         );
 
         content_y = msg_end + 1; // +1 for spacing
@@ -242,7 +331,9 @@ fn render_message_list(
             // Skip clipped lines at the top
             let visible_lines: Vec<Line> = all_lines.into_iter().skip(clip_top as usize).collect();
 
-            let para = Paragraph::new(visible_lines).wrap(Wrap { trim: false });
+            let para = Paragraph::new(visible_lines)
+                .style(Style::default().fg(theme.assistant))
+                .wrap(Wrap { trim: false });
             frame.render_widget(para, streaming_area);
         }
     }
@@ -273,7 +364,7 @@ pub fn calculate_content_height(
         let width = width.max(1) as usize;
         let streaming_height: u16 = streaming_text
             .lines()
-            .map(|line| wrapped_line_height(line.chars().count(), width))
+            .map(|line| wrapped_line_height(line.width(), width))
             .sum::<u16>()
             .max(1);
         total = total.saturating_add(streaming_height);
@@ -290,6 +381,7 @@ mod tests {
         DisplayMessage::User {
             text: text.to_string(),
             timestamp: None,
+            id: None,
         }
     }
 
@@ -299,6 +391,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn context_indicator_warns_as_window_fills() {
+        assert_eq!(format_tokens(850), "850");
+        assert_eq!(format_tokens(12_345), "12k");
+        assert_eq!(format_tokens(1_500_000), "1.5M");
+        assert_eq!(format_tokens(2_000_000), "2M");
+
+        let span = context_span(12_000, 200_000);
+        assert_eq!(span.content, "12k / 200k");
+        assert_eq!(span.style.fg, None);
+        assert_eq!(context_span(160_000, 200_000).style.fg, Some(Color::Yellow));
+        assert_eq!(context_span(190_000, 200_000).style.fg, Some(Color::Red));
+    }
+
     #[test]
     fn estimate_height_single_line() {
         let msg = user_message("hello");
@@ -331,6 +437,15 @@ mod tests {
         assert_eq!(height, 1);
     }
 
+    #[test]
+    fn scrollbar_only_when_content_overflows() {
+        assert!(scrollbar_state(10, 20, 0).is_none());
+        assert!(scrollbar_state(20, 20, 0).is_none());
+
+        let state = scrollbar_state(50, 20, 45).unwrap();
+        assert_eq!(state.get_position(), 30);
+    }
+
     #[test]
     fn calculate_content_height_empty() {
         let height = calculate_content_height(&[], "", 80);
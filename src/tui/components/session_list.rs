@@ -8,13 +8,13 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
 };
 
-use crate::core::session::{Session, SessionManager};
+use crate::core::color;
+use crate::core::session::{Recency, Session, SessionManager};
+use crate::tui::theme::Theme;
 
 /// Brand colors.
-const BRAND_TEAL: Color = Color::Rgb(77, 201, 176);
-const DIMMED: Color = Color::Rgb(100, 100, 110);
-const DIALOG_BG: Color = Color::Rgb(30, 32, 38);
-const SELECTED_BG: Color = Color::Rgb(45, 48, 55);
+const RECENT_TITLE: Color = Color::Rgb(240, 240, 245);
+const WEEK_TITLE: Color = Color::Rgb(180, 180, 190);
 
 /// Session list dialog state.
 pub struct SessionListDialog {
@@ -142,6 +142,14 @@ impl SessionListDialog {
         &self.sessions
     }
 
+    /// Add a session to the top of the list and select it.
+    pub fn add_session(&mut self, session: Session) {
+        self.sessions.insert(0, session);
+        self.filter.clear();
+        self.selected = 0;
+        self.list_state.select(Some(0));
+    }
+
     /// Remove a session from the list by ID.
     pub fn remove_session(&mut self, session_id: &str) {
         self.sessions.retain(|s| s.id != session_id);
@@ -186,9 +194,18 @@ fn format_relative_time(timestamp: i64) -> String {
     }
 }
 
+/// Title style for an unselected session, brighter for recent work.
+fn recency_style(recency: Recency, theme: &Theme) -> Style {
+    match recency {
+        Recency::Today => Style::default().fg(RECENT_TITLE),
+        Recency::ThisWeek => Style::default().fg(WEEK_TITLE),
+        Recency::Older => Style::default().fg(theme.dim),
+    }
+}
+
 /// Render the session list dialog.
 #[allow(clippy::cast_possible_truncation)]
-pub fn render_session_list(frame: &mut Frame, dialog: &mut SessionListDialog) {
+pub fn render_session_list(frame: &mut Frame, dialog: &mut SessionListDialog, theme: &Theme) {
     let area = frame.area();
 
     // Center the dialog
@@ -207,8 +224,8 @@ pub fn render_session_list(frame: &mut Frame, dialog: &mut SessionListDialog) {
         .title(" Sessions ")
         .title_alignment(Alignment::Center)
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(BRAND_TEAL))
-        .style(Style::default().bg(DIALOG_BG));
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.dialog));
 
     let inner = block.inner(dialog_area);
     frame.render_widget(block, dialog_area);
@@ -225,25 +242,27 @@ pub fn render_session_list(frame: &mut Frame, dialog: &mut SessionListDialog) {
     let search_text = if dialog.filter.is_empty() {
         Line::from(Span::styled(
             "Type to filter...",
-            Style::default().fg(DIMMED),
+            Style::default().fg(theme.dim),
         ))
     } else {
         Line::from(Span::styled(
             dialog.filter(),
-            Style::default().fg(Color::White),
+            Style::default().fg(theme.text),
         ))
     };
 
     let search_block = Block::default()
         .title(" Search ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(DIMMED));
+        .border_style(Style::default().fg(theme.dim));
 
     let search = Paragraph::new(search_text).block(search_block);
     frame.render_widget(search, chunks[0]);
 
     // Session list - build items from owned data to avoid borrow conflicts
     let selected_idx = dialog.selected;
+    let now = chrono::Utc::now().timestamp_millis();
+    let use_color = color::enabled();
     let items: Vec<ListItem> = dialog
         .sessions
         .iter()
@@ -264,17 +283,19 @@ pub fn render_session_list(frame: &mut Frame, dialog: &mut SessionListDialog) {
 
             let style = if is_selected {
                 Style::default()
-                    .bg(SELECTED_BG)
-                    .fg(Color::White)
+                    .bg(theme.selection)
+                    .fg(theme.text)
                     .add_modifier(Modifier::BOLD)
+            } else if use_color {
+                recency_style(session.time.recency(now), theme)
             } else {
-                Style::default().fg(Color::White)
+                Style::default().fg(theme.text)
             };
 
             let time_style = if is_selected {
-                Style::default().bg(SELECTED_BG).fg(DIMMED)
+                Style::default().bg(theme.selection).fg(theme.dim)
             } else {
-                Style::default().fg(DIMMED)
+                Style::default().fg(theme.dim)
             };
 
             let line = Line::from(vec![
@@ -291,23 +312,25 @@ pub fn render_session_list(frame: &mut Frame, dialog: &mut SessionListDialog) {
     let list = List::new(items).block(
         Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(DIMMED)),
+            .border_style(Style::default().fg(theme.dim)),
     );
 
     frame.render_stateful_widget(list, chunks[1], dialog.list_state_mut());
 
     // Help text
     let help = Paragraph::new(Line::from(vec![
-        Span::styled("↑↓", Style::default().fg(BRAND_TEAL)),
-        Span::styled(" navigate  ", Style::default().fg(DIMMED)),
-        Span::styled("Enter", Style::default().fg(BRAND_TEAL)),
-        Span::styled(" select  ", Style::default().fg(DIMMED)),
-        Span::styled("n", Style::default().fg(BRAND_TEAL)),
-        Span::styled(" new  ", Style::default().fg(DIMMED)),
-        Span::styled("d", Style::default().fg(BRAND_TEAL)),
-        Span::styled(" delete  ", Style::default().fg(DIMMED)),
-        Span::styled("Esc", Style::default().fg(BRAND_TEAL)),
-        Span::styled(" close", Style::default().fg(DIMMED)),
+        Span::styled("↑↓", Style::default().fg(theme.accent)),
+        Span::styled(" navigate  ", Style::default().fg(theme.dim)),
+        Span::styled("Enter", Style::default().fg(theme.accent)),
+        Span::styled(" select  ", Style::default().fg(theme.dim)),
+        Span::styled("n", Style::default().fg(theme.accent)),
+        Span::styled(" new  ", Style::default().fg(theme.dim)),
+        Span::styled("f", Style::default().fg(theme.accent)),
+        Span::styled(" fork  ", Style::default().fg(theme.dim)),
+        Span::styled("d", Style::default().fg(theme.accent)),
+        Span::styled(" delete  ", Style::default().fg(theme.dim)),
+        Span::styled("Esc", Style::default().fg(theme.accent)),
+        Span::styled(" close", Style::default().fg(theme.dim)),
     ]))
     .alignment(Alignment::Center);
 
@@ -16,12 +16,11 @@ use ratatui::{
 use super::prompt::{PromptMode, render_prompt};
 use crate::core::agent::AgentMode;
 use crate::tui::app::{LOGO_LINES, LOGO_SHADOW};
+use crate::tui::theme::Theme;
 
-/// Brand colors
+/// Brand colors; the logo itself uses the theme accent
 const SHADOW_COLOR: Color = Color::Rgb(30, 80, 70);
-const LOGO_COLOR: Color = Color::Rgb(77, 201, 176);
 const TAGLINE_COLOR: Color = Color::Rgb(140, 140, 150);
-const CWD_COLOR: Color = Color::Rgb(100, 100, 110);
 const TIP_COLOR: Color = Color::Rgb(180, 160, 100);
 
 /// Minimum width to show footer links and version.
@@ -34,13 +33,16 @@ const MIN_WIDTH_FOR_FOOTER: u16 = 60;
 pub fn render_welcome(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     tagline: &str,
     tip: &str,
+    submit_hint: &str,
     input: &str,
     cursor: usize,
     placeholder: &str,
     agent_mode: AgentMode,
     model: &str,
+    vim_label: Option<&str>,
 ) -> ((u16, u16), Rect) {
     // Early return for tiny terminals
     if area.width < 10 || area.height < 5 {
@@ -73,7 +75,7 @@ pub fn render_welcome(
     }
 
     // Render main logo (skip spaces to preserve shadow)
-    let logo_style = Style::default().fg(LOGO_COLOR);
+    let logo_style = Style::default().fg(theme.accent);
     for (i, logo_line) in LOGO_LINES.iter().enumerate() {
         let y = start_y + i as u16;
         if y < area.y + area.height {
@@ -103,7 +105,7 @@ pub fn render_welcome(
         };
         let cwd_width = cwd_text.chars().count() as u16;
         let cwd_x = area.x + area.width.saturating_sub(cwd_width) / 2;
-        let cwd_span = Span::styled(cwd_text, Style::default().fg(CWD_COLOR));
+        let cwd_span = Span::styled(cwd_text, Style::default().fg(theme.dim));
         let para = Paragraph::new(Line::from(cwd_span));
         let clamped = clamp_rect(Rect::new(cwd_x, cwd_y, cwd_width + 2, 1), area);
         frame.render_widget(para, clamped);
@@ -115,7 +117,7 @@ pub fn render_welcome(
         let model_text = format!("using {model}");
         let model_width = model_text.chars().count() as u16;
         let model_x = area.x + area.width.saturating_sub(model_width) / 2;
-        let model_span = Span::styled(model_text, Style::default().fg(CWD_COLOR));
+        let model_span = Span::styled(model_text, Style::default().fg(theme.dim));
         let para = Paragraph::new(Line::from(model_span));
         let clamped = clamp_rect(Rect::new(model_x, model_y, model_width + 2, 1), area);
         frame.render_widget(para, clamped);
@@ -152,9 +154,22 @@ pub fn render_welcome(
             frame.render_widget(tip_para, clamped);
         }
 
+        // Render submit key hint below the tip
+        let hint_y = tip_y + 1;
+        if !submit_hint.is_empty() && hint_y < footer_y {
+            let hint_width = submit_hint.chars().count() as u16;
+            let hint_x = area.x + area.width.saturating_sub(hint_width) / 2;
+            let hint_para = Paragraph::new(Line::from(Span::styled(
+                submit_hint,
+                Style::default().fg(theme.dim),
+            )));
+            let clamped = clamp_rect(Rect::new(hint_x, hint_y, hint_width, 1), area);
+            frame.render_widget(hint_para, clamped);
+        }
+
         // Render socials in bottom left corner
         let links_text = "x.com/omnidotdev · discord.gg/omnidotdev · docs.omni.dev";
-        let links_style = Style::default().fg(CWD_COLOR);
+        let links_style = Style::default().fg(theme.dim);
         render_text(
             frame.buffer_mut(),
             area,
@@ -168,7 +183,7 @@ pub fn render_welcome(
         let version = format!("early access | {}", crate::build_info::short_version());
         let version_width = version.chars().count() as u16;
         let version_x = area.x + area.width.saturating_sub(version_width + 1);
-        let version_span = Span::styled(version, Style::default().fg(CWD_COLOR));
+        let version_span = Span::styled(version, Style::default().fg(theme.dim));
         let version_para = Paragraph::new(Line::from(version_span));
         let clamped = clamp_rect(Rect::new(version_x, footer_y, version_width, 1), area);
         frame.render_widget(version_para, clamped);
@@ -177,6 +192,7 @@ pub fn render_welcome(
     render_prompt(
         frame,
         prompt_area,
+        theme,
         input,
         cursor,
         PromptMode::Centered,
@@ -184,6 +200,7 @@ pub fn render_welcome(
         None,
         Some(placeholder),
         agent_mode,
+        vim_label,
     )
 }
 
@@ -0,0 +1,155 @@
+//! Grapheme-aware text editing helpers.
+//!
+//! Cursors are byte offsets that always sit on grapheme cluster boundaries,
+//! so emoji ZWJ sequences and combining marks move and delete as one unit.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Byte offset of the grapheme boundary before `cursor`.
+#[must_use]
+pub fn prev_grapheme(text: &str, cursor: usize) -> usize {
+    text[..cursor]
+        .grapheme_indices(true)
+        .next_back()
+        .map_or(0, |(i, _)| i)
+}
+
+/// Byte offset of the grapheme boundary after `cursor`.
+#[must_use]
+pub fn next_grapheme(text: &str, cursor: usize) -> usize {
+    text[cursor..]
+        .graphemes(true)
+        .next()
+        .map_or(text.len(), |g| cursor + g.len())
+}
+
+/// Byte offset of the start of the word before `cursor`.
+///
+/// Skips whitespace, then word graphemes.
+#[must_use]
+pub fn prev_word_start(text: &str, cursor: usize) -> usize {
+    let mut graphemes = text[..cursor].grapheme_indices(true).rev().peekable();
+
+    while graphemes.next_if(|(_, g)| is_whitespace(g)).is_some() {}
+    let mut start = graphemes.peek().map_or(0, |&(i, _)| i);
+    while let Some((i, _)) = graphemes.next_if(|(_, g)| !is_whitespace(g)) {
+        start = i;
+    }
+
+    start
+}
+
+/// Byte offset of the start of the word after `cursor`.
+///
+/// Skips the current word, then whitespace.
+#[must_use]
+pub fn next_word_start(text: &str, cursor: usize) -> usize {
+    let mut graphemes = text[cursor..].grapheme_indices(true).peekable();
+
+    while graphemes.next_if(|(_, g)| !is_whitespace(g)).is_some() {}
+    while graphemes.next_if(|(_, g)| is_whitespace(g)).is_some() {}
+
+    graphemes.peek().map_or(text.len(), |&(i, _)| cursor + i)
+}
+
+/// Number of graphemes in `text`.
+#[must_use]
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Byte offset of the `n`th grapheme in `text`, or its length if shorter.
+#[must_use]
+pub fn grapheme_offset(text: &str, n: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(n)
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// Byte offset of the start of the line containing `cursor`.
+#[must_use]
+pub fn line_start(text: &str, cursor: usize) -> usize {
+    text[..cursor].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Byte offset of the end of the line containing `cursor`, before its newline.
+#[must_use]
+pub fn line_end(text: &str, cursor: usize) -> usize {
+    text[cursor..].find('\n').map_or(text.len(), |i| cursor + i)
+}
+
+/// Byte range of the line containing `cursor` along with one adjoining newline.
+///
+/// Takes the trailing newline, or the leading one on the last line, so that
+/// removing the range leaves the surrounding lines joined correctly.
+#[must_use]
+pub fn line_with_newline(text: &str, cursor: usize) -> std::ops::Range<usize> {
+    let start = line_start(text, cursor);
+    let end = line_end(text, cursor);
+    if end < text.len() {
+        start..end + 1
+    } else {
+        start.saturating_sub(1)..end
+    }
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILY: &str = "👨‍👩‍👧";
+    const E_ACUTE: &str = "e\u{301}";
+
+    #[test]
+    fn steps_over_zwj_sequences_and_combining_marks() {
+        let text = format!("a{FAMILY}{E_ACUTE}b");
+        let after_a = 1;
+        let after_family = after_a + FAMILY.len();
+        let after_accent = after_family + E_ACUTE.len();
+
+        assert_eq!(next_grapheme(&text, after_a), after_family);
+        assert_eq!(next_grapheme(&text, after_family), after_accent);
+        assert_eq!(prev_grapheme(&text, after_accent), after_family);
+        assert_eq!(prev_grapheme(&text, after_family), after_a);
+        assert_eq!(prev_grapheme(&text, after_a), 0);
+        assert_eq!(next_grapheme(&text, text.len()), text.len());
+        assert_eq!(prev_grapheme(&text, 0), 0);
+    }
+
+    #[test]
+    fn word_motion_respects_cluster_boundaries() {
+        let text = format!("hi {FAMILY}{E_ACUTE}  there");
+        let word = 3;
+        let next = text.find("there").unwrap();
+
+        assert_eq!(prev_word_start(&text, text.len()), next);
+        assert_eq!(prev_word_start(&text, next), word);
+        assert_eq!(prev_word_start(&text, word), 0);
+        assert_eq!(next_word_start(&text, 0), word);
+        assert_eq!(next_word_start(&text, word), next);
+        assert_eq!(next_word_start(&text, next), text.len());
+    }
+
+    #[test]
+    fn line_bounds_and_removal_range() {
+        let text = "one\ntwo\nthree";
+        let in_two = 5;
+        assert_eq!(line_start(text, in_two), 4);
+        assert_eq!(line_end(text, in_two), 7);
+        assert_eq!(line_with_newline(text, in_two), 4..8);
+        assert_eq!(line_with_newline(text, text.len()), 7..13);
+        assert_eq!(line_with_newline("solo", 2), 0..4);
+    }
+
+    #[test]
+    fn columns_count_graphemes() {
+        let text = format!("{FAMILY}{E_ACUTE}x");
+        assert_eq!(grapheme_count(&text), 3);
+        assert_eq!(grapheme_offset(&text, 1), FAMILY.len());
+        assert_eq!(grapheme_offset(&text, 5), text.len());
+    }
+}
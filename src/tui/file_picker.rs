@@ -0,0 +1,259 @@
+//! Project files offered by the `@` mention picker.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use ignore::WalkBuilder;
+
+/// Most matches shown in the dropdown.
+const MAX_MATCHES: usize = 8;
+
+/// How long the query must stay unchanged before matches are recomputed.
+const DEBOUNCE: Duration = Duration::from_millis(60);
+
+/// Score for each matched character.
+const SCORE_MATCH: i32 = 16;
+
+/// Bonus for a match at the start of the path or of a path segment or word.
+const BONUS_BOUNDARY: i32 = 10;
+
+/// Bonus for a match right after the previous one.
+const BONUS_CONSECUTIVE: i32 = 8;
+
+/// Penalty for each character skipped between two matches.
+const PENALTY_GAP: i32 = 1;
+
+/// Files and directories under `root` as `/`-separated relative paths, sorted.
+///
+/// Directories end in `/`. Walks with the same rules as git: `.gitignore`,
+/// `.git/info/exclude` and the global excludes file are respected, and
+/// hidden entries are skipped. Stops after `max_files` entries so huge trees
+/// stay responsive.
+#[must_use]
+pub fn list_project_files(root: &Path, max_files: usize) -> Vec<String> {
+    let walker = WalkBuilder::new(root)
+        // Honor .gitignore even outside a git checkout
+        .require_git(false)
+        .build();
+
+    let mut files: Vec<String> = walker
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth() > 0)
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).ok()?;
+            let path = relative.to_string_lossy().replace('\\', "/");
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            Some(if is_dir { format!("{path}/") } else { path })
+        })
+        .take(max_files)
+        .collect();
+    files.sort();
+    files
+}
+
+/// Score `candidate` against `query` as a fuzzy subsequence match.
+///
+/// Characters of the query must appear in order, ignoring ASCII case. Matches
+/// at segment starts and runs of adjacent matches score higher, gaps lower.
+/// Returns `None` when the query isn't a subsequence of the candidate.
+#[must_use]
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let query: Vec<char> = query.chars().map(|c| c.to_ascii_lowercase()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+    let lowered: Vec<char> = chars.iter().map(char::to_ascii_lowercase).collect();
+
+    let bonus = |j: usize| -> i32 {
+        let at_boundary = j == 0
+            || matches!(chars[j - 1], '/' | '_' | '-' | '.' | ' ')
+            || (chars[j - 1].is_lowercase() && chars[j].is_uppercase());
+        if at_boundary { BONUS_BOUNDARY } else { 0 }
+    };
+
+    // best[j]: best score with the current query char matched at position j
+    let mut best: Vec<Option<i32>> = lowered
+        .iter()
+        .enumerate()
+        .map(|(j, &c)| (c == query[0]).then(|| SCORE_MATCH + bonus(j)))
+        .collect();
+
+    for &qc in &query[1..] {
+        let mut next = vec![None; chars.len()];
+        // Best of best[k] + k * gap over k < j - 1, so a jump from k costs its gap
+        let mut gapped: Option<i32> = None;
+        for j in 1..chars.len() {
+            let adjacent = best[j - 1].map(|score| score + BONUS_CONSECUTIVE);
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let from_gap = gapped.map(|score| score - (j as i32 - 1) * PENALTY_GAP);
+            if lowered[j] == qc {
+                next[j] = adjacent
+                    .max(from_gap)
+                    .map(|score| score + SCORE_MATCH + bonus(j));
+            }
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let shifted = best[j - 1].map(|score| score + j as i32 * PENALTY_GAP);
+            gapped = gapped.max(shifted);
+        }
+        best = next;
+    }
+
+    best.into_iter().flatten().max()
+}
+
+/// Fuzzy-filtered view of the project files, recomputed as the query settles.
+#[derive(Debug)]
+pub struct FilePicker {
+    /// Every listed path.
+    entries: Vec<String>,
+    /// Query the current matches were computed for.
+    matched_query: Option<String>,
+    /// Indices into `entries`, best match first.
+    matches: Vec<usize>,
+    /// Latest query seen and when it was first seen.
+    pending: Option<(String, Instant)>,
+}
+
+impl FilePicker {
+    /// Picker over `entries`, with no query applied yet.
+    #[must_use]
+    pub const fn new(entries: Vec<String>) -> Self {
+        Self {
+            entries,
+            matched_query: None,
+            matches: Vec::new(),
+            pending: None,
+        }
+    }
+
+    /// Bring the matches up to date with `query`.
+    ///
+    /// The first query is matched right away. After that a changed query is
+    /// only matched once it has stayed the same for a short moment, so fast
+    /// typing isn't slowed down by rescoring every keystroke.
+    pub fn update(&mut self, query: &str, now: Instant) {
+        if self.matched_query.as_deref() == Some(query) {
+            self.pending = None;
+            return;
+        }
+        if self.matched_query.is_some() {
+            match &self.pending {
+                Some((pending, since)) if pending == query => {
+                    if now.duration_since(*since) < DEBOUNCE {
+                        return;
+                    }
+                }
+                _ => {
+                    self.pending = Some((query.to_string(), now));
+                    return;
+                }
+            }
+        }
+        self.rescore(query);
+    }
+
+    /// Match a query still waiting out the debounce right away.
+    ///
+    /// Completing a mention must use the query as typed, not the last one
+    /// that settled.
+    pub fn flush(&mut self) {
+        if let Some((query, _)) = self.pending.take() {
+            self.rescore(&query);
+        }
+    }
+
+    /// Score every entry against `query`.
+    fn rescore(&mut self, query: &str) {
+        let mut scored: Vec<(i32, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, path)| fuzzy_score(query, path).map(|score| (score, i)))
+            .collect();
+        // Best score first, then shorter paths, then listing order
+        scored.sort_by_key(|&(score, i)| (std::cmp::Reverse(score), self.entries[i].len(), i));
+        self.matches = scored
+            .into_iter()
+            .take(MAX_MATCHES)
+            .map(|(_, i)| i)
+            .collect();
+        self.matched_query = Some(query.to_string());
+        self.pending = None;
+    }
+
+    /// Matching paths, best first.
+    #[must_use]
+    pub fn matches(&self) -> Vec<&str> {
+        self.matches
+            .iter()
+            .map(|&i| self.entries[i].as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_gitignored_and_hidden_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        std::fs::write(dir.path().join("README.md"), "").unwrap();
+        std::fs::write(dir.path().join("debug.log"), "").unwrap();
+        std::fs::write(dir.path().join("target/app"), "").unwrap();
+
+        let files = list_project_files(dir.path(), 100);
+        assert_eq!(files, vec!["README.md", "src/", "src/main.rs"]);
+    }
+
+    #[test]
+    fn stops_at_max_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..5 {
+            std::fs::write(dir.path().join(format!("{i}.txt")), "").unwrap();
+        }
+        assert_eq!(list_project_files(dir.path(), 3).len(), 3);
+    }
+
+    #[test]
+    fn fuzzy_matches_rank_segment_starts_first() {
+        assert!(fuzzy_score("agntmod", "src/core/agent/mod.rs").is_some());
+        assert!(fuzzy_score("xyz", "src/core/agent/mod.rs").is_none());
+
+        let mut picker = FilePicker::new(vec![
+            "src/core/agent/permission.rs".to_string(),
+            "src/core/agent/mod.rs".to_string(),
+            "src/core/agent/".to_string(),
+            "docs/management.md".to_string(),
+        ]);
+        picker.update("agntmod", Instant::now());
+        assert_eq!(picker.matches(), vec!["src/core/agent/mod.rs"]);
+
+        let mut picker = FilePicker::new(picker.entries);
+        picker.update("agent", Instant::now());
+        assert_eq!(picker.matches()[0], "src/core/agent/");
+    }
+
+    #[test]
+    fn changed_query_waits_for_debounce() {
+        let start = Instant::now();
+        let mut picker = FilePicker::new(vec!["src/main.rs".to_string(), "README.md".to_string()]);
+        picker.update("", start);
+        assert_eq!(picker.matches().len(), 2);
+
+        picker.update("read", start);
+        assert_eq!(picker.matches().len(), 2);
+        picker.update("read", start + DEBOUNCE);
+        assert_eq!(picker.matches(), vec!["README.md"]);
+
+        picker.update("main", start + DEBOUNCE);
+        assert_eq!(picker.matches(), vec!["README.md"]);
+        picker.flush();
+        assert_eq!(picker.matches(), vec!["src/main.rs"]);
+    }
+}
@@ -11,6 +11,7 @@ pub mod icons {
     pub const SEARCH: &str = "○";
     pub const DEFAULT: &str = "●";
     pub const ERROR: &str = "✗";
+    pub const GROUP: &str = "▸";
     #[allow(dead_code)]
     pub const SUCCESS: &str = "✓";
 }
@@ -37,6 +38,8 @@ pub enum DisplayMessage {
         text: String,
         /// When the message was sent
         timestamp: Option<SystemTime>,
+        /// ID the prompt was sent to the agent as, if it can be edited
+        id: Option<String>,
     },
     /// Assistant response without border
     Assistant {
@@ -54,6 +57,13 @@ pub enum DisplayMessage {
         /// Whether the tool encountered an error
         is_error: bool,
     },
+    /// Collapsed run of consecutive tool calls
+    ToolGroup {
+        /// Summary of the folded calls (e.g., "8 file reads, 2 searches")
+        summary: String,
+        /// Index of the first folded call in the full transcript
+        first: usize,
+    },
 }
 
 impl DisplayMessage {
@@ -63,6 +73,17 @@ impl DisplayMessage {
         Self::User {
             text: text.into(),
             timestamp: Some(SystemTime::now()),
+            id: None,
+        }
+    }
+
+    /// Create a user message for a prompt sent to the agent as `id`
+    #[must_use]
+    pub fn prompt(id: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::User {
+            text: text.into(),
+            timestamp: Some(SystemTime::now()),
+            id: Some(id.into()),
         }
     }
 
@@ -100,6 +121,219 @@ impl DisplayMessage {
     }
 }
 
+/// Replace the messages the agent summarized with `notice`.
+///
+/// The agent keeps history from `kept_replies` replies and tool calls ahead
+/// of the prompt `kept_prompt`, or ahead of the end without one. Everything
+/// before that point is folded.
+pub fn fold_compacted(
+    messages: &mut Vec<DisplayMessage>,
+    notice: DisplayMessage,
+    kept_prompt: Option<&str>,
+    kept_replies: usize,
+) {
+    let mut start = kept_prompt
+        .and_then(|kept| {
+            messages
+                .iter()
+                .position(|m| matches!(m, DisplayMessage::User { id: Some(id), .. } if id == kept))
+        })
+        .unwrap_or(messages.len());
+
+    // Step back over the kept replies, passing notices that aren't prompts
+    let mut remaining = kept_replies;
+    while remaining > 0 && start > 0 {
+        match &messages[start - 1] {
+            DisplayMessage::User { id: Some(_), .. } => break,
+            DisplayMessage::Assistant { .. } | DisplayMessage::Tool { .. } => remaining -= 1,
+            DisplayMessage::User { .. } | DisplayMessage::ToolGroup { .. } => {}
+        }
+        start -= 1;
+    }
+    messages.splice(..start, [notice]);
+}
+
+/// Fold runs of at least `threshold` consecutive successful tool calls into summaries.
+///
+/// Failed tool calls break a run so errors stay visible. Runs whose first call
+/// is at an index in `expanded` are left as-is.
+#[must_use]
+pub fn collapse_tool_runs(
+    messages: &[DisplayMessage],
+    threshold: usize,
+    expanded: &[usize],
+) -> Vec<DisplayMessage> {
+    let mut collapsed = Vec::with_capacity(messages.len());
+    let mut run: Vec<&DisplayMessage> = Vec::new();
+    let mut run_start = 0;
+
+    let flush = |run: &mut Vec<&DisplayMessage>, first: usize, out: &mut Vec<DisplayMessage>| {
+        if run.len() >= threshold.max(1) && !expanded.contains(&first) {
+            let names: Vec<&str> = run
+                .iter()
+                .filter_map(|m| match m {
+                    DisplayMessage::Tool { name, .. } => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect();
+            out.push(DisplayMessage::ToolGroup {
+                summary: summarize_tools(&names),
+                first,
+            });
+        } else {
+            out.extend(run.iter().map(|m| (*m).clone()));
+        }
+        run.clear();
+    };
+
+    for (index, message) in messages.iter().enumerate() {
+        if matches!(
+            message,
+            DisplayMessage::Tool {
+                is_error: false,
+                ..
+            }
+        ) {
+            if run.is_empty() {
+                run_start = index;
+            }
+            run.push(message);
+        } else {
+            flush(&mut run, run_start, &mut collapsed);
+            collapsed.push(message.clone());
+        }
+    }
+    flush(&mut run, run_start, &mut collapsed);
+
+    collapsed
+}
+
+/// Conversation messages as shown, keeping the collapsed view between frames.
+///
+/// The view is rebuilt only after the messages, the collapse threshold or the
+/// expanded runs change.
+#[derive(Debug, Default)]
+pub struct Transcript {
+    /// Every message, in order.
+    messages: Vec<DisplayMessage>,
+    /// Runs expanded on demand, by the index of their first call.
+    expanded: Vec<usize>,
+    /// Threshold the view was last refreshed with.
+    threshold: Option<usize>,
+    /// Collapsed view, built lazily while a threshold is set.
+    collapsed: Option<Vec<DisplayMessage>>,
+}
+
+impl Transcript {
+    /// Append a message.
+    pub fn push(&mut self, message: DisplayMessage) {
+        self.messages.push(message);
+        self.collapsed = None;
+    }
+
+    /// Keep the first `len` messages.
+    pub fn truncate(&mut self, len: usize) {
+        self.messages.truncate(len);
+        self.expanded.retain(|&first| first < len);
+        self.collapsed = None;
+    }
+
+    /// Remove every message.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.expanded.clear();
+        self.collapsed = None;
+    }
+
+    /// Change the messages in place, such as folding compacted history.
+    ///
+    /// Messages may move, so expanded runs fold again.
+    pub fn edit(&mut self, edit: impl FnOnce(&mut Vec<DisplayMessage>)) {
+        edit(&mut self.messages);
+        self.expanded.clear();
+        self.collapsed = None;
+    }
+
+    /// Show the run starting at message `first` call by call.
+    pub fn expand_run(&mut self, first: usize) {
+        if !self.expanded.contains(&first) {
+            self.expanded.push(first);
+            self.collapsed = None;
+        }
+    }
+
+    /// Bring the view up to date, folding runs of `threshold` calls if set.
+    pub fn refresh_view(&mut self, threshold: Option<usize>) {
+        if self.threshold != threshold {
+            self.threshold = threshold;
+            self.collapsed = None;
+        }
+        if let (Some(threshold), None) = (threshold, &self.collapsed) {
+            self.collapsed = Some(collapse_tool_runs(
+                &self.messages,
+                threshold,
+                &self.expanded,
+            ));
+        }
+    }
+
+    /// Messages to render, as of the last [`Transcript::refresh_view`].
+    #[must_use]
+    pub fn view(&self) -> &[DisplayMessage] {
+        self.collapsed.as_deref().unwrap_or(&self.messages)
+    }
+}
+
+impl From<Vec<DisplayMessage>> for Transcript {
+    fn from(messages: Vec<DisplayMessage>) -> Self {
+        Self {
+            messages,
+            ..Self::default()
+        }
+    }
+}
+
+impl std::ops::Deref for Transcript {
+    type Target = [DisplayMessage];
+
+    fn deref(&self) -> &Self::Target {
+        &self.messages
+    }
+}
+
+/// Summarize tool calls by category, e.g. "8 file reads, 2 searches"
+fn summarize_tools(names: &[&str]) -> String {
+    const CATEGORIES: [(&str, &str); 5] = [
+        ("file read", "file reads"),
+        ("search", "searches"),
+        ("command", "commands"),
+        ("edit", "edits"),
+        ("other tool call", "other tool calls"),
+    ];
+
+    let mut counts = [0usize; CATEGORIES.len()];
+    for name in names {
+        let index = match *name {
+            "read_file" | "Read" | "list_dir" => 0,
+            "glob" | "Glob" | "grep" | "Grep" | "find" | "code_search" | "web_search" => 1,
+            "shell" | "Bash" | "bash" => 2,
+            "write_file" | "Write" | "edit_file" | "Edit" | "multi_edit" | "apply_patch" => 3,
+            _ => 4,
+        };
+        counts[index] += 1;
+    }
+
+    counts
+        .iter()
+        .zip(CATEGORIES)
+        .filter(|(count, _)| **count > 0)
+        .map(|(&count, (singular, plural))| {
+            format!("{count} {}", if count == 1 { singular } else { plural })
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Format tool input for display
 #[must_use]
 pub fn format_tool_invocation(name: &str, input: &serde_json::Value) -> String {
@@ -197,3 +431,99 @@ fn shorten_path(path: &str) -> String {
     }
     path.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapse_folds_runs_at_threshold() {
+        let messages = vec![
+            DisplayMessage::user("look around"),
+            DisplayMessage::tool("read_file", "a.rs", "", false),
+            DisplayMessage::tool("read_file", "b.rs", "", false),
+            DisplayMessage::tool("grep", "fn main", "", false),
+            DisplayMessage::assistant("found it"),
+            DisplayMessage::tool("shell", "ls", "", false),
+            DisplayMessage::tool("shell", "pwd", "", false),
+        ];
+
+        let collapsed = collapse_tool_runs(&messages, 3, &[]);
+        assert_eq!(collapsed.len(), 5);
+        assert!(matches!(
+            &collapsed[1],
+            DisplayMessage::ToolGroup { summary, first: 1 } if summary == "2 file reads, 1 search"
+        ));
+        // Shorter runs are left as-is
+        assert!(matches!(&collapsed[3], DisplayMessage::Tool { name, .. } if name == "shell"));
+
+        // Expanded runs are shown call by call
+        assert_eq!(collapse_tool_runs(&messages, 3, &[1]).len(), messages.len());
+    }
+
+    #[test]
+    fn transcript_rebuilds_its_view_when_messages_change() {
+        let mut transcript = Transcript::from(vec![
+            DisplayMessage::tool("read_file", "a.rs", "", false),
+            DisplayMessage::tool("read_file", "b.rs", "", false),
+        ]);
+        transcript.refresh_view(Some(2));
+        assert_eq!(transcript.view().len(), 1);
+
+        transcript.push(DisplayMessage::assistant("done"));
+        transcript.refresh_view(Some(2));
+        assert_eq!(transcript.view().len(), 2);
+
+        transcript.expand_run(0);
+        transcript.refresh_view(Some(2));
+        assert_eq!(transcript.view().len(), 3);
+
+        transcript.refresh_view(None);
+        assert_eq!(transcript.view().len(), transcript.len());
+    }
+
+    #[test]
+    fn fold_compacted_folds_where_the_agent_split() {
+        let notice = || DisplayMessage::tool("compact", "Compacted", "summary", false);
+        let transcript = vec![
+            DisplayMessage::prompt("p1", "first"),
+            DisplayMessage::assistant("one"),
+            DisplayMessage::tool("read_file", "a.rs", "", false),
+            DisplayMessage::assistant("two"),
+            DisplayMessage::prompt("p2", "second"),
+            DisplayMessage::tool("shell", "ls", "", false),
+            DisplayMessage::assistant("three"),
+        ];
+
+        // The split fell after the first tool call of the first turn
+        let mut messages = transcript.clone();
+        fold_compacted(&mut messages, notice(), Some("p2"), 1);
+        assert_eq!(messages.len(), 5);
+        assert!(matches!(&messages[0], DisplayMessage::Tool { name, .. } if name == "compact"));
+        assert!(matches!(&messages[1], DisplayMessage::Assistant { text } if text == "two"));
+
+        // No prompt kept, so the replies count back from the end
+        let mut messages = transcript;
+        fold_compacted(&mut messages, notice(), None, 1);
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(&messages[1], DisplayMessage::Assistant { text } if text == "three"));
+    }
+
+    #[test]
+    fn collapse_keeps_errors_visible() {
+        let messages = vec![
+            DisplayMessage::tool("read_file", "a.rs", "", false),
+            DisplayMessage::tool("read_file", "b.rs", "", false),
+            DisplayMessage::tool_error("shell", "boom"),
+            DisplayMessage::tool("read_file", "c.rs", "", false),
+        ];
+
+        let collapsed = collapse_tool_runs(&messages, 2, &[]);
+        assert_eq!(collapsed.len(), 3);
+        assert!(matches!(&collapsed[0], DisplayMessage::ToolGroup { .. }));
+        assert!(matches!(
+            &collapsed[1],
+            DisplayMessage::Tool { is_error: true, .. }
+        ));
+    }
+}
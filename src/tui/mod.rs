@@ -2,11 +2,18 @@
 
 mod app;
 mod components;
+mod edit;
+mod file_picker;
 mod message;
+mod search;
 mod state;
+mod theme;
+mod vim;
 
 use std::fmt::Write as _;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 use crossterm::{
@@ -26,22 +33,32 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Wrap},
 };
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
+use crate::config::SubmitKey;
+use crate::core::Agent;
 use crate::core::agent::{
-    AskUserResponse, InterfaceMessage, PermissionAction, PermissionActor, PermissionClient,
-    PermissionContext, PermissionMessage, PermissionResponse,
+    AgentError, AgentMode, ApprovalStore, AskUserResponse, InterfaceMessage, PermissionAction,
+    PermissionActor, PermissionContext, PermissionMessage, PermissionResponse,
 };
-use crate::core::session::SessionTarget;
+use crate::core::approvals::ApprovalManager;
+use crate::core::project::Project;
+use crate::core::session::{SessionManager, SessionTarget, TurnChanges, new_message_id};
+use crate::core::shell::preview_command;
+use crate::core::snapshot::SnapshotManager;
 
 pub use app::App;
-use app::{ActiveAskUserDialog, ActiveDialog, ActivePermissionDialog, ChatMessage};
+use app::{ActiveAskUserDialog, ActiveDialog, ActivePermissionDialog, ChatMessage, PromptEdit};
 use components::{
-    DropdownMode, MESSAGE_PADDING_X, calculate_content_height, dropdown_mode, filter_commands,
-    filter_models, render_command_dropdown, render_model_dropdown, render_session,
-    render_session_list, render_welcome, should_show_dropdown,
+    DropdownMode, MESSAGE_PADDING_X, OutputDialog, calculate_content_height, complete_file_mention,
+    dropdown_mode, filter_commands, filter_models, model_command, model_completion,
+    render_command_dropdown, render_file_dropdown, render_model_dropdown, render_output_dialog,
+    render_persona_list, render_session, render_session_list, render_welcome, should_show_dropdown,
 };
-use message::DisplayMessage;
+use message::{DisplayMessage, fold_compacted};
+use search::SearchState;
 use state::ViewState;
+use vim::{VimCommand, VimMode};
 
 /// Run the TUI application.
 ///
@@ -49,15 +66,22 @@ use state::ViewState;
 ///
 /// Returns an error if terminal initialization fails or the event loop encounters an error.
 pub async fn run() -> anyhow::Result<()> {
-    run_with_target(SessionTarget::default()).await
+    run_with_target(SessionTarget::default(), None, None).await
 }
 
 /// Run the TUI application with a specific session target.
 ///
+/// If `initial_mode` is set, the TUI starts in that mode instead of the persisted one.
+/// If `working_dir` is set, the agent's tools run there instead of the current directory.
+///
 /// # Errors
 ///
 /// Returns an error if terminal initialization fails or the event loop encounters an error.
-pub async fn run_with_target(target: SessionTarget) -> anyhow::Result<()> {
+pub async fn run_with_target(
+    target: SessionTarget,
+    initial_mode: Option<AgentMode>,
+    working_dir: Option<PathBuf>,
+) -> anyhow::Result<()> {
     // Set up terminal
     // Note: Mouse capture is disabled to allow native terminal copy/paste
     enable_raw_mode()?;
@@ -124,19 +148,21 @@ pub async fn run_with_target(target: SessionTarget) -> anyhow::Result<()> {
     });
 
     // Create app state with permission channels and session target
-    let mut app = App::with_session_target(target);
+    let mut app = App::with_session_target(target, initial_mode);
+    if let (Some(dir), Some(agent)) = (working_dir, app.agent.as_mut()) {
+        agent.set_working_dir(dir);
+    }
     app.interface_rx = Some(interface_rx);
     app.permission_response_tx = Some(perm_response_tx);
     app.ask_user_response_tx = Some(ask_response_tx);
+    app.approval_store = ApprovalManager::for_current_project()
+        .map_err(|e| tracing::warn!("saved approvals unavailable: {e}"))
+        .ok()
+        .map(|manager| Arc::new(manager) as Arc<dyn ApprovalStore>);
 
     // Set up permission client for agent with current permission presets
-    let presets = app.current_permissions();
+    let client = app.permission_client(permission_tx.clone());
     if let Some(ref mut agent) = app.agent {
-        let client = PermissionClient::with_presets(
-            "tui-session".to_string(),
-            permission_tx.clone(),
-            presets,
-        );
         agent.set_permission_client(client);
     }
 
@@ -164,9 +190,14 @@ async fn run_app(
     app: &mut App,
     permission_tx: mpsc::UnboundedSender<PermissionMessage>,
 ) -> anyhow::Result<()> {
+    // Command previews can run `git status`, so they're worked out off the event loop
+    let (preview_tx, mut preview_rx) = mpsc::unbounded_channel();
+
     loop {
         // Clear selected text before render (will be populated if selection is active)
         app.selected_text.clear();
+        app.refresh_file_picker();
+        let context_usage = app.context_usage();
 
         terminal.draw(|f| {
             let full_area = f.area();
@@ -181,11 +212,22 @@ async fn run_app(
 
             // Calculate content height for scroll calculations (account for message area padding)
             let padded_width = area.width.saturating_sub(MESSAGE_PADDING_X * 2);
+            let collapse_threshold = app.collapse_tools.then_some(app.collapse_tools_threshold);
+            app.messages.refresh_view(collapse_threshold);
             let content_height =
-                calculate_content_height(&app.messages, &app.streaming_text, padded_width);
+                calculate_content_height(app.messages.view(), &app.streaming_text, padded_width);
 
             // Update dimensions for scroll calculations
             app.update_dimensions(area.width, area.height, content_height);
+            let messages = app.messages.view();
+            if let Some(search) = app.search.as_mut() {
+                search.refresh(messages);
+                if let Some(row) = search.take_jump(messages, padded_width) {
+                    // Stop following new output so the match stays in view
+                    app.message_scroll = row.min(app.max_message_scroll);
+                    app.auto_scroll = false;
+                }
+            }
 
             // Dispatch rendering based on view state
             let (cursor_pos, prompt_area) = match app.view_state {
@@ -194,37 +236,50 @@ async fn run_app(
                     render_welcome(
                         f,
                         area,
+                        &app.theme,
                         app.tagline,
                         app.tip,
+                        app.submit_key.hint(),
                         &app.input,
                         app.cursor,
                         app.placeholder,
                         app.agent_mode,
                         &app.model,
+                        app.vim_label(),
                     )
                 }
                 ViewState::Session => {
                     // Session view with messages and bottom prompt
                     // Show activity status, or "Thinking..." if loading with no specific status
+                    let notice = app.notice().map(str::to_string);
+                    let notice = notice.or_else(|| app.prompt_edit_status());
+                    let notice = app.search.as_ref().map(SearchState::status).or(notice);
+                    let vim_label = app.vim_label();
                     let status = if app.loading {
                         Some(app.activity_status.as_deref().unwrap_or("Thinking..."))
                     } else {
-                        None
+                        Some(notice.as_deref().unwrap_or_else(|| app.submit_key.hint()))
                     };
                     render_session(
                         f,
                         area,
-                        &app.messages,
+                        &app.theme,
+                        messages,
                         &app.streaming_text,
                         &app.input,
                         app.cursor,
                         app.message_scroll,
+                        content_height,
                         status,
                         &app.model,
                         app.agent_mode,
+                        app.plan_phase,
                         app.selection.as_ref(),
                         &mut app.selected_text,
+                        app.search.as_ref(),
                         app.session_cost,
+                        context_usage,
+                        vim_label,
                     )
                 }
             };
@@ -236,15 +291,32 @@ async fn run_app(
             if app.show_command_dropdown && should_show_dropdown(&app.input) {
                 match dropdown_mode(&app.input) {
                     DropdownMode::Commands => {
-                        render_command_dropdown(f, prompt_area, &app.input, app.command_selection);
+                        render_command_dropdown(
+                            f,
+                            prompt_area,
+                            &app.theme,
+                            &app.input,
+                            app.command_selection,
+                        );
                     }
                     DropdownMode::Models => {
                         render_model_dropdown(
                             f,
                             prompt_area,
+                            &app.theme,
+                            &app.input,
+                            app.command_selection,
+                            &app.agent_config,
+                        );
+                    }
+                    DropdownMode::Files => {
+                        render_file_dropdown(
+                            f,
+                            prompt_area,
+                            &app.theme,
                             &app.input,
                             app.command_selection,
-                            &app.agent_config.models,
+                            &app.file_matches(),
                         );
                     }
                     DropdownMode::None => {}
@@ -254,9 +326,13 @@ async fn run_app(
             // Render dialog overlay if active
             if let Some(ref mut dialog) = app.active_dialog {
                 match dialog {
-                    ActiveDialog::Permission(d) => render_permission_dialog(f, d),
+                    ActiveDialog::Permission(d) => render_permission_dialog(f, d, &app.theme),
                     ActiveDialog::AskUser(d) => render_ask_user_dialog(f, d),
-                    ActiveDialog::SessionList(d) => render_session_list(f, d),
+                    ActiveDialog::SessionList(d) => render_session_list(f, d, &app.theme),
+                    ActiveDialog::PersonaList(d) => render_persona_list(f, d, &app.theme),
+                    ActiveDialog::Output(d) | ActiveDialog::ConfirmUndo(d, _) => {
+                        render_output_dialog(f, d, &app.theme);
+                    }
                 }
             }
         })?;
@@ -276,6 +352,11 @@ async fn run_app(
                                     if handle_dialog_key(app, key.code, key.modifiers) {
                                         return Ok(());
                                     }
+                                    app.show_queued_dialog();
+                                } else if handle_search_key(app, key.code, key.modifiers) {
+                                    // Consumed by the search bar
+                                } else if handle_vim_key(app, key.code, key.modifiers) {
+                                    // Consumed by normal mode
                                 } else if handle_key(app, key.code, key.modifiers, &permission_tx) {
                                     return Ok(());
                                 }
@@ -315,10 +396,53 @@ async fn run_app(
                         // Clear activity status when receiving text
                         app.activity_status = None;
                     }
+                    Some(ChatMessage::Reasoning(_)) => {
+                        app.activity_status = Some("Thinking...".to_string());
+                    }
                     Some(ChatMessage::ToolStart { name }) => {
                         // Update activity status to show current tool
                         app.activity_status = Some(format!("Using {name}..."));
                     }
+                    Some(ChatMessage::Reconnecting { attempt, max_attempts }) => {
+                        app.activity_status =
+                            Some(format!("Reconnecting ({attempt}/{max_attempts})..."));
+                    }
+                    Some(ChatMessage::Compacted { messages }) => {
+                        app.activity_status =
+                            Some(format!("Compacted history ({messages} messages summarized)"));
+                        // The next response reports the new size
+                        app.context_tokens = 0;
+                    }
+                    Some(ChatMessage::ManualCompacted(Some(compacted))) => {
+                        app.activity_status = None;
+                        app.context_tokens = compacted.tokens_after;
+                        let notice = DisplayMessage::tool(
+                            "compact",
+                            format!(
+                                "Compacted {} messages (~{} -> ~{} tokens)",
+                                compacted.messages, compacted.tokens_before, compacted.tokens_after
+                            ),
+                            compacted.summary,
+                            false,
+                        );
+                        app.messages.edit(|messages| {
+                            fold_compacted(
+                                messages,
+                                notice,
+                                compacted.kept_prompt.as_deref(),
+                                compacted.kept_replies,
+                            );
+                        });
+                    }
+                    Some(ChatMessage::ManualCompacted(None)) => {
+                        app.activity_status = None;
+                        app.messages.push(DisplayMessage::tool(
+                            "compact",
+                            "Not enough history to compact",
+                            "",
+                            false,
+                        ));
+                    }
                     Some(ChatMessage::Tool { name, invocation, output, is_error }) => {
                         // Finalize any pending streaming text before tool message
                         app.finalize_streaming();
@@ -332,6 +456,10 @@ async fn run_app(
                         app.session_tokens.0 += input_tokens;
                         app.session_tokens.1 += output_tokens;
                         app.session_cost += cost_usd;
+                        app.context_tokens = input_tokens + output_tokens;
+                    }
+                    Some(ChatMessage::PlanPhase(phase)) => {
+                        app.plan_phase = phase;
                     }
                     Some(ChatMessage::Done(agent)) => {
                         // Finalize streaming text into an assistant message
@@ -342,9 +470,14 @@ async fn run_app(
                             tracing::warn!("failed to save history: {e}");
                         }
                         app.agent = Some(agent);
+                        revert_regenerate_model(app);
+                        // Agent may have switched mode via plan_enter/plan_exit
+                        app.sync_agent_mode();
                         app.loading = false;
                         app.activity_status = None;
                         app.chat_rx = None;
+                        app.chat_cancel = None;
+                        app.cancel_armed = false;
                     }
                     Some(ChatMessage::Error(e, agent)) => {
                         // Finalize any partial streaming text
@@ -354,20 +487,31 @@ async fn run_app(
                         app.messages.push(DisplayMessage::tool_error("Error", &e));
 
                         app.agent = Some(agent);
+                        revert_regenerate_model(app);
+                        app.sync_agent_mode();
                         let _ = write!(app.output, "\nError: {e}");
                         app.loading = false;
                         app.activity_status = None;
                         app.chat_rx = None;
+                        app.chat_cancel = None;
+                        app.cancel_armed = false;
                     }
                     None => {
                         app.finalize_streaming();
                         app.loading = false;
                         app.activity_status = None;
                         app.chat_rx = None;
+                        app.chat_cancel = None;
+                        app.cancel_armed = false;
                     }
                 }
             }
 
+            // Attach command previews as they're worked out
+            Some((request_id, preview)) = preview_rx.recv() => {
+                app.set_command_preview(request_id, preview);
+            }
+
             // Check for interface messages (permission dialogs)
             msg = async {
                 if let Some(ref mut rx) = app.interface_rx {
@@ -379,6 +523,15 @@ async fn run_app(
                 if let Some(msg) = msg {
                     match msg {
                         InterfaceMessage::ShowPermissionDialog { request_id, tool_name, action, context } => {
+                            if let PermissionContext::Bash { command, working_dir } = &context {
+                                let (command, working_dir) = (command.clone(), working_dir.clone());
+                                let preview_tx = preview_tx.clone();
+                                tokio::task::spawn_blocking(move || {
+                                    if let Some(preview) = preview_command(&command, &working_dir) {
+                                        let _ = preview_tx.send((request_id, preview));
+                                    }
+                                });
+                            }
                             app.show_permission_dialog(request_id, tool_name, action, context);
                         }
                         InterfaceMessage::ShowAskUserDialog { request_id, question, options } => {
@@ -394,6 +547,122 @@ async fn run_app(
     }
 }
 
+/// Check whether an Enter press with `modifiers` submits under `submit_key`.
+fn is_submit_key(submit_key: SubmitKey, modifiers: KeyModifiers) -> bool {
+    let newline_modifiers = KeyModifiers::SHIFT | KeyModifiers::ALT;
+    match submit_key {
+        SubmitKey::Enter => !modifiers.intersects(newline_modifiers),
+        SubmitKey::CtrlEnter => modifiers.contains(KeyModifiers::CONTROL),
+        SubmitKey::AltEnter => modifiers.contains(KeyModifiers::ALT),
+    }
+}
+
+/// Handle a key while the search bar is open, returning whether it was consumed.
+///
+/// While the query is being typed, text keys edit it and Enter confirms it.
+/// Afterwards `n`/`N` jump between matches as long as the prompt is empty,
+/// and Ctrl+F goes back to editing. Esc closes search in either state.
+fn handle_search_key(app: &mut App, code: KeyCode, modifiers: KeyModifiers) -> bool {
+    let Some(search) = app.search.as_mut() else {
+        return false;
+    };
+    let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+
+    if code == KeyCode::Esc {
+        app.search = None;
+        return true;
+    }
+    if ctrl && code == KeyCode::Char('f') {
+        search.editing = true;
+        return true;
+    }
+
+    if search.editing {
+        match code {
+            KeyCode::Char(c) if !ctrl => {
+                search.query.push(c);
+                search.current = 0;
+                search.jump = true;
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                search.current = 0;
+                search.jump = true;
+            }
+            KeyCode::Enter => search.editing = false,
+            _ => return false,
+        }
+        return true;
+    }
+
+    match code {
+        KeyCode::Char('n') if !ctrl && app.input.is_empty() => search.next(),
+        KeyCode::Char('N') if !ctrl && app.input.is_empty() => search.prev(),
+        _ => return false,
+    }
+    true
+}
+
+/// Handle a key for `[tui] vim_mode`, returning whether it was consumed.
+///
+/// In insert mode only Esc is taken, and only when it would not dismiss the
+/// dropdown or cancel a running response. In normal mode printable keys run
+/// commands or are ignored so they never type; everything else, including
+/// Enter and Ctrl combinations, falls through to the usual bindings.
+fn handle_vim_key(app: &mut App, code: KeyCode, modifiers: KeyModifiers) -> bool {
+    let Some(vim) = app.vim.as_mut() else {
+        return false;
+    };
+
+    if vim.mode == VimMode::Insert {
+        if code == KeyCode::Esc && !app.loading && !app.show_command_dropdown {
+            vim.enter_normal();
+            // Normal mode sits on a character, not after the last one
+            if app.cursor > edit::line_start(&app.input, app.cursor) {
+                app.move_left();
+            }
+            return true;
+        }
+        return false;
+    }
+
+    let KeyCode::Char(c) = code else {
+        if code == KeyCode::Enter {
+            vim.enter_insert();
+        }
+        return false;
+    };
+    if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+        return false;
+    }
+
+    match vim.command(c) {
+        // h and l stay on the current line
+        Some(VimCommand::Left) if app.cursor > edit::line_start(&app.input, app.cursor) => {
+            app.move_left();
+        }
+        Some(VimCommand::Right) if app.cursor < edit::line_end(&app.input, app.cursor) => {
+            app.move_right();
+        }
+        Some(VimCommand::Down) => app.move_down(),
+        Some(VimCommand::Up) => app.move_up(),
+        Some(VimCommand::WordForward) => app.move_word_right(),
+        Some(VimCommand::WordBack) => app.move_word_left(),
+        Some(VimCommand::LineStart) => app.move_line_start(),
+        Some(VimCommand::LineEnd) => app.move_line_end(),
+        Some(VimCommand::DeleteChar) => app.delete_char_under_cursor(),
+        Some(VimCommand::DeleteLine) => app.delete_line(),
+        Some(VimCommand::Insert) => vim.enter_insert(),
+        Some(VimCommand::Append) => {
+            vim.enter_insert();
+            app.move_right();
+        }
+        _ => {}
+    }
+    app.show_command_dropdown = should_show_dropdown(&app.input);
+    true
+}
+
 /// Handle a key press. Returns true if the app should exit.
 #[allow(clippy::too_many_lines)]
 fn handle_key(
@@ -402,24 +671,26 @@ fn handle_key(
     modifiers: KeyModifiers,
     permission_tx: &mpsc::UnboundedSender<PermissionMessage>,
 ) -> bool {
-    // Handle Shift+Enter or Alt+Enter for newline insertion
-    // Allow even while loading so user can prepare next message
-    if (modifiers.contains(KeyModifiers::SHIFT) || modifiers.contains(KeyModifiers::ALT))
-        && code == KeyCode::Enter
-    {
-        app.insert_char('\n');
-        return false;
-    }
+    // Enter combinations either submit or insert a newline, per `[tui] submit_key`
+    // Allow newlines even while loading so user can prepare next message
+    let modifiers = if code == KeyCode::Enter {
+        if !is_submit_key(app.submit_key, modifiers) && !app.show_command_dropdown {
+            app.insert_char('\n');
+            return false;
+        }
+        // Submit behaves like a plain Enter from here on
+        KeyModifiers::NONE
+    } else {
+        modifiers
+    };
 
     // Handle Ctrl combinations
     if modifiers.contains(KeyModifiers::CONTROL) {
         match code {
             KeyCode::Char('c') => {
                 if app.loading {
-                    // Cancel streaming
-                    app.chat_rx = None;
-                    app.finalize_streaming();
-                    app.loading = false;
+                    // The agent returns through the chat channel once stopped
+                    app.cancel_chat();
                 } else if app.input.is_empty() {
                     return true; // Exit.
                 } else {
@@ -466,11 +737,27 @@ fn handle_key(
                 // Open session list dialog
                 app.show_session_list();
             }
+            KeyCode::Char('y') => app.copy_selection(),
+            KeyCode::Char('t') => app.cycle_reasoning_effort(),
+            KeyCode::Char('r') if !app.loading => regenerate(app, "", permission_tx),
+            KeyCode::Char('p') if !app.loading => app.edit_previous_prompt(),
+            KeyCode::Char('o') if app.view_state == ViewState::Session => {
+                app.expand_run_in_view();
+            }
+            KeyCode::Char('f') if app.view_state == ViewState::Session => {
+                app.search = Some(SearchState::new());
+            }
             _ => {}
         }
         return false;
     }
 
+    // While text is selected, `y` yanks it instead of typing
+    if code == KeyCode::Char('y') && modifiers.is_empty() && app.selection.is_some() {
+        app.copy_selection();
+        return false;
+    }
+
     // Use stored max scroll values from app state
 
     // Handle regular keys
@@ -488,16 +775,42 @@ fn handle_key(
                             }
                         }
                         DropdownMode::Models => {
-                            let filtered = filter_models(&app.input, &app.agent_config.models);
+                            let filtered = filter_models(&app.input, &app.agent_config);
                             if let Some(model) = filtered.get(app.command_selection) {
-                                app.input = format!("/model {}", model.id);
+                                let cmd = model_command(&app.input).unwrap_or("/model");
+                                let name = model_completion(&app.input, model, &app.agent_config);
+                                app.input = format!("{cmd} {name}");
                                 app.show_command_dropdown = false;
                             }
                         }
+                        DropdownMode::Files => {
+                            // Completing a mention keeps editing the prompt
+                            app.flush_file_picker();
+                            let completed = app
+                                .file_matches()
+                                .get(app.command_selection)
+                                .map(|path| complete_file_mention(&app.input, path));
+                            if let Some(input) = completed {
+                                app.input = input;
+                                app.cursor = app.input.len();
+                                app.show_command_dropdown = false;
+                                return false;
+                            }
+                        }
                         DropdownMode::None => {}
                     }
                 }
 
+                // An edited earlier prompt is resent in a fork, never run as a command
+                if app.prompt_edit.is_some() {
+                    if app.input.trim().is_empty() {
+                        app.set_notice("Edited prompt is empty; Esc cancels the edit");
+                    } else if let Some(edit) = app.prompt_edit.take() {
+                        resend_edited_prompt(app, edit, permission_tx);
+                    }
+                    return false;
+                }
+
                 let trimmed = app.input.trim();
 
                 // Handle exit commands
@@ -525,14 +838,21 @@ fn handle_key(
 
                 // Handle model switch command
                 if trimmed == "/model" || trimmed.starts_with("/model ") {
-                    let model_arg = trimmed.strip_prefix("/model").unwrap_or("").trim();
+                    let model_arg = trimmed
+                        .strip_prefix("/model")
+                        .unwrap_or("")
+                        .trim()
+                        .to_string();
                     if model_arg.is_empty() {
                         // Show available models (dropdown will appear)
                         let models_list: String = app
                             .agent_config
                             .models
                             .iter()
-                            .map(|m| format!("  {} ({})", m.id, m.provider))
+                            .map(|m| match app.agent_config.alias_for(&m.id) {
+                                Some(alias) => format!("  {} [{alias}] ({})", m.id, m.provider),
+                                None => format!("  {} ({})", m.id, m.provider),
+                            })
                             .collect::<Vec<_>>()
                             .join("\n");
                         app.messages.push(DisplayMessage::tool(
@@ -542,51 +862,51 @@ fn handle_key(
                             false,
                         ));
                         app.enter_session();
-                    } else if let Some(agent) = &mut app.agent {
-                        // Check if we need to switch providers
-                        let current_provider = agent.provider_name();
-                        let target_provider = app.agent_config.provider_for_model(model_arg);
-
-                        if let Some(provider_name) = target_provider {
-                            if provider_name != current_provider {
-                                match app.agent_config.create_provider_by_name(provider_name) {
-                                    Ok(new_provider) => {
-                                        agent.set_provider(new_provider);
-                                        app.messages.push(DisplayMessage::tool(
-                                            "model",
-                                            format!("Switched provider to {provider_name}"),
-                                            "",
-                                            false,
-                                        ));
-                                    }
-                                    Err(e) => {
-                                        app.messages.push(DisplayMessage::tool_error(
-                                            "model",
-                                            format!("Failed to switch provider: {e}"),
-                                        ));
-                                        app.clear_input();
-                                        app.enter_session();
-                                        return false;
-                                    }
+                    } else if app.agent.is_some() {
+                        match switch_model(app, &model_arg) {
+                            Ok(switched_provider) => {
+                                if let Some(provider_name) = switched_provider {
+                                    app.messages.push(DisplayMessage::tool(
+                                        "model",
+                                        format!("Switched provider to {provider_name}"),
+                                        "",
+                                        false,
+                                    ));
                                 }
+                                let provider_info =
+                                    app.agent.as_ref().map_or("", Agent::provider_name);
+                                app.messages.push(DisplayMessage::tool(
+                                    "model",
+                                    format!("Switched to {model_arg} ({provider_info})"),
+                                    "",
+                                    false,
+                                ));
+                            }
+                            Err(e) => {
+                                app.messages.push(DisplayMessage::tool_error(
+                                    "model",
+                                    format!("Failed to switch provider: {e}"),
+                                ));
                             }
                         }
-
-                        agent.set_model(model_arg);
-                        app.model = model_arg.to_string();
-                        let provider_info = agent.provider_name();
-                        app.messages.push(DisplayMessage::tool(
-                            "model",
-                            format!("Switched to {model_arg} ({provider_info})"),
-                            "",
-                            false,
-                        ));
                         app.enter_session();
                     }
                     app.clear_input();
                     return false;
                 }
 
+                // Handle regenerate command (optionally with another model)
+                if trimmed == "/regenerate" || trimmed.starts_with("/regenerate ") {
+                    let model_arg = trimmed
+                        .strip_prefix("/regenerate")
+                        .unwrap_or("")
+                        .trim()
+                        .to_string();
+                    app.clear_input();
+                    regenerate(app, &model_arg, permission_tx);
+                    return false;
+                }
+
                 // Handle mode switch commands
                 if trimmed == "/plan" {
                     if let Some(agent) = &mut app.agent {
@@ -607,6 +927,108 @@ fn handle_key(
                     return false;
                 }
 
+                // Handle persona switch command
+                if trimmed == "/persona" || trimmed.starts_with("/persona ") {
+                    let name = trimmed
+                        .strip_prefix("/persona")
+                        .unwrap_or("")
+                        .trim()
+                        .to_string();
+                    app.clear_input();
+                    if name.is_empty() {
+                        app.show_persona_list();
+                    } else {
+                        switch_persona(app, &name);
+                        app.enter_session();
+                    }
+                    return false;
+                }
+
+                // Handle system prompt append command
+                if trimmed == "/system" || trimmed.starts_with("/system ") {
+                    let text = trimmed.strip_prefix("/system").unwrap_or("").trim();
+                    if text.is_empty() {
+                        let current = app
+                            .agent
+                            .as_ref()
+                            .map(|a| a.system_append().join("\n\n"))
+                            .unwrap_or_default();
+                        let current = if current.is_empty() {
+                            "None".to_string()
+                        } else {
+                            current
+                        };
+                        app.messages.push(DisplayMessage::tool(
+                            "system",
+                            "Appended instructions",
+                            format!("{current}\n\nUsage: /system <text>"),
+                            false,
+                        ));
+                    } else if let Some(agent) = &mut app.agent {
+                        agent.append_system(text);
+                        app.messages.push(DisplayMessage::tool(
+                            "system",
+                            "Appended to system prompt",
+                            text,
+                            false,
+                        ));
+                    }
+                    app.clear_input();
+                    app.enter_session();
+                    return false;
+                }
+
+                // Toggle folding of consecutive tool calls
+                if trimmed == "/collapse" {
+                    app.collapse_tools = !app.collapse_tools;
+                    app.clear_input();
+                    app.enter_session();
+                    return false;
+                }
+
+                // Search the conversation, confirming the query if one is given
+                if trimmed == "/search" || trimmed.starts_with("/search ") {
+                    let query = trimmed.strip_prefix("/search").unwrap_or("").trim();
+                    let mut search = SearchState::new();
+                    if !query.is_empty() {
+                        search.query = query.to_string();
+                        search.editing = false;
+                        search.jump = true;
+                    }
+                    app.search = Some(search);
+                    app.clear_input();
+                    app.enter_session();
+                    return false;
+                }
+
+                // Show what the session has changed, or revert its last turn
+                if trimmed == "/diff" {
+                    app.clear_input();
+                    show_session_diff(app);
+                    return false;
+                }
+                if trimmed == "/undo" {
+                    app.clear_input();
+                    confirm_undo(app);
+                    return false;
+                }
+
+                // Summarize older history now
+                if trimmed == "/compact" {
+                    app.clear_input();
+                    app.enter_session();
+                    start_compact(app);
+                    return false;
+                }
+
+                // Regenerate the session title from the first message
+                if trimmed == "/retitle" {
+                    app.clear_input();
+                    app.enter_session();
+                    start_retitle(app);
+                    return false;
+                }
+
                 // Handle sessions command
                 if trimmed == "/sessions" {
                     app.clear_input();
@@ -630,10 +1052,24 @@ fn handle_key(
                             }
                         }
                         DropdownMode::Models => {
-                            let filtered = filter_models(&app.input, &app.agent_config.models);
+                            let filtered = filter_models(&app.input, &app.agent_config);
                             if let Some(model) = filtered.get(app.command_selection) {
-                                app.input = format!("/model {}", model.id);
+                                let cmd = model_command(&app.input).unwrap_or("/model");
+                                let name = model_completion(&app.input, model, &app.agent_config);
+                                app.input = format!("{cmd} {name}");
+                                app.cursor = app.input.len();
+                            }
+                        }
+                        DropdownMode::Files => {
+                            app.flush_file_picker();
+                            let completed = app
+                                .file_matches()
+                                .get(app.command_selection)
+                                .map(|path| complete_file_mention(&app.input, path));
+                            if let Some(input) = completed {
+                                app.input = input;
                                 app.cursor = app.input.len();
+                                app.show_command_dropdown = false;
                             }
                         }
                         DropdownMode::None => {}
@@ -670,10 +1106,16 @@ fn handle_key(
             }
         }
         KeyCode::Esc => {
-            // Dismiss dropdown and clear input
+            // Dismiss dropdown and clear input, keeping the prompt around a mention
             if app.show_command_dropdown {
                 app.show_command_dropdown = false;
-                app.clear_input();
+                if dropdown_mode(&app.input) != DropdownMode::Files {
+                    app.clear_input();
+                }
+            } else if app.prompt_edit.is_some() {
+                app.cancel_prompt_edit();
+            } else if app.loading {
+                app.press_cancel();
             }
         }
         KeyCode::Left => app.move_left(),
@@ -700,9 +1142,10 @@ fn handle_key(
                 // Navigate dropdown selection up (wrap to bottom)
                 let max_idx = match dropdown_mode(&app.input) {
                     DropdownMode::Commands => filter_commands(&app.input).len().saturating_sub(1),
-                    DropdownMode::Models => filter_models(&app.input, &app.agent_config.models)
+                    DropdownMode::Models => filter_models(&app.input, &app.agent_config)
                         .len()
                         .saturating_sub(1),
+                    DropdownMode::Files => app.file_matches().len().saturating_sub(1),
                     DropdownMode::None => 0,
                 };
                 app.command_selection = if app.command_selection == 0 {
@@ -723,9 +1166,10 @@ fn handle_key(
                 // Navigate dropdown selection down (wrap to top)
                 let max_idx = match dropdown_mode(&app.input) {
                     DropdownMode::Commands => filter_commands(&app.input).len().saturating_sub(1),
-                    DropdownMode::Models => filter_models(&app.input, &app.agent_config.models)
+                    DropdownMode::Models => filter_models(&app.input, &app.agent_config)
                         .len()
                         .saturating_sub(1),
+                    DropdownMode::Files => app.file_matches().len().saturating_sub(1),
                     DropdownMode::None => 0,
                 };
                 app.command_selection = if app.command_selection >= max_idx {
@@ -747,10 +1191,8 @@ fn handle_key(
     false
 }
 
-/// Permission dialog colors.
-const PERMISSION_BORDER: Color = Color::Rgb(245, 167, 66); // Orange/warning
-const PERMISSION_HIGHLIGHT: Color = Color::Rgb(245, 167, 66);
-const PERMISSION_DIM: Color = Color::Rgb(100, 100, 110);
+/// High-risk permission color; other dialogs use the theme border.
+const HIGH_RISK_BORDER: Color = Color::Rgb(235, 80, 80); // Red/danger
 
 /// Render a centered dialog overlay.
 fn render_dialog(
@@ -825,7 +1267,11 @@ fn render_dialog(
 
 /// Render permission dialog with improved UX.
 #[allow(clippy::too_many_lines)]
-fn render_permission_dialog(frame: &mut ratatui::Frame, dialog: &ActivePermissionDialog) {
+fn render_permission_dialog(
+    frame: &mut ratatui::Frame,
+    dialog: &ActivePermissionDialog,
+    theme: &theme::Theme,
+) {
     use ratatui::text::{Line, Span};
 
     // Determine icon and title based on operation type
@@ -840,10 +1286,23 @@ fn render_permission_dialog(frame: &mut ratatui::Frame, dialog: &ActivePermissio
         PermissionContext::Grep { .. } => ("⊛", "Search Content"),
         PermissionContext::ListDir { .. } => ("▤", "List Directory"),
         PermissionContext::WebFetch { .. } => ("↓", "Fetch URL"),
+        PermissionContext::Remember { .. } => ("◆", "Remember"),
+        PermissionContext::PluginTool { .. } => ("⚙", "Plugin Tool"),
+    };
+
+    let (icon, title) = if dialog.action.is_high_risk() {
+        ("!", "High-Risk Command")
+    } else {
+        (icon, title)
+    };
+    let accent = if dialog.action.is_high_risk() {
+        HIGH_RISK_BORDER
+    } else {
+        theme.border
     };
 
-    let header_style = Style::default().fg(PERMISSION_HIGHLIGHT);
-    let dim_style = Style::default().fg(PERMISSION_DIM);
+    let header_style = Style::default().fg(accent);
+    let dim_style = Style::default().fg(theme.dim);
     let code_style = Style::default().fg(Color::White);
 
     let mut content = vec![
@@ -855,6 +1314,19 @@ fn render_permission_dialog(frame: &mut ratatui::Frame, dialog: &ActivePermissio
         Line::from(""),
     ];
 
+    // Explain why this asks again even after "Session" or "Always"
+    if let PermissionAction::ExecuteHighRisk { pattern } = &dialog.action {
+        content.push(Line::from(Span::styled(
+            format!("Matches high-risk pattern \"{pattern}\"."),
+            header_style,
+        )));
+        content.push(Line::from(Span::styled(
+            "High-risk commands always ask; saved approvals do not apply.",
+            dim_style,
+        )));
+        content.push(Line::from(""));
+    }
+
     match &dialog.context {
         PermissionContext::Bash {
             command,
@@ -876,6 +1348,25 @@ fn render_permission_dialog(frame: &mut ratatui::Frame, dialog: &ActivePermissio
                 format!("in {}", working_dir.display()),
                 dim_style,
             )));
+            if let Some(preview) = &dialog.preview {
+                content.push(Line::from(""));
+                content.push(Line::from(Span::styled(
+                    format!("Predicted effect ({}), not yet run:", preview.source),
+                    dim_style,
+                )));
+                if preview.lines.is_empty() {
+                    content.push(Line::from(Span::styled("  (nothing)", dim_style)));
+                }
+                for line in &preview.lines {
+                    content.push(Line::from(Span::styled(format!("  {line}"), code_style)));
+                }
+                if preview.omitted > 0 {
+                    content.push(Line::from(Span::styled(
+                        format!("  ... and {} more", preview.omitted),
+                        dim_style,
+                    )));
+                }
+            }
         }
         PermissionContext::WriteFile {
             path,
@@ -984,6 +1475,33 @@ fn render_permission_dialog(frame: &mut ratatui::Frame, dialog: &ActivePermissio
                 code_style,
             )));
         }
+        PermissionContext::Remember {
+            content: fact,
+            category,
+        } => {
+            content.push(Line::from(Span::styled(
+                format!("Category: {category}"),
+                dim_style,
+            )));
+            content.push(Line::from(""));
+            for line in fact.lines().take(6) {
+                content.push(Line::from(Span::styled(format!("  {line}"), code_style)));
+            }
+        }
+        PermissionContext::PluginTool {
+            plugin,
+            tool,
+            input,
+        } => {
+            content.push(Line::from(Span::styled(
+                format!("{plugin} → {tool}"),
+                code_style,
+            )));
+            content.push(Line::from(""));
+            for line in input.lines().take(6) {
+                content.push(Line::from(Span::styled(format!("  {line}"), dim_style)));
+            }
+        }
     }
 
     // Add navigation hint
@@ -993,12 +1511,19 @@ fn render_permission_dialog(frame: &mut ratatui::Frame, dialog: &ActivePermissio
         dim_style,
     )));
 
-    // Button labels with keyboard shortcuts
-    let buttons = [
-        ("[a] Allow once", dialog.selected == 0),
-        ("[s] Always", dialog.selected == 1),
-        ("[d] Deny", dialog.selected == 2),
-    ];
+    // Button labels with keyboard shortcuts (no standing approval for high-risk commands)
+    let mut buttons = vec![("[a] Allow once", dialog.selected == 0)];
+    if !dialog.action.is_high_risk() {
+        buttons.push(("[s] Session", dialog.selected == 1));
+        buttons.push(("[A] Always", dialog.selected == 2));
+    }
+    buttons.push(("[d] Deny", dialog.selected == 3));
+
+    // Room for the preview: blank line, heading, lines and the "more" note
+    #[allow(clippy::cast_possible_truncation)]
+    let preview_height = dialog.preview.as_ref().map_or(0, |preview| {
+        (preview.lines.len().max(1) + 2 + usize::from(preview.omitted > 0)) as u16
+    });
 
     render_dialog(
         frame,
@@ -1006,8 +1531,8 @@ fn render_permission_dialog(frame: &mut ratatui::Frame, dialog: &ActivePermissio
         content,
         &buttons,
         70,
-        22,
-        Some(PERMISSION_BORDER),
+        22 + preview_height,
+        Some(accent),
     );
 }
 
@@ -1091,7 +1616,7 @@ fn handle_dialog_key(app: &mut App, code: KeyCode, _modifiers: KeyModifiers) ->
                     ));
                 }
             }
-            KeyCode::Char('s') => {
+            KeyCode::Char('s') if !d.action.is_high_risk() => {
                 if let Some(ref tx) = app.permission_response_tx {
                     let _ = tx.send((
                         d.request_id,
@@ -1102,6 +1627,17 @@ fn handle_dialog_key(app: &mut App, code: KeyCode, _modifiers: KeyModifiers) ->
                     ));
                 }
             }
+            KeyCode::Char('A') if !d.action.is_high_risk() => {
+                if let Some(ref tx) = app.permission_response_tx {
+                    let _ = tx.send((
+                        d.request_id,
+                        "tui-session".to_string(),
+                        d.tool_name,
+                        d.action,
+                        PermissionResponse::AllowAlways,
+                    ));
+                }
+            }
             KeyCode::Char('d') | KeyCode::Esc => {
                 // Esc always denies (cancel = abort = deny)
                 if let Some(ref tx) = app.permission_response_tx {
@@ -1119,6 +1655,7 @@ fn handle_dialog_key(app: &mut App, code: KeyCode, _modifiers: KeyModifiers) ->
                 let response = match d.selected {
                     0 => PermissionResponse::Allow,
                     1 => PermissionResponse::AllowForSession,
+                    2 => PermissionResponse::AllowAlways,
                     _ => PermissionResponse::Deny,
                 };
                 if let Some(ref tx) = app.permission_response_tx {
@@ -1132,14 +1669,23 @@ fn handle_dialog_key(app: &mut App, code: KeyCode, _modifiers: KeyModifiers) ->
                 }
             }
             // Navigation
+            // High-risk dialogs skip the "Session" and "Always" buttons
             KeyCode::Left | KeyCode::Char('h') => {
                 let mut new_d = d;
-                new_d.selected = new_d.selected.saturating_sub(1);
+                new_d.selected = if new_d.action.is_high_risk() {
+                    0
+                } else {
+                    new_d.selected.saturating_sub(1)
+                };
                 app.active_dialog = Some(ActiveDialog::Permission(new_d));
             }
             KeyCode::Right | KeyCode::Char('l') => {
                 let mut new_d = d;
-                new_d.selected = (new_d.selected + 1).min(2);
+                new_d.selected = if new_d.action.is_high_risk() {
+                    3
+                } else {
+                    (new_d.selected + 1).min(3)
+                };
                 app.active_dialog = Some(ActiveDialog::Permission(new_d));
             }
             _ => {
@@ -1191,14 +1737,9 @@ fn handle_dialog_key(app: &mut App, code: KeyCode, _modifiers: KeyModifiers) ->
                     }
                     KeyCode::Backspace => {
                         let mut new_d = d;
-                        if new_d.cursor > 0 {
-                            let prev = new_d.input[..new_d.cursor]
-                                .char_indices()
-                                .next_back()
-                                .map_or(0, |(i, _)| i);
-                            new_d.input.drain(prev..new_d.cursor);
-                            new_d.cursor = prev;
-                        }
+                        let prev = edit::prev_grapheme(&new_d.input, new_d.cursor);
+                        new_d.input.drain(prev..new_d.cursor);
+                        new_d.cursor = prev;
                         app.active_dialog = Some(ActiveDialog::AskUser(new_d));
                     }
                     KeyCode::Enter => {
@@ -1236,7 +1777,7 @@ fn handle_dialog_key(app: &mut App, code: KeyCode, _modifiers: KeyModifiers) ->
                             if let Some(manager) = agent.session_manager() {
                                 match App::load_session_messages(manager, &session_id) {
                                     Ok(messages) => {
-                                        app.messages = messages;
+                                        app.messages = messages.into();
                                         app.message_scroll = 0;
                                         app.streaming_text.clear();
                                         // Switch to session view if we have messages
@@ -1277,6 +1818,28 @@ fn handle_dialog_key(app: &mut App, code: KeyCode, _modifiers: KeyModifiers) ->
                     }
                 }
             }
+            KeyCode::Char('f') => {
+                // Fork the selected session and list the copy first
+                let fork = d.selected_session().and_then(|session| {
+                    let manager = app.agent.as_ref()?.session_manager()?;
+                    let last = manager.list_messages(&session.id).ok()?.pop()?;
+                    match manager.fork_session(&session.id, last.id()) {
+                        Ok(fork) => Some(fork),
+                        Err(e) => {
+                            tracing::error!("failed to fork session: {e}");
+                            None
+                        }
+                    }
+                });
+                match fork {
+                    Some(fork) => {
+                        app.set_notice(format!("Created {}", fork.title));
+                        d.add_session(fork);
+                    }
+                    None => app.set_notice("Nothing to fork"),
+                }
+                app.active_dialog = Some(ActiveDialog::SessionList(d));
+            }
             KeyCode::Char('d') => {
                 // Delete selected session
                 if let Some(session) = d.selected_session() {
@@ -1340,11 +1903,420 @@ fn handle_dialog_key(app: &mut App, code: KeyCode, _modifiers: KeyModifiers) ->
                 app.active_dialog = Some(ActiveDialog::SessionList(d));
             }
         },
+        ActiveDialog::PersonaList(mut d) => match code {
+            KeyCode::Esc => {}
+            KeyCode::Enter => {
+                if let Some(name) = d.selected_persona().map(|p| p.name.clone()) {
+                    switch_persona(app, &name);
+                    app.enter_session();
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                d.select_previous();
+                app.active_dialog = Some(ActiveDialog::PersonaList(d));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                d.select_next();
+                app.active_dialog = Some(ActiveDialog::PersonaList(d));
+            }
+            _ => {
+                app.active_dialog = Some(ActiveDialog::PersonaList(d));
+            }
+        },
+        ActiveDialog::Output(mut d) => match code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {}
+            _ => {
+                scroll_output_dialog(&mut d, code);
+                app.active_dialog = Some(ActiveDialog::Output(d));
+            }
+        },
+        ActiveDialog::ConfirmUndo(mut d, turn) => match code {
+            KeyCode::Char('y') | KeyCode::Enter => undo_turn(app, &turn),
+            KeyCode::Esc | KeyCode::Char('n') => app.set_notice("Undo cancelled"),
+            _ => {
+                scroll_output_dialog(&mut d, code);
+                app.active_dialog = Some(ActiveDialog::ConfirmUndo(d, turn));
+            }
+        },
     }
 
     false
 }
 
+/// Scroll an output dialog for navigation keys.
+fn scroll_output_dialog(dialog: &mut OutputDialog, code: KeyCode) {
+    match code {
+        KeyCode::Up | KeyCode::Char('k') => dialog.scroll_up(1),
+        KeyCode::Down | KeyCode::Char('j') => dialog.scroll_down(1),
+        KeyCode::PageUp => dialog.page_up(),
+        KeyCode::PageDown => dialog.page_down(),
+        _ => {}
+    }
+}
+
+/// The agent's session manager and current session, with snapshots for
+/// the directory it works in.
+fn session_snapshots(
+    agent: &Agent,
+) -> anyhow::Result<Option<(&SessionManager, &str, SnapshotManager)>> {
+    let (Some(manager), Some(session_id)) = (agent.session_manager(), agent.session_id()) else {
+        return Ok(None);
+    };
+    let project = Project::detect(&agent.working_dir())?;
+    Ok(Some((
+        manager,
+        session_id,
+        SnapshotManager::for_project(&project)?,
+    )))
+}
+
+/// Diff of every file change the session has made since its first snapshot.
+fn session_diff(agent: &Agent) -> anyhow::Result<String> {
+    let Some((manager, session_id, snapshots)) = session_snapshots(agent)? else {
+        return Ok(String::new());
+    };
+    match manager.first_snapshot(session_id)? {
+        Some(base) => snapshots.diff(&base),
+        None => Ok(String::new()),
+    }
+}
+
+/// Show the session's cumulative file changes in an output dialog.
+fn show_session_diff(app: &mut App) {
+    let Some(agent) = app.agent.as_ref() else {
+        app.output = "No provider configured".to_string();
+        return;
+    };
+    match session_diff(agent) {
+        Ok(diff) if diff.is_empty() => {
+            app.messages.push(DisplayMessage::tool(
+                "diff",
+                "No file changes in this session",
+                "",
+                false,
+            ));
+        }
+        Ok(diff) => {
+            app.active_dialog = Some(ActiveDialog::Output(OutputDialog::new(
+                "Session diff",
+                &diff,
+            )));
+        }
+        Err(e) => {
+            app.messages.push(DisplayMessage::tool_error(
+                "diff",
+                format!("Failed to diff session: {e}"),
+            ));
+        }
+    }
+}
+
+/// Files changed by `turn`, relative to `root` where possible, sorted.
+fn turn_files(turn: &TurnChanges, root: &Path) -> Vec<String> {
+    let mut files: Vec<String> = turn
+        .files()
+        .into_iter()
+        .map(|file| {
+            file.strip_prefix(root)
+                .unwrap_or(file)
+                .display()
+                .to_string()
+        })
+        .collect();
+    files.dedup();
+    files
+}
+
+/// List the files the last turn changed and ask before reverting them.
+fn confirm_undo(app: &mut App) {
+    let Some(agent) = app.agent.as_ref() else {
+        app.output = "No provider configured".to_string();
+        return;
+    };
+    let turn = match (agent.session_manager(), agent.session_id()) {
+        (Some(manager), Some(session_id)) => manager.last_turn_changes(session_id),
+        _ => Ok(None),
+    };
+    match turn {
+        Ok(Some(turn)) => {
+            let files = turn_files(&turn, &agent.working_dir());
+            let text = format!(
+                "The last turn changed:\n\n  {}\n\nRevert these files?",
+                files.join("\n  ")
+            );
+            let dialog = OutputDialog::new("Undo last turn", &text).confirming();
+            app.active_dialog = Some(ActiveDialog::ConfirmUndo(dialog, turn));
+        }
+        Ok(None) => {
+            app.messages
+                .push(DisplayMessage::tool("undo", "Nothing to undo", "", false));
+        }
+        Err(e) => {
+            app.messages.push(DisplayMessage::tool_error(
+                "undo",
+                format!("Failed to load changes: {e}"),
+            ));
+        }
+    }
+}
+
+/// Revert the files changed by `turn` and forget it.
+fn undo_turn(app: &mut App, turn: &TurnChanges) {
+    let Some(agent) = app.agent.as_ref() else {
+        return;
+    };
+    let result = session_snapshots(agent).and_then(|found| {
+        let (manager, session_id, snapshots) =
+            found.ok_or_else(|| anyhow::anyhow!("no active session"))?;
+        snapshots.revert(&turn.patches)?;
+        manager.remove_turn_changes(session_id, &turn.id)
+    });
+
+    match result {
+        Ok(()) => {
+            let files = turn_files(turn, &agent.working_dir());
+            app.messages.push(DisplayMessage::tool(
+                "undo",
+                format!("Reverted {} file(s)", files.len()),
+                files.join("\n"),
+                false,
+            ));
+        }
+        Err(e) => {
+            app.messages.push(DisplayMessage::tool_error(
+                "undo",
+                format!("Failed to undo: {e}"),
+            ));
+        }
+    }
+    app.enter_session();
+}
+
+/// Summarize older history on request, then hand the agent back.
+fn start_compact(app: &mut App) {
+    if app.loading {
+        app.set_notice("Busy; compact once the current response finishes");
+        return;
+    }
+    let Some(mut agent) = app.agent.take() else {
+        app.output = "No provider configured".to_string();
+        return;
+    };
+
+    app.loading = true;
+    app.activity_status = Some("Compacting history...".to_string());
+
+    let cancel = CancellationToken::new();
+    agent.set_cancellation_token(cancel.clone());
+    app.chat_cancel = Some(cancel);
+    app.cancel_armed = false;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    app.chat_rx = Some(rx);
+
+    tokio::spawn(async move {
+        match agent.compact().await {
+            Ok(compacted) => {
+                let _ = tx.send(ChatMessage::ManualCompacted(compacted));
+                let _ = tx.send(ChatMessage::Done(agent));
+            }
+            Err(e) => {
+                let _ = tx.send(ChatMessage::Error(format!("Compaction failed: {e}"), agent));
+            }
+        }
+    });
+}
+
+/// Load persona `name` and swap its instructions into the agent's system prompt.
+fn switch_persona(app: &mut App, name: &str) {
+    // `load_persona` falls back to Orin for unknown names, so check first.
+    // Names match case-insensitively but files are looked up by their own name
+    let known = crate::config::list_personas().map(|personas| {
+        personas
+            .into_iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    });
+    let persona = match known {
+        Ok(Some(entry)) => crate::config::load_persona(&entry.name).map(|p| (entry.name, p)),
+        Ok(None) => Err(anyhow::anyhow!("unknown persona '{name}'")),
+        Err(e) => Err(e),
+    };
+
+    match (persona, app.agent.as_mut()) {
+        (Ok((name, persona)), Some(agent)) => {
+            agent.set_persona(persona.build_system_prompt());
+            app.agent_config.persona = name;
+            app.messages.push(DisplayMessage::tool(
+                "persona",
+                format!("Switched persona to {}", persona.name),
+                persona.tagline.unwrap_or_default(),
+                false,
+            ));
+        }
+        (Ok(_), None) => {
+            app.messages.push(DisplayMessage::tool_error(
+                "persona",
+                "No provider configured, so there is no agent to switch",
+            ));
+        }
+        (Err(e), _) => {
+            app.messages.push(DisplayMessage::tool_error(
+                "persona",
+                format!("Failed to switch persona: {e}"),
+            ));
+        }
+    }
+}
+
+/// Switch the agent to `model`, changing provider if the model needs one.
+///
+/// Returns the new provider name if the provider changed.
+fn switch_model(app: &mut App, model: &str) -> anyhow::Result<Option<String>> {
+    let Some(agent) = app.agent.as_mut() else {
+        return Ok(None);
+    };
+
+    let mut switched_provider = None;
+    if let Some(provider_name) = app.agent_config.provider_for_model(model) {
+        if provider_name != agent.provider_key() {
+            agent.set_provider(app.agent_config.create_provider_by_name(provider_name)?);
+            agent.set_provider_key(provider_name);
+            switched_provider = Some(provider_name.to_string());
+        }
+    }
+
+    agent.set_model(model);
+    app.model = model.to_string();
+    Ok(switched_provider)
+}
+
+/// Resend the last prompt, optionally with another model.
+///
+/// The agent forks the session just before the prompt, so the previous
+/// answer stays in the original session, and it stays on screen above the
+/// new one for comparison.
+fn regenerate(
+    app: &mut App,
+    model_arg: &str,
+    permission_tx: &mpsc::UnboundedSender<PermissionMessage>,
+) {
+    app.enter_session();
+
+    let previous_session = app
+        .agent
+        .as_ref()
+        .and_then(|agent| agent.session_id().map(str::to_string));
+    let Some((_, prompt)) = app
+        .agent
+        .as_mut()
+        .and_then(Agent::branch_before_last_prompt)
+    else {
+        app.messages.push(DisplayMessage::tool_error(
+            "regenerate",
+            "Nothing to regenerate",
+        ));
+        return;
+    };
+
+    let previous_model = app.model.clone();
+    if !model_arg.is_empty() && model_arg != previous_model {
+        if let Err(e) = switch_model(app, model_arg) {
+            app.messages.push(DisplayMessage::tool_error(
+                "regenerate",
+                format!("Failed to switch provider: {e}"),
+            ));
+        } else if !app.regenerate_keeps_model {
+            app.pending_model_revert = Some(previous_model.clone());
+        }
+    }
+
+    let kept = previous_session.map_or_else(String::new, |id| format!(", kept in session {id}"));
+    app.messages.push(DisplayMessage::tool(
+        "regenerate",
+        format!(
+            "Regenerating with {} (answer above from {previous_model}{kept})",
+            app.model
+        ),
+        "",
+        false,
+    ));
+    // Keep any draft the user was typing
+    let draft = std::mem::replace(&mut app.input, prompt);
+    start_chat(app, permission_tx.clone());
+    app.cursor = draft.len();
+    app.input = draft;
+}
+
+/// Replace an earlier prompt with the edited input and resend it.
+///
+/// The agent forks the session just before that prompt, so the original
+/// branch stays available from the session list.
+fn resend_edited_prompt(
+    app: &mut App,
+    edit: PromptEdit,
+    permission_tx: &mpsc::UnboundedSender<PermissionMessage>,
+) {
+    if app
+        .agent
+        .as_mut()
+        .and_then(|agent| agent.branch_before_prompt(&edit.prompt_id))
+        .is_none()
+    {
+        app.messages.push(DisplayMessage::tool_error(
+            "edit",
+            "That prompt is no longer in the conversation",
+        ));
+        return;
+    }
+    app.remove_turns_from(&edit.prompt_id);
+
+    start_chat(app, permission_tx.clone());
+    app.cursor = edit.draft.len();
+    app.input = edit.draft;
+}
+
+/// Restore the model used before a `/regenerate <model>` turn.
+fn revert_regenerate_model(app: &mut App) {
+    if let Some(model) = app.pending_model_revert.take() {
+        if let Err(e) = switch_model(app, &model) {
+            tracing::warn!("failed to restore model {model}: {e}");
+        }
+    }
+}
+
+/// Regenerate the session title in the background, reporting the result.
+fn start_retitle(app: &mut App) {
+    let Some(agent) = app.agent.take() else {
+        app.output = "No provider configured".to_string();
+        return;
+    };
+
+    app.loading = true;
+    app.activity_status = Some("Generating title...".to_string());
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    app.chat_rx = Some(rx);
+
+    tokio::spawn(async move {
+        let message = match agent.regenerate_title().await {
+            Ok(title) => ChatMessage::Tool {
+                name: "retitle".to_string(),
+                invocation: format!("Title: {title}"),
+                output: String::new(),
+                is_error: false,
+            },
+            Err(e) => ChatMessage::Tool {
+                name: "retitle".to_string(),
+                invocation: String::new(),
+                output: format!("Failed to regenerate title: {e}"),
+                is_error: true,
+            },
+        };
+        let _ = tx.send(message);
+        let _ = tx.send(ChatMessage::Done(agent));
+    });
+}
+
 /// Start a chat request in the background.
 fn start_chat(app: &mut App, permission_tx: mpsc::UnboundedSender<PermissionMessage>) {
     let Some(mut agent) = app.agent.take() else {
@@ -1353,12 +2325,7 @@ fn start_chat(app: &mut App, permission_tx: mpsc::UnboundedSender<PermissionMess
     };
 
     // Ensure agent has permission client with current permission presets
-    let client = PermissionClient::with_presets(
-        "tui-session".to_string(),
-        permission_tx,
-        app.current_permissions(),
-    );
-    agent.set_permission_client(client);
+    agent.set_permission_client(app.permission_client(permission_tx));
 
     let prompt = std::mem::take(&mut app.input);
     app.cursor = 0;
@@ -1367,13 +2334,19 @@ fn start_chat(app: &mut App, permission_tx: mpsc::UnboundedSender<PermissionMess
     app.enter_session();
 
     // Add user message to the conversation
-    app.add_user_message(&prompt);
+    let prompt_id = new_message_id();
+    app.add_prompt(&prompt_id, &prompt);
 
     // Clear streaming state for new response
     app.streaming_text.clear();
     app.output.clear();
     app.loading = true;
 
+    let cancel = CancellationToken::new();
+    agent.set_cancellation_token(cancel.clone());
+    app.chat_cancel = Some(cancel);
+    app.cancel_armed = false;
+
     let (tx, rx) = mpsc::unbounded_channel();
     app.chat_rx = Some(rx);
 
@@ -1382,12 +2355,15 @@ fn start_chat(app: &mut App, permission_tx: mpsc::UnboundedSender<PermissionMess
         let tx_clone = tx.clone();
 
         let result = agent
-            .chat_with_events(&prompt, |event| {
+            .chat_prompt_with_events(&prompt_id, &prompt, |event| {
                 use crate::core::agent::ChatEvent;
                 match event {
                     ChatEvent::Text(text) => {
                         let _ = tx_clone.send(ChatMessage::Text(text));
                     }
+                    ChatEvent::Reasoning(text) => {
+                        let _ = tx_clone.send(ChatMessage::Reasoning(text));
+                    }
                     ChatEvent::ToolStart { name } => {
                         let _ = tx_clone.send(ChatMessage::ToolStart { name });
                     }
@@ -1415,6 +2391,21 @@ fn start_chat(app: &mut App, permission_tx: mpsc::UnboundedSender<PermissionMess
                             cost_usd,
                         });
                     }
+                    ChatEvent::PlanPhase(phase) => {
+                        let _ = tx_clone.send(ChatMessage::PlanPhase(phase));
+                    }
+                    ChatEvent::Reconnecting {
+                        attempt,
+                        max_attempts,
+                    } => {
+                        let _ = tx_clone.send(ChatMessage::Reconnecting {
+                            attempt,
+                            max_attempts,
+                        });
+                    }
+                    ChatEvent::Compacted { messages } => {
+                        let _ = tx_clone.send(ChatMessage::Compacted { messages });
+                    }
                 }
             })
             .await;
@@ -1429,6 +2420,10 @@ fn start_chat(app: &mut App, permission_tx: mpsc::UnboundedSender<PermissionMess
                 }
                 let _ = tx.send(ChatMessage::Done(agent));
             }
+            // Partial text was kept by the agent
+            Err(AgentError::Cancelled) => {
+                let _ = tx.send(ChatMessage::Done(agent));
+            }
             Err(e) => {
                 let _ = tx.send(ChatMessage::Error(e.to_string(), agent));
             }
@@ -0,0 +1,294 @@
+//! In-conversation search.
+//!
+//! Matching is ASCII case-insensitive so byte offsets in the lowered text
+//! line up with the original, and ranges always sit on char boundaries.
+
+use unicode_width::UnicodeWidthStr;
+
+use super::components::{TOOL_OUTPUT_PREVIEW_LINES, message_height, wrapped_line_height};
+use super::message::DisplayMessage;
+
+/// Lines of context kept above a match when scrolling to it.
+const SCROLL_CONTEXT: u16 = 2;
+
+/// One occurrence of the query in the message list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Index into the displayed messages.
+    pub message: usize,
+    /// Line within the message's searchable text.
+    pub line: usize,
+    /// Byte offset of the match within the line.
+    pub start: usize,
+}
+
+/// Search query, its matches and the selected one.
+#[derive(Debug, Clone, Default)]
+pub struct SearchState {
+    /// Text being searched for.
+    pub query: String,
+    /// Whether keystrokes still go to the query.
+    pub editing: bool,
+    /// Matches in display order, refreshed every frame.
+    pub matches: Vec<SearchMatch>,
+    /// Index of the selected match.
+    pub current: usize,
+    /// Scroll to the selected match on the next frame.
+    pub jump: bool,
+}
+
+/// Search highlighting for a single message.
+#[derive(Debug, Clone, Copy)]
+pub struct MessageHighlight<'a> {
+    /// Text being searched for.
+    pub query: &'a str,
+    /// Line and byte offset of the selected match, if it is in this message.
+    pub current: Option<(usize, usize)>,
+}
+
+impl SearchState {
+    /// Start editing a new query.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            editing: true,
+            ..Self::default()
+        }
+    }
+
+    /// Recompute matches against the displayed messages.
+    pub fn refresh(&mut self, messages: &[DisplayMessage]) {
+        self.matches = find_matches(messages, &self.query);
+        if self.current >= self.matches.len() {
+            self.current = 0;
+        }
+    }
+
+    /// Select the next match, wrapping at the end.
+    pub fn next(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + 1) % self.matches.len();
+            self.jump = true;
+        }
+    }
+
+    /// Select the previous match, wrapping at the start.
+    pub fn prev(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + self.matches.len() - 1) % self.matches.len();
+            self.jump = true;
+        }
+    }
+
+    /// The selected match.
+    #[must_use]
+    pub fn current_match(&self) -> Option<SearchMatch> {
+        self.matches.get(self.current).copied()
+    }
+
+    /// Highlighting for the message at `index`.
+    #[must_use]
+    pub fn highlight_for(&self, index: usize) -> Option<MessageHighlight<'_>> {
+        if self.query.is_empty() {
+            return None;
+        }
+        let current = self
+            .current_match()
+            .filter(|m| m.message == index)
+            .map(|m| (m.line, m.start));
+        Some(MessageHighlight {
+            query: &self.query,
+            current,
+        })
+    }
+
+    /// Scroll offset that brings the selected match into view, once per jump.
+    pub fn take_jump(&mut self, messages: &[DisplayMessage], width: u16) -> Option<u16> {
+        if !std::mem::take(&mut self.jump) {
+            return None;
+        }
+        let row = match_row(messages, self.current_match()?, width);
+        Some(row.saturating_sub(SCROLL_CONTEXT))
+    }
+
+    /// Status line text, such as "Search: foo · match 2 of 5".
+    #[must_use]
+    pub fn status(&self) -> String {
+        let cursor = if self.editing { "_" } else { "" };
+        let position = if self.query.is_empty() {
+            "type to search · Esc closes".to_string()
+        } else if self.matches.is_empty() {
+            "no matches".to_string()
+        } else {
+            format!("match {} of {}", self.current + 1, self.matches.len())
+        };
+        let keys = if self.editing || self.matches.is_empty() {
+            ""
+        } else {
+            " · n/N to jump"
+        };
+        format!("Search: {}{cursor} · {position}{keys}", self.query)
+    }
+}
+
+/// Lines of a message that search looks at, in render order.
+///
+/// Tool calls contribute the visible part of their output body, not the
+/// header, so matches in the truncated tail are not counted.
+#[must_use]
+pub fn searchable_lines(message: &DisplayMessage) -> Vec<&str> {
+    match message {
+        DisplayMessage::User { text, .. } | DisplayMessage::Assistant { text } => {
+            text.lines().collect()
+        }
+        DisplayMessage::Tool { output, .. } => {
+            output.lines().take(TOOL_OUTPUT_PREVIEW_LINES).collect()
+        }
+        DisplayMessage::ToolGroup { .. } => Vec::new(),
+    }
+}
+
+/// Byte ranges of every occurrence of `query` in `line`.
+#[must_use]
+pub fn match_ranges(line: &str, query: &str) -> Vec<(usize, usize)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let haystack = line.to_ascii_lowercase();
+    let needle = query.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(start, m)| (start, start + m.len()))
+        .collect()
+}
+
+/// Every occurrence of `query` across `messages`.
+#[must_use]
+pub fn find_matches(messages: &[DisplayMessage], query: &str) -> Vec<SearchMatch> {
+    let mut matches = Vec::new();
+    for (message, msg) in messages.iter().enumerate() {
+        for (line, text) in searchable_lines(msg).into_iter().enumerate() {
+            matches.extend(
+                match_ranges(text, query)
+                    .into_iter()
+                    .map(|(start, _)| SearchMatch {
+                        message,
+                        line,
+                        start,
+                    }),
+            );
+        }
+    }
+    matches
+}
+
+/// Row of a match in message list content space.
+///
+/// Mirrors the layout in `render_message_list`: each message is followed by
+/// one blank row, user messages have a padding row on top and tool output
+/// sits below a header and is cut off after the preview.
+#[must_use]
+pub fn match_row(messages: &[DisplayMessage], m: SearchMatch, width: u16) -> u16 {
+    let above: u16 = messages
+        .iter()
+        .take(m.message)
+        .map(|msg| message_height(msg, width) + 1)
+        .sum();
+    let Some(message) = messages.get(m.message) else {
+        return above;
+    };
+
+    let width = usize::from(width.max(1));
+    let lines = searchable_lines(message);
+    let (top, line_width, line) = match message {
+        DisplayMessage::User { .. } => (1, width, m.line),
+        DisplayMessage::Tool { .. } => (1, width.saturating_sub(5).max(1), m.line),
+        _ => (0, width, m.line),
+    };
+    let within: u16 = lines
+        .iter()
+        .take(line)
+        .map(|text| wrapped_line_height(text.width(), line_width))
+        .sum();
+    above + top + within
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<DisplayMessage> {
+        vec![
+            DisplayMessage::user("Find the Config loader"),
+            DisplayMessage::assistant("The config lives in\nsrc/config.rs"),
+            DisplayMessage::Tool {
+                name: "read_file".to_string(),
+                invocation: "src/config.rs".to_string(),
+                output: "use serde;\npub struct Config;".to_string(),
+                is_error: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn matches_case_insensitively_across_message_kinds() {
+        let matches = find_matches(&conversation(), "CONFIG");
+        let found: Vec<(usize, usize, usize)> = matches
+            .iter()
+            .map(|m| (m.message, m.line, m.start))
+            .collect();
+        assert_eq!(found, vec![(0, 0, 9), (1, 0, 4), (1, 1, 4), (2, 1, 11)],);
+    }
+
+    #[test]
+    fn next_and_prev_wrap_around() {
+        let messages = conversation();
+        let mut search = SearchState::new();
+        search.query = "config".to_string();
+        search.refresh(&messages);
+        assert_eq!(search.matches.len(), 4);
+
+        search.prev();
+        assert_eq!(search.current, 3);
+        search.next();
+        assert_eq!(search.current, 0);
+        assert!(search.jump);
+        assert_eq!(search.status(), "Search: config_ · match 1 of 4");
+    }
+
+    #[test]
+    fn row_accounts_for_padding_spacing_and_headers() {
+        let messages = conversation();
+        let rows: Vec<u16> = find_matches(&messages, "config")
+            .into_iter()
+            .map(|m| match_row(&messages, m, 80))
+            .collect();
+        // User: padding row, then the line; assistant starts after 3 rows + spacing;
+        // tool output starts below its header
+        assert_eq!(rows, vec![1, 4, 5, 9]);
+    }
+
+    #[test]
+    fn skips_tool_output_past_the_preview() {
+        let mut output = "line\n".repeat(TOOL_OUTPUT_PREVIEW_LINES);
+        output.push_str("needle");
+        let messages = vec![DisplayMessage::Tool {
+            name: "bash".to_string(),
+            invocation: String::new(),
+            output,
+            is_error: false,
+        }];
+        assert!(find_matches(&messages, "needle").is_empty());
+    }
+
+    #[test]
+    fn jump_is_taken_once() {
+        let messages = conversation();
+        let mut search = SearchState::new();
+        search.query = "struct".to_string();
+        search.refresh(&messages);
+        search.jump = true;
+        assert_eq!(search.take_jump(&messages, 80), Some(7));
+        assert_eq!(search.take_jump(&messages, 80), None);
+    }
+}
@@ -0,0 +1,144 @@
+//! TUI color theme built from `[tui.theme]`.
+
+use std::str::FromStr;
+
+use ratatui::style::Color;
+
+use crate::config::{ThemeConfig, ThemePreset};
+
+/// Colors for each themed role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Dialog borders.
+    pub border: Color,
+    /// Highlights such as the prompt bar, command palette and selected items.
+    pub accent: Color,
+    /// Bar beside user messages.
+    pub user: Color,
+    /// Assistant response text.
+    pub assistant: Color,
+    /// Tool call names.
+    pub tool: Color,
+    /// Added lines in diffs.
+    pub diff_add: Color,
+    /// Removed lines in diffs.
+    pub diff_remove: Color,
+    /// Secondary text such as hints, status and tool output.
+    pub dim: Color,
+    /// Background of user messages.
+    pub panel: Color,
+    /// Background of the prompt input.
+    pub input: Color,
+    /// Background of dialogs and dropdowns.
+    pub dialog: Color,
+    /// Background of the selected item in lists.
+    pub selection: Color,
+    /// Text in dialogs and dropdowns.
+    pub text: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+impl Theme {
+    /// The original dark palette.
+    pub const DARK: Self = Self {
+        border: Color::Rgb(245, 167, 66),
+        accent: Color::Rgb(77, 201, 176),
+        user: Color::Rgb(77, 201, 176),
+        assistant: Color::Reset,
+        tool: Color::White,
+        diff_add: Color::Rgb(80, 160, 80),
+        diff_remove: Color::Rgb(180, 80, 80),
+        dim: Color::Rgb(100, 100, 110),
+        panel: Color::Rgb(28, 30, 35),
+        input: Color::Rgb(22, 24, 28),
+        dialog: Color::Rgb(30, 32, 38),
+        selection: Color::Rgb(45, 48, 55),
+        text: Color::White,
+    };
+
+    /// A palette for light terminal backgrounds.
+    pub const LIGHT: Self = Self {
+        border: Color::Rgb(200, 110, 20),
+        accent: Color::Rgb(0, 130, 110),
+        user: Color::Rgb(0, 130, 110),
+        assistant: Color::Reset,
+        tool: Color::Rgb(30, 30, 35),
+        diff_add: Color::Rgb(30, 120, 30),
+        diff_remove: Color::Rgb(170, 40, 40),
+        dim: Color::Rgb(110, 110, 120),
+        panel: Color::Rgb(232, 234, 238),
+        input: Color::Rgb(240, 241, 244),
+        dialog: Color::Rgb(246, 247, 249),
+        selection: Color::Rgb(218, 222, 228),
+        text: Color::Rgb(30, 30, 35),
+    };
+
+    /// Build a theme from its preset and role overrides.
+    ///
+    /// Colors that fail to parse are logged and left at the preset's value.
+    #[must_use]
+    pub fn from_config(config: &ThemeConfig) -> Self {
+        let mut theme = match config.preset {
+            ThemePreset::Dark => Self::DARK,
+            ThemePreset::Light => Self::LIGHT,
+        };
+
+        let overrides = [
+            ("border", &config.border, &mut theme.border),
+            ("accent", &config.accent, &mut theme.accent),
+            ("user", &config.user, &mut theme.user),
+            ("assistant", &config.assistant, &mut theme.assistant),
+            ("tool", &config.tool, &mut theme.tool),
+            ("diff_add", &config.diff_add, &mut theme.diff_add),
+            ("diff_remove", &config.diff_remove, &mut theme.diff_remove),
+            ("dim", &config.dim, &mut theme.dim),
+            ("panel", &config.panel, &mut theme.panel),
+            ("input", &config.input, &mut theme.input),
+            ("dialog", &config.dialog, &mut theme.dialog),
+            ("selection", &config.selection, &mut theme.selection),
+            ("text", &config.text, &mut theme.text),
+        ];
+        for (role, value, color) in overrides {
+            let Some(value) = value else { continue };
+            if let Ok(parsed) = Color::from_str(value.trim()) {
+                *color = parsed;
+            } else {
+                tracing::warn!("ignoring invalid [tui.theme] {role} color: {value}");
+            }
+        }
+
+        theme
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_theme_keeps_dark_palette() {
+        assert_eq!(Theme::from_config(&ThemeConfig::default()), Theme::DARK);
+    }
+
+    #[test]
+    fn overrides_apply_over_preset() {
+        let config = ThemeConfig {
+            preset: ThemePreset::Light,
+            accent: Some("#ff8800".to_string()),
+            dim: Some("gray".to_string()),
+            diff_add: Some("not a color".to_string()),
+            ..ThemeConfig::default()
+        };
+        let theme = Theme::from_config(&config);
+        assert_eq!(theme.accent, Color::Rgb(255, 136, 0));
+        assert_eq!(theme.dim, Color::Gray);
+        assert_eq!(theme.diff_add, Theme::LIGHT.diff_add);
+        assert_eq!(theme.panel, Theme::LIGHT.panel);
+        assert_eq!(theme.dialog, Theme::LIGHT.dialog);
+    }
+}
@@ -0,0 +1,145 @@
+//! Vim-style modal editing for the prompt (`[tui] vim_mode`).
+
+/// Whether keys type text or run motions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    /// Keys type into the prompt.
+    #[default]
+    Insert,
+    /// Keys move the cursor and edit.
+    Normal,
+}
+
+impl VimMode {
+    /// Indicator shown in the prompt area.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Insert => "-- INSERT --",
+            Self::Normal => "-- NORMAL --",
+        }
+    }
+}
+
+/// A normal mode command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimCommand {
+    /// `h`
+    Left,
+    /// `l`
+    Right,
+    /// `j`
+    Down,
+    /// `k`
+    Up,
+    /// `w`
+    WordForward,
+    /// `b`
+    WordBack,
+    /// `0`
+    LineStart,
+    /// `$`
+    LineEnd,
+    /// `x`
+    DeleteChar,
+    /// `dd`
+    DeleteLine,
+    /// `i`
+    Insert,
+    /// `a`
+    Append,
+}
+
+/// Modal editing state.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Vim {
+    /// Current mode.
+    pub mode: VimMode,
+    /// A `d` waiting for its second key.
+    pending_delete: bool,
+}
+
+impl Vim {
+    /// Switch to normal mode, dropping any half-typed command.
+    pub const fn enter_normal(&mut self) {
+        self.mode = VimMode::Normal;
+        self.pending_delete = false;
+    }
+
+    /// Switch to insert mode, dropping any half-typed command.
+    pub const fn enter_insert(&mut self) {
+        self.mode = VimMode::Insert;
+        self.pending_delete = false;
+    }
+
+    /// Parse a normal mode key into a command.
+    ///
+    /// Returns `None` for unbound keys and for the first `d` of `dd`.
+    pub const fn command(&mut self, key: char) -> Option<VimCommand> {
+        if self.pending_delete {
+            self.pending_delete = false;
+            return if key == 'd' {
+                Some(VimCommand::DeleteLine)
+            } else {
+                None
+            };
+        }
+
+        let command = match key {
+            'h' => VimCommand::Left,
+            'l' => VimCommand::Right,
+            'j' => VimCommand::Down,
+            'k' => VimCommand::Up,
+            'w' => VimCommand::WordForward,
+            'b' => VimCommand::WordBack,
+            '0' => VimCommand::LineStart,
+            '$' => VimCommand::LineEnd,
+            'x' => VimCommand::DeleteChar,
+            'i' => VimCommand::Insert,
+            'a' => VimCommand::Append,
+            'd' => {
+                self.pending_delete = true;
+                return None;
+            }
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_motions_and_edits() {
+        let mut vim = Vim::default();
+        assert_eq!(vim.command('h'), Some(VimCommand::Left));
+        assert_eq!(vim.command('$'), Some(VimCommand::LineEnd));
+        assert_eq!(vim.command('a'), Some(VimCommand::Append));
+        assert_eq!(vim.command('z'), None);
+    }
+
+    #[test]
+    fn dd_needs_two_keys() {
+        let mut vim = Vim::default();
+        assert_eq!(vim.command('d'), None);
+        assert_eq!(vim.command('d'), Some(VimCommand::DeleteLine));
+
+        // Any other key cancels the pending delete
+        assert_eq!(vim.command('d'), None);
+        assert_eq!(vim.command('w'), None);
+        assert_eq!(vim.command('w'), Some(VimCommand::WordForward));
+    }
+
+    #[test]
+    fn switching_modes_drops_pending_delete() {
+        let mut vim = Vim::default();
+        vim.enter_normal();
+        assert_eq!(vim.command('d'), None);
+        vim.enter_insert();
+        vim.enter_normal();
+        assert_eq!(vim.command('d'), None);
+        assert_eq!(vim.mode.label(), "-- NORMAL --");
+    }
+}